@@ -0,0 +1,4 @@
+#!/bin/sh
+# Nautilus 脚本：右键 → 脚本 → Send with Cattysend
+# Nautilus 把选中的本地文件作为参数传入
+exec cattysend send --gui-picker "$@"
@@ -1,10 +1,12 @@
 //! IPC Client - 与守护进程通信
 
 use anyhow::Result;
+use cattysend_core::{PowerState, SyncPlan, TimeOfDay, tr};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixStream;
+use tokio::net::unix::OwnedReadHalf;
 
 pub fn socket_path() -> PathBuf {
     std::env::var("XDG_RUNTIME_DIR")
@@ -19,32 +21,132 @@ pub enum IpcRequest {
     #[serde(rename = "status")]
     Status,
     #[serde(rename = "scan")]
-    Scan { timeout_secs: u64 },
+    Scan {
+        timeout_secs: u64,
+        passive: bool,
+        /// 找到第一个设备就结束扫描
+        stop_on_first: bool,
+        /// 记录原始广播的文件（绝对路径）
+        #[serde(skip_serializing_if = "Option::is_none")]
+        capture: Option<String>,
+    },
     #[serde(rename = "send")]
     Send {
-        file_path: String,
+        /// 要发送的文件（绝对路径，守护进程的工作目录与客户端不同）
+        file_paths: Vec<String>,
+        device_addr: Option<String>,
+        /// 在下一个该时刻发送，由守护进程排期
+        #[serde(skip_serializing_if = "Option::is_none")]
+        at: Option<TimeOfDay>,
+        /// 跳过电池电量检查
+        force: bool,
+    },
+    /// 把目录同步到另一台 cattysend，`dry_run` 时只返回 `SyncPlan`
+    #[serde(rename = "sync")]
+    Sync {
+        /// 要同步的目录（绝对路径）
+        dir: String,
         device_addr: Option<String>,
+        delete: bool,
+        dry_run: bool,
+        force: bool,
     },
     #[serde(rename = "receive")]
-    Receive,
+    Receive {
+        #[serde(default)]
+        output_dir: Option<String>,
+        /// 可发现窗口时长（秒），None 表示一直广播
+        #[serde(default)]
+        window_secs: Option<u64>,
+    },
     #[serde(rename = "stop")]
     Stop,
+    /// 暂停当前接收会话的下载
+    #[serde(rename = "pause")]
+    Pause,
+    /// 恢复已暂停的下载
+    #[serde(rename = "resume")]
+    Resume,
+    /// 订阅事件流，之后连接上会持续收到 `IpcResponse::Event`
+    #[serde(rename = "subscribe")]
+    Subscribe,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 pub enum IpcResponse {
     #[serde(rename = "ok")]
-    Ok { message: String },
+    Ok {
+        message: String,
+        /// 请求启动的会话（`send`），用于从事件流中挑出本次传输的事件
+        #[serde(default)]
+        session_id: Option<String>,
+    },
     #[serde(rename = "error")]
     Error { message: String },
     #[serde(rename = "devices")]
     Devices { devices: Vec<DeviceInfo> },
+    /// `send` 指定的设备匹配到多个结果
+    #[serde(rename = "ambiguous")]
+    Ambiguous { devices: Vec<DeviceInfo> },
     #[serde(rename = "status")]
     Status {
         state: String,
         progress: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        discoverable_remaining_secs: Option<u64>,
+        #[serde(default)]
+        power: Option<PowerState>,
+    },
+    /// `sync --dry-run` 的结果
+    #[serde(rename = "sync_plan")]
+    SyncPlan { plan: SyncPlan },
+    #[serde(rename = "event")]
+    Event {
+        #[serde(default)]
+        session_id: Option<String>,
+        event: DaemonEvent,
+    },
+}
+
+/// 守护进程事件，通过 `subscribe` 推送
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum DaemonEvent {
+    #[serde(rename = "discoverable")]
+    Discoverable {
+        remaining_secs: u64,
+        total_secs: u64,
     },
+    #[serde(rename = "discoverable_ended")]
+    DiscoverableEnded,
+    #[serde(rename = "status")]
+    Status { message: String },
+    #[serde(rename = "devices")]
+    Devices { devices: Vec<DeviceInfo> },
+    #[serde(rename = "request")]
+    Request {
+        sender_name: String,
+        file_name: String,
+        file_count: u32,
+        total_size: u64,
+    },
+    /// 传输进度（发送时 `received` 为已发送的字节数）
+    #[serde(rename = "progress")]
+    Progress { received: u64, total: u64 },
+    /// 下载已暂停
+    #[serde(rename = "paused")]
+    Paused,
+    /// 下载已恢复
+    #[serde(rename = "resumed")]
+    Resumed,
+    #[serde(rename = "complete")]
+    Complete { files: Vec<String> },
+    #[serde(rename = "error")]
+    Error { message: String },
+    /// 当前的发送和接收被 `stop` 停止
+    #[serde(rename = "cancelled")]
+    Cancelled,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -52,40 +154,93 @@ pub struct DeviceInfo {
     pub name: String,
     pub address: String,
     pub rssi: Option<i16>,
+    #[serde(default)]
+    pub brand: Option<String>,
 }
 
-pub async fn send_request(request: IpcRequest) -> Result<IpcResponse> {
+/// 连接守护进程并发出请求，返回用于读取响应的 reader
+async fn connect(request: &IpcRequest) -> Result<BufReader<OwnedReadHalf>> {
     let path = socket_path();
 
     let stream = match UnixStream::connect(&path).await {
         Ok(s) => s,
         Err(e) => {
-            eprintln!("❌ 无法连接到守护进程: {}", e);
-            eprintln!("   请确保 cattysend-daemon 正在运行");
-            eprintln!("   运行: cargo xtask dev 或 systemctl start cattysend");
+            eprintln!("❌ {}", tr!("cli.daemon.unreachable", error = e));
+            eprintln!("   {}", tr!("cli.daemon.hint_running"));
+            eprintln!("   {}", tr!("cli.daemon.hint_start"));
             return Err(e.into());
         }
     };
 
     let (reader, mut writer) = stream.into_split();
-    let mut reader = BufReader::new(reader);
 
-    // 发送请求
-    let json = serde_json::to_string(&request)?;
+    let json = serde_json::to_string(request)?;
     writer.write_all(json.as_bytes()).await?;
     writer.write_all(b"\n").await?;
 
-    // 读取响应
-    let mut line = String::new();
-    reader.read_line(&mut line).await?;
+    Ok(BufReader::new(reader))
+}
 
-    let response: IpcResponse = serde_json::from_str(&line)?;
+/// 发出请求并打印 `ok` / `error` 响应
+pub async fn send_request(request: IpcRequest) -> Result<IpcResponse> {
+    let response = request_quietly(request).await?;
 
     match &response {
-        IpcResponse::Ok { message } => println!("✅ {}", message),
+        IpcResponse::Ok { message, .. } => println!("✅ {}", message),
         IpcResponse::Error { message } => eprintln!("❌ {}", message),
         _ => {}
     }
 
     Ok(response)
 }
+
+/// 发出请求，响应由调用方处理
+pub async fn request_quietly(request: IpcRequest) -> Result<IpcResponse> {
+    let mut reader = connect(&request).await?;
+
+    // 读取响应
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// 守护进程的事件流
+pub struct Events {
+    reader: BufReader<OwnedReadHalf>,
+    line: String,
+}
+
+impl Events {
+    /// 订阅事件；订阅之后发出的请求产生的事件都能收到
+    pub async fn subscribe() -> Result<Self> {
+        Ok(Self {
+            reader: connect(&IpcRequest::Subscribe).await?,
+            line: String::new(),
+        })
+    }
+
+    /// 下一行事件的原始 JSON 和解析结果，守护进程断开时为 None
+    ///
+    /// 较新的守护进程推送了本客户端不认识的事件时解析结果为 None。
+    pub async fn next(&mut self) -> Result<Option<(String, Option<IpcResponse>)>> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line).await? == 0 {
+            return Ok(None);
+        }
+        let raw = self.line.trim_end().to_string();
+        let parsed = serde_json::from_str(&raw).ok();
+        Ok(Some((raw, parsed)))
+    }
+}
+
+/// 订阅守护进程事件，直到守护进程断开
+///
+/// 每收到一行调用一次 `on_line`，参数为原始 JSON 和解析结果。
+pub async fn subscribe(mut on_line: impl FnMut(&str, Option<IpcResponse>)) -> Result<()> {
+    let mut events = Events::subscribe().await?;
+    while let Some((raw, response)) = events.next().await? {
+        on_line(&raw, response);
+    }
+    Ok(())
+}
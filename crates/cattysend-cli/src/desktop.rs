@@ -0,0 +1,275 @@
+//! 文件管理器集成（`send --gui-picker`）
+//!
+//! 从右键菜单启动时没有终端，设备选择、进度和结果改用 zenity（GNOME）或
+//! kdialog（KDE）对话框显示；两者都没有时只能用 `--device`/`--first`/`--name`
+//! 选定设备，结果通过 notify-send 通知。
+
+use crate::client::{self, DeviceInfo, IpcRequest, IpcResponse};
+use crate::picker;
+use anyhow::{Result, bail};
+use cattysend_core::tr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+/// 对话框和通知的标题
+const TITLE: &str = "Cattysend";
+
+/// 可用的对话框工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialog {
+    Zenity,
+    Kdialog,
+}
+
+impl Dialog {
+    /// 按 zenity、kdialog 的顺序在 PATH 中查找
+    pub fn detect() -> Option<Self> {
+        [Self::Zenity, Self::Kdialog]
+            .into_iter()
+            .find(|dialog| has_command(dialog.program()))
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Zenity => "zenity",
+            Self::Kdialog => "kdialog",
+        }
+    }
+
+    /// 列出设备让用户选择的命令，选中后在标准输出打印其下标
+    fn list_command(self, devices: &[&DeviceInfo]) -> Command {
+        let mut cmd = Command::new(self.program());
+        let prompt = tr!("cli.pick.prompt");
+        match self {
+            Self::Zenity => {
+                cmd.args(["--list", "--title", TITLE, "--text", &prompt])
+                    .args(["--column", "#", "--column", &prompt])
+                    .args(["--hide-column=1", "--print-column=1", "--hide-header"])
+                    .args(["--width=480", "--height=320"]);
+            }
+            Self::Kdialog => {
+                cmd.args(["--title", TITLE, "--menu", &prompt]);
+            }
+        }
+        for (index, device) in devices.iter().enumerate() {
+            cmd.arg(index.to_string()).arg(picker::describe(device));
+        }
+        cmd
+    }
+}
+
+/// `send --gui-picker` 的完整流程，结果以通知或错误对话框告知用户
+pub async fn send(
+    files: &[PathBuf],
+    device: Option<String>,
+    name: Option<&str>,
+    first: bool,
+    scan_timeout: u64,
+) -> Result<()> {
+    let dialog = Dialog::detect();
+    match run_send(dialog, files, device, name, first, scan_timeout).await {
+        Ok(message) => {
+            notify(&message);
+            Ok(())
+        }
+        Err(e) => {
+            show_error(dialog, &e.to_string());
+            Err(e)
+        }
+    }
+}
+
+async fn run_send(
+    dialog: Option<Dialog>,
+    files: &[PathBuf],
+    device: Option<String>,
+    name: Option<&str>,
+    first: bool,
+    scan_timeout: u64,
+) -> Result<String> {
+    let files = crate::absolute_files(files)?;
+    // 守护进程没运行时给出可操作的提示，而不是底层的 socket 错误
+    if tokio::net::UnixStream::connect(client::socket_path())
+        .await
+        .is_err()
+    {
+        bail!(
+            "{}\n{}",
+            tr!("cli.desktop.daemon_down"),
+            tr!("cli.daemon.hint_start")
+        );
+    }
+
+    let device = match device {
+        Some(device) => device,
+        None => {
+            let progress = Progress::show(dialog, &tr!("cli.scan.scanning", secs = scan_timeout));
+            let resp = client::send_request(IpcRequest::Scan {
+                timeout_secs: scan_timeout,
+                passive: false,
+                stop_on_first: first && name.is_none(),
+                capture: None,
+            })
+            .await?;
+            drop(progress);
+            let devices = match resp {
+                IpcResponse::Devices { devices } => devices,
+                IpcResponse::Error { message } => bail!(message),
+                _ => bail!(tr!("cli.pick.scan_failed")),
+            };
+            picker::choose_with(&devices, name, first, |candidates| ask(dialog, candidates))?
+                .address
+        }
+    };
+
+    let summary = match files.as_slice() {
+        [file] => Path::new(file)
+            .file_name()
+            .map_or_else(|| file.clone(), |n| n.to_string_lossy().to_string()),
+        _ => tr!("cli.desktop.file_count", count = files.len()),
+    };
+    let _progress = Progress::show(dialog, &tr!("cli.desktop.sending", files = summary));
+    let mut resp = client::send_request(IpcRequest::Send {
+        file_paths: files.clone(),
+        device_addr: Some(device),
+        at: None,
+        force: false,
+    })
+    .await?;
+    // 名称匹配到多个设备时再让用户选一次
+    if let IpcResponse::Ambiguous { devices } = resp {
+        let chosen = picker::choose_with(&devices, None, false, |c| ask(dialog, c))?;
+        resp = client::send_request(IpcRequest::Send {
+            file_paths: files,
+            device_addr: Some(chosen.address),
+            at: None,
+            force: false,
+        })
+        .await?;
+    }
+    match resp {
+        IpcResponse::Ok { message, .. } => Ok(message),
+        IpcResponse::Error { message } => bail!(message),
+        _ => bail!(tr!("cli.desktop.unexpected_response")),
+    }
+}
+
+/// 用对话框询问目标设备，返回候选的下标
+fn ask(dialog: Option<Dialog>, candidates: &[&DeviceInfo]) -> Result<usize> {
+    let Some(dialog) = dialog else {
+        bail!(tr!("cli.desktop.no_dialog"));
+    };
+    let output = dialog
+        .list_command(candidates)
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        bail!(tr!("cli.pick.cancelled"));
+    }
+    parse_selection(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow::anyhow!(tr!("cli.pick.cancelled")))
+}
+
+/// 解析对话框输出的下标（zenity 可能附带 `|` 分隔的重复值）
+fn parse_selection(output: &str) -> Option<usize> {
+    output.trim().split('|').next()?.trim().parse().ok()
+}
+
+/// 不确定时长的进度窗口，drop 时关闭
+///
+/// 只有 zenity 提供进度窗口；kdialog 或没有对话框工具时改为一条通知。
+struct Progress {
+    child: Option<Child>,
+}
+
+impl Progress {
+    fn show(dialog: Option<Dialog>, text: &str) -> Self {
+        let child = match dialog {
+            Some(Dialog::Zenity) => Command::new("zenity")
+                .args(["--progress", "--pulsate", "--no-cancel", "--title", TITLE])
+                .args(["--text", text])
+                .stdin(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok(),
+            _ => None,
+        };
+        if child.is_none() {
+            notify(text);
+        }
+        Self { child }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = writeln!(stdin, "100");
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// 发送桌面通知，没有 notify-send 时忽略
+fn notify(text: &str) {
+    let _ = Command::new("notify-send")
+        .args(["--app-name", TITLE, "--icon", "document-send", TITLE, text])
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// 显示错误对话框，没有对话框工具时退回通知
+fn show_error(dialog: Option<Dialog>, text: &str) {
+    let status = match dialog {
+        Some(Dialog::Zenity) => Command::new("zenity")
+            .args(["--error", "--title", TITLE, "--text", text])
+            .status(),
+        Some(Dialog::Kdialog) => Command::new("kdialog")
+            .args(["--title", TITLE, "--error", text])
+            .status(),
+        None => {
+            notify(text);
+            return;
+        }
+    };
+    if status.is_err() {
+        notify(text);
+    }
+}
+
+fn has_command(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(name).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("2\n"), Some(2));
+        assert_eq!(parse_selection("1|1\n"), Some(1));
+        assert_eq!(parse_selection(""), None);
+        assert_eq!(parse_selection("Redmi"), None);
+    }
+
+    #[test]
+    fn test_list_command_passes_index_and_description() {
+        let device = DeviceInfo {
+            name: "Redmi K70".to_string(),
+            address: "AA:BB:CC:DD:EE:FF".to_string(),
+            rssi: Some(-40),
+            brand: None,
+        };
+        let cmd = Dialog::Kdialog.list_command(&[&device]);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+        assert_eq!(args[args.len() - 2], "0");
+        assert_eq!(args[args.len() - 1], picker::describe(&device));
+        assert_eq!(cmd.get_program(), "kdialog");
+    }
+}
@@ -1,14 +1,35 @@
 //! Cattysend CLI
 //!
 //! 命令行客户端，通过 Unix Socket 与守护进程通信
+//!
+//! `doctor`、`fav`、`history`、`identity`、`keygen` 和 `decrypt` 在本地运行，不需要守护进程。
+//! `send --at` 只把任务交给守护进程排期，结果在执行后写入 `history`。
+//! `sync` 由守护进程扫描目录并传输（包括 `--dry-run`），同步记录与守护进程共用。
 
 mod client;
+mod desktop;
+mod picker;
+mod progress;
+mod watch;
 
 use anyhow::Result;
+use cattysend_core::ble::learn::{CANDIDATE_MIN_SEEN, ShapeLearner};
+use cattysend_core::crypto::{AtRestKey, at_rest};
+use cattysend_core::diagnostics::{self, Severity};
+use cattysend_core::favorites::{self, Favorite, Favorites};
+use cattysend_core::tr;
+use cattysend_core::{
+    AppSettings, DeviceIdentity, History, HistoryOutcome, IdentityStore, KeyLocation, PowerState,
+    SyncPlan, TimeOfDay,
+};
 use clap::{Parser, Subcommand};
+use progress::Output;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Parser)]
-#[command(name = "cattysend", version, about = "互传联盟 - Linux 文件传输工具")]
+#[command(name = "cattysend", version, about = tr!("cli.about"))]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
@@ -16,87 +37,785 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// 发送文件
+    #[command(about = tr!("cli.cmd.send"))]
     Send {
-        /// 要发送的文件路径
-        file: String,
-        /// 目标设备地址 (可选，不指定则交互式选择)
-        #[arg(short, long)]
+        #[arg(required_unless_present = "latest", help = tr!("cli.arg.file"))]
+        files: Vec<String>,
+        #[arg(long, value_name = "DIR", conflicts_with = "files", help = tr!("cli.arg.latest"))]
+        latest: Option<PathBuf>,
+        #[arg(short, long, conflicts_with_all = ["first", "name"], help = tr!("cli.arg.device"))]
+        device: Option<String>,
+        #[arg(long, help = tr!("cli.arg.first"))]
+        first: bool,
+        #[arg(long, value_name = "SUBSTRING", help = tr!("cli.arg.name"))]
+        name: Option<String>,
+        #[arg(long, default_value = "5", help = tr!("cli.arg.scan_timeout"))]
+        scan_timeout: u64,
+        #[arg(long, help = tr!("cli.arg.gui_picker"))]
+        gui_picker: bool,
+        #[arg(long, value_name = "HH:MM", requires = "device", conflicts_with = "gui_picker", help = tr!("cli.arg.at"))]
+        at: Option<TimeOfDay>,
+        #[arg(long, help = tr!("cli.arg.force"))]
+        force: bool,
+        #[arg(short, long, conflicts_with = "json", help = tr!("cli.arg.quiet"))]
+        quiet: bool,
+        #[arg(long, help = tr!("cli.arg.transfer_json"))]
+        json: bool,
+    },
+    #[command(about = tr!("cli.cmd.sync"))]
+    Sync {
+        #[arg(help = tr!("cli.arg.sync_dir"))]
+        dir: PathBuf,
+        #[arg(short, long, conflicts_with_all = ["first", "name"], help = tr!("cli.arg.device"))]
         device: Option<String>,
+        #[arg(long, help = tr!("cli.arg.first"))]
+        first: bool,
+        #[arg(long, value_name = "SUBSTRING", help = tr!("cli.arg.name"))]
+        name: Option<String>,
+        #[arg(long, default_value = "5", help = tr!("cli.arg.scan_timeout"))]
+        scan_timeout: u64,
+        #[arg(long, help = tr!("cli.arg.dry_run"))]
+        dry_run: bool,
+        #[arg(long, help = tr!("cli.arg.delete"))]
+        delete: bool,
+        #[arg(long, help = tr!("cli.arg.force"))]
+        force: bool,
+        #[arg(short, long, conflicts_with = "json", help = tr!("cli.arg.quiet"))]
+        quiet: bool,
+        #[arg(long, help = tr!("cli.arg.transfer_json"))]
+        json: bool,
     },
-    /// 接收文件
+    #[command(about = tr!("cli.cmd.receive"))]
     Receive {
-        /// 保存目录 (默认: ~/Downloads)
-        #[arg(short, long)]
+        #[arg(short, long, help = tr!("cli.arg.output"))]
         output: Option<String>,
+        #[arg(short, long, value_parser = parse_duration, help = tr!("cli.arg.window"))]
+        window: Option<Duration>,
+        #[arg(short, long, conflicts_with = "json", help = tr!("cli.arg.quiet"))]
+        quiet: bool,
+        #[arg(long, help = tr!("cli.arg.transfer_json"))]
+        json: bool,
     },
-    /// 扫描附近设备
+    #[command(about = tr!("cli.cmd.scan"))]
     Scan {
-        /// 扫描超时时间 (秒)
-        #[arg(short, long, default_value = "10")]
+        #[arg(short, long, default_value = "10", help = tr!("cli.arg.timeout"))]
         timeout: u64,
+        #[arg(long, help = tr!("cli.arg.scan_first"))]
+        first: bool,
+        #[arg(long, help = tr!("cli.arg.passive"))]
+        passive: bool,
+        #[arg(long, value_name = "FILE", help = tr!("cli.arg.capture"))]
+        capture: Option<PathBuf>,
     },
-    /// 查看当前状态
+    #[command(about = tr!("cli.cmd.status"))]
     Status,
-    /// 停止当前传输
+    #[command(about = tr!("cli.cmd.stop"))]
     Stop,
+    #[command(about = tr!("cli.cmd.pause"))]
+    Pause,
+    #[command(about = tr!("cli.cmd.resume"))]
+    Resume,
+    #[command(about = tr!("cli.cmd.watch"))]
+    Watch {
+        #[arg(long, help = tr!("cli.arg.json"))]
+        json: bool,
+    },
+    #[command(about = tr!("cli.cmd.fav"))]
+    Fav {
+        #[command(subcommand)]
+        action: Option<FavAction>,
+    },
+    #[command(about = tr!("cli.cmd.history"))]
+    History {
+        #[arg(short = 'n', long, default_value = "20", help = tr!("cli.arg.history_limit"))]
+        limit: usize,
+        #[arg(long, help = tr!("cli.arg.history_json"))]
+        json: bool,
+    },
+    #[command(about = tr!("cli.cmd.learn"))]
+    Learn {
+        #[arg(long, default_value_t = CANDIDATE_MIN_SEEN, help = tr!("cli.arg.learn_min_seen"))]
+        min_seen: u32,
+        #[arg(long, help = tr!("cli.arg.learn_reset"))]
+        reset: bool,
+    },
+    #[command(about = tr!("cli.cmd.doctor"))]
+    Doctor,
+    #[command(about = tr!("cli.cmd.identity"))]
+    Identity {
+        #[command(subcommand)]
+        action: IdentityAction,
+    },
+    #[command(about = tr!("cli.cmd.keygen"))]
+    Keygen {
+        #[arg(help = tr!("cli.arg.key_file"))]
+        path: PathBuf,
+    },
+    #[command(about = tr!("cli.cmd.decrypt"))]
+    Decrypt {
+        #[arg(required = true, help = tr!("cli.arg.encrypted_files"))]
+        files: Vec<PathBuf>,
+        #[arg(short, long, help = tr!("cli.arg.key"))]
+        key: PathBuf,
+        #[arg(short, long, help = tr!("cli.arg.decrypt_output"))]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FavAction {
+    #[command(about = tr!("cli.cmd.fav_list"))]
+    List,
+    #[command(about = tr!("cli.cmd.fav_add"))]
+    Add {
+        #[arg(help = tr!("cli.arg.alias"))]
+        alias: String,
+        #[arg(help = tr!("cli.arg.address"))]
+        address: String,
+        #[arg(long, help = tr!("cli.arg.fav_name"))]
+        name: Option<String>,
+    },
+    #[command(about = tr!("cli.cmd.fav_remove"))]
+    Remove {
+        #[arg(help = tr!("cli.arg.alias"))]
+        alias: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum IdentityAction {
+    #[command(about = tr!("cli.cmd.identity_show"))]
+    Show {
+        #[arg(long, help = tr!("cli.arg.identity_json"))]
+        json: bool,
+    },
+    #[command(about = tr!("cli.cmd.identity_reset"))]
+    Reset {
+        #[arg(short, long, help = tr!("cli.arg.yes"))]
+        yes: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    cattysend_core::i18n::init();
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Send { file, device } => {
-            println!("📤 发送文件: {}", file);
-            if let Some(dev) = &device {
-                println!("   目标设备: {}", dev);
-            }
-            client::send_request(client::IpcRequest::Send {
-                file_path: file,
-                device_addr: device,
-            })
-            .await?;
+        Commands::Send {
+            files,
+            latest,
+            device,
+            first,
+            name,
+            scan_timeout,
+            gui_picker,
+            at,
+            force,
+            quiet,
+            json,
+        } => {
+            let files = match latest {
+                Some(dir) => vec![cattysend_core::watch::latest_file(&dir)?.ok_or_else(|| {
+                    anyhow::anyhow!(tr!("cli.send.empty_dir", dir = dir.display()))
+                })?],
+                None => files.iter().map(PathBuf::from).collect(),
+            };
+            if gui_picker {
+                return desktop::send(&files, device, name.as_deref(), first, scan_timeout).await;
+            }
+            let output = Output::new(quiet, json);
+            let files = absolute_files(&files)?;
+            if let Some(at) = at {
+                // 到点时才查找设备，这里不扫描
+                let resp = client::request_quietly(client::IpcRequest::Send {
+                    file_paths: files,
+                    device_addr: device,
+                    at: Some(at),
+                    force,
+                })
+                .await?;
+                return output.response(&resp);
+            }
+            let device = match device {
+                Some(addr) => addr,
+                None => pick_device(scan_timeout, name.as_deref(), first, output).await?,
+            };
+            if output.is_human() {
+                for file in &files {
+                    println!("📤 {}", tr!("cli.send.sending", file = file));
+                }
+                println!("   {}", tr!("cli.send.target", device = device));
+            }
+            // 先订阅再发出请求，才不会漏掉本次发送的事件
+            let events = client::Events::subscribe().await?;
+            let resp =
+                request_for_device(&device, output, |device_addr| client::IpcRequest::Send {
+                    file_paths: files.clone(),
+                    device_addr: Some(device_addr),
+                    at: None,
+                    force,
+                })
+                .await?;
+            output.response(&resp)?;
+            let client::IpcResponse::Ok { session_id, .. } = resp else {
+                anyhow::bail!(tr!("cli.desktop.unexpected_response"));
+            };
+            progress::follow(events, output, session_id).await?;
         }
-        Commands::Receive { output } => {
-            let dir = output.unwrap_or_else(|| {
+        Commands::Sync {
+            dir,
+            device,
+            first,
+            name,
+            scan_timeout,
+            dry_run,
+            delete,
+            force,
+            quiet,
+            json,
+        } => {
+            if !dir.is_dir() {
+                anyhow::bail!(tr!("cli.sync.not_dir", dir = dir.display()));
+            }
+            let output = Output::new(quiet, json);
+            let dir = std::path::absolute(&dir)?.to_string_lossy().to_string();
+            let device = match device {
+                Some(addr) => addr,
+                None => pick_device(scan_timeout, name.as_deref(), first, output).await?,
+            };
+            let request = |device_addr| client::IpcRequest::Sync {
+                dir: dir.clone(),
+                device_addr: Some(device_addr),
+                delete,
+                dry_run,
+                force,
+            };
+            if dry_run {
+                let resp = request_for_device(&device, output, request).await?;
+                output.response(&resp)?;
+                match resp {
+                    client::IpcResponse::SyncPlan { plan } => {
+                        if output.is_human() {
+                            print_sync_plan(&plan);
+                        }
+                    }
+                    _ => anyhow::bail!(tr!("cli.desktop.unexpected_response")),
+                }
+                return Ok(());
+            }
+            if output.is_human() {
+                println!("🔄 {}", tr!("cli.sync.syncing", dir = dir));
+                println!("   {}", tr!("cli.send.target", device = device));
+            }
+            let events = client::Events::subscribe().await?;
+            let resp = request_for_device(&device, output, request).await?;
+            output.response(&resp)?;
+            let client::IpcResponse::Ok { session_id, .. } = resp else {
+                anyhow::bail!(tr!("cli.desktop.unexpected_response"));
+            };
+            progress::follow(events, output, session_id).await?;
+        }
+        Commands::Receive {
+            output: dir,
+            window,
+            quiet,
+            json,
+        } => {
+            let output = Output::new(quiet, json);
+            let dir = dir.unwrap_or_else(|| {
                 dirs::download_dir()
                     .map(|p| p.to_string_lossy().to_string())
                     .unwrap_or_else(|| ".".to_string())
             });
-            println!("📥 接收模式 (保存到: {})", dir);
-            client::send_request(client::IpcRequest::Receive).await?;
+            if output.is_human() {
+                println!("📥 {}", tr!("cli.receive.mode", dir = dir));
+                if let Some(w) = window {
+                    println!("   {}", tr!("cli.receive.window", secs = w.as_secs()));
+                }
+            }
+            let events = client::Events::subscribe().await?;
+            let resp = client::request_quietly(client::IpcRequest::Receive {
+                output_dir: Some(dir),
+                window_secs: window.map(|w| w.as_secs()),
+            })
+            .await?;
+            output.response(&resp)?;
+            progress::follow(events, output, None).await?;
         }
-        Commands::Scan { timeout } => {
-            println!("🔍 扫描设备 ({}s)...", timeout);
+        Commands::Scan {
+            timeout,
+            first,
+            passive,
+            capture,
+        } => {
+            println!("🔍 {}", tr!("cli.scan.scanning", secs = timeout));
+            let capture = capture
+                .map(|path| std::path::absolute(path).map(|p| p.to_string_lossy().to_string()))
+                .transpose()?;
             let resp = client::send_request(client::IpcRequest::Scan {
                 timeout_secs: timeout,
+                passive,
+                stop_on_first: first,
+                capture: capture.clone(),
             })
             .await?;
+            if let Some(path) = &capture {
+                println!("   {}", tr!("cli.scan.captured", path = path));
+            }
             if let client::IpcResponse::Devices { devices } = resp {
                 if devices.is_empty() {
-                    println!("   未发现设备");
+                    println!("   {}", tr!("cli.scan.none"));
                 } else {
                     for (i, dev) in devices.iter().enumerate() {
-                        println!("   [{}] {} ({})", i, dev.name, dev.address);
+                        println!("   [{}] {}", i, picker::describe(dev));
                     }
                 }
             }
         }
         Commands::Status => {
             let resp = client::send_request(client::IpcRequest::Status).await?;
-            if let client::IpcResponse::Status { state, progress } = resp {
-                println!("状态: {}", state);
+            if let client::IpcResponse::Status {
+                state,
+                progress,
+                discoverable_remaining_secs,
+                power,
+            } = resp
+            {
+                println!("{}", tr!("cli.status.state", state = state));
                 if let Some(p) = progress {
-                    println!("进度: {:.1}%", p * 100.0);
+                    println!(
+                        "{}",
+                        tr!("cli.status.progress", percent = format!("{:.1}", p * 100.0))
+                    );
+                }
+                if let Some(secs) = discoverable_remaining_secs {
+                    println!("{}", tr!("cli.status.discoverable", secs = secs));
+                }
+                match power {
+                    Some(PowerState {
+                        on_battery: true,
+                        battery_percent: Some(percent),
+                    }) => println!("{}", tr!("cli.status.battery", percent = percent)),
+                    Some(PowerState {
+                        on_battery: true,
+                        battery_percent: None,
+                    }) => println!("{}", tr!("cli.status.on_battery")),
+                    Some(_) => println!("{}", tr!("cli.status.ac_power")),
+                    None => {}
                 }
             }
         }
         Commands::Stop => {
-            println!("⏹️  停止传输");
+            println!("⏹️  {}", tr!("cli.stop"));
             client::send_request(client::IpcRequest::Stop).await?;
         }
+        Commands::Pause => {
+            println!("⏸️  {}", tr!("cli.pause"));
+            client::send_request(client::IpcRequest::Pause).await?;
+        }
+        Commands::Resume => {
+            println!("▶️  {}", tr!("cli.resume"));
+            client::send_request(client::IpcRequest::Resume).await?;
+        }
+        Commands::Watch { json } => watch::run(json).await?,
+        Commands::Fav { action } => manage_favorites(action.unwrap_or(FavAction::List))?,
+        Commands::History { limit, json } => show_history(limit, json)?,
+        Commands::Learn { min_seen, reset } => show_learned(min_seen, reset)?,
+        Commands::Doctor => doctor().await?,
+        Commands::Identity { action } => manage_identity(action).await?,
+        Commands::Keygen { path } => {
+            AtRestKey::generate().save(&path)?;
+            println!("🔑 {}", tr!("cli.keygen.saved", path = path.display()));
+            println!("   {}", tr!("cli.keygen.hint", path = path.display()));
+        }
+        Commands::Decrypt { files, key, output } => decrypt(&files, &key, output.as_deref())?,
     }
 
     Ok(())
 }
+
+/// 扫描附近设备并选出发送目标，返回设备地址
+async fn pick_device(
+    timeout: u64,
+    name: Option<&str>,
+    first: bool,
+    output: Output,
+) -> Result<String> {
+    if output.is_human() {
+        println!("🔍 {}", tr!("cli.scan.scanning", secs = timeout));
+    }
+    let resp = client::request_quietly(client::IpcRequest::Scan {
+        timeout_secs: timeout,
+        passive: false,
+        // 按名称挑选时第一个找到的设备不一定匹配，要扫完
+        stop_on_first: first && name.is_none(),
+        capture: None,
+    })
+    .await?;
+    let devices = match resp {
+        client::IpcResponse::Devices { devices } => devices,
+        client::IpcResponse::Error { message } => anyhow::bail!(message),
+        _ => anyhow::bail!(tr!("cli.pick.scan_failed")),
+    };
+    Ok(picker::choose(&devices, name, first)?.address)
+}
+
+/// 发出针对设备的请求；名称匹配到多个设备时由用户选择，再用地址重新发出
+async fn request_for_device(
+    device: &str,
+    output: Output,
+    request: impl Fn(String) -> client::IpcRequest,
+) -> Result<client::IpcResponse> {
+    let resp = client::request_quietly(request(device.to_string())).await?;
+    let client::IpcResponse::Ambiguous { devices } = resp else {
+        return Ok(resp);
+    };
+    if output.is_human() {
+        println!(
+            "   {}",
+            tr!("cli.pick.multiple", name = device, count = devices.len())
+        );
+    }
+    let chosen = picker::choose(&devices, None, false)?;
+    client::request_quietly(request(chosen.address)).await
+}
+
+/// 打印 `sync --dry-run` 的结果：`+` 新增、`~` 修改、`-` 删除
+fn print_sync_plan(plan: &SyncPlan) {
+    println!("🔄 {}", tr!("cli.sync.plan", folder = plan.folder));
+    if plan.is_empty() {
+        println!("   {}", tr!("cli.sync.up_to_date", count = plan.unchanged));
+        return;
+    }
+    for name in &plan.added {
+        println!("   + {}", name);
+    }
+    for name in &plan.modified {
+        println!("   ~ {}", name);
+    }
+    if plan.delete {
+        for name in &plan.deleted {
+            println!("   - {}", name);
+        }
+    } else if !plan.deleted.is_empty() {
+        println!("   {}", tr!("cli.sync.kept", count = plan.deleted.len()));
+    }
+    println!(
+        "   {}",
+        tr!(
+            "cli.sync.summary",
+            size = watch::format_bytes(plan.transfer_size),
+            count = plan.unchanged
+        )
+    );
+}
+
+/// 检查要发送的文件并转为绝对路径（守护进程不在当前目录下运行）
+fn absolute_files(files: &[PathBuf]) -> Result<Vec<String>> {
+    if files.is_empty() {
+        anyhow::bail!(tr!("cli.send.missing_file"));
+    }
+    files
+        .iter()
+        .map(|file| {
+            if !file.is_file() {
+                anyhow::bail!(tr!("cli.send.not_found", file = file.display()));
+            }
+            Ok(std::path::absolute(file)?.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+/// 还原静态加密保存的文件（`<文件名>.cattyenc`）
+///
+/// 默认写到加密文件旁边，不覆盖已有文件。
+fn decrypt(files: &[PathBuf], key: &Path, output: Option<&Path>) -> Result<()> {
+    let key = AtRestKey::load(key)?;
+    for file in files {
+        if file.extension() != Some(OsStr::new(at_rest::ENCRYPTED_EXTENSION)) {
+            anyhow::bail!(tr!("cli.decrypt.not_encrypted", file = file.display()));
+        }
+        let plain = file.with_extension("");
+        let target = match (output, plain.file_name()) {
+            (Some(dir), Some(name)) => dir.join(name),
+            _ => plain,
+        };
+        if target.exists() {
+            anyhow::bail!(tr!("cli.decrypt.exists", file = target.display()));
+        }
+        at_rest::decrypt_file(&key, file, &target)?;
+        println!("🔓 {}", tr!("cli.decrypt.done", file = target.display()));
+    }
+    Ok(())
+}
+
+/// 收藏直接读写本地文件，不经过守护进程
+/// 身份密钥与守护进程共用同一个存储（密钥环或 `~/.config/cattysend/identity.key`）
+async fn manage_identity(action: IdentityAction) -> Result<()> {
+    match action {
+        IdentityAction::Show { json } => {
+            // 还没有密钥时生成一个，与守护进程启动时的行为一致
+            let security =
+                tokio::task::spawn_blocking(|| IdentityStore::default().load_or_create()).await??;
+            let identity = DeviceIdentity::new(&security, &AppSettings::load());
+            if json {
+                println!("{}", serde_json::to_string_pretty(&identity)?);
+                return Ok(());
+            }
+            println!(
+                "🔑 {}: {}",
+                tr!("cli.identity.fingerprint"),
+                identity.fingerprint
+            );
+            println!(
+                "   {}: {}",
+                tr!("cli.identity.sender_id"),
+                identity.sender_id
+            );
+            println!("   {}: {}", tr!("cli.identity.name"), identity.device_name);
+            println!(
+                "   {}: {} ({})",
+                tr!("cli.identity.brand"),
+                identity.brand,
+                identity.brand_id
+            );
+            println!(
+                "   {}: {}",
+                tr!("cli.identity.public_key"),
+                identity.public_key
+            );
+            println!("   {}", tr!("cli.identity.verify_hint"));
+        }
+        IdentityAction::Reset { yes } => {
+            let confirmed = yes
+                || dialoguer::Confirm::new()
+                    .with_prompt(tr!("cli.identity.confirm_reset"))
+                    .default(false)
+                    .interact()?;
+            if !confirmed {
+                println!("{}", tr!("cli.identity.kept"));
+                return Ok(());
+            }
+            // 密钥环是同步 D-Bus 调用，不能直接在异步运行时里阻塞
+            let (_, location) =
+                tokio::task::spawn_blocking(|| IdentityStore::default().reset()).await??;
+            let location = match location {
+                KeyLocation::Keyring => tr!("cli.identity.keyring"),
+                KeyLocation::File(path) => path.display().to_string(),
+            };
+            println!("🔑 {}", tr!("cli.identity.reset", location = location));
+            println!("   {}", tr!("cli.identity.restart_hint"));
+        }
+    }
+    Ok(())
+}
+
+/// 列出最近的定时传输结果
+/// 输出学习到的候选解析规则（本地文件，不经过守护进程）
+fn show_learned(min_seen: u32, reset: bool) -> Result<()> {
+    let path = ShapeLearner::default_path();
+    if reset {
+        ShapeLearner::default().save_to(&path)?;
+        println!("{}", tr!("cli.learn.reset", path = path.display()));
+        return Ok(());
+    }
+    if !AppSettings::load().learn_unknown_vendors {
+        println!("{}", tr!("cli.learn.disabled"));
+    }
+    let learner = ShapeLearner::load_from(&path);
+    if learner.candidates(min_seen).is_empty() {
+        println!("{}", tr!("cli.learn.empty", count = learner.shapes.len()));
+        return Ok(());
+    }
+    println!("{}", tr!("cli.learn.hint"));
+    print!("{}", learner.candidates_toml(min_seen)?);
+    Ok(())
+}
+
+fn show_history(limit: usize, json: bool) -> Result<()> {
+    let entries = History::default().recent(limit)?;
+    if json {
+        for entry in &entries {
+            println!("{}", serde_json::to_string(entry)?);
+        }
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("{}", tr!("cli.history.empty"));
+    }
+    for entry in &entries {
+        let icon = match entry.outcome {
+            HistoryOutcome::Completed => "✅",
+            HistoryOutcome::Failed => "❌",
+            HistoryOutcome::Missed => "⏰",
+            HistoryOutcome::Cancelled => "⏹",
+        };
+        let schedule = entry
+            .schedule
+            .as_deref()
+            .map(|name| format!(" [{}]", name))
+            .unwrap_or_default();
+        println!(
+            "{} {:<14} {}{} - {}",
+            icon,
+            format_ago(entry.time),
+            entry.device,
+            schedule,
+            entry.files.join(", ")
+        );
+        if let Some(message) = &entry.message {
+            println!("   {}", message);
+        }
+    }
+    Ok(())
+}
+
+fn manage_favorites(action: FavAction) -> Result<()> {
+    let mut favorites = Favorites::load();
+    match action {
+        FavAction::List => {
+            if favorites.devices.is_empty() {
+                println!("{}", tr!("cli.fav.empty"));
+            }
+            for fav in &favorites.devices {
+                let seen = if fav.last_seen == 0 {
+                    tr!("cli.fav.never_seen")
+                } else {
+                    tr!("cli.fav.last_seen", time = format_ago(fav.last_seen))
+                };
+                println!(
+                    "@{:<16} {} ({}) - {}",
+                    fav.alias, fav.name, fav.address, seen
+                );
+            }
+        }
+        FavAction::Add {
+            alias,
+            address,
+            name,
+        } => {
+            let alias = alias
+                .trim_start_matches(favorites::ALIAS_PREFIX)
+                .to_string();
+            let mut favorite = Favorite::new(name.as_deref().unwrap_or(&address), &address);
+            favorite.alias.clone_from(&alias);
+            favorite.last_seen = 0;
+            favorites.add(favorite);
+            favorites.save()?;
+            println!(
+                "⭐ {}",
+                tr!("cli.fav.added", alias = alias, address = address)
+            );
+        }
+        FavAction::Remove { alias } => {
+            if !favorites.remove(&alias) {
+                anyhow::bail!(favorites::UnknownAlias(
+                    alias
+                        .trim_start_matches(favorites::ALIAS_PREFIX)
+                        .to_string()
+                ));
+            }
+            favorites.save()?;
+            println!("{}", tr!("cli.fav.removed", alias = alias));
+        }
+    }
+    Ok(())
+}
+
+/// 检查运行环境，逐项打印问题和修复建议；有错误时返回失败
+async fn doctor() -> Result<()> {
+    println!("🩺 {}", tr!("cli.doctor.title"));
+    let report = diagnostics::run().await;
+    let min = format!(
+        "{}.{}",
+        diagnostics::MIN_BLUEZ_VERSION.0,
+        diagnostics::MIN_BLUEZ_VERSION.1
+    );
+
+    for check in &report.checks {
+        let icon = match check.severity() {
+            Severity::Ok => "✅",
+            Severity::Warning => "⚠️ ",
+            Severity::Error => "❌",
+        };
+        let name = tr!(&format!("cli.doctor.check.{}", check.check.id()));
+        let summary = check
+            .summary
+            .clone()
+            .unwrap_or_else(|| tr!("cli.doctor.ok"));
+        println!("{} {}: {}", icon, name, summary);
+        for issue in &check.issues {
+            let key = issue.id();
+            let detail = issue.detail();
+            println!(
+                "   {}",
+                tr!(
+                    &format!("cli.doctor.issue.{}", key),
+                    detail = detail,
+                    min = min
+                )
+            );
+            println!(
+                "   → {}",
+                tr!(
+                    &format!("cli.doctor.fix.{}", key),
+                    detail = detail,
+                    min = min
+                )
+            );
+        }
+    }
+
+    let count = |severity| report.issues().filter(|i| i.severity() == severity).count();
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+    println!();
+    if errors == 0 && warnings == 0 {
+        println!("{}", tr!("cli.doctor.all_good"));
+    } else {
+        println!(
+            "{}",
+            tr!("cli.doctor.summary", errors = errors, warnings = warnings)
+        );
+    }
+    if report.has_errors() {
+        anyhow::bail!(tr!("cli.doctor.failed"));
+    }
+    Ok(())
+}
+
+/// 把 Unix 时间戳格式化为"多久以前"
+fn format_ago(secs: u64) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let ago = now.saturating_sub(secs);
+    match ago {
+        0..=59 => tr!("cli.fav.ago_secs", n = ago),
+        60..=3599 => tr!("cli.fav.ago_mins", n = ago / 60),
+        3600..=86399 => tr!("cli.fav.ago_hours", n = ago / 3600),
+        _ => tr!("cli.fav.ago_days", n = ago / 86400),
+    }
+}
+
+/// 解析时长参数: 纯数字按秒计，支持 s/m/h 后缀
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (num, unit) = match s.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((idx, _)) => s.split_at(idx),
+        None => (s, "s"),
+    };
+    let value: u64 = num
+        .parse()
+        .map_err(|_| tr!("cli.duration.invalid", value = s))?;
+    let secs = match unit {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        _ => return Err(tr!("cli.duration.unknown_unit", unit = unit)),
+    };
+    if secs == 0 {
+        return Err(tr!("cli.duration.zero"));
+    }
+    Ok(Duration::from_secs(secs))
+}
@@ -0,0 +1,135 @@
+//! 发送目标选择
+//!
+//! 未指定 `--device` 时先扫描，再按以下顺序决定目标：
+//!
+//! - `--name <子串>`: 只保留名称包含该子串的设备（不区分大小写）
+//! - `--first`: 直接取信号最强的候选，不再询问
+//! - 否则在终端中列出候选设备供用户选择（`--gui-picker` 时改用对话框，见 [`crate::desktop`]）
+
+use crate::client::DeviceInfo;
+use anyhow::{Result, bail};
+use cattysend_core::tr;
+use dialoguer::Select;
+use dialoguer::theme::ColorfulTheme;
+use std::io::IsTerminal;
+
+/// 从扫描结果中选出目标设备
+///
+/// `devices` 应已按信号强度从强到弱排序（守护进程返回的顺序）。
+pub fn choose(devices: &[DeviceInfo], name: Option<&str>, first: bool) -> Result<DeviceInfo> {
+    choose_with(devices, name, first, prompt)
+}
+
+/// 同 [`choose`]，但需要询问用户时调用 `ask`，返回所选候选的下标
+pub fn choose_with(
+    devices: &[DeviceInfo],
+    name: Option<&str>,
+    first: bool,
+    ask: impl FnOnce(&[&DeviceInfo]) -> Result<usize>,
+) -> Result<DeviceInfo> {
+    let candidates = match name {
+        Some(pattern) => filter_by_name(devices, pattern),
+        None => devices.iter().collect(),
+    };
+
+    if candidates.is_empty() {
+        match name {
+            Some(pattern) => bail!(tr!("cli.pick.no_match", name = pattern)),
+            None => bail!(tr!("cli.scan.none")),
+        }
+    }
+    // 名称唯一匹配时无需再确认
+    if first || (name.is_some() && candidates.len() == 1) {
+        return Ok(candidates[0].clone());
+    }
+    let index = ask(&candidates)?;
+    match candidates.get(index) {
+        Some(device) => Ok((*device).clone()),
+        None => bail!(tr!("cli.pick.cancelled")),
+    }
+}
+
+/// 在终端中列出候选设备供用户选择
+fn prompt(candidates: &[&DeviceInfo]) -> Result<usize> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        bail!(tr!("cli.pick.ambiguous", count = candidates.len()));
+    }
+
+    let items: Vec<String> = candidates.iter().map(|d| describe(d)).collect();
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(tr!("cli.pick.prompt"))
+        .items(&items)
+        .default(0)
+        .interact_opt()?;
+
+    match selection {
+        Some(index) => Ok(index),
+        None => bail!(tr!("cli.pick.cancelled")),
+    }
+}
+
+/// 名称包含 `pattern`（不区分大小写）的设备，保持原有顺序
+pub fn filter_by_name<'a>(devices: &'a [DeviceInfo], pattern: &str) -> Vec<&'a DeviceInfo> {
+    let pattern = pattern.to_lowercase();
+    devices
+        .iter()
+        .filter(|d| d.name.to_lowercase().contains(&pattern))
+        .collect()
+}
+
+/// 设备的单行描述：名称、品牌、信号强度和地址
+pub fn describe(device: &DeviceInfo) -> String {
+    let rssi = device
+        .rssi
+        .map_or_else(|| "-- dBm".to_string(), |rssi| format!("{} dBm", rssi));
+    match &device.brand {
+        Some(brand) => format!("{} [{}] {} ({})", device.name, brand, rssi, device.address),
+        None => format!("{} {} ({})", device.name, rssi, device.address),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, rssi: i16) -> DeviceInfo {
+        DeviceInfo {
+            name: name.to_string(),
+            address: format!("AA:BB:CC:DD:EE:{:02X}", rssi.unsigned_abs()),
+            rssi: Some(rssi),
+            brand: Some("Xiaomi".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_filter_by_name_is_case_insensitive() {
+        let devices = [device("Redmi K70", -40), device("OPPO Find X7", -60)];
+        let matched = filter_by_name(&devices, "redmi");
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].name, "Redmi K70");
+        assert!(filter_by_name(&devices, "pixel").is_empty());
+    }
+
+    #[test]
+    fn test_choose_without_prompt() {
+        let devices = [device("Redmi K70", -40), device("Redmi Note", -70)];
+
+        // --first 取信号最强的
+        assert_eq!(choose(&devices, None, true).unwrap().name, "Redmi K70");
+        // 名称唯一匹配时直接选中
+        assert_eq!(
+            choose(&devices, Some("note"), false).unwrap().name,
+            "Redmi Note"
+        );
+        assert!(choose(&devices, Some("pixel"), true).is_err());
+        assert!(choose(&[], None, true).is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe(&device("Redmi K70", -40)),
+            "Redmi K70 [Xiaomi] -40 dBm (AA:BB:CC:DD:EE:28)"
+        );
+    }
+}
@@ -0,0 +1,385 @@
+//! `send` / `receive` 的进度输出
+//!
+//! 请求发出前先订阅事件流，之后只跟随本次传输的事件，直到完成、失败或被停止：
+//!
+//! - 默认显示进度条（速度、剩余时间），结束后打印文件汇总表
+//! - `--json` 每行输出一个 JSON：先是守护进程的响应，之后是本次传输的事件
+//! - `--quiet` 不输出进度，失败时只有错误信息
+
+use crate::client::{DaemonEvent, Events, IpcResponse};
+use crate::watch::format_bytes;
+use anyhow::{Result, bail};
+use cattysend_core::tr;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// 等待传输开始时的提示行
+const SPINNER_TEMPLATE: &str = "{spinner} {msg}";
+
+/// 传输中的进度条
+const BAR_TEMPLATE: &str = "{bar:30} {bytes}/{total_bytes} {bytes_per_sec} ETA {eta} {msg}";
+
+/// 输出方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Human,
+    Quiet,
+    Json,
+}
+
+impl Output {
+    pub fn new(quiet: bool, json: bool) -> Self {
+        match (quiet, json) {
+            (_, true) => Self::Json,
+            (true, false) => Self::Quiet,
+            (false, false) => Self::Human,
+        }
+    }
+
+    /// 是否输出给人看的提示
+    pub fn is_human(self) -> bool {
+        self == Self::Human
+    }
+
+    /// 输出守护进程对请求的响应，`error` 响应转为错误
+    pub fn response(self, response: &IpcResponse) -> Result<()> {
+        if self == Self::Json {
+            println!("{}", serde_json::to_string(response)?);
+        }
+        match response {
+            IpcResponse::Error { message } => bail!(message.clone()),
+            IpcResponse::Ok { message, .. } if self.is_human() => println!("✅ {}", message),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// 跟随本次传输直到结束
+///
+/// `session_id` 为 None 时（接收）跟随之后第一个出现的会话。
+pub async fn follow(mut events: Events, output: Output, session_id: Option<String>) -> Result<()> {
+    let mut transfer = Transfer::new(session_id);
+    let mut view = View::new(output);
+    while let Some((raw, response)) = events.next().await? {
+        let Some(IpcResponse::Event { session_id, event }) = response else {
+            continue;
+        };
+        if !transfer.accepts(session_id.as_deref()) {
+            continue;
+        }
+        if output == Output::Json {
+            println!("{}", raw);
+        }
+        match transfer.step(&event) {
+            Step::Continue => view.show(&event),
+            Step::Complete(files) => {
+                view.finish(&files);
+                return Ok(());
+            }
+            Step::Failed(message) => {
+                view.abandon();
+                bail!(message);
+            }
+            Step::Stopped => {
+                view.abandon();
+                bail!(tr!("cli.transfer.stopped"));
+            }
+            Step::WindowEnded => {
+                view.abandon();
+                if output.is_human() {
+                    println!("{}", tr!("cli.watch.discoverable_ended"));
+                }
+                return Ok(());
+            }
+        }
+    }
+    view.abandon();
+    bail!(tr!("cli.watch.disconnected"))
+}
+
+/// 事件对本次传输的意义
+#[derive(Debug, PartialEq, Eq)]
+enum Step {
+    Continue,
+    Complete(Vec<String>),
+    Failed(String),
+    /// 守护进程收到 stop
+    Stopped,
+    /// 可发现窗口结束，没有发送端发起传输
+    WindowEnded,
+}
+
+/// 过滤其他会话的事件，判断传输何时结束
+struct Transfer {
+    session_id: Option<String>,
+    /// 传输已经开始，之后的 `discoverable_ended` 不代表结束
+    engaged: bool,
+}
+
+impl Transfer {
+    fn new(session_id: Option<String>) -> Self {
+        Self {
+            session_id,
+            engaged: false,
+        }
+    }
+
+    /// 事件是否属于本次传输；不带会话 ID 的事件（可发现倒计时、stop）总是接收
+    fn accepts(&mut self, session_id: Option<&str>) -> bool {
+        match (self.session_id.as_deref(), session_id) {
+            (Some(ours), Some(id)) => ours == id,
+            (None, Some(id)) => {
+                self.session_id = Some(id.to_string());
+                true
+            }
+            (_, None) => true,
+        }
+    }
+
+    fn step(&mut self, event: &DaemonEvent) -> Step {
+        match event {
+            DaemonEvent::Request { .. } | DaemonEvent::Progress { .. } => {
+                self.engaged = true;
+                Step::Continue
+            }
+            DaemonEvent::DiscoverableEnded if !self.engaged => Step::WindowEnded,
+            DaemonEvent::Complete { files } => Step::Complete(files.clone()),
+            DaemonEvent::Error { message } => Step::Failed(message.clone()),
+            DaemonEvent::Cancelled => Step::Stopped,
+            _ => Step::Continue,
+        }
+    }
+}
+
+/// 默认模式下的进度条；其他模式下什么也不画
+struct View {
+    bar: Option<ProgressBar>,
+    /// 第一次收到进度的时间，用于计算平均速度
+    transfer_started: Option<Instant>,
+    started: Instant,
+}
+
+impl View {
+    fn new(output: Output) -> Self {
+        let bar = output.is_human().then(|| {
+            let bar = ProgressBar::new_spinner();
+            bar.set_style(style(SPINNER_TEMPLATE));
+            bar.enable_steady_tick(Duration::from_millis(120));
+            bar
+        });
+        Self {
+            bar,
+            transfer_started: None,
+            started: Instant::now(),
+        }
+    }
+
+    /// 在进度条上方打印一行（输出不是终端时进度条隐藏，直接打印）
+    fn line(&self, text: &str) {
+        match &self.bar {
+            Some(bar) if !bar.is_hidden() => bar.println(format!("   {}", text)),
+            Some(_) => println!("   {}", text),
+            None => {}
+        }
+    }
+
+    fn show(&mut self, event: &DaemonEvent) {
+        if matches!(event, DaemonEvent::Progress { .. }) && self.transfer_started.is_none() {
+            self.transfer_started = Some(Instant::now());
+            if let Some(bar) = &self.bar {
+                bar.set_style(style(BAR_TEMPLATE));
+                bar.set_message("");
+            }
+        }
+        let Some(bar) = &self.bar else {
+            return;
+        };
+        match event {
+            DaemonEvent::Status { message } => self.line(message),
+            DaemonEvent::Discoverable {
+                remaining_secs,
+                total_secs,
+            } => bar.set_message(tr!(
+                "cli.watch.discoverable",
+                remaining = remaining_secs,
+                total = total_secs
+            )),
+            DaemonEvent::Request {
+                sender_name,
+                file_name,
+                file_count,
+                total_size,
+            } => self.line(&tr!(
+                "cli.watch.request",
+                sender = sender_name,
+                file = file_name,
+                count = file_count,
+                size = format_bytes(*total_size)
+            )),
+            DaemonEvent::Progress { received, total } => {
+                bar.set_length(*total);
+                bar.set_position(*received);
+            }
+            DaemonEvent::Paused => bar.set_message(tr!("cli.watch.paused")),
+            DaemonEvent::Resumed => bar.set_message(""),
+            _ => {}
+        }
+    }
+
+    /// 清掉进度条并打印汇总表
+    fn finish(self, files: &[String]) {
+        let Some(bar) = self.bar else {
+            return;
+        };
+        bar.finish_and_clear();
+        let rows: Vec<(String, Option<u64>)> = files
+            .iter()
+            .map(|file| {
+                let path = Path::new(file);
+                let name = path
+                    .file_name()
+                    .map_or_else(|| file.clone(), |n| n.to_string_lossy().to_string());
+                (name, std::fs::metadata(path).ok().map(|m| m.len()))
+            })
+            .collect();
+        let elapsed = self.transfer_started.unwrap_or(self.started).elapsed();
+        for line in summary(&rows, elapsed) {
+            println!("{}", line);
+        }
+    }
+
+    fn abandon(self) {
+        if let Some(bar) = self.bar {
+            bar.abandon();
+        }
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template).unwrap_or_else(|_| ProgressStyle::default_bar())
+}
+
+/// 汇总表：每个文件的名称和大小，最后一行为总量、用时和平均速度
+fn summary(rows: &[(String, Option<u64>)], elapsed: Duration) -> Vec<String> {
+    let file_header = tr!("cli.transfer.file");
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain([file_header.chars().count()])
+        .max()
+        .unwrap_or(0);
+    let mut lines = vec![
+        format!("✅ {}", tr!("cli.transfer.complete", count = rows.len())),
+        format!(
+            "   {:<width$}  {:>10}",
+            file_header,
+            tr!("cli.transfer.size"),
+            width = width
+        ),
+    ];
+    for (name, size) in rows {
+        let size = size.map_or_else(|| "-".to_string(), format_bytes);
+        lines.push(format!("   {:<width$}  {:>10}", name, size, width = width));
+    }
+    let total: u64 = rows.iter().filter_map(|(_, size)| *size).sum();
+    let secs = elapsed.as_secs_f64().max(0.001);
+    lines.push(format!(
+        "   {}",
+        tr!(
+            "cli.transfer.summary",
+            size = format_bytes(total),
+            secs = format!("{:.1}", elapsed.as_secs_f64()),
+            speed = format_bytes((total as f64 / secs) as u64)
+        )
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_flags() {
+        assert_eq!(Output::new(false, false), Output::Human);
+        assert_eq!(Output::new(true, false), Output::Quiet);
+        assert_eq!(Output::new(false, true), Output::Json);
+    }
+
+    #[test]
+    fn test_follows_own_session() {
+        let mut transfer = Transfer::new(Some("send-1".to_string()));
+        assert!(transfer.accepts(Some("send-1")));
+        assert!(!transfer.accepts(Some("receive-9")));
+        assert!(transfer.accepts(None));
+
+        // 接收时跟随第一个出现的会话
+        let mut transfer = Transfer::new(None);
+        assert!(transfer.accepts(None));
+        assert!(transfer.accepts(Some("a")));
+        assert!(!transfer.accepts(Some("b")));
+        assert!(transfer.accepts(Some("a")));
+    }
+
+    #[test]
+    fn test_transfer_steps() {
+        let mut transfer = Transfer::new(None);
+        assert_eq!(
+            transfer.step(&DaemonEvent::Discoverable {
+                remaining_secs: 5,
+                total_secs: 10
+            }),
+            Step::Continue
+        );
+        assert_eq!(
+            transfer.step(&DaemonEvent::DiscoverableEnded),
+            Step::WindowEnded
+        );
+
+        // 传输开始后窗口结束不影响本次传输
+        let mut transfer = Transfer::new(None);
+        transfer.step(&DaemonEvent::Progress {
+            received: 1,
+            total: 2,
+        });
+        assert_eq!(
+            transfer.step(&DaemonEvent::DiscoverableEnded),
+            Step::Continue
+        );
+        assert_eq!(
+            transfer.step(&DaemonEvent::Complete {
+                files: vec!["/tmp/a".to_string()]
+            }),
+            Step::Complete(vec!["/tmp/a".to_string()])
+        );
+        assert_eq!(
+            transfer.step(&DaemonEvent::Error {
+                message: "boom".to_string()
+            }),
+            Step::Failed("boom".to_string())
+        );
+        assert_eq!(transfer.step(&DaemonEvent::Cancelled), Step::Stopped);
+    }
+
+    #[test]
+    fn test_summary_table() {
+        let rows = vec![
+            ("photo.jpg".to_string(), Some(2 * 1024 * 1024)),
+            ("a.txt".to_string(), Some(12)),
+            ("gone.bin".to_string(), None),
+        ];
+        let lines = summary(&rows, Duration::from_secs(2));
+        assert_eq!(lines.len(), 6);
+        // 名称列按最长的名称对齐
+        let offset = |line: &str| line.find("2.0 MB").or_else(|| line.find("12 B"));
+        assert_eq!(
+            offset(&lines[2]).map(|i| i + "2.0 MB".len()),
+            offset(&lines[3]).map(|i| i + "12 B".len())
+        );
+        assert!(lines[4].trim_end().ends_with('-'));
+        assert!(lines[5].contains("2.0 MB"));
+        assert!(lines[5].contains("1.0 MB"));
+    }
+}
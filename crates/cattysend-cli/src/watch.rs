@@ -0,0 +1,212 @@
+//! 实时输出守护进程事件
+//!
+//! 默认打印可读的单行描述；`--json` 时原样输出守护进程推送的每一行 JSON，
+//! 便于用 `jq` 等工具处理。进度和可发现倒计时推送很频繁，可读模式下会抽稀。
+
+use crate::client::{self, DaemonEvent, IpcResponse};
+use crate::picker;
+use anyhow::Result;
+use cattysend_core::tr;
+
+/// 可读模式下可发现倒计时的打印间隔（秒）
+const DISCOVERABLE_PRINT_INTERVAL: u64 = 10;
+
+/// 订阅事件并输出，直到守护进程退出
+pub async fn run(json: bool) -> Result<()> {
+    if !json {
+        eprintln!("👀 {}", tr!("cli.watch.started"));
+    }
+    let mut printer = EventPrinter::default();
+    client::subscribe(|raw, response| {
+        if json {
+            println!("{}", raw);
+        } else if let Some(IpcResponse::Event { session_id, event }) = response {
+            for line in printer.format(session_id.as_deref(), &event) {
+                println!("{}", line);
+            }
+        }
+    })
+    .await?;
+    eprintln!("{}", tr!("cli.watch.disconnected"));
+    Ok(())
+}
+
+/// 把事件整理成可读的行，记住上次打印的进度以免刷屏
+#[derive(Default)]
+struct EventPrinter {
+    /// 上次打印的进度百分比
+    last_percent: Option<u64>,
+    /// 本轮可发现窗口是否已打印过
+    discoverable: bool,
+}
+
+impl EventPrinter {
+    /// 事件对应的输出行，不需要打印时为空
+    fn format(&mut self, session_id: Option<&str>, event: &DaemonEvent) -> Vec<String> {
+        let prefix = match session_id {
+            Some(id) => format!("[{}] ", id.get(..8).unwrap_or(id)),
+            None => String::new(),
+        };
+        let mut lines = match event {
+            DaemonEvent::Discoverable {
+                remaining_secs,
+                total_secs,
+            } => {
+                if self.discoverable && remaining_secs % DISCOVERABLE_PRINT_INTERVAL != 0 {
+                    return Vec::new();
+                }
+                self.discoverable = true;
+                vec![tr!(
+                    "cli.watch.discoverable",
+                    remaining = remaining_secs,
+                    total = total_secs
+                )]
+            }
+            DaemonEvent::DiscoverableEnded => {
+                self.discoverable = false;
+                vec![tr!("cli.watch.discoverable_ended")]
+            }
+            DaemonEvent::Status { message } => vec![message.clone()],
+            DaemonEvent::Devices { devices } => {
+                let mut lines = vec![tr!("cli.watch.devices", count = devices.len())];
+                lines.extend(devices.iter().map(|d| format!("  {}", picker::describe(d))));
+                lines
+            }
+            DaemonEvent::Request {
+                sender_name,
+                file_name,
+                file_count,
+                total_size,
+            } => {
+                self.last_percent = None;
+                vec![tr!(
+                    "cli.watch.request",
+                    sender = sender_name,
+                    file = file_name,
+                    count = file_count,
+                    size = format_bytes(*total_size)
+                )]
+            }
+            DaemonEvent::Progress { received, total } => {
+                let percent = received
+                    .saturating_mul(100)
+                    .checked_div(*total)
+                    .unwrap_or(0);
+                if self.last_percent == Some(percent) {
+                    return Vec::new();
+                }
+                self.last_percent = Some(percent);
+                vec![tr!(
+                    "cli.watch.progress",
+                    percent = percent,
+                    received = format_bytes(*received),
+                    total = format_bytes(*total)
+                )]
+            }
+            DaemonEvent::Paused => vec![tr!("cli.watch.paused")],
+            DaemonEvent::Resumed => vec![tr!("cli.watch.resumed")],
+            DaemonEvent::Complete { files } => {
+                self.last_percent = None;
+                let mut lines = vec![tr!("cli.watch.complete", count = files.len())];
+                lines.extend(files.iter().map(|f| format!("  {}", f)));
+                lines
+            }
+            DaemonEvent::Error { message } => {
+                self.last_percent = None;
+                vec![tr!("cli.watch.error", error = message)]
+            }
+            DaemonEvent::Cancelled => {
+                self.last_percent = None;
+                vec![tr!("cli.watch.cancelled")]
+            }
+        };
+        if let Some(first) = lines.first_mut() {
+            first.insert_str(0, &prefix);
+        }
+        lines
+    }
+}
+
+/// 以 B/KB/MB/GB 显示字节数
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_printed_once_per_percent() {
+        let mut printer = EventPrinter::default();
+        let progress = |received| DaemonEvent::Progress {
+            received,
+            total: 1000,
+        };
+
+        assert_eq!(printer.format(Some("0123456789"), &progress(5)).len(), 1);
+        assert!(printer.format(Some("0123456789"), &progress(9)).is_empty());
+        let lines = printer.format(Some("0123456789"), &progress(10));
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("[01234567] "));
+        // 总大小未知时不除以 0
+        assert_eq!(
+            printer
+                .format(
+                    None,
+                    &DaemonEvent::Progress {
+                        received: 1,
+                        total: 0
+                    }
+                )
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_discoverable_countdown_is_thinned() {
+        let mut printer = EventPrinter::default();
+        let tick = |remaining_secs| DaemonEvent::Discoverable {
+            remaining_secs,
+            total_secs: 95,
+        };
+
+        let printed: Vec<u64> = (85..=95)
+            .rev()
+            .filter(|&secs| !printer.format(None, &tick(secs)).is_empty())
+            .collect();
+        assert_eq!(printed, vec![95, 90]);
+
+        assert_eq!(
+            printer.format(None, &DaemonEvent::DiscoverableEnded).len(),
+            1
+        );
+        assert_eq!(printer.format(None, &tick(59)).len(), 1);
+    }
+
+    #[test]
+    fn test_multi_line_events() {
+        let mut printer = EventPrinter::default();
+        let lines = printer.format(
+            Some("abc"),
+            &DaemonEvent::Complete {
+                files: vec!["/tmp/a.txt".to_string(), "/tmp/b.txt".to_string()],
+            },
+        );
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("[abc] "));
+        assert_eq!(lines[1], "  /tmp/a.txt");
+    }
+}
@@ -0,0 +1,48 @@
+//! 会话加密基准
+//!
+//! P2P 信息的每个字段都要经过一次 [`SessionCipher`] 加解密，BLE 握手还要做一次
+//! ECDH 密钥派生。
+//!
+//! ```bash
+//! cargo bench -p cattysend-core --bench crypto
+//! ```
+
+use cattysend_core::crypto::{BleSecurityPersistent, SessionCipher};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+fn session_cipher() -> SessionCipher {
+    let local = BleSecurityPersistent::new().unwrap();
+    let peer = BleSecurityPersistent::new().unwrap();
+    local.derive_session_key(peer.get_public_key()).unwrap()
+}
+
+fn bench_derive_session_key(c: &mut Criterion) {
+    let local = BleSecurityPersistent::new().unwrap();
+    let peer = BleSecurityPersistent::new().unwrap();
+    let peer_key = peer.get_public_key().to_string();
+    c.bench_function("derive_session_key", |b| {
+        b.iter(|| local.derive_session_key(black_box(&peer_key)).unwrap())
+    });
+}
+
+fn bench_session_cipher(c: &mut Criterion) {
+    let cipher = session_cipher();
+    let mut group = c.benchmark_group("session_cipher");
+    // SSID/PSK/MAC 这类短字段，以及较长的 JSON 负载
+    for size in [16, 64, 1024, 16 * 1024] {
+        let plaintext = "x".repeat(size);
+        let ciphertext = cipher.encrypt(&plaintext).unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("encrypt", size), &plaintext, |b, data| {
+            b.iter(|| cipher.encrypt(black_box(data)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decrypt", size), &ciphertext, |b, data| {
+            b.iter(|| cipher.decrypt(black_box(data)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_derive_session_key, bench_session_cipher);
+criterion_main!(benches);
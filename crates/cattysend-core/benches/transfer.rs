@@ -0,0 +1,114 @@
+//! 传输路径基准
+//!
+//! - `zip`：发送端把任务文件打包成 `/download` 的响应体
+//! - `loopback`：127.0.0.1 上的完整 HTTP 传输（WebSocket 协商 → ZIP 下载 → 解压落盘），
+//!   不经过 BLE 和 WiFi，只衡量传输栈本身
+//!
+//! ```bash
+//! cargo bench -p cattysend-core --features loopback-test --bench transfer
+//! ```
+
+use cattysend_core::testing::temp_dir;
+use cattysend_core::transfer::sender_server::create_zip_response;
+use cattysend_core::transfer::{TransferServer, TransferTask};
+use cattysend_core::{FileEntry, ReceiverCallback, ReceiverClient, SendRequest};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const SIZES: [usize; 3] = [64 * 1024, 4 * 1024 * 1024, 64 * 1024 * 1024];
+
+struct AcceptAll;
+
+impl ReceiverCallback for AcceptAll {
+    fn on_send_request(&self, _request: &SendRequest) -> bool {
+        true
+    }
+    fn on_progress(&self, _received: u64, _total: u64) {}
+    fn on_complete(&self, _files: Vec<PathBuf>) {}
+    fn on_error(&self, _error: String) {}
+}
+
+/// 写一个 `size` 字节的输入文件（内容不可压缩，与照片、视频相近）
+fn input_file(dir: &Path, size: usize) -> PathBuf {
+    let mut data = vec![0u8; size];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut data);
+    let path = dir.join(format!("{}.bin", size));
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+}
+
+fn bench_zip(c: &mut Criterion) {
+    let rt = runtime();
+    let input_dir = temp_dir("zip");
+    let mut group = c.benchmark_group("zip");
+    for size in SIZES {
+        let path = input_file(&input_dir, size);
+        let files = vec![rt.block_on(FileEntry::from_path(&path)).unwrap()];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &files, |b, files| {
+            b.to_async(&rt)
+                .iter(|| async { create_zip_response(files).await.unwrap() })
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_dir_all(input_dir);
+}
+
+/// 提供一次传输任务并用 [`ReceiverClient`] 接收，只计接收耗时
+async fn loopback_transfer(files: &[FileEntry]) -> Duration {
+    let task = TransferTask {
+        task_id: uuid::Uuid::new_v4().to_string(),
+        files: files.to_vec(),
+        sender_id: "0000".to_string(),
+        sender_name: "bench".to_string(),
+        verification_code: Default::default(),
+        auth_token: None,
+        tls: None,
+        sync: None,
+    };
+    let mut server = TransferServer::new(task);
+    let port = server.start().await.unwrap();
+    let output_dir = temp_dir("loopback-recv");
+    let client = ReceiverClient::new("127.0.0.1", port, output_dir.clone()).with_tls(false);
+
+    let start = Instant::now();
+    client.start(&AcceptAll).await.unwrap();
+    let elapsed = start.elapsed();
+
+    let _ = tokio::fs::remove_dir_all(output_dir).await;
+    elapsed
+}
+
+fn bench_loopback(c: &mut Criterion) {
+    let rt = runtime();
+    let input_dir = temp_dir("loopback-send");
+    let mut group = c.benchmark_group("loopback");
+    group.sample_size(10);
+    for size in SIZES {
+        let path = input_file(&input_dir, size);
+        let files = vec![rt.block_on(FileEntry::from_path(&path)).unwrap()];
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &files, |b, files| {
+            b.to_async(&rt).iter_custom(|iters| async move {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    total += loopback_transfer(files).await;
+                }
+                total
+            })
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_dir_all(input_dir);
+}
+
+criterion_group!(benches, bench_zip, bench_loopback);
+criterion_main!(benches);
@@ -0,0 +1,248 @@
+//! 高层 API
+//!
+//! 给嵌入协议的第三方程序使用，不需要了解 [`Sender`] / [`Receiver`] 的阶段和回调：
+//!
+//! ```ignore
+//! use cattysend_core::{Cattysend, ReceiveOptions, SendOptions};
+//! use futures_util::StreamExt;
+//!
+//! // 发送：扫描后直接发给第一个设备
+//! let devices = Cattysend::discover().await?;
+//! devices[0].send(["photo.jpg"], SendOptions::default()).await?;
+//!
+//! // 接收：边收边看事件，结束后取回文件列表
+//! let mut receiving = Cattysend::receive(ReceiveOptions {
+//!     auto_accept: true,
+//!     ..Default::default()
+//! });
+//! while let Some(event) = receiving.next().await {
+//!     println!("{:?}", event);
+//! }
+//! let files = receiving.await?;
+//! ```
+//!
+//! 每个操作都在后台任务中运行，返回的 [`Task`] 既是事件流（[`Stream`]），
+//! 也可以直接 `.await` 得到结果。丢弃 [`Task`] 会取消操作。
+
+use crate::ble::{DiscoveredDevice, ScanCallback};
+use crate::cancel::CancellationToken;
+use crate::discovery::{DiscoveryMethod, discover_devices};
+use crate::workflow::{
+    ReceiveEvent, ReceiveOptions, Receiver, SendEvent, SendOptions, Sender, SimpleReceiveCallback,
+    SimpleSendCallback,
+};
+use async_trait::async_trait;
+use futures_util::Stream;
+use futures_util::future::BoxFuture;
+use std::future::{Future, IntoFuture};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::DropGuard;
+
+/// [`Cattysend::discover`] 的扫描时长
+pub const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 在后台运行的操作
+///
+/// 作为 [`Stream`] 依次产生事件 `E`；`.await` 等待操作结束并返回结果 `T`，
+/// 尚未取走的事件被丢弃。事件通道满时新事件同样被丢弃，不会阻塞操作本身。
+pub struct Task<E, T> {
+    events: mpsc::Receiver<E>,
+    handle: JoinHandle<anyhow::Result<T>>,
+    cancel: CancellationToken,
+    /// 丢弃时取消操作
+    guard: DropGuard,
+}
+
+/// 发送操作，事件为 [`SendEvent`]
+pub type SendTask = Task<SendEvent, ()>;
+
+/// 接收操作，事件为 [`ReceiveEvent`]，结果为保存的文件
+pub type ReceiveTask = Task<ReceiveEvent, Vec<PathBuf>>;
+
+/// 扫描操作，边扫描边产生发现的设备，结果为排好序的全部设备
+pub type DiscoverTask = Task<Device, Vec<Device>>;
+
+impl<E: Send + 'static, T: Send + 'static> Task<E, T> {
+    /// 在后台运行 `run`，它拿到取消令牌和事件发送端
+    fn spawn<F, Fut>(capacity: usize, run: F) -> Self
+    where
+        F: FnOnce(CancellationToken, mpsc::Sender<E>) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        let cancel = CancellationToken::new();
+        let (tx, events) = mpsc::channel(capacity);
+        let handle = tokio::spawn(run(cancel.clone(), tx));
+        Self {
+            events,
+            handle,
+            guard: cancel.clone().drop_guard(),
+            cancel,
+        }
+    }
+
+    /// 在后台运行 `run`，事件通道由 `run` 自己创建（如 [`SimpleSendCallback::new`]）
+    fn spawn_with_events<Fut>(
+        events: mpsc::Receiver<E>,
+        run: impl FnOnce(CancellationToken) -> Fut,
+    ) -> Self
+    where
+        Fut: Future<Output = anyhow::Result<T>> + Send + 'static,
+    {
+        let cancel = CancellationToken::new();
+        let handle = tokio::spawn(run(cancel.clone()));
+        Self {
+            events,
+            handle,
+            guard: cancel.clone().drop_guard(),
+            cancel,
+        }
+    }
+
+    /// 取消操作，之后 `.await` 返回 [`Interrupted::Cancelled`](crate::Interrupted::Cancelled)
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl<E, T> Stream for Task<E, T> {
+    type Item = E;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<E>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl<E: Send + 'static, T: Send + 'static> IntoFuture for Task<E, T> {
+    type Output = anyhow::Result<T>;
+    type IntoFuture = BoxFuture<'static, anyhow::Result<T>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        let Task {
+            events,
+            handle,
+            guard,
+            ..
+        } = self;
+        drop(events);
+        Box::pin(async move {
+            // 等待期间丢弃 future 同样取消操作
+            let _guard = guard;
+            handle.await?
+        })
+    }
+}
+
+/// 扫描到的接收端
+#[derive(Debug, Clone)]
+pub struct Device {
+    info: DiscoveredDevice,
+}
+
+impl From<DiscoveredDevice> for Device {
+    fn from(info: DiscoveredDevice) -> Self {
+        Self { info }
+    }
+}
+
+impl Device {
+    pub fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    pub fn address(&self) -> &str {
+        &self.info.address
+    }
+
+    /// 扫描得到的完整信息（厂商、信号强度、是否忙碌等）
+    pub fn info(&self) -> &DiscoveredDevice {
+        &self.info
+    }
+
+    /// 把文件发送给该设备
+    pub fn send<P: Into<PathBuf>>(
+        &self,
+        paths: impl IntoIterator<Item = P>,
+        options: SendOptions,
+    ) -> SendTask {
+        let device = self.info.clone();
+        let paths = paths.into_iter().map(Into::into).collect();
+        let (callback, events) = SimpleSendCallback::new();
+        Task::spawn_with_events(events, move |cancel| async move {
+            let sender = Sender::new(options)?.with_cancellation(cancel);
+            sender.send_to_device(&device, paths, &callback).await
+        })
+    }
+
+    /// 用已经配置好的 [`Sender`]（如替换了 WiFi / BLE 后端）发送
+    ///
+    /// `sender` 原有的取消令牌被返回的 [`Task`] 的令牌取代。
+    pub fn send_with<P: Into<PathBuf>>(
+        &self,
+        sender: Sender,
+        paths: impl IntoIterator<Item = P>,
+    ) -> SendTask {
+        let device = self.info.clone();
+        let paths = paths.into_iter().map(Into::into).collect();
+        let (callback, events) = SimpleSendCallback::new();
+        Task::spawn_with_events(events, move |cancel| async move {
+            let sender = sender.with_cancellation(cancel);
+            sender.send_to_device(&device, paths, &callback).await
+        })
+    }
+}
+
+/// 高层 API 的入口
+pub struct Cattysend;
+
+impl Cattysend {
+    /// 用默认方式（BLE + 局域网）扫描 [`DEFAULT_SCAN_TIMEOUT`]
+    pub fn discover() -> DiscoverTask {
+        Self::discover_with(DiscoveryMethod::default(), DEFAULT_SCAN_TIMEOUT)
+    }
+
+    /// 用指定方式扫描 `timeout`
+    pub fn discover_with(method: DiscoveryMethod, timeout: Duration) -> DiscoverTask {
+        Task::spawn(32, move |cancel, tx| async move {
+            let callback: Arc<dyn ScanCallback> = Arc::new(DeviceSink(tx));
+            let devices = discover_devices(method, timeout, Some(callback), &cancel).await?;
+            Ok(devices.into_iter().map(Device::from).collect())
+        })
+    }
+
+    /// 进入接收模式，处理一个发送端后结束
+    ///
+    /// `options.auto_accept` 为 `false` 时拒绝所有请求；需要逐个确认时直接使用
+    /// [`Receiver`] 并实现 [`ReceiveProgressCallback::on_request`](crate::ReceiveProgressCallback::on_request)。
+    pub fn receive(options: ReceiveOptions) -> ReceiveTask {
+        let (callback, events) = SimpleReceiveCallback::new(options.auto_accept);
+        Task::spawn_with_events(events, move |cancel| async move {
+            let receiver = Receiver::new(options)?.with_cancellation(cancel);
+            receiver.start(&callback).await
+        })
+    }
+
+    /// 用已经配置好的 [`Receiver`] 接收（是否接受由其 `auto_accept` 决定）
+    pub fn receive_with(receiver: Receiver) -> ReceiveTask {
+        let (callback, events) = SimpleReceiveCallback::new(false);
+        Task::spawn_with_events(events, move |cancel| async move {
+            let receiver = receiver.with_cancellation(cancel);
+            receiver.start(&callback).await
+        })
+    }
+}
+
+/// 把扫描到的设备交给 [`DiscoverTask`] 的事件流
+struct DeviceSink(mpsc::Sender<Device>);
+
+#[async_trait]
+impl ScanCallback for DeviceSink {
+    async fn on_device_found(&self, device: DiscoveredDevice) {
+        let _ = self.0.try_send(device.into());
+    }
+}
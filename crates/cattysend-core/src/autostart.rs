@@ -0,0 +1,405 @@
+//! 登录后自动进入接收模式
+//!
+//! 两种方式（[`AutostartMode`]）：
+//!
+//! - `daemon`：systemd 用户单元 `cattysend-receive.service`，以 `--receive` 启动守护进程，
+//!   进入被动监听。它与按需启动的 `cattysend.service` / `cattysend.socket` 互斥，
+//!   启动后由它提供 IPC socket
+//! - `gui`：XDG autostart 条目，登录后以 `--receive` 启动 GUI
+//!
+//! 生成的文件都带有 [`GENERATED_MARKER`]，删除时只删除自己生成的文件。
+//!
+//! 设置了 [`AppSettings::trusted_networks`](crate::AppSettings::trusted_networks) 时，
+//! 自动进入的接收模式只在连接到其中某个 WiFi 网络时才可被发现（见 [`on_trusted_network`]）。
+
+use anyhow::Context;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 生成文件的第一行
+pub const GENERATED_MARKER: &str = "# Generated by cattysend (autostart)";
+/// systemd 用户单元名
+pub const UNIT_NAME: &str = "cattysend-receive.service";
+/// XDG autostart 条目文件名
+pub const DESKTOP_ENTRY_NAME: &str = "cattysend-receive.desktop";
+/// 守护进程和 GUI 以接收模式启动的参数
+pub const RECEIVE_ARG: &str = "--receive";
+/// 设置了受信任网络时，自动进入的接收模式检查当前 WiFi 网络的间隔
+pub const TRUSTED_NETWORK_POLL: Duration = Duration::from_secs(30);
+
+/// 登录后自动进入接收模式的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AutostartMode {
+    #[default]
+    Off,
+    /// 守护进程被动监听（systemd 用户单元）
+    Daemon,
+    /// GUI 接收模式（XDG autostart）
+    Gui,
+}
+
+impl AutostartMode {
+    /// 获取所有方式
+    pub fn all() -> &'static [AutostartMode] {
+        &[
+            AutostartMode::Off,
+            AutostartMode::Daemon,
+            AutostartMode::Gui,
+        ]
+    }
+
+    /// 获取显示名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            AutostartMode::Off => "off",
+            AutostartMode::Daemon => "daemon",
+            AutostartMode::Gui => "gui",
+        }
+    }
+
+    /// 对应的可执行文件名
+    fn binary(&self) -> Option<&'static str> {
+        match self {
+            AutostartMode::Off => None,
+            AutostartMode::Daemon => Some("cattysend-daemon"),
+            AutostartMode::Gui => Some("cattysend-gui"),
+        }
+    }
+}
+
+/// 自启动条目的安装位置
+#[derive(Debug, Clone)]
+pub struct Autostart {
+    config_dir: PathBuf,
+    /// 修改单元后是否执行 `systemctl --user daemon-reload`
+    reload_systemd: bool,
+}
+
+impl Default for Autostart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Autostart {
+    /// 当前用户的配置目录（`$XDG_CONFIG_HOME`，默认 `~/.config`）
+    pub fn new() -> Self {
+        Self {
+            config_dir: dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")),
+            reload_systemd: true,
+        }
+    }
+
+    /// 使用指定的配置目录（不通知 systemd，用于测试）
+    pub fn with_config_dir(config_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            config_dir: config_dir.into(),
+            reload_systemd: false,
+        }
+    }
+
+    fn unit_dir(&self) -> PathBuf {
+        self.config_dir.join("systemd/user")
+    }
+
+    fn unit_path(&self) -> PathBuf {
+        self.unit_dir().join(UNIT_NAME)
+    }
+
+    fn wants_link(&self) -> PathBuf {
+        self.unit_dir().join("default.target.wants").join(UNIT_NAME)
+    }
+
+    fn desktop_entry_path(&self) -> PathBuf {
+        self.config_dir.join("autostart").join(DESKTOP_ENTRY_NAME)
+    }
+
+    /// 当前安装的方式
+    pub fn installed(&self) -> AutostartMode {
+        if is_generated(&self.unit_path()) && self.wants_link().exists() {
+            AutostartMode::Daemon
+        } else if is_generated(&self.desktop_entry_path()) {
+            AutostartMode::Gui
+        } else {
+            AutostartMode::Off
+        }
+    }
+
+    /// 安装 `mode` 对应的条目并删除另一种；`bin_dir` 为可执行文件所在目录
+    /// （见 [`default_bin_dir`]）
+    pub fn apply(&self, mode: AutostartMode, bin_dir: &Path) -> anyhow::Result<()> {
+        self.remove_unit()?;
+        remove_generated(&self.desktop_entry_path())?;
+
+        let Some(binary) = mode.binary() else {
+            self.reload();
+            return Ok(());
+        };
+        let exe = bin_dir.join(binary);
+        if !exe.exists() {
+            warn!("{} not found, autostart entry may not work", exe.display());
+        }
+        match mode {
+            AutostartMode::Daemon => self.install_unit(&exe)?,
+            AutostartMode::Gui => {
+                write_generated(&self.desktop_entry_path(), &desktop_entry(&exe))?
+            }
+            AutostartMode::Off => {}
+        }
+        self.reload();
+        Ok(())
+    }
+
+    fn install_unit(&self, exe: &Path) -> anyhow::Result<()> {
+        let unit = self.unit_path();
+        write_generated(&unit, &systemd_unit(exe))?;
+        // 等同于 `systemctl --user enable`：在 default.target.wants 中建立链接
+        let link = self.wants_link();
+        if let Some(parent) = link.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if link.symlink_metadata().is_ok() {
+            fs::remove_file(&link)?;
+        }
+        std::os::unix::fs::symlink(&unit, &link)
+            .with_context(|| format!("Failed to link {}", link.display()))?;
+        Ok(())
+    }
+
+    fn remove_unit(&self) -> anyhow::Result<()> {
+        let link = self.wants_link();
+        if is_generated(&self.unit_path()) && link.symlink_metadata().is_ok() {
+            fs::remove_file(&link)?;
+        }
+        remove_generated(&self.unit_path())
+    }
+
+    fn reload(&self) {
+        if !self.reload_systemd {
+            return;
+        }
+        match std::process::Command::new("systemctl")
+            .args(["--user", "daemon-reload"])
+            .status()
+        {
+            Ok(status) if status.success() => {}
+            Ok(status) => debug!("systemctl --user daemon-reload exited with {}", status),
+            Err(e) => debug!("systemctl not available: {}", e),
+        }
+    }
+}
+
+/// 当前可执行文件所在目录（各二进制文件安装在一起）
+pub fn default_bin_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("/usr/local/bin"))
+}
+
+/// 是否连接到受信任的网络；`trusted` 为空时不限制
+///
+/// 无法查询 NetworkManager 时视为不受信任。
+pub async fn on_trusted_network(trusted: &[String]) -> bool {
+    if trusted.is_empty() {
+        return true;
+    }
+    let connected = match crate::wifi::nm_dbus::NmClient::new().await {
+        Ok(client) => client.connected_ssids().await,
+        Err(e) => Err(e),
+    };
+    match connected {
+        Ok(connected) => is_trusted(&connected, trusted),
+        Err(e) => {
+            warn!("Cannot query the current WiFi network: {}", e);
+            false
+        }
+    }
+}
+
+/// `connected` 中是否有 `trusted` 里的网络；`trusted` 为空时不限制
+pub fn is_trusted(connected: &[String], trusted: &[String]) -> bool {
+    trusted.is_empty() || connected.iter().any(|ssid| trusted.contains(ssid))
+}
+
+fn systemd_unit(exe: &Path) -> String {
+    format!(
+        "{GENERATED_MARKER}
+[Unit]
+Description=Cattysend receive mode
+After=bluetooth.target network-online.target
+# 与按需启动的守护进程共用 IPC socket，不能同时运行
+Conflicts=cattysend.service cattysend.socket
+
+[Service]
+Type=simple
+ExecStart={} {RECEIVE_ARG}
+Restart=on-failure
+RestartSec=5
+
+[Install]
+WantedBy=default.target
+",
+        systemd_quote(exe)
+    )
+}
+
+fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "{GENERATED_MARKER}
+[Desktop Entry]
+Type=Application
+Name=Cattysend
+Comment=Start Cattysend in receive mode
+Exec={} {RECEIVE_ARG}
+Icon=document-send
+Terminal=false
+NoDisplay=true
+X-GNOME-Autostart-enabled=true
+",
+        desktop_quote(exe)
+    )
+}
+
+/// systemd 的 `ExecStart` 参数：双引号包围，转义 `\` 和 `"`，`%`/`$` 写两次
+fn systemd_quote(path: &Path) -> String {
+    let mut quoted = String::from('"');
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '\\' | '"' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            '$' => quoted.push_str("$$"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Desktop Entry 的 `Exec` 参数：双引号包围，转义 `"`、`` ` ``、`$`、`\`，`%` 写两次
+fn desktop_quote(path: &Path) -> String {
+    let mut quoted = String::from('"');
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn is_generated(path: &Path) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.starts_with(GENERATED_MARKER))
+}
+
+fn write_generated(path: &Path, content: &str) -> anyhow::Result<()> {
+    if path.exists() && !is_generated(path) {
+        anyhow::bail!("{} exists and was not created by cattysend", path.display());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    debug!("Wrote autostart entry {:?}", path);
+    Ok(())
+}
+
+/// 删除自己生成的文件，用户自己的同名文件保留
+fn remove_generated(path: &Path) -> anyhow::Result<()> {
+    if is_generated(path) {
+        fs::remove_file(path)?;
+        debug!("Removed autostart entry {:?}", path);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    fn temp_config() -> PathBuf {
+        temp_dir("autostart")
+    }
+
+    #[test]
+    fn test_switch_modes() {
+        let config = temp_config();
+        let autostart = Autostart::with_config_dir(&config);
+        let bin = Path::new("/opt/cattysend/bin");
+        assert_eq!(autostart.installed(), AutostartMode::Off);
+
+        autostart.apply(AutostartMode::Gui, bin).unwrap();
+        assert_eq!(autostart.installed(), AutostartMode::Gui);
+        let entry = fs::read_to_string(config.join("autostart").join(DESKTOP_ENTRY_NAME)).unwrap();
+        assert!(entry.contains("Exec=\"/opt/cattysend/bin/cattysend-gui\" --receive"));
+
+        autostart.apply(AutostartMode::Daemon, bin).unwrap();
+        assert_eq!(autostart.installed(), AutostartMode::Daemon);
+        assert!(!config.join("autostart").join(DESKTOP_ENTRY_NAME).exists());
+        let unit = fs::read_to_string(config.join("systemd/user").join(UNIT_NAME)).unwrap();
+        assert!(unit.contains("ExecStart=\"/opt/cattysend/bin/cattysend-daemon\" --receive"));
+        assert!(unit.contains("WantedBy=default.target"));
+
+        autostart.apply(AutostartMode::Off, bin).unwrap();
+        assert_eq!(autostart.installed(), AutostartMode::Off);
+        let wants = config
+            .join("systemd/user/default.target.wants")
+            .join(UNIT_NAME);
+        assert!(wants.symlink_metadata().is_err());
+
+        let _ = fs::remove_dir_all(config);
+    }
+
+    #[test]
+    fn test_user_files_are_kept() {
+        let config = temp_config();
+        let autostart = Autostart::with_config_dir(&config);
+        let entry = config.join("autostart").join(DESKTOP_ENTRY_NAME);
+        fs::create_dir_all(entry.parent().unwrap()).unwrap();
+        fs::write(&entry, "[Desktop Entry]\nExec=my-own\n").unwrap();
+
+        assert!(
+            autostart
+                .apply(AutostartMode::Gui, Path::new("/usr/bin"))
+                .is_err()
+        );
+        autostart
+            .apply(AutostartMode::Off, Path::new("/usr/bin"))
+            .unwrap();
+        assert!(entry.exists());
+
+        let _ = fs::remove_dir_all(config);
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(systemd_quote(Path::new("/a b/100%")), "\"/a b/100%%\"");
+        assert_eq!(
+            desktop_quote(Path::new("/a$b/\"c\"")),
+            "\"/a\\$b/\\\"c\\\"\""
+        );
+    }
+
+    #[test]
+    fn test_is_trusted() {
+        let home = vec!["home".to_string()];
+        assert!(is_trusted(&[], &[]));
+        assert!(is_trusted(&["cafe".to_string()], &[]));
+        assert!(is_trusted(&["home".to_string()], &home));
+        assert!(!is_trusted(&["cafe".to_string()], &home));
+        assert!(!is_trusted(&[], &home));
+    }
+}
@@ -0,0 +1,122 @@
+//! Legacy BLE 广播参数
+//!
+//! 控制广播间隔和占空比（duty cycle），由 [`PowerProfile`] 统一决定默认值。
+//!
+//! # 占空比模式
+//!
+//! 开启 `duty_cycle` 后，广播会按 "开 N 秒 / 关 M 秒" 循环：
+//! 关闭期间不占用射频，发送端扫描时可能需要等待下一个开启周期才能发现本机。
+
+use crate::config::PowerProfile;
+use std::time::Duration;
+
+/// 广播占空比
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DutyCycle {
+    /// 每个周期内广播的时长
+    pub on: Duration,
+    /// 每个周期内停止广播的时长
+    pub off: Duration,
+}
+
+impl DutyCycle {
+    /// 广播时间占比 (0.0 ~ 1.0)
+    pub fn ratio(&self) -> f64 {
+        let total = self.on + self.off;
+        if total.is_zero() {
+            return 1.0;
+        }
+        self.on.as_secs_f64() / total.as_secs_f64()
+    }
+}
+
+/// Legacy 广播配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyAdvConfig {
+    /// 最小广播间隔 (None 表示由 BlueZ 决定)
+    pub min_interval: Option<Duration>,
+    /// 最大广播间隔 (None 表示由 BlueZ 决定)
+    pub max_interval: Option<Duration>,
+    /// 占空比 (None 表示持续广播)
+    pub duty_cycle: Option<DutyCycle>,
+}
+
+impl Default for LegacyAdvConfig {
+    fn default() -> Self {
+        Self::from_profile(PowerProfile::default())
+    }
+}
+
+impl LegacyAdvConfig {
+    /// 根据电源策略生成广播参数
+    ///
+    /// - `Performance`: 100~150ms 间隔，持续广播（发现最快）
+    /// - `Balanced`: 200~300ms 间隔，开 10s / 关 10s
+    /// - `Battery`: 500~1000ms 间隔，开 5s / 关 25s
+    pub fn from_profile(profile: PowerProfile) -> Self {
+        match profile {
+            PowerProfile::Performance => Self {
+                min_interval: Some(Duration::from_millis(100)),
+                max_interval: Some(Duration::from_millis(150)),
+                duty_cycle: None,
+            },
+            PowerProfile::Balanced => Self {
+                min_interval: Some(Duration::from_millis(200)),
+                max_interval: Some(Duration::from_millis(300)),
+                duty_cycle: Some(DutyCycle {
+                    on: Duration::from_secs(10),
+                    off: Duration::from_secs(10),
+                }),
+            },
+            PowerProfile::Battery => Self {
+                min_interval: Some(Duration::from_millis(500)),
+                max_interval: Some(Duration::from_millis(1000)),
+                duty_cycle: Some(DutyCycle {
+                    on: Duration::from_secs(5),
+                    off: Duration::from_secs(25),
+                }),
+            },
+        }
+    }
+
+    /// 设置广播间隔
+    pub fn with_interval(mut self, min: Duration, max: Duration) -> Self {
+        self.min_interval = Some(min);
+        self.max_interval = Some(max.max(min));
+        self
+    }
+
+    /// 设置占空比 (`None` 表示持续广播)
+    pub fn with_duty_cycle(mut self, duty_cycle: Option<DutyCycle>) -> Self {
+        self.duty_cycle = duty_cycle;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_performance_is_continuous() {
+        let config = LegacyAdvConfig::from_profile(PowerProfile::Performance);
+        assert!(config.duty_cycle.is_none());
+        assert_eq!(config.min_interval, Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_battery_duty_cycle() {
+        let config = LegacyAdvConfig::from_profile(PowerProfile::Battery);
+        let duty = config.duty_cycle.unwrap();
+        assert_eq!(duty.on, Duration::from_secs(5));
+        assert_eq!(duty.off, Duration::from_secs(25));
+        assert!((duty.ratio() - 1.0 / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_with_interval_keeps_max_not_below_min() {
+        let config = LegacyAdvConfig::default()
+            .with_interval(Duration::from_millis(300), Duration::from_millis(200));
+        assert_eq!(config.max_interval, Some(Duration::from_millis(300)));
+    }
+}
@@ -0,0 +1,188 @@
+//! BlueZ 后端 (bluer)
+
+use super::{GattClientBackend, GattConnection};
+use crate::ble::MAIN_SERVICE_UUID;
+use crate::ble::client::BleClientError;
+use crate::ble::pairing::PairingAgent;
+use async_trait::async_trait;
+use bluer::gatt::remote::Characteristic;
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// 服务发现最长等待时间
+const SERVICES_RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl From<bluer::Error> for BleClientError {
+    fn from(e: bluer::Error) -> Self {
+        BleClientError::Backend(e.to_string())
+    }
+}
+
+/// 基于 bluer 的 GATT 客户端
+pub struct BluezBackend {
+    adapter: bluer::Adapter,
+    _agent: Option<bluer::agent::AgentHandle>,
+    _session: bluer::Session,
+}
+
+impl BluezBackend {
+    /// 只处理 Just-Works 配对
+    pub async fn new() -> Result<Self, BleClientError> {
+        Self::with_agent(PairingAgent::default()).await
+    }
+
+    /// 在后端的会话上注册配对代理（非默认 Agent，只处理本进程发起的配对）
+    ///
+    /// 注册失败（例如已有同路径的 Agent）时只记录警告，不需要配对的设备仍可使用。
+    pub async fn with_agent(agent: PairingAgent) -> Result<Self, BleClientError> {
+        let session = bluer::Session::new().await?;
+        let adapter = session
+            .default_adapter()
+            .await
+            .map_err(|_| BleClientError::NoAdapter)?;
+        adapter.set_powered(true).await?;
+        let agent = match agent.register(&session).await {
+            Ok(handle) => Some(handle),
+            Err(e) => {
+                warn!("Failed to register pairing agent: {}", e);
+                None
+            }
+        };
+        Ok(Self {
+            adapter,
+            _agent: agent,
+            _session: session,
+        })
+    }
+}
+
+#[async_trait]
+impl GattClientBackend for BluezBackend {
+    fn name(&self) -> &'static str {
+        "bluez"
+    }
+
+    async fn connect(&self, address: &str) -> Result<Box<dyn GattConnection>, BleClientError> {
+        let addr: bluer::Address = address
+            .parse()
+            .map_err(|_| BleClientError::DeviceNotFound)?;
+        let device = self
+            .adapter
+            .device(addr)
+            .map_err(|_| BleClientError::DeviceNotFound)?;
+
+        if !device.is_connected().await? {
+            info!("Connecting to BLE device: {}", address);
+            device
+                .connect()
+                .await
+                .map_err(|e| BleClientError::ConnectionFailed(e.to_string()))?;
+        }
+
+        // 等待 BlueZ 完成服务发现
+        let deadline = tokio::time::Instant::now() + SERVICES_RESOLVE_TIMEOUT;
+        while !device.is_services_resolved().await? {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(BleClientError::ServiceNotFound(MAIN_SERVICE_UUID));
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        debug!("Discovering GATT services...");
+        let mut characteristics = HashMap::new();
+        for service in device.services().await? {
+            if service.uuid().await? != MAIN_SERVICE_UUID {
+                continue;
+            }
+            for characteristic in service.characteristics().await? {
+                characteristics.insert(characteristic.uuid().await?, characteristic);
+            }
+        }
+        if characteristics.is_empty() {
+            return Err(BleClientError::ServiceNotFound(MAIN_SERVICE_UUID));
+        }
+
+        Ok(Box::new(BluezConnection {
+            device,
+            characteristics,
+        }))
+    }
+}
+
+struct BluezConnection {
+    device: bluer::Device,
+    characteristics: HashMap<Uuid, Characteristic>,
+}
+
+impl BluezConnection {
+    fn characteristic(&self, uuid: Uuid) -> Result<&Characteristic, BleClientError> {
+        self.characteristics
+            .get(&uuid)
+            .ok_or(BleClientError::CharacteristicNotFound(uuid))
+    }
+
+    /// 读写因未配对被拒绝时先配对，返回是否需要重试
+    ///
+    /// 已绑定过的设备（或要求加密的特征）会以 `NotAuthorized` / `NotPermitted`
+    /// 拒绝读写；配对由注册的 [`PairingAgent`] 应答。
+    async fn pair_on_denied(&self, error: &bluer::Error) -> Result<bool, BleClientError> {
+        if !matches!(
+            error.kind,
+            bluer::ErrorKind::NotAuthorized | bluer::ErrorKind::NotPermitted
+        ) || self.device.is_paired().await?
+        {
+            return Ok(false);
+        }
+        info!("{} requires pairing, pairing...", self.device.address());
+        self.device
+            .pair()
+            .await
+            .map_err(|e| BleClientError::ConnectionFailed(format!("pairing failed: {}", e)))?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl GattConnection for BluezConnection {
+    async fn read(&self, characteristic: Uuid) -> Result<Vec<u8>, BleClientError> {
+        let characteristic = self.characteristic(characteristic)?;
+        match characteristic.read().await {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                if !self.pair_on_denied(&e).await? {
+                    return Err(e.into());
+                }
+                Ok(characteristic.read().await?)
+            }
+        }
+    }
+
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> Result<(), BleClientError> {
+        let characteristic = self.characteristic(characteristic)?;
+        match characteristic.write(data).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if !self.pair_on_denied(&e).await? {
+                    return Err(e.into());
+                }
+                Ok(characteristic.write(data).await?)
+            }
+        }
+    }
+
+    async fn subscribe(
+        &self,
+        characteristic: Uuid,
+    ) -> Result<BoxStream<'static, Vec<u8>>, BleClientError> {
+        let notifications = self.characteristic(characteristic)?.notify().await?;
+        Ok(notifications.boxed())
+    }
+
+    async fn disconnect(&self) -> Result<(), BleClientError> {
+        Ok(self.device.disconnect().await?)
+    }
+}
@@ -0,0 +1,136 @@
+//! btleplug 后端
+//!
+//! 跨平台 GATT 客户端（Linux/macOS/Windows），需启用 `btleplug` feature。
+
+use super::{GattClientBackend, GattConnection};
+use crate::ble::MAIN_SERVICE_UUID;
+use crate::ble::client::BleClientError;
+use async_trait::async_trait;
+use btleplug::api::{Central, Characteristic, Manager as _, Peripheral, WriteType};
+use btleplug::platform::{Adapter, Manager, Peripheral as PlatformPeripheral};
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
+use log::{debug, info};
+use std::time::Duration;
+use uuid::Uuid;
+
+impl From<btleplug::Error> for BleClientError {
+    fn from(e: btleplug::Error) -> Self {
+        BleClientError::Backend(e.to_string())
+    }
+}
+
+/// 基于 btleplug 的 GATT 客户端
+pub struct BtleplugBackend {
+    adapter: Adapter,
+}
+
+impl BtleplugBackend {
+    pub async fn new() -> Result<Self, BleClientError> {
+        let manager = Manager::new().await?;
+        let adapters = manager.adapters().await?;
+        let adapter = adapters
+            .into_iter()
+            .next()
+            .ok_or(BleClientError::NoAdapter)?;
+
+        Ok(Self { adapter })
+    }
+
+    async fn find_device(&self, address: &str) -> Result<PlatformPeripheral, BleClientError> {
+        let peripherals = self.adapter.peripherals().await?;
+
+        for peripheral in peripherals {
+            if let Some(props) = peripheral.properties().await?
+                && props.address.to_string().to_uppercase() == address.to_uppercase()
+            {
+                return Ok(peripheral);
+            }
+        }
+
+        Err(BleClientError::DeviceNotFound)
+    }
+}
+
+#[async_trait]
+impl GattClientBackend for BtleplugBackend {
+    fn name(&self) -> &'static str {
+        "btleplug"
+    }
+
+    async fn connect(&self, address: &str) -> Result<Box<dyn GattConnection>, BleClientError> {
+        // 查找目标设备
+        let peripheral = self.find_device(address).await?;
+
+        // 连接
+        info!("Connecting to BLE device: {}", address);
+        peripheral
+            .connect()
+            .await
+            .map_err(|e| BleClientError::ConnectionFailed(e.to_string()))?;
+
+        // 等待连接稳定
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        // 请求更大的 MTU
+        // Note: btleplug 不直接支持 MTU 请求，跳过
+
+        // 发现服务
+        debug!("Discovering GATT services...");
+        peripheral.discover_services().await?;
+
+        Ok(Box::new(BtleplugConnection { peripheral }))
+    }
+}
+
+struct BtleplugConnection {
+    peripheral: PlatformPeripheral,
+}
+
+impl BtleplugConnection {
+    fn find_characteristic(&self, uuid: Uuid) -> Result<Characteristic, BleClientError> {
+        for service in self.peripheral.services() {
+            if service.uuid == MAIN_SERVICE_UUID {
+                for char in service.characteristics {
+                    if char.uuid == uuid {
+                        return Ok(char);
+                    }
+                }
+            }
+        }
+        Err(BleClientError::CharacteristicNotFound(uuid))
+    }
+}
+
+#[async_trait]
+impl GattConnection for BtleplugConnection {
+    async fn read(&self, characteristic: Uuid) -> Result<Vec<u8>, BleClientError> {
+        let char = self.find_characteristic(characteristic)?;
+        Ok(self.peripheral.read(&char).await?)
+    }
+
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> Result<(), BleClientError> {
+        let char = self.find_characteristic(characteristic)?;
+        Ok(self
+            .peripheral
+            .write(&char, data, WriteType::WithResponse)
+            .await?)
+    }
+
+    async fn subscribe(
+        &self,
+        characteristic: Uuid,
+    ) -> Result<BoxStream<'static, Vec<u8>>, BleClientError> {
+        let char = self.find_characteristic(characteristic)?;
+        // 先取通知流再订阅，避免漏掉订阅后立即到达的通知
+        let notifications = self.peripheral.notifications().await?;
+        self.peripheral.subscribe(&char).await?;
+        Ok(notifications
+            .filter_map(move |n| async move { (n.uuid == characteristic).then_some(n.value) })
+            .boxed())
+    }
+
+    async fn disconnect(&self) -> Result<(), BleClientError> {
+        Ok(self.peripheral.disconnect().await?)
+    }
+}
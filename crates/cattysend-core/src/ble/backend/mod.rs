@@ -0,0 +1,88 @@
+//! BLE 客户端后端抽象
+//!
+//! 发送端只需要很少的 GATT 客户端操作：连接、读特征、写特征、订阅通知、断开。
+//! 这里把它们抽象为 trait，使 `BleClient` 不依赖具体的蓝牙栈。
+//!
+//! # 后端
+//!
+//! - `bluez`: 基于 bluer (BlueZ D-Bus)，Linux 默认后端
+//! - `btleplug`: 基于 btleplug，启用 `btleplug` feature 后可用，
+//!   为 macOS/Windows 发送端做准备
+
+pub mod bluez;
+#[cfg(feature = "btleplug")]
+pub mod btleplug;
+
+use crate::ble::client::BleClientError;
+use async_trait::async_trait;
+use futures_util::stream::BoxStream;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg(feature = "btleplug")]
+pub use self::btleplug::BtleplugBackend;
+pub use bluez::BluezBackend;
+
+/// GATT 客户端后端
+#[async_trait]
+pub trait GattClientBackend: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 连接到设备并完成服务发现
+    async fn connect(&self, address: &str) -> Result<Box<dyn GattConnection>, BleClientError>;
+}
+
+/// 共享后端（例如同一个后端需要被多次重试使用）
+#[async_trait]
+impl GattClientBackend for Arc<dyn GattClientBackend> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    async fn connect(&self, address: &str) -> Result<Box<dyn GattConnection>, BleClientError> {
+        (**self).connect(address).await
+    }
+}
+
+/// 已建立的 GATT 连接
+///
+/// 特征均在 `MAIN_SERVICE_UUID` 服务下查找。
+#[async_trait]
+pub trait GattConnection: Send + Sync {
+    /// 读取特征值
+    async fn read(&self, characteristic: Uuid) -> Result<Vec<u8>, BleClientError>;
+
+    /// 写入特征值（带响应）
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> Result<(), BleClientError>;
+
+    /// 订阅特征通知，返回之后收到的通知值
+    ///
+    /// 只有反向模式（接收端建组）需要，默认不支持
+    async fn subscribe(
+        &self,
+        characteristic: Uuid,
+    ) -> Result<BoxStream<'static, Vec<u8>>, BleClientError> {
+        let _ = characteristic;
+        Err(BleClientError::Backend(
+            "GATT notifications are not supported by this backend".to_string(),
+        ))
+    }
+
+    /// 断开连接
+    async fn disconnect(&self) -> Result<(), BleClientError>;
+}
+
+/// 当前平台的默认后端
+///
+/// Linux 上始终使用 BlueZ；其他平台在启用 `btleplug` feature 时使用 btleplug。
+pub async fn default_backend() -> Result<Box<dyn GattClientBackend>, BleClientError> {
+    #[cfg(all(feature = "btleplug", not(target_os = "linux")))]
+    {
+        Ok(Box::new(BtleplugBackend::new().await?))
+    }
+    #[cfg(not(all(feature = "btleplug", not(target_os = "linux"))))]
+    {
+        Ok(Box::new(BluezBackend::new().await?))
+    }
+}
@@ -0,0 +1,202 @@
+//! 厂商预设
+//!
+//! 广播端（[`crate::ble::server`]）和扫描端（[`crate::ble::scanner`]）共用的厂商目录。
+//! 身份 Service Data 的 UUID 为 `0000XXYY-0000-1000-8000-00805f9b34fb`，
+//! XX 为能力字节，YY 为厂商 ID；两端都通过 [`ident_uuid`] / [`parse_ident_uuid`]
+//! 编解码，保证本机广播的厂商能被扫描端原样解析回来。
+//!
+//! Android 端 CatShare 按 [`Brand`] 中的区间识别厂商，不在区间内的 ID
+//! （如 `Linux`、`Windows`）在手机上显示为未知设备，见 [`BrandPreset::recognized_by_android`]。
+
+use crate::ble::scanner::Brand;
+use crate::ble::{CAPABILITY_5GHZ, CAPABILITY_BUSY, CAPABILITY_WPA3};
+use crate::config::BrandId;
+use uuid::Uuid;
+
+/// 蓝牙 SIG 基底 UUID 的低 96 位
+const BASE_UUID_LOW: u128 = 0x0000_1000_8000_0080_5f9b_34fb;
+
+/// 厂商预设
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BrandPreset {
+    /// 厂商
+    pub brand: BrandId,
+    /// 广播中使用的厂商 ID
+    pub id: u8,
+    /// 该厂商真机广播的能力位（[`CAPABILITY_5GHZ`] 等）
+    pub capabilities: u8,
+}
+
+/// 厂商目录，顺序与 [`BrandId::all`] 一致
+const PRESETS: &[BrandPreset] = &[
+    phone(BrandId::Xiaomi),
+    phone(BrandId::BlackShark),
+    phone(BrandId::Vivo),
+    phone(BrandId::Oppo),
+    phone(BrandId::Realme),
+    phone(BrandId::OnePlus),
+    phone(BrandId::Honor),
+    phone(BrandId::Meizu),
+    phone(BrandId::Samsung),
+    phone(BrandId::Lenovo),
+    phone(BrandId::Motorola),
+    phone(BrandId::ZTE),
+    phone(BrandId::Nubia),
+    phone(BrandId::Smartisan),
+    phone(BrandId::Asus),
+    phone(BrandId::ROG),
+    phone(BrandId::Hisense),
+    phone(BrandId::NIO),
+    desktop(BrandId::Windows),
+    desktop(BrandId::Linux),
+    desktop(BrandId::Unknown),
+];
+
+/// 手机厂商默认支持 5GHz
+const fn phone(brand: BrandId) -> BrandPreset {
+    BrandPreset {
+        brand,
+        id: brand as u8,
+        capabilities: CAPABILITY_5GHZ,
+    }
+}
+
+/// 电脑端不保证有 5GHz 网卡
+const fn desktop(brand: BrandId) -> BrandPreset {
+    BrandPreset {
+        brand,
+        id: brand as u8,
+        capabilities: 0,
+    }
+}
+
+impl BrandPreset {
+    /// 全部预设
+    pub fn all() -> &'static [BrandPreset] {
+        PRESETS
+    }
+
+    /// 指定厂商的预设
+    pub fn for_brand(brand: BrandId) -> &'static BrandPreset {
+        PRESETS
+            .iter()
+            .find(|p| p.brand == brand)
+            .expect("every BrandId has a preset")
+    }
+
+    /// 按广播中的厂商 ID 查找（区间内的 ID 归到对应厂商）
+    pub fn from_id(id: u8) -> &'static BrandPreset {
+        Self::for_brand(BrandId::from_id(id))
+    }
+
+    /// 厂商名称
+    pub fn name(&self) -> &'static str {
+        self.brand.name()
+    }
+
+    /// Android 端 CatShare 能否识别该厂商 ID
+    pub fn recognized_by_android(&self) -> bool {
+        !matches!(Brand::from(self.id as i16), Brand::Unknown(_))
+    }
+
+    /// 该预设广播的身份 UUID（`supports_5ghz` 覆盖预设中的 5GHz 位）
+    pub fn ident_uuid(&self, supports_5ghz: bool) -> Uuid {
+        let capabilities = if supports_5ghz {
+            self.capabilities | CAPABILITY_5GHZ
+        } else {
+            self.capabilities & !CAPABILITY_5GHZ
+        };
+        ident_uuid(capabilities, self.id)
+    }
+}
+
+/// 构造身份 Service Data 的 UUID: `0000XXYY-0000-1000-8000-00805f9b34fb`
+pub fn ident_uuid(capabilities: u8, brand_id: u8) -> Uuid {
+    let high = ((capabilities as u128) << 8) | brand_id as u128;
+    Uuid::from_u128((high << 96) | BASE_UUID_LOW)
+}
+
+/// 从身份 UUID 解析 (能力字节, 厂商 ID)，前两个字节不为 0 时返回 None
+///
+/// 与 CatShare 一样不检查基底部分，调用方需先确认这是 6 字节的身份 Service Data。
+pub fn parse_ident_uuid(uuid: &Uuid) -> Option<(u8, u8)> {
+    let bytes = uuid.as_bytes();
+    (bytes[0..2] == [0, 0]).then_some((bytes[2], bytes[3]))
+}
+
+/// 能力字节中的 (支持 5GHz, 忙碌, 支持 WPA3) 标志
+pub fn capability_flags(capabilities: u8) -> (bool, bool, bool) {
+    (
+        capabilities & CAPABILITY_5GHZ != 0,
+        capabilities & CAPABILITY_BUSY != 0,
+        capabilities & CAPABILITY_WPA3 != 0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ble::scanner::get_vendor_name;
+
+    #[test]
+    fn test_every_brand_has_preset() {
+        assert_eq!(BrandPreset::all().len(), BrandId::all().len());
+        for brand in BrandId::all() {
+            let preset = BrandPreset::for_brand(*brand);
+            assert_eq!(preset.id, brand.id());
+            assert_eq!(preset.name(), brand.name());
+        }
+    }
+
+    /// 广播任一预设后，扫描端解析出的厂商和能力位与广播一致
+    #[test]
+    fn test_advertised_brand_round_trips_through_scanner() {
+        for preset in BrandPreset::all() {
+            for supports_5ghz in [true, false] {
+                let uuid = preset.ident_uuid(supports_5ghz);
+                let (capabilities, id) = parse_ident_uuid(&uuid).unwrap();
+                assert_eq!(BrandPreset::from_id(id), preset, "{:?}", preset.brand);
+                assert_eq!(
+                    capability_flags(capabilities),
+                    (supports_5ghz, false, false)
+                );
+            }
+        }
+        assert_eq!(
+            capability_flags(CAPABILITY_5GHZ | CAPABILITY_WPA3),
+            (true, false, true)
+        );
+    }
+
+    /// 手机厂商的 ID 必须落在 CatShare 识别的区间内，扫描端显示的名称也不是 Unknown
+    #[test]
+    fn test_phone_presets_recognized_by_android() {
+        for preset in BrandPreset::all() {
+            let desktop = matches!(
+                preset.brand,
+                BrandId::Windows | BrandId::Linux | BrandId::Unknown
+            );
+            assert_eq!(
+                preset.recognized_by_android(),
+                !desktop,
+                "{:?}",
+                preset.brand
+            );
+            if preset.brand != BrandId::Unknown {
+                assert!(!get_vendor_name(preset.id as i16).starts_with("Unknown"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_ident_uuid_rejects_other_uuids() {
+        assert_eq!(
+            parse_ident_uuid(&ident_uuid(CAPABILITY_BUSY, 200)),
+            Some((CAPABILITY_BUSY, 200))
+        );
+        assert_eq!(
+            parse_ident_uuid(&Uuid::from_u128(0x1234_011e_0000_1000_8000_0080_5f9b_34fb)),
+            None
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! BLE 广播原始数据记录
+//!
+//! 调试用：扫描时把广播的原始内容（服务 UUID、服务数据、厂商数据的十六进制）
+//! 逐条追加到文件，供用户报告与未知厂商的兼容问题。只记录识别为 CatShare 的设备
+//! 和"差一点"的设备（见 [`is_near_miss`]），不记录耳机、手环等无关广播。
+//!
+//! 文件每行一条 JSON（[`AdvRecord`]），可以直接附在 issue 里：
+//!
+//! ```text
+//! {"time_ms":1760000000000,"address":"AA:BB:CC:DD:EE:01","verdict":"near_miss",...}
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// 蓝牙 SIG 基础 UUID 的后 12 字节
+const BASE_UUID_SUFFIX: [u8; 12] = [
+    0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0x80, 0x5f, 0x9b, 0x34, 0xfb,
+];
+
+/// 扫描器能解析的服务数据长度（27 字节设备信息、6 字节能力位）
+const KNOWN_SERVICE_DATA_LENGTHS: [usize; 2] = [27, 6];
+
+/// 记录的判定结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureVerdict {
+    /// 识别为 CatShare 设备
+    Matched,
+    /// 未识别，但广播内容与 CatShare 相似
+    NearMiss,
+}
+
+/// 一条广播记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdvRecord {
+    /// 记录时间（Unix 毫秒）
+    pub time_ms: u64,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rssi: Option<i16>,
+    pub verdict: CaptureVerdict,
+    /// 广播的服务 UUID
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub uuids: Vec<String>,
+    /// 服务数据：UUID -> 十六进制
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub service_data: BTreeMap<String, String>,
+    /// 厂商数据：厂商 ID（`0x038f` 形式）-> 十六进制
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub manufacturer_data: BTreeMap<String, String>,
+}
+
+impl AdvRecord {
+    /// 由广播内容创建记录，时间为当前时间
+    pub fn new(
+        address: String,
+        name: Option<String>,
+        rssi: Option<i16>,
+        verdict: CaptureVerdict,
+        uuids: &HashSet<Uuid>,
+        service_data: &HashMap<Uuid, Vec<u8>>,
+        manufacturer_data: &HashMap<u16, Vec<u8>>,
+    ) -> Self {
+        let mut uuids: Vec<String> = uuids.iter().map(Uuid::to_string).collect();
+        uuids.sort_unstable();
+        Self {
+            time_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            address,
+            name,
+            rssi,
+            verdict,
+            uuids,
+            service_data: service_data
+                .iter()
+                .map(|(uuid, data)| (uuid.to_string(), hex(data)))
+                .collect(),
+            manufacturer_data: manufacturer_data
+                .iter()
+                .map(|(id, data)| (format!("0x{:04x}", id), hex(data)))
+                .collect(),
+        }
+    }
+}
+
+/// 未被识别的广播是否与 CatShare 相似，值得记录
+///
+/// 带有 `0x33xx` 范围的 16-bit 服务 UUID（CatShare 使用 `0x3331`-`0x3334`），
+/// 或者服务数据的长度与 CatShare 的设备信息/能力位相同。
+pub fn is_near_miss(
+    uuids: &HashSet<Uuid>,
+    service_data: &HashMap<Uuid, Vec<u8>>,
+    manufacturer_data: &HashMap<u16, Vec<u8>>,
+) -> bool {
+    uuids.iter().any(is_near_uuid)
+        || service_data.keys().any(is_near_uuid)
+        || service_data.values().any(|data| is_near_length(data))
+        || manufacturer_data.values().any(|data| is_near_length(data))
+}
+
+/// `0x33xx` 范围的 16-bit 服务 UUID
+pub(crate) fn is_near_uuid(uuid: &Uuid) -> bool {
+    short_uuid(uuid).is_some_and(|id| id >> 8 == 0x33)
+}
+
+/// 长度与 CatShare 的设备信息/能力位相同的数据
+pub(crate) fn is_near_length(data: &[u8]) -> bool {
+    KNOWN_SERVICE_DATA_LENGTHS.contains(&data.len())
+}
+
+/// 基于 SIG 基础 UUID 的 16-bit UUID
+pub(crate) fn short_uuid(uuid: &Uuid) -> Option<u16> {
+    let b = uuid.as_bytes();
+    (b[4..] == BASE_UUID_SUFFIX && b[0] == 0 && b[1] == 0).then(|| u16::from_be_bytes([b[2], b[3]]))
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 追加写入的记录文件
+pub struct AdvCapture {
+    writer: BufWriter<File>,
+    records: usize,
+}
+
+impl AdvCapture {
+    /// 打开（或创建）`path`，之后的记录追加到文件末尾
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            records: 0,
+        })
+    }
+
+    /// 写入一条记录
+    pub fn record(&mut self, record: &AdvRecord) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// 已写入的记录数
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_capture_near_miss_and_record() {
+        let none = HashMap::new();
+        let uuid16 =
+            |id: u16| Uuid::from_u128(((id as u128) << 96) | 0x0000_1000_8000_00805f9b34fb);
+
+        // 耳机一类的无关广播不记录
+        let headset = HashSet::from([uuid16(0x110b)]);
+        assert!(!is_near_miss(&headset, &none, &HashMap::new()));
+
+        // 相邻的 0x33xx 服务 UUID
+        let nearby = HashSet::from([uuid16(0x3338)]);
+        assert!(is_near_miss(&nearby, &none, &HashMap::new()));
+
+        // 未知厂商 ID 下与设备信息同样长度的厂商数据
+        let manufacturer = HashMap::from([(0x1234, vec![0; 27])]);
+        assert!(is_near_miss(&HashSet::new(), &none, &manufacturer));
+
+        let service_data = HashMap::from([(uuid16(0x3331), vec![0xab, 0x01])]);
+        let record = AdvRecord::new(
+            "AA:BB:CC:DD:EE:01".to_string(),
+            None,
+            Some(-60),
+            CaptureVerdict::Matched,
+            &nearby,
+            &service_data,
+            &manufacturer,
+        );
+        assert_eq!(
+            record.service_data["00003331-0000-1000-8000-00805f9b34fb"],
+            "ab01"
+        );
+        assert!(record.manufacturer_data.contains_key("0x1234"));
+
+        let dir = temp_dir("capture");
+        let path = dir.join("capture.jsonl");
+        let mut capture = AdvCapture::open(&path).unwrap();
+        capture.record(&record).unwrap();
+        capture.record(&record).unwrap();
+        capture.flush().unwrap();
+        assert_eq!(capture.records(), 2);
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let parsed: AdvRecord = serde_json::from_str(written.lines().next().unwrap()).unwrap();
+        assert_eq!(parsed, record);
+        assert!(written.contains("\"verdict\":\"matched\""));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
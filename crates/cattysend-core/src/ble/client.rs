@@ -9,21 +9,41 @@
 //! 3. 派生会话密钥 (ECDH)
 //! 4. 加密 P2pInfo 并写入 CHAR_P2P
 //!
+//! 反向模式（[`BleClient::connect_and_join_with_progress`]）第 4 步写入的是建组请求，
+//! 接收端建组后通过 CHAR_P2P 的通知发回自己的 P2pInfo。
+//!
+//! 第 1、2 步不依赖 P2pInfo，发送端可以一边创建热点一边用
+//! [`BleClient::begin_handshake`] 完成，热点建好后再用 [`BleClient::finish_handshake`] 写入。
+//!
 //! # 安全性
 //!
 //! - 使用 ECDH P-256 密钥协商
 //! - P2pInfo 中的敏感字段 (SSID, PSK, MAC) 使用 AES-256-CTR 加密
 //! - 每次连接使用新的临时密钥对
+//!
+//! # 取消与超时
+//!
+//! 连接和每一步特征读写都受 [`BleClient::with_cancellation`] 的令牌约束，
+//! 单步最长 [`HANDSHAKE_TIMEOUT`]；取消或超时后仍会断开已建立的连接。
+//!
+//! # 后端
+//!
+//! 具体的 GATT 操作由 [`GattClientBackend`] 完成，Linux 默认使用 BlueZ，
+//! 见 [`crate::ble::backend`]。
 
-use crate::ble::{DeviceInfo, MAIN_SERVICE_UUID, P2P_CHAR_UUID, STATUS_CHAR_UUID};
-use crate::crypto::{BleSecurity, BleSecurityPersistent};
+use crate::ble::backend::{self, BluezBackend, GattClientBackend, GattConnection};
+use crate::ble::gatt::GattHandler;
+use crate::ble::pairing::{PairingAgent, PairingRequest};
+use crate::ble::{DeviceInfo, P2P_CHAR_UUID, ReceiverInfo, STATUS_CHAR_UUID};
+use crate::cancel::{self, CancellationToken, HANDSHAKE_TIMEOUT, Interrupted};
+use crate::crypto::{BleSecurity, BleSecurityPersistent, SessionCipher};
 use crate::wifi::P2pInfo;
-use btleplug::api::{Central, Characteristic, Manager as _, Peripheral, WriteType};
-use btleplug::platform::{Adapter, Manager, Peripheral as PlatformPeripheral};
-use log::{debug, info, trace};
+use futures_util::StreamExt;
+use log::{debug, info, trace, warn};
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time;
+use tokio::sync::mpsc;
 use uuid::Uuid;
 
 /// BLE 客户端错误
@@ -44,31 +64,84 @@ pub enum BleClientError {
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
 
-    #[error("IO error: {0}")]
-    IoError(#[from] btleplug::Error),
+    #[error("Backend error: {0}")]
+    Backend(String),
 
     #[error("Protocol error: {0}")]
     ProtocolError(String),
+
+    #[error("Device is busy receiving from another sender")]
+    DeviceBusy,
+
+    #[error("Receiver rejected the P2P info")]
+    P2pRejected,
+
+    #[error("Receiver did not send its group info in time")]
+    GroupTimeout,
+
+    #[error("{0}")]
+    Interrupted(#[from] Interrupted),
+}
+
+/// 握手进行到的步骤（连接建立之后）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeStep {
+    /// 读取接收端的 DeviceInfo
+    ReadingStatus,
+    /// 写入 P2P 信息
+    WritingP2p,
+}
+
+/// 已连接并读取了 DeviceInfo、等待写入 P2P 信息的握手
+///
+/// 用 [`BleClient::finish_handshake`] 完成或 [`BleClient::abort_handshake`] 放弃，两者都会断开连接。
+pub struct PendingHandshake {
+    connection: Box<dyn GattConnection>,
+    address: String,
+    device_info: DeviceInfo,
+}
+
+impl PendingHandshake {
+    /// 接收端的 DeviceInfo
+    pub fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
 }
 
 pub struct BleClient {
-    adapter: Adapter,
+    backend: Box<dyn GattClientBackend>,
     security: Option<Arc<BleSecurityPersistent>>,
+    cancel: CancellationToken,
+    timeout: Duration,
+    pairing: Option<mpsc::Receiver<PairingRequest>>,
 }
 
 impl BleClient {
+    /// 使用当前平台的默认后端创建客户端
     pub async fn new() -> Result<Self, BleClientError> {
-        let manager = Manager::new().await?;
-        let adapters = manager.adapters().await?;
-        let adapter = adapters
-            .into_iter()
-            .next()
-            .ok_or(BleClientError::NoAdapter)?;
-
-        Ok(Self {
-            adapter,
+        Ok(Self::with_backend(backend::default_backend().await?))
+    }
+
+    /// 使用 BlueZ 后端创建客户端，需要用户确认的配对提示通过
+    /// [`BleClient::take_pairing_prompts`] 取出
+    pub async fn with_pairing() -> Result<Self, BleClientError> {
+        let (agent, prompts) = PairingAgent::with_prompts();
+        let backend = BluezBackend::with_agent(agent).await?;
+        let mut client = Self::with_backend(Box::new(backend));
+        client.pairing = Some(prompts);
+        Ok(client)
+    }
+
+    /// 使用指定后端创建客户端
+    pub fn with_backend(backend: Box<dyn GattClientBackend>) -> Self {
+        debug!("Using BLE client backend: {}", backend.name());
+        Self {
+            backend,
             security: None,
-        })
+            cancel: CancellationToken::new(),
+            timeout: HANDSHAKE_TIMEOUT,
+            pairing: None,
+        }
     }
 
     /// 设置持久化安全上下文，使发送端身份保持稳定
@@ -77,8 +150,66 @@ impl BleClient {
         self
     }
 
+    /// `token` 取消时中断正在进行的连接和握手
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// 设置连接和单步读写的超时（默认 [`HANDSHAKE_TIMEOUT`]）
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 获取配对提示通道（见 [`crate::ble::pairing::answer_while`]）
+    pub fn take_pairing_prompts(&mut self) -> Option<mpsc::Receiver<PairingRequest>> {
+        self.pairing.take()
+    }
+
+    /// 在取消令牌和 `timeout` 的约束下等待一步 BLE 操作
+    async fn guard<T>(
+        &self,
+        timeout: Duration,
+        step: impl Future<Output = Result<T, BleClientError>>,
+    ) -> Result<T, BleClientError> {
+        cancel::run(&self.cancel, Some(timeout), step).await?
+    }
+
+    async fn connect(
+        &self,
+        device_address: &str,
+    ) -> Result<Box<dyn GattConnection>, BleClientError> {
+        self.guard(self.timeout, self.backend.connect(device_address))
+            .await
+    }
+
+    async fn disconnect(&self, connection: &dyn GattConnection, device_address: &str) {
+        // 取消后也要断开，这里只受超时约束
+        match tokio::time::timeout(self.timeout, connection.disconnect()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Failed to disconnect from {}: {}", device_address, e),
+            Err(_) => warn!("Timed out disconnecting from {}", device_address),
+        }
+    }
+
+    /// 读取接收端的 DeviceInfo（不写入 P2P 信息）
+    pub async fn read_receiver_info(
+        &self,
+        device_address: &str,
+    ) -> Result<ReceiverInfo, BleClientError> {
+        let connection = self.connect(device_address).await?;
+        let status_data = self
+            .guard(self.timeout, connection.read(STATUS_CHAR_UUID))
+            .await;
+        self.disconnect(connection.as_ref(), device_address).await;
+        let device_info = parse_device_info(&status_data?)?;
+        Ok(ReceiverInfo::from(&device_info))
+    }
+
     /// 连接到设备并执行 P2P 握手
     ///
+    /// 接收端正忙时返回 [`BleClientError::DeviceBusy`]，不会写入 P2P 信息。
     /// 返回接收端的 DeviceInfo
     pub async fn connect_and_handshake(
         &self,
@@ -86,123 +217,307 @@ impl BleClient {
         p2p_info: &P2pInfo,
         sender_id: &str,
     ) -> Result<DeviceInfo, BleClientError> {
-        // 查找目标设备
-        let peripheral = self.find_device(device_address).await?;
+        self.connect_and_handshake_with_progress(device_address, p2p_info, sender_id, |_| {})
+            .await
+    }
+
+    /// 同 [`Self::connect_and_handshake`]，每进入一个步骤调用一次 `on_step`
+    #[tracing::instrument(skip_all, fields(address = %device_address))]
+    pub async fn connect_and_handshake_with_progress(
+        &self,
+        device_address: &str,
+        p2p_info: &P2pInfo,
+        sender_id: &str,
+        on_step: impl Fn(HandshakeStep) + Send + Sync,
+    ) -> Result<DeviceInfo, BleClientError> {
+        // 连接并发现服务
+        let connection = self.connect(device_address).await?;
+        let result = self
+            .handshake(connection.as_ref(), p2p_info, sender_id, on_step)
+            .await;
+
+        // 断开连接（写入已经成功时，断开失败不影响握手结果）
+        self.disconnect(connection.as_ref(), device_address).await;
+        result
+    }
 
-        // 连接
-        info!("Connecting to BLE device: {}", device_address);
-        peripheral.connect().await?;
+    /// 握手的前半段：连接并读取 DeviceInfo，连接保持打开
+    ///
+    /// 接收端正忙时断开并返回 [`BleClientError::DeviceBusy`]。
+    #[tracing::instrument(skip_all, fields(address = %device_address))]
+    pub async fn begin_handshake(
+        &self,
+        device_address: &str,
+        on_step: impl Fn(HandshakeStep) + Send + Sync,
+    ) -> Result<PendingHandshake, BleClientError> {
+        let connection = self.connect(device_address).await?;
+        match self.read_status(connection.as_ref(), &on_step).await {
+            Ok(device_info) => Ok(PendingHandshake {
+                connection,
+                address: device_address.to_string(),
+                device_info,
+            }),
+            Err(e) => {
+                self.disconnect(connection.as_ref(), device_address).await;
+                Err(e)
+            }
+        }
+    }
 
-        // 等待连接稳定
-        time::sleep(Duration::from_millis(500)).await;
+    /// 握手的后半段：写入 P2P 信息并断开，返回接收端的 DeviceInfo
+    pub async fn finish_handshake(
+        &self,
+        pending: PendingHandshake,
+        p2p_info: &P2pInfo,
+        sender_id: &str,
+        on_step: impl Fn(HandshakeStep) + Send + Sync,
+    ) -> Result<DeviceInfo, BleClientError> {
+        let result = self
+            .write_p2p(
+                pending.connection.as_ref(),
+                &pending.device_info,
+                p2p_info,
+                sender_id,
+                &on_step,
+            )
+            .await;
+        self.disconnect(pending.connection.as_ref(), &pending.address)
+            .await;
+        result.map(|()| pending.device_info)
+    }
 
-        // 请求更大的 MTU
-        // Note: btleplug 不直接支持 MTU 请求，跳过
+    /// 放弃握手（例如热点创建失败），断开连接
+    pub async fn abort_handshake(&self, pending: PendingHandshake) {
+        self.disconnect(pending.connection.as_ref(), &pending.address)
+            .await;
+    }
 
-        // 发现服务
-        debug!("Discovering GATT services...");
-        peripheral.discover_services().await?;
+    async fn handshake(
+        &self,
+        connection: &dyn GattConnection,
+        p2p_info: &P2pInfo,
+        sender_id: &str,
+        on_step: impl Fn(HandshakeStep) + Send + Sync,
+    ) -> Result<DeviceInfo, BleClientError> {
+        let device_info = self.read_status(connection, &on_step).await?;
+        self.write_p2p(connection, &device_info, p2p_info, sender_id, &on_step)
+            .await?;
+        Ok(device_info)
+    }
 
-        // 查找并读取 STATUS 特征
-        let status_char = self.find_characteristic(&peripheral, STATUS_CHAR_UUID)?;
-        let status_data = peripheral.read(&status_char).await?;
-        let device_info: DeviceInfo = serde_json::from_slice(&status_data)
-            .map_err(|e| BleClientError::ProtocolError(format!("Invalid DeviceInfo: {}", e)))?;
+    /// 读取 STATUS 特征中的 DeviceInfo，接收端正忙时返回错误
+    async fn read_status(
+        &self,
+        connection: &dyn GattConnection,
+        on_step: impl Fn(HandshakeStep),
+    ) -> Result<DeviceInfo, BleClientError> {
+        // 读取 STATUS 特征
+        on_step(HandshakeStep::ReadingStatus);
+        let status_data = self
+            .guard(self.timeout, connection.read(STATUS_CHAR_UUID))
+            .await?;
+        let device_info = parse_device_info(&status_data)?;
+        let receiver = ReceiverInfo::from(&device_info);
 
         debug!(
-            "Remote DeviceInfo: state={}, mac={}",
-            device_info.state, device_info.mac
+            "Remote DeviceInfo: state={:?}, mac={}, catShare={}",
+            receiver.state, receiver.mac, receiver.protocol_version
         );
         trace!("Full DeviceInfo: {:?}", device_info);
 
-        // 如果对方提供了公钥，派生会话密钥并加密 P2P 信息
-        let p2p_data = if let Some(peer_key) = &device_info.key {
-            let (sender_public_key, cipher) = if let Some(sec) = &self.security {
-                // 使用持久化上下文
-                let pub_key = sec.get_public_key().to_string();
-                let cip = sec.derive_session_key(peer_key).map_err(|e| {
-                    BleClientError::ProtocolError(format!("Key exchange failed: {}", e))
-                })?;
-                (pub_key, cip)
-            } else {
-                // 回退到临时上下文
-                let security = BleSecurity::new().map_err(|e| {
-                    BleClientError::ProtocolError(format!("Failed to init security: {}", e))
-                })?;
-                let pub_key = security.get_public_key().to_string();
-                let cip = security.derive_session_key(peer_key).map_err(|e| {
-                    BleClientError::ProtocolError(format!("Key exchange failed: {}", e))
-                })?;
-                (pub_key, cip)
-            };
-
-            // 加密 P2P 信息
-            let encrypted_p2p = P2pInfo::with_encryption(
-                sender_id.to_string(),
-                cipher
-                    .encrypt(&p2p_info.ssid)
-                    .map_err(|e| BleClientError::ProtocolError(e.to_string()))?,
-                cipher
-                    .encrypt(&p2p_info.psk)
-                    .map_err(|e| BleClientError::ProtocolError(e.to_string()))?,
-                cipher
-                    .encrypt(&p2p_info.mac)
-                    .map_err(|e| BleClientError::ProtocolError(e.to_string()))?,
-                p2p_info.port,
-                sender_public_key,
-            );
-            serde_json::to_vec(&encrypted_p2p)
-                .map_err(|e| BleClientError::ProtocolError(e.to_string()))?
-        } else {
-            // 不加密
-            serde_json::to_vec(p2p_info)
-                .map_err(|e| BleClientError::ProtocolError(e.to_string()))?
-        };
+        if receiver.is_busy() {
+            return Err(BleClientError::DeviceBusy);
+        }
+        Ok(device_info)
+    }
+
+    /// 加密并写入 P2P 信息
+    async fn write_p2p(
+        &self,
+        connection: &dyn GattConnection,
+        device_info: &DeviceInfo,
+        p2p_info: &P2pInfo,
+        sender_id: &str,
+        on_step: impl Fn(HandshakeStep),
+    ) -> Result<(), BleClientError> {
+        let p2p_data =
+            build_p2p_payload(device_info, p2p_info, sender_id, self.security.as_deref())?;
 
         // 写入 P2P 特征
-        let p2p_char = self.find_characteristic(&peripheral, P2P_CHAR_UUID)?;
+        on_step(HandshakeStep::WritingP2p);
         info!(
             "Writing encrypted P2P info ({} bytes) to receiver",
             p2p_data.len()
         );
-        peripheral
-            .write(&p2p_char, &p2p_data, WriteType::WithResponse)
+        self.guard(self.timeout, connection.write(P2P_CHAR_UUID, &p2p_data))
+            .await
+    }
+
+    /// 反向模式：请求接收端创建 P2P 组，返回接收端的 DeviceInfo 和它的 P2pInfo
+    ///
+    /// 先订阅 P2P 特征的通知，再写入 [`P2pInfo::group_request`]；接收端建组后把
+    /// 自己的 P2pInfo（用同一个会话密钥加密）通过通知发回。`timeout` 内没有收到时返回错误。
+    #[tracing::instrument(skip_all, fields(address = %device_address))]
+    pub async fn connect_and_join_with_progress(
+        &self,
+        device_address: &str,
+        sender_id: &str,
+        mac: &str,
+        timeout: Duration,
+        on_step: impl Fn(HandshakeStep) + Send + Sync,
+    ) -> Result<(DeviceInfo, P2pInfo), BleClientError> {
+        let connection = self.connect(device_address).await?;
+        let result = self
+            .request_group(connection.as_ref(), sender_id, mac, timeout, on_step)
+            .await;
+        self.disconnect(connection.as_ref(), device_address).await;
+        result
+    }
+
+    async fn request_group(
+        &self,
+        connection: &dyn GattConnection,
+        sender_id: &str,
+        mac: &str,
+        timeout: Duration,
+        on_step: impl Fn(HandshakeStep) + Send + Sync,
+    ) -> Result<(DeviceInfo, P2pInfo), BleClientError> {
+        on_step(HandshakeStep::ReadingStatus);
+        let status_data = self
+            .guard(self.timeout, connection.read(STATUS_CHAR_UUID))
             .await?;
+        let device_info = parse_device_info(&status_data)?;
+        if ReceiverInfo::from(&device_info).is_busy() {
+            return Err(BleClientError::DeviceBusy);
+        }
 
-        // 断开连接
-        peripheral.disconnect().await?;
+        let session = negotiate(device_info.key.as_deref(), self.security.as_deref())?;
+        let request = P2pInfo::group_request(mac.to_string());
+        let payload = encode_p2p(&request, sender_id, session.as_ref())?;
 
-        Ok(device_info)
+        let mut notifications = self
+            .guard(self.timeout, connection.subscribe(P2P_CHAR_UUID))
+            .await?;
+        on_step(HandshakeStep::WritingP2p);
+        info!("Asking receiver to create a P2P group");
+        self.guard(self.timeout, connection.write(P2P_CHAR_UUID, &payload))
+            .await?;
+
+        let data = cancel::run(&self.cancel, Some(timeout), notifications.next())
+            .await
+            .map_err(|e| match e {
+                Interrupted::TimedOut(_) => BleClientError::GroupTimeout,
+                cancelled => cancelled.into(),
+            })?
+            .ok_or_else(|| BleClientError::ConnectionFailed("Notifications ended".to_string()))?;
+        let group = decode_group_info(&data, &device_info, session.as_ref())?;
+        info!(
+            "Receiver created group ssid='{}', port={}",
+            group.ssid, group.port
+        );
+        Ok((device_info, group))
     }
+}
 
-    async fn find_device(&self, address: &str) -> Result<PlatformPeripheral, BleClientError> {
-        let peripherals = self.adapter.peripherals().await?;
+/// 解析 STATUS 特征中的 DeviceInfo
+pub(crate) fn parse_device_info(data: &[u8]) -> Result<DeviceInfo, BleClientError> {
+    serde_json::from_slice(data)
+        .map_err(|e| BleClientError::ProtocolError(format!("Invalid DeviceInfo: {}", e)))
+}
 
-        for peripheral in peripherals {
-            if let Some(props) = peripheral.properties().await?
-                && props.address.to_string().to_uppercase() == address.to_uppercase()
-            {
-                return Ok(peripheral);
-            }
-        }
+/// 构造写入 P2P 特征的数据
+///
+/// 如果对方 DeviceInfo 带有公钥，则派生会话密钥并加密 SSID/PSK/MAC；
+/// 否则直接发送明文。BLE 与局域网握手共用此逻辑。
+pub(crate) fn build_p2p_payload(
+    device_info: &DeviceInfo,
+    p2p_info: &P2pInfo,
+    sender_id: &str,
+    security: Option<&BleSecurityPersistent>,
+) -> Result<Vec<u8>, BleClientError> {
+    build_p2p_payload_for_key(device_info.key.as_deref(), p2p_info, sender_id, security)
+}
 
-        Err(BleClientError::DeviceNotFound)
-    }
+/// 同 [`build_p2p_payload`]，直接给出对方公钥（二维码引导时由用户事先取得）
+pub(crate) fn build_p2p_payload_for_key(
+    peer_key: Option<&str>,
+    p2p_info: &P2pInfo,
+    sender_id: &str,
+    security: Option<&BleSecurityPersistent>,
+) -> Result<Vec<u8>, BleClientError> {
+    let session = negotiate(peer_key, security)?;
+    encode_p2p(p2p_info, sender_id, session.as_ref())
+}
 
-    fn find_characteristic(
-        &self,
-        peripheral: &PlatformPeripheral,
-        uuid: Uuid,
-    ) -> Result<Characteristic, BleClientError> {
-        for service in peripheral.services() {
-            if service.uuid == MAIN_SERVICE_UUID {
-                for char in service.characteristics {
-                    if char.uuid == uuid {
-                        return Ok(char);
-                    }
-                }
+/// 与对方协商会话密钥，返回本端公钥和加密器；对方没有公钥时返回 None
+fn negotiate(
+    peer_key: Option<&str>,
+    security: Option<&BleSecurityPersistent>,
+) -> Result<Option<(String, SessionCipher)>, BleClientError> {
+    let Some(peer_key) = peer_key else {
+        return Ok(None);
+    };
+    let key_exchange_failed =
+        |e: anyhow::Error| BleClientError::ProtocolError(format!("Key exchange failed: {}", e));
+    let session = if let Some(sec) = security {
+        // 使用持久化上下文
+        let cipher = sec
+            .derive_session_key(peer_key)
+            .map_err(key_exchange_failed)?;
+        (sec.get_public_key().to_string(), cipher)
+    } else {
+        // 回退到临时上下文
+        let security = BleSecurity::new().map_err(|e| {
+            BleClientError::ProtocolError(format!("Failed to init security: {}", e))
+        })?;
+        let pub_key = security.get_public_key().to_string();
+        let cipher = security
+            .derive_session_key(peer_key)
+            .map_err(key_exchange_failed)?;
+        (pub_key, cipher)
+    };
+    Ok(Some(session))
+}
+
+/// 序列化 P2P 信息，有会话密钥时加密 SSID/PSK/MAC/host
+fn encode_p2p(
+    p2p_info: &P2pInfo,
+    sender_id: &str,
+    session: Option<&(String, SessionCipher)>,
+) -> Result<Vec<u8>, BleClientError> {
+    let Some((sender_public_key, cipher)) = session else {
+        // 不加密
+        return serde_json::to_vec(p2p_info)
+            .map_err(|e| BleClientError::ProtocolError(e.to_string()));
+    };
+    let encrypted = GattHandler::encrypt_p2p_info(p2p_info, cipher, sender_id, sender_public_key)
+        .map_err(|e| BleClientError::ProtocolError(e.to_string()))?;
+    serde_json::to_vec(&encrypted).map_err(|e| BleClientError::ProtocolError(e.to_string()))
+}
+
+/// 解析接收端通知的 P2P 信息（反向模式），加密时用握手时的会话密钥解密
+fn decode_group_info(
+    data: &[u8],
+    device_info: &DeviceInfo,
+    session: Option<&(String, SessionCipher)>,
+) -> Result<P2pInfo, BleClientError> {
+    let info: P2pInfo = serde_json::from_slice(data)
+        .map_err(|e| BleClientError::ProtocolError(format!("Invalid P2P info: {}", e)))?;
+    let info = match &info.key {
+        None => info,
+        Some(key) => match session {
+            Some((_, cipher)) if device_info.key.as_ref() == Some(key) => {
+                GattHandler::decrypt_p2p_info(&info, cipher)
+                    .map_err(|e| BleClientError::ProtocolError(format!("Decrypt failed: {}", e)))?
             }
-        }
-        Err(BleClientError::CharacteristicNotFound(uuid))
-    }
+            _ => {
+                return Err(BleClientError::ProtocolError(
+                    "P2P info encrypted with an unknown key".to_string(),
+                ));
+            }
+        },
+    };
+    info.validate()
+        .map_err(|e| BleClientError::ProtocolError(format!("Invalid P2P info: {}", e)))?;
+    Ok(info)
 }
@@ -30,6 +30,22 @@ impl GattHandler {
             port: encrypted_info.port,
             key: None,
             cat_share: encrypted_info.cat_share,
+            host: encrypted_info
+                .host
+                .as_deref()
+                .map(|h| cipher.decrypt(h))
+                .transpose()?,
+            security: encrypted_info.security,
+            auth_token: encrypted_info
+                .auth_token
+                .as_deref()
+                .map(|t| cipher.decrypt(t))
+                .transpose()?,
+            cert_sha256: encrypted_info
+                .cert_sha256
+                .as_deref()
+                .map(|f| cipher.decrypt(f))
+                .transpose()?,
         })
     }
 
@@ -40,13 +56,30 @@ impl GattHandler {
         sender_id: &str,
         sender_public_key: &str,
     ) -> anyhow::Result<P2pInfo> {
-        Ok(P2pInfo::with_encryption(
+        let mut encrypted = P2pInfo::with_encryption(
             sender_id.to_string(),
             cipher.encrypt(&info.ssid)?,
             cipher.encrypt(&info.psk)?,
             cipher.encrypt(&info.mac)?,
             info.port,
             sender_public_key.to_string(),
-        ))
+        );
+        encrypted.host = info
+            .host
+            .as_deref()
+            .map(|h| cipher.encrypt(h))
+            .transpose()?;
+        encrypted.security = info.security;
+        encrypted.auth_token = info
+            .auth_token
+            .as_deref()
+            .map(|t| cipher.encrypt(t))
+            .transpose()?;
+        encrypted.cert_sha256 = info
+            .cert_sha256
+            .as_deref()
+            .map(|f| cipher.encrypt(f))
+            .transpose()?;
+        Ok(encrypted)
     }
 }
@@ -0,0 +1,275 @@
+//! 未知厂商的广播学习
+//!
+//! 新厂商的手机往往只是换了服务 UUID、厂商 ID 或数据布局，扫描器因此识别不到。
+//! 开启学习模式（[`ScanOptions::learn`](crate::ble::ScanOptions::learn)，
+//! 设置项 `learn_unknown_vendors`）后，扫描时把"差一点"的广播
+//! （见 [`is_near_miss`](crate::ble::capture::is_near_miss)）归纳为 [`LearnedShape`] 并计数，
+//! 保存在状态目录的 `cattysend/learned_shapes.toml`。
+//!
+//! 只记录形状：数据类型、UUID / 厂商 ID、长度和可打印字符段的位置，
+//! 不记录地址、设备名和数据内容。同一形状见到足够多次后成为候选解析规则，
+//! `cattysend learn` 输出为 TOML，附在 issue 里供维护者确认后加入扫描器：
+//!
+//! ```toml
+//! [[candidate]]
+//! kind = "service_data"
+//! key = "0x3338"
+//! length = 27
+//! name_offset = 8
+//! seen = 12
+//! ```
+
+use crate::ble::capture::{is_near_length, is_near_uuid, short_uuid};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// 同一形状至少见到这么多次才作为候选规则
+pub const CANDIDATE_MIN_SEEN: u32 = 3;
+
+/// 可能是设备名的可打印 ASCII 段的最短长度
+const NAME_MIN_LEN: usize = 4;
+
+/// 广播中载荷的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadKind {
+    /// 服务 UUID 列表中的 UUID（没有数据）
+    ServiceUuid,
+    /// 服务数据
+    ServiceData,
+    /// 厂商数据
+    ManufacturerData,
+}
+
+/// 一种载荷形状及其出现次数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LearnedShape {
+    pub kind: PayloadKind,
+    /// 16-bit UUID 或厂商 ID（`0x3338` 形式），其他 UUID 为完整形式
+    pub key: String,
+    /// 数据长度（服务 UUID 为 0）
+    #[serde(default)]
+    pub length: usize,
+    /// 数据中最长的可打印 ASCII 段的起始位置，可能是设备名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_offset: Option<usize>,
+    /// 见到的次数（每次扫描每个设备计一次）
+    #[serde(default)]
+    pub seen: u32,
+}
+
+impl LearnedShape {
+    fn new(kind: PayloadKind, key: String, data: &[u8]) -> Self {
+        Self {
+            kind,
+            key,
+            length: data.len(),
+            name_offset: name_offset(data),
+            seen: 1,
+        }
+    }
+
+    /// 除次数外是否相同
+    pub fn same_shape(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.key == other.key
+            && self.length == other.length
+            && self.name_offset == other.name_offset
+    }
+}
+
+/// 学习到的形状
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShapeLearner {
+    #[serde(default, rename = "shape")]
+    pub shapes: Vec<LearnedShape>,
+}
+
+/// 输出候选规则用
+#[derive(Serialize)]
+struct Candidates<'a> {
+    candidate: Vec<&'a LearnedShape>,
+}
+
+impl ShapeLearner {
+    /// 形状文件路径
+    pub fn default_path() -> PathBuf {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("cattysend")
+            .join("learned_shapes.toml")
+    }
+
+    /// 加载（文件不存在或无法解析时为空）
+    pub fn load() -> Self {
+        Self::load_from(&Self::default_path())
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.save_to(&Self::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(path).map(|content| toml::from_str(&content)) {
+            Ok(Ok(learner)) => {
+                debug!("Loaded learned shapes from {:?}", path);
+                learner
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to parse learned shapes: {}, ignoring", e);
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Failed to read learned shapes: {}, ignoring", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        debug!("Saved learned shapes to {:?}", path);
+        Ok(())
+    }
+
+    /// 记录一条未识别广播中与 CatShare 相似的载荷
+    ///
+    /// 广播带有 `0x33xx` 服务 UUID 时记录它的全部服务数据和厂商数据，
+    /// 否则只记录长度与 CatShare 相同的数据。
+    pub fn observe(
+        &mut self,
+        uuids: &HashSet<Uuid>,
+        service_data: &HashMap<Uuid, Vec<u8>>,
+        manufacturer_data: &HashMap<u16, Vec<u8>>,
+    ) {
+        let near = uuids.iter().chain(service_data.keys()).any(is_near_uuid);
+        let mut shapes: Vec<LearnedShape> = uuids
+            .iter()
+            .filter(|uuid| is_near_uuid(uuid) && !service_data.contains_key(uuid))
+            .map(|uuid| LearnedShape::new(PayloadKind::ServiceUuid, uuid_key(uuid), &[]))
+            .collect();
+        shapes.extend(
+            service_data
+                .iter()
+                .filter(|(uuid, data)| near || is_near_uuid(uuid) || is_near_length(data))
+                .map(|(uuid, data)| {
+                    LearnedShape::new(PayloadKind::ServiceData, uuid_key(uuid), data)
+                }),
+        );
+        shapes.extend(
+            manufacturer_data
+                .iter()
+                .filter(|(_, data)| near || is_near_length(data))
+                .map(|(id, data)| {
+                    LearnedShape::new(PayloadKind::ManufacturerData, format!("0x{:04x}", id), data)
+                }),
+        );
+
+        for shape in shapes {
+            match self.shapes.iter_mut().find(|s| s.same_shape(&shape)) {
+                Some(known) => known.seen = known.seen.saturating_add(1),
+                None => self.shapes.push(shape),
+            }
+        }
+    }
+
+    /// 至少见到 `min_seen` 次的形状，次数多的在前
+    pub fn candidates(&self, min_seen: u32) -> Vec<&LearnedShape> {
+        let mut candidates: Vec<_> = self.shapes.iter().filter(|s| s.seen >= min_seen).collect();
+        candidates.sort_by(|a, b| b.seen.cmp(&a.seen).then_with(|| a.key.cmp(&b.key)));
+        candidates
+    }
+
+    /// 候选规则的 TOML 形式
+    pub fn candidates_toml(&self, min_seen: u32) -> anyhow::Result<String> {
+        let candidates = Candidates {
+            candidate: self.candidates(min_seen),
+        };
+        Ok(toml::to_string_pretty(&candidates)?)
+    }
+}
+
+fn uuid_key(uuid: &Uuid) -> String {
+    short_uuid(uuid).map_or_else(|| uuid.to_string(), |id| format!("0x{:04x}", id))
+}
+
+/// 最长的可打印 ASCII 段的起始位置（至少 [`NAME_MIN_LEN`] 字节）
+fn name_offset(data: &[u8]) -> Option<usize> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = 0;
+    for (i, b) in data.iter().chain(std::iter::once(&0)).enumerate() {
+        if (0x20..0x7f).contains(b) {
+            continue;
+        }
+        let len = i - start;
+        if len >= NAME_MIN_LEN && best.map_or(true, |(_, best_len)| len > best_len) {
+            best = Some((start, len));
+        }
+        start = i + 1;
+    }
+    best.map(|(offset, _)| offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_learn_shapes_and_candidates() {
+        let uuid16 =
+            |id: u16| Uuid::from_u128(((id as u128) << 96) | 0x0000_1000_8000_00805f9b34fb);
+        let mut payload = vec![0x01, 0x02, 0x03];
+        payload.extend_from_slice(b"Phone X");
+        payload.resize(27, 0xff);
+        assert_eq!(name_offset(&payload), Some(3));
+        assert_eq!(name_offset(&[0x01, b'a', b'b', 0x02]), None);
+
+        let uuids = HashSet::from([uuid16(0x3338)]);
+        let service_data = HashMap::from([(uuid16(0x3338), payload.clone())]);
+        // 带相似 UUID 时，任意长度的厂商数据也记录
+        let manufacturer = HashMap::from([(0x1234, vec![0; 9])]);
+
+        let mut learner = ShapeLearner::default();
+        for _ in 0..3 {
+            learner.observe(&uuids, &service_data, &manufacturer);
+        }
+        // 没有相似 UUID 时只记录长度相同的数据
+        learner.observe(
+            &HashSet::new(),
+            &HashMap::new(),
+            &HashMap::from([(0x5678, vec![0; 9]), (0x9abc, vec![0; 6])]),
+        );
+
+        assert_eq!(learner.shapes.len(), 3);
+        let candidates = learner.candidates(CANDIDATE_MIN_SEEN);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].key, "0x1234");
+        assert_eq!(candidates[1].kind, PayloadKind::ServiceData);
+        assert_eq!(candidates[1].key, "0x3338");
+        assert_eq!(candidates[1].length, 27);
+        assert_eq!(candidates[1].name_offset, Some(3));
+
+        let rendered = learner.candidates_toml(CANDIDATE_MIN_SEEN).unwrap();
+        assert!(rendered.contains("[[candidate]]"));
+        assert!(rendered.contains("kind = \"service_data\""));
+        assert!(!rendered.contains("Phone"));
+
+        let dir = temp_dir("shapes");
+        let path = dir.join("shapes.toml");
+        learner.save_to(&path).unwrap();
+        assert_eq!(ShapeLearner::load_from(&path), learner);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -5,9 +5,15 @@
 //! # 模块
 //!
 //! - `scanner`: BLE 扫描器（发现接收端设备）
+//! - `capture`: 扫描时记录原始广播数据（调试兼容问题）
+//! - `learn`: 学习未知厂商的广播形状，生成候选解析规则
 //! - `client`: BLE 客户端（连接接收端并交换 P2P 信息）
+//! - `backend`: BLE 客户端后端抽象（BlueZ / btleplug）
 //! - `server`: GATT 服务器（作为接收端等待连接）
 //! - `advertiser`: 广播器（发布接收端广播）
+//! - `adv_config`: 广播间隔与占空比配置
+//! - `brand`: 广播端与扫描端共用的厂商预设
+//! - `pairing`: 配对代理（需要配对才能读写特征的设备）
 //!
 //! # UUID 常量
 //!
@@ -17,9 +23,15 @@
 //! - `STATUS_CHAR_UUID`: 读取 DeviceInfo 的特征
 //! - `P2P_CHAR_UUID`: 写入 P2pInfo 的特征
 
+pub mod adv_config;
 pub mod advertiser;
+pub mod backend;
+pub mod brand;
+pub mod capture;
 pub mod client;
 pub mod gatt;
+pub mod learn;
+pub mod pairing;
 pub mod scanner;
 pub mod server;
 
@@ -61,7 +73,7 @@ pub const P2P_CHAR_UUID: Uuid = Uuid::from_u128(0x00009953_0000_1000_8000_00805f
 ///
 /// # 字段
 ///
-/// - `state`: 设备状态 (通常为 0)
+/// - `state`: 设备状态 (见 [`ReceiverState`])
 /// - `key`: Base64 编码的 ECDH 公钥 (SPKI 格式)
 /// - `mac`: 设备 MAC 地址
 /// - `cat_share`: 协议版本号 (序列化为 `catShare`)
@@ -85,7 +97,7 @@ impl DeviceInfo {
     /// - `mac`: 设备 MAC 地址
     pub fn new(public_key: String, mac: String) -> Self {
         Self {
-            state: 0,
+            state: ReceiverState::Idle.code(),
             key: Some(public_key),
             mac,
             cat_share: Some(1),
@@ -93,10 +105,97 @@ impl DeviceInfo {
     }
 }
 
+/// 接收端状态（DeviceInfo.state）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiverState {
+    /// 空闲，可以接收
+    Idle,
+    /// 正在接收其他发送端的文件
+    Busy,
+    /// 已接受当前发送端的传输请求（只通过 STATUS 通知推送）
+    Accepted,
+    /// 已拒绝当前发送端的传输请求（只通过 STATUS 通知推送）
+    Rejected,
+    /// 未知状态码（新版本协议）
+    Unknown(i32),
+}
+
+impl ReceiverState {
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            0 => Self::Idle,
+            1 => Self::Busy,
+            2 => Self::Accepted,
+            3 => Self::Rejected,
+            other => Self::Unknown(other),
+        }
+    }
+
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Idle => 0,
+            Self::Busy => 1,
+            Self::Accepted => 2,
+            Self::Rejected => 3,
+            Self::Unknown(code) => code,
+        }
+    }
+}
+
+/// 广播身份 UUID 的能力字节（第 3 字节）：支持 5GHz
+pub const CAPABILITY_5GHZ: u8 = 0x01;
+/// 能力字节：正在接收，暂不可用（cattysend 扩展，CatShare 只看 5GHz 位）
+pub const CAPABILITY_BUSY: u8 = 0x02;
+/// 能力字节：能接入 WPA3-SAE 热点（cattysend 扩展，CatShare 不会设置），
+/// 发送端据此判断对端是 cattysend，见 [`crate::wifi::credentials`]
+pub const CAPABILITY_WPA3: u8 = 0x04;
+
+/// 从 DeviceInfo 解析出的接收端信息
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiverInfo {
+    pub state: ReceiverState,
+    /// 接收端 ECDH 公钥（为空时视为不支持加密）
+    pub public_key: Option<String>,
+    pub mac: String,
+    /// 协议版本号（旧版 MTA 设备不带 catShare 字段，记为 0）
+    pub protocol_version: i32,
+}
+
+impl ReceiverInfo {
+    /// 接收端是否正忙
+    pub fn is_busy(&self) -> bool {
+        self.state == ReceiverState::Busy
+    }
+
+    /// 是否需要加密 P2P 信息
+    pub fn supports_encryption(&self) -> bool {
+        self.public_key.is_some()
+    }
+}
+
+impl From<&DeviceInfo> for ReceiverInfo {
+    fn from(info: &DeviceInfo) -> Self {
+        Self {
+            state: ReceiverState::from_code(info.state),
+            public_key: info.key.clone().filter(|k| !k.trim().is_empty()),
+            mac: info.mac.clone(),
+            protocol_version: info.cat_share.unwrap_or(0),
+        }
+    }
+}
+
 // Re-exports
-pub use client::BleClient;
-pub use scanner::{BleScanner, ChannelScanCallback, DiscoveredDevice, ScanCallback};
-pub use server::{GattServer, GattServerHandle, P2pReceiveEvent};
+pub use adv_config::{DutyCycle, LegacyAdvConfig};
+pub use backend::{GattClientBackend, GattConnection};
+pub use brand::BrandPreset;
+pub use client::{BleClient, BleClientError, HandshakeStep, PendingHandshake};
+pub use pairing::{PairingAgent, PairingPrompt, PairingRequest};
+pub use scanner::{
+    BleScanner, ChannelScanCallback, DiscoveredDevice, ScanCallback, ScanOptions, rank_devices,
+};
+pub use server::{
+    GattConnectionEvent, GattServer, GattServerHandle, P2pNotifier, P2pReceiveEvent, StatusNotifier,
+};
 
 #[cfg(test)]
 mod tests {
@@ -201,4 +300,33 @@ mod tests {
         assert!(!json.contains("key"));
         assert!(!json.contains("catShare"));
     }
+
+    /// 验证 ReceiverInfo 解析状态、公钥和协议版本
+    #[test]
+    fn test_receiver_info_from_device_info() {
+        let json = r#"{"state":1,"key":"","mac":"11:22:33:44:55:66"}"#;
+        let info: DeviceInfo = serde_json::from_str(json).unwrap();
+        let receiver = ReceiverInfo::from(&info);
+
+        assert!(receiver.is_busy());
+        assert!(!receiver.supports_encryption());
+        assert_eq!(receiver.protocol_version, 0);
+
+        let info = DeviceInfo::new("KEY".to_string(), "AA:BB:CC:DD:EE:FF".to_string());
+        let receiver = ReceiverInfo::from(&info);
+        assert_eq!(receiver.state, ReceiverState::Idle);
+        assert_eq!(receiver.public_key.as_deref(), Some("KEY"));
+        assert_eq!(receiver.protocol_version, 1);
+        assert_eq!(ReceiverState::from_code(7), ReceiverState::Unknown(7));
+    }
+
+    /// 验证通知专用的状态码往返
+    #[test]
+    fn test_receiver_state_decision_codes() {
+        for state in [ReceiverState::Accepted, ReceiverState::Rejected] {
+            assert_eq!(ReceiverState::from_code(state.code()), state);
+        }
+        assert_eq!(ReceiverState::Accepted.code(), 2);
+        assert_eq!(ReceiverState::Rejected.code(), 3);
+    }
 }
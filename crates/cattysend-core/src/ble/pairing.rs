@@ -0,0 +1,282 @@
+//! 配对代理
+//!
+//! 部分接收端（多见于已与电脑绑定过的手机）要求先配对才允许写 GATT 特征，
+//! 此时 BlueZ 会回调已注册的 Agent；没有 Agent 时配对直接失败，握手报
+//! `NotAuthorized`。[`PairingAgent`] 注册到 bluer 会话后：
+//!
+//! - Just-Works 配对（`RequestAuthorization`）和服务授权直接同意；
+//! - 数字比较（`RequestConfirmation`）需要用户确认，转为 [`PairingPrompt`] 交给界面，
+//!   没有界面时拒绝；
+//! - 需要在对端输入的密钥 / PIN 码只转给界面显示。
+//!
+//! 发送端通过 [`BleClient::with_pairing`](crate::ble::BleClient::with_pairing)、
+//! 接收端通过 [`GattServer::with_pairing`](crate::ble::GattServer::with_pairing) 使用，
+//! 提示最终到达 `SendProgressCallback::on_pairing` / `ReceiveProgressCallback::on_pairing`。
+
+use bluer::agent::{Agent, AgentHandle, ReqError, ReqResult};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// 等待用户确认配对的最长时间（BlueZ 自身约 30 秒后取消）
+pub const PAIRING_CONFIRM_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// 需要转给界面的配对提示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PairingPrompt {
+    /// 数字比较：两端显示相同的六位数时确认
+    Confirm { address: String, passkey: u32 },
+    /// 需要在对端输入的密钥
+    DisplayPasskey { address: String, passkey: u32 },
+    /// 需要在对端输入的 PIN 码
+    DisplayPinCode { address: String, pin_code: String },
+}
+
+impl PairingPrompt {
+    /// 对端蓝牙地址
+    pub fn address(&self) -> &str {
+        match self {
+            Self::Confirm { address, .. }
+            | Self::DisplayPasskey { address, .. }
+            | Self::DisplayPinCode { address, .. } => address,
+        }
+    }
+
+    /// 显示给用户的配对码（六位数字或 PIN 码）
+    pub fn code(&self) -> String {
+        match self {
+            Self::Confirm { passkey, .. } | Self::DisplayPasskey { passkey, .. } => {
+                format!("{:06}", passkey)
+            }
+            Self::DisplayPinCode { pin_code, .. } => pin_code.clone(),
+        }
+    }
+
+    /// 是否需要用户回答（只显示的提示不需要）
+    pub fn needs_confirmation(&self) -> bool {
+        matches!(self, Self::Confirm { .. })
+    }
+}
+
+impl fmt::Display for PairingPrompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Confirm { address, passkey } => {
+                write!(f, "confirm pairing with {} (code {:06})", address, passkey)
+            }
+            Self::DisplayPasskey { address, passkey } => {
+                write!(f, "enter {:06} on {} to pair", passkey, address)
+            }
+            Self::DisplayPinCode { address, pin_code } => {
+                write!(f, "enter PIN {} on {} to pair", pin_code, address)
+            }
+        }
+    }
+}
+
+/// 等待界面处理的配对提示
+#[derive(Debug)]
+pub struct PairingRequest {
+    pub prompt: PairingPrompt,
+    reply: Option<oneshot::Sender<bool>>,
+}
+
+impl PairingRequest {
+    /// 回答提示；只显示的提示忽略 `accept`
+    pub fn respond(mut self, accept: bool) {
+        if let Some(reply) = self.reply.take() {
+            let _ = reply.send(accept);
+        }
+    }
+}
+
+/// BlueZ 配对代理
+#[derive(Debug, Clone, Default)]
+pub struct PairingAgent {
+    prompts: Option<mpsc::Sender<PairingRequest>>,
+    request_default: bool,
+}
+
+impl PairingAgent {
+    /// 同时返回配对提示通道；不读取通道时需要确认的配对会被拒绝
+    pub fn with_prompts() -> (Self, mpsc::Receiver<PairingRequest>) {
+        let (tx, rx) = mpsc::channel(4);
+        (
+            Self {
+                prompts: Some(tx),
+                request_default: false,
+            },
+            rx,
+        )
+    }
+
+    /// 注册为默认 Agent
+    ///
+    /// 对端主动发起的配对（接收端）只会交给默认 Agent；注销后 BlueZ 恢复之前的默认 Agent。
+    pub fn as_default(mut self) -> Self {
+        self.request_default = true;
+        self
+    }
+
+    /// 注册到 `session`，返回的句柄被丢弃时注销
+    pub async fn register(self, session: &bluer::Session) -> bluer::Result<AgentHandle> {
+        let request_default = self.request_default;
+        let confirm = self.prompts.clone();
+        let passkey = self.prompts.clone();
+        let pin_code = self.prompts;
+        let agent = Agent {
+            request_default,
+            request_confirmation: Some(Box::new(move |req| {
+                let prompts = confirm.clone();
+                Box::pin(async move {
+                    let prompt = PairingPrompt::Confirm {
+                        address: req.device.to_string(),
+                        passkey: req.passkey,
+                    };
+                    ask(prompts.as_ref(), prompt).await
+                })
+            })),
+            request_authorization: Some(Box::new(|req| {
+                Box::pin(async move {
+                    info!("Accepting Just-Works pairing with {}", req.device);
+                    Ok(())
+                })
+            })),
+            authorize_service: Some(Box::new(|req| {
+                Box::pin(async move {
+                    debug!("Authorizing service {} for {}", req.service, req.device);
+                    Ok(())
+                })
+            })),
+            display_passkey: Some(Box::new(move |req| {
+                let prompts = passkey.clone();
+                Box::pin(async move {
+                    let prompt = PairingPrompt::DisplayPasskey {
+                        address: req.device.to_string(),
+                        passkey: req.passkey,
+                    };
+                    show(prompts.as_ref(), prompt);
+                    Ok(())
+                })
+            })),
+            display_pin_code: Some(Box::new(move |req| {
+                let prompts = pin_code.clone();
+                Box::pin(async move {
+                    let prompt = PairingPrompt::DisplayPinCode {
+                        address: req.device.to_string(),
+                        pin_code: req.pincode,
+                    };
+                    show(prompts.as_ref(), prompt);
+                    Ok(())
+                })
+            })),
+            ..Default::default()
+        };
+        session.register_agent(agent).await
+    }
+}
+
+/// 把需要确认的提示交给界面并等待回答
+async fn ask(
+    prompts: Option<&mpsc::Sender<PairingRequest>>,
+    prompt: PairingPrompt,
+) -> ReqResult<()> {
+    let Some(prompts) = prompts else {
+        warn!("Rejecting pairing without a UI: {}", prompt);
+        return Err(ReqError::Rejected);
+    };
+    let (tx, rx) = oneshot::channel();
+    let request = PairingRequest {
+        prompt,
+        reply: Some(tx),
+    };
+    if prompts.send(request).await.is_err() {
+        return Err(ReqError::Rejected);
+    }
+    match tokio::time::timeout(PAIRING_CONFIRM_TIMEOUT, rx).await {
+        Ok(Ok(true)) => Ok(()),
+        Ok(_) => Err(ReqError::Rejected),
+        Err(_) => Err(ReqError::Canceled),
+    }
+}
+
+/// 把只显示的提示交给界面，界面忙时不等待
+fn show(prompts: Option<&mpsc::Sender<PairingRequest>>, prompt: PairingPrompt) {
+    info!("Pairing: {}", prompt);
+    if let Some(prompts) = prompts {
+        let _ = prompts.try_send(PairingRequest {
+            prompt,
+            reply: None,
+        });
+    }
+}
+
+/// 运行 `work`，期间用 `answer` 回答 `prompts` 收到的配对提示
+///
+/// 通道只是借用，同一个客户端分几步操作时可以反复使用。
+pub async fn answer_while<T>(
+    prompts: Option<&mut mpsc::Receiver<PairingRequest>>,
+    answer: impl Fn(&PairingPrompt) -> bool,
+    work: impl Future<Output = T>,
+) -> T {
+    let Some(prompts) = prompts else {
+        return work.await;
+    };
+    tokio::pin!(work);
+    loop {
+        tokio::select! {
+            output = &mut work => return output,
+            Some(request) = prompts.recv() => {
+                let accept = answer(&request.prompt);
+                request.respond(accept);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pairing_prompts() {
+        let confirm = PairingPrompt::Confirm {
+            address: "AA:BB:CC:DD:EE:01".to_string(),
+            passkey: 42,
+        };
+        assert!(confirm.needs_confirmation());
+        assert_eq!(confirm.code(), "000042");
+        assert_eq!(
+            confirm.to_string(),
+            "confirm pairing with AA:BB:CC:DD:EE:01 (code 000042)"
+        );
+
+        // 没有界面时拒绝数字比较
+        assert!(matches!(
+            ask(None, confirm.clone()).await,
+            Err(ReqError::Rejected)
+        ));
+
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        assert!(matches!(
+            ask(Some(&tx), confirm.clone()).await,
+            Err(ReqError::Rejected)
+        ));
+
+        // 界面的回答传回 Agent
+        let (agent, mut rx) = PairingAgent::with_prompts();
+        let prompts = agent.prompts.clone();
+        let result = answer_while(
+            Some(&mut rx),
+            |prompt| prompt.address() == "AA:BB:CC:DD:EE:01",
+            ask(prompts.as_ref(), confirm),
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+}
@@ -9,20 +9,58 @@
 //! 1. Service UUIDs in the range `00003331` to `00003334` (base `00805f9b34fb`).
 //! 2. Manufacturer Data (specifically Xiaomi `0x038F`).
 //! 3. Service Data for specific UUIDs containing legacy device info.
+//!
+//! Matches can be narrowed with [`ScanOptions`]; results are returned in
+//! [`rank_devices`] order.
+//!
+//! [`ScanOptions::passive`] listens through a BlueZ advertisement monitor
+//! instead of active discovery, and [`ScanOptions::capture`] records raw
+//! advertisements for interop reports (see [`crate::ble::capture`]).
+//! [`ScanOptions::learn`] counts the shapes of near-miss advertisements for
+//! unknown vendors (see [`crate::ble::learn`]).
+//! [`ScanOptions::stop_on_first`] ends the scan at the first match, which
+//! keeps the send path short when only one receiver is around.
+//!
+//! Phones advertise from rotating random (RPA) addresses, so devices are
+//! merged by [`DiscoveredDevice::identity`] rather than by address: a device
+//! seen again under a new address replaces its old entry and is reported to
+//! the callback again. [`BleScanner::resolve_address`] maps an address that
+//! has since rotated to the device's latest one. A scan stops early with
+//! [`Interrupted::Cancelled`] once the token passed to
+//! [`BleScanner::with_cancellation`] is cancelled.
 
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use anyhow::Context;
 use async_trait::async_trait;
+use bluer::monitor::{
+    Monitor, MonitorEvent, MonitorHandle, MonitorManager, Pattern, RssiSamplingPeriod,
+    Type as MonitorType,
+};
 use bluer::{Adapter, AdapterEvent, Device, Session};
+use futures_util::stream::BoxStream;
 use futures_util::{StreamExt, pin_mut};
 use log::{debug, info, warn};
 use uuid::Uuid;
 
+use crate::ble::brand::{BrandPreset, capability_flags, parse_ident_uuid};
+use crate::ble::capture::{AdvCapture, AdvRecord, CaptureVerdict, is_near_miss};
+use crate::ble::learn::ShapeLearner;
+use crate::cancel::{CancellationToken, Interrupted};
+use crate::config::BrandId;
+
 /// Manufacturer ID for Xiaomi
 const MANUF_ID_XIAOMI: u16 = 0x038F;
 
+/// AD types matched by the passive scan monitor.
+const AD_INCOMPLETE_UUID16: u8 = 0x02;
+const AD_COMPLETE_UUID16: u8 = 0x03;
+const AD_SERVICE_DATA_UUID16: u8 = 0x16;
+const AD_MANUFACTURER_DATA: u8 = 0xff;
+
 /// Scan Response UUID (Legacy)
 const SCAN_RESP_UUID_STR: &str = "0000ffff-0000-1000-8000-00805f9b34fb";
 
@@ -111,9 +149,18 @@ impl From<i16> for Brand {
     }
 }
 
-/// Helper for backward compatibility with existing code
+/// Display name for a brand ID.
+///
+/// IDs Android does not know (e.g. Linux/Windows peers) fall back to the shared
+/// [`BrandPreset`] catalog so cattysend devices still show their brand.
 pub fn get_vendor_name(id: i16) -> String {
-    Brand::from(id).to_string()
+    match Brand::from(id) {
+        Brand::Unknown(_) => match u8::try_from(id).map(BrandPreset::from_id) {
+            Ok(preset) if preset.id != 0 => preset.name().to_string(),
+            _ => Brand::Unknown(id).to_string(),
+        },
+        brand => brand.to_string(),
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -125,6 +172,148 @@ pub struct DiscoveredDevice {
     pub brand_id: Option<i16>,
     pub rssi: Option<i16>,
     pub supports_5ghz: bool,
+    /// Receiver advertises that it is busy with another transfer.
+    pub busy: bool,
+    /// Receiver is cattysend and can join a WPA3-SAE hotspot.
+    pub supports_wpa3: bool,
+    /// LAN handshake endpoint when the device was found via mDNS instead of BLE.
+    pub lan_endpoint: Option<std::net::SocketAddr>,
+}
+
+impl DiscoveredDevice {
+    /// Identity that survives address rotation: the advertised sender ID and
+    /// the name. `None` when the advertisement carries no sender ID.
+    pub fn identity(&self) -> Option<(&str, &str)> {
+        (self.sender_id != NO_SENDER_ID).then_some((self.sender_id.as_str(), self.name.as_str()))
+    }
+
+    /// Whether `other` is this device, possibly under a rotated address.
+    pub fn is_same_device(&self, other: &DiscoveredDevice) -> bool {
+        self.address == other.address
+            || self
+                .identity()
+                .is_some_and(|identity| other.identity() == Some(identity))
+    }
+
+    /// Brand from the advertised ID (signed Java bytes map onto the same IDs).
+    pub fn brand_id(&self) -> BrandId {
+        match self.brand_id {
+            Some(id @ -128..=255) => BrandId::from_id(id as u8),
+            _ => BrandId::Unknown,
+        }
+    }
+}
+
+/// Filters applied while scanning.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanOptions {
+    /// Ignore devices weaker than this (devices without RSSI are kept).
+    pub min_rssi: Option<i16>,
+    /// Only report these brands (empty = all).
+    pub brands: Vec<BrandId>,
+    /// Only report receivers advertising 5GHz support.
+    pub require_5ghz: bool,
+    /// Listen through a BlueZ advertisement monitor (passive scanning, no scan
+    /// requests are sent). Needs bluetoothd with experimental features enabled;
+    /// only CatShare advertisements are reported, so no near misses are captured.
+    pub passive: bool,
+    /// Append raw advertisements of matched and near-miss devices to this file.
+    pub capture: Option<PathBuf>,
+    /// Record anonymized shapes of near-miss advertisements into the learned
+    /// shapes file (see [`crate::ble::learn`]). Has no effect on passive scans.
+    pub learn: bool,
+    /// Stop as soon as the first device passing the filters is found instead
+    /// of waiting for the timeout.
+    pub stop_on_first: bool,
+}
+
+impl ScanOptions {
+    /// Whether `device` passes the RSSI, brand and 5GHz filters.
+    pub fn matches(&self, device: &DiscoveredDevice) -> bool {
+        if let (Some(min), Some(rssi)) = (self.min_rssi, device.rssi)
+            && rssi < min
+        {
+            return false;
+        }
+        if !self.brands.is_empty() && !self.brands.contains(&device.brand_id()) {
+            return false;
+        }
+        !self.require_5ghz || device.supports_5ghz
+    }
+}
+
+/// Sort devices for display: idle before busy, then strongest signal first
+/// (devices without RSSI last), then by name.
+pub fn rank_devices(devices: &mut [DiscoveredDevice]) {
+    devices.sort_by(|a, b| {
+        a.busy
+            .cmp(&b.busy)
+            .then_with(|| b.rssi.unwrap_or(i16::MIN).cmp(&a.rssi.unwrap_or(i16::MIN)))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+}
+
+/// Sender ID reported when the advertisement does not carry one.
+const NO_SENDER_ID: &str = "0000";
+
+/// Map key for a device's identity.
+fn identity_key(device: &DiscoveredDevice) -> Option<String> {
+    device
+        .identity()
+        .map(|(sender_id, name)| format!("{}/{}", sender_id, name))
+}
+
+/// Current address of each logical device, kept for the scanner's lifetime
+/// so that address rotations across scans are followed.
+#[derive(Debug, Default)]
+struct AddressTracker {
+    /// Identity key -> latest address.
+    current: HashMap<String, String>,
+    /// Address the device no longer uses -> identity key.
+    retired: HashMap<String, String>,
+}
+
+impl AddressTracker {
+    /// Record `device` at its address; returns the previous address if it moved.
+    fn observe(&mut self, device: &DiscoveredDevice) -> Option<String> {
+        let key = identity_key(device)?;
+        self.retired.remove(&device.address);
+        let previous = self.current.insert(key.clone(), device.address.clone())?;
+        if previous == device.address {
+            return None;
+        }
+        self.retired.insert(previous.clone(), key);
+        Some(previous)
+    }
+
+    /// Latest address of the device that used to advertise from `address`.
+    fn resolve(&self, address: &str) -> Option<&str> {
+        let key = self.retired.get(address)?;
+        self.current.get(key).map(String::as_str)
+    }
+}
+
+/// Advertisement monitor patterns: the CatShare 16-bit UUIDs in service
+/// lists or service data, the legacy scan response and Xiaomi manufacturer data.
+fn passive_patterns() -> Vec<Pattern> {
+    let pattern = |data_type, id: u16| Pattern {
+        data_type,
+        start_position: 0,
+        content: id.to_le_bytes().to_vec(),
+    };
+    let mut patterns = Vec::new();
+    for id in 0x3331..=0x3334 {
+        for data_type in [
+            AD_INCOMPLETE_UUID16,
+            AD_COMPLETE_UUID16,
+            AD_SERVICE_DATA_UUID16,
+        ] {
+            patterns.push(pattern(data_type, id));
+        }
+    }
+    patterns.push(pattern(AD_SERVICE_DATA_UUID16, 0xffff));
+    patterns.push(pattern(AD_MANUFACTURER_DATA, MANUF_ID_XIAOMI));
+    patterns
 }
 
 #[async_trait]
@@ -191,14 +380,42 @@ fn extract_ascii_name(data: &[u8]) -> Option<String> {
 
 pub struct BleScanner {
     session: Session,
+    options: ScanOptions,
+    cancel: CancellationToken,
+    addresses: Mutex<AddressTracker>,
 }
 
 impl BleScanner {
     pub async fn new() -> anyhow::Result<Self> {
         let session = Session::new().await?;
-        Ok(Self { session })
+        Ok(Self {
+            session,
+            options: ScanOptions::default(),
+            cancel: CancellationToken::new(),
+            addresses: Mutex::new(AddressTracker::default()),
+        })
+    }
+
+    /// Filter scan results with `options`.
+    pub fn with_options(mut self, options: ScanOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Abort the scan when `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
     }
 
+    /// Latest address of the device last seen at `address` by this scanner
+    /// (`address` itself when it has not rotated).
+    pub fn resolve_address(&self, address: &str) -> String {
+        let addresses = self.addresses.lock().unwrap();
+        addresses.resolve(address).unwrap_or(address).to_string()
+    }
+
+    #[tracing::instrument(skip_all, fields(timeout = ?timeout))]
     pub async fn scan(
         &self,
         timeout: Duration,
@@ -206,14 +423,55 @@ impl BleScanner {
     ) -> anyhow::Result<Vec<DiscoveredDevice>> {
         let adapter = self.init_adapter().await?;
         let mut discovered_map = HashMap::new();
+        let mut processed = HashSet::new();
+        let started = Instant::now();
+        let mut first_device = None;
+        let mut capture = match &self.options.capture {
+            Some(path) => Some(
+                AdvCapture::open(path)
+                    .with_context(|| format!("Failed to open capture file {}", path.display()))?,
+            ),
+            None => None,
+        };
+        let mut learner = self.options.learn.then(ShapeLearner::load);
 
         info!(
-            "Starting BLE scan for {}s on {}",
+            "Starting {} BLE scan for {}s on {}",
+            if self.options.passive {
+                "passive"
+            } else {
+                "active"
+            },
             timeout.as_secs(),
             adapter.name()
         );
 
-        let mut device_events = adapter.discover_devices().await?;
+        // The monitor is unregistered when dropped, keep it until the scan ends
+        let (_monitor, mut device_events): (_, BoxStream<'_, bluer::Address>) =
+            if self.options.passive {
+                let (manager, handle) = self.passive_monitor(&adapter).await?;
+                let found = handle
+                    .filter_map(|event| async move {
+                        match event {
+                            MonitorEvent::DeviceFound(id) => Some(id.device),
+                            _ => None,
+                        }
+                    })
+                    .boxed();
+                (Some(manager), found)
+            } else {
+                let added = adapter
+                    .discover_devices()
+                    .await?
+                    .filter_map(|event| async move {
+                        match event {
+                            AdapterEvent::DeviceAdded(addr) => Some(addr),
+                            _ => None,
+                        }
+                    })
+                    .boxed();
+                (None, added)
+            };
         let timeout_fut = tokio::time::sleep(timeout);
         pin_mut!(timeout_fut);
 
@@ -221,10 +479,19 @@ impl BleScanner {
         loop {
             tokio::select! {
                 _ = &mut timeout_fut => break,
-                Some(event) = device_events.next() => {
-                    if let AdapterEvent::DeviceAdded(addr) = event {
-                        if let Ok(device) = adapter.device(addr) {
-                            self.process_device(&device, &mut discovered_map, callback.as_ref()).await;
+                _ = self.cancel.cancelled() => {
+                    info!("BLE scan cancelled");
+                    return Err(Interrupted::Cancelled.into());
+                }
+                Some(addr) = device_events.next() => {
+                    if let Ok(device) = adapter.device(addr) {
+                        self.process_device(&device, &mut discovered_map, &mut processed, &mut capture, &mut learner, callback.as_ref()).await;
+                        if first_device.is_none() && !discovered_map.is_empty() {
+                            first_device = Some(started.elapsed());
+                            if self.options.stop_on_first {
+                                info!("Stopping scan at the first match");
+                                break;
+                            }
                         }
                     }
                 }
@@ -237,17 +504,48 @@ impl BleScanner {
         if let Ok(cached_addrs) = adapter.device_addresses().await {
             debug!("Checking {} cached devices", cached_addrs.len());
             for addr in cached_addrs {
-                if !discovered_map.contains_key(&addr) {
+                if self.options.stop_on_first && !discovered_map.is_empty() {
+                    break;
+                }
+                if !processed.contains(&addr) {
                     if let Ok(device) = adapter.device(addr) {
-                        self.process_device(&device, &mut discovered_map, callback.as_ref())
-                            .await;
+                        self.process_device(
+                            &device,
+                            &mut discovered_map,
+                            &mut processed,
+                            &mut capture,
+                            &mut learner,
+                            callback.as_ref(),
+                        )
+                        .await;
                     }
                 }
             }
         }
 
+        if let (Some(capture), Some(path)) = (capture.as_mut(), &self.options.capture) {
+            if let Err(e) = capture.flush() {
+                warn!("Failed to write capture file {}: {}", path.display(), e);
+            }
+            info!(
+                "Captured {} advertisements to {}",
+                capture.records(),
+                path.display()
+            );
+        }
+
+        if let Some(learner) = &learner {
+            match learner.save() {
+                Ok(()) => debug!("Learned {} advertisement shapes", learner.shapes.len()),
+                Err(e) => warn!("Failed to save learned shapes: {:#}", e),
+            }
+        }
+
         info!("Scan complete. Found {} devices.", discovered_map.len());
-        Ok(discovered_map.into_values().collect())
+        crate::metrics::scan_finished(first_device, discovered_map.len());
+        let mut devices: Vec<_> = discovered_map.into_values().collect();
+        rank_devices(&mut devices);
+        Ok(devices)
     }
 
     async fn init_adapter(&self) -> bluer::Result<Adapter> {
@@ -261,22 +559,54 @@ impl BleScanner {
     async fn process_device(
         &self,
         device: &Device,
-        discovered_map: &mut HashMap<bluer::Address, DiscoveredDevice>,
+        discovered_map: &mut HashMap<String, DiscoveredDevice>,
+        processed: &mut HashSet<bluer::Address>,
+        capture: &mut Option<AdvCapture>,
+        learner: &mut Option<ShapeLearner>,
         callback: Option<&Arc<dyn ScanCallback>>,
     ) {
         let addr = device.address();
         // Skip if already processed
-        if discovered_map.contains_key(&addr) {
+        if !processed.insert(addr) {
             return;
         }
 
-        match self.parse_device(device).await {
+        let parsed = self.parse_device(device).await;
+        if let Some(capture) = capture {
+            let matched = matches!(parsed, Ok(Some(_)));
+            if let Err(e) = self.capture_device(device, matched, capture).await {
+                warn!("Failed to capture advertisement of {}: {:#}", addr, e);
+            }
+        }
+        if let (Some(learner), Ok(None)) = (learner.as_mut(), &parsed) {
+            if let Err(e) = Self::learn_device(device, learner).await {
+                debug!("Failed to learn advertisement of {}: {:#}", addr, e);
+            }
+        }
+
+        match parsed {
             Ok(Some(dev)) => {
+                if !self.options.matches(&dev) {
+                    debug!("Filtered out device: {} ({})", dev.name, addr);
+                    return;
+                }
+                let key = identity_key(&dev).unwrap_or_else(|| dev.address.clone());
+                // BlueZ keeps retired addresses cached; those entries have no RSSI
+                if let Some(existing) = discovered_map.get(&key)
+                    && existing.rssi.is_some()
+                    && dev.rssi.is_none()
+                {
+                    debug!("Ignoring stale address {} of {}", addr, dev.name);
+                    return;
+                }
+                if let Some(previous) = self.addresses.lock().unwrap().observe(&dev) {
+                    info!("{} moved from {} to {}", dev.name, previous, addr);
+                }
                 debug!("Matched CatShare device: {} ({})", dev.name, addr);
                 if let Some(cb) = callback {
                     cb.on_device_found(dev.clone()).await;
                 }
-                discovered_map.insert(addr, dev);
+                discovered_map.insert(key, dev);
             }
             Ok(None) => { /* Not a target device */ }
             Err(e) => {
@@ -285,6 +615,66 @@ impl BleScanner {
         }
     }
 
+    /// Record the raw advertisement of `device` if it matched or nearly matched.
+    async fn capture_device(
+        &self,
+        device: &Device,
+        matched: bool,
+        capture: &mut AdvCapture,
+    ) -> anyhow::Result<()> {
+        let uuids = device.uuids().await?.unwrap_or_default();
+        let service_data = device.service_data().await?.unwrap_or_default();
+        let manuf_data = device.manufacturer_data().await?.unwrap_or_default();
+        let verdict = if matched {
+            CaptureVerdict::Matched
+        } else if is_near_miss(&uuids, &service_data, &manuf_data) {
+            CaptureVerdict::NearMiss
+        } else {
+            return Ok(());
+        };
+        let record = AdvRecord::new(
+            device.address().to_string(),
+            device.name().await?,
+            device.rssi().await?,
+            verdict,
+            &uuids,
+            &service_data,
+            &manuf_data,
+        );
+        capture.record(&record)?;
+        Ok(())
+    }
+
+    /// Count the payload shapes of an unrecognized advertisement that nearly matched.
+    async fn learn_device(device: &Device, learner: &mut ShapeLearner) -> anyhow::Result<()> {
+        let uuids = device.uuids().await?.unwrap_or_default();
+        let service_data = device.service_data().await?.unwrap_or_default();
+        let manuf_data = device.manufacturer_data().await?.unwrap_or_default();
+        if is_near_miss(&uuids, &service_data, &manuf_data) {
+            learner.observe(&uuids, &service_data, &manuf_data);
+        }
+        Ok(())
+    }
+
+    /// Register an advertisement monitor matching CatShare advertisements.
+    async fn passive_monitor(
+        &self,
+        adapter: &Adapter,
+    ) -> anyhow::Result<(MonitorManager, MonitorHandle)> {
+        let manager = adapter.monitor().await.context(
+            "Advertisement monitor unavailable (enable Experimental in /etc/bluetooth/main.conf)",
+        )?;
+        let handle = manager
+            .register(Monitor {
+                monitor_type: MonitorType::OrPatterns,
+                rssi_sampling_period: Some(RssiSamplingPeriod::First),
+                patterns: Some(passive_patterns()),
+                ..Default::default()
+            })
+            .await?;
+        Ok((manager, handle))
+    }
+
     async fn parse_device(&self, device: &Device) -> anyhow::Result<Option<DiscoveredDevice>> {
         let uuids = device.uuids().await?.unwrap_or_default();
         let service_data = device.service_data().await?.unwrap_or_default();
@@ -300,11 +690,11 @@ impl BleScanner {
         let name = self.resolve_device_name(device, &manuf_data).await?;
 
         // 3. Extract Metadata (Sender ID, Brand, etc.)
-        let (sender_id, brand_id, supports_5ghz) =
+        let (sender_id, brand_id, supports_5ghz, busy, supports_wpa3) =
             self.parse_service_metadata(&service_data, &manuf_data);
 
         let brand = brand_id
-            .map(|id| Brand::from(id).to_string())
+            .map(get_vendor_name)
             .unwrap_or_else(|| "Unknown".to_string());
 
         let rssi = device.rssi().await?;
@@ -317,6 +707,9 @@ impl BleScanner {
             brand_id,
             rssi,
             supports_5ghz,
+            busy,
+            supports_wpa3,
+            lan_endpoint: None,
         }))
     }
 
@@ -396,10 +789,12 @@ impl BleScanner {
         &self,
         service_data: &HashMap<Uuid, Vec<u8>>,
         manuf_data: &HashMap<u16, Vec<u8>>,
-    ) -> (String, Option<i16>, bool) {
-        let mut sender_id = "0000".to_string();
+    ) -> (String, Option<i16>, bool, bool, bool) {
+        let mut sender_id = NO_SENDER_ID.to_string();
         let mut brand_id = None;
         let mut supports_5ghz = false;
+        let mut busy = false;
+        let mut supports_wpa3 = false;
 
         for (uuid, data) in service_data {
             match data.len() {
@@ -412,11 +807,10 @@ impl BleScanner {
                 }
                 // 6-byte data: often contains capability flags in UUID + data
                 6 => {
-                    let u_bytes = uuid.as_bytes();
-                    if u_bytes[0..2] == [0, 0] {
-                        supports_5ghz = u_bytes[2] == 1;
-                        // Brand ID is often in the UUID byte 3
-                        brand_id = Some(u_bytes[3] as i16);
+                    if let Some((capabilities, id)) = parse_ident_uuid(uuid) {
+                        (supports_5ghz, busy, supports_wpa3) = capability_flags(capabilities);
+                        // Brand ID is in the UUID byte 3
+                        brand_id = Some(id as i16);
                     }
                 }
                 _ => {}
@@ -434,6 +828,134 @@ impl BleScanner {
             }
         }
 
-        (sender_id, brand_id, supports_5ghz)
+        (sender_id, brand_id, supports_5ghz, busy, supports_wpa3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, rssi: Option<i16>, brand_id: i16) -> DiscoveredDevice {
+        DiscoveredDevice {
+            name: name.to_string(),
+            address: format!("AA:BB:CC:DD:EE:{:02X}", name.len()),
+            sender_id: format!("{:04x}", name.len()),
+            brand: get_vendor_name(brand_id),
+            brand_id: Some(brand_id),
+            rssi,
+            supports_5ghz: true,
+            busy: false,
+            supports_wpa3: false,
+            lan_endpoint: None,
+        }
+    }
+
+    #[test]
+    fn test_scan_options_filters() {
+        let xiaomi = device("Redmi K70", Some(-50), 30);
+        let mut far_vivo = device("vivo X100", Some(-90), 20);
+        far_vivo.supports_5ghz = false;
+
+        assert!(ScanOptions::default().matches(&far_vivo));
+
+        let min_rssi = ScanOptions {
+            min_rssi: Some(-70),
+            ..Default::default()
+        };
+        assert!(min_rssi.matches(&xiaomi));
+        assert!(!min_rssi.matches(&far_vivo));
+        assert!(min_rssi.matches(&device("LAN", None, 200)));
+
+        let brands = ScanOptions {
+            brands: vec![BrandId::Vivo],
+            ..Default::default()
+        };
+        assert!(!brands.matches(&xiaomi));
+        assert!(brands.matches(&far_vivo));
+
+        let require_5ghz = ScanOptions {
+            require_5ghz: true,
+            ..Default::default()
+        };
+        assert!(require_5ghz.matches(&xiaomi));
+        assert!(!require_5ghz.matches(&far_vivo));
+    }
+
+    /// Signed (Java byte) and unsigned forms of an ID map to the same brand.
+    #[test]
+    fn test_device_brand_id_signed() {
+        assert_eq!(device("ROG", None, -96).brand_id(), BrandId::ROG);
+        assert_eq!(device("ROG", None, 160).brand_id(), BrandId::ROG);
+        assert_eq!(device("Mi", None, 0x038F).brand_id(), BrandId::Unknown);
+    }
+
+    #[test]
+    fn test_rank_devices() {
+        let mut busy = device("Busy", Some(-30), 30);
+        busy.busy = true;
+        let mut devices = vec![
+            device("Far", Some(-80), 30),
+            device("LAN", None, 200),
+            busy,
+            device("Near", Some(-40), 30),
+            device("Also far", Some(-80), 30),
+        ];
+        rank_devices(&mut devices);
+        let names: Vec<_> = devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["Near", "Also far", "Far", "LAN", "Busy"]);
+    }
+
+    #[test]
+    fn test_passive_patterns() {
+        let patterns = passive_patterns();
+        assert_eq!(patterns.len(), 14);
+        assert!(
+            patterns
+                .iter()
+                .any(|p| p.data_type == AD_SERVICE_DATA_UUID16 && p.content == [0x31, 0x33])
+        );
+        assert!(
+            patterns
+                .iter()
+                .any(|p| p.data_type == AD_MANUFACTURER_DATA && p.content == [0x8f, 0x03])
+        );
+    }
+
+    #[test]
+    fn test_address_rotation() {
+        let first = device("Redmi K70", Some(-50), 30);
+        let mut rotated = first.clone();
+        rotated.address = "11:22:33:44:55:66".to_string();
+        assert!(first.is_same_device(&rotated));
+        assert!(!first.is_same_device(&device("Redmi K70 Pro", Some(-50), 30)));
+
+        let mut tracker = AddressTracker::default();
+        assert_eq!(tracker.observe(&first), None);
+        assert_eq!(tracker.observe(&first), None);
+        assert_eq!(tracker.resolve(&first.address), None);
+
+        assert_eq!(tracker.observe(&rotated), Some(first.address.clone()));
+        assert_eq!(tracker.resolve(&first.address), Some("11:22:33:44:55:66"));
+
+        // Rotating back retires the newer address instead
+        assert_eq!(
+            tracker.observe(&first),
+            Some("11:22:33:44:55:66".to_string())
+        );
+        assert_eq!(tracker.resolve(&first.address), None);
+        assert_eq!(
+            tracker.resolve(&rotated.address),
+            Some(first.address.as_str())
+        );
+
+        // Devices without a sender ID are never merged
+        let mut anonymous = first.clone();
+        anonymous.sender_id = NO_SENDER_ID.to_string();
+        let mut other = anonymous.clone();
+        other.address = "11:22:33:44:55:77".to_string();
+        assert!(!anonymous.is_same_device(&other));
+        assert_eq!(tracker.observe(&anonymous), None);
+        assert_eq!(tracker.observe(&other), None);
     }
 }
@@ -6,7 +6,10 @@
 //!
 //! - 发布 BLE 广播（与 CatShare 广播格式兼容）
 //! - 提供 GATT 服务包含 STATUS 和 P2P 特征
+//! - STATUS 特征支持 notify/indicate，状态变化时主动推送给已订阅的发送端
 //! - 处理发送端的 P2P 信息写入
+//! - P2P 特征支持 notify：反向模式下接收端建组后经 [`P2pNotifier`] 把自己的 P2pInfo 推送给发送端
+//! - 上报中心设备（发送端）的连接与断开
 //!
 //! # 广播数据格式
 //!
@@ -14,31 +17,43 @@
 //! - Service UUID: `00003331-0000-1000-8000-008123456789`
 //! - Service Data (0x01FF): 6 字节身份数据
 //! - Scan Response (0xFFFF): 27 字节，包含设备名称和协议版本
+//!
+//! 广播间隔与占空比由 [`LegacyAdvConfig`] 控制。
+//!
+//! 传输期间通过 [`GattServerHandle::set_state`] 切换为忙碌：STATUS 特征返回
+//! `state = 1`，广播能力字节带上 [`CAPABILITY_BUSY`]，其他发送端扫描时即可看到。
+//!
+//! 订阅了 STATUS 的发送端（CatShare 手机会这样做）在状态切换时收到新的 DeviceInfo；
+//! 接受/拒绝传输请求的结果经 [`StatusNotifier`] 只推送、不改变读取到的值。
 
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
 
+use crate::ble::brand::{BrandPreset, ident_uuid};
+use crate::ble::pairing::PairingAgent;
 use crate::ble::{
-    ADV_SERVICE_UUID, DeviceInfo, MAIN_SERVICE_UUID, P2P_CHAR_UUID, STATUS_CHAR_UUID,
+    ADV_SERVICE_UUID, CAPABILITY_5GHZ, CAPABILITY_BUSY, CAPABILITY_WPA3, DeviceInfo,
+    LegacyAdvConfig, MAIN_SERVICE_UUID, P2P_CHAR_UUID, ReceiverState, STATUS_CHAR_UUID,
 };
 use crate::config::{AppSettings, BrandId};
-use crate::crypto::BleSecurityPersistent;
+use crate::crypto::{BleSecurityPersistent, identity};
 use crate::wifi::P2pInfo;
 use bluer::{
+    Address, DeviceEvent, DeviceProperty,
     adv::Advertisement,
     gatt::local::{
-        Application, Characteristic, CharacteristicRead, CharacteristicWrite,
-        CharacteristicWriteMethod, ReqError, Service,
+        Application, Characteristic, CharacteristicNotify, CharacteristicNotifyMethod,
+        CharacteristicRead, CharacteristicWrite, CharacteristicWriteMethod, ReqError, Service,
     },
 };
-use futures_util::FutureExt;
-use std::collections::BTreeSet;
+use futures_util::{FutureExt, StreamExt};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
-use tokio::sync::{Mutex, mpsc};
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
 
-/// 从随机数据生成 sender ID
-fn sender_id_from_random_data(random_data: &[u8; 2]) -> String {
-    format!("{:02x}{:02x}", random_data[0], random_data[1])
-}
+/// 注销旧广播后等待多久再注册新广播（部分控制器只有一个广播实例）
+const READVERTISE_DELAY: Duration = Duration::from_millis(100);
 
 /// P2P 信息接收事件
 #[derive(Debug, Clone)]
@@ -47,6 +62,18 @@ pub struct P2pReceiveEvent {
     pub sender_public_key: Option<String>,
 }
 
+/// 中心设备（发送端）连接事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GattConnectionEvent {
+    /// 中心设备连接
+    ///
+    /// BlueZ 不会直接通知 GATT 应用有新连接，因此在该设备第一次读写特征时上报，
+    /// 通常是发送端读取 STATUS 特征的时候，早于 P2P 信息写入。
+    Connected { address: String, mtu: u16 },
+    /// 中心设备断开
+    Disconnected { address: String },
+}
+
 /// GATT Server 状态
 pub struct GattServerState {
     pub device_info: DeviceInfo,
@@ -69,6 +96,45 @@ impl GattServerState {
         self.device_info_bytes = serde_json::to_vec(&self.device_info)?;
         Ok(())
     }
+
+    pub fn set_state(&mut self, state: ReceiverState) -> anyhow::Result<()> {
+        self.device_info.state = state.code();
+        self.device_info_bytes = serde_json::to_vec(&self.device_info)?;
+        Ok(())
+    }
+
+    /// 当前 DeviceInfo 换成 `state` 后的 JSON（用于 STATUS 通知）
+    pub fn encode_with_state(&self, state: ReceiverState) -> anyhow::Result<Vec<u8>> {
+        let mut device_info = self.device_info.clone();
+        device_info.state = state.code();
+        Ok(serde_json::to_vec(&device_info)?)
+    }
+}
+
+/// 向订阅了 STATUS 特征的发送端推送状态
+#[derive(Clone)]
+pub struct StatusNotifier {
+    tx: broadcast::Sender<ReceiverState>,
+}
+
+impl StatusNotifier {
+    /// 推送 `state`；没有订阅者时什么也不做
+    pub fn notify(&self, state: ReceiverState) {
+        let _ = self.tx.send(state);
+    }
+}
+
+/// 向订阅了 P2P 特征的发送端推送数据（反向模式下接收端的 P2pInfo）
+#[derive(Clone)]
+pub struct P2pNotifier {
+    tx: broadcast::Sender<Vec<u8>>,
+}
+
+impl P2pNotifier {
+    /// 推送 `data`，没有订阅者时返回 false
+    pub fn notify(&self, data: Vec<u8>) -> bool {
+        self.tx.send(data).is_ok()
+    }
 }
 
 /// GATT Server
@@ -76,7 +142,7 @@ pub struct GattServer {
     state: Arc<Mutex<GattServerState>>,
     p2p_tx: mpsc::Sender<P2pReceiveEvent>,
     p2p_rx: Option<mpsc::Receiver<P2pReceiveEvent>>,
-    /// 随机数据 (2 bytes)，用于 sender ID 和广播身份
+    /// 广播中的 "随机数据" (2 bytes)，由公钥派生，即 sender ID 的原始字节
     random_data: [u8; 2],
     sender_id: String,
     device_name: String,
@@ -85,6 +151,12 @@ pub struct GattServer {
     brand_id: BrandId,
     /// 是否支持 5GHz
     supports_5ghz: bool,
+    /// 是否能接入 WPA3-SAE 热点
+    supports_wpa3: bool,
+    /// 广播间隔与占空比
+    adv_config: LegacyAdvConfig,
+    /// 配对代理，服务运行期间注册为默认 Agent
+    pairing: Option<PairingAgent>,
 }
 
 impl GattServer {
@@ -94,12 +166,12 @@ impl GattServer {
         device_name: String,
         public_key: String,
     ) -> anyhow::Result<Self> {
+        // sender ID 由公钥确定，身份密钥不变时对端看到的 ID 也不变
+        let random_data = identity::sender_id_bytes(&public_key);
+        let sender_id = identity::sender_id(&public_key);
         let state = GattServerState::new(mac_address, public_key)?;
 
         let (p2p_tx, p2p_rx) = mpsc::channel(16);
-        // 生成随机数据 (2 bytes)，在整个 GATT Server 生命周期内保持不变
-        let random_data: [u8; 2] = rand::random();
-        let sender_id = sender_id_from_random_data(&random_data);
 
         Ok(Self {
             state: Arc::new(Mutex::new(state)),
@@ -111,6 +183,9 @@ impl GattServer {
             security: None,
             brand_id: BrandId::Linux,
             supports_5ghz: true,
+            supports_wpa3: false,
+            adv_config: LegacyAdvConfig::default(),
+            pairing: None,
         })
     }
 
@@ -122,7 +197,16 @@ impl GattServer {
     ) -> anyhow::Result<Self> {
         let mut server = Self::new(mac_address, settings.device_name.clone(), public_key)?;
         server.brand_id = settings.brand_id;
+        let preset = BrandPreset::for_brand(settings.brand_id);
+        if !preset.recognized_by_android() {
+            warn!(
+                "Brand {} (id {}) is not recognized by CatShare on Android, phones will show this device as unknown",
+                preset.name(),
+                preset.id
+            );
+        }
         server.supports_5ghz = settings.supports_5ghz;
+        server.adv_config = LegacyAdvConfig::from_profile(settings.power_profile);
         Ok(server)
     }
 
@@ -144,6 +228,27 @@ impl GattServer {
         self
     }
 
+    /// 设置 WPA3-SAE 支持
+    pub fn with_wpa3_support(mut self, supports_wpa3: bool) -> Self {
+        self.supports_wpa3 = supports_wpa3;
+        self
+    }
+
+    /// 服务运行期间用 `agent` 应答发送端发起的配对
+    ///
+    /// 要求配对后才能写特征的发送端需要接收端有默认 Agent；桌面环境通常已有，
+    /// 无界面运行（守护进程）时没有 Agent 会导致配对失败。
+    pub fn with_pairing(mut self, agent: PairingAgent) -> Self {
+        self.pairing = Some(agent);
+        self
+    }
+
+    /// 设置广播间隔与占空比
+    pub fn with_adv_config(mut self, adv_config: LegacyAdvConfig) -> Self {
+        self.adv_config = adv_config;
+        self
+    }
+
     /// 获取 sender ID
     pub fn sender_id(&self) -> &str {
         &self.sender_id
@@ -155,6 +260,7 @@ impl GattServer {
     }
 
     /// 启动 GATT 服务
+    #[tracing::instrument(skip_all)]
     pub async fn start(&self) -> anyhow::Result<GattServerHandle> {
         debug!("Initializing BLE session...");
         let session = bluer::Session::new().await?;
@@ -166,17 +272,37 @@ impl GattServer {
         debug!("Powering on adapter: {}", adapter_name);
         adapter.set_powered(true).await?;
 
+        let agent = match &self.pairing {
+            Some(agent) => match agent.clone().as_default().register(&session).await {
+                Ok(handle) => Some(handle),
+                Err(e) => {
+                    warn!("Failed to register pairing agent: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
         let state = self.state.clone();
         let p2p_tx = self.p2p_tx.clone();
+        let (connection_tx, connection_rx) = mpsc::channel(16);
+        let connections = Arc::new(ConnectionTracker::new(adapter.clone(), connection_tx));
+
+        let (status_tx, _) = broadcast::channel(8);
+        let (p2p_notify_tx, _) = broadcast::channel(4);
 
-        // STATUS 特征 - 只读，返回 DeviceInfo JSON
+        // STATUS 特征 - 读取返回 DeviceInfo JSON，订阅后推送状态变化
         let state_for_read = state.clone();
+        let state_for_notify = state.clone();
+        let status_tx_for_notify = status_tx.clone();
+        let connections_for_read = connections.clone();
         let status_char = Characteristic {
             uuid: STATUS_CHAR_UUID,
             read: Some(CharacteristicRead {
                 read: true,
                 fun: Box::new(move |req| {
                     let state = state_for_read.clone();
+                    connections_for_read.seen(req.device_address, req.mtu);
                     async move {
                         let s = state.lock().await;
                         let offset = req.offset as usize;
@@ -194,18 +320,34 @@ impl GattServer {
                 }),
                 ..Default::default()
             }),
+            notify: Some(CharacteristicNotify {
+                notify: true,
+                indicate: true,
+                method: CharacteristicNotifyMethod::Fun(Box::new(move |notifier| {
+                    let state = state_for_notify.clone();
+                    let updates = status_tx_for_notify.subscribe();
+                    async move {
+                        tokio::spawn(push_status(notifier, state, updates));
+                    }
+                    .boxed()
+                })),
+                ..Default::default()
+            }),
             ..Default::default()
         };
 
-        // P2P 特征 - 可写，接收 P2pInfo JSON
+        // P2P 特征 - 可写，接收 P2pInfo JSON；订阅后推送本机建组的 P2pInfo（反向模式）
+        let p2p_notify_tx_clone = p2p_notify_tx.clone();
         let p2p_tx_clone = p2p_tx.clone();
         let security_clone = self.security.clone();
+        let connections_for_write = connections.clone();
         let p2p_char = Characteristic {
             uuid: P2P_CHAR_UUID,
             write: Some(CharacteristicWrite {
                 write: true,
                 write_without_response: true,
-                method: CharacteristicWriteMethod::Fun(Box::new(move |data, _req| {
+                method: CharacteristicWriteMethod::Fun(Box::new(move |data, req| {
+                    connections_for_write.seen(req.device_address, req.mtu);
                     let p2p_tx = p2p_tx_clone.clone();
                     let security = security_clone.clone();
                     async move {
@@ -224,6 +366,17 @@ impl GattServer {
                 })),
                 ..Default::default()
             }),
+            notify: Some(CharacteristicNotify {
+                notify: true,
+                method: CharacteristicNotifyMethod::Fun(Box::new(move |notifier| {
+                    let updates = p2p_notify_tx_clone.subscribe();
+                    async move {
+                        tokio::spawn(push_p2p(notifier, updates));
+                    }
+                    .boxed()
+                })),
+                ..Default::default()
+            }),
             ..Default::default()
         };
 
@@ -246,28 +399,81 @@ impl GattServer {
         debug!("GATT application registered successfully");
 
         // 构造 Legacy BLE 广播
-        // 关键: secondary_channel: None 强制使用 Legacy Advertising PDUs
-        let random_data = self.random_data;
+        let payload = self.build_adv_payload();
+        let adv_config = self.adv_config;
 
-        let mut service_uuids = BTreeSet::new();
-        service_uuids.insert(ADV_SERVICE_UUID);
+        debug!(
+            "Starting Legacy BLE advertisement: service={}, ident=0x{:04x}, name='{}', config={:?}",
+            ADV_SERVICE_UUID, payload.capability_short, self.device_name, adv_config
+        );
+        // 先同步注册一次，尽早暴露 BlueZ 配置错误；之后由后台任务维持
+        let first = adapter
+            .advertise(build_advertisement(&payload, &adv_config))
+            .await?;
+        match adv_config.duty_cycle {
+            None => debug!("Legacy BLE advertisement started successfully"),
+            Some(duty) => debug!(
+                "Legacy BLE advertisement started with duty cycle {:?} on / {:?} off",
+                duty.on, duty.off
+            ),
+        }
+        let (payload_tx, payload_rx) = watch::channel(payload);
+        let advertising = Advertising {
+            payload: payload_tx,
+            task: tokio::spawn(keep_advertising(
+                adapter.clone(),
+                adv_config,
+                payload_rx,
+                first,
+            )),
+        };
 
-        // ========== 主广播包数据 (31 bytes max) ==========
-        // 构造身份数据 (Service Data, 约 10 bytes)
-        let flag_5ghz: u8 = if self.supports_5ghz { 0x01 } else { 0x00 };
-        let brand = self.brand_id.id();
-        let capability_short = ((flag_5ghz as u16) << 8) | (brand as u16);
-        let ident_uuid = uuid::Uuid::from_u128(
-            ((capability_short as u128) << 96) | 0x0000_1000_8000_0080_5f9b_34fb_u128,
+        info!(
+            "GATT Server started, sender_id={}, device_name='{}'",
+            self.sender_id, self.device_name
         );
 
+        Ok(GattServerHandle {
+            state,
+            status: StatusNotifier { tx: status_tx },
+            p2p: P2pNotifier { tx: p2p_notify_tx },
+            connections,
+            connection_rx: Some(connection_rx),
+            advertising,
+            _app_handle,
+            _agent: agent,
+            _session: session,
+        })
+    }
+}
+
+impl GattServer {
+    /// 构造广播载荷
+    ///
+    /// 主广播包 (31 bytes max) 包含身份 Service Data，
+    /// 扫描响应包含 0xFFFF 名称 Service Data。
+    fn build_adv_payload(&self) -> AdvPayload {
+        let random_data = self.random_data;
+
+        // ========== 主广播包数据 (31 bytes max) ==========
+        // 构造身份数据 (Service Data, 约 10 bytes)，UUID 中带能力字节和厂商 ID
+        let flag_5ghz = if self.supports_5ghz {
+            CAPABILITY_5GHZ
+        } else {
+            0x00
+        };
+        let flag_wpa3 = if self.supports_wpa3 {
+            CAPABILITY_WPA3
+        } else {
+            0x00
+        };
+        let brand = BrandPreset::for_brand(self.brand_id).id;
+        let capability_short = (((flag_5ghz | flag_wpa3) as u16) << 8) | (brand as u16);
+
         let mut ident_payload = vec![0u8; 6];
         ident_payload[0] = random_data[0];
         ident_payload[1] = random_data[1];
 
-        let mut service_data = std::collections::BTreeMap::new();
-        service_data.insert(ident_uuid, ident_payload);
-
         // ========== 扫描响应包数据 (31 bytes max) ==========
         // 构造 Name Service Data (27 bytes)
         // CatShare 格式:
@@ -290,49 +496,267 @@ impl GattServer {
 
         // Name Service Data 使用 UUID 0xFFFF (标准蓝牙基底)
         let name_uuid = uuid::Uuid::from_u128(0x0000_ffff_0000_1000_8000_0080_5f9b_34fb_u128);
-        let mut scan_response_service_data = std::collections::BTreeMap::new();
+        let mut scan_response_service_data = BTreeMap::new();
         scan_response_service_data.insert(name_uuid, name_payload);
 
-        let adv = Advertisement {
-            advertisement_type: bluer::adv::Type::Peripheral,
-            service_uuids,
-            service_data,
-            // ⭐ 使用 scan_response_service_data 而不是 local_name
-            // 这需要 BlueZ experimental 功能 (Experimental = true in /etc/bluetooth/main.conf)
+        AdvPayload {
+            capability_short,
+            ident_payload,
             scan_response_service_data,
-            // 不再使用 local_name，因为 CatShare 不读取它
-            // local_name: Some(self.device_name.clone()),
-            discoverable: Some(true),
-            // 关键: secondary_channel: None 强制 Legacy Advertising
-            // 不设置辅助信道 = 使用主信道 = Legacy PDUs
-            secondary_channel: None,
-            ..Default::default()
+        }
+    }
+}
+
+/// 广播载荷（占空比模式下每个周期、忙碌状态变化时都要重新注册广播）
+#[derive(Clone)]
+struct AdvPayload {
+    /// 高字节为能力字节，低字节为厂商 ID
+    capability_short: u16,
+    ident_payload: Vec<u8>,
+    scan_response_service_data: BTreeMap<uuid::Uuid, Vec<u8>>,
+}
+
+impl AdvPayload {
+    /// 身份 Service Data 的 UUID: 0000XXYY-0000-1000-8000-00805f9b34fb
+    fn ident_uuid(&self) -> uuid::Uuid {
+        let [capabilities, brand] = self.capability_short.to_be_bytes();
+        ident_uuid(capabilities, brand)
+    }
+
+    /// 设置忙碌位，返回是否有变化
+    fn set_busy(&mut self, busy: bool) -> bool {
+        let before = self.capability_short;
+        let bit = (CAPABILITY_BUSY as u16) << 8;
+        if busy {
+            self.capability_short |= bit;
+        } else {
+            self.capability_short &= !bit;
+        }
+        self.capability_short != before
+    }
+}
+
+/// 构造 Legacy BLE 广播
+///
+/// 关键: secondary_channel: None 强制使用 Legacy Advertising PDUs
+fn build_advertisement(payload: &AdvPayload, config: &LegacyAdvConfig) -> Advertisement {
+    Advertisement {
+        advertisement_type: bluer::adv::Type::Peripheral,
+        service_uuids: BTreeSet::from([ADV_SERVICE_UUID]),
+        service_data: BTreeMap::from([(payload.ident_uuid(), payload.ident_payload.clone())]),
+        // ⭐ 使用 scan_response_service_data 而不是 local_name
+        // 这需要 BlueZ experimental 功能 (Experimental = true in /etc/bluetooth/main.conf)
+        scan_response_service_data: payload.scan_response_service_data.clone(),
+        // 不再使用 local_name，因为 CatShare 不读取它
+        // local_name: Some(self.device_name.clone()),
+        discoverable: Some(true),
+        // 关键: secondary_channel: None 强制 Legacy Advertising
+        // 不设置辅助信道 = 使用主信道 = Legacy PDUs
+        secondary_channel: None,
+        min_interval: config.min_interval,
+        max_interval: config.max_interval,
+        ..Default::default()
+    }
+}
+
+/// 广播生命周期，由后台任务持有广播句柄，drop 时注销
+struct Advertising {
+    payload: watch::Sender<AdvPayload>,
+    task: JoinHandle<()>,
+}
+
+impl Advertising {
+    fn set_busy(&self, busy: bool) {
+        self.payload
+            .send_if_modified(|payload| payload.set_busy(busy));
+    }
+}
+
+impl Drop for Advertising {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 维持广播：载荷变化时重新注册；占空比模式下按周期注销/注册
+async fn keep_advertising(
+    adapter: bluer::Adapter,
+    config: LegacyAdvConfig,
+    mut payload: watch::Receiver<AdvPayload>,
+    first: bluer::adv::AdvertisementHandle,
+) {
+    let mut handle = Some(first);
+    loop {
+        let alive = match config.duty_cycle {
+            None => payload.changed().await.is_ok(),
+            Some(duty) => tokio::select! {
+                changed = payload.changed() => changed.is_ok(),
+                _ = tokio::time::sleep(duty.on) => {
+                    drop(handle.take());
+                    trace!("Advertisement paused (duty cycle)");
+                    tokio::time::sleep(duty.off).await;
+                    true
+                }
+            },
         };
+        if !alive {
+            return;
+        }
+        if handle.take().is_some() {
+            tokio::time::sleep(READVERTISE_DELAY).await;
+        }
 
-        debug!(
-            "Starting Legacy BLE advertisement: service={}, ident=0x{:04x}, name='{}'",
-            ADV_SERVICE_UUID, capability_short, self.device_name
-        );
-        let adv_handle = adapter.advertise(adv).await?;
-        debug!("Legacy BLE advertisement started successfully");
+        let current = payload.borrow_and_update().clone();
+        match adapter
+            .advertise(build_advertisement(&current, &config))
+            .await
+        {
+            Ok(h) => {
+                trace!(
+                    "Advertisement registered, ident=0x{:04x}",
+                    current.capability_short
+                );
+                handle = Some(h);
+            }
+            Err(e) => {
+                error!("Failed to register advertisement: {}", e);
+            }
+        }
+    }
+}
 
-        info!(
-            "GATT Server started, sender_id={}, device_name='{}'",
-            self.sender_id, self.device_name
-        );
+/// 把状态变化推送给一个 STATUS 订阅者，直到对方取消订阅
+async fn push_status(
+    mut notifier: bluer::gatt::local::CharacteristicNotifier,
+    state: Arc<Mutex<GattServerState>>,
+    mut updates: broadcast::Receiver<ReceiverState>,
+) {
+    debug!("STATUS subscribed (indicate={})", notifier.confirming());
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if notifier.is_stopped() {
+            break;
+        }
+        let value = match state.lock().await.encode_with_state(update) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Failed to encode STATUS notification: {}", e);
+                continue;
+            }
+        };
+        if let Err(e) = notifier.notify(value).await {
+            debug!("STATUS notification failed: {}", e);
+            break;
+        }
+        trace!("STATUS notified: {:?}", update);
+    }
+    debug!("STATUS unsubscribed");
+}
 
-        Ok(GattServerHandle {
-            _adv_handle: adv_handle,
-            _app_handle,
-            _session: session,
-        })
+/// 把推送的数据转发给一个 P2P 特征的订阅者，直到对方取消订阅
+async fn push_p2p(
+    mut notifier: bluer::gatt::local::CharacteristicNotifier,
+    mut updates: broadcast::Receiver<Vec<u8>>,
+) {
+    debug!("P2P subscribed");
+    loop {
+        let value = match updates.recv().await {
+            Ok(value) => value,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if notifier.is_stopped() {
+            break;
+        }
+        if let Err(e) = notifier.notify(value).await {
+            debug!("P2P notification failed: {}", e);
+            break;
+        }
+        trace!("P2P info notified");
+    }
+    debug!("P2P unsubscribed");
+}
+
+/// 记录访问过 GATT 服务的中心设备，并在其断开时上报
+struct ConnectionTracker {
+    adapter: bluer::Adapter,
+    tx: mpsc::Sender<GattConnectionEvent>,
+    /// 每个已连接设备一个断开监视任务，任务结束即表示已断开
+    watchers: std::sync::Mutex<HashMap<Address, JoinHandle<()>>>,
+}
+
+impl ConnectionTracker {
+    fn new(adapter: bluer::Adapter, tx: mpsc::Sender<GattConnectionEvent>) -> Self {
+        Self {
+            adapter,
+            tx,
+            watchers: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 某设备访问了 GATT 特征；首次访问（或断开后重连）时上报连接事件
+    fn seen(&self, address: Address, mtu: u16) {
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.get(&address).is_some_and(|w| !w.is_finished()) {
+            return;
+        }
+
+        info!("Central {} connected (MTU {})", address, mtu);
+        let _ = self.tx.try_send(GattConnectionEvent::Connected {
+            address: address.to_string(),
+            mtu,
+        });
+
+        let adapter = self.adapter.clone();
+        let tx = self.tx.clone();
+        let watcher = tokio::spawn(async move {
+            if let Err(e) = wait_for_disconnect(&adapter, address).await {
+                debug!("Stopped watching central {}: {}", address, e);
+            }
+            info!("Central {} disconnected", address);
+            let _ = tx
+                .send(GattConnectionEvent::Disconnected {
+                    address: address.to_string(),
+                })
+                .await;
+        });
+        watchers.insert(address, watcher);
+    }
+
+    fn abort_all(&self) {
+        for (_, watcher) in self.watchers.lock().unwrap().drain() {
+            watcher.abort();
+        }
     }
 }
 
+/// 等待设备的 Connected 属性变为 false
+///
+/// BlueZ 会在临时设备断开后移除其对象，此时返回的错误同样视为已断开。
+async fn wait_for_disconnect(adapter: &bluer::Adapter, address: Address) -> bluer::Result<()> {
+    let device = adapter.device(address)?;
+    let mut events = device.events().await?;
+    // 订阅之前可能已经断开
+    if !device.is_connected().await? {
+        return Ok(());
+    }
+    while let Some(event) = events.next().await {
+        if let DeviceEvent::PropertyChanged(DeviceProperty::Connected(false)) = event {
+            break;
+        }
+    }
+    Ok(())
+}
+
 /// 处理 P2P 特征写入
 ///
 /// 如果提供 security 且 P2pInfo 包含发送端公钥 (key 字段)，则自动解密 SSID/PSK/MAC 字段。
-fn process_p2p_write(
+/// 解密后的字段不合法时（见 [`P2pInfo::validate`]）返回错误。
+pub(crate) fn process_p2p_write(
     data: &[u8],
     security: Option<&BleSecurityPersistent>,
 ) -> anyhow::Result<P2pReceiveEvent> {
@@ -349,6 +773,11 @@ fn process_p2p_write(
                 p2p_info.ssid = cipher.decrypt(&p2p_info.ssid).unwrap_or(p2p_info.ssid);
                 p2p_info.psk = cipher.decrypt(&p2p_info.psk).unwrap_or(p2p_info.psk);
                 p2p_info.mac = cipher.decrypt(&p2p_info.mac).unwrap_or(p2p_info.mac);
+                p2p_info.host = p2p_info.host.map(|h| cipher.decrypt(&h).unwrap_or(h));
+                p2p_info.auth_token = p2p_info.auth_token.map(|t| cipher.decrypt(&t).unwrap_or(t));
+                p2p_info.cert_sha256 = p2p_info
+                    .cert_sha256
+                    .map(|f| cipher.decrypt(&f).unwrap_or(f));
                 p2p_info.key = None; // 表示已解密
                 info!("Successfully decrypted P2P info");
             }
@@ -358,6 +787,9 @@ fn process_p2p_write(
         }
     }
 
+    // 解密失败时字段仍是密文，同样会在这里被拒绝
+    p2p_info.validate()?;
+
     info!(
         "Received P2P info from sender, ssid='{}', port={}, decrypted={}",
         p2p_info.ssid,
@@ -374,15 +806,52 @@ fn process_p2p_write(
 
 /// GATT Server Handle - 保持服务运行
 pub struct GattServerHandle {
-    _adv_handle: bluer::adv::AdvertisementHandle,
+    state: Arc<Mutex<GattServerState>>,
+    status: StatusNotifier,
+    p2p: P2pNotifier,
+    connections: Arc<ConnectionTracker>,
+    connection_rx: Option<mpsc::Receiver<GattConnectionEvent>>,
+    advertising: Advertising,
     _app_handle: bluer::gatt::local::ApplicationHandle,
+    _agent: Option<bluer::agent::AgentHandle>,
     _session: bluer::Session,
 }
 
 impl GattServerHandle {
+    /// 获取中心设备连接/断开事件通道
+    pub fn take_connection_events(&mut self) -> Option<mpsc::Receiver<GattConnectionEvent>> {
+        self.connection_rx.take()
+    }
+
+    /// 切换接收端状态：STATUS 特征中的 `state` 和广播中的忙碌位同时更新
+    pub async fn set_state(&self, state: ReceiverState) {
+        if let Err(e) = self.state.lock().await.set_state(state) {
+            warn!("Failed to update DeviceInfo state: {}", e);
+        }
+        self.advertising.set_busy(state == ReceiverState::Busy);
+        self.status.notify(state);
+        info!("Receiver state set to {:?}", state);
+    }
+
+    /// STATUS 通知的推送端，可交给传输流程上报接受/拒绝
+    pub fn status_notifier(&self) -> StatusNotifier {
+        self.status.clone()
+    }
+
+    /// P2P 特征通知的推送端，反向模式下用来回复建组请求
+    pub fn p2p_notifier(&self) -> P2pNotifier {
+        self.p2p.clone()
+    }
+
     /// 等待服务关闭信号
     pub async fn wait_for_shutdown(&self) {
         // 永远等待，直到被 drop
         std::future::pending::<()>().await;
     }
 }
+
+impl Drop for GattServerHandle {
+    fn drop(&mut self) {
+        self.connections.abort_all();
+    }
+}
@@ -0,0 +1,106 @@
+//! 取消与超时
+//!
+//! 扫描、BLE 握手、接入 WiFi 和文件传输都接受一个 [`CancellationToken`]
+//! （[`BleScanner::with_cancellation`](crate::BleScanner::with_cancellation)、
+//! [`Sender::with_cancellation`](crate::Sender::with_cancellation) 等）。
+//! 令牌取消后，正在等待的操作立即以 [`Interrupted::Cancelled`] 结束，
+//! 已经创建的热点、接入的网络由各组件自行清理；守护进程的 Stop 和界面上的取消按钮
+//! 只需调用 `token.cancel()`，不必直接中止任务。
+//!
+//! 没有自带超时的等待点使用这里的默认超时。
+
+use std::future::Future;
+use std::time::Duration;
+
+pub use tokio_util::sync::CancellationToken;
+
+/// BLE 连接、读写特征的默认超时
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 接入对端 WiFi（含 DHCP）的默认超时
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 操作被取消或超时
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum Interrupted {
+    #[error("operation cancelled")]
+    Cancelled,
+
+    #[error("operation timed out after {0:?}")]
+    TimedOut(Duration),
+}
+
+/// 等待 `fut` 完成，`token` 取消或超过 `timeout` 时提前返回
+///
+/// 取消优先于超时和 `fut` 本身的结果。
+pub async fn run<F: Future>(
+    token: &CancellationToken,
+    timeout: Option<Duration>,
+    fut: F,
+) -> Result<F::Output, Interrupted> {
+    let deadline = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(Interrupted::Cancelled),
+        output = fut => Ok(output),
+        _ = deadline => Err(Interrupted::TimedOut(timeout.unwrap_or_default())),
+    }
+}
+
+/// 错误链中是否有 [`Interrupted::Cancelled`]
+pub fn is_cancelled(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|e| e.downcast_ref::<Interrupted>() == Some(&Interrupted::Cancelled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_completes() {
+        let token = CancellationToken::new();
+        assert_eq!(run(&token, None, async { 7 }).await, Ok(7));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_interrupts_pending_future() {
+        let token = CancellationToken::new();
+        let child = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            child.cancel();
+        });
+        let result = run(&token, None, std::future::pending::<()>()).await;
+        assert_eq!(result, Err(Interrupted::Cancelled));
+
+        // 已取消的令牌不再等待
+        assert_eq!(
+            run(&token, None, async { 1 }).await,
+            Err(Interrupted::Cancelled)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let token = CancellationToken::new();
+        let timeout = Duration::from_millis(20);
+        let result = run(&token, Some(timeout), std::future::pending::<()>()).await;
+        assert_eq!(result, Err(Interrupted::TimedOut(timeout)));
+    }
+
+    #[test]
+    fn test_is_cancelled_searches_chain() {
+        let err = anyhow::Error::new(Interrupted::Cancelled).context("handshake failed");
+        assert!(is_cancelled(&err));
+        assert!(!is_cancelled(&anyhow::Error::new(Interrupted::TimedOut(
+            Duration::from_secs(1)
+        ))));
+    }
+}
@@ -2,10 +2,18 @@
 //!
 //! 提供设备名称、厂商 ID 等设置的存储和读取。
 
+use crate::autostart::AutostartMode;
+use crate::ble::BrandPreset;
+use crate::discovery::PresenceHook;
+use crate::power::DEFAULT_MIN_BATTERY_PERCENT;
+use crate::schedule::ScheduleRule;
+use crate::wifi::{BusyInterfacePolicy, CredentialPolicy};
+use crate::workflow::sender::DEFAULT_HOTSPOT_IDLE_TIMEOUT;
 use log::debug;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// 厂商 ID 枚举
 ///
@@ -142,8 +150,87 @@ impl BrandId {
     }
 }
 
+/// 电源策略
+///
+/// 决定 BLE 广播的间隔和占空比，见 [`crate::ble::LegacyAdvConfig::from_profile`]。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerProfile {
+    /// 持续高频广播，发现最快
+    #[default]
+    Performance,
+    /// 中等间隔，周期性广播
+    Balanced,
+    /// 低频间歇广播，最省电
+    Battery,
+}
+
+impl PowerProfile {
+    /// 获取所有电源策略
+    pub fn all() -> &'static [PowerProfile] {
+        &[
+            PowerProfile::Performance,
+            PowerProfile::Balanced,
+            PowerProfile::Battery,
+        ]
+    }
+
+    /// 获取显示名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerProfile::Performance => "performance",
+            PowerProfile::Balanced => "balanced",
+            PowerProfile::Battery => "battery",
+        }
+    }
+}
+
+/// 守护进程日志格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// 人类可读的文本
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象（带 session_id / peer / phase 字段），便于 journald / ELK 解析
+    Json,
+}
+
+/// 传输端口范围（闭区间）
+///
+/// 配置文件中写作 `transfer_ports = { start = 33331, end = 33340 }`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    pub fn new(start: u16, end: u16) -> Self {
+        Self { start, end }
+    }
+
+    /// 范围内的所有端口
+    pub fn ports(&self) -> std::ops::RangeInclusive<u16> {
+        self.start..=self.end
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.ports().contains(&port)
+    }
+}
+
+impl std::fmt::Display for PortRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
 /// 应用设置
+///
+/// 新增字段缺省时取默认值，旧版本的配置文件可以直接加载。
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AppSettings {
     /// 设备名称（在扫描时显示）
     pub device_name: String,
@@ -153,12 +240,62 @@ pub struct AppSettings {
     pub supports_5ghz: bool,
     /// WiFi 接口名称
     pub wifi_interface: String,
+    /// 热点固定使用的网卡；不设置时使用 `wifi_interface`，它已连接网络时优先换用空闲网卡
+    pub hotspot_interface: Option<String>,
     /// 下载目录
     pub download_dir: PathBuf,
     /// 是否自动接受传输
     pub auto_accept: bool,
     /// 详细日志模式
     pub verbose: bool,
+    /// 可发现窗口默认时长（秒），用于快捷键/信号触发的限时接收
+    pub discoverable_window_secs: u64,
+    /// 电源策略（影响 BLE 广播间隔和占空比）
+    pub power_profile: PowerProfile,
+    /// 使用电池供电时 BLE 广播改用 [`PowerProfile::Battery`]，见 [`crate::power`]
+    pub battery_saver: bool,
+    /// 电池电量低于该百分比时拒绝创建热点（`--force` 跳过，0 表示不检查）
+    pub min_battery_percent: u8,
+    /// 守护进程日志格式
+    pub log_format: LogFormat,
+    /// 发送端监听端口范围（未设置时由系统分配随机端口）
+    pub transfer_ports: Option<PortRange>,
+    /// 发送端热点的口令长度、字符集和 WPA3；接收端不支持的选项会自动降级
+    pub hotspot_credentials: CredentialPolicy,
+    /// 热点建好后多少秒内没有接收端接入就关闭热点（0 表示只受传输总超时限制）
+    pub hotspot_idle_secs: u64,
+    /// WiFi 接口已连接网络时如何创建热点：`second_adapter`（改用空闲网卡）、
+    /// `restore_after`（传输结束后重新连接）或 `abort`（放弃发送）
+    pub busy_interface: BusyInterfacePolicy,
+    /// 静态加密密钥文件（`cattysend-cli keygen` 生成），设置后收到的文件加密保存
+    pub encryption_key_file: Option<PathBuf>,
+    /// 按发送端设备名和日期把收到的文件分到下载目录的子目录中
+    pub sort_by_sender: bool,
+    /// 允许其他 cattysend 的文件夹同步（`sync --delete`）删除本机同步目录中的文件
+    pub allow_sync_delete: bool,
+    /// 发送端请求建组（反向模式）时由本机创建热点并接收上传
+    pub group_owner: bool,
+    /// 守护进程由 systemd socket 激活时，空闲多少秒后退出（0 表示不退出）
+    pub idle_exit_secs: u64,
+    /// 被动接收：守护进程常驻 GATT 服务保持可发现，发送端写入连接信息后才接入 WiFi 并开始接收
+    ///
+    /// 开启后守护进程不会因空闲而退出。
+    pub passive_receive: bool,
+    /// 登录后自动进入接收模式（守护进程被动监听或 GUI），见 [`crate::autostart`]
+    pub autostart: AutostartMode,
+    /// 被动监听只在连接到这些 WiFi 网络（SSID）时可被发现，为空时不限制
+    pub trusted_networks: Vec<String>,
+    /// 扫描时记录未识别但与 CatShare 相似的广播形状（匿名），见 [`crate::ble::learn`]
+    pub learn_unknown_vendors: bool,
+    /// 守护进程提前准备热点连接（不开启）和传输端口，缩短点击发送到接收端能连上的时间，
+    /// 见 [`crate::workflow::standby`]
+    pub warm_standby: bool,
+    /// 重复执行的定时传输（`[[schedule]]`），由守护进程执行，见 [`crate::schedule`]
+    #[serde(rename = "schedule", skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<ScheduleRule>,
+    /// 设备出现时执行的钩子（`[[on_appear]]`），由守护进程执行，见 [`crate::discovery::presence`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub on_appear: Vec<PresenceHook>,
 }
 
 impl Default for AppSettings {
@@ -168,14 +305,41 @@ impl Default for AppSettings {
             brand_id: BrandId::Xiaomi,
             supports_5ghz: true,
             wifi_interface: "wlan0".to_string(),
+            hotspot_interface: None,
             download_dir: dirs::download_dir().unwrap_or_else(|| PathBuf::from(".")),
             auto_accept: false,
             verbose: false,
+            discoverable_window_secs: 600,
+            power_profile: PowerProfile::default(),
+            battery_saver: true,
+            min_battery_percent: DEFAULT_MIN_BATTERY_PERCENT,
+            log_format: LogFormat::default(),
+            transfer_ports: None,
+            hotspot_credentials: CredentialPolicy::default(),
+            hotspot_idle_secs: DEFAULT_HOTSPOT_IDLE_TIMEOUT.as_secs(),
+            busy_interface: BusyInterfacePolicy::default(),
+            encryption_key_file: None,
+            sort_by_sender: false,
+            allow_sync_delete: false,
+            group_owner: true,
+            idle_exit_secs: 300,
+            passive_receive: false,
+            autostart: AutostartMode::Off,
+            trusted_networks: Vec::new(),
+            learn_unknown_vendors: false,
+            warm_standby: false,
+            schedules: Vec::new(),
+            on_appear: Vec::new(),
         }
     }
 }
 
 impl AppSettings {
+    /// 发送端的热点空闲超时，见 [`SendOptions::hotspot_idle_timeout`](crate::SendOptions::hotspot_idle_timeout)
+    pub fn hotspot_idle_timeout(&self) -> Option<Duration> {
+        (self.hotspot_idle_secs > 0).then(|| Duration::from_secs(self.hotspot_idle_secs))
+    }
+
     /// 获取配置文件路径
     fn config_path() -> PathBuf {
         let config_dir = dirs::config_dir()
@@ -224,11 +388,7 @@ impl AppSettings {
     /// - XX = 5GHz 标志 (0x01 = 支持, 0x00 = 不支持)
     /// - YY = 厂商 ID
     pub fn capability_uuid(&self) -> uuid::Uuid {
-        let flag_5ghz: u8 = if self.supports_5ghz { 0x01 } else { 0x00 };
-        let brand = self.brand_id.id();
-        // 构造 UUID: 0000XXYY-0000-1000-8000-00805f9b34fb
-        let high = (flag_5ghz as u16) << 8 | (brand as u16);
-        uuid::Uuid::from_u128(((high as u128) << 96) | (0x0000_1000_8000_0080_5f9b_34fb_u128))
+        BrandPreset::for_brand(self.brand_id).ident_uuid(self.supports_5ghz)
     }
 }
 
@@ -292,4 +452,110 @@ mod tests {
         assert_eq!(settings.brand_id, BrandId::Xiaomi);
         assert!(settings.supports_5ghz);
     }
+
+    #[test]
+    fn test_load_legacy_settings_without_new_fields() {
+        let legacy = r#"
+            device_name = "old-laptop"
+            brand_id = "Xiaomi"
+            supports_5ghz = false
+            wifi_interface = "wlp2s0"
+            download_dir = "/tmp"
+            auto_accept = true
+            verbose = false
+        "#;
+        let settings: AppSettings = toml::from_str(legacy).unwrap();
+        assert_eq!(settings.device_name, "old-laptop");
+        assert!(!settings.supports_5ghz);
+        assert_eq!(settings.discoverable_window_secs, 600);
+        assert_eq!(settings.power_profile, PowerProfile::Performance);
+        assert!(settings.battery_saver);
+        assert_eq!(settings.min_battery_percent, 20);
+        assert_eq!(settings.log_format, LogFormat::Text);
+        assert_eq!(settings.transfer_ports, None);
+        assert_eq!(settings.hotspot_credentials, CredentialPolicy::default());
+        assert_eq!(settings.hotspot_idle_secs, 120);
+        assert_eq!(settings.hotspot_interface, None);
+        assert_eq!(settings.busy_interface, BusyInterfacePolicy::SecondAdapter);
+        assert_eq!(settings.encryption_key_file, None);
+        assert!(!settings.sort_by_sender);
+        assert!(!settings.allow_sync_delete);
+        assert!(settings.group_owner);
+        assert_eq!(settings.idle_exit_secs, 300);
+        assert!(!settings.passive_receive);
+        assert_eq!(settings.autostart, AutostartMode::Off);
+        assert!(settings.trusted_networks.is_empty());
+        assert!(!settings.learn_unknown_vendors);
+        assert!(!settings.warm_standby);
+        assert!(settings.schedules.is_empty());
+        assert!(settings.on_appear.is_empty());
+    }
+
+    #[test]
+    fn test_autostart_setting() {
+        let settings: AppSettings =
+            toml::from_str("autostart = \"daemon\"\ntrusted_networks = [\"home\"]").unwrap();
+        assert_eq!(settings.autostart, AutostartMode::Daemon);
+        assert_eq!(settings.trusted_networks, vec!["home".to_string()]);
+    }
+
+    #[test]
+    fn test_schedule_setting() {
+        let settings: AppSettings = toml::from_str(
+            r#"
+            device_name = "laptop"
+
+            [[schedule]]
+            name = "nightly"
+            device = "@phone"
+            paths = ["/tmp/backup.zip"]
+            at = "02:00"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(settings.device_name, "laptop");
+        assert_eq!(settings.schedules.len(), 1);
+        assert_eq!(settings.schedules[0].at.to_string(), "02:00");
+
+        // 保存后能原样读回
+        let saved: AppSettings =
+            toml::from_str(&toml::to_string_pretty(&settings).unwrap()).unwrap();
+        assert_eq!(saved.schedules, settings.schedules);
+    }
+
+    #[test]
+    fn test_log_format_setting() {
+        let settings: AppSettings = toml::from_str(r#"log_format = "json""#).unwrap();
+        assert_eq!(settings.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn test_transfer_ports_setting() {
+        let settings: AppSettings =
+            toml::from_str("transfer_ports = { start = 33331, end = 33340 }").unwrap();
+        let range = settings.transfer_ports.unwrap();
+        assert_eq!(range, PortRange::new(33331, 33340));
+        assert!(range.contains(33331) && range.contains(33340));
+        assert!(!range.contains(33341));
+        assert_eq!(range.to_string(), "33331-33340");
+
+        let saved = toml::to_string_pretty(&settings).unwrap();
+        let reloaded: AppSettings = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.transfer_ports, Some(range));
+    }
+
+    #[test]
+    fn test_hotspot_credentials_setting() {
+        let settings: AppSettings =
+            toml::from_str(r#"hotspot_credentials = { psk_length = 16, charset = "symbols" }"#)
+                .unwrap();
+        let credentials = settings.hotspot_credentials;
+        assert_eq!(credentials.psk_length, 16);
+        assert_eq!(credentials.charset, crate::wifi::PskCharset::Symbols);
+        assert!(!credentials.wpa3);
+
+        let saved = toml::to_string_pretty(&settings).unwrap();
+        let reloaded: AppSettings = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.hotspot_credentials, credentials);
+    }
 }
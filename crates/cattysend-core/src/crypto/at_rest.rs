@@ -0,0 +1,260 @@
+//! 接收文件的静态加密
+//!
+//! 多人共用的机器上，接收端可以把收到的文件加密后再放进输出目录（见
+//! `ReceiveOptions::encryption_key`），只有持有密钥的人能用 `cattysend-cli decrypt` 还原。
+//!
+//! # 文件格式（`<原文件名>.cattyenc`）
+//!
+//! ```text
+//! "CATTYEN1" (8) | salt (16) | 密文 | HMAC-SHA256 (32)
+//! ```
+//!
+//! - 每个文件用随机 salt 经 HKDF-SHA256 从主密钥派生加密密钥和 MAC 密钥
+//! - 加密: AES-256-CTR（IV 全零，密钥按文件唯一）
+//! - 认证: HMAC-SHA256 覆盖头部和密文，解密前先校验，密钥错误或文件被改动时不输出任何明文
+//!
+//! 下载和解压仍在输出目录下的暂存目录中以明文进行（权限 0700），加密完成后即删除。
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use base64::{Engine as _, engine::general_purpose};
+use hkdf::Hkdf;
+use hkdf::hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use sha2::Sha256;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// 加密文件的扩展名
+pub const ENCRYPTED_EXTENSION: &str = "cattyenc";
+
+const MAGIC: &[u8; 8] = b"CATTYEN1";
+const SALT_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// 静态加密主密钥（32 字节，以 Base64 保存在密钥文件中）
+#[derive(Clone, PartialEq, Eq)]
+pub struct AtRestKey([u8; 32]);
+
+impl fmt::Debug for AtRestKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AtRestKey(..)")
+    }
+}
+
+impl AtRestKey {
+    /// 生成随机密钥
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// 从 Base64 解析
+    pub fn from_base64(encoded: &str) -> anyhow::Result<Self> {
+        let bytes = general_purpose::STANDARD.decode(encoded.trim())?;
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| anyhow::anyhow!("Key must be 32 bytes, got {}", b.len()))?;
+        Ok(Self(key))
+    }
+
+    /// Base64 编码
+    pub fn to_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.0)
+    }
+
+    /// 读取密钥文件
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read key file {}: {}", path.display(), e))?;
+        Self::from_base64(&content)
+    }
+
+    /// 写入新的密钥文件（权限 0600，已存在时失败，避免覆盖仍在使用的密钥）
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        writeln!(file, "{}", self.to_base64())?;
+        Ok(())
+    }
+
+    /// 按 salt 派生单个文件的加密器和 MAC
+    fn file_keys(&self, salt: &[u8]) -> (Aes256Ctr, HmacSha256) {
+        let hkdf = Hkdf::<Sha256>::new(Some(salt), &self.0);
+        let mut enc_key = [0u8; 32];
+        let mut mac_key = [0u8; 32];
+        // 输出长度固定为 32 字节，远小于 HKDF 上限
+        hkdf.expand(b"cattysend at-rest encryption", &mut enc_key)
+            .expect("valid HKDF length");
+        hkdf.expand(b"cattysend at-rest authentication", &mut mac_key)
+            .expect("valid HKDF length");
+        let cipher = Aes256Ctr::new(&enc_key.into(), &[0u8; 16].into());
+        let mac = HmacSha256::new_from_slice(&mac_key).expect("HMAC accepts any key length");
+        (cipher, mac)
+    }
+}
+
+/// 把 `src` 加密写入 `dst`（权限 0600）
+pub fn encrypt_file(key: &AtRestKey, src: &Path, dst: &Path) -> anyhow::Result<()> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let (mut cipher, mut mac) = key.file_keys(&salt);
+
+    let mut input = File::open(src)?;
+    let mut output = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(dst)?;
+
+    output.write_all(MAGIC)?;
+    output.write_all(&salt)?;
+    mac.update(MAGIC);
+    mac.update(&salt);
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = input.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let chunk = &mut buf[..n];
+        cipher.apply_keystream(chunk);
+        mac.update(chunk);
+        output.write_all(chunk)?;
+    }
+    output.write_all(&mac.finalize().into_bytes())?;
+    output.sync_all()?;
+    Ok(())
+}
+
+/// 校验并解密 `src` 到 `dst`
+///
+/// 先完整校验 MAC，通过后才创建 `dst`。
+pub fn decrypt_file(key: &AtRestKey, src: &Path, dst: &Path) -> anyhow::Result<()> {
+    let mut input = File::open(src)?;
+    let len = input.metadata()?.len();
+    let min_len = (HEADER_LEN + TAG_LEN) as u64;
+    let mut header = [0u8; HEADER_LEN];
+    if len < min_len || input.read_exact(&mut header).is_err() || &header[..MAGIC.len()] != MAGIC {
+        anyhow::bail!("{} is not an encrypted cattysend file", src.display());
+    }
+    let body_len = len - min_len;
+    let (mut cipher, mut mac) = key.file_keys(&header[MAGIC.len()..]);
+
+    // 第一遍：校验
+    mac.update(&header);
+    let mut body = (&mut input).take(body_len);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = body.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        mac.update(&buf[..n]);
+    }
+    let mut tag = [0u8; TAG_LEN];
+    input.read_exact(&mut tag)?;
+    if mac.verify_slice(&tag).is_err() {
+        anyhow::bail!(
+            "Authentication failed for {}: wrong key or the file was modified",
+            src.display()
+        );
+    }
+
+    // 第二遍：解密
+    input.seek(SeekFrom::Start(HEADER_LEN as u64))?;
+    let mut body = input.take(body_len);
+    let mut output = File::create(dst)?;
+    loop {
+        let n = body.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        let chunk = &mut buf[..n];
+        cipher.apply_keystream(chunk);
+        output.write_all(chunk)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let dir = temp_dir("at-rest");
+        let plain = dir.join("photo.jpg");
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&plain, &data).unwrap();
+
+        let key = AtRestKey::generate();
+        let encrypted = dir.join("photo.jpg.cattyenc");
+        encrypt_file(&key, &plain, &encrypted).unwrap();
+        let stored = std::fs::read(&encrypted).unwrap();
+        assert_eq!(stored.len(), data.len() + HEADER_LEN + TAG_LEN);
+        assert!(!stored.windows(64).any(|w| w == &data[1000..1064]));
+
+        let restored = dir.join("restored.jpg");
+        decrypt_file(&key, &encrypted, &restored).unwrap();
+        assert_eq!(std::fs::read(&restored).unwrap(), data);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_tampering() {
+        let dir = temp_dir("at-rest");
+        let plain = dir.join("notes.txt");
+        std::fs::write(&plain, b"secret notes").unwrap();
+        let key = AtRestKey::generate();
+        let encrypted = dir.join("notes.txt.cattyenc");
+        encrypt_file(&key, &plain, &encrypted).unwrap();
+        let out = dir.join("out.txt");
+
+        assert!(decrypt_file(&AtRestKey::generate(), &encrypted, &out).is_err());
+        assert!(!out.exists());
+
+        let mut stored = std::fs::read(&encrypted).unwrap();
+        stored[HEADER_LEN] ^= 1;
+        std::fs::write(&encrypted, &stored).unwrap();
+        assert!(decrypt_file(&key, &encrypted, &out).is_err());
+        assert!(!out.exists());
+
+        let err = decrypt_file(&key, &plain, &out).unwrap_err();
+        assert!(err.to_string().contains("not an encrypted"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_key_file() {
+        let dir = temp_dir("at-rest");
+        let path = dir.join("receive.key");
+        let key = AtRestKey::generate();
+        key.save(&path).unwrap();
+        assert_eq!(AtRestKey::load(&path).unwrap(), key);
+        // 不覆盖已有密钥
+        assert!(AtRestKey::generate().save(&path).is_err());
+        assert!(AtRestKey::from_base64("c2hvcnQ=").is_err());
+        assert_eq!(format!("{:?}", key), "AtRestKey(..)");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
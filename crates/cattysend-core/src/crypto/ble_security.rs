@@ -13,6 +13,9 @@
 //!    这会返回原始的 ECDH 共享密钥（32 字节），**不使用** HKDF。
 //!
 //! 3. **AES IV**: 是字符串 `"0102030405060708"` 的 **ASCII 字节**，不是十六进制。
+//!
+//! 另外，两端可以从同一个共享密钥派生 6 位验证码（[`SessionCipher::verification_code`]），
+//! 供用户核对连接的是不是目标设备。CatShare 没有这一步。
 
 use aes::cipher::{KeyIvInit, StreamCipher};
 use base64::{Engine as _, engine::general_purpose};
@@ -20,6 +23,7 @@ use log::{debug, trace};
 use p256::pkcs8::EncodePublicKey;
 use p256::{PublicKey, ecdh::EphemeralSecret};
 use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
 
 type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
 
@@ -27,6 +31,9 @@ type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
 /// 实际字节: [0x30, 0x31, 0x30, 0x32, 0x30, 0x33, 0x30, 0x34, 0x30, 0x35, 0x30, 0x36, 0x30, 0x37, 0x30, 0x38]
 const AES_IV: &[u8; 16] = b"0102030405060708";
 
+/// 派生验证码时加在共享密钥前的标签，避免摘要与密钥的其他用途重合
+const VERIFICATION_LABEL: &[u8] = b"cattysend-verification-code";
+
 /// BLE 安全上下文 - 管理 ECDH 密钥对
 ///
 /// # 生命周期
@@ -183,6 +190,19 @@ impl SessionCipher {
         SessionCipherRef { key: &self.key }
     }
 
+    /// 6 位数字验证码（类似蓝牙配对的数字比较）
+    ///
+    /// 取 `SHA-256(标签 || 共享密钥)` 的前 4 字节对 10^6 取模。
+    /// 两端显示相同的数字，说明双方协商出的是同一个密钥，没有第三方插在中间。
+    pub fn verification_code(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(VERIFICATION_LABEL)
+            .chain_update(self.key)
+            .finalize();
+        let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+        format!("{:06}", value % 1_000_000)
+    }
+
     /// 使用 AES-256-CTR 加密数据
     ///
     /// # 参数
@@ -290,7 +310,23 @@ pub struct BleSecurityPersistent {
 impl BleSecurityPersistent {
     /// 生成新的持久化 ECDH 密钥对
     pub fn new() -> anyhow::Result<Self> {
-        let secret_key = p256::SecretKey::random(&mut OsRng);
+        Self::from_secret_key(p256::SecretKey::random(&mut OsRng))
+    }
+
+    /// 从 Base64 编码的私钥标量恢复（见 [`IdentityStore`](super::IdentityStore)）
+    pub fn from_secret_base64(encoded: &str) -> anyhow::Result<Self> {
+        let bytes = general_purpose::STANDARD.decode(encoded.trim())?;
+        let secret_key = p256::SecretKey::from_slice(&bytes)
+            .map_err(|_| anyhow::anyhow!("Invalid P-256 private key"))?;
+        Self::from_secret_key(secret_key)
+    }
+
+    /// 私钥标量（32 字节）的 Base64 编码，只用于持久化
+    pub fn secret_to_base64(&self) -> String {
+        general_purpose::STANDARD.encode(self.secret_key.to_bytes())
+    }
+
+    fn from_secret_key(secret_key: p256::SecretKey) -> anyhow::Result<Self> {
         let public_key = secret_key.public_key();
 
         // 使用 X.509 SPKI DER 格式编码公钥
@@ -300,7 +336,7 @@ impl BleSecurityPersistent {
         let public_key_b64 = general_purpose::STANDARD.encode(spki_der.as_bytes());
 
         debug!(
-            "Generated persistent ECDH key pair, public key (SPKI) length: {} bytes",
+            "Persistent ECDH key pair ready, public key (SPKI) length: {} bytes",
             spki_der.as_bytes().len()
         );
 
@@ -444,6 +480,35 @@ mod tests {
         assert_ne!(cipher_p_alice.key, cipher_p_bob.key);
     }
 
+    /// 双方派生出相同的验证码，换一个对端则不同
+    #[test]
+    fn test_verification_code() {
+        let persistent = BleSecurityPersistent::new().unwrap();
+        let alice = BleSecurity::new().unwrap();
+        let alice_pub = alice.get_public_key().to_string();
+
+        let code = persistent
+            .derive_session_key(&alice_pub)
+            .unwrap()
+            .verification_code();
+        let peer_code = alice
+            .derive_session_key(persistent.get_public_key())
+            .unwrap()
+            .verification_code();
+        assert_eq!(code, peer_code);
+        assert_eq!(code.len(), 6);
+        assert!(code.chars().all(|c| c.is_ascii_digit()));
+
+        assert_eq!(
+            SessionCipher::new([0u8; 32]).verification_code(),
+            SessionCipher::new([0u8; 32]).verification_code()
+        );
+        assert_ne!(
+            SessionCipher::new([0u8; 32]).verification_code(),
+            SessionCipher::new([1u8; 32]).verification_code()
+        );
+    }
+
     /// 测试跨格式公钥兼容性
     #[test]
     fn test_parse_sec1_public_key() {
@@ -482,6 +547,76 @@ mod tests {
         assert_eq!(plaintext, decrypted);
     }
 
+    /// CatShare 的 Java 实现（`KeyAgreement("ECDH")` + `generateSecret("TlsPremasterSecret")`、
+    /// `AES/CTR/NoPadding`）在固定私钥下的输出，由 OpenJDK 17 生成
+    mod java_vectors {
+        use super::*;
+
+        /// RFC 6979 A.2.5 的 P-256 私钥
+        const PRIVATE_A: &str = "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721";
+        const PRIVATE_B: &str = "4b2fb1a7c5b08d0e7a6b3c0f9e8d7c6b5a4938271605f4e3d2c1b0a998877665";
+
+        /// `ECPublicKey.getEncoded()` 的 Base64
+        const PUBLIC_A: &str = "MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEYP7UuiVanTHJYet0xjVtaMBJuJI7Yfps5mliLmDyn7Z5A/4QCLi8maQa6elWKLxk8vGyDC1+n1F3o8KU1EYimQ==";
+        const PUBLIC_B: &str = "MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEVQ7oyUOB7AxCZVxy4P7MiqBGGWe/zCKu9CcRcF+xGYfGYBQ/vl3uHn4Jaj9umxbbwxqF9g8heUs0I9Z+i6qINw==";
+
+        /// `generateSecret("TlsPremasterSecret").getEncoded()`
+        const SHARED_SECRET: &str =
+            "49670257257f2e4dbf3b048f7f6081328e8945e93e8020475a9e04b09fcc3c8c";
+
+        /// (明文, IV 为 `"0102030405060708".getBytes()` 时的 Base64 密文)
+        const CIPHERTEXTS: &[(&str, &str)] = &[
+            ("DIRECT-cattysend", "OpdcJt9YA6zm4UnXKi+zpw=="),
+            ("x9y8z7w6", "Bud3W+Y7Wfk="),
+            ("AA:BB:CC:DD:EE:FF", "P580Id42bYy90XmUHA/nhYY="),
+            ("文件互传", "mEiJhye6ynUVcYEO"),
+            (
+                r#"{"ssid":"DIRECT-cattysend","psk":"x9y8z7w6","port":33331}"#,
+                "Bfx9EPVoDPWl0XT8HAmJ7qP9gGu1nRsYWoG+wdCdZTOSZrSCJATBSHGT8bVum8AuZCtldGrRpdGS",
+            ),
+        ];
+
+        fn unhex(s: &str) -> Vec<u8> {
+            (0..s.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+                .collect()
+        }
+
+        fn keypair(private: &str) -> BleSecurityPersistent {
+            let secret_key = p256::SecretKey::from_slice(&unhex(private)).unwrap();
+            BleSecurityPersistent::from_secret_key(secret_key).unwrap()
+        }
+
+        /// 公钥的 SPKI 编码与 Java 逐字节相同
+        #[test]
+        fn test_public_key_matches_java() {
+            assert_eq!(keypair(PRIVATE_A).get_public_key(), PUBLIC_A);
+            assert_eq!(keypair(PRIVATE_B).get_public_key(), PUBLIC_B);
+        }
+
+        /// 会话密钥就是原始共享密钥，不经过 HKDF
+        #[test]
+        fn test_shared_secret_matches_java() {
+            let a = keypair(PRIVATE_A).derive_session_key(PUBLIC_B).unwrap();
+            let b = keypair(PRIVATE_B).derive_session_key(PUBLIC_A).unwrap();
+            assert_eq!(a.key.to_vec(), unhex(SHARED_SECRET));
+            assert_eq!(b.key, a.key);
+        }
+
+        /// 密文与 Java 相同（IV 是 ASCII 字节，计数器跨块递增）
+        #[test]
+        fn test_ciphertext_matches_java() {
+            let key: [u8; 32] = unhex(SHARED_SECRET).try_into().unwrap();
+            let cipher = SessionCipher::new(key);
+            for (plaintext, ciphertext) in CIPHERTEXTS {
+                assert_eq!(cipher.encrypt(plaintext).unwrap(), *ciphertext);
+                assert_eq!(cipher.decrypt(ciphertext).unwrap(), *plaintext);
+                assert_eq!(cipher.as_ref().encrypt(plaintext).unwrap(), *ciphertext);
+            }
+        }
+    }
+
     /// 测试 Unicode 数据加密
     #[test]
     fn test_encrypt_unicode() {
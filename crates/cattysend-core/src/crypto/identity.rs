@@ -0,0 +1,484 @@
+//! 本机 ECDH 身份密钥的持久化
+//!
+//! [`BleSecurityPersistent`] 的公钥会被对端记住（收藏、验证码核对），所以私钥需要在
+//! 重启后保持不变。私钥优先保存在桌面密钥环（Secret Service，需启用 `keyring`
+//! feature）；密钥环不可用时（没有运行 gnome-keyring / KWallet、无头环境等）退回到
+//! `~/.config/cattysend/identity.key`（权限 0600）。两处保存的都是私钥标量的 Base64。
+//!
+//! 私钥存进密钥环时，在密钥文件旁边留下 `identity.keyring` 标记。之后密钥环暂时打不开
+//! （未解锁、服务还没启动）时据此报错，而不是当作没有密钥、生成一个新的顶替；
+//! 密钥环可用后，旧版本或密钥环不可用时写下的明文密钥文件会迁移进密钥环。
+//!
+//! 密钥环通过 D-Bus 同步访问，异步代码中应放在 `tokio::task::spawn_blocking` 里调用。
+//!
+//! 公钥的 SHA-256 即本机指纹（[`fingerprint`]），用于带外核对；广播和传输请求中的
+//! sender ID 取指纹的前两个字节，随身份密钥固定下来。
+
+use super::BleSecurityPersistent;
+use crate::config::AppSettings;
+use base64::{Engine as _, engine::general_purpose};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 有标记时读取密钥环的尝试次数
+const KEYRING_ATTEMPTS: u32 = 3;
+
+/// 两次尝试之间的等待时间
+const KEYRING_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// 私钥实际保存的位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyLocation {
+    /// 系统密钥环（Secret Service）
+    Keyring,
+    /// 密钥文件
+    File(PathBuf),
+}
+
+impl fmt::Display for KeyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Keyring => f.write_str("system keyring"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// 身份密钥的存储位置
+#[derive(Debug, Clone)]
+pub struct IdentityStore {
+    file: PathBuf,
+    keyring: bool,
+}
+
+impl Default for IdentityStore {
+    fn default() -> Self {
+        Self {
+            file: Self::default_path(),
+            keyring: cfg!(feature = "keyring"),
+        }
+    }
+}
+
+impl IdentityStore {
+    /// 默认的密钥文件路径
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("cattysend")
+            .join("identity.key")
+    }
+
+    /// 只使用 `path` 处的密钥文件，不访问密钥环
+    pub fn file_only(path: impl Into<PathBuf>) -> Self {
+        Self {
+            file: path.into(),
+            keyring: false,
+        }
+    }
+
+    /// 读取保存的密钥，还没有时返回 `None`
+    ///
+    /// 密钥保存在密钥环（有标记）而密钥环打不开时返回错误，不会退回密钥文件。
+    pub fn load(&self) -> anyhow::Result<Option<(BleSecurityPersistent, KeyLocation)>> {
+        if self.keyring {
+            match self.load_keyring() {
+                Ok(Some(security)) => return Ok(Some((security, KeyLocation::Keyring))),
+                Ok(None) => {}
+                Err(e) if self.marker().exists() => {
+                    return Err(anyhow::anyhow!(
+                        "Identity key is stored in the system keyring, which is unavailable: {}",
+                        e
+                    ));
+                }
+                Err(e) => warn!(
+                    "Keyring unavailable, falling back to {}: {}",
+                    self.file.display(),
+                    e
+                ),
+            }
+        }
+
+        let Some(security) = self.load_file()? else {
+            return Ok(None);
+        };
+        if self.keyring {
+            // 旧版本或密钥环不可用时写下的明文密钥，迁移进密钥环
+            match self.save_keyring(&security) {
+                Ok(()) => {
+                    info!(
+                        "Migrated identity key from {} to system keyring",
+                        self.file.display()
+                    );
+                    return Ok(Some((security, KeyLocation::Keyring)));
+                }
+                Err(e) => debug!("Identity key stays in {}: {}", self.file.display(), e),
+            }
+        }
+        Ok(Some((security, KeyLocation::File(self.file.clone()))))
+    }
+
+    /// 标记密钥保存在密钥环中的文件
+    fn marker(&self) -> PathBuf {
+        self.file.with_extension("keyring")
+    }
+
+    /// 从密钥环读取；有标记时失败会重试几次（登录后密钥环可能还没就绪）
+    fn load_keyring(&self) -> anyhow::Result<Option<BleSecurityPersistent>> {
+        let attempts = if self.marker().exists() {
+            KEYRING_ATTEMPTS
+        } else {
+            1
+        };
+        let mut attempt = 1;
+        let encoded = loop {
+            match backend::get() {
+                Ok(encoded) => break encoded,
+                Err(e) if attempt >= attempts => return Err(e),
+                Err(e) => {
+                    debug!("Keyring read failed (attempt {}): {}", attempt, e);
+                    attempt += 1;
+                    std::thread::sleep(KEYRING_RETRY_DELAY);
+                }
+            }
+        };
+        let Some(encoded) = encoded else {
+            return Ok(None);
+        };
+        let security = BleSecurityPersistent::from_secret_base64(&encoded)?;
+        // 有标记之前存进密钥环的密钥补上标记
+        if !self.marker().exists()
+            && let Err(e) = write_marker(&self.marker())
+        {
+            warn!("Failed to write {}: {}", self.marker().display(), e);
+        }
+        Ok(Some(security))
+    }
+
+    fn load_file(&self) -> anyhow::Result<Option<BleSecurityPersistent>> {
+        match fs::read_to_string(&self.file) {
+            Ok(content) => {
+                let security =
+                    BleSecurityPersistent::from_secret_base64(&content).map_err(|e| {
+                        anyhow::anyhow!("Invalid identity key {}: {}", self.file.display(), e)
+                    })?;
+                Ok(Some(security))
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow::anyhow!(
+                "Failed to read identity key {}: {}",
+                self.file.display(),
+                e
+            )),
+        }
+    }
+
+    /// 写入密钥环并留下标记，删除旧的明文副本
+    fn save_keyring(&self, security: &BleSecurityPersistent) -> anyhow::Result<()> {
+        backend::set(&security.secret_to_base64())?;
+        write_marker(&self.marker())?;
+        remove_file(&self.file)
+    }
+
+    /// 读取保存的密钥，还没有时生成一个并保存
+    pub fn load_or_create(&self) -> anyhow::Result<BleSecurityPersistent> {
+        if let Some((security, location)) = self.load()? {
+            debug!("Loaded identity key from {}", location);
+            return Ok(security);
+        }
+        let security = BleSecurityPersistent::new()?;
+        let location = self.save(&security)?;
+        info!("Generated new identity key, stored in {}", location);
+        Ok(security)
+    }
+
+    /// 保存密钥：优先写入密钥环，失败时写密钥文件
+    pub fn save(&self, security: &BleSecurityPersistent) -> anyhow::Result<KeyLocation> {
+        if self.keyring {
+            match self.save_keyring(security) {
+                Ok(()) => return Ok(KeyLocation::Keyring),
+                Err(e) => warn!(
+                    "Failed to store identity key in keyring, using {}: {}",
+                    self.file.display(),
+                    e
+                ),
+            }
+        }
+        write_key_file(&self.file, &security.secret_to_base64())?;
+        remove_file(&self.marker())?;
+        Ok(KeyLocation::File(self.file.clone()))
+    }
+
+    /// 删除密钥环和密钥文件中保存的密钥
+    pub fn delete(&self) -> anyhow::Result<()> {
+        if self.keyring
+            && let Err(e) = backend::delete()
+        {
+            warn!("Failed to delete identity key from keyring: {}", e);
+        }
+        remove_file(&self.marker())?;
+        remove_file(&self.file)
+    }
+
+    /// 丢弃旧密钥并生成新的
+    ///
+    /// 之后本机的公钥和验证码都会改变，对端记住的旧公钥不再有效。
+    pub fn reset(&self) -> anyhow::Result<(BleSecurityPersistent, KeyLocation)> {
+        self.delete()?;
+        let security = BleSecurityPersistent::new()?;
+        let location = self.save(&security)?;
+        info!("Identity key reset, stored in {}", location);
+        Ok((security, location))
+    }
+}
+
+/// 对外展示的本机身份，供 `identity show` 和 UI 显示指纹 / 二维码
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    /// Base64 编码的公钥（X.509 SPKI）
+    pub public_key: String,
+    /// 公钥指纹，见 [`fingerprint`]
+    pub fingerprint: String,
+    /// 广播和传输请求中的 sender ID
+    pub sender_id: String,
+    /// 广播的设备名称
+    pub device_name: String,
+    /// 广播的厂商名称
+    pub brand: String,
+    /// 广播的厂商 ID
+    pub brand_id: u8,
+}
+
+impl DeviceIdentity {
+    pub fn new(security: &BleSecurityPersistent, settings: &AppSettings) -> Self {
+        let public_key = security.get_public_key();
+        Self {
+            public_key: public_key.to_string(),
+            fingerprint: fingerprint(public_key),
+            sender_id: sender_id(public_key),
+            device_name: settings.device_name.clone(),
+            brand: settings.brand_id.name().to_string(),
+            brand_id: settings.brand_id.id(),
+        }
+    }
+}
+
+/// 公钥的 SHA-256（按 SPKI DER 计算；不是合法 Base64 时按原始字符串计算）
+fn public_key_digest(public_key: &str) -> [u8; 32] {
+    let der = general_purpose::STANDARD
+        .decode(public_key)
+        .unwrap_or_else(|_| public_key.as_bytes().to_vec());
+    Sha256::digest(der).into()
+}
+
+/// 公钥指纹：SHA-256 的大写十六进制，每 4 个字符一组，如 `3F2A 91C0 …`
+pub fn fingerprint(public_key: &str) -> String {
+    public_key_digest(public_key)
+        .chunks(2)
+        .map(|pair| format!("{:02X}{:02X}", pair[0], pair[1]))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 由公钥确定的 2 字节 sender ID（广播数据中的原始字节）
+pub fn sender_id_bytes(public_key: &str) -> [u8; 2] {
+    let digest = public_key_digest(public_key);
+    [digest[0], digest[1]]
+}
+
+/// 由公钥确定的 sender ID（4 位小写十六进制）
+pub fn sender_id(public_key: &str) -> String {
+    let [a, b] = sender_id_bytes(public_key);
+    format!("{:02x}{:02x}", a, b)
+}
+
+fn remove_file(path: &Path) -> anyhow::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(anyhow::anyhow!(
+            "Failed to remove identity key {}: {}",
+            path.display(),
+            e
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn write_marker(path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, "identity key is stored in the system keyring\n")?;
+    Ok(())
+}
+
+/// 先写临时文件（权限 0600）再改名，中途失败不会留下半个密钥
+fn write_key_file(path: &Path, encoded: &str) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("key.tmp");
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp)?;
+    writeln!(file, "{}", encoded)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(feature = "keyring")]
+mod backend {
+    const SERVICE: &str = "cattysend";
+    const USER: &str = "ble-identity";
+
+    fn entry() -> keyring::Result<keyring::Entry> {
+        keyring::Entry::new(SERVICE, USER)
+    }
+
+    pub fn get() -> anyhow::Result<Option<String>> {
+        match entry()?.get_password() {
+            Ok(encoded) => Ok(Some(encoded)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn set(encoded: &str) -> anyhow::Result<()> {
+        Ok(entry()?.set_password(encoded)?)
+    }
+
+    pub fn delete() -> anyhow::Result<()> {
+        match entry()?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// 未启用 `keyring` feature 时 [`IdentityStore`] 不会访问密钥环
+#[cfg(not(feature = "keyring"))]
+mod backend {
+    fn unsupported() -> anyhow::Error {
+        anyhow::anyhow!("built without keyring support")
+    }
+
+    pub fn get() -> anyhow::Result<Option<String>> {
+        Err(unsupported())
+    }
+
+    pub fn set(_encoded: &str) -> anyhow::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn delete() -> anyhow::Result<()> {
+        Err(unsupported())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn temp_store() -> (PathBuf, IdentityStore) {
+        let dir = temp_dir("identity");
+        let store = IdentityStore::file_only(dir.join("identity.key"));
+        (dir, store)
+    }
+
+    #[test]
+    fn test_load_or_create_persists_key() {
+        let (dir, store) = temp_store();
+        assert!(store.load().unwrap().is_none());
+
+        let first = store.load_or_create().unwrap();
+        let path = dir.join("identity.key");
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // 再次加载得到同一个密钥对
+        let second = store.load_or_create().unwrap();
+        assert_eq!(first.get_public_key(), second.get_public_key());
+        let (_, location) = store.load().unwrap().unwrap();
+        assert_eq!(location, KeyLocation::File(path));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reset_replaces_key() {
+        let (dir, store) = temp_store();
+        let old = store.load_or_create().unwrap();
+        let (new, _) = store.reset().unwrap();
+        assert_ne!(old.get_public_key(), new.get_public_key());
+        assert_eq!(
+            store.load_or_create().unwrap().get_public_key(),
+            new.get_public_key()
+        );
+
+        store.delete().unwrap();
+        assert!(store.load().unwrap().is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fingerprint_and_sender_id() {
+        let security = BleSecurityPersistent::new().unwrap();
+        let public_key = security.get_public_key();
+        let fp = fingerprint(public_key);
+        assert_eq!(fp.len(), 16 * 4 + 15);
+        assert!(fp.split(' ').all(|group| group.len() == 4));
+        // sender ID 是指纹的前两个字节
+        assert_eq!(sender_id(public_key), fp[..4].to_lowercase());
+        assert_eq!(fingerprint(public_key), fp);
+
+        let settings = AppSettings::default();
+        let identity = DeviceIdentity::new(&security, &settings);
+        assert_eq!(identity.fingerprint, fp);
+        assert_eq!(identity.brand, settings.brand_id.name());
+        assert_eq!(identity.device_name, settings.device_name);
+    }
+
+    /// 密钥存在密钥环里而密钥环打不开时，不能生成新密钥顶替
+    #[cfg(not(feature = "keyring"))]
+    #[test]
+    fn test_unavailable_keyring_does_not_mint_new_key() {
+        let dir = temp_dir("identity");
+        let store = IdentityStore {
+            file: dir.join("identity.key"),
+            keyring: true,
+        };
+        // 从没存进过密钥环：退回密钥文件
+        let security = store.load_or_create().unwrap();
+        let (loaded, location) = store.load().unwrap().unwrap();
+        assert_eq!(loaded.get_public_key(), security.get_public_key());
+        assert_eq!(location, KeyLocation::File(dir.join("identity.key")));
+
+        // 标记表明密钥在密钥环里
+        fs::remove_file(dir.join("identity.key")).unwrap();
+        write_marker(&dir.join("identity.keyring")).unwrap();
+        assert!(store.load().is_err());
+        assert!(store.load_or_create().is_err());
+        assert!(!dir.join("identity.key").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_corrupt_key_file() {
+        let (dir, store) = temp_store();
+        fs::write(dir.join("identity.key"), "not a key\n").unwrap();
+        assert!(store.load().is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,3 +1,9 @@
+pub mod at_rest;
 pub mod ble_security;
+pub mod identity;
+pub mod tls;
 
+pub use at_rest::AtRestKey;
 pub use ble_security::{BleSecurity, BleSecurityPersistent, SessionCipher};
+pub use identity::{DeviceIdentity, IdentityStore, KeyLocation};
+pub use tls::TlsIdentity;
@@ -0,0 +1,179 @@
+//! 传输服务的 TLS 证书
+//!
+//! 接收端也是 cattysend 时，发送端用一张长期保存的自签名证书提供 HTTPS，并把证书
+//! DER 的 SHA-256（[`TlsIdentity::fingerprint`]）随加密的 P2P 信息发给接收端。接收端只
+//! 接受指纹一致的证书（证书固定），而不是像对 CatShare 那样跳过证书验证。
+//!
+//! 证书和私钥保存在 `~/.config/cattysend/tls/`（`cert.pem`、`key.pem`，私钥权限 0600），
+//! 第一次使用时生成，之后保持不变。
+
+use log::{debug, info};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// 自签名证书中的主机名（接收端按 IP 连接，不校验主机名）
+const SUBJECT_NAME: &str = "cattysend";
+
+/// 传输服务的证书和私钥（PEM）
+#[derive(Clone)]
+pub struct TlsIdentity {
+    cert_pem: String,
+    key_pem: String,
+    cert_der: Vec<u8>,
+}
+
+impl std::fmt::Debug for TlsIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出私钥
+        f.debug_struct("TlsIdentity")
+            .field("fingerprint", &self.fingerprint())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TlsIdentity {
+    /// 生成新的自签名证书
+    pub fn generate() -> anyhow::Result<Self> {
+        let rcgen::CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(vec![SUBJECT_NAME.to_string()])?;
+        Ok(Self {
+            cert_pem: cert.pem(),
+            key_pem: key_pair.serialize_pem(),
+            cert_der: cert.der().to_vec(),
+        })
+    }
+
+    /// 从 PEM 格式的证书和 PKCS#8 私钥构造
+    pub fn from_pem(cert_pem: String, key_pem: String) -> anyhow::Result<Self> {
+        let cert_der = native_tls::Certificate::from_pem(cert_pem.as_bytes())?.to_der()?;
+        // 提前确认私钥与证书可以配对，不要等到第一次握手才失败
+        native_tls::Identity::from_pkcs8(cert_pem.as_bytes(), key_pem.as_bytes())?;
+        Ok(Self {
+            cert_pem,
+            key_pem,
+            cert_der,
+        })
+    }
+
+    /// 默认的证书目录
+    pub fn default_dir() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("cattysend")
+            .join("tls")
+    }
+
+    /// 读取默认目录中的证书，还没有时生成并保存
+    pub fn load_or_create() -> anyhow::Result<Self> {
+        Self::load_or_create_in(&Self::default_dir())
+    }
+
+    /// 读取 `dir` 中的证书，还没有时生成并保存
+    pub fn load_or_create_in(dir: &Path) -> anyhow::Result<Self> {
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        match (
+            fs::read_to_string(&cert_path),
+            fs::read_to_string(&key_path),
+        ) {
+            (Ok(cert), Ok(key)) => {
+                let identity = Self::from_pem(cert, key).map_err(|e| {
+                    anyhow::anyhow!("Invalid TLS certificate in {}: {}", dir.display(), e)
+                })?;
+                debug!("Loaded TLS certificate {}", identity.fingerprint());
+                return Ok(identity);
+            }
+            (Err(e), _) | (_, Err(e)) if e.kind() != ErrorKind::NotFound => {
+                return Err(anyhow::anyhow!(
+                    "Failed to read TLS certificate in {}: {}",
+                    dir.display(),
+                    e
+                ));
+            }
+            _ => {}
+        }
+
+        let identity = Self::generate()?;
+        fs::create_dir_all(dir)?;
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&key_path)?
+            .write_all(identity.key_pem.as_bytes())?;
+        fs::write(&cert_path, &identity.cert_pem)?;
+        info!(
+            "Generated TLS certificate {}, stored in {}",
+            identity.fingerprint(),
+            dir.display()
+        );
+        Ok(identity)
+    }
+
+    /// 证书 DER 的 SHA-256（小写十六进制），写入 P2P 信息的 `certSha256`
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.cert_der)
+    }
+
+    /// 证书（DER）
+    pub fn cert_der(&self) -> &[u8] {
+        &self.cert_der
+    }
+
+    /// 用于服务端握手的 TLS acceptor
+    pub fn acceptor(&self) -> anyhow::Result<tokio_native_tls::TlsAcceptor> {
+        let identity =
+            native_tls::Identity::from_pkcs8(self.cert_pem.as_bytes(), self.key_pem.as_bytes())?;
+        Ok(native_tls::TlsAcceptor::new(identity)?.into())
+    }
+}
+
+/// 证书（DER）的 SHA-256 指纹（小写十六进制）
+pub fn fingerprint(cert_der: &[u8]) -> String {
+    Sha256::digest(cert_der)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 证书是否与固定的指纹一致
+pub fn matches_fingerprint(cert_der: &[u8], expected: &str) -> bool {
+    fingerprint(cert_der).eq_ignore_ascii_case(expected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_load_or_create_keeps_certificate() {
+        let dir = temp_dir("tls");
+        let created = TlsIdentity::load_or_create_in(&dir).unwrap();
+        let loaded = TlsIdentity::load_or_create_in(&dir).unwrap();
+        assert_eq!(created.fingerprint(), loaded.fingerprint());
+        assert_eq!(created.fingerprint().len(), 64);
+        assert!(matches_fingerprint(
+            loaded.cert_der(),
+            &created.fingerprint().to_uppercase()
+        ));
+
+        let mode = fs::metadata(dir.join("key.pem")).unwrap().permissions();
+        assert_eq!(
+            std::os::unix::fs::PermissionsExt::mode(&mode) & 0o777,
+            0o600
+        );
+        assert!(loaded.acceptor().is_ok());
+
+        let other = TlsIdentity::generate().unwrap();
+        assert!(!matches_fingerprint(
+            other.cert_der(),
+            &created.fingerprint()
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,518 @@
+//! 运行环境诊断
+//!
+//! 检查 BlueZ、蓝牙适配器、进程权限、NetworkManager、rfkill、polkit 授权和防火墙，
+//! 每项检查给出发现的问题（[`Issue`]）。问题只携带数据和稳定的 ID，
+//! 展示文本和修复建议由前端按 ID 从文本目录中取（见 `cattysend doctor`）。
+
+use crate::wifi::nm_dbus::{PERMISSION_MODIFY_SYSTEM, PERMISSION_NETWORK_CONTROL};
+use std::path::Path;
+use std::process::Command;
+
+/// 建议的最低 BlueZ 版本（更早的版本缺少扫描响应相关的实验性接口）
+pub const MIN_BLUEZ_VERSION: (u32, u32) = (5, 60);
+
+/// CAP_NET_ADMIN 的位号
+pub const CAP_NET_ADMIN: u32 = 12;
+/// CAP_NET_RAW 的位号
+pub const CAP_NET_RAW: u32 = 13;
+
+/// 创建热点需要的 NetworkManager polkit 授权
+pub const REQUIRED_NM_PERMISSIONS: &[&str] = &[
+    PERMISSION_NETWORK_CONTROL,
+    PERMISSION_MODIFY_SYSTEM,
+    "org.freedesktop.NetworkManager.wifi.share.protected",
+];
+
+/// 检查项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    Bluez,
+    Adapter,
+    Capabilities,
+    NetworkManager,
+    Rfkill,
+    Polkit,
+    Firewall,
+}
+
+impl Check {
+    /// 稳定的 ID（用作文本目录的 key）
+    pub fn id(&self) -> &'static str {
+        match self {
+            Check::Bluez => "bluez",
+            Check::Adapter => "adapter",
+            Check::Capabilities => "capabilities",
+            Check::NetworkManager => "network_manager",
+            Check::Rfkill => "rfkill",
+            Check::Polkit => "polkit",
+            Check::Firewall => "firewall",
+        }
+    }
+}
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Ok,
+    /// 可能影响部分功能
+    Warning,
+    /// 收发文件会失败
+    Error,
+}
+
+/// 检查中发现的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// 找不到 bluetoothctl / bluetoothd
+    BluezNotInstalled,
+    /// BlueZ 版本低于 [`MIN_BLUEZ_VERSION`]
+    BluezOutdated { version: String },
+    /// 未启用 BlueZ 实验性功能
+    ExperimentalDisabled,
+    /// 无法连接 bluetoothd
+    BluezUnavailable { error: String },
+    /// 没有蓝牙适配器
+    NoAdapter,
+    /// 适配器未上电
+    AdapterPoweredOff { adapter: String },
+    /// 缺少 Linux capability
+    MissingCapabilities { caps: Vec<&'static str> },
+    /// 未安装 NetworkManager (nmcli)
+    NetworkManagerMissing,
+    /// NetworkManager 未运行
+    NetworkManagerNotRunning,
+    /// 被 rfkill 软件屏蔽
+    SoftBlocked { device: String },
+    /// 被硬件开关屏蔽
+    HardBlocked { device: String },
+    /// polkit 拒绝了创建热点需要的操作
+    PolkitDenied { actions: Vec<String> },
+    /// polkit 需要交互式认证（后台运行时会失败）
+    PolkitNeedsAuth { actions: Vec<String> },
+    /// 防火墙在运行，可能拦截随机传输端口和 mDNS
+    FirewallActive { name: String },
+}
+
+impl Issue {
+    /// 稳定的 ID（用作文本目录的 key）
+    pub fn id(&self) -> &'static str {
+        match self {
+            Issue::BluezNotInstalled => "bluez_not_installed",
+            Issue::BluezOutdated { .. } => "bluez_outdated",
+            Issue::ExperimentalDisabled => "experimental_disabled",
+            Issue::BluezUnavailable { .. } => "bluez_unavailable",
+            Issue::NoAdapter => "no_adapter",
+            Issue::AdapterPoweredOff { .. } => "adapter_powered_off",
+            Issue::MissingCapabilities { .. } => "missing_capabilities",
+            Issue::NetworkManagerMissing => "network_manager_missing",
+            Issue::NetworkManagerNotRunning => "network_manager_not_running",
+            Issue::SoftBlocked { .. } => "soft_blocked",
+            Issue::HardBlocked { .. } => "hard_blocked",
+            Issue::PolkitDenied { .. } => "polkit_denied",
+            Issue::PolkitNeedsAuth { .. } => "polkit_needs_auth",
+            Issue::FirewallActive { .. } => "firewall_active",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::BluezOutdated { .. }
+            | Issue::ExperimentalDisabled
+            | Issue::PolkitNeedsAuth { .. }
+            | Issue::FirewallActive { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// 问题附带的数据（版本号、设备名、缺少的权限等），没有时为空
+    pub fn detail(&self) -> String {
+        match self {
+            Issue::BluezOutdated { version } => version.clone(),
+            Issue::BluezUnavailable { error } => error.clone(),
+            Issue::AdapterPoweredOff { adapter } => adapter.clone(),
+            Issue::MissingCapabilities { caps } => caps.join(", "),
+            Issue::SoftBlocked { device } | Issue::HardBlocked { device } => device.clone(),
+            Issue::PolkitDenied { actions } | Issue::PolkitNeedsAuth { actions } => {
+                actions.join(", ")
+            }
+            Issue::FirewallActive { name } => name.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// 单项检查的结果
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub check: Check,
+    /// 检查到的信息（版本号、适配器名等）
+    pub summary: Option<String>,
+    pub issues: Vec<Issue>,
+}
+
+impl CheckResult {
+    fn new(check: Check) -> Self {
+        Self {
+            check,
+            summary: None,
+            issues: Vec::new(),
+        }
+    }
+
+    /// 最严重的问题等级
+    pub fn severity(&self) -> Severity {
+        self.issues
+            .iter()
+            .map(Issue::severity)
+            .max()
+            .unwrap_or(Severity::Ok)
+    }
+}
+
+/// 诊断报告
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub checks: Vec<CheckResult>,
+}
+
+impl Report {
+    /// 所有问题
+    pub fn issues(&self) -> impl Iterator<Item = &Issue> {
+        self.checks.iter().flat_map(|c| c.issues.iter())
+    }
+
+    /// 是否有会导致收发失败的问题
+    pub fn has_errors(&self) -> bool {
+        self.issues().any(|i| i.severity() == Severity::Error)
+    }
+}
+
+/// 运行全部检查
+pub async fn run() -> Report {
+    let nm = check_network_manager();
+    let nm_available = !nm.issues.contains(&Issue::NetworkManagerMissing);
+    let mut checks = vec![
+        check_bluez(),
+        check_adapter().await,
+        check_capabilities(),
+        nm,
+        check_rfkill(Path::new("/sys/class/rfkill")),
+    ];
+    if nm_available {
+        checks.push(check_polkit());
+    }
+    checks.push(check_firewall().await);
+    Report { checks }
+}
+
+fn check_bluez() -> CheckResult {
+    let mut result = CheckResult::new(Check::Bluez);
+    let version = ["bluetoothctl", "bluetoothd"]
+        .iter()
+        .find_map(|cmd| command_output(cmd, &["--version"]))
+        .and_then(|out| parse_bluez_version(&out));
+    let Some(version) = version else {
+        result.issues.push(Issue::BluezNotInstalled);
+        return result;
+    };
+
+    let version_str = format!("{}.{}", version.0, version.1);
+    if version < MIN_BLUEZ_VERSION {
+        result.issues.push(Issue::BluezOutdated {
+            version: version_str.clone(),
+        });
+    }
+    result.summary = Some(version_str);
+
+    let config = std::fs::read_to_string("/etc/bluetooth/main.conf").unwrap_or_default();
+    if !experimental_in_config(&config) && !bluetoothd_has_experimental_flag() {
+        result.issues.push(Issue::ExperimentalDisabled);
+    }
+    result
+}
+
+async fn check_adapter() -> CheckResult {
+    let mut result = CheckResult::new(Check::Adapter);
+    let session = match bluer::Session::new().await {
+        Ok(session) => session,
+        Err(e) => {
+            result.issues.push(Issue::BluezUnavailable {
+                error: e.to_string(),
+            });
+            return result;
+        }
+    };
+    let adapter = match session.default_adapter().await {
+        Ok(adapter) => adapter,
+        Err(_) => {
+            result.issues.push(Issue::NoAdapter);
+            return result;
+        }
+    };
+    let name = adapter.name().to_string();
+    if !adapter.is_powered().await.unwrap_or(false) {
+        result.issues.push(Issue::AdapterPoweredOff {
+            adapter: name.clone(),
+        });
+    }
+    result.summary = Some(name);
+    result
+}
+
+fn check_capabilities() -> CheckResult {
+    let mut result = CheckResult::new(Check::Capabilities);
+    let mut caps = effective_capabilities().unwrap_or(0);
+    // 有特权助手时，需要 CAP_NET_ADMIN 的命令由它代为执行
+    if crate::wifi::helper::locate()
+        .is_some_and(|helper| crate::wifi::helper::is_privileged(&helper))
+    {
+        caps |= 1 << CAP_NET_ADMIN;
+    }
+    let missing: Vec<_> = [
+        (CAP_NET_ADMIN, "CAP_NET_ADMIN"),
+        (CAP_NET_RAW, "CAP_NET_RAW"),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| caps & (1 << bit) == 0)
+    .map(|(_, name)| name)
+    .collect();
+    if !missing.is_empty() {
+        result
+            .issues
+            .push(Issue::MissingCapabilities { caps: missing });
+    }
+    result
+}
+
+fn check_network_manager() -> CheckResult {
+    let mut result = CheckResult::new(Check::NetworkManager);
+    let Some(version) = command_output("nmcli", &["--version"]) else {
+        result.issues.push(Issue::NetworkManagerMissing);
+        return result;
+    };
+    result.summary = version.split_whitespace().last().map(str::to_string);
+
+    let running = command_output("nmcli", &["-t", "-f", "RUNNING", "general"])
+        .is_some_and(|out| out.trim() == "running");
+    if !running {
+        result.issues.push(Issue::NetworkManagerNotRunning);
+    }
+    result
+}
+
+fn check_rfkill(dir: &Path) -> CheckResult {
+    let mut result = CheckResult::new(Check::Rfkill);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return result;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let read = |name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        let kind = read("type");
+        if kind != "bluetooth" && kind != "wlan" {
+            continue;
+        }
+        let device = format!("{} ({})", read("name"), kind);
+        if read("hard") == "1" {
+            result.issues.push(Issue::HardBlocked { device });
+        } else if read("soft") == "1" {
+            result.issues.push(Issue::SoftBlocked { device });
+        }
+    }
+    result
+}
+
+fn check_polkit() -> CheckResult {
+    let mut result = CheckResult::new(Check::Polkit);
+    let Some(out) = command_output(
+        "nmcli",
+        &["-t", "-f", "PERMISSION,VALUE", "general", "permissions"],
+    ) else {
+        return result;
+    };
+    let (denied, auth) = classify_nm_permissions(&out);
+    if !denied.is_empty() {
+        result.issues.push(Issue::PolkitDenied { actions: denied });
+    }
+    if !auth.is_empty() {
+        result.issues.push(Issue::PolkitNeedsAuth { actions: auth });
+    }
+    result
+}
+
+async fn check_firewall() -> CheckResult {
+    let mut result = CheckResult::new(Check::Firewall);
+    for kind in crate::firewall::detect().await {
+        result.issues.push(Issue::FirewallActive {
+            name: kind.name().to_string(),
+        });
+    }
+    result
+}
+
+/// 运行命令，成功时返回标准输出
+fn command_output(cmd: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(cmd).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 当前进程的有效 capability 位图（`/proc/self/status` 中的 `CapEff`）
+pub fn effective_capabilities() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    parse_cap_eff(&status)
+}
+
+fn parse_cap_eff(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))
+        .and_then(|hex| u64::from_str_radix(hex.trim(), 16).ok())
+}
+
+/// 从 `bluetoothctl --version`（"bluetoothctl: 5.72"）或 `bluetoothd --version`（"5.72"）解析版本
+fn parse_bluez_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.split_whitespace().last()?;
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// `main.conf` 的 `[General]` 段中是否设置了 `Experimental = true`
+fn experimental_in_config(config: &str) -> bool {
+    let mut in_general = false;
+    for line in config.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_general = line == "[General]";
+        } else if in_general
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "Experimental"
+        {
+            return value.trim().eq_ignore_ascii_case("true");
+        }
+    }
+    false
+}
+
+/// bluetoothd 是否以 `-E` / `--experimental` 启动
+fn bluetoothd_has_experimental_flag() -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let Ok(cmdline) = std::fs::read(entry.path().join("cmdline")) else {
+            return false;
+        };
+        let mut args = cmdline
+            .split(|b| *b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).into_owned());
+        args.next().is_some_and(|bin| bin.ends_with("bluetoothd"))
+            && args.any(|arg| arg == "-E" || arg == "--experimental")
+    })
+}
+
+/// 把 `nmcli -t general permissions` 的输出分为（被拒绝的, 需要认证的）必需操作
+fn classify_nm_permissions(output: &str) -> (Vec<String>, Vec<String>) {
+    let mut denied = Vec::new();
+    let mut auth = Vec::new();
+    for line in output.lines() {
+        let Some((action, value)) = line.rsplit_once(':') else {
+            continue;
+        };
+        if !REQUIRED_NM_PERMISSIONS.contains(&action) {
+            continue;
+        }
+        match value.trim() {
+            "yes" => {}
+            "auth" => auth.push(action.to_string()),
+            _ => denied.push(action.to_string()),
+        }
+    }
+    (denied, auth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_parse_bluez_version() {
+        assert_eq!(parse_bluez_version("bluetoothctl: 5.72\n"), Some((5, 72)));
+        assert_eq!(parse_bluez_version("5.66\n"), Some((5, 66)));
+        assert_eq!(parse_bluez_version(""), None);
+        assert!(Some((5, 55)) < Some(MIN_BLUEZ_VERSION));
+    }
+
+    #[test]
+    fn test_parse_cap_eff() {
+        let status = "Name:\tcat\nCapInh:\t0000000000000000\nCapEff:\t0000000000003000\n";
+        let caps = parse_cap_eff(status).unwrap();
+        assert_ne!(caps & (1 << CAP_NET_ADMIN), 0);
+        assert_ne!(caps & (1 << CAP_NET_RAW), 0);
+        assert_eq!(parse_cap_eff("Name:\tcat\n"), None);
+    }
+
+    #[test]
+    fn test_experimental_in_config() {
+        assert!(experimental_in_config("[General]\nExperimental = true\n"));
+        assert!(!experimental_in_config("[General]\n#Experimental = true\n"));
+        assert!(!experimental_in_config("[Policy]\nExperimental = true\n"));
+        assert!(!experimental_in_config("[General]\nExperimental=false\n"));
+    }
+
+    #[test]
+    fn test_classify_nm_permissions() {
+        let output = "org.freedesktop.NetworkManager.network-control:yes\n\
+            org.freedesktop.NetworkManager.settings.modify.system:auth\n\
+            org.freedesktop.NetworkManager.wifi.share.protected:no\n\
+            org.freedesktop.NetworkManager.sleep-wake:no\n";
+        let (denied, auth) = classify_nm_permissions(output);
+        assert_eq!(
+            denied,
+            vec!["org.freedesktop.NetworkManager.wifi.share.protected"]
+        );
+        assert_eq!(
+            auth,
+            vec!["org.freedesktop.NetworkManager.settings.modify.system"]
+        );
+    }
+
+    #[test]
+    fn test_check_rfkill() {
+        let root = temp_dir("rfkill");
+        for (dir, kind, soft, hard) in [
+            ("rfkill0", "bluetooth", "1", "0"),
+            ("rfkill1", "wlan", "0", "1"),
+            ("rfkill2", "nfc", "1", "0"),
+        ] {
+            let path = root.join(dir);
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(path.join("type"), kind).unwrap();
+            std::fs::write(path.join("name"), dir).unwrap();
+            std::fs::write(path.join("soft"), soft).unwrap();
+            std::fs::write(path.join("hard"), hard).unwrap();
+        }
+
+        let mut issues = check_rfkill(&root).issues;
+        issues.sort_by_key(|i| i.id());
+        assert_eq!(
+            issues,
+            vec![
+                Issue::HardBlocked {
+                    device: "rfkill1 (wlan)".to_string()
+                },
+                Issue::SoftBlocked {
+                    device: "rfkill0 (bluetooth)".to_string()
+                },
+            ]
+        );
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
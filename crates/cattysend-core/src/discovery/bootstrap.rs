@@ -0,0 +1,122 @@
+//! 二维码引导
+//!
+//! 蓝牙不可用时，发送端把本该写入 CHAR_P2P 的 P2P 信息编码成一段文本，
+//! 以二维码显示（或直接复制），接收端扫描/粘贴后走与 BLE 相同的接收流程。
+//!
+//! 格式为 `cattysend:p2p:` 加上 P2P 信息 JSON 的 base64url（无填充）。
+//! JSON 与 BLE 写入的内容完全相同：
+//!
+//! - 发送端事先知道接收端公钥（见 `cattysend identity show`）时，
+//!   用 ECDH 会话密钥加密 SSID/PSK/MAC，双方都能看到验证码
+//! - 否则为明文，看到二维码的人都能接入热点，与分享 WiFi 二维码相同
+
+use crate::ble::P2pReceiveEvent;
+use crate::ble::client::build_p2p_payload_for_key;
+use crate::ble::server::process_p2p_write;
+use crate::crypto::BleSecurityPersistent;
+use crate::wifi::P2pInfo;
+use anyhow::Context;
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+
+/// 引导载荷的前缀
+pub const PAYLOAD_PREFIX: &str = "cattysend:p2p:";
+
+/// 把 P2P 信息编码为引导载荷
+///
+/// `peer_key` 为接收端公钥（SPKI base64），为 `None` 时不加密。
+pub fn encode(
+    p2p_info: &P2pInfo,
+    sender_id: &str,
+    peer_key: Option<&str>,
+    security: &BleSecurityPersistent,
+) -> anyhow::Result<String> {
+    let data = build_p2p_payload_for_key(peer_key, p2p_info, sender_id, Some(security))?;
+    Ok(format!(
+        "{}{}",
+        PAYLOAD_PREFIX,
+        URL_SAFE_NO_PAD.encode(data)
+    ))
+}
+
+/// 解析引导载荷，加密时用本机身份 `security` 解密
+///
+/// 载荷中的空白（手动输入或换行粘贴时带入）会被忽略。
+/// 载荷是发给其他设备的（用别的公钥加密）时，解密出的字段不合法，返回错误。
+pub fn decode(payload: &str, security: &BleSecurityPersistent) -> anyhow::Result<P2pReceiveEvent> {
+    let payload: String = payload.chars().filter(|c| !c.is_whitespace()).collect();
+    let encoded = payload
+        .strip_prefix(PAYLOAD_PREFIX)
+        .context("Not a cattysend bootstrap payload")?;
+    let data = URL_SAFE_NO_PAD
+        .decode(encoded)
+        .context("Bootstrap payload is not valid base64url")?;
+    let event = process_p2p_write(&data, Some(security))
+        .context("Bootstrap payload is not valid P2P info for this device")?;
+    if event.p2p_info.is_group_request() {
+        anyhow::bail!("Bootstrap payload must carry the sender's P2P info");
+    }
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hotspot() -> P2pInfo {
+        P2pInfo::new(
+            "DIRECT-ab-cattysend".to_string(),
+            "12345678".to_string(),
+            "02:11:22:33:44:55".to_string(),
+            52000,
+        )
+    }
+
+    #[test]
+    fn test_plain_round_trip() {
+        let sender = BleSecurityPersistent::new().unwrap();
+        let receiver = BleSecurityPersistent::new().unwrap();
+
+        let payload = encode(&hotspot(), "a1b2", None, &sender).unwrap();
+        assert!(payload.starts_with(PAYLOAD_PREFIX));
+
+        // 分行粘贴的载荷同样可以解析
+        let (head, tail) = payload.split_at(30);
+        let event = decode(&format!(" {}\n{} ", head, tail), &receiver).unwrap();
+        assert_eq!(event.p2p_info.ssid, "DIRECT-ab-cattysend");
+        assert_eq!(event.p2p_info.port, 52000);
+        assert_eq!(event.sender_public_key, None);
+    }
+
+    #[test]
+    fn test_encrypted_for_receiver() {
+        let sender = BleSecurityPersistent::new().unwrap();
+        let receiver = BleSecurityPersistent::new().unwrap();
+
+        let payload = encode(&hotspot(), "a1b2", Some(receiver.get_public_key()), &sender).unwrap();
+        let event = decode(&payload, &receiver).unwrap();
+        assert_eq!(event.p2p_info.psk, "12345678");
+        assert_eq!(event.p2p_info.mac, "02:11:22:33:44:55");
+        assert_eq!(
+            event.sender_public_key.as_deref(),
+            Some(sender.get_public_key())
+        );
+
+        // 其他设备解不开
+        let other = BleSecurityPersistent::new().unwrap();
+        assert!(decode(&payload, &other).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let security = BleSecurityPersistent::new().unwrap();
+        assert!(decode("https://example.com", &security).is_err());
+        assert!(decode("cattysend:p2p:!!!", &security).is_err());
+
+        let json = URL_SAFE_NO_PAD.encode(br#"{"ssid":"x"}"#);
+        assert!(decode(&format!("{}{}", PAYLOAD_PREFIX, json), &security).is_err());
+
+        let request = P2pInfo::group_request("02:11:22:33:44:55".to_string());
+        let payload = encode(&request, "a1b2", None, &security).unwrap();
+        assert!(decode(&payload, &security).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! 热点上的主机名广播
+//!
+//! 发送端创建热点后通过 mDNS 把自己发布为 `catshare.local`，接收端接入热点后先解析这个
+//! 主机名，解析不到再按 [`sender_addr`](crate::wifi::sender_addr) 的顺序推断发送端地址。
+//! 这样不依赖热点网段和 GO 实现（NetworkManager、wpa_cli、Android）的地址分配习惯。
+//!
+//! 主机名挂在 `_catshare-transfer._tcp` 服务上发布，端口即传输端口。
+//! CatShare 不发布该主机名，与其互通时接收端会在超时后回退到地址推断。
+
+use log::{debug, info};
+use mdns_sd::{HostnameResolutionEvent, ServiceDaemon, ServiceInfo};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+/// 发送端在热点上使用的主机名
+pub const SENDER_HOSTNAME: &str = "catshare.local.";
+
+/// 承载主机名的服务类型
+const TRANSFER_SERVICE_TYPE: &str = "_catshare-transfer._tcp.local.";
+
+/// 发送端主机名广播，drop 时注销
+pub struct HostAdvertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for HostAdvertisement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// 把 `ip`（热点地址）发布为 [`SENDER_HOSTNAME`]
+pub fn advertise_sender(ip: Ipv4Addr, port: u16) -> anyhow::Result<HostAdvertisement> {
+    let daemon = ServiceDaemon::new()?;
+    let service = ServiceInfo::new(
+        TRANSFER_SERVICE_TYPE,
+        "cattysend",
+        SENDER_HOSTNAME,
+        ip.to_string().as_str(),
+        port,
+        HashMap::<String, String>::new(),
+    )?;
+    let fullname = service.get_fullname().to_string();
+    daemon.register(service)?;
+    info!("Advertising {} as {} (port {})", SENDER_HOSTNAME, ip, port);
+    Ok(HostAdvertisement { daemon, fullname })
+}
+
+/// 解析 [`SENDER_HOSTNAME`]（接收端）
+///
+/// 只接受与 `local_ip` 同一 /24 网段的地址：双连接时原有网络里可能也有发送端在广播。
+pub async fn resolve_sender(local_ip: Ipv4Addr, timeout: Duration) -> Option<Ipv4Addr> {
+    let daemon = ServiceDaemon::new().ok()?;
+    let events = daemon
+        .resolve_hostname(SENDER_HOSTNAME, Some(timeout.as_millis() as u64))
+        .ok()?;
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut found = None;
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+        match event {
+            HostnameResolutionEvent::AddressesFound(_, addresses) => {
+                found = addresses.iter().find_map(|ip| match ip {
+                    IpAddr::V4(ip) if same_subnet(*ip, local_ip) => Some(*ip),
+                    _ => None,
+                });
+                if found.is_some() {
+                    break;
+                }
+                debug!(
+                    "Ignoring {} on other networks: {:?}",
+                    SENDER_HOSTNAME, addresses
+                );
+            }
+            HostnameResolutionEvent::SearchTimeout(_) => break,
+            _ => {}
+        }
+    }
+    let _ = daemon.shutdown();
+    found
+}
+
+/// 是否在同一 /24 网段（热点网段均为 /24）
+fn same_subnet(a: Ipv4Addr, b: Ipv4Addr) -> bool {
+    a.octets()[..3] == b.octets()[..3]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_subnet() {
+        let local = Ipv4Addr::new(192, 168, 49, 23);
+        assert!(same_subnet(Ipv4Addr::new(192, 168, 49, 200), local));
+        assert!(!same_subnet(Ipv4Addr::new(10, 42, 0, 1), local));
+    }
+}
@@ -0,0 +1,456 @@
+//! 局域网发现 (mDNS/DNS-SD)
+//!
+//! 接收端通过 mDNS 发布 `_catshare._tcp` 服务，并在服务端口上提供一个
+//! 与 GATT 服务等价的 TCP 握手：
+//!
+//! 1. 发送端连接后，接收端先写一行 DeviceInfo JSON（相当于读取 STATUS 特征）
+//! 2. 发送端写一行 P2pInfo JSON（相当于写入 P2P 特征，加密规则完全相同）
+//! 3. 接收端回复 `ok` 或 `error`
+//!
+//! # 信任模型
+//!
+//! 与 BLE 一样，握手本身不认证发送端：任何能连上端口的设备都能读到 DeviceInfo（只含公钥）
+//! 并写入 P2P 信息。因此：
+//!
+//! - 只在一个接口上监听和发布（默认为默认路由所在的接口，见 [`LanAdvertiser::with_bind_addr`]）：
+//!   mDNS 只在该接口上收发，握手只接受与该接口同一子网的对端，经网关转发来的连接直接断开
+//! - 设置了安全上下文时只接受用 ECDH 会话密钥加密的 P2P 信息，明文一律拒绝；
+//!   两端显示的验证码一致才说明中间没有人替换过公钥
+//! - 收到 P2P 信息后仍按接收流程确认发送请求，握手成功不等于接受文件
+//!
+//! TXT 记录:
+//! - `name`: 设备名称
+//! - `id`: sender ID (4 位十六进制)
+//! - `brand`: 厂商 ID
+//! - `5g`: 是否支持 5GHz (`1`/`0`)
+//! - `wpa3`: 是否能接入 WPA3-SAE 热点 (`1`/`0`)
+
+use crate::ble::client::{BleClientError, HandshakeStep, build_p2p_payload};
+use crate::ble::scanner::get_vendor_name;
+use crate::ble::server::process_p2p_write;
+use crate::ble::{
+    DeviceInfo, DiscoveredDevice, P2pReceiveEvent, ReceiverInfo, ReceiverState, ScanCallback,
+};
+use crate::config::BrandId;
+use crate::crypto::BleSecurityPersistent;
+use crate::wifi::P2pInfo;
+use crate::wifi::command::{self, CommandRunner, SystemRunner};
+use log::{debug, info, warn};
+use mdns_sd::{IfKind, ServiceDaemon, ServiceEvent, ServiceInfo};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// mDNS 服务类型
+pub const LAN_SERVICE_TYPE: &str = "_catshare._tcp.local.";
+
+/// 握手单行最大长度（P2pInfo 加密后通常不超过 512 字节）
+const MAX_LINE_LEN: u64 = 4096;
+
+/// 握手超时
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 局域网广播器（接收端）
+pub struct LanAdvertiser {
+    device_name: String,
+    sender_id: String,
+    device_info: DeviceInfo,
+    brand_id: BrandId,
+    supports_5ghz: bool,
+    supports_wpa3: bool,
+    security: Option<Arc<BleSecurityPersistent>>,
+    bind_addr: Option<IpAddr>,
+}
+
+impl LanAdvertiser {
+    pub fn new(device_name: String, sender_id: String, device_info: DeviceInfo) -> Self {
+        Self {
+            device_name,
+            sender_id,
+            device_info,
+            brand_id: BrandId::Linux,
+            supports_5ghz: true,
+            supports_wpa3: false,
+            security: None,
+            bind_addr: None,
+        }
+    }
+
+    /// 设置安全上下文，用于自动解密 P2P 信息
+    pub fn with_security(mut self, security: Arc<BleSecurityPersistent>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    /// 只在 `addr` 所在的接口上监听和发布（默认取默认路由对应的本机地址）
+    pub fn with_bind_addr(mut self, addr: IpAddr) -> Self {
+        self.bind_addr = Some(addr);
+        self
+    }
+
+    /// 设置厂商 ID
+    pub fn with_brand(mut self, brand_id: BrandId) -> Self {
+        self.brand_id = brand_id;
+        self
+    }
+
+    /// 设置 5GHz 支持
+    pub fn with_5ghz_support(mut self, supports_5ghz: bool) -> Self {
+        self.supports_5ghz = supports_5ghz;
+        self
+    }
+
+    /// 设置 WPA3-SAE 支持
+    pub fn with_wpa3_support(mut self, supports_wpa3: bool) -> Self {
+        self.supports_wpa3 = supports_wpa3;
+        self
+    }
+
+    /// 启动握手监听并发布 mDNS 服务
+    pub async fn start(
+        self,
+    ) -> anyhow::Result<(LanAdvertiserHandle, mpsc::Receiver<P2pReceiveEvent>)> {
+        let bind_ip = match self.bind_addr {
+            Some(ip) => ip,
+            None => local_ip_towards(None)?,
+        };
+        let interface = LanInterface::find(&SystemRunner, bind_ip)?;
+        let listener = TcpListener::bind(SocketAddr::new(bind_ip, 0)).await?;
+        let port = listener.local_addr()?.port();
+
+        // 只在选定的接口上收发 mDNS
+        let daemon = ServiceDaemon::new()?;
+        daemon.disable_interface(IfKind::All)?;
+        daemon.enable_interface(IfKind::Name(interface.name.clone()))?;
+        let host = hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "cattysend".to_string());
+        let instance = format!("{}-{}", self.device_name, self.sender_id);
+        let brand = self.brand_id.id().to_string();
+        let properties = [
+            ("name", self.device_name.as_str()),
+            ("id", self.sender_id.as_str()),
+            ("brand", brand.as_str()),
+            ("5g", if self.supports_5ghz { "1" } else { "0" }),
+            ("wpa3", if self.supports_wpa3 { "1" } else { "0" }),
+        ];
+        let service = ServiceInfo::new(
+            LAN_SERVICE_TYPE,
+            &instance,
+            &format!("{}.local.", host),
+            bind_ip.to_string(),
+            port,
+            &properties[..],
+        )?;
+        let fullname = service.get_fullname().to_string();
+        daemon.register(service)?;
+
+        info!(
+            "LAN discovery published: {} on {}:{} via {} (name='{}')",
+            LAN_SERVICE_TYPE, bind_ip, port, interface.name, self.device_name
+        );
+
+        let (p2p_tx, p2p_rx) = mpsc::channel(16);
+        let device_info = Arc::new(std::sync::Mutex::new(self.device_info));
+        let shared_info = device_info.clone();
+        let security = self.security;
+
+        let task = tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        warn!("LAN handshake accept failed: {}", e);
+                        continue;
+                    }
+                };
+                if !interface.contains(peer.ip()) {
+                    warn!(
+                        "Rejecting LAN handshake from {} (not on the subnet of {})",
+                        peer, interface.name
+                    );
+                    continue;
+                }
+                debug!("LAN handshake connection from {}", peer);
+                let device_info = device_info.lock().unwrap().clone();
+                let security = security.clone();
+                let p2p_tx = p2p_tx.clone();
+                tokio::spawn(async move {
+                    let result = tokio::time::timeout(
+                        HANDSHAKE_TIMEOUT,
+                        serve_handshake(stream, &device_info, security.as_deref(), &p2p_tx),
+                    )
+                    .await;
+                    match result {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => warn!("LAN handshake with {} failed: {}", peer, e),
+                        Err(_) => warn!("LAN handshake with {} timed out", peer),
+                    }
+                });
+            }
+        });
+
+        Ok((
+            LanAdvertiserHandle {
+                daemon,
+                fullname,
+                port,
+                task,
+                device_info: shared_info,
+            },
+            p2p_rx,
+        ))
+    }
+}
+
+/// 局域网广播句柄，drop 时注销 mDNS 服务并停止监听
+pub struct LanAdvertiserHandle {
+    daemon: ServiceDaemon,
+    fullname: String,
+    port: u16,
+    task: JoinHandle<()>,
+    device_info: Arc<std::sync::Mutex<DeviceInfo>>,
+}
+
+impl LanAdvertiserHandle {
+    /// 握手监听端口
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// 切换握手时返回的 DeviceInfo 状态
+    pub fn set_state(&self, state: ReceiverState) {
+        self.device_info.lock().unwrap().state = state.code();
+    }
+}
+
+impl Drop for LanAdvertiserHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// 发布服务和接受握手的接口
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LanInterface {
+    name: String,
+    addr: Ipv4Addr,
+    /// 网络前缀长度
+    prefix: u8,
+}
+
+impl LanInterface {
+    /// 查找本机地址 `addr` 所在的接口（只支持 IPv4）
+    fn find(runner: &dyn CommandRunner, addr: IpAddr) -> anyhow::Result<Self> {
+        let IpAddr::V4(addr) = addr else {
+            anyhow::bail!("LAN discovery needs an IPv4 address, got {}", addr);
+        };
+        let output = runner.run("ip", &["-o", "addr", "show"])?;
+        let (name, prefix) = command::interface_with_ipv4(&output.stdout, addr)
+            .ok_or_else(|| anyhow::anyhow!("No interface has the address {}", addr))?;
+        Ok(Self { name, addr, prefix })
+    }
+
+    /// `peer` 是否与本接口在同一子网
+    fn contains(&self, peer: IpAddr) -> bool {
+        let IpAddr::V4(peer) = peer else {
+            return false;
+        };
+        let mask = u32::MAX
+            .checked_shl(32u32.saturating_sub(self.prefix as u32))
+            .unwrap_or(0);
+        u32::from(peer) & mask == u32::from(self.addr) & mask
+    }
+}
+
+/// 处理一次握手（接收端）
+async fn serve_handshake(
+    stream: TcpStream,
+    device_info: &DeviceInfo,
+    security: Option<&BleSecurityPersistent>,
+    p2p_tx: &mpsc::Sender<P2pReceiveEvent>,
+) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader).take(MAX_LINE_LEN);
+
+    writer
+        .write_all(serde_json::to_string(device_info)?.as_bytes())
+        .await?;
+    writer.write_all(b"\n").await?;
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    if line.trim().is_empty() {
+        anyhow::bail!("peer closed connection before sending P2P info");
+    }
+
+    match process_p2p_write(line.trim().as_bytes(), security) {
+        // 局域网上任何设备都能连上来，不接受明文 P2P 信息
+        Ok(event) if security.is_some() && event.sender_public_key.is_none() => {
+            writer.write_all(b"error\n").await?;
+            anyhow::bail!("refusing unencrypted P2P info over LAN")
+        }
+        Ok(event) => {
+            writer.write_all(b"ok\n").await?;
+            p2p_tx
+                .send(event)
+                .await
+                .map_err(|_| anyhow::anyhow!("P2P channel closed"))?;
+            Ok(())
+        }
+        Err(e) => {
+            writer.write_all(b"error\n").await?;
+            Err(e)
+        }
+    }
+}
+
+/// 通过局域网执行 P2P 握手（发送端）
+///
+/// 返回接收端的 DeviceInfo；步骤划分与 BLE 握手相同，每进入一步调用一次 `on_step`
+#[tracing::instrument(skip_all, fields(endpoint = %endpoint))]
+pub async fn lan_handshake(
+    endpoint: SocketAddr,
+    p2p_info: &P2pInfo,
+    sender_id: &str,
+    security: Option<&BleSecurityPersistent>,
+    on_step: impl Fn(HandshakeStep) + Send + Sync,
+) -> anyhow::Result<DeviceInfo> {
+    info!("Connecting to LAN peer: {}", endpoint);
+    let stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, TcpStream::connect(endpoint)).await??;
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader).take(MAX_LINE_LEN);
+
+    on_step(HandshakeStep::ReadingStatus);
+    let mut line = String::new();
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, reader.read_line(&mut line)).await??;
+    let device_info: DeviceInfo = serde_json::from_str(line.trim())
+        .map_err(|e| anyhow::anyhow!("Invalid DeviceInfo from {}: {}", endpoint, e))?;
+    if ReceiverInfo::from(&device_info).is_busy() {
+        return Err(BleClientError::DeviceBusy.into());
+    }
+
+    let payload = build_p2p_payload(&device_info, p2p_info, sender_id, security)?;
+    on_step(HandshakeStep::WritingP2p);
+    writer.write_all(&payload).await?;
+    writer.write_all(b"\n").await?;
+
+    line.clear();
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, reader.read_line(&mut line)).await??;
+    if line.trim() != "ok" {
+        return Err(BleClientError::P2pRejected.into());
+    }
+
+    Ok(device_info)
+}
+
+/// 选择通往 `peer` 的本机 IP
+///
+/// 通过 UDP "connect" 让内核选路，不会真正发包。
+/// `peer` 未知时使用默认路由对应的地址。
+pub fn local_ip_towards(peer: Option<IpAddr>) -> std::io::Result<IpAddr> {
+    let target = peer.unwrap_or(IpAddr::from([192, 0, 2, 1]));
+    let unspecified = if target.is_ipv6() {
+        IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+    } else {
+        IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+    };
+    let socket = std::net::UdpSocket::bind(SocketAddr::new(unspecified, 0))?;
+    socket.connect(SocketAddr::new(target, 9))?;
+    Ok(socket.local_addr()?.ip())
+}
+
+/// 浏览局域网内的 Cattysend 设备
+pub async fn browse(
+    timeout: Duration,
+    callback: Option<Arc<dyn ScanCallback>>,
+) -> anyhow::Result<Vec<DiscoveredDevice>> {
+    let daemon = ServiceDaemon::new()?;
+    let events = daemon.browse(LAN_SERVICE_TYPE)?;
+    let mut found: HashMap<String, DiscoveredDevice> = HashMap::new();
+
+    info!("Starting LAN discovery for {}s", timeout.as_secs());
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+        if let ServiceEvent::ServiceResolved(info) = event
+            && !found.contains_key(info.get_fullname())
+            && let Some(device) = device_from_service(&info)
+        {
+            debug!("Found LAN device: {} ({})", device.name, device.address);
+            if let Some(cb) = &callback {
+                cb.on_device_found(device.clone()).await;
+            }
+            found.insert(info.get_fullname().to_string(), device);
+        }
+    }
+
+    let _ = daemon.stop_browse(LAN_SERVICE_TYPE);
+    let _ = daemon.shutdown();
+
+    info!("LAN discovery complete. Found {} devices.", found.len());
+    Ok(found.into_values().collect())
+}
+
+fn device_from_service(info: &ServiceInfo) -> Option<DiscoveredDevice> {
+    // 优先使用 IPv4 地址（热点网段通常只有 IPv4）
+    let ip = info
+        .get_addresses()
+        .iter()
+        .copied()
+        .min_by_key(|ip| matches!(ip, IpAddr::V6(_)))?;
+    let endpoint = SocketAddr::new(ip, info.get_port());
+
+    let name = info
+        .get_property_val_str("name")
+        .unwrap_or(info.get_hostname())
+        .to_string();
+    let brand_id = info
+        .get_property_val_str("brand")
+        .and_then(|b| b.parse::<i16>().ok());
+
+    Some(DiscoveredDevice {
+        name,
+        address: endpoint.to_string(),
+        sender_id: info
+            .get_property_val_str("id")
+            .unwrap_or("0000")
+            .to_string(),
+        brand: brand_id.map_or_else(|| "Unknown".to_string(), get_vendor_name),
+        brand_id,
+        rssi: None,
+        supports_5ghz: info.get_property_val_str("5g") == Some("1"),
+        busy: false,
+        supports_wpa3: info.get_property_val_str("wpa3") == Some("1"),
+        lan_endpoint: Some(endpoint),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wifi::command::FakeRunner;
+
+    #[test]
+    fn test_lan_interface_subnet() {
+        let runner = FakeRunner::multi_interface();
+        let interface = LanInterface::find(&runner, "192.168.1.10".parse().unwrap()).unwrap();
+        assert_eq!(interface.name, "eth0");
+        assert_eq!(interface.prefix, 24);
+
+        assert!(interface.contains("192.168.1.77".parse().unwrap()));
+        // 经网关转发来的对端和 IPv6 对端都不接受
+        assert!(!interface.contains("10.0.0.5".parse().unwrap()));
+        assert!(!interface.contains("192.168.49.2".parse().unwrap()));
+        assert!(!interface.contains("fe80::1".parse().unwrap()));
+
+        assert!(LanInterface::find(&runner, "10.9.9.9".parse().unwrap()).is_err());
+        assert!(LanInterface::find(&runner, "::1".parse().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,323 @@
+//! 设备发现
+//!
+//! 除 BLE 外，同一局域网内的设备也可以通过 mDNS/DNS-SD 互相发现，
+//! 这在蓝牙不可用或蓝牙不稳定的桌面环境下很有用。
+//!
+//! # 模块
+//!
+//! - `lan`: mDNS 服务发布/浏览，以及基于 TCP 的 P2P 握手
+//! - `host`: 发送端在热点上发布 `catshare.local`，接收端据此找到发送端
+//! - `bootstrap`: 不经过 BLE，用二维码或文本交换 P2P 信息
+//! - `presence`: 低占空比持续扫描，目标设备出现或离开时通知（`[[on_appear]]` 钩子）
+//!
+//! [`find_device`] 用于按地址、sender_id 或名称在扫描结果中查找目标设备；
+//! 无人确认的发送用只认地址和 sender_id 的 [`find_device_exact`]。
+//!
+//! # 使用
+//!
+//! ```ignore
+//! use cattysend_core::discovery::{DiscoveryMethod, discover_devices};
+//!
+//! let cancel = CancellationToken::new();
+//! let devices =
+//!     discover_devices(DiscoveryMethod::Auto, Duration::from_secs(5), None, &cancel).await?;
+//! ```
+
+pub mod bootstrap;
+pub mod host;
+pub mod lan;
+pub mod presence;
+
+use crate::ble::{BleScanner, DiscoveredDevice, ScanCallback, rank_devices};
+use crate::cancel::{self, CancellationToken};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use host::{HostAdvertisement, SENDER_HOSTNAME};
+pub use lan::{LAN_SERVICE_TYPE, LanAdvertiser, LanAdvertiserHandle};
+pub use presence::{Presence, PresenceEvent, PresenceHook, PresenceWatcher};
+
+/// 设备发现方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryMethod {
+    /// 仅 BLE（与 CatShare 兼容）
+    #[default]
+    Ble,
+    /// 仅局域网 mDNS（仅 Cattysend 设备之间）
+    Lan,
+    /// 同时使用 BLE 和局域网
+    Auto,
+}
+
+impl DiscoveryMethod {
+    /// 是否使用 BLE
+    pub fn uses_ble(self) -> bool {
+        matches!(self, DiscoveryMethod::Ble | DiscoveryMethod::Auto)
+    }
+
+    /// 是否使用局域网
+    pub fn uses_lan(self) -> bool {
+        matches!(self, DiscoveryMethod::Lan | DiscoveryMethod::Auto)
+    }
+}
+
+/// 按指定方式发现设备
+///
+/// `Auto` 模式下 BLE 和 mDNS 并行进行；任一方式失败只记录警告，
+/// 只要另一种方式可用就返回其结果。结果按 [`rank_devices`] 排序。
+/// `cancel` 取消时立即返回 [`Interrupted::Cancelled`](crate::cancel::Interrupted)。
+pub async fn discover_devices(
+    method: DiscoveryMethod,
+    timeout: Duration,
+    callback: Option<Arc<dyn ScanCallback>>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<Vec<DiscoveredDevice>> {
+    let ble = async {
+        if !method.uses_ble() {
+            return Ok(Vec::new());
+        }
+        let scanner = BleScanner::new().await?.with_cancellation(cancel.clone());
+        scanner.scan(timeout, callback.clone()).await
+    };
+    let lan = async {
+        if !method.uses_lan() {
+            return Ok(Vec::new());
+        }
+        lan::browse(timeout, callback.clone()).await
+    };
+
+    let (ble_res, lan_res) = cancel::run(cancel, None, async { tokio::join!(ble, lan) }).await?;
+
+    match (ble_res, lan_res) {
+        (Ok(mut ble), Ok(lan)) => {
+            ble.extend(lan);
+            rank_devices(&mut ble);
+            Ok(ble)
+        }
+        (Ok(devices), Err(e)) => {
+            if method.uses_lan() {
+                warn!("LAN discovery failed: {}", e);
+            }
+            Ok(devices)
+        }
+        (Err(e), Ok(devices)) if method == DiscoveryMethod::Auto => {
+            warn!("BLE discovery failed: {}", e);
+            Ok(devices)
+        }
+        (Err(e), _) => Err(e),
+    }
+}
+
+/// [`find_device`] 的查找结果
+#[derive(Debug, Clone)]
+pub enum DeviceMatch {
+    Found(DiscoveredDevice),
+    /// 多个设备同样匹配，需要用户进一步选择
+    Ambiguous(Vec<DiscoveredDevice>),
+    NotFound,
+}
+
+/// 在扫描结果中查找 `query` 指定的设备
+///
+/// 按以下顺序逐级匹配（均不区分大小写），某一级有结果即停止：
+///
+/// 1. 地址或 sender_id 完全相同
+/// 2. 名称完全相同
+/// 3. 名称包含 `query`
+/// 4. `query` 的各个字符按顺序出现在名称中（如 `rk70` 匹配 `Redmi K70`）
+///
+/// 同一级有多个结果时返回 [`DeviceMatch::Ambiguous`]，顺序与 `devices` 一致。
+pub fn find_device(devices: &[DiscoveredDevice], query: &str) -> DeviceMatch {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return DeviceMatch::NotFound;
+    }
+
+    let tiers: [&dyn Fn(&DiscoveredDevice) -> bool; 4] = [
+        &|d| is_exact_match(d, &query),
+        &|d| d.name.to_lowercase() == query,
+        &|d| d.name.to_lowercase().contains(&query),
+        &|d| is_subsequence(&query, &d.name.to_lowercase()),
+    ];
+
+    for matches in tiers {
+        let mut found: Vec<_> = devices.iter().filter(|d| matches(d)).cloned().collect();
+        match found.len() {
+            0 => {}
+            1 => return DeviceMatch::Found(found.remove(0)),
+            _ => return DeviceMatch::Ambiguous(found),
+        }
+    }
+    DeviceMatch::NotFound
+}
+
+/// 只按地址或 sender_id 查找（不区分大小写），用于定时发送、到达触发等无人确认的场合
+///
+/// 名称由对端自己广播，附近任何设备都能冒用，不能作为无人确认时的目标。
+pub fn find_device_exact(devices: &[DiscoveredDevice], query: &str) -> DeviceMatch {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return DeviceMatch::NotFound;
+    }
+    let mut found: Vec<_> = devices
+        .iter()
+        .filter(|d| is_exact_match(d, &query))
+        .cloned()
+        .collect();
+    match found.len() {
+        0 => DeviceMatch::NotFound,
+        1 => DeviceMatch::Found(found.remove(0)),
+        _ => DeviceMatch::Ambiguous(found),
+    }
+}
+
+/// `query` 是否是 [`find_device_exact`] 能匹配的写法：蓝牙地址（`AA:BB:CC:DD:EE:FF`）、
+/// 局域网地址（`IP:端口`）或 4 位十六进制的 sender_id
+pub fn is_exact_query(query: &str) -> bool {
+    let query = query.trim();
+    let is_hex = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit());
+    let is_bluetooth = {
+        let groups: Vec<&str> = query.split(':').collect();
+        groups.len() == 6 && groups.iter().all(|g| g.len() == 2 && is_hex(g))
+    };
+    (query.len() == 4 && is_hex(query))
+        || is_bluetooth
+        || query.parse::<std::net::SocketAddr>().is_ok()
+}
+
+/// 地址或 sender_id 与 `query`（已转为小写）相同
+fn is_exact_match(device: &DiscoveredDevice, query: &str) -> bool {
+    device.address.to_lowercase() == query || device.sender_id.to_lowercase() == query
+}
+
+/// `needle` 的非空白字符是否按顺序出现在 `haystack` 中
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut chars = haystack.chars();
+    needle
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|c| chars.any(|h| h == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, address: &str, sender_id: &str) -> DiscoveredDevice {
+        DiscoveredDevice {
+            name: name.to_string(),
+            address: address.to_string(),
+            sender_id: sender_id.to_string(),
+            brand: "Xiaomi".to_string(),
+            brand_id: None,
+            rssi: None,
+            supports_5ghz: false,
+            busy: false,
+            supports_wpa3: false,
+            lan_endpoint: None,
+        }
+    }
+
+    fn name_of(m: DeviceMatch) -> Option<String> {
+        match m {
+            DeviceMatch::Found(d) => Some(d.name),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_find_device() {
+        let devices = [
+            device("Redmi K70", "AA:BB:CC:DD:EE:01", "1a2b"),
+            device("Redmi Note 13", "AA:BB:CC:DD:EE:02", "3c4d"),
+            device("OPPO Find X7", "AA:BB:CC:DD:EE:03", "5e6f"),
+        ];
+
+        assert_eq!(
+            name_of(find_device(&devices, "aa:bb:cc:dd:ee:03")).as_deref(),
+            Some("OPPO Find X7")
+        );
+        assert_eq!(
+            name_of(find_device(&devices, "3C4D")).as_deref(),
+            Some("Redmi Note 13")
+        );
+        assert_eq!(
+            name_of(find_device(&devices, "redmi k70")).as_deref(),
+            Some("Redmi K70")
+        );
+        assert_eq!(
+            name_of(find_device(&devices, "find")).as_deref(),
+            Some("OPPO Find X7")
+        );
+        assert_eq!(
+            name_of(find_device(&devices, "rk70")).as_deref(),
+            Some("Redmi K70")
+        );
+        assert!(matches!(
+            find_device(&devices, "redmi"),
+            DeviceMatch::Ambiguous(found) if found.len() == 2
+        ));
+        assert!(matches!(
+            find_device(&devices, "pixel"),
+            DeviceMatch::NotFound
+        ));
+        assert!(matches!(find_device(&devices, "  "), DeviceMatch::NotFound));
+    }
+
+    #[test]
+    fn test_find_device_exact_ignores_names() {
+        let devices = [
+            device("Redmi K70", "AA:BB:CC:DD:EE:01", "1a2b"),
+            device("1A2B", "AA:BB:CC:DD:EE:02", "3c4d"),
+        ];
+        assert_eq!(
+            name_of(find_device_exact(&devices, "aa:bb:cc:dd:ee:02")).as_deref(),
+            Some("1A2B")
+        );
+        // 名称与别的设备的 sender_id 相同也不会被选中
+        assert_eq!(
+            name_of(find_device_exact(&devices, "1a2b")).as_deref(),
+            Some("Redmi K70")
+        );
+        assert!(matches!(
+            find_device_exact(&devices, "redmi k70"),
+            DeviceMatch::NotFound
+        ));
+        assert!(matches!(
+            find_device_exact(&devices, "rk70"),
+            DeviceMatch::NotFound
+        ));
+    }
+
+    #[test]
+    fn test_is_exact_query() {
+        assert!(is_exact_query("AA:BB:CC:DD:EE:01"));
+        assert!(is_exact_query("1a2b"));
+        assert!(is_exact_query("192.168.1.20:45678"));
+        assert!(is_exact_query("[fe80::1]:45678"));
+        assert!(!is_exact_query("Redmi K70"));
+        assert!(!is_exact_query("phone"));
+        assert!(!is_exact_query("AA:BB:CC:DD:EE"));
+        assert!(!is_exact_query(""));
+    }
+
+    #[test]
+    fn test_discovery_method_flags() {
+        assert!(DiscoveryMethod::Ble.uses_ble());
+        assert!(!DiscoveryMethod::Ble.uses_lan());
+        assert!(DiscoveryMethod::Lan.uses_lan());
+        assert!(!DiscoveryMethod::Lan.uses_ble());
+        assert!(DiscoveryMethod::Auto.uses_ble() && DiscoveryMethod::Auto.uses_lan());
+    }
+
+    #[test]
+    fn test_discovery_method_serde() {
+        let json = serde_json::to_string(&DiscoveryMethod::Auto).unwrap();
+        assert_eq!(json, "\"auto\"");
+        let parsed: DiscoveryMethod = serde_json::from_str("\"lan\"").unwrap();
+        assert_eq!(parsed, DiscoveryMethod::Lan);
+    }
+}
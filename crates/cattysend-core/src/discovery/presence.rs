@@ -0,0 +1,282 @@
+//! 设备在场监视
+//!
+//! [`PresenceWatcher`] 以低占空比持续扫描（默认每分钟扫描 5 秒），目标设备出现或
+//! 离开时发出 [`PresenceEvent`]。守护进程据此执行设置中的 `[[on_appear]]` 钩子
+//! （[`PresenceHook`]），实现"手机在旁边时自动发送备份"一类的自动化：
+//!
+//! ```toml
+//! [[on_appear]]
+//! device = "@phone"
+//! exec = "notify-send '手机在附近'"
+//! send = ["/home/me/backup.zip"]
+//! cooldown_mins = 720
+//! ```
+//!
+//! 设备连续 `away_after` 没有被扫描到才算离开，偶尔漏扫一次不会重复触发。
+//!
+//! 钩子会无人确认地发送文件，目标只按地址或 sender ID 匹配（[`find_device_exact`]），
+//! 附近的设备无法靠改名冒充。
+
+use super::{DeviceMatch, find_device_exact};
+use crate::ble::{BleScanner, DiscoveredDevice, DutyCycle};
+use crate::cancel::{self, CancellationToken};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// 默认占空比：每分钟扫描 5 秒
+pub const DEFAULT_PRESENCE_DUTY_CYCLE: DutyCycle = DutyCycle {
+    on: Duration::from_secs(5),
+    off: Duration::from_secs(55),
+};
+
+/// 默认多久没扫描到算离开
+pub const DEFAULT_AWAY_AFTER: Duration = Duration::from_secs(5 * 60);
+
+/// 目标设备出现或离开
+#[derive(Debug, Clone)]
+pub enum PresenceEvent {
+    /// `query` 指定的设备出现（此前不在附近）
+    Appeared {
+        query: String,
+        device: DiscoveredDevice,
+    },
+    /// `query` 指定的设备已有 `away_after` 没有出现
+    Left { query: String },
+}
+
+/// 各目标设备的在场状态
+#[derive(Debug, Clone)]
+pub struct Presence {
+    /// 查询和最后一次扫描到的时间
+    targets: Vec<(String, Option<Instant>)>,
+    away_after: Duration,
+}
+
+impl Presence {
+    /// `queries` 为地址或 sender ID（同 [`find_device_exact`]，不按名称匹配）
+    pub fn new(queries: impl IntoIterator<Item = String>, away_after: Duration) -> Self {
+        Self {
+            targets: queries.into_iter().map(|q| (q, None)).collect(),
+            away_after,
+        }
+    }
+
+    /// 根据一轮扫描结果更新状态，返回状态变化
+    ///
+    /// 匹配到多个设备时不算出现。
+    pub fn update(&mut self, devices: &[DiscoveredDevice], now: Instant) -> Vec<PresenceEvent> {
+        let mut events = Vec::new();
+        for (query, last_seen) in &mut self.targets {
+            match find_device_exact(devices, query) {
+                DeviceMatch::Found(device) => {
+                    if last_seen.is_none() {
+                        events.push(PresenceEvent::Appeared {
+                            query: query.clone(),
+                            device,
+                        });
+                    }
+                    *last_seen = Some(now);
+                }
+                _ => {
+                    if last_seen.is_some_and(|seen| now.duration_since(seen) >= self.away_after) {
+                        *last_seen = None;
+                        events.push(PresenceEvent::Left {
+                            query: query.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// `query` 指定的设备当前是否在附近
+    pub fn is_present(&self, query: &str) -> bool {
+        self.targets
+            .iter()
+            .any(|(q, seen)| q == query && seen.is_some())
+    }
+}
+
+/// 低占空比的持续扫描
+pub struct PresenceWatcher {
+    queries: Vec<String>,
+    duty_cycle: DutyCycle,
+    away_after: Duration,
+    cancel: CancellationToken,
+}
+
+impl PresenceWatcher {
+    pub fn new(queries: Vec<String>) -> Self {
+        Self {
+            queries,
+            duty_cycle: DEFAULT_PRESENCE_DUTY_CYCLE,
+            away_after: DEFAULT_AWAY_AFTER,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// 每个周期扫描 `on`，之后停止 `off`
+    pub fn with_duty_cycle(mut self, duty_cycle: DutyCycle) -> Self {
+        self.duty_cycle = duty_cycle;
+        self
+    }
+
+    /// 多久没扫描到算离开
+    pub fn with_away_after(mut self, away_after: Duration) -> Self {
+        self.away_after = away_after;
+        self
+    }
+
+    /// 令牌取消后停止扫描
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// 持续扫描并把状态变化发送到 `events`，直到被取消或 `events` 被关闭
+    ///
+    /// 某一轮扫描失败时只记录警告，不影响在场状态。
+    pub async fn run(&self, events: mpsc::Sender<PresenceEvent>) -> anyhow::Result<()> {
+        let scanner = BleScanner::new()
+            .await?
+            .with_cancellation(self.cancel.clone());
+        let mut presence = Presence::new(self.queries.iter().cloned(), self.away_after);
+        let scan_time = self.duty_cycle.on.max(Duration::from_secs(1));
+
+        loop {
+            match scanner.scan(scan_time, None).await {
+                Ok(devices) => {
+                    debug!("Presence scan found {} devices", devices.len());
+                    for event in presence.update(&devices, Instant::now()) {
+                        if events.send(event).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                }
+                Err(e) if cancel::is_cancelled(&e) => return Ok(()),
+                Err(e) => warn!("Presence scan failed: {}", e),
+            }
+            tokio::select! {
+                () = self.cancel.cancelled() => return Ok(()),
+                () = tokio::time::sleep(self.duty_cycle.off) => {}
+            }
+        }
+    }
+}
+
+/// 设备出现时执行的钩子（设置中的 `[[on_appear]]`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresenceHook {
+    /// 钩子名称，写入日志和传输历史；不填时使用 `device`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 目标设备：地址、sender ID 或 `@收藏别名`，不能只写名称
+    pub device: String,
+    /// 用 `sh -c` 执行的命令，环境变量 `CATTYSEND_DEVICE_NAME` / `CATTYSEND_DEVICE_ADDRESS`
+    /// 为出现的设备
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec: Option<String>,
+    /// 发送给该设备的文件
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub send: Vec<PathBuf>,
+    /// 触发后至少间隔多久（分钟）才再次触发，0 表示每次出现都触发
+    #[serde(default)]
+    pub cooldown_mins: u64,
+}
+
+impl PresenceHook {
+    /// 日志和历史记录中的名称
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.device)
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_mins * 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, address: &str) -> DiscoveredDevice {
+        DiscoveredDevice {
+            name: name.to_string(),
+            address: address.to_string(),
+            sender_id: String::new(),
+            brand: "Xiaomi".to_string(),
+            brand_id: None,
+            rssi: None,
+            supports_5ghz: false,
+            busy: false,
+            supports_wpa3: false,
+            lan_endpoint: None,
+        }
+    }
+
+    #[test]
+    fn test_presence_appears_and_leaves() {
+        let away = Duration::from_secs(300);
+        let mut presence = Presence::new(["AA:BB:CC:DD:EE:01".to_string()], away);
+        let phone = [device("Redmi K70", "AA:BB:CC:DD:EE:01")];
+        let start = Instant::now();
+
+        let events = presence.update(&phone, start);
+        assert!(
+            matches!(&events[..], [PresenceEvent::Appeared { device, .. }] if device.name == "Redmi K70")
+        );
+        assert!(presence.is_present("AA:BB:CC:DD:EE:01"));
+
+        // 已经在附近时不再触发；短暂漏扫不算离开
+        assert!(
+            presence
+                .update(&phone, start + Duration::from_secs(60))
+                .is_empty()
+        );
+        assert!(
+            presence
+                .update(&[], start + Duration::from_secs(120))
+                .is_empty()
+        );
+        assert!(
+            presence
+                .update(&phone, start + Duration::from_secs(180))
+                .is_empty()
+        );
+
+        let events = presence.update(&[], start + Duration::from_secs(480));
+        assert!(matches!(&events[..], [PresenceEvent::Left { .. }]));
+        assert!(!presence.is_present("AA:BB:CC:DD:EE:01"));
+
+        let events = presence.update(&phone, start + Duration::from_secs(540));
+        assert!(matches!(&events[..], [PresenceEvent::Appeared { .. }]));
+    }
+
+    /// 名称相同的设备不会触发钩子
+    #[test]
+    fn test_presence_ignores_names() {
+        let mut presence = Presence::new(["Redmi K70".to_string()], Duration::from_secs(300));
+        let impostor = [device("Redmi K70", "AA:BB:CC:DD:EE:99")];
+        assert!(presence.update(&impostor, Instant::now()).is_empty());
+        assert!(!presence.is_present("Redmi K70"));
+    }
+
+    #[test]
+    fn test_parse_hook() {
+        let hook: PresenceHook = toml::from_str(
+            r#"
+            device = "@phone"
+            send = ["/tmp/backup.zip"]
+            cooldown_mins = 60
+            "#,
+        )
+        .unwrap();
+        assert_eq!(hook.label(), "@phone");
+        assert_eq!(hook.exec, None);
+        assert_eq!(hook.cooldown(), Duration::from_secs(3600));
+    }
+}
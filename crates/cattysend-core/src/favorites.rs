@@ -0,0 +1,288 @@
+//! 常用设备（收藏）
+//!
+//! 收藏保存在 `~/.config/cattysend/favorites.toml`，每个设备有一个别名，
+//! CLI 可以用 `cattysend send -d @laptop` 直接指定目标。TUI/GUI 的设备列表
+//! 会把收藏的设备单独列在最前面。
+//!
+//! 设备以地址识别；扫描时再次见到收藏的设备会刷新其名称和 `last_seen`。
+
+use crate::ble::DiscoveredDevice;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 别名前缀，`@laptop` 表示别名为 `laptop` 的收藏
+pub const ALIAS_PREFIX: char = '@';
+
+/// 一个收藏的设备
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Favorite {
+    /// 别名（不含 `@`）
+    pub alias: String,
+    pub name: String,
+    pub address: String,
+    /// 设备的 ECDH 公钥（Base64），已知时记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    /// 最后一次扫描到的时间（Unix 秒），0 表示从未见到
+    #[serde(default)]
+    pub last_seen: u64,
+}
+
+impl Favorite {
+    /// 以设备当前的名称和地址创建收藏，别名由名称生成
+    pub fn new(name: &str, address: &str) -> Self {
+        Self {
+            alias: alias_from_name(name),
+            name: name.to_string(),
+            address: address.to_string(),
+            public_key: None,
+            last_seen: now_secs(),
+        }
+    }
+}
+
+/// 收藏列表
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Favorites {
+    #[serde(default, rename = "favorite")]
+    pub devices: Vec<Favorite>,
+}
+
+impl Favorites {
+    /// 收藏文件路径
+    fn path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("cattysend")
+            .join("favorites.toml")
+    }
+
+    /// 加载收藏（文件不存在或无法解析时为空）
+    pub fn load() -> Self {
+        Self::load_from(&Self::path())
+    }
+
+    /// 保存收藏
+    pub fn save(&self) -> anyhow::Result<()> {
+        self.save_to(&Self::path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(path).map(|content| toml::from_str(&content)) {
+            Ok(Ok(favorites)) => {
+                debug!("Loaded favorites from {:?}", path);
+                favorites
+            }
+            Ok(Err(e)) => {
+                warn!("Failed to parse favorites: {}, ignoring", e);
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Failed to read favorites file: {}, ignoring", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        debug!("Saved favorites to {:?}", path);
+        Ok(())
+    }
+
+    /// 按别名查找（不区分大小写，可带 `@` 前缀）
+    pub fn get(&self, alias: &str) -> Option<&Favorite> {
+        let alias = alias.strip_prefix(ALIAS_PREFIX).unwrap_or(alias);
+        self.devices
+            .iter()
+            .find(|f| f.alias.eq_ignore_ascii_case(alias))
+    }
+
+    /// 按地址查找
+    pub fn by_address(&self, address: &str) -> Option<&Favorite> {
+        self.devices
+            .iter()
+            .find(|f| f.address.eq_ignore_ascii_case(address))
+    }
+
+    pub fn contains_address(&self, address: &str) -> bool {
+        self.by_address(address).is_some()
+    }
+
+    /// 把 `@alias` 形式的目标解析为设备地址
+    ///
+    /// 不以 `@` 开头时返回 `None`，调用方应按普通地址或名称处理。
+    pub fn resolve(&self, target: &str) -> Option<Result<String, UnknownAlias>> {
+        let alias = target.strip_prefix(ALIAS_PREFIX)?;
+        Some(
+            self.get(alias)
+                .map(|f| f.address.clone())
+                .ok_or_else(|| UnknownAlias(alias.to_string())),
+        )
+    }
+
+    /// 添加收藏，替换别名或地址相同的旧条目
+    pub fn add(&mut self, favorite: Favorite) {
+        self.devices.retain(|f| {
+            !f.alias.eq_ignore_ascii_case(&favorite.alias)
+                && !f.address.eq_ignore_ascii_case(&favorite.address)
+        });
+        self.devices.push(favorite);
+    }
+
+    /// 按别名删除，返回是否删除了条目
+    pub fn remove(&mut self, alias: &str) -> bool {
+        let alias = alias.strip_prefix(ALIAS_PREFIX).unwrap_or(alias);
+        let before = self.devices.len();
+        self.devices
+            .retain(|f| !f.alias.eq_ignore_ascii_case(alias));
+        self.devices.len() != before
+    }
+
+    /// 收藏或取消收藏设备，返回操作后是否处于收藏状态
+    pub fn toggle(&mut self, name: &str, address: &str) -> bool {
+        let before = self.devices.len();
+        self.devices
+            .retain(|f| !f.address.eq_ignore_ascii_case(address));
+        if self.devices.len() != before {
+            return false;
+        }
+        let mut favorite = Favorite::new(name, address);
+        // 别名冲突时追加序号
+        let base = favorite.alias.clone();
+        let mut n = 2;
+        while self.get(&favorite.alias).is_some() {
+            favorite.alias = format!("{}-{}", base, n);
+            n += 1;
+        }
+        self.devices.push(favorite);
+        true
+    }
+
+    /// 用扫描结果刷新收藏设备的名称和最后见到时间，返回是否有变化
+    pub fn touch(&mut self, devices: &[DiscoveredDevice]) -> bool {
+        let now = now_secs();
+        let mut changed = false;
+        for favorite in &mut self.devices {
+            if let Some(device) = devices
+                .iter()
+                .find(|d| d.address.eq_ignore_ascii_case(&favorite.address))
+            {
+                favorite.name.clone_from(&device.name);
+                favorite.last_seen = now;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// `@alias` 没有对应的收藏
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown favorite alias: @{0}")]
+pub struct UnknownAlias(pub String);
+
+/// 由设备名生成别名：小写，字母数字以外的字符替换为 `-`
+pub fn alias_from_name(name: &str) -> String {
+    let alias = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if alias.is_empty() {
+        "device".to_string()
+    } else {
+        alias
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    fn device(name: &str, address: &str) -> DiscoveredDevice {
+        DiscoveredDevice {
+            name: name.to_string(),
+            address: address.to_string(),
+            sender_id: "1a2b".to_string(),
+            brand: "Xiaomi".to_string(),
+            brand_id: None,
+            rssi: None,
+            supports_5ghz: false,
+            busy: false,
+            supports_wpa3: false,
+            lan_endpoint: None,
+        }
+    }
+
+    #[test]
+    fn test_alias_from_name() {
+        assert_eq!(alias_from_name("Redmi K70 Pro"), "redmi-k70-pro");
+        assert_eq!(alias_from_name("  My_Laptop!! "), "my-laptop");
+        assert_eq!(alias_from_name("小米平板"), "小米平板");
+        assert_eq!(alias_from_name("***"), "device");
+    }
+
+    #[test]
+    fn test_toggle_and_resolve() {
+        let mut favorites = Favorites::default();
+        assert!(favorites.toggle("Redmi K70", "AA:BB:CC:DD:EE:01"));
+        assert!(favorites.toggle("Redmi K70", "AA:BB:CC:DD:EE:02"));
+        assert_eq!(favorites.devices[1].alias, "redmi-k70-2");
+
+        assert_eq!(
+            favorites.resolve("@REDMI-K70"),
+            Some(Ok("AA:BB:CC:DD:EE:01".to_string()))
+        );
+        assert_eq!(
+            favorites.resolve("@laptop"),
+            Some(Err(UnknownAlias("laptop".to_string())))
+        );
+        assert_eq!(favorites.resolve("AA:BB:CC:DD:EE:01"), None);
+
+        assert!(!favorites.toggle("Redmi K70", "AA:BB:CC:DD:EE:01"));
+        assert!(!favorites.contains_address("aa:bb:cc:dd:ee:01"));
+        assert!(favorites.remove("@redmi-k70-2"));
+        assert!(favorites.devices.is_empty());
+    }
+
+    #[test]
+    fn test_touch_and_roundtrip() {
+        let mut favorites = Favorites::default();
+        favorites.add(Favorite {
+            alias: "laptop".to_string(),
+            name: "old name".to_string(),
+            address: "AA:BB:CC:DD:EE:01".to_string(),
+            public_key: Some("BASE64KEY".to_string()),
+            last_seen: 0,
+        });
+
+        assert!(!favorites.touch(&[device("Phone", "AA:BB:CC:DD:EE:09")]));
+        assert!(favorites.touch(&[device("ThinkPad", "aa:bb:cc:dd:ee:01")]));
+        assert_eq!(favorites.devices[0].name, "ThinkPad");
+        assert!(favorites.devices[0].last_seen > 0);
+
+        let dir = temp_dir("fav");
+        let path = dir.join("favorites.toml");
+        favorites.save_to(&path).unwrap();
+        assert_eq!(Favorites::load_from(&path), favorites);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,444 @@
+//! 传输端口的防火墙处理
+//!
+//! 热点模式下接收端要主动连接发送端的传输端口。firewalld / ufw 默认拒绝入站连接，
+//! 端口被拦截时接收端只会连接超时，发送端看不出原因。
+//!
+//! - **firewalld**: 通过 D-Bus 在热点接口所在的 zone 临时放行端口（只改运行时配置，
+//!   并带超时），[`PortGuard`] 关闭或 drop 时撤销
+//! - **ufw**: 没有 D-Bus 接口，放行需要 root，只能报告 [`FirewallError::Blocked`]
+//!
+//! 工作流通过 [`WifiBackend::allow_port`](crate::wifi::WifiBackend::allow_port) 调用这里，
+//! 传输超时且接收端从未连上时，把 [`FirewallError`] 作为最终错误返回。
+//!
+//! 另外，[`isolate_hotspot`] 用 nftables 把热点客户端限制在传输端口、DHCP 和 mDNS 上，
+//! 接入的手机在传输期间访问不到本机的其他服务，也不能经本机转发到其他网络。
+
+use crate::wifi::command::CommandRunner;
+use log::{debug, info, warn};
+use std::fmt;
+use std::sync::Arc;
+use zbus::Connection;
+use zbus::proxy;
+
+/// 临时规则的超时（秒），进程异常退出时由 firewalld 自行撤销
+const PORT_TIMEOUT_SECS: i32 = 3600;
+
+/// 热点隔离规则所在的 nftables 表（inet 族）
+pub(crate) const ISOLATION_TABLE: &str = "cattysend_hotspot";
+
+/// 热点客户端可以访问的 UDP 端口：DHCP 和 mDNS
+const ISOLATION_UDP_PORTS: &str = "{ 67, 5353 }";
+
+/// firewalld 主接口代理
+#[proxy(
+    interface = "org.fedoraproject.FirewallD1",
+    default_service = "org.fedoraproject.FirewallD1",
+    default_path = "/org/fedoraproject/FirewallD1"
+)]
+trait FirewallD {
+    /// 默认 zone
+    #[zbus(name = "getDefaultZone")]
+    fn get_default_zone(&self) -> zbus::Result<String>;
+}
+
+/// firewalld zone 接口代理（运行时配置）
+#[proxy(
+    interface = "org.fedoraproject.FirewallD1.zone",
+    default_service = "org.fedoraproject.FirewallD1",
+    default_path = "/org/fedoraproject/FirewallD1"
+)]
+trait FirewallDZone {
+    /// 接口所属的 zone，未绑定时为空字符串
+    #[zbus(name = "getZoneOfInterface")]
+    fn get_zone_of_interface(&self, interface: &str) -> zbus::Result<String>;
+
+    /// 端口是否已放行
+    #[zbus(name = "queryPort")]
+    fn query_port(&self, zone: &str, port: &str, protocol: &str) -> zbus::Result<bool>;
+
+    /// 放行端口，`timeout` 秒后自动撤销（0 表示不超时）
+    #[zbus(name = "addPort")]
+    fn add_port(
+        &self,
+        zone: &str,
+        port: &str,
+        protocol: &str,
+        timeout: i32,
+    ) -> zbus::Result<String>;
+
+    /// 撤销放行
+    #[zbus(name = "removePort")]
+    fn remove_port(&self, zone: &str, port: &str, protocol: &str) -> zbus::Result<String>;
+}
+
+/// 防火墙类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirewallKind {
+    Firewalld,
+    Ufw,
+}
+
+impl FirewallKind {
+    /// systemd 服务名
+    pub fn name(self) -> &'static str {
+        match self {
+            FirewallKind::Firewalld => "firewalld",
+            FirewallKind::Ufw => "ufw",
+        }
+    }
+}
+
+impl fmt::Display for FirewallKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 防火墙错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum FirewallError {
+    #[error(
+        "Port {port}/tcp is likely blocked by {firewall}; allow incoming TCP on this port and retry"
+    )]
+    Blocked { firewall: FirewallKind, port: u16 },
+
+    #[error("firewalld refused to open port {port}/tcp in zone {zone}: {reason}")]
+    OpenFailed {
+        zone: String,
+        port: u16,
+        reason: String,
+    },
+}
+
+impl FirewallError {
+    /// 被拦截的端口
+    pub fn port(&self) -> u16 {
+        match self {
+            FirewallError::Blocked { port, .. } | FirewallError::OpenFailed { port, .. } => *port,
+        }
+    }
+}
+
+/// [`allow_port`] 的结果
+#[derive(Debug)]
+pub enum PortAccess {
+    /// 没有活动的防火墙，或端口本来就已放行
+    Allowed,
+    /// 已在 firewalld 中临时放行
+    Opened(PortGuard),
+    /// 防火墙处于活动状态且无法放行
+    Blocked(FirewallError),
+}
+
+impl PortAccess {
+    /// 端口可能被拦截时的错误
+    pub fn blocked(&self) -> Option<&FirewallError> {
+        match self {
+            PortAccess::Blocked(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// 撤销临时放行（如果有）
+    pub async fn close(self) {
+        if let PortAccess::Opened(guard) = self {
+            guard.close().await;
+        }
+    }
+}
+
+/// firewalld 中临时放行的端口，关闭或 drop 时撤销
+#[derive(Debug)]
+pub struct PortGuard {
+    connection: Connection,
+    zone: String,
+    port: u16,
+    /// 已撤销
+    closed: bool,
+}
+
+impl PortGuard {
+    /// 放行的 zone
+    pub fn zone(&self) -> &str {
+        &self.zone
+    }
+
+    /// 放行的端口
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// 撤销放行
+    pub async fn close(mut self) {
+        self.closed = true;
+        remove_port(&self.connection, &self.zone, self.port).await;
+    }
+}
+
+impl Drop for PortGuard {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+        // drop 不能等待，交给运行时后台撤销；没有运行时就等 firewalld 超时
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let connection = self.connection.clone();
+            let zone = std::mem::take(&mut self.zone);
+            let port = self.port;
+            handle.spawn(async move { remove_port(&connection, &zone, port).await });
+        }
+    }
+}
+
+async fn remove_port(connection: &Connection, zone: &str, port: u16) {
+    let result = async {
+        FirewallDZoneProxy::new(connection)
+            .await?
+            .remove_port(zone, &port.to_string(), "tcp")
+            .await
+    }
+    .await;
+    match result {
+        Ok(_) => info!("Closed port {}/tcp in firewalld zone {}", port, zone),
+        // 超时已到时 firewalld 会返回 NOT_ENABLED
+        Err(e) => debug!("Failed to close port {}/tcp in zone {}: {}", port, zone, e),
+    }
+}
+
+/// 检测处于活动状态的防火墙
+pub async fn detect() -> Vec<FirewallKind> {
+    let mut active = Vec::new();
+    for kind in [FirewallKind::Firewalld, FirewallKind::Ufw] {
+        let status = tokio::process::Command::new("systemctl")
+            .args(["is-active", "--quiet", kind.name()])
+            .status()
+            .await;
+        if status.is_ok_and(|s| s.success()) {
+            active.push(kind);
+        }
+    }
+    active
+}
+
+/// 确保对端能连上 `interface` 上的 TCP 端口 `port`
+///
+/// firewalld 处于活动状态时在该接口的 zone（未绑定时为默认 zone）临时放行；
+/// 只有 ufw 时无法放行，返回 [`PortAccess::Blocked`]。
+pub async fn allow_port(interface: &str, port: u16) -> PortAccess {
+    let active = detect().await;
+    if active.contains(&FirewallKind::Firewalld) {
+        return match open_firewalld_port(interface, port).await {
+            Ok(Some(guard)) => PortAccess::Opened(guard),
+            Ok(None) => PortAccess::Allowed,
+            Err(e) => {
+                warn!("{}", e);
+                PortAccess::Blocked(e)
+            }
+        };
+    }
+    if active.contains(&FirewallKind::Ufw) {
+        let e = FirewallError::Blocked {
+            firewall: FirewallKind::Ufw,
+            port,
+        };
+        warn!("{}", e);
+        return PortAccess::Blocked(e);
+    }
+    PortAccess::Allowed
+}
+
+/// 在 firewalld 中放行端口，已放行时返回 `None`
+async fn open_firewalld_port(
+    interface: &str,
+    port: u16,
+) -> Result<Option<PortGuard>, FirewallError> {
+    let mut zone = String::new();
+    let result = async {
+        let connection = Connection::system().await?;
+        let zones = FirewallDZoneProxy::new(&connection).await?;
+        zone = zones.get_zone_of_interface(interface).await?;
+        if zone.is_empty() {
+            zone = FirewallDProxy::new(&connection)
+                .await?
+                .get_default_zone()
+                .await?;
+        }
+
+        let port_str = port.to_string();
+        if zones.query_port(&zone, &port_str, "tcp").await? {
+            debug!("Port {}/tcp already open in firewalld zone {}", port, zone);
+            return Ok(None);
+        }
+        zones
+            .add_port(&zone, &port_str, "tcp", PORT_TIMEOUT_SECS)
+            .await?;
+        Ok::<_, zbus::Error>(Some(connection))
+    }
+    .await;
+
+    match result {
+        Ok(Some(connection)) => {
+            info!(
+                "Opened port {}/tcp in firewalld zone {} for {}",
+                port, zone, interface
+            );
+            Ok(Some(PortGuard {
+                connection,
+                zone,
+                port,
+                closed: false,
+            }))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => Err(FirewallError::OpenFailed {
+            zone,
+            port,
+            reason: e.to_string(),
+        }),
+    }
+}
+
+/// 热点客户端隔离，drop 时删除规则
+pub struct HotspotIsolation {
+    runner: Arc<dyn CommandRunner>,
+    interface: String,
+}
+
+impl HotspotIsolation {
+    /// 被隔离的热点接口
+    pub fn interface(&self) -> &str {
+        &self.interface
+    }
+}
+
+impl Drop for HotspotIsolation {
+    fn drop(&mut self) {
+        let result = self
+            .runner
+            .run("nft", &["delete", "table", "inet", ISOLATION_TABLE]);
+        match result {
+            Ok(output) if output.success => {
+                info!("Removed hotspot isolation on {}", self.interface)
+            }
+            Ok(output) => warn!(
+                "Failed to remove hotspot isolation: {}",
+                output.stderr.trim()
+            ),
+            Err(e) => warn!("Failed to remove hotspot isolation: {}", e),
+        }
+    }
+}
+
+/// 限制热点接口 `interface` 上的流量：入站只放行 TCP `port`、DHCP 和 mDNS，转发全部丢弃
+///
+/// 规则放在独立的 nftables 表中，整张表随 [`HotspotIsolation`] 删除。
+/// 需要 `CAP_NET_ADMIN`，失败时返回错误，调用方可以不隔离继续传输。
+pub fn isolate_hotspot(
+    runner: Arc<dyn CommandRunner>,
+    interface: &str,
+    port: u16,
+) -> anyhow::Result<HotspotIsolation> {
+    let script = isolation_ruleset(interface, port);
+    let output = runner
+        .run("nft", &[script.as_str()])
+        .map_err(|e| anyhow::anyhow!("Failed to run nft: {}", e))?;
+    if !output.success {
+        return Err(anyhow::anyhow!(
+            "nft refused hotspot isolation rules: {}",
+            output.stderr.trim()
+        ));
+    }
+    info!(
+        "Isolated hotspot clients on {} (allowing {}/tcp, DHCP, mDNS)",
+        interface, port
+    );
+    Ok(HotspotIsolation {
+        runner,
+        interface: interface.to_string(),
+    })
+}
+
+/// 隔离规则（单个 nft 事务）
+///
+/// 先 add 再 delete 表，清掉上次异常退出时残留的规则。
+pub(crate) fn isolation_ruleset(interface: &str, port: u16) -> String {
+    let table = format!("inet {}", ISOLATION_TABLE);
+    let iif = format!("iifname \"{}\"", interface);
+    [
+        format!("add table {}", table),
+        format!("delete table {}", table),
+        format!("add table {}", table),
+        format!(
+            "add chain {} input {{ type filter hook input priority -5; policy accept; }}",
+            table
+        ),
+        format!(
+            "add rule {} input {} ct state established,related accept",
+            table, iif
+        ),
+        format!("add rule {} input {} tcp dport {} accept", table, iif, port),
+        format!(
+            "add rule {} input {} udp dport {} accept",
+            table, iif, ISOLATION_UDP_PORTS
+        ),
+        format!("add rule {} input {} drop", table, iif),
+        format!(
+            "add chain {} forward {{ type filter hook forward priority -5; policy accept; }}",
+            table
+        ),
+        format!("add rule {} forward {} drop", table, iif),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_firewall_error_reports_port() {
+        let blocked = FirewallError::Blocked {
+            firewall: FirewallKind::Ufw,
+            port: 45678,
+        };
+        assert_eq!(blocked.port(), 45678);
+        assert!(blocked.to_string().contains("45678/tcp"));
+        assert!(blocked.to_string().contains("ufw"));
+
+        let access = PortAccess::Blocked(blocked.clone());
+        assert_eq!(access.blocked(), Some(&blocked));
+        assert_eq!(PortAccess::Allowed.blocked(), None);
+    }
+
+    #[test]
+    fn test_hotspot_isolation_rules() {
+        use crate::wifi::FakeRunner;
+
+        let runner = Arc::new(FakeRunner::new().with_output("nft", ""));
+        let isolation = isolate_hotspot(runner.clone(), "p2p-wlan0-0", 45678).unwrap();
+        assert_eq!(isolation.interface(), "p2p-wlan0-0");
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0].contains("iifname \"p2p-wlan0-0\" tcp dport 45678 accept"));
+        assert!(calls[0].contains("iifname \"p2p-wlan0-0\" drop"));
+        assert!(calls[0].contains("forward iifname \"p2p-wlan0-0\" drop"));
+
+        drop(isolation);
+        assert_eq!(
+            runner.calls().last().unwrap(),
+            "nft delete table inet cattysend_hotspot"
+        );
+    }
+
+    #[test]
+    fn test_hotspot_isolation_without_permission() {
+        use crate::wifi::FakeRunner;
+
+        let runner = FakeRunner::new().with_failure("nft", "Operation not permitted");
+        let err = isolate_hotspot(Arc::new(runner), "wlan0", 45678)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Operation not permitted"));
+    }
+}
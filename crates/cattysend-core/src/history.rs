@@ -0,0 +1,194 @@
+//! 传输历史
+//!
+//! 守护进程执行定时传输（见 [`crate::schedule`]）或设备出现时的发送
+//! （见 [`crate::discovery::presence`]）后把结果追加到
+//! `~/.local/state/cattysend/history.jsonl`，每行一条 [`HistoryEntry`]。
+//! 无人值守时可以事后用 `cattysend-cli history` 查看是否发送成功。
+//!
+//! 文件超过 [`MAX_HISTORY_BYTES`] 时只保留较新的一半记录。
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 历史文件的大小上限
+pub const MAX_HISTORY_BYTES: u64 = 1024 * 1024;
+
+/// 一次传输的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryOutcome {
+    Completed,
+    Failed,
+    /// 扫描窗口内没有找到目标设备
+    Missed,
+    /// 被 `stop` 或新的发送取消
+    Cancelled,
+}
+
+/// 一条历史记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// 结束时间（Unix 秒）
+    pub time: u64,
+    /// 目标设备（名称或用户给出的查询）
+    pub device: String,
+    pub files: Vec<String>,
+    pub outcome: HistoryOutcome,
+    /// 失败原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// 触发传输的定时规则或 `[[on_appear]]` 钩子的名称；`send --at` 的一次性任务为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+}
+
+impl HistoryEntry {
+    /// 以当前时间创建记录
+    pub fn now(device: &str, files: Vec<String>, outcome: HistoryOutcome) -> Self {
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            device: device.to_string(),
+            files,
+            outcome,
+            message: None,
+            schedule: None,
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn with_schedule(mut self, schedule: Option<String>) -> Self {
+        self.schedule = schedule;
+        self
+    }
+}
+
+/// 历史文件
+#[derive(Debug, Clone)]
+pub struct History {
+    path: PathBuf,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::at(Self::default_path())
+    }
+}
+
+impl History {
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 默认的历史文件路径
+    pub fn default_path() -> PathBuf {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("cattysend")
+            .join("history.jsonl")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 追加一条记录
+    pub fn append(&self, entry: &HistoryEntry) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        if file.metadata()?.len() > MAX_HISTORY_BYTES {
+            self.truncate()?;
+        }
+        Ok(())
+    }
+
+    /// 最近的 `limit` 条记录，从旧到新；文件不存在时为空，无法解析的行被跳过
+    pub fn recent(&self, limit: usize) -> anyhow::Result<Vec<HistoryEntry>> {
+        let mut entries = self.read()?;
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+        Ok(entries)
+    }
+
+    fn read(&self) -> anyhow::Result<Vec<HistoryEntry>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// 只保留较新的一半记录
+    fn truncate(&self) -> anyhow::Result<()> {
+        let entries = self.read()?;
+        let keep = &entries[entries.len() / 2..];
+        let mut content = String::new();
+        for entry in keep {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_append_and_read_recent() {
+        let dir = temp_dir("history");
+        let history = History::at(dir.join("history.jsonl"));
+        assert!(history.recent(10).unwrap().is_empty());
+
+        let files = vec!["/tmp/a.zip".to_string()];
+        history
+            .append(&HistoryEntry::now(
+                "Phone",
+                files.clone(),
+                HistoryOutcome::Completed,
+            ))
+            .unwrap();
+        history
+            .append(
+                &HistoryEntry::now("Phone", files, HistoryOutcome::Missed)
+                    .with_message("not found")
+                    .with_schedule(Some("nightly".to_string())),
+            )
+            .unwrap();
+
+        let entries = history.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].outcome, HistoryOutcome::Completed);
+        assert_eq!(entries[1].schedule.as_deref(), Some("nightly"));
+
+        let last = history.recent(1).unwrap();
+        assert_eq!(last, entries[1..]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
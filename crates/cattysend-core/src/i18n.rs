@@ -0,0 +1,147 @@
+//! 界面文本国际化
+//!
+//! CLI / TUI / GUI 共用同一套文本目录 `locales/{en,zh-CN}.yml`，由 rust-i18n
+//! 在编译期载入。前端启动时调用 [`init`] 按环境变量选择语言，之后通过
+//! [`tr!`](crate::tr) 取文本：
+//!
+//! ```ignore
+//! cattysend_core::i18n::init();
+//! println!("{}", cattysend_core::tr!("cli.send.sending", file = path));
+//! ```
+//!
+//! 文本中的占位符写作 `%{name}`。当前语言缺少某个 key 时回退到英文，
+//! 英文也没有时返回 key 本身。
+
+rust_i18n::i18n!("locales", fallback = "en");
+
+/// 支持的语言
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "zh-CN"];
+
+/// 无法从环境判断语言时使用的语言
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// 按环境变量设置界面语言
+pub fn init() {
+    set_locale(detect_locale());
+}
+
+/// 依次检查 `LC_ALL`、`LC_MESSAGES`、`LANG`，取第一个非空值
+pub fn detect_locale() -> &'static str {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map_or(DEFAULT_LOCALE, |value| locale_from_env(&value))
+}
+
+/// 把 `zh_CN.UTF-8` 一类的 POSIX locale 映射为支持的语言
+pub fn locale_from_env(value: &str) -> &'static str {
+    let lang = value.split(['.', '@']).next().unwrap_or_default();
+    if lang.to_ascii_lowercase().starts_with("zh") {
+        "zh-CN"
+    } else {
+        DEFAULT_LOCALE
+    }
+}
+
+/// 设置当前语言
+pub fn set_locale(locale: &str) {
+    rust_i18n::set_locale(locale);
+}
+
+/// 当前语言
+pub fn locale() -> String {
+    rust_i18n::locale().to_string()
+}
+
+/// 取 `locale` 下 `key` 对应的文本并替换占位符
+///
+/// 一般通过 [`tr!`](crate::tr) 调用。
+pub fn translate(locale: &str, key: &str, args: &[(&str, String)]) -> String {
+    let mut text = _rust_i18n_try_translate(locale, key)
+        .map_or_else(|| key.to_string(), |text| text.into_owned());
+    for (name, value) in args {
+        text = text.replace(&format!("%{{{}}}", name), value);
+    }
+    text
+}
+
+/// 取当前语言的界面文本
+///
+/// ```ignore
+/// tr!("tui.status.ready");
+/// tr!("tui.log.scan_finished", count = devices.len());
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::translate(&$crate::i18n::locale(), $key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate(
+            &$crate::i18n::locale(),
+            $key,
+            &[$((stringify!($name), $value.to_string())),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_env() {
+        assert_eq!(locale_from_env("zh_CN.UTF-8"), "zh-CN");
+        assert_eq!(locale_from_env("zh_TW"), "zh-CN");
+        assert_eq!(locale_from_env("en_US.UTF-8"), "en");
+        assert_eq!(locale_from_env("C"), "en");
+        assert_eq!(locale_from_env("de_DE@euro"), "en");
+    }
+
+    #[test]
+    fn test_translate() {
+        assert_eq!(translate("en", "tui.status.ready", &[]), "Ready");
+        assert_eq!(translate("zh-CN", "tui.status.ready", &[]), "就绪");
+        // 未支持的语言回退到英文
+        assert_eq!(translate("fr", "tui.status.ready", &[]), "Ready");
+        assert_eq!(translate("en", "no.such.key", &[]), "no.such.key");
+        assert_eq!(
+            translate(
+                "zh-CN",
+                "tui.log.scan_finished",
+                &[("count", "3".to_string())]
+            ),
+            "扫描完成，发现 3 个设备"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_have_same_keys() {
+        let keys = |yaml: &str| -> Vec<String> {
+            let mut path: Vec<String> = Vec::new();
+            let mut keys = Vec::new();
+            for line in yaml.lines() {
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    continue;
+                }
+                let depth = (line.len() - trimmed.len()) / 2;
+                let Some((key, value)) = trimmed.split_once(':') else {
+                    continue;
+                };
+                path.truncate(depth);
+                path.push(key.to_string());
+                if !value.trim().is_empty() {
+                    keys.push(path.join("."));
+                }
+            }
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            keys(include_str!("../locales/en.yml")),
+            keys(include_str!("../locales/zh-CN.yml"))
+        );
+    }
+}
@@ -4,17 +4,36 @@
 //!
 //! # 模块
 //!
+//! - **api**: 高层 API（[`Cattysend::discover`]、[`Device::send`]、[`Cattysend::receive`]），
+//!   不需要了解工作流细节
 //! - **ble**: BLE 扫描、广播、GATT 客户端/服务器
-//! - **crypto**: ECDH 密钥交换和 AES-CTR 加密
+//! - **cancel**: 扫描、握手、接入网络和传输的取消令牌与默认超时
+//! - **crypto**: ECDH 密钥交换和 AES-CTR 加密；本机身份密钥保存在系统密钥环或密钥文件中
+//!   （密钥环需要 `keyring` feature）；cattysend 之间的传输使用固定指纹的自签名 TLS 证书
+//! - **discovery**: BLE / 局域网 mDNS 设备发现
 //! - **wifi**: WiFi P2P 热点创建和连接
 //! - **transfer**: HTTP/WebSocket 文件传输
+//! - **favorites**: 常用设备收藏和 `@别名`
+//! - **i18n**: CLI / TUI / GUI 共用的界面文本目录（英文、中文）
+//! - **watch**: 目录中最新文件的查找与监视（"分享最新截图"）
+//! - **diagnostics**: 运行环境检查（BlueZ、权限、NetworkManager、rfkill 等），供 `doctor` 命令使用
+//! - **firewall**: 热点模式下传输端口的防火墙放行（firewalld）和拦截检测（ufw）
+//! - **metrics**: 扫描、握手、传输的耗时与失败计数（`metrics` feature）
+//! - **quirks**: 按品牌和 catShare 版本调整超时、重试和频段的厂商兼容性表
+//! - **sync**: cattysend 之间的文件夹同步（只传输变化的文件，可同步删除）
+//! - **schedule**: 定时传输（`send --at` 和设置中的重复规则）
+//! - **history**: 定时传输结果的历史记录
+//! - **power**: UPower 电源状态（电池供电时降低广播占空比，电量过低时拒绝创建热点）
+//!
+//! 主要流程都带有 `tracing` span（发送、握手、连接热点、下载等），
+//! 配合 tracing subscriber 可以看出卡在哪一步。
 //!
 //! # 使用示例
 //!
 //! ## 发送文件
 //!
 //! ```ignore
-//! use cattysend_core::{BleScanner, BleClient, WiFiP2pSender, TransferServer};
+//! use cattysend_core::{BleScanner, BleClient, CredentialPolicy, WiFiP2pSender, TransferServer};
 //!
 //! // 1. 扫描接收端设备
 //! let scanner = BleScanner::new().await?;
@@ -22,7 +41,7 @@
 //!
 //! // 2. 创建 WiFi P2P 热点并启动传输服务器
 //! let sender = WiFiP2pSender::new("wlan0");
-//! let p2p_info = sender.create_group(8443).await?;
+//! let p2p_info = sender.create_group(8443, &CredentialPolicy::default()).await?;
 //!
 //! // 3. 连接到接收端并发送 P2P 信息
 //! let ble_client = BleClient::new().await?;
@@ -52,41 +71,103 @@
 //! client.start(&callback).await?;
 //! ```
 
+pub mod api;
+pub mod autostart;
 pub mod ble;
+pub mod cancel;
 pub mod config;
 pub mod crypto;
+pub mod diagnostics;
+pub mod discovery;
+pub mod favorites;
+pub mod firewall;
+pub mod history;
+pub mod i18n;
 pub mod logging;
+pub mod metrics;
+pub mod power;
+pub mod quirks;
+pub mod schedule;
+pub mod sync;
+#[cfg(any(test, feature = "loopback-test"))]
+pub mod testing;
 pub mod transfer;
+pub mod watch;
 pub mod wifi;
 pub mod workflow;
 
+// High-level API re-exports
+pub use api::{Cattysend, Device, DiscoverTask, ReceiveTask, SendTask, Task};
+
+// Autostart re-exports
+pub use autostart::{Autostart, AutostartMode};
+
 // Config re-exports
-pub use config::{AppSettings, BrandId};
+pub use config::{AppSettings, BrandId, LogFormat, PortRange, PowerProfile};
+
+// Power re-exports
+pub use power::{LowBattery, PowerState};
+
+// Quirks re-exports
+pub use quirks::{QuirkRule, Quirks, QuirksTable};
+
+// Schedule re-exports
+pub use schedule::{ScheduleRule, TimeOfDay, Weekday};
+
+// History re-exports
+pub use history::{History, HistoryEntry, HistoryOutcome};
+
+// Sync re-exports
+pub use sync::{SyncJob, SyncPlan, SyncState, SyncedFile};
 
 // Logging re-exports
-pub use logging::{LogEntry, LogLevel};
+pub use logging::{DaemonLogs, LogEntry, LogLevel, RotatingFile};
 
 // BLE re-exports
 pub use ble::{
-    ADV_SERVICE_UUID, BleClient, BleScanner, ChannelScanCallback, DeviceInfo, DiscoveredDevice,
-    GattServer, GattServerHandle, MAIN_SERVICE_UUID, P2P_CHAR_UUID, SERVICE_UUID, STATUS_CHAR_UUID,
-    ScanCallback,
+    ADV_SERVICE_UUID, BleClient, BleScanner, BrandPreset, ChannelScanCallback, DeviceInfo,
+    DiscoveredDevice, DutyCycle, GattConnectionEvent, GattServer, GattServerHandle,
+    LegacyAdvConfig, MAIN_SERVICE_UUID, P2P_CHAR_UUID, P2pNotifier, PairingAgent, PairingPrompt,
+    ReceiverInfo, ReceiverState, SERVICE_UUID, STATUS_CHAR_UUID, ScanCallback, ScanOptions,
+    StatusNotifier, rank_devices,
 };
 
+// Cancellation re-exports
+pub use cancel::{CancellationToken, Interrupted};
+
+// Discovery re-exports
+pub use discovery::{
+    DeviceMatch, DiscoveryMethod, LanAdvertiser, PresenceEvent, PresenceHook, PresenceWatcher,
+    find_device, find_device_exact, is_exact_query,
+};
+
+// Favorites re-exports
+pub use favorites::{Favorite, Favorites};
+
 // Crypto re-exports
-pub use crypto::{BleSecurity, BleSecurityPersistent, SessionCipher};
+pub use crypto::{
+    AtRestKey, BleSecurity, BleSecurityPersistent, DeviceIdentity, IdentityStore, KeyLocation,
+    SessionCipher, TlsIdentity,
+};
 
 // WiFi re-exports
-pub use wifi::{P2pConfig, P2pInfo, WiFiP2pReceiver, WiFiP2pSender};
+pub use wifi::{
+    BusyInterfacePolicy, CredentialPolicy, HotspotSecurity, InterfaceBusy, LinuxWifiBackend,
+    P2pConfig, P2pInfo, P2pInfoError, PskCharset, StationInfo, WiFiP2pReceiver, WiFiP2pSender,
+    WifiBackend,
+};
 
 // Transfer re-exports
 pub use transfer::{
-    FileEntry, ReceiverCallback, ReceiverClient, SendRequest, TransferServer, TransferTask,
-    WsMessage,
+    Capabilities, FileEntry, FileProgress, HttpTransport, ReceiverCallback, ReceiverClient,
+    SendRequest, SyncRequest, TransferControl, TransferServer, TransferStats, TransferTarget,
+    TransferTask, TransferTransport, WarmHttpTransport, WsMessage,
 };
 
 // Workflow re-exports
 pub use workflow::{
-    ReceiveEvent, ReceiveOptions, ReceiveProgressCallback, ReceiveRequest, Receiver, SendEvent,
-    SendOptions, SendProgressCallback, Sender, SimpleReceiveCallback, SimpleSendCallback,
+    Failure, IncomingTransfer, ReceiveEvent, ReceiveOptions, ReceivePhase, ReceiveProgressCallback,
+    ReceiveRequest, Receiver, RetryAttempt, RetryPolicy, SendEvent, SendOptions, SendPhase,
+    SendProgressCallback, Sender, Session, SessionListener, SimpleReceiveCallback,
+    SimpleSendCallback, Standby, TransferMode, WorkflowState,
 };
@@ -0,0 +1,217 @@
+//! 日志文件输出
+//!
+//! [`RotatingFile`] 按大小轮转：当前文件为 `<name>.log`，写满后依次改名为
+//! `<name>.log.1`、`<name>.log.2`……，超出保留数量的最旧文件被删除。
+//! 它只实现了 [`std::io::Write`]，守护进程和 TUI 用 `Mutex` 包装后作为
+//! tracing-subscriber 的 writer。
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// 按大小轮转的日志文件
+#[derive(Debug)]
+pub struct RotatingFile {
+    dir: PathBuf,
+    name: String,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    /// 当前文件已写入的字节数
+    written: u64,
+}
+
+impl RotatingFile {
+    /// 单个文件的默认上限（10 MiB）
+    pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+    /// 默认保留的历史文件数（不含当前文件）
+    pub const DEFAULT_MAX_FILES: usize = 5;
+
+    /// 在 `dir` 下打开（追加写入）`<name>.log`，目录不存在时创建
+    pub fn open(dir: impl Into<PathBuf>, name: &str) -> io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.log", name));
+        let file = open_append(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            dir,
+            name: name.to_string(),
+            max_bytes: Self::DEFAULT_MAX_BYTES,
+            max_files: Self::DEFAULT_MAX_FILES,
+            file,
+            written,
+        })
+    }
+
+    /// 设置单个文件的大小上限
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes.max(1);
+        self
+    }
+
+    /// 设置保留的历史文件数，0 表示轮转时直接丢弃旧内容
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// 当前日志文件的路径
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.log", self.name))
+    }
+
+    /// 第 `index` 个历史文件的路径（1 为最新）
+    fn archive_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{}.log.{}", self.name, index))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            self.file = File::create(self.path())?;
+            self.written = 0;
+            return Ok(());
+        }
+        remove_if_exists(&self.archive_path(self.max_files))?;
+        for index in (1..self.max_files).rev() {
+            let from = self.archive_path(index);
+            if from.exists() {
+                std::fs::rename(&from, self.archive_path(index + 1))?;
+            }
+        }
+        std::fs::rename(self.path(), self.archive_path(1))?;
+        self.file = open_append(&self.path())?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 一条日志不跨文件；超长的单条日志仍完整写入新文件
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.written += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 指定日志目录的命令行参数（守护进程和 TUI）
+pub const LOG_DIR_ARG: &str = "--log-dir";
+
+/// 从命令行参数中取出 `--log-dir <dir>` 或 `--log-dir=<dir>`，返回目录和其余参数
+///
+/// 只给出 `--log-dir` 时使用 [`default_log_dir`]。
+pub fn take_log_dir_arg(args: Vec<String>) -> (Option<PathBuf>, Vec<String>) {
+    let mut dir = None;
+    let mut rest = Vec::with_capacity(args.len());
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == LOG_DIR_ARG {
+            let value = args.next_if(|next| !next.starts_with("--"));
+            dir = Some(value.map_or_else(default_log_dir, PathBuf::from));
+        } else if let Some(value) = arg
+            .strip_prefix(LOG_DIR_ARG)
+            .and_then(|v| v.strip_prefix('='))
+        {
+            dir = Some(PathBuf::from(value));
+        } else {
+            rest.push(arg);
+        }
+    }
+    (dir, rest)
+}
+
+/// 默认日志目录：`~/.local/state/cattysend/logs`
+pub fn default_log_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cattysend")
+        .join("logs")
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_rotates_by_size_and_keeps_max_files() {
+        let dir = temp_dir("logs");
+        let mut log = RotatingFile::open(&dir, "daemon")
+            .unwrap()
+            .with_max_bytes(10)
+            .with_max_files(2);
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("daemon.log"), "fourth\n");
+        assert_eq!(read("daemon.log.1"), "third\n");
+        assert_eq!(read("daemon.log.2"), "second\n");
+        assert!(!dir.join("daemon.log.3").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_take_log_dir_arg() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let (dir, rest) = take_log_dir_arg(args(&["tui", "--log-dir", "/tmp/logs", "a.txt"]));
+        assert_eq!(dir, Some(PathBuf::from("/tmp/logs")));
+        assert_eq!(rest, args(&["tui", "a.txt"]));
+
+        let (dir, rest) = take_log_dir_arg(args(&["daemon", "--log-dir=/var/log/c", "--receive"]));
+        assert_eq!(dir, Some(PathBuf::from("/var/log/c")));
+        assert_eq!(rest, args(&["daemon", "--receive"]));
+
+        let (dir, rest) = take_log_dir_arg(args(&["daemon", "--log-dir", "--receive"]));
+        assert_eq!(dir, Some(default_log_dir()));
+        assert_eq!(rest, args(&["daemon", "--receive"]));
+
+        let (dir, _) = take_log_dir_arg(args(&["daemon"]));
+        assert_eq!(dir, None);
+    }
+
+    #[test]
+    fn test_appends_to_existing_file() {
+        let dir = temp_dir("logs");
+        RotatingFile::open(&dir, "tui")
+            .unwrap()
+            .write_all(b"before\n")
+            .unwrap();
+        let mut log = RotatingFile::open(&dir, "tui").unwrap().with_max_bytes(10);
+        // 已有 7 字节，再写 6 字节超过上限，先轮转
+        log.write_all(b"after\n").unwrap();
+
+        assert_eq!(std::fs::read_to_string(log.path()).unwrap(), "after\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("tui.log.1")).unwrap(),
+            "before\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,13 @@
 //! 日志模块
 //!
-//! 提供跨 UI 的统一日志级别和条目定义。
+//! 提供跨 UI 的统一日志级别和条目定义，按大小轮转的日志文件（[`RotatingFile`]），
+//! 以及订阅守护进程日志的客户端（[`DaemonLogs`]）。
+
+mod file;
+mod remote;
+
+pub use file::{LOG_DIR_ARG, RotatingFile, default_log_dir, take_log_dir_arg};
+pub use remote::{DAEMON_LOGS_ARG, DaemonLogs, daemon_socket_path};
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
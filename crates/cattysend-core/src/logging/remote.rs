@@ -0,0 +1,124 @@
+//! 订阅守护进程的日志
+//!
+//! 守护进程的 IPC socket 支持 `{"type":"logs","level":"Debug"}` 请求，之后逐行推送
+//! `{"type":"log","entry":{...}}`（[`LogEntry`]），只包含不低于所请求级别的日志。
+//! TUI 和 GUI 用 [`DaemonLogs`] 把守护进程执行传输时的日志显示在自己的日志面板中，
+//! 排查问题时不需要再去看 journalctl。
+
+use super::{LogEntry, LogLevel};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+/// 启动时即附加到守护进程日志的命令行参数（TUI 和 GUI）
+pub const DAEMON_LOGS_ARG: &str = "--daemon-logs";
+
+/// 守护进程的 IPC socket 路径
+pub fn daemon_socket_path() -> PathBuf {
+    std::env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("/tmp"))
+        .join("cattysend.sock")
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename = "logs")]
+struct LogsRequest {
+    level: LogLevel,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum LogsResponse {
+    #[serde(rename = "log")]
+    Log { entry: LogEntry },
+    #[serde(rename = "error")]
+    Error { message: String },
+}
+
+/// 守护进程的日志流
+pub struct DaemonLogs {
+    stream: BufReader<UnixStream>,
+    line: String,
+}
+
+impl DaemonLogs {
+    /// 连接守护进程，订阅 `level` 及更重要的日志
+    pub async fn connect(level: LogLevel) -> io::Result<Self> {
+        Self::connect_to(&daemon_socket_path(), level).await
+    }
+
+    /// 连接指定的 socket
+    pub async fn connect_to(path: &Path, level: LogLevel) -> io::Result<Self> {
+        let mut stream = UnixStream::connect(path).await?;
+        let mut request = serde_json::to_string(&LogsRequest { level })?;
+        request.push('\n');
+        stream.write_all(request.as_bytes()).await?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+            line: String::new(),
+        })
+    }
+
+    /// 下一条日志，守护进程断开时为 None
+    ///
+    /// 守护进程不支持日志订阅时返回它给出的错误；无法识别的行被跳过。
+    pub async fn next(&mut self) -> io::Result<Option<LogEntry>> {
+        loop {
+            self.line.clear();
+            if self.stream.read_line(&mut self.line).await? == 0 {
+                return Ok(None);
+            }
+            match serde_json::from_str(&self.line) {
+                Ok(LogsResponse::Log { entry }) => return Ok(Some(entry)),
+                Ok(LogsResponse::Error { message }) => return Err(io::Error::other(message)),
+                Err(_) => continue,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use tokio::net::UnixListener;
+
+    #[tokio::test]
+    async fn test_daemon_logs_reads_entries() {
+        let dir = temp_dir("logs");
+        let path = dir.join("daemon.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let daemon = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(stream);
+            let mut request = String::new();
+            stream.read_line(&mut request).await.unwrap();
+            let lines = concat!(
+                r#"{"type":"log","entry":{"level":"Warn","message":"weak signal"}}"#,
+                "\n",
+                r#"{"type":"event","event":{"kind":"paused"}}"#,
+                "\n",
+                r#"{"type":"log","entry":{"level":"Debug","message":"mtu 512"}}"#,
+                "\n",
+            );
+            stream.get_mut().write_all(lines.as_bytes()).await.unwrap();
+            request
+        });
+
+        let mut logs = DaemonLogs::connect_to(&path, LogLevel::Debug)
+            .await
+            .unwrap();
+        let first = logs.next().await.unwrap().unwrap();
+        assert_eq!(first, LogEntry::new(LogLevel::Warn, "weak signal"));
+        let second = logs.next().await.unwrap().unwrap();
+        assert_eq!(second, LogEntry::new(LogLevel::Debug, "mtu 512"));
+        assert!(logs.next().await.unwrap().is_none());
+
+        let request = daemon.await.unwrap();
+        assert_eq!(request.trim(), r#"{"type":"logs","level":"Debug"}"#);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,111 @@
+//! 运行指标
+//!
+//! 启用 `metrics` feature 后，扫描、握手、热点、传输的耗时以及各阶段的失败次数
+//! 通过 [`metrics`](https://docs.rs/metrics) 门面记录，由宿主程序（如 daemon）
+//! 安装导出器；未启用时这里的函数都是空操作，调用处不需要条件编译。
+
+use std::time::Duration;
+
+/// 指标名称
+pub mod names {
+    /// 扫描开始到发现第一个设备的时间（秒）
+    pub const SCAN_FIRST_DEVICE_SECONDS: &str = "cattysend_scan_first_device_seconds";
+    /// 每次扫描发现的设备数
+    pub const SCAN_DEVICES_FOUND: &str = "cattysend_scan_devices_found";
+    /// BLE / 局域网握手耗时（秒），标签 `via`
+    pub const HANDSHAKE_SECONDS: &str = "cattysend_handshake_seconds";
+    /// 热点创建耗时（秒）
+    pub const HOTSPOT_UP_SECONDS: &str = "cattysend_hotspot_up_seconds";
+    /// 传输的字节数，标签 `direction`
+    pub const TRANSFER_BYTES_TOTAL: &str = "cattysend_transfer_bytes_total";
+    /// 单次传输的平均吞吐（字节/秒），标签 `direction`
+    pub const TRANSFER_THROUGHPUT: &str = "cattysend_transfer_throughput_bytes_per_second";
+    /// 失败次数，标签 `stage`
+    pub const FAILURES_TOTAL: &str = "cattysend_failures_total";
+}
+
+/// 注册指标说明，安装导出器之后调用一次
+pub fn describe() {
+    #[cfg(feature = "metrics")]
+    {
+        use metrics::{Unit, describe_counter, describe_histogram};
+
+        describe_histogram!(
+            names::SCAN_FIRST_DEVICE_SECONDS,
+            Unit::Seconds,
+            "Time from scan start to the first discovered receiver"
+        );
+        describe_histogram!(
+            names::SCAN_DEVICES_FOUND,
+            Unit::Count,
+            "Receivers found per scan"
+        );
+        describe_histogram!(
+            names::HANDSHAKE_SECONDS,
+            Unit::Seconds,
+            "Duration of the BLE or LAN handshake"
+        );
+        describe_histogram!(
+            names::HOTSPOT_UP_SECONDS,
+            Unit::Seconds,
+            "Time to bring the Wi-Fi hotspot up"
+        );
+        describe_counter!(
+            names::TRANSFER_BYTES_TOTAL,
+            Unit::Bytes,
+            "Bytes transferred"
+        );
+        describe_histogram!(
+            names::TRANSFER_THROUGHPUT,
+            "Average throughput of completed transfers in bytes per second"
+        );
+        describe_counter!(names::FAILURES_TOTAL, Unit::Count, "Failures by stage");
+    }
+}
+
+/// 一次扫描结束
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn scan_finished(first_device: Option<Duration>, found: usize) {
+    #[cfg(feature = "metrics")]
+    {
+        if let Some(elapsed) = first_device {
+            metrics::histogram!(names::SCAN_FIRST_DEVICE_SECONDS).record(elapsed.as_secs_f64());
+        }
+        metrics::histogram!(names::SCAN_DEVICES_FOUND).record(found as f64);
+    }
+}
+
+/// 握手成功，`via` 为 `"ble"` 或 `"lan"`
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn handshake(via: &'static str, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(names::HANDSHAKE_SECONDS, "via" => via).record(elapsed.as_secs_f64());
+}
+
+/// 热点已创建
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn hotspot_up(elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(names::HOTSPOT_UP_SECONDS).record(elapsed.as_secs_f64());
+}
+
+/// 传输完成，`direction` 为 `"send"` 或 `"receive"`
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn transfer_finished(direction: &'static str, bytes: u64, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!(names::TRANSFER_BYTES_TOTAL, "direction" => direction).increment(bytes);
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            metrics::histogram!(names::TRANSFER_THROUGHPUT, "direction" => direction)
+                .record(bytes as f64 / secs);
+        }
+    }
+}
+
+/// 某个阶段最终失败（重试耗尽之后）
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn failure(stage: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(names::FAILURES_TOTAL, "stage" => stage).increment(1);
+}
@@ -0,0 +1,170 @@
+//! 笔记本电源状态
+//!
+//! 通过 D-Bus 读取 UPower 的电源状态：
+//! - 使用电池供电时，接收端的 BLE 广播改用 [`PowerProfile::Battery`] 的低占空比
+//!   （设置 `battery_saver`）；
+//! - 电量低于设置 `min_battery_percent` 时拒绝创建热点（热点模式的发送），
+//!   `send --force` 可以跳过检查。
+//!
+//! 没有 UPower、没有电池（台式机）或读取失败时按外接电源处理，不影响任何行为。
+
+use crate::config::PowerProfile;
+use anyhow::{Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use zbus::Connection;
+use zbus::proxy;
+
+/// 默认的热点最低电量（百分比）
+pub const DEFAULT_MIN_BATTERY_PERCENT: u8 = 20;
+
+/// UPower 主接口代理
+#[proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    /// 是否使用电池供电
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+/// UPower 设备接口代理（默认路径为汇总所有电池的显示设备）
+#[proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower/devices/DisplayDevice"
+)]
+trait UPowerDevice {
+    /// 是否存在电池
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+
+    /// 剩余电量（0-100）
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+}
+
+/// 当前电源状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PowerState {
+    /// 是否使用电池供电
+    pub on_battery: bool,
+    /// 剩余电量（百分比），没有电池时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_percent: Option<u8>,
+}
+
+impl PowerState {
+    /// 读取电源状态，失败时按外接电源处理
+    pub async fn read() -> Self {
+        match Self::try_read().await {
+            Ok(state) => state,
+            Err(e) => {
+                debug!("Failed to read power state from UPower: {:#}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// 从 UPower 读取电源状态
+    pub async fn try_read() -> Result<Self> {
+        let connection = Connection::system()
+            .await
+            .context("Failed to connect to system D-Bus")?;
+        let upower = UPowerProxy::new(&connection).await?;
+        let on_battery = upower
+            .on_battery()
+            .await
+            .context("Failed to query UPower")?;
+
+        let device = UPowerDeviceProxy::new(&connection).await?;
+        let battery_percent = if device.is_present().await.unwrap_or(false) {
+            device
+                .percentage()
+                .await
+                .ok()
+                .map(|p| p.round().clamp(0.0, 100.0) as u8)
+        } else {
+            None
+        };
+        Ok(Self {
+            on_battery,
+            battery_percent,
+        })
+    }
+
+    /// 电池供电时改用 [`PowerProfile::Battery`]，否则使用 `profile`
+    pub fn effective_profile(&self, profile: PowerProfile) -> PowerProfile {
+        if self.on_battery {
+            PowerProfile::Battery
+        } else {
+            profile
+        }
+    }
+
+    /// 电池供电且电量低于 `min_percent` 时拒绝创建热点；`min_percent` 为 0 时不检查
+    pub fn check_hotspot(&self, min_percent: u8) -> Result<(), LowBattery> {
+        match self.battery_percent {
+            Some(percent) if self.on_battery && percent < min_percent => Err(LowBattery {
+                percent,
+                min: min_percent,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 电量过低，拒绝创建热点
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("battery at {percent}% is below the {min}% hotspot threshold (use --force to override)")]
+pub struct LowBattery {
+    /// 当前电量
+    pub percent: u8,
+    /// 设置的最低电量
+    pub min: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_state_policy() {
+        let ac = PowerState {
+            on_battery: false,
+            battery_percent: Some(5),
+        };
+        assert_eq!(
+            ac.effective_profile(PowerProfile::Performance),
+            PowerProfile::Performance
+        );
+        assert_eq!(ac.check_hotspot(20), Ok(()));
+
+        let low = PowerState {
+            on_battery: true,
+            battery_percent: Some(12),
+        };
+        assert_eq!(
+            low.effective_profile(PowerProfile::Performance),
+            PowerProfile::Battery
+        );
+        assert_eq!(
+            low.check_hotspot(20),
+            Err(LowBattery {
+                percent: 12,
+                min: 20
+            })
+        );
+        assert_eq!(low.check_hotspot(10), Ok(()));
+        assert_eq!(low.check_hotspot(0), Ok(()));
+
+        // 读不到电量时不拒绝
+        let unknown = PowerState {
+            on_battery: true,
+            battery_percent: None,
+        };
+        assert_eq!(unknown.check_hotspot(20), Ok(()));
+    }
+}
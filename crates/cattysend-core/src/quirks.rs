@@ -0,0 +1,287 @@
+//! 厂商兼容性表（quirks）
+//!
+//! 各厂商对互传协议的实现细节不完全一致：有的只能接入 2.4GHz 热点，有的在 P2P 信息里
+//! 写的端口与实际监听端口有固定偏移，有的 WebSocket 服务要晚一点才就绪。
+//! [`QuirksTable`] 按品牌和 catShare 协议版本记录这些差异，发送端和接收端工作流据此
+//! 调整超时、重试次数和频段。
+//!
+//! 内置规则之后再叠加覆盖文件 `~/.config/cattysend/quirks.toml`（同一字段后面的规则优先）：
+//!
+//! ```toml
+//! [[quirk]]
+//! brand = "Meizu"
+//! only_2ghz = true
+//!
+//! [[quirk]]
+//! min_version = 0
+//! max_version = 0
+//! websocket_delay_ms = 800
+//! port_offset = 1
+//! ```
+
+use crate::config::BrandId;
+use crate::workflow::RetryPolicy;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 一组兼容性调整，未设置的字段保持默认行为
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quirks {
+    /// 对端只能接入 2.4GHz 热点
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only_2ghz: Option<bool>,
+    /// 实际端口相对 P2P 信息中端口的偏移（接收端连接发送端时使用）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_offset: Option<i32>,
+    /// 接入网络后等待多久再连接 WebSocket（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websocket_delay_ms: Option<u64>,
+    /// 单次 BLE 握手的超时（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handshake_timeout_secs: Option<u64>,
+    /// 热点创建、握手和连接的最大尝试次数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+}
+
+impl Quirks {
+    /// 用 `other` 中设置了的字段覆盖自身
+    pub fn merge(&mut self, other: &Quirks) {
+        self.only_2ghz = other.only_2ghz.or(self.only_2ghz);
+        self.port_offset = other.port_offset.or(self.port_offset);
+        self.websocket_delay_ms = other.websocket_delay_ms.or(self.websocket_delay_ms);
+        self.handshake_timeout_secs = other.handshake_timeout_secs.or(self.handshake_timeout_secs);
+        self.max_attempts = other.max_attempts.or(self.max_attempts);
+    }
+
+    /// 是否可以使用 5GHz 热点（`use_5ghz` 为用户设置）
+    pub fn use_5ghz(&self, use_5ghz: bool) -> bool {
+        use_5ghz && !self.only_2ghz.unwrap_or(false)
+    }
+
+    /// 按偏移修正后的端口，结果越界时保持原端口
+    pub fn port(&self, port: u16) -> u16 {
+        let offset = self.port_offset.unwrap_or(0);
+        u16::try_from(i32::from(port) + offset)
+            .ok()
+            .filter(|&p| p != 0)
+            .unwrap_or(port)
+    }
+
+    /// 连接 WebSocket 前的等待时间
+    pub fn websocket_delay(&self) -> Option<Duration> {
+        self.websocket_delay_ms
+            .filter(|&ms| ms > 0)
+            .map(Duration::from_millis)
+    }
+
+    /// 单次握手的超时
+    pub fn handshake_timeout(&self) -> Option<Duration> {
+        self.handshake_timeout_secs
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// 在 `retry` 的基础上应用尝试次数
+    pub fn retry(&self, retry: RetryPolicy) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(retry.max_attempts).max(1),
+            ..retry
+        }
+    }
+}
+
+/// 一条规则：匹配条件和对应的调整
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuirkRule {
+    /// 匹配的品牌（不填为任意品牌）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<BrandId>,
+    /// 匹配的最低 catShare 协议版本（含）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<i32>,
+    /// 匹配的最高 catShare 协议版本（含）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_version: Option<i32>,
+    #[serde(flatten)]
+    pub quirks: Quirks,
+}
+
+impl QuirkRule {
+    /// 规则是否适用于 `brand` / `version`
+    ///
+    /// 限定了品牌的规则只匹配已知品牌；限定了版本范围的规则只在知道版本时匹配。
+    pub fn matches(&self, brand: Option<BrandId>, version: Option<i32>) -> bool {
+        let brand_ok = match self.brand {
+            Some(expected) => brand == Some(expected),
+            None => true,
+        };
+        let version_ok = match (self.min_version, self.max_version) {
+            (None, None) => true,
+            (min, max) => version.is_some_and(|v| {
+                min.map_or(true, |min| v >= min) && max.map_or(true, |max| v <= max)
+            }),
+        };
+        brand_ok && version_ok
+    }
+}
+
+/// 兼容性表
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuirksTable {
+    #[serde(default, rename = "quirk")]
+    rules: Vec<QuirkRule>,
+}
+
+impl QuirksTable {
+    /// 只含内置规则
+    pub fn builtin() -> Self {
+        Self {
+            rules: vec![
+                // 不带 catShare 字段的旧版 MTA 设备：握手较慢，WebSocket 服务晚于热点就绪
+                QuirkRule {
+                    brand: None,
+                    min_version: Some(0),
+                    max_version: Some(0),
+                    quirks: Quirks {
+                        websocket_delay_ms: Some(500),
+                        handshake_timeout_secs: Some(20),
+                        max_attempts: Some(4),
+                        ..Default::default()
+                    },
+                },
+            ],
+        }
+    }
+
+    /// 内置规则加上覆盖文件中的规则；覆盖文件无法解析时只使用内置规则
+    pub fn load() -> Self {
+        let mut table = Self::builtin();
+        let path = Self::override_path();
+        if path.exists() {
+            match Self::from_file(&path) {
+                Ok(overrides) => {
+                    debug!(
+                        "Loaded {} quirk rules from {:?}",
+                        overrides.rules.len(),
+                        path
+                    );
+                    table.extend(overrides);
+                }
+                Err(e) => warn!("Failed to load quirks from {:?}: {}", path, e),
+            }
+        }
+        table
+    }
+
+    /// 从 TOML 文件读取规则（不含内置规则）
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        Ok(toml::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// 覆盖文件路径
+    pub fn override_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("cattysend")
+            .join("quirks.toml")
+    }
+
+    /// 追加一条规则（优先于已有规则）
+    pub fn with_rule(mut self, rule: QuirkRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// 追加 `other` 的全部规则
+    pub fn extend(&mut self, other: QuirksTable) {
+        self.rules.extend(other.rules);
+    }
+
+    /// 全部规则，按优先级从低到高
+    pub fn rules(&self) -> &[QuirkRule] {
+        &self.rules
+    }
+
+    /// 合并所有适用于 `brand` / `version` 的规则
+    pub fn lookup(&self, brand: Option<BrandId>, version: Option<i32>) -> Quirks {
+        let mut quirks = Quirks::default();
+        for rule in self.rules.iter().filter(|r| r.matches(brand, version)) {
+            quirks.merge(&rule.quirks);
+        }
+        quirks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override_file() {
+        let table: QuirksTable = toml::from_str(
+            r#"
+            [[quirk]]
+            brand = "Meizu"
+            only_2ghz = true
+
+            [[quirk]]
+            min_version = 2
+            port_offset = -1
+            websocket_delay_ms = 800
+            "#,
+        )
+        .unwrap();
+        assert_eq!(table.rules().len(), 2);
+        assert_eq!(table.rules()[0].brand, Some(BrandId::Meizu));
+        assert_eq!(table.rules()[1].quirks.port_offset, Some(-1));
+
+        let meizu = table.lookup(Some(BrandId::Meizu), None);
+        assert!(!meizu.use_5ghz(true));
+        assert_eq!(meizu.websocket_delay(), None);
+
+        let v2 = table.lookup(None, Some(2));
+        assert!(v2.use_5ghz(true));
+        assert_eq!(v2.port(8443), 8442);
+        assert_eq!(v2.websocket_delay(), Some(Duration::from_millis(800)));
+    }
+
+    #[test]
+    fn test_later_rules_override_earlier() {
+        let table = QuirksTable::builtin().with_rule(QuirkRule {
+            brand: Some(BrandId::Xiaomi),
+            min_version: None,
+            max_version: Some(0),
+            quirks: Quirks {
+                max_attempts: Some(6),
+                ..Default::default()
+            },
+        });
+
+        let legacy = table.lookup(Some(BrandId::Xiaomi), Some(0));
+        assert_eq!(legacy.max_attempts, Some(6));
+        assert_eq!(legacy.websocket_delay_ms, Some(500));
+        assert_eq!(legacy.retry(RetryPolicy::default()).max_attempts, 6);
+
+        // 版本未知时不匹配限定了版本的规则
+        assert_eq!(table.lookup(Some(BrandId::Xiaomi), None), Quirks::default());
+        assert_eq!(
+            table.lookup(Some(BrandId::Vivo), Some(0)).max_attempts,
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_port_offset_stays_in_range() {
+        let quirks = Quirks {
+            port_offset: Some(-10),
+            ..Default::default()
+        };
+        assert_eq!(quirks.port(5), 5);
+        assert_eq!(quirks.port(8443), 8433);
+    }
+}
@@ -0,0 +1,235 @@
+//! 定时传输
+//!
+//! 两种来源：
+//! - `cattysend-cli send <文件> --at 02:00`：一次性任务，保存在守护进程内存中，
+//!   到达下一个 02:00（本地时间）时执行；
+//! - 设置中的 `[[schedule]]` 规则（[`ScheduleRule`]）：每天或 `days` 列出的星期几重复执行。
+//!
+//! ```toml
+//! [[schedule]]
+//! name = "nightly-photos"
+//! device = "@phone"
+//! paths = ["/home/me/Pictures/export.zip"]
+//! at = "02:00"
+//! days = ["mon", "wed", "fri"]
+//! window_mins = 30
+//! ```
+//!
+//! 到点后守护进程在 `window_mins` 分钟的扫描窗口内反复查找目标设备，找到后发送，
+//! 失败时在窗口内继续重试。每次执行的结果写入传输历史（见 [`crate::history`]）。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// 默认的扫描窗口（分钟）
+pub const DEFAULT_WINDOW_MINS: u64 = 30;
+
+/// 一天中的时刻（本地时间，精确到分钟）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    hour: u8,
+    minute: u8,
+}
+
+impl TimeOfDay {
+    /// 小时或分钟越界时为 None
+    pub fn new(hour: u8, minute: u8) -> Option<Self> {
+        (hour < 24 && minute < 60).then_some(Self { hour, minute })
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// 该时刻在 `now` 之后（不含）的下一次出现；`days` 为空时不限星期几
+    pub fn next_after(&self, days: &[Weekday], now: SystemTime) -> Option<SystemTime> {
+        let now = libc::time_t::try_from(now.duration_since(UNIX_EPOCH).ok()?.as_secs()).ok()?;
+        // SAFETY: tm 由 localtime_r 填充，不共享静态缓冲区
+        let mut today: libc::tm = unsafe { std::mem::zeroed() };
+        if unsafe { libc::localtime_r(&now, &mut today) }.is_null() {
+            return None;
+        }
+        // 多看一天：今天的时刻已过且只允许今天这个星期几时，下一次在一周后
+        for offset in 0..8 {
+            let mut tm = today;
+            tm.tm_mday += offset;
+            tm.tm_hour = i32::from(self.hour);
+            tm.tm_min = i32::from(self.minute);
+            tm.tm_sec = 0;
+            // 由 mktime 判断夏令时，并规范化日期、填写 tm_wday
+            tm.tm_isdst = -1;
+            // SAFETY: tm 是有效的局部变量
+            let at = unsafe { libc::mktime(&mut tm) };
+            if at == -1 || at <= now {
+                continue;
+            }
+            if days.is_empty() || days.iter().any(|d| *d as i32 == tm.tm_wday) {
+                return Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(at).ok()?));
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// 无法解析的时刻
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid time of day: {0:?} (expected HH:MM)")]
+pub struct InvalidTimeOfDay(String);
+
+impl FromStr for TimeOfDay {
+    type Err = InvalidTimeOfDay;
+
+    /// `HH:MM` 或 `H:MM`（24 小时制）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidTimeOfDay(s.to_string());
+        let (hour, minute) = s.trim().split_once(':').ok_or_else(invalid)?;
+        if hour.is_empty() || hour.len() > 2 || minute.len() != 2 {
+            return Err(invalid());
+        }
+        let hour = hour.parse().map_err(|_| invalid())?;
+        let minute = minute.parse().map_err(|_| invalid())?;
+        Self::new(hour, minute).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = InvalidTimeOfDay;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(t: TimeOfDay) -> Self {
+        t.to_string()
+    }
+}
+
+/// 星期几，取值与 `tm_wday` 相同（周日为 0）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Sun = 0,
+    Mon = 1,
+    Tue = 2,
+    Wed = 3,
+    Thu = 4,
+    Fri = 5,
+    Sat = 6,
+}
+
+/// 设置中的重复定时传输规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    /// 规则名称，写入历史记录和日志
+    pub name: String,
+    /// 目标设备：地址、sender ID 或 `@收藏别名`（定时发送无人确认，不按名称查找）
+    pub device: String,
+    /// 要发送的文件
+    pub paths: Vec<PathBuf>,
+    /// 执行时刻（本地时间）
+    pub at: TimeOfDay,
+    /// 只在这些日子执行，为空时每天执行
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    /// 到点后查找设备和重试的时长（分钟）
+    #[serde(default = "default_window_mins")]
+    pub window_mins: u64,
+}
+
+fn default_window_mins() -> u64 {
+    DEFAULT_WINDOW_MINS
+}
+
+impl ScheduleRule {
+    /// `now` 之后的下一次执行时间
+    pub fn next_run(&self, now: SystemTime) -> Option<SystemTime> {
+        self.at.next_after(&self.days, now)
+    }
+
+    /// 扫描窗口
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_mins.max(1) * 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `time` 的本地时刻和星期几
+    fn local(time: SystemTime) -> (i32, i32, i32) {
+        let secs = time.duration_since(UNIX_EPOCH).unwrap().as_secs() as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        assert!(!unsafe { libc::localtime_r(&secs, &mut tm) }.is_null());
+        (tm.tm_hour, tm.tm_min, tm.tm_wday)
+    }
+
+    #[test]
+    fn test_parse_time_of_day() {
+        assert_eq!("02:00".parse(), Ok(TimeOfDay::new(2, 0).unwrap()));
+        assert_eq!("7:05".parse(), Ok(TimeOfDay::new(7, 5).unwrap()));
+        assert_eq!(TimeOfDay::new(7, 5).unwrap().to_string(), "07:05");
+        for bad in ["24:00", "12:60", "12", "12:5", ":30", "123:00", "ab:cd"] {
+            assert!(bad.parse::<TimeOfDay>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_next_after() {
+        let now = SystemTime::now();
+        let at = TimeOfDay::new(2, 30).unwrap();
+        let next = at.next_after(&[], now).unwrap();
+        assert!(next > now);
+        // 夏令时切换当天可能多出一小时
+        assert!(next.duration_since(now).unwrap() <= Duration::from_secs(25 * 3600));
+        let (hour, minute, _) = local(next);
+        assert_eq!((hour, minute), (2, 30));
+
+        // 从下一次执行时刻开始算，下一次在第二天
+        let again = at.next_after(&[], next).unwrap();
+        assert!(again.duration_since(next).unwrap() >= Duration::from_secs(23 * 3600));
+
+        let friday = at.next_after(&[Weekday::Fri], now).unwrap();
+        assert_eq!(local(friday).2, Weekday::Fri as i32);
+        assert!(friday.duration_since(now).unwrap() <= Duration::from_secs(8 * 24 * 3600));
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: ScheduleRule = toml::from_str(
+            r#"
+            name = "nightly"
+            device = "@phone"
+            paths = ["/tmp/a.zip"]
+            at = "02:00"
+            days = ["mon", "fri"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(rule.at, TimeOfDay::new(2, 0).unwrap());
+        assert_eq!(rule.days, vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(rule.window(), Duration::from_secs(DEFAULT_WINDOW_MINS * 60));
+        assert!(
+            toml::from_str::<ScheduleRule>(
+                "name = \"x\"\ndevice = \"a\"\npaths = []\nat = \"25:00\""
+            )
+            .is_err()
+        );
+    }
+}
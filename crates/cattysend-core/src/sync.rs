@@ -0,0 +1,351 @@
+//! 文件夹同步
+//!
+//! `cattysend-cli sync <目录> --device X` 把整个目录同步到另一台 cattysend 上，只在 cattysend
+//! 之间的扩展模式中可用（需要协商出 `raw_files`）：
+//!
+//! - 发送端把目录下的全部文件以相对路径（`/` 分隔）列在 sendRequest 中，并带上
+//!   [`SyncRequest`]；接收端保存到 `<下载目录>/<目录名>/`，覆盖同名文件
+//! - 接收端已有且大小和 SHA-256 都相同的文件不重新下载（增量同步），所以只有变化的文件
+//!   真正经过网络
+//! - 请求了 `delete` 且接收端允许（[`AppSettings::allow_sync_delete`](crate::AppSettings::allow_sync_delete)）时，
+//!   目标目录中不在文件列表里的文件被删除
+//!
+//! 发送端在 `~/.local/state/cattysend/sync/` 中为每个（设备, 目录）记录上次同步成功时的文件列表。
+//! 大小和修改时间都没变的文件沿用记录中的 SHA-256，不再重新计算；dry-run 时与这份记录比较，
+//! 给出会新增、修改和删除的文件（[`SyncPlan`]）。
+
+use crate::ble::DiscoveredDevice;
+use crate::transfer::protocol::SyncRequest;
+use crate::transfer::sha256_file;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// 同步目录中一个文件的状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncedFile {
+    pub size: u64,
+    /// 修改时间（Unix 毫秒）
+    pub modified_time: u64,
+    /// 内容的 SHA-256（小写十六进制）
+    pub sha256: String,
+}
+
+/// 上次同步成功时的文件列表，键为相对路径
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// 同步的目录（只用于人工查看）
+    #[serde(default)]
+    pub root: PathBuf,
+    /// 目标设备
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub files: BTreeMap<String, SyncedFile>,
+}
+
+impl SyncState {
+    /// 读取记录；不存在或无法解析时为空（相当于第一次同步）
+    pub fn load(path: &Path) -> Self {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(_) => return Self::default(),
+        };
+        serde_json::from_str(&data).unwrap_or_else(|e| {
+            warn!("Ignoring invalid sync state {:?}: {}", path, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // 先写临时文件再改名，中途退出时不会留下半个记录
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// 同步将要做的改动（与上次同步成功时相比）
+///
+/// 接收端的副本可能已经与记录不同，实际传输时仍以接收端上的内容为准：
+/// `added` 中接收端已有的相同文件同样不会重新下载。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncPlan {
+    /// 目录名（接收端保存到 `<下载目录>/<folder>/`）
+    pub folder: String,
+    /// 新增的文件
+    pub added: Vec<String>,
+    /// 内容变化的文件
+    pub modified: Vec<String>,
+    /// 没有变化的文件数
+    pub unchanged: usize,
+    /// 已从本地删除的文件（`delete` 时接收端也删除）
+    pub deleted: Vec<String>,
+    /// 是否请求接收端删除多余的文件
+    pub delete: bool,
+    /// 需要传输的字节数（新增和修改的文件）
+    pub transfer_size: u64,
+}
+
+impl SyncPlan {
+    /// 没有任何需要传输或删除的文件
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// 一次同步：扫描后的目录内容和上次同步的记录
+#[derive(Debug, Clone)]
+pub struct SyncJob {
+    root: PathBuf,
+    folder: String,
+    delete: bool,
+    files: BTreeMap<String, SyncedFile>,
+    previous: SyncState,
+    device: String,
+    state_path: PathBuf,
+}
+
+impl SyncJob {
+    /// 扫描 `root`，准备同步到 `device`（设备的 sender ID 或地址）
+    ///
+    /// 会读取整个目录并计算新文件的摘要（阻塞），应在 `spawn_blocking` 中调用。
+    pub fn prepare(root: &Path, device: &str, delete: bool) -> anyhow::Result<Self> {
+        Self::prepare_in(&default_state_dir(), root, device, delete)
+    }
+
+    /// 同 [`Self::prepare`]，记录保存在 `state_dir` 中
+    pub fn prepare_in(
+        state_dir: &Path,
+        root: &Path,
+        device: &str,
+        delete: bool,
+    ) -> anyhow::Result<Self> {
+        let root = fs::canonicalize(root)
+            .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", root.display(), e))?;
+        if !root.is_dir() {
+            anyhow::bail!("{} is not a directory", root.display());
+        }
+        let folder = root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Cannot sync {}", root.display()))?;
+
+        let state_path = state_dir.join(format!("{}.json", state_key(device, &root)));
+        let previous = SyncState::load(&state_path);
+        let files = scan_dir(&root, &previous.files)?;
+        debug!(
+            "Scanned {} file(s) in {} ({} previously synced)",
+            files.len(),
+            root.display(),
+            previous.files.len()
+        );
+        Ok(Self {
+            root,
+            folder,
+            delete,
+            files,
+            previous,
+            device: device.to_string(),
+            state_path,
+        })
+    }
+
+    /// 同步的目录（绝对路径）
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 目录中的文件，键为相对路径
+    pub fn files(&self) -> &BTreeMap<String, SyncedFile> {
+        &self.files
+    }
+
+    /// 随 sendRequest 发给接收端的同步请求
+    pub fn request(&self) -> SyncRequest {
+        SyncRequest {
+            folder: self.folder.clone(),
+            delete: self.delete,
+        }
+    }
+
+    /// 与上次同步相比的改动
+    pub fn plan(&self) -> SyncPlan {
+        let mut plan = SyncPlan {
+            folder: self.folder.clone(),
+            delete: self.delete,
+            ..Default::default()
+        };
+        for (name, file) in &self.files {
+            match self.previous.files.get(name) {
+                None => plan.added.push(name.clone()),
+                Some(old) if old.sha256 != file.sha256 => plan.modified.push(name.clone()),
+                Some(_) => {
+                    plan.unchanged += 1;
+                    continue;
+                }
+            }
+            plan.transfer_size += file.size;
+        }
+        plan.deleted = self
+            .previous
+            .files
+            .keys()
+            .filter(|name| !self.files.contains_key(*name))
+            .cloned()
+            .collect();
+        plan
+    }
+
+    /// 同步成功后把当前内容记为下次比较的基准
+    pub fn save_state(&self) -> anyhow::Result<()> {
+        SyncState {
+            root: self.root.clone(),
+            device: self.device.clone(),
+            files: self.files.clone(),
+        }
+        .save(&self.state_path)
+    }
+}
+
+/// 同步记录中标识设备的键：sender ID（BLE 地址可能随机变化），没有时用地址
+pub fn device_key(device: &DiscoveredDevice) -> &str {
+    if device.sender_id.is_empty() {
+        &device.address
+    } else {
+        &device.sender_id
+    }
+}
+
+/// 默认的同步记录目录：`~/.local/state/cattysend/sync`
+pub fn default_state_dir() -> PathBuf {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("cattysend")
+        .join("sync")
+}
+
+/// 记录文件名：设备和目录的 SHA-256 前 16 位
+fn state_key(device: &str, root: &Path) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(device.as_bytes());
+    hasher.update([0]);
+    hasher.update(root.as_os_str().as_encoded_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 递归列出 `root` 下的普通文件（不跟随符号链接）
+///
+/// 大小和修改时间与 `known` 中的记录一致时沿用记录的摘要，否则重新计算。
+/// 文件名不是 UTF-8 的文件无法写进 sendRequest，跳过。
+fn scan_dir(
+    root: &Path,
+    known: &BTreeMap<String, SyncedFile>,
+) -> anyhow::Result<BTreeMap<String, SyncedFile>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                warn!("Skipping non-UTF-8 file name in {}", dir.display());
+                continue;
+            };
+            let relative = format!("{}{}", prefix, name);
+            let metadata = entry.path().symlink_metadata()?;
+            if metadata.is_dir() {
+                pending.push((entry.path(), format!("{}/", relative)));
+                continue;
+            }
+            if !metadata.is_file() {
+                debug!("Skipping {} (not a regular file)", relative);
+                continue;
+            }
+
+            let size = metadata.len();
+            let modified_time = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as u64);
+            let sha256 = match known.get(&relative) {
+                Some(old) if old.size == size && old.modified_time == modified_time => {
+                    old.sha256.clone()
+                }
+                _ => sha256_file(&entry.path())?,
+            };
+            files.insert(
+                relative,
+                SyncedFile {
+                    size,
+                    modified_time,
+                    sha256,
+                },
+            );
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_plan_against_last_sync() {
+        // 目录名即同步文件夹名
+        let base = temp_dir("sync");
+        let root = base.join("photos");
+        let state_dir = base.join("state");
+        fs::create_dir_all(root.join("2024/may")).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("2024/may/b.jpg"), "b").unwrap();
+        fs::write(root.join("old.txt"), "old").unwrap();
+
+        let first = SyncJob::prepare_in(&state_dir, &root, "AA:BB", false).unwrap();
+        assert_eq!(
+            first.files().keys().collect::<Vec<_>>(),
+            vec!["2024/may/b.jpg", "a.txt", "old.txt"]
+        );
+        let plan = first.plan();
+        assert_eq!(plan.folder, "photos");
+        assert_eq!(plan.added.len(), 3);
+        assert_eq!(plan.transfer_size, 5);
+        first.save_state().unwrap();
+
+        fs::write(root.join("a.txt"), "changed").unwrap();
+        fs::remove_file(root.join("old.txt")).unwrap();
+        fs::write(root.join("c.txt"), "c").unwrap();
+
+        let second = SyncJob::prepare_in(&state_dir, &root, "AA:BB", true).unwrap();
+        let plan = second.plan();
+        assert_eq!(plan.added, vec!["c.txt"]);
+        assert_eq!(plan.modified, vec!["a.txt"]);
+        assert_eq!(plan.deleted, vec!["old.txt"]);
+        assert_eq!(plan.unchanged, 1);
+        assert!(plan.delete);
+        assert_eq!(second.request().folder, "photos");
+
+        // 另一台设备没有同步记录
+        let other = SyncJob::prepare_in(&state_dir, &root, "CC:DD", false).unwrap();
+        assert_eq!(other.plan().added.len(), 3);
+
+        let _ = fs::remove_dir_all(base);
+    }
+}
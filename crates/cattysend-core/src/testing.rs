@@ -0,0 +1,231 @@
+//! 本机回环测试支持（`loopback-test` feature，单元测试中总是可用）
+//!
+//! 提供不依赖蓝牙和 WiFi 硬件的模拟后端，让完整的 Sender ↔ Receiver 流程
+//! （握手 JSON、加密、WebSocket 协商、ZIP 传输）在 127.0.0.1 上运行：
+//!
+//! - [`LoopbackWifiBackend`]: "热点" 即本机回环，接收端 "连接" 后得到 127.0.0.1
+//! - [`LoopbackGattBackend`]: 发送端的 GATT 客户端，读写直接落到接收端的
+//!   DeviceInfo 和 P2P 处理逻辑上，解密后的 [`P2pReceiveEvent`] 通过 channel 交给
+//!   [`Receiver::handle_p2p_event`](crate::Receiver::handle_p2p_event)；
+//!   设置 [`LoopbackGattBackend::with_group_owner`] 后模拟由接收端建组的设备
+//! - [`temp_dir`]: 单元测试、回环测试和基准测试共用的临时目录
+
+use crate::ble::client::BleClientError;
+use crate::ble::gatt::GattHandler;
+use crate::ble::server::process_p2p_write;
+use crate::ble::{
+    DeviceInfo, GattClientBackend, GattConnection, P2P_CHAR_UUID, P2pReceiveEvent, ReceiverState,
+    STATUS_CHAR_UUID,
+};
+use crate::crypto::BleSecurityPersistent;
+use crate::wifi::{CredentialPolicy, P2pInfo, WifiBackend};
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use futures_util::stream::BoxStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use uuid::Uuid;
+
+/// 回环地址（接收端推断的网关 127.0.0.1 恰好也是发送端）
+const LOOPBACK_IP: &str = "127.0.0.1";
+
+/// 模拟接收端的 MAC 地址
+pub const LOOPBACK_RECEIVER_MAC: &str = "02:00:00:00:00:02";
+
+/// 新建唯一的临时目录 `<系统临时目录>/cattysend-<name>-<UUID>`，由调用方删除
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("cattysend-{}-{}", name, Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 不操作任何网卡的 WiFi 后端
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LoopbackWifiBackend;
+
+#[async_trait]
+impl WifiBackend for LoopbackWifiBackend {
+    fn name(&self) -> &'static str {
+        "loopback"
+    }
+
+    async fn create_hotspot(
+        &self,
+        port: i32,
+        _credentials: &CredentialPolicy,
+    ) -> anyhow::Result<P2pInfo> {
+        Ok(P2pInfo::new(
+            "DIRECT-loopback".to_string(),
+            "loopback".to_string(),
+            "02:00:00:00:00:01".to_string(),
+            port,
+        ))
+    }
+
+    async fn stop_hotspot(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn connect(&self, _info: &P2pInfo) -> anyhow::Result<String> {
+        Ok(LOOPBACK_IP.to_string())
+    }
+
+    async fn disconnect(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn get_ip(&self) -> anyhow::Result<String> {
+        Ok(LOOPBACK_IP.to_string())
+    }
+}
+
+/// 直接连到本进程内 "接收端" 的 GATT 客户端后端
+pub struct LoopbackGattBackend {
+    device_info: DeviceInfo,
+    security: Arc<BleSecurityPersistent>,
+    events: mpsc::Sender<P2pReceiveEvent>,
+    group: Option<P2pInfo>,
+}
+
+impl LoopbackGattBackend {
+    /// `security` 为接收端的密钥对，需与传给 `Receiver::with_security` 的相同
+    pub fn new(security: Arc<BleSecurityPersistent>) -> (Self, mpsc::Receiver<P2pReceiveEvent>) {
+        let (events, rx) = mpsc::channel(4);
+        let device_info = DeviceInfo::new(
+            security.get_public_key().to_string(),
+            LOOPBACK_RECEIVER_MAC.to_string(),
+        );
+        (
+            Self {
+                device_info,
+                security,
+                events,
+                group: None,
+            },
+            rx,
+        )
+    }
+
+    /// 设置接收端上报的状态（用于测试忙碌拒绝）
+    pub fn with_state(mut self, state: ReceiverState) -> Self {
+        self.device_info.state = state.code();
+        self
+    }
+
+    /// 模拟由接收端建组的设备：收到建组请求后通过 P2P 特征的通知回复 `group`
+    pub fn with_group_owner(mut self, group: P2pInfo) -> Self {
+        self.group = Some(group);
+        self
+    }
+}
+
+#[async_trait]
+impl GattClientBackend for LoopbackGattBackend {
+    fn name(&self) -> &'static str {
+        "loopback"
+    }
+
+    async fn connect(&self, _address: &str) -> Result<Box<dyn GattConnection>, BleClientError> {
+        let device_info = serde_json::to_vec(&self.device_info)
+            .map_err(|e| BleClientError::ProtocolError(e.to_string()))?;
+        Ok(Box::new(LoopbackConnection {
+            device_info,
+            security: self.security.clone(),
+            events: self.events.clone(),
+            group: self.group.clone(),
+            notify: Mutex::new(None),
+        }))
+    }
+}
+
+struct LoopbackConnection {
+    device_info: Vec<u8>,
+    security: Arc<BleSecurityPersistent>,
+    events: mpsc::Sender<P2pReceiveEvent>,
+    group: Option<P2pInfo>,
+    /// P2P 特征的通知订阅
+    notify: Mutex<Option<mpsc::UnboundedSender<Vec<u8>>>>,
+}
+
+impl LoopbackConnection {
+    /// 按接收端的方式加密 P2pInfo 并通知给发送端
+    fn notify_group(
+        &self,
+        group: &P2pInfo,
+        sender_key: Option<&str>,
+    ) -> Result<(), BleClientError> {
+        let protocol_error = |e: anyhow::Error| BleClientError::ProtocolError(e.to_string());
+        let info = match sender_key {
+            Some(key) => {
+                let cipher = self
+                    .security
+                    .derive_session_key(key)
+                    .map_err(protocol_error)?;
+                GattHandler::encrypt_p2p_info(
+                    group,
+                    &cipher,
+                    LOOPBACK_RECEIVER_MAC,
+                    self.security.get_public_key(),
+                )
+                .map_err(protocol_error)?
+            }
+            None => group.clone(),
+        };
+        let data =
+            serde_json::to_vec(&info).map_err(|e| BleClientError::ProtocolError(e.to_string()))?;
+        match self.notify.lock().unwrap().as_ref() {
+            Some(tx) => tx
+                .send(data)
+                .map_err(|_| BleClientError::ConnectionFailed("sender unsubscribed".to_string())),
+            None => Err(BleClientError::ProtocolError(
+                "group request written before subscribing".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl GattConnection for LoopbackConnection {
+    async fn read(&self, characteristic: Uuid) -> Result<Vec<u8>, BleClientError> {
+        if characteristic == STATUS_CHAR_UUID {
+            Ok(self.device_info.clone())
+        } else {
+            Err(BleClientError::CharacteristicNotFound(characteristic))
+        }
+    }
+
+    async fn write(&self, characteristic: Uuid, data: &[u8]) -> Result<(), BleClientError> {
+        if characteristic != P2P_CHAR_UUID {
+            return Err(BleClientError::CharacteristicNotFound(characteristic));
+        }
+        let event = process_p2p_write(data, Some(&self.security))
+            .map_err(|e| BleClientError::ProtocolError(e.to_string()))?;
+        if let Some(group) = &self.group
+            && event.p2p_info.is_group_request()
+        {
+            return self.notify_group(group, event.sender_public_key.as_deref());
+        }
+        self.events
+            .send(event)
+            .await
+            .map_err(|_| BleClientError::ConnectionFailed("receiver dropped".to_string()))
+    }
+
+    async fn subscribe(
+        &self,
+        characteristic: Uuid,
+    ) -> Result<BoxStream<'static, Vec<u8>>, BleClientError> {
+        if characteristic != P2P_CHAR_UUID {
+            return Err(BleClientError::CharacteristicNotFound(characteristic));
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.notify.lock().unwrap() = Some(tx);
+        Ok(UnboundedReceiverStream::new(rx).boxed())
+    }
+
+    async fn disconnect(&self) -> Result<(), BleClientError> {
+        Ok(())
+    }
+}
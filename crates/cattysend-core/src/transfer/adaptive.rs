@@ -0,0 +1,143 @@
+//! 按链路质量调整块大小和进度间隔
+//!
+//! 2.4GHz 热点在信号差时只有几百 KB/s，5GHz 近距离时可达几十 MB/s。固定的 64 KiB 块
+//! 在慢链路上一块要发很久，在快链路上又意味着大量小块和过于频繁的进度通知。
+//! [`AdaptiveChunker`] 用最近的吞吐量（指数加权平均）估算：
+//!
+//! - 块大小：约 [`TARGET_CHUNK_TIME`] 能传完的字节数，限制在
+//!   [`MIN_CHUNK_SIZE`]-[`MAX_CHUNK_SIZE`] 之间
+//! - 进度间隔：慢链路 [`MIN_PROGRESS_INTERVAL`]，吞吐量越高间隔越长，最多
+//!   [`MAX_PROGRESS_INTERVAL`]
+//!
+//! 发送端用它切分 `/download` 的响应体，接收端用它决定攒多少数据再写盘。
+
+use std::time::{Duration, Instant};
+
+/// 最小块大小
+pub const MIN_CHUNK_SIZE: usize = 16 * 1024;
+/// 最大块大小
+pub const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+/// 还没有吞吐量样本时的块大小
+pub const INITIAL_CHUNK_SIZE: usize = 64 * 1024;
+/// 每块期望的传输时间
+pub const TARGET_CHUNK_TIME: Duration = Duration::from_millis(50);
+/// 最短进度间隔（慢链路）
+pub const MIN_PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// 最长进度间隔（快链路）
+pub const MAX_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 每 MB/s 吞吐量对应的进度间隔
+const PROGRESS_INTERVAL_PER_MBPS: Duration = Duration::from_millis(20);
+/// 新样本在加权平均中的权重
+const SMOOTHING: f64 = 0.3;
+/// 块大小按页对齐
+const CHUNK_ALIGN: usize = 4096;
+
+/// 吞吐量估计
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveChunker {
+    /// 平滑后的吞吐量（字节/秒）
+    rate: Option<f64>,
+    last: Option<Instant>,
+}
+
+impl AdaptiveChunker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录自上次调用以来传完的 `bytes`；第一次调用只开始计时
+    pub fn record(&mut self, bytes: usize) {
+        self.record_at(bytes, Instant::now());
+    }
+
+    fn record_at(&mut self, bytes: usize, now: Instant) {
+        let Some(last) = self.last.replace(now) else {
+            return;
+        };
+        // 时钟精度以下的间隔按 1ms 计，避免除零和虚高的样本
+        let elapsed = now.duration_since(last).max(Duration::from_millis(1));
+        let sample = bytes as f64 / elapsed.as_secs_f64();
+        self.rate = Some(match self.rate {
+            Some(rate) => rate + SMOOTHING * (sample - rate),
+            None => sample,
+        });
+    }
+
+    /// 当前估计的吞吐量（字节/秒）
+    pub fn bytes_per_sec(&self) -> Option<u64> {
+        self.rate.map(|rate| rate as u64)
+    }
+
+    /// 下一块的大小
+    pub fn chunk_size(&self) -> usize {
+        let Some(rate) = self.rate else {
+            return INITIAL_CHUNK_SIZE;
+        };
+        let size = (rate * TARGET_CHUNK_TIME.as_secs_f64()) as usize;
+        (size / CHUNK_ALIGN * CHUNK_ALIGN).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE)
+    }
+
+    /// 两次进度通知之间的最短间隔
+    pub fn progress_interval(&self) -> Duration {
+        let mbps = self.rate.unwrap_or(0.0) / 1_000_000.0;
+        PROGRESS_INTERVAL_PER_MBPS
+            .mul_f64(mbps)
+            .clamp(MIN_PROGRESS_INTERVAL, MAX_PROGRESS_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 以 `bytes_per_sec` 的速度传 `chunks` 块，每块 `chunk` 字节
+    fn simulate(bytes_per_sec: f64, chunk: usize, chunks: u32) -> AdaptiveChunker {
+        let mut chunker = AdaptiveChunker::new();
+        let mut now = Instant::now();
+        chunker.record_at(0, now);
+        let per_chunk = Duration::from_secs_f64(chunk as f64 / bytes_per_sec);
+        for _ in 0..chunks {
+            now += per_chunk;
+            chunker.record_at(chunk, now);
+        }
+        chunker
+    }
+
+    #[test]
+    fn test_initial_values() {
+        let chunker = AdaptiveChunker::new();
+        assert_eq!(chunker.chunk_size(), INITIAL_CHUNK_SIZE);
+        assert_eq!(chunker.progress_interval(), MIN_PROGRESS_INTERVAL);
+        assert_eq!(chunker.bytes_per_sec(), None);
+    }
+
+    #[test]
+    fn test_slow_link_uses_small_chunks() {
+        // 200 KB/s：50ms 只够 10 KB，取下限
+        let chunker = simulate(200_000.0, 16 * 1024, 20);
+        assert_eq!(chunker.chunk_size(), MIN_CHUNK_SIZE);
+        assert_eq!(chunker.progress_interval(), MIN_PROGRESS_INTERVAL);
+    }
+
+    #[test]
+    fn test_fast_link_uses_large_chunks() {
+        // 40 MB/s：50ms 约 2 MB，取上限；进度间隔也放宽到上限
+        let chunker = simulate(40_000_000.0, 1024 * 1024, 20);
+        assert_eq!(chunker.chunk_size(), MAX_CHUNK_SIZE);
+        assert_eq!(chunker.progress_interval(), MAX_PROGRESS_INTERVAL);
+        let rate = chunker.bytes_per_sec().unwrap();
+        assert!((39_000_000..=41_000_000).contains(&rate), "{}", rate);
+    }
+
+    #[test]
+    fn test_medium_link_is_aligned() {
+        // 4 MB/s：50ms 约 200 KB
+        let chunker = simulate(4_000_000.0, 64 * 1024, 50);
+        let size = chunker.chunk_size();
+        assert_eq!(size % CHUNK_ALIGN, 0);
+        assert!((180_000..=200_000).contains(&size), "{}", size);
+        assert!(chunker.progress_interval() > MIN_PROGRESS_INTERVAL);
+        assert!(chunker.progress_interval() < MAX_PROGRESS_INTERVAL);
+    }
+}
@@ -0,0 +1,98 @@
+//! 传输暂停控制
+//!
+//! 接收端暂停时不再读取下载的响应体，TCP 窗口填满后发送端自然停下；
+//! 发送端是 cattysend 时同时通过 WebSocket 发送 `status` 通知它，由发送端显示为已暂停。
+//! 暂停期间连接如果被断开，恢复后用 HTTP `Range` 从已下载的位置续传。
+
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// WebSocket `status` 消息的类型：接收端暂停下载
+///
+/// CatShare 只定义了 1（完成）和 3（拒绝），不会处理其他类型，只发给 cattysend 发送端。
+pub const STATUS_PAUSED: i32 = 4;
+/// WebSocket `status` 消息的类型：接收端恢复下载
+pub const STATUS_RESUMED: i32 = 5;
+
+/// 暂停/恢复句柄
+///
+/// 可以克隆后交给界面，所有克隆共享同一个状态。
+#[derive(Debug, Clone)]
+pub struct TransferControl {
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl Default for TransferControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransferControl {
+    pub fn new() -> Self {
+        let (paused, _) = watch::channel(false);
+        Self {
+            paused: Arc::new(paused),
+        }
+    }
+
+    /// 暂停下载（已暂停时无效果）
+    pub fn pause(&self) {
+        self.paused
+            .send_if_modified(|p| !std::mem::replace(p, true));
+    }
+
+    /// 恢复下载（未暂停时无效果）
+    pub fn resume(&self) {
+        self.paused
+            .send_if_modified(|p| std::mem::replace(p, false));
+    }
+
+    /// 是否处于暂停状态
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// 订阅暂停状态变化
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+}
+
+/// 暂停时等待恢复，未暂停时立即返回
+pub(crate) async fn wait_resumed(paused: &mut watch::Receiver<bool>) {
+    // 发送端随 TransferControl 存活，wait_for 只会在状态满足时返回
+    let _ = paused.wait_for(|p| !*p).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let control = TransferControl::new();
+        let mut rx = control.subscribe();
+        assert!(!control.is_paused());
+
+        control.pause();
+        control.pause();
+        assert!(control.clone().is_paused());
+        assert!(rx.has_changed().unwrap());
+        assert!(*rx.borrow_and_update());
+
+        let waiter = tokio::spawn({
+            let mut rx = control.subscribe();
+            async move { wait_resumed(&mut rx).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+
+        control.resume();
+        waiter.await.unwrap();
+        assert!(!*rx.borrow_and_update());
+        // 未暂停时恢复不产生变化
+        control.resume();
+        assert!(!rx.has_changed().unwrap());
+    }
+}
@@ -0,0 +1,163 @@
+//! 传输服务的连接限制
+//!
+//! 热点上可能同时接入多台设备，发送端不限制时一台设备就能开出大量连接，
+//! 每个下载还会各自读出整个 ZIP。[`ConnectionLimiter`] 统计每个 IP 同时进行的请求
+//! （WebSocket 连接持续到断开）和全局同时进行的下载，超出时由服务器返回 429。
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 连接上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// 每个 IP 同时进行的请求数（接收端正常需要 `/info`、`/websocket`、`/download` 三个）
+    pub per_ip: usize,
+    /// 全局同时进行的下载数
+    pub downloads: usize,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        Self {
+            per_ip: 4,
+            downloads: 2,
+        }
+    }
+}
+
+/// 连接计数
+#[derive(Debug, Default)]
+pub struct ConnectionLimiter {
+    limits: ConnectionLimits,
+    per_ip: Mutex<HashMap<IpAddr, usize>>,
+    downloads: AtomicUsize,
+}
+
+impl ConnectionLimiter {
+    pub fn new(limits: ConnectionLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            ..Default::default()
+        })
+    }
+
+    /// 当前的上限
+    pub fn limits(&self) -> ConnectionLimits {
+        self.limits
+    }
+
+    /// 为 `ip` 占用一个连接，已达上限时返回 `None`
+    pub fn try_connect(self: &Arc<Self>, ip: IpAddr) -> Option<ConnectionGuard> {
+        let mut per_ip = self.per_ip.lock().unwrap();
+        let count = per_ip.entry(ip).or_default();
+        if *count >= self.limits.per_ip {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionGuard {
+            limiter: self.clone(),
+            ip,
+        })
+    }
+
+    /// 占用一个下载名额，已达上限时返回 `None`
+    pub fn try_download(self: &Arc<Self>) -> Option<DownloadGuard> {
+        self.downloads
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.limits.downloads).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| DownloadGuard {
+                limiter: self.clone(),
+            })
+    }
+
+    /// `ip` 当前占用的连接数
+    pub fn connections(&self, ip: IpAddr) -> usize {
+        self.per_ip.lock().unwrap().get(&ip).copied().unwrap_or(0)
+    }
+
+    /// 当前进行中的下载数
+    pub fn active_downloads(&self) -> usize {
+        self.downloads.load(Ordering::Acquire)
+    }
+}
+
+/// 一个连接名额，释放时归还
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    limiter: Arc<ConnectionLimiter>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut per_ip = self.limiter.per_ip.lock().unwrap();
+        if let Some(count) = per_ip.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                per_ip.remove(&self.ip);
+            }
+        }
+    }
+}
+
+/// 一个下载名额，响应体发送完毕（或连接断开）时归还
+#[derive(Debug)]
+pub struct DownloadGuard {
+    limiter: Arc<ConnectionLimiter>,
+}
+
+impl Drop for DownloadGuard {
+    fn drop(&mut self) {
+        self.limiter.downloads.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_ip_limit() {
+        let limiter = ConnectionLimiter::new(ConnectionLimits {
+            per_ip: 2,
+            downloads: 1,
+        });
+        let a: IpAddr = "10.42.0.2".parse().unwrap();
+        let b: IpAddr = "10.42.0.3".parse().unwrap();
+
+        let first = limiter.try_connect(a).unwrap();
+        let _second = limiter.try_connect(a).unwrap();
+        assert!(limiter.try_connect(a).is_none());
+        // 其他设备不受影响
+        let _other = limiter.try_connect(b).unwrap();
+
+        drop(first);
+        assert_eq!(limiter.connections(a), 1);
+        assert!(limiter.try_connect(a).is_some());
+    }
+
+    #[test]
+    fn test_download_limit() {
+        let limiter = ConnectionLimiter::new(ConnectionLimits {
+            per_ip: 4,
+            downloads: 1,
+        });
+        let download = limiter.try_download().unwrap();
+        assert!(limiter.try_download().is_none());
+        drop(download);
+        assert_eq!(limiter.active_downloads(), 0);
+        assert!(limiter.try_download().is_some());
+    }
+
+    #[test]
+    fn test_released_ips_are_forgotten() {
+        let limiter = ConnectionLimiter::new(ConnectionLimits::default());
+        let ip: IpAddr = "10.42.0.2".parse().unwrap();
+        drop(limiter.try_connect(ip).unwrap());
+        assert!(limiter.per_ip.lock().unwrap().is_empty());
+    }
+}
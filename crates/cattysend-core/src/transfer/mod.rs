@@ -4,18 +4,51 @@
 //! - WebSocket 协议实现 (CatShare 兼容)
 //! - HTTP/HTTPS 服务器 (发送端)
 //! - HTTP/HTTPS 客户端 (接收端)
+//! - 反向上传服务 (接收端，可选)
+//! - 可替换的传输层抽象 ([`TransferTransport`])
+//! - 接收端下载的暂停/恢复 ([`TransferControl`])
+//! - 监听端口分配（可限定在配置的端口范围内）
+//! - 发送端的连接数限制（[`ConnectionLimits`]）
+//! - 按链路吞吐量调整块大小和进度间隔（[`AdaptiveChunker`]）
+//! - 两端都是 cattysend 时协商的扩展能力（[`Capabilities`]：压缩、逐文件摘要、
+//!   不打包 ZIP 的原始文件下载、增量同步和文件夹同步）
 
+pub mod adaptive;
+pub mod control;
 pub mod http_server;
+pub mod limit;
+pub mod port;
 pub mod protocol;
 pub mod receiver_client;
 pub mod sender_server;
+pub mod stats;
+pub mod transport;
+pub mod upload_server;
 pub mod websocket_handler;
 
-pub use protocol::{SendRequest, WsMessage};
-pub use receiver_client::{ReceiverCallback, ReceiverClient};
+pub use adaptive::AdaptiveChunker;
+pub use control::TransferControl;
+pub use limit::{ConnectionLimiter, ConnectionLimits};
+pub use port::bind_listener;
+pub use protocol::{
+    Capabilities, DownloadToken, PROTOCOL_V1, PROTOCOL_V2, SendRequest, SenderInfo, SyncRequest,
+    WsMessage, negotiate_version,
+};
+pub use receiver_client::{
+    InsufficientSpace, ProbeFailure, ReceiverCallback, ReceiverClient, Unreachable,
+};
 pub use sender_server::{FileEntry, TransferServer, TransferStatus, TransferTask};
+pub use stats::{FileProgress, StatsTracker, TransferStats};
+pub use transport::{
+    HttpTransport, ServedTask, TransferConnection, TransferTarget, TransferTransport,
+    WarmHttpTransport,
+};
+pub use upload_server::{UploadServer, UploadServerHandle, upload_file};
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
 
 /// 文件信息（用于传输协商）
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -23,6 +56,32 @@ use serde::{Deserialize, Serialize};
 pub struct FileInfo {
     pub name: String,
     pub size: u64,
+    /// 修改时间（Unix 毫秒）
     pub modified_time: u64,
     pub mime_type: Option<String>,
+    /// Unix 权限位（CatShare 不发送此字段）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<u32>,
+    /// 文件内容的 SHA-256（小写十六进制），仅在双方协商了摘要扩展时发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// 计算文件的 SHA-256（小写十六进制），按块读取（阻塞）
+pub(crate) fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 256 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
 }
@@ -0,0 +1,69 @@
+//! 监听端口分配
+//!
+//! 默认由系统分配随机端口。配置了 [`PortRange`] 时（例如防火墙只放行了固定的一段端口），
+//! 按顺序尝试范围内的端口，被占用（`EADDRINUSE`）就换下一个。
+
+use crate::config::PortRange;
+use log::{debug, info};
+use std::io::ErrorKind;
+use tokio::net::TcpListener;
+
+/// 在 `ports` 范围内（`None` 时为随机端口）监听所有地址
+pub async fn bind_listener(ports: Option<PortRange>) -> anyhow::Result<TcpListener> {
+    let Some(range) = ports else {
+        return Ok(TcpListener::bind("0.0.0.0:0").await?);
+    };
+
+    let mut in_use = 0;
+    for port in range.ports() {
+        match TcpListener::bind(("0.0.0.0", port)).await {
+            Ok(listener) => {
+                if in_use > 0 {
+                    info!(
+                        "Bound port {} after {} port(s) in {} were in use",
+                        port, in_use, range
+                    );
+                }
+                return Ok(listener);
+            }
+            Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                debug!("Port {} in use, trying next", port);
+                in_use += 1;
+            }
+            Err(e) => return Err(anyhow::anyhow!("Failed to bind port {}: {}", port, e)),
+        }
+    }
+    Err(anyhow::anyhow!("No free port in range {}", range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bind_listener_skips_ports_in_use() {
+        // 先占住一个随机端口，再让范围从它开始
+        let taken = TcpListener::bind("0.0.0.0:0").await.unwrap();
+        let start = taken.local_addr().unwrap().port();
+        let Some(end) = start.checked_add(20) else {
+            return;
+        };
+        let range = PortRange::new(start, end);
+
+        let listener = bind_listener(Some(range)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_ne!(port, start);
+        assert!(range.contains(port));
+    }
+
+    #[tokio::test]
+    async fn test_bind_listener_reports_exhausted_range() {
+        let taken = TcpListener::bind("0.0.0.0:0").await.unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let err = bind_listener(Some(PortRange::new(port, port)))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(&format!("{}-{}", port, port)));
+    }
+}
@@ -5,17 +5,85 @@
 //! - id: 消息 ID (数字)
 //! - name: 动作名称
 //! - payload: 可选的 JSON 载荷
+//!
+//! # 语法
+//!
+//! ```text
+//! frame   = type ":" id ":" name [ "?" payload ]
+//! type    = 1*( ALPHA / DIGIT / "_" )
+//! id      = 1*DIGIT                      ; u32
+//! name    = 1*( ALPHA / DIGIT / "_" )
+//! payload = <JSON 文本，可以为空>
+//! ```
+//!
+//! 头部只允许字母、数字和下划线，因此不需要转义：头部在第一个 `?` 处结束，
+//! 其后的全部内容都是 payload，其中出现的 `?`、`:` 都原样保留。
+//! 构造消息时 type/name 若含有其他字符，[`WsMessage::validate`] 会拒绝。
+//!
+//! # 协议版本
+//!
+//! - [`PROTOCOL_V1`]：WebSocket 建立后由发送端先发 `versionNegotiation`，
+//!   接收端接受 `sendRequest` 后直接 `GET /download?taskId=...`
+//! - [`PROTOCOL_V2`]（信息优先）：部分 CatShare 版本要求接收端先 `GET /info`
+//!   （[`SenderInfo`]），再打开 WebSocket 并由接收端发起 `versionNegotiation`；
+//!   接受 `sendRequest` 后发送端推送 `downloadToken`（[`DownloadToken`]），
+//!   下载请求需带上 `&token=...`
+//!
+//! 接收端按 `/info` 的响应选择版本（[`negotiate_version`]），没有 `/info` 的发送端按 V1 处理。
+//!
+//! # 扩展能力
+//!
+//! 双方的 `versionNegotiation`（发起和 ack）都在载荷中带上 `cattysend` 字段
+//! （[`Capabilities`]），CatShare 会忽略这个未知字段。只有两端都给出时才按交集
+//! 启用扩展功能（[`Capabilities::negotiate`]），对端是 CatShare 时行为不变。
+//!
+//! 协商了 `raw_files` 时接收端不再下载 ZIP，而是逐个请求 `GET /file?taskId=..&index=N`
+//! 取得原始文件；再加上摘要扩展，输出目录中名称和 SHA-256 都相同的文件直接跳过（增量同步）。
+//! 在此基础上，sendRequest 可以带上 `sync`（[`SyncRequest`]）同步整个文件夹，见 [`crate::sync`]。
 
-use regex::Regex;
+use crate::transfer::FileInfo;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::sync::LazyLock;
 
-static MSG_PATTERN: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^(\w+):(\d+):(\w+)(\?(.*))?$").unwrap());
+/// 单帧最大长度（缩略图等 payload 可能较大，但不应超过此值）
+pub const MAX_FRAME_LEN: usize = 4 * 1024 * 1024;
+
+/// 发送端先发起版本协商的流程
+pub const PROTOCOL_V1: u32 = 1;
+
+/// 接收端先获取 `/info` 并发起版本协商、发送端推送下载令牌的流程
+pub const PROTOCOL_V2: u32 = 2;
+
+/// 本端支持的协议版本
+pub const SUPPORTED_VERSIONS: &[u32] = &[PROTOCOL_V1, PROTOCOL_V2];
+
+/// 消息解析错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WsParseError {
+    #[error("frame is empty")]
+    Empty,
+
+    #[error("frame too long: {0} bytes")]
+    TooLong(usize),
+
+    #[error("missing field: {0}")]
+    MissingField(&'static str),
+
+    #[error("invalid type: {0:?}")]
+    InvalidType(String),
+
+    #[error("invalid id: {0:?}")]
+    InvalidId(String),
+
+    #[error("invalid name: {0:?}")]
+    InvalidName(String),
+
+    #[error("invalid payload: {0}")]
+    InvalidPayload(String),
+}
 
 /// CatShare 兼容的 WebSocket 消息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WsMessage {
     pub msg_type: String,
     pub id: u32,
@@ -33,27 +101,77 @@ impl std::fmt::Display for WsMessage {
     }
 }
 
+/// 头部标识符：非空，只含字母、数字、下划线
+fn is_ident(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
 impl WsMessage {
-    /// 解析 CatShare 格式的消息
+    /// 解析 CatShare 格式的消息，格式错误时返回 `None`
     pub fn parse(text: &str) -> Option<Self> {
-        let caps = MSG_PATTERN.captures(text)?;
+        Self::try_parse(text).ok()
+    }
+
+    /// 解析 CatShare 格式的消息，返回具体的错误原因
+    pub fn try_parse(text: &str) -> Result<Self, WsParseError> {
+        if text.is_empty() {
+            return Err(WsParseError::Empty);
+        }
+        if text.len() > MAX_FRAME_LEN {
+            return Err(WsParseError::TooLong(text.len()));
+        }
+
+        let (header, payload) = match text.split_once('?') {
+            Some((header, payload)) => (header, Some(payload)),
+            None => (text, None),
+        };
 
-        let msg_type = caps.get(1)?.as_str().to_string();
-        let id: u32 = caps.get(2)?.as_str().parse().ok()?;
-        let name = caps.get(3)?.as_str().to_string();
+        let mut fields = header.splitn(3, ':');
+        let msg_type = fields.next().ok_or(WsParseError::MissingField("type"))?;
+        let id = fields.next().ok_or(WsParseError::MissingField("id"))?;
+        let name = fields.next().ok_or(WsParseError::MissingField("name"))?;
 
-        let payload = caps
-            .get(5)
-            .and_then(|m| serde_json::from_str(m.as_str()).ok());
+        if !is_ident(msg_type) {
+            return Err(WsParseError::InvalidType(msg_type.to_string()));
+        }
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(WsParseError::InvalidId(id.to_string()));
+        }
+        let id: u32 = id
+            .parse()
+            .map_err(|_| WsParseError::InvalidId(id.to_string()))?;
+        if !is_ident(name) {
+            return Err(WsParseError::InvalidName(name.to_string()));
+        }
 
-        Some(Self {
-            msg_type,
+        // `?` 后为空视为没有 payload
+        let payload = match payload.map(str::trim) {
+            None | Some("") => None,
+            Some(json) => Some(
+                serde_json::from_str(json)
+                    .map_err(|e| WsParseError::InvalidPayload(e.to_string()))?,
+            ),
+        };
+
+        Ok(Self {
+            msg_type: msg_type.to_string(),
             id,
-            name,
+            name: name.to_string(),
             payload,
         })
     }
 
+    /// 检查消息能否被编码为合法的帧
+    pub fn validate(&self) -> Result<(), WsParseError> {
+        if !is_ident(&self.msg_type) {
+            return Err(WsParseError::InvalidType(self.msg_type.clone()));
+        }
+        if !is_ident(&self.name) {
+            return Err(WsParseError::InvalidName(self.name.clone()));
+        }
+        Ok(())
+    }
+
     /// 创建 action 消息
     pub fn action(id: u32, name: &str, payload: Option<Value>) -> Self {
         Self {
@@ -86,6 +204,42 @@ impl WsMessage {
         )
     }
 
+    /// 信息优先流程中由接收端发起的版本协商
+    pub fn receiver_negotiation(id: u32, version: u32) -> Self {
+        Self::action(
+            id,
+            "versionNegotiation",
+            Some(serde_json::json!({
+                "version": version,
+                "versions": SUPPORTED_VERSIONS,
+                "threadLimit": 5
+            })),
+        )
+    }
+
+    /// 在载荷中附上本端的扩展能力（载荷不是 JSON 对象时不变）
+    pub fn with_capabilities(mut self, capabilities: &Capabilities) -> Self {
+        if let Some(Value::Object(payload)) = &mut self.payload
+            && let Ok(value) = serde_json::to_value(capabilities)
+        {
+            payload.insert(CAPABILITIES_KEY.to_string(), value);
+        }
+        self
+    }
+
+    /// 对端在载荷中给出的扩展能力（CatShare 不会给出）
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.payload
+            .as_ref()?
+            .get(CAPABILITIES_KEY)
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+
+    /// 发送端推送下载令牌
+    pub fn download_token(id: u32, token: &DownloadToken) -> Self {
+        Self::action(id, "downloadToken", serde_json::to_value(token).ok())
+    }
+
     /// 创建状态消息
     pub fn status(id: u32, task_id: &str, status_type: i32, reason: &str) -> Self {
         Self::action(
@@ -123,6 +277,18 @@ pub struct SendRequest {
     pub cat_share_text: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub thumbnail: Option<String>,
+    /// 每个文件的元数据，顺序与 ZIP 中的 `<index>/<name>` 一致（CatShare 不发送）
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub files: Vec<FileInfo>,
+    /// 发起方提供 `/download` 的端口（双向会话中非主机一端使用，CatShare 不发送）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub download_port: Option<u16>,
+    /// 发送端算出的验证码，见 [`SessionCipher::verification_code`](crate::crypto::SessionCipher::verification_code)（CatShare 不发送）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub verification_code: Option<String>,
+    /// 文件夹同步，设置后 `files` 中的名称是相对路径（见 [`crate::sync`]，CatShare 不发送）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sync: Option<SyncRequest>,
 }
 
 impl SendRequest {
@@ -142,6 +308,153 @@ impl SendRequest {
     }
 }
 
+/// sendRequest 中的文件夹同步请求（需要协商出 `raw_files`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncRequest {
+    /// 目录名，接收端保存到 `<下载目录>/<folder>/`
+    pub folder: String,
+    /// 删除接收端目录中不在文件列表里的文件
+    #[serde(default)]
+    pub delete: bool,
+}
+
+/// 发送端 `GET /info` 的响应（[`PROTOCOL_V2`]）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SenderInfo {
+    /// 发送端首选的版本
+    pub version: u32,
+    /// 发送端支持的全部版本（旧版本只给 `version`）
+    #[serde(default)]
+    pub versions: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sender_name: Option<String>,
+}
+
+impl SenderInfo {
+    /// 发送端是否支持 `version`
+    pub fn supports(&self, version: u32) -> bool {
+        self.version == version || self.versions.contains(&version)
+    }
+}
+
+/// 与发送端都支持的最高版本；没有 `/info`（`None`）时为 [`PROTOCOL_V1`]
+pub fn negotiate_version(info: Option<&SenderInfo>) -> u32 {
+    info.and_then(|info| {
+        SUPPORTED_VERSIONS
+            .iter()
+            .rev()
+            .copied()
+            .find(|&v| info.supports(v))
+    })
+    .unwrap_or(PROTOCOL_V1)
+}
+
+/// 版本协商载荷中存放 [`Capabilities`] 的字段
+pub const CAPABILITIES_KEY: &str = "cattysend";
+
+/// ZIP 条目的 Deflate 压缩
+pub const COMPRESSION_DEFLATE: &str = "deflate";
+
+/// 逐文件的 SHA-256 摘要（写在 sendRequest 的 `files[].sha256` 中）
+pub const HASH_SHA256: &str = "sha256";
+
+/// cattysend 之间的扩展能力
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    /// 支持的 ZIP 压缩方式（[`COMPRESSION_DEFLATE`]）
+    #[serde(default)]
+    pub compression: Vec<String>,
+    /// 支持按 `Range` 续传下载
+    #[serde(default)]
+    pub resume: bool,
+    /// 支持的文件摘要算法（[`HASH_SHA256`]）
+    #[serde(default)]
+    pub hashes: Vec<String>,
+    /// 同时下载的连接数上限（1 表示只用一个连接）
+    #[serde(default = "default_streams")]
+    pub streams: u32,
+    /// 支持通过 `/file` 逐个下载原始文件（不打包 ZIP）
+    #[serde(default)]
+    pub raw_files: bool,
+}
+
+fn default_streams() -> u32 {
+    1
+}
+
+impl Default for Capabilities {
+    /// 本端支持的全部扩展
+    fn default() -> Self {
+        Self {
+            compression: vec![COMPRESSION_DEFLATE.to_string()],
+            resume: true,
+            hashes: vec![HASH_SHA256.to_string()],
+            streams: 1,
+            raw_files: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// 不启用任何扩展（对端是 CatShare 时的协商结果）
+    pub fn none() -> Self {
+        Self {
+            compression: Vec::new(),
+            resume: false,
+            hashes: Vec::new(),
+            streams: 1,
+            raw_files: false,
+        }
+    }
+
+    /// 与对端能力的交集；对端没有给出时为 [`Self::none`]
+    pub fn negotiate(&self, peer: Option<&Capabilities>) -> Self {
+        let Some(peer) = peer else {
+            return Self::none();
+        };
+        let common = |ours: &[String], theirs: &[String]| {
+            ours.iter()
+                .filter(|item| theirs.contains(item))
+                .cloned()
+                .collect()
+        };
+        Self {
+            compression: common(&self.compression, &peer.compression),
+            resume: self.resume && peer.resume,
+            hashes: common(&self.hashes, &peer.hashes),
+            streams: self.streams.min(peer.streams).max(1),
+            raw_files: self.raw_files && peer.raw_files,
+        }
+    }
+
+    /// 是否启用了 `method` 压缩
+    pub fn compresses(&self, method: &str) -> bool {
+        self.compression.iter().any(|m| m == method)
+    }
+
+    /// 是否启用了 `algorithm` 摘要
+    pub fn hashes_with(&self, algorithm: &str) -> bool {
+        self.hashes.iter().any(|h| h == algorithm)
+    }
+
+    /// 能否跳过接收端已有的相同文件（需要逐个下载原始文件和 SHA-256 摘要）
+    pub fn delta_sync(&self) -> bool {
+        self.raw_files && self.hashes_with(HASH_SHA256)
+    }
+}
+
+/// `downloadToken` 消息的载荷
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadToken {
+    pub task_id: String,
+    pub token: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,6 +483,58 @@ mod tests {
         assert!(text.starts_with("action:0:versionNegotiation?"));
     }
 
+    #[test]
+    fn test_payload_may_contain_separators() {
+        let msg = WsMessage::parse(r#"action:2:status?{"reason":"why? a:b"}"#).unwrap();
+        assert_eq!(msg.payload.unwrap()["reason"], "why? a:b");
+    }
+
+    #[test]
+    fn test_empty_payload_is_none() {
+        let msg = WsMessage::parse("ack:3:status?").unwrap();
+        assert!(msg.payload.is_none());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(WsMessage::try_parse(""), Err(WsParseError::Empty));
+        assert_eq!(
+            WsMessage::try_parse("action"),
+            Err(WsParseError::MissingField("id"))
+        );
+        assert_eq!(
+            WsMessage::try_parse("action:1"),
+            Err(WsParseError::MissingField("name"))
+        );
+        assert!(matches!(
+            WsMessage::try_parse("action:-1:status"),
+            Err(WsParseError::InvalidId(_))
+        ));
+        assert!(matches!(
+            WsMessage::try_parse("action:99999999999:status"),
+            Err(WsParseError::InvalidId(_))
+        ));
+        assert!(matches!(
+            WsMessage::try_parse("action:1:a:b"),
+            Err(WsParseError::InvalidName(_))
+        ));
+        assert!(matches!(
+            WsMessage::try_parse("action:1:status?{not json"),
+            Err(WsParseError::InvalidPayload(_))
+        ));
+        assert!(matches!(
+            WsMessage::try_parse(&"a".repeat(MAX_FRAME_LEN + 1)),
+            Err(WsParseError::TooLong(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_separator_in_name() {
+        let msg = WsMessage::action(1, "bad?name", None);
+        assert!(msg.validate().is_err());
+        assert!(WsMessage::version_negotiation(0).validate().is_ok());
+    }
+
     #[test]
     fn test_roundtrip() {
         let original = WsMessage::status(99, "task123", 1, "ok");
@@ -180,4 +545,115 @@ mod tests {
         assert_eq!(parsed.id, original.id);
         assert_eq!(parsed.name, original.name);
     }
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(None), PROTOCOL_V1);
+
+        let info: SenderInfo = serde_json::from_str(r#"{"version":2,"taskId":"t1"}"#).unwrap();
+        assert_eq!(info.task_id.as_deref(), Some("t1"));
+        assert_eq!(negotiate_version(Some(&info)), PROTOCOL_V2);
+
+        let legacy: SenderInfo = serde_json::from_str(r#"{"version":1,"versions":[1]}"#).unwrap();
+        assert_eq!(negotiate_version(Some(&legacy)), PROTOCOL_V1);
+
+        // 只支持未知的新版本时退回 V1
+        let future: SenderInfo = serde_json::from_str(r#"{"version":9}"#).unwrap();
+        assert_eq!(negotiate_version(Some(&future)), PROTOCOL_V1);
+    }
+
+    #[test]
+    fn test_capabilities_roundtrip_in_negotiation() {
+        let ours = Capabilities::default();
+        let msg = WsMessage::version_negotiation(0).with_capabilities(&ours);
+        let parsed = WsMessage::parse(&msg.to_string()).unwrap();
+        assert_eq!(parsed.payload.as_ref().unwrap()["version"], 1);
+        assert_eq!(parsed.capabilities(), Some(ours));
+
+        // CatShare 的协商消息没有扩展字段
+        let catshare = WsMessage::parse("action:0:versionNegotiation?{\"version\":1}").unwrap();
+        assert_eq!(catshare.capabilities(), None);
+    }
+
+    #[test]
+    fn test_capabilities_negotiate() {
+        let ours = Capabilities::default();
+        assert_eq!(ours.negotiate(None), Capabilities::none());
+
+        // 旧版 cattysend 只给出部分字段
+        let peer: Capabilities = serde_json::from_str(r#"{"hashes":["sha256","blake3"]}"#).unwrap();
+        let agreed = ours.negotiate(Some(&peer));
+        assert!(agreed.hashes_with(HASH_SHA256));
+        assert!(!agreed.compresses(COMPRESSION_DEFLATE));
+        assert!(!agreed.resume);
+        assert!(!agreed.delta_sync());
+        assert_eq!(agreed.streams, 1);
+
+        assert!(ours.negotiate(Some(&ours)).delta_sync());
+    }
+
+    #[test]
+    fn test_download_token_message() {
+        let token = DownloadToken {
+            task_id: "t1".to_string(),
+            token: "abc".to_string(),
+        };
+        let text = WsMessage::download_token(4, &token).to_string();
+        assert_eq!(
+            text,
+            r#"action:4:downloadToken?{"taskId":"t1","token":"abc"}"#
+        );
+        let parsed = WsMessage::parse(&text).unwrap();
+        let decoded: DownloadToken = serde_json::from_value(parsed.payload.unwrap()).unwrap();
+        assert_eq!(decoded, token);
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn json_value() -> impl Strategy<Value = Value> {
+            let leaf = prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::from),
+                any::<i64>().prop_map(Value::from),
+                ".*".prop_map(Value::from),
+            ];
+            leaf.prop_recursive(3, 16, 4, |inner| {
+                prop_oneof![
+                    prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+                    prop::collection::hash_map("[a-zA-Z?:]{0,8}", inner, 0..4)
+                        .prop_map(|m| Value::Object(m.into_iter().collect())),
+                ]
+            })
+        }
+
+        proptest! {
+            /// 任意输入都不能让解析器 panic
+            #[test]
+            fn parse_never_panics(text in ".*") {
+                let _ = WsMessage::try_parse(&text);
+            }
+
+            /// 看起来像帧的输入也不能 panic
+            #[test]
+            fn parse_frame_like_never_panics(text in "[a-z_]{0,8}:[0-9-]{0,12}:[a-zA-Z:?]{0,12}(\\?.{0,32})?") {
+                let _ = WsMessage::try_parse(&text);
+            }
+
+            /// 合法消息编码后可以原样解析回来
+            #[test]
+            fn roundtrip(
+                msg_type in "[a-zA-Z_][a-zA-Z0-9_]{0,10}",
+                id in any::<u32>(),
+                name in "[a-zA-Z_][a-zA-Z0-9_]{0,20}",
+                payload in proptest::option::of(json_value()),
+            ) {
+                let msg = WsMessage { msg_type, id, name, payload };
+                prop_assert!(msg.validate().is_ok());
+                let parsed = WsMessage::try_parse(&msg.to_string()).unwrap();
+                prop_assert_eq!(parsed, msg);
+            }
+        }
+    }
 }
@@ -6,22 +6,101 @@
 //!
 //! - 连接发送端的 HTTPS WebSocket
 //! - 协商版本和处理发送请求
-//! - 下载 ZIP 文件并解压
+//! - 下载 ZIP 文件并解压到暂存目录，校验大小后移入输出目录
+//! - 可按发送端名称和日期分到子目录，与已有文件重名时自动改名
+//! - 下载可暂停/恢复（[`TransferControl`]），连接中断时按 `Range` 续传
+//! - 兼容信息优先流程（[`PROTOCOL_V2`]）：[`ReceiverClient::detect_protocol`]
+//!   通过 `GET /info` 判断发送端使用的流程
+//! - P2P 信息带有访问令牌时，WebSocket、`/info` 和下载请求都带上 `Authorization: Bearer <令牌>`
+//!   （同时提供多个任务的发送端据此区分任务）
+//! - 版本协商时交换扩展能力（[`Capabilities`]），发送端也是 cattysend 时按
+//!   sendRequest 中的 SHA-256 逐文件校验，逐个下载原始文件而不是 ZIP，
+//!   并跳过输出目录中已有的相同文件
+//! - 文件夹同步（sendRequest 带有 `sync`，见 [`crate::sync`]）：文件按相对路径保存到
+//!   `<输出目录>/<目录名>/`，覆盖旧版本；允许时删除不在文件列表中的文件
+//! - 连接前先探测发送端（[`ReceiverClient::ensure_reachable`]），区分 DNS 解析失败、
+//!   连接被拒绝、超时和 TLS 握手失败（[`ProbeFailure`]），首选地址不通时依次尝试备选地址
 //!
 //! # 安全性
 //!
-//! - 使用 HTTPS 传输（跳过证书验证，因为发送端使用自签名证书）
+//! - 使用 HTTPS 传输（CatShare 发送端使用自签名证书，跳过证书验证；P2P 信息带有
+//!   证书指纹时只接受指纹一致的证书，见 [`ReceiverClient::with_pinned_cert`]）
 //! - WebSocket 协议用于状态同步
 
 use log::{debug, error, info, warn};
 
-use crate::transfer::protocol::{SendRequest, WsMessage};
+use crate::crypto::at_rest::{self, AtRestKey};
+use crate::crypto::tls;
+use crate::transfer::adaptive::AdaptiveChunker;
+use crate::transfer::control::{self, STATUS_PAUSED, STATUS_RESUMED, TransferControl};
+use crate::transfer::protocol::{
+    Capabilities, DownloadToken, HASH_SHA256, PROTOCOL_V1, PROTOCOL_V2, SendRequest, SenderInfo,
+    SyncRequest, WsMessage, negotiate_version,
+};
+use crate::transfer::upload_server::{unique_path, unique_path_with_suffix};
+use crate::transfer::{FileInfo, sha256_file};
 use futures_util::{SinkExt, StreamExt};
-use std::io::Read;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::io::{Read, Write as _};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tokio::fs::{File, create_dir_all};
 use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_native_tls::TlsStream;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderMap, HeaderValue, header};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+/// 已建立的 WebSocket 连接
+pub type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// `GET /info` 的超时（旧版发送端直接返回 404，不会等满）
+const INFO_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 连接前探测发送端时，DNS 解析、TCP 连接和 TLS 握手各自的超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 探测发送端失败的原因
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProbeFailure {
+    /// 主机名无法解析
+    #[error("DNS lookup failed: {0}")]
+    Dns(String),
+    /// 地址可达但端口没有监听
+    #[error("connection refused")]
+    Refused,
+    /// 超时没有响应，地址多半不对
+    #[error("no response within {0:?}")]
+    Timeout(Duration),
+    /// 其他 TCP 错误（如没有路由）
+    #[error("TCP connect failed: {0}")]
+    Tcp(String),
+    /// TCP 已连通但 TLS 握手失败（包括证书指纹不一致）
+    #[error("TLS handshake failed: {0}")]
+    Tls(String),
+}
+
+/// 发送端的所有候选地址都无法连通
+#[derive(Debug, thiserror::Error)]
+#[error("sender unreachable on port {port}: {}", describe_attempts(.attempts))]
+pub struct Unreachable {
+    pub port: u16,
+    /// 尝试过的地址及失败原因，按尝试顺序
+    pub attempts: Vec<(String, ProbeFailure)>,
+}
+
+fn describe_attempts(attempts: &[(String, ProbeFailure)]) -> String {
+    attempts
+        .iter()
+        .map(|(host, failure)| format!("{} ({})", host, failure))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
 /// 接收事件回调
 pub trait ReceiverCallback: Send + Sync {
@@ -31,6 +110,9 @@ pub trait ReceiverCallback: Send + Sync {
     /// 进度更新
     fn on_progress(&self, received: u64, total: u64);
 
+    /// 下载暂停或恢复
+    fn on_paused(&self, _paused: bool) {}
+
     /// 接收完成
     fn on_complete(&self, files: Vec<PathBuf>);
 
@@ -43,6 +125,22 @@ pub struct ReceiverClient {
     host: String,
     port: u16,
     output_dir: PathBuf,
+    tls: bool,
+    restore_permissions: bool,
+    encryption: Option<AtRestKey>,
+    control: TransferControl,
+    sort_by_sender: bool,
+    protocol: u32,
+    auth_token: Option<String>,
+    capabilities: Capabilities,
+    /// 固定的证书指纹（来自 P2P 信息）
+    pinned_cert: Option<String>,
+    /// 核对过指纹的证书（DER），HTTP 客户端只信任它
+    pinned_der: OnceLock<Vec<u8>>,
+    /// 文件夹同步时是否按发送端的要求删除多余的文件
+    allow_sync_delete: bool,
+    /// `host` 不可达时依次尝试的备选地址
+    fallback_hosts: Vec<String>,
 }
 
 impl ReceiverClient {
@@ -51,39 +149,359 @@ impl ReceiverClient {
             host: host.to_string(),
             port,
             output_dir,
+            tls: true,
+            restore_permissions: false,
+            encryption: None,
+            control: TransferControl::new(),
+            sort_by_sender: false,
+            protocol: PROTOCOL_V1,
+            auth_token: None,
+            capabilities: Capabilities::default(),
+            pinned_cert: None,
+            pinned_der: OnceLock::new(),
+            allow_sync_delete: false,
+            fallback_hosts: Vec::new(),
         }
     }
 
-    /// 开始接收
-    pub async fn start<C: ReceiverCallback>(&self, callback: &C) -> anyhow::Result<Vec<PathBuf>> {
-        // 创建输出目录
-        create_dir_all(&self.output_dir).await?;
+    /// 是否使用 TLS（默认开启，与 CatShare 一致；本机回环测试时关闭）
+    pub fn with_tls(mut self, tls: bool) -> Self {
+        self.tls = tls;
+        self
+    }
 
-        // 连接 WebSocket (不验证证书)
-        let ws_url = format!("wss://{}:{}/websocket", self.host, self.port);
-        info!("Connecting to WebSocket: {}", ws_url);
+    /// 是否恢复发送端文件的可执行位（默认关闭，只恢复修改时间）
+    pub fn with_restore_permissions(mut self, restore: bool) -> Self {
+        self.restore_permissions = restore;
+        self
+    }
+
+    /// 设置后收到的文件加密存放为 `<文件名>.cattyenc`（见 [`crate::crypto::at_rest`]）
+    pub fn with_encryption(mut self, key: Option<AtRestKey>) -> Self {
+        self.encryption = key;
+        self
+    }
+
+    /// 使用外部的暂停/恢复句柄（默认每个客户端独立一个）
+    pub fn with_control(mut self, control: TransferControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// 是否按发送端分类保存到 `<输出目录>/<发送端名称>/<YYYY-MM-DD>/`（默认关闭）
+    pub fn with_sort_by_sender(mut self, sort: bool) -> Self {
+        self.sort_by_sender = sort;
+        self
+    }
+
+    /// 使用的协议版本（默认 [`PROTOCOL_V1`]，一般取 [`Self::detect_protocol`] 的结果）
+    pub fn with_protocol(mut self, version: u32) -> Self {
+        self.protocol = version;
+        self
+    }
+
+    /// 发送端要求的访问令牌（来自 P2P 信息，见 [`TransferTask::auth_token`](crate::transfer::TransferTask::auth_token)）
+    pub fn with_auth_token(mut self, token: Option<String>) -> Self {
+        self.auth_token = token;
+        self
+    }
+
+    /// 向发送端提供的扩展能力（默认 [`Capabilities::default`]，[`Capabilities::none`] 关闭扩展）
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// 只接受 SHA-256 指纹为 `fingerprint` 的证书（来自 P2P 信息，见 [`crate::crypto::tls`]）
+    ///
+    /// 设置后总是使用 TLS，忽略 [`Self::with_tls`]；`None` 时保持跳过证书验证（CatShare）。
+    pub fn with_pinned_cert(mut self, fingerprint: Option<String>) -> Self {
+        self.pinned_cert = fingerprint;
+        self
+    }
+
+    /// 是否允许文件夹同步删除目标目录中发送端已没有的文件（默认不允许，只新增和覆盖）
+    pub fn with_sync_delete(mut self, allow: bool) -> Self {
+        self.allow_sync_delete = allow;
+        self
+    }
+
+    /// 当前连接的发送端地址（[`Self::ensure_reachable`] 之后为实际连通的地址）
+    pub fn host(&self) -> &str {
+        &self.host
+    }
 
-        // 使用不验证证书的 TLS 配置
+    /// 首选地址不可达时依次尝试的备选地址（见 [`Self::ensure_reachable`]）
+    pub fn with_fallback_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.fallback_hosts = hosts;
+        self
+    }
+
+    /// 能否接受文件夹同步，不能时返回拒绝原因
+    fn check_sync(
+        &self,
+        sync: &SyncRequest,
+        negotiated: &Capabilities,
+    ) -> Result<(), &'static str> {
+        if !negotiated.raw_files {
+            return Err("sync unsupported");
+        }
+        // 加密保存的文件无法按名称覆盖，也无法与发送端比较
+        if self.encryption.is_some() {
+            return Err("sync unsupported with encryption");
+        }
+        if safe_file_name(&sync.folder).is_none() {
+            return Err("invalid sync folder");
+        }
+        Ok(())
+    }
+
+    fn uses_tls(&self) -> bool {
+        self.tls || self.pinned_cert.is_some()
+    }
+
+    /// 完成 TLS 握手；固定了证书时核对指纹，不一致时在发送任何请求之前断开
+    async fn tls_connect(&self, tcp_stream: TcpStream) -> anyhow::Result<TlsStream<TcpStream>> {
+        // 自签名证书无法按 CA 验证，固定证书时在握手后自行核对
         let connector = native_tls::TlsConnector::builder()
             .danger_accept_invalid_certs(true)
             .build()?;
         let connector = tokio_native_tls::TlsConnector::from(connector);
+        let stream = connector.connect(&self.host, tcp_stream).await?;
+        if let Some(expected) = &self.pinned_cert {
+            let der = stream
+                .get_ref()
+                .peer_certificate()?
+                .map(|cert| cert.to_der())
+                .transpose()?
+                .ok_or_else(|| anyhow::anyhow!("Sender presented no TLS certificate"))?;
+            if !tls::matches_fingerprint(&der, expected) {
+                anyhow::bail!(
+                    "TLS certificate mismatch: expected {}, got {}",
+                    expected,
+                    tls::fingerprint(&der)
+                );
+            }
+            let _ = self.pinned_der.set(der);
+        }
+        Ok(stream)
+    }
+
+    /// HTTP 客户端：固定了证书时只信任该证书，否则跳过证书验证
+    async fn http_client(&self, timeout: Option<Duration>) -> anyhow::Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().default_headers(self.auth_headers()?);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if self.pinned_cert.is_some() {
+            if self.pinned_der.get().is_none() {
+                // 先单独握手一次取得证书，令牌只会发给核对过的发送端
+                let tcp_stream = TcpStream::connect(format!("{}:{}", self.host, self.port)).await?;
+                self.tls_connect(tcp_stream).await?;
+            }
+            let der = self
+                .pinned_der
+                .get()
+                .ok_or_else(|| anyhow::anyhow!("Pinned certificate unavailable"))?;
+            // 按 IP 连接，证书里的主机名对不上，只校验证书本身
+            builder = builder
+                .tls_built_in_root_certs(false)
+                .add_root_certificate(reqwest::Certificate::from_der(der)?)
+                .danger_accept_invalid_hostnames(true);
+        } else {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// 带访问令牌的请求头（没有令牌时为空）
+    fn auth_headers(&self) -> anyhow::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = &self.auth_token {
+            let value = HeaderValue::from_str(&format!("Bearer {}", token))?;
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+
+    /// 通过 `GET /info` 判断发送端的协议版本
+    ///
+    /// 请求失败或响应无法解析时视为不支持 `/info` 的旧版发送端，返回 [`PROTOCOL_V1`]。
+    pub async fn detect_protocol(&self) -> u32 {
+        let info = match self.fetch_info().await {
+            Ok(info) => Some(info),
+            Err(e) => {
+                debug!("No usable /info ({}), assuming protocol v1", e);
+                None
+            }
+        };
+        let version = negotiate_version(info.as_ref());
+        info!("Using transfer protocol v{}", version);
+        version
+    }
+
+    async fn fetch_info(&self) -> anyhow::Result<SenderInfo> {
+        let client = self.http_client(Some(INFO_TIMEOUT)).await?;
+        let info = client
+            .get(self.url("http", "/info"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(info)
+    }
+
+    fn url(&self, scheme: &str, path: &str) -> String {
+        let scheme = if self.uses_tls() {
+            format!("{}s", scheme)
+        } else {
+            scheme.to_string()
+        };
+        format!("{}://{}:{}{}", scheme, self.host, self.port, path)
+    }
+
+    /// 找到可连通的发送端地址
+    ///
+    /// 依次探测首选地址和 [`Self::with_fallback_hosts`] 中的地址：解析地址、建立 TCP 连接，
+    /// 使用 TLS 时再完成握手（固定了证书时同时核对指纹）。之后的请求都使用第一个连通的地址；
+    /// 全部失败时返回每个地址的失败原因。
+    pub async fn ensure_reachable(mut self) -> Result<Self, Unreachable> {
+        let mut hosts = vec![self.host.clone()];
+        for host in std::mem::take(&mut self.fallback_hosts) {
+            if !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        }
+
+        let mut attempts = Vec::new();
+        for host in hosts {
+            self.host = host;
+            match self.probe().await {
+                Ok(()) => {
+                    if !attempts.is_empty() {
+                        info!("Sender reachable at fallback address {}", self.host);
+                    }
+                    return Ok(self);
+                }
+                Err(failure) => {
+                    warn!(
+                        "Sender {}:{} unreachable: {}",
+                        self.host, self.port, failure
+                    );
+                    attempts.push((self.host.clone(), failure));
+                }
+            }
+        }
+        Err(Unreachable {
+            port: self.port,
+            attempts,
+        })
+    }
+
+    /// 探测当前地址能否连通
+    async fn probe(&self) -> Result<(), ProbeFailure> {
+        let addr = match tokio::time::timeout(
+            PROBE_TIMEOUT,
+            tokio::net::lookup_host((self.host.as_str(), self.port)),
+        )
+        .await
+        {
+            Ok(Ok(mut addrs)) => addrs
+                .next()
+                .ok_or_else(|| ProbeFailure::Dns("no addresses".to_string()))?,
+            Ok(Err(e)) => return Err(ProbeFailure::Dns(e.to_string())),
+            Err(_) => return Err(ProbeFailure::Dns("lookup timed out".to_string())),
+        };
+
+        let tcp_stream = match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                return Err(ProbeFailure::Refused);
+            }
+            Ok(Err(e)) => return Err(ProbeFailure::Tcp(e.to_string())),
+            Err(_) => return Err(ProbeFailure::Timeout(PROBE_TIMEOUT)),
+        };
+
+        if self.uses_tls() {
+            match tokio::time::timeout(PROBE_TIMEOUT, self.tls_connect(tcp_stream)).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(ProbeFailure::Tls(e.to_string())),
+                Err(_) => return Err(ProbeFailure::Tls("handshake timed out".to_string())),
+            }
+        }
+        debug!("Sender {}:{} reachable", self.host, self.port);
+        Ok(())
+    }
+
+    /// 开始接收（探测地址 + 探测协议版本 + 连接 + 接收）
+    pub async fn start<C: ReceiverCallback + ?Sized>(
+        self,
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let client = self.ensure_reachable().await?;
+        let version = client.detect_protocol().await;
+        let client = client.with_protocol(version);
+        let ws_stream = client.connect().await?;
+        client.receive(ws_stream, callback).await
+    }
+
+    /// 连接发送端的 WebSocket
+    ///
+    /// 与 [`Self::receive`] 分开，便于调用方只对连接阶段重试
+    #[tracing::instrument(skip_all, fields(host = %self.host, port = self.port, tls = self.uses_tls()))]
+    pub async fn connect(&self) -> anyhow::Result<WsStream> {
+        let ws_url = self.url("ws", "/websocket");
+        info!("Connecting to WebSocket: {}", ws_url);
 
         // 建立 TCP 连接
-        let tcp_stream =
-            tokio::net::TcpStream::connect(format!("{}:{}", self.host, self.port)).await?;
+        let tcp_stream = TcpStream::connect(format!("{}:{}", self.host, self.port)).await?;
 
-        // TLS 握手
-        let tls_stream = connector.connect(&self.host, tcp_stream).await?;
+        let stream = if self.uses_tls() {
+            MaybeTlsStream::NativeTls(self.tls_connect(tcp_stream).await?)
+        } else {
+            MaybeTlsStream::Plain(tcp_stream)
+        };
 
         // WebSocket 握手
-        let (ws_stream, _) = tokio_tungstenite::client_async(&ws_url, tls_stream).await?;
+        let mut request = ws_url.as_str().into_client_request()?;
+        request.headers_mut().extend(self.auth_headers()?);
+        let (ws_stream, _) = tokio_tungstenite::client_async(request, stream).await?;
+
+        Ok(ws_stream)
+    }
+
+    /// 在已建立的连接上完成协商并下载文件
+    #[tracing::instrument(skip_all, fields(host = %self.host, port = self.port))]
+    pub async fn receive<C: ReceiverCallback + ?Sized>(
+        &self,
+        ws_stream: WsStream,
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        // 创建输出目录
+        create_dir_all(&self.output_dir).await?;
 
         let (mut write, mut read) = ws_stream.split();
 
         let mut msg_id: u32 = 0;
         let mut task_id: Option<String> = None;
         let mut total_size: u64 = 0;
+        let mut file_infos: Vec<FileInfo> = Vec::new();
+        let mut sender_name = String::new();
+        let mut download_token: Option<String> = None;
+        let mut sync: Option<SyncRequest> = None;
+        // 发送端是 CatShare 时不启用任何扩展
+        let mut negotiated = Capabilities::none();
+
+        // 信息优先流程由接收端发起协商
+        let info_first = self.protocol >= PROTOCOL_V2;
+        // 发送端是 cattysend（协商时给出了扩展能力，或支持信息优先流程）
+        let mut cattysend_peer = info_first;
+        if info_first {
+            let negotiation = WsMessage::receiver_negotiation(msg_id, self.protocol)
+                .with_capabilities(&self.capabilities);
+            write.send(Message::Text(negotiation.to_string())).await?;
+        }
 
         // 消息循环
         while let Some(msg) = read.next().await {
@@ -110,9 +528,20 @@ impl ReceiverClient {
                 ws_msg.msg_type, ws_msg.name
             );
 
+            // 发送端对我方消息的确认（信息优先流程中的版本协商）不需要回复
+            if ws_msg.msg_type == "ack" {
+                if ws_msg.name == "versionNegotiation" {
+                    negotiated = self.capabilities.negotiate(ws_msg.capabilities().as_ref());
+                    cattysend_peer |= ws_msg.capabilities().is_some();
+                }
+                continue;
+            }
+
             match ws_msg.name.as_str() {
                 "versionNegotiation" => {
                     // 版本协商
+                    negotiated = self.capabilities.negotiate(ws_msg.capabilities().as_ref());
+                    cattysend_peer |= ws_msg.capabilities().is_some();
                     let ack = WsMessage::ack(
                         ws_msg.id,
                         "versionNegotiation",
@@ -120,7 +549,8 @@ impl ReceiverClient {
                             "version": 1,
                             "threadLimit": 5
                         })),
-                    );
+                    )
+                    .with_capabilities(&self.capabilities);
                     write.send(Message::Text(ack.to_string())).await?;
                 }
 
@@ -135,10 +565,35 @@ impl ReceiverClient {
                             }
                         };
                         total_size = request.total_size;
+                        file_infos = request.files.clone();
+                        sender_name = request.sender_name.clone();
 
                         // 获取任务 ID
                         let req_task_id = request.get_task_id();
 
+                        if let Some(request_sync) = &request.sync {
+                            if let Err(reason) = self.check_sync(request_sync, &negotiated) {
+                                warn!("Rejecting folder sync: {}", reason);
+                                msg_id += 1;
+                                let status = WsMessage::status(msg_id, &req_task_id, 3, reason);
+                                write.send(Message::Text(status.to_string())).await?;
+                                callback.on_error(format!("Folder sync rejected: {}", reason));
+                                anyhow::bail!("Folder sync rejected: {}", reason);
+                            }
+                            sync = request.sync.clone();
+                        }
+
+                        // 空间不足时直接拒绝，不再询问用户
+                        if let Err(e) = check_free_space(&self.output_dir, total_size) {
+                            warn!("Rejecting transfer: {}", e);
+                            msg_id += 1;
+                            let status =
+                                WsMessage::status(msg_id, &req_task_id, 3, "insufficient space");
+                            write.send(Message::Text(status.to_string())).await?;
+                            callback.on_error(e.to_string());
+                            return Err(e.into());
+                        }
+
                         // 询问用户是否接受
                         if callback.on_send_request(&request) {
                             task_id = Some(req_task_id.clone());
@@ -147,8 +602,10 @@ impl ReceiverClient {
                             let ack = WsMessage::ack(ws_msg.id, "sendRequest", None);
                             write.send(Message::Text(ack.to_string())).await?;
 
-                            // 开始下载
-                            break;
+                            // 开始下载（信息优先流程还要等下载令牌）
+                            if !info_first {
+                                break;
+                            }
                         } else {
                             // 拒绝
                             msg_id += 1;
@@ -159,6 +616,20 @@ impl ReceiverClient {
                     }
                 }
 
+                "downloadToken" if task_id.is_some() => {
+                    let token: DownloadToken = ws_msg
+                        .payload
+                        .and_then(|p| serde_json::from_value(p).ok())
+                        .ok_or_else(|| anyhow::anyhow!("Protocol error: invalid downloadToken"))?;
+                    if task_id.as_deref() != Some(token.task_id.as_str()) {
+                        anyhow::bail!("Protocol error: downloadToken for unknown task");
+                    }
+                    let ack = WsMessage::ack(ws_msg.id, "downloadToken", None);
+                    write.send(Message::Text(ack.to_string())).await?;
+                    download_token = Some(token.token);
+                    break;
+                }
+
                 _ => {
                     // 发送 ACK
                     let ack = WsMessage::ack(ws_msg.id, &ws_msg.name, None);
@@ -167,25 +638,56 @@ impl ReceiverClient {
             }
         }
 
-        // 下载文件
+        // 下载文件，期间把暂停/恢复通过 status 消息告诉 cattysend 发送端
         let task_id = task_id.ok_or_else(|| anyhow::anyhow!("No task ID received"))?;
-        let download_url = format!(
-            "https://{}:{}/download?taskId={}",
-            self.host, self.port, task_id
-        );
-
-        info!("Downloading file from: {}", download_url);
-
-        // 使用不验证证书的 HTTP 客户端
-        let client = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?;
-
-        let response = client.get(&download_url).send().await?;
-        let zip_bytes = response.bytes().await?;
-
-        // 解压 ZIP
-        let files = self.extract_zip(&zip_bytes, callback, total_size).await?;
+        if info_first && download_token.is_none() {
+            anyhow::bail!("Sender closed the connection before sending a download token");
+        }
+        let output_dir = match &sync {
+            Some(sync) => self.output_dir.join(&sync.folder),
+            None if self.sort_by_sender => {
+                sender_dir(&self.output_dir, &sender_name, &local_date())
+            }
+            None => self.output_dir.clone(),
+        };
+        if negotiated != Capabilities::none() {
+            debug!("Negotiated capabilities: {:?}", negotiated);
+        }
+        let plan = DownloadPlan {
+            task_id: &task_id,
+            token: download_token.as_deref(),
+            total_size,
+            file_infos: &file_infos,
+            negotiated: &negotiated,
+            sync: sync.as_ref(),
+        };
+        let download = self.download_into(&output_dir, &plan, callback);
+        tokio::pin!(download);
+        let mut paused = self.control.subscribe();
+        if *paused.borrow() {
+            // 下载开始前就已暂停，同样要通知发送端
+            paused.mark_changed();
+        }
+        let files = loop {
+            tokio::select! {
+                result = &mut download => break result?,
+                Ok(()) = paused.changed() => {
+                    let is_paused = *paused.borrow_and_update();
+                    // CatShare 不认识暂停/恢复状态，对它只靠停止读取响应体来暂停
+                    if cattysend_peer {
+                        let (status_type, reason) = if is_paused {
+                            (STATUS_PAUSED, "paused")
+                        } else {
+                            (STATUS_RESUMED, "resumed")
+                        };
+                        msg_id += 1;
+                        let status = WsMessage::status(msg_id, &task_id, status_type, reason);
+                        write.send(Message::Text(status.to_string())).await?;
+                    }
+                    callback.on_paused(is_paused);
+                }
+            }
+        };
 
         // 发送完成状态
         msg_id += 1;
@@ -197,46 +699,1051 @@ impl ReceiverClient {
         Ok(files)
     }
 
-    async fn extract_zip<C: ReceiverCallback>(
+    /// 下载 `task_id` 对应的 ZIP 并解压到输出目录
+    ///
+    /// 用于 sendRequest 已被接受之后；不发送任何 WebSocket 消息。
+    pub async fn download<C: ReceiverCallback + ?Sized>(
         &self,
-        data: &[u8],
-        callback: &C,
+        task_id: &str,
         total_size: u64,
+        file_infos: &[FileInfo],
+        callback: &C,
     ) -> anyhow::Result<Vec<PathBuf>> {
-        let cursor = std::io::Cursor::new(data);
-        let mut archive = zip::ZipArchive::new(cursor)?;
+        let none = Capabilities::none();
+        let plan = DownloadPlan {
+            task_id,
+            token: None,
+            total_size,
+            file_infos,
+            negotiated: &none,
+            sync: None,
+        };
+        self.download_into(&self.output_dir, &plan, callback).await
+    }
 
-        let mut received: u64 = 0;
-        let mut files = Vec::new();
+    /// 同 [`Self::download`]，但文件最终移入 `output_dir`（暂存目录仍在客户端的输出目录下）
+    #[tracing::instrument(skip_all, fields(task_id = %plan.task_id, total_size = plan.total_size))]
+    async fn download_into<C: ReceiverCallback + ?Sized>(
+        &self,
+        output_dir: &Path,
+        plan: &DownloadPlan<'_>,
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        create_dir_all(&self.output_dir).await?;
+        create_dir_all(output_dir).await?;
+        let client = self.http_client(None).await?;
+
+        // 下载和解压都在隐藏的暂存目录中进行，校验通过后才移入输出目录，
+        // 连接中断时输出目录里不会留下不完整的文件
+        let staging_dir = self.output_dir.join(format!(".cattysend-{}", plan.task_id));
+        let started = Instant::now();
+        let result = match self
+            .stage(&client, plan, &staging_dir, output_dir, callback)
+            .await
+        {
+            Ok(staged) => {
+                let committed = match (plan.sync, &self.encryption) {
+                    (Some(sync), _) => {
+                        self.commit_sync(&staged.files, &staging_dir, output_dir, plan, sync)
+                            .await
+                    }
+                    (None, Some(key)) => commit_encrypted(&staged.files, output_dir, key).await,
+                    (None, None) => commit_staged(&staged.files, output_dir).await,
+                };
+                committed.map(|mut files| {
+                    files.extend(staged.unchanged);
+                    files
+                })
+            }
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(_) => {
+                crate::metrics::transfer_finished("receive", plan.total_size, started.elapsed())
+            }
+            Err(_) => crate::metrics::failure("download"),
+        }
+        let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+        result
+    }
 
-        for i in 0..archive.len() {
-            // 读取并写入 (先读到内存，释放 zip 文件句柄避免跨 await)
-            let (filename, buffer, is_dir) = {
-                let mut file = archive.by_index(i)?;
-                let is_dir = file.is_dir();
-                let name = file.name().to_string();
-                let filename = name.split('/').next_back().unwrap_or(&name).to_string();
-                let mut buffer = Vec::new();
-                if !is_dir {
-                    file.read_to_end(&mut buffer)?;
+    /// 文件夹同步：按相对路径覆盖到 `output_dir`，允许时再删除不在文件列表中的文件
+    async fn commit_sync(
+        &self,
+        staged: &[PathBuf],
+        staging_dir: &Path,
+        output_dir: &Path,
+        plan: &DownloadPlan<'_>,
+        sync: &SyncRequest,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let files = commit_replacing(staged, &staging_dir.join("files"), output_dir).await?;
+        if sync.delete {
+            if self.allow_sync_delete {
+                let keep: HashSet<PathBuf> = plan
+                    .file_infos
+                    .iter()
+                    .filter_map(|info| plan.local_path(&info.name))
+                    .collect();
+                let dir = output_dir.to_path_buf();
+                let removed =
+                    tokio::task::spawn_blocking(move || remove_unlisted(&dir, &keep)).await??;
+                if removed > 0 {
+                    info!(
+                        "Removed {} file(s) no longer present on the sender",
+                        removed
+                    );
                 }
-                (filename, buffer, is_dir)
+            } else {
+                warn!("Sender asked to delete files, but sync deletion is not allowed");
+            }
+        }
+        Ok(files)
+    }
+
+    /// 把文件下载到 `staging_dir` 中的 `files/`，校验后返回
+    ///
+    /// 协商了 `raw_files` 时逐个下载原始文件，否则下载 ZIP 再解压。
+    async fn stage<C: ReceiverCallback + ?Sized>(
+        &self,
+        client: &reqwest::Client,
+        plan: &DownloadPlan<'_>,
+        staging_dir: &Path,
+        output_dir: &Path,
+        callback: &C,
+    ) -> anyhow::Result<Staged> {
+        let files_dir = staging_dir.join("files");
+        create_dir_all(&files_dir).await?;
+        if self.encryption.is_some() {
+            // 加密前的明文只对本用户可见
+            let mode = std::fs::Permissions::from_mode(0o700);
+            tokio::fs::set_permissions(staging_dir, mode).await?;
+        }
+
+        let mut unchanged = Vec::new();
+        let files = if plan.negotiated.raw_files && !plan.file_infos.is_empty() {
+            // 加密保存的文件无法与发送端的摘要比较，总是重新下载
+            if plan.negotiated.delta_sync() && self.encryption.is_none() {
+                let (dir, infos) = (output_dir.to_path_buf(), plan.file_infos.to_vec());
+                let nested = plan.sync.is_some();
+                unchanged =
+                    tokio::task::spawn_blocking(move || unchanged_files(&dir, &infos, nested))
+                        .await?;
+                if !unchanged.is_empty() {
+                    info!("Skipping {} unchanged file(s)", unchanged.len());
+                }
+            }
+            self.download_raw(client, plan, &files_dir, &unchanged, callback)
+                .await?
+        } else {
+            // 流式写入磁盘，内存占用与 ZIP 大小无关
+            let url = self.url("http", &plan.path("/download", None));
+            info!("Downloading file from: {}", url);
+            let zip_path = staging_dir.join("download.zip");
+            // ZIP 与文件总大小相近（压缩时更小），下载进度直接按字节上报
+            let mut downloaded = 0;
+            download_to_file(client, &url, &zip_path, &self.control, |n| {
+                downloaded = n.min(plan.total_size);
+                callback.on_progress(downloaded, plan.total_size)
+            })
+            .await?;
+            self.extract_zip(
+                &zip_path,
+                &files_dir,
+                callback,
+                plan.total_size,
+                downloaded,
+                plan.file_infos,
+            )
+            .await?
+        };
+        verify_staged(&files, &files_dir, plan.file_infos, plan.total_size)?;
+        if plan.negotiated.hashes_with(HASH_SHA256) {
+            let (staged, infos) = (files.clone(), plan.file_infos.to_vec());
+            tokio::task::spawn_blocking(move || verify_hashes(&staged, &files_dir, &infos))
+                .await??;
+        }
+
+        Ok(Staged {
+            files,
+            unchanged: unchanged
+                .iter()
+                .filter_map(|&i| plan.local_path(&plan.file_infos[i].name))
+                .map(|path| output_dir.join(path))
+                .collect(),
+        })
+    }
+
+    /// 通过 `/file` 逐个下载原始文件到 `files_dir`，跳过下标在 `skip` 中的文件
+    async fn download_raw<C: ReceiverCallback + ?Sized>(
+        &self,
+        client: &reqwest::Client,
+        plan: &DownloadPlan<'_>,
+        files_dir: &Path,
+        skip: &[usize],
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let mut received: u64 = skip.iter().map(|&i| plan.file_infos[i].size).sum();
+        callback.on_progress(received, plan.total_size);
+
+        let mut files = Vec::new();
+        for (index, info) in plan.file_infos.iter().enumerate() {
+            if skip.contains(&index) {
+                continue;
+            }
+            let name = plan
+                .local_path(&info.name)
+                .ok_or_else(|| anyhow::anyhow!("Invalid file name from sender: {:?}", info.name))?;
+            let path = files_dir.join(&name);
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent).await?;
+            }
+            let url = self.url("http", &plan.path("/file", Some(index)));
+            debug!("Downloading {:?} from {}", name, url);
+
+            let before = received;
+            download_to_file(client, &url, &path, &self.control, |n| {
+                callback.on_progress(before + n, plan.total_size)
+            })
+            .await?;
+            received += info.size;
+
+            // 元数据恢复失败不影响文件本身
+            if let Err(e) = apply_metadata(&path, info, self.restore_permissions) {
+                warn!("Failed to restore metadata for {:?}: {}", path, e);
+            }
+            files.push(path);
+        }
+        Ok(files)
+    }
+
+    /// 解压已下载的 ZIP 到 `dest_dir`
+    ///
+    /// 解压在阻塞线程中按块进行，进度经 channel 回到当前任务再交给回调。
+    /// 下载时已经上报到 `reported`，解压进度只在超过它之后上报，保证进度不倒退。
+    async fn extract_zip<C: ReceiverCallback + ?Sized>(
+        &self,
+        zip_path: &Path,
+        dest_dir: &Path,
+        callback: &C,
+        total_size: u64,
+        reported: u64,
+        file_infos: &[FileInfo],
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
+        let zip_path = zip_path.to_path_buf();
+        let output_dir = dest_dir.to_path_buf();
+        let file_infos = file_infos.to_vec();
+        let restore_exec = self.restore_permissions;
+
+        let task = tokio::task::spawn_blocking(move || {
+            extract_zip_blocking(&zip_path, &output_dir, &file_infos, restore_exec, |n| {
+                let _ = progress_tx.send(n);
+            })
+        });
+
+        while let Some(received) = progress_rx.recv().await {
+            if received > reported {
+                callback.on_progress(received, total_size);
+            }
+        }
+
+        task.await?
+    }
+}
+
+/// 一次下载的参数（来自 sendRequest 和版本协商）
+struct DownloadPlan<'a> {
+    task_id: &'a str,
+    /// 信息优先流程中发送端推送的下载令牌
+    token: Option<&'a str>,
+    total_size: u64,
+    file_infos: &'a [FileInfo],
+    /// 与发送端协商出的扩展能力
+    negotiated: &'a Capabilities,
+    /// 文件夹同步请求，设置后文件名为相对路径
+    sync: Option<&'a SyncRequest>,
+}
+
+impl DownloadPlan<'_> {
+    /// `/download` 或 `/file`（带文件下标）的路径和查询参数
+    ///
+    /// taskId 和令牌来自对端，按查询参数编码，`&`、`#` 等字符不能混进其他参数。
+    fn path(&self, route: &str, index: Option<usize>) -> String {
+        let mut url = reqwest::Url::parse("http://localhost").expect("valid base URL");
+        url.set_path(route);
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("taskId", self.task_id);
+            if let Some(index) = index {
+                query.append_pair("index", &index.to_string());
+            }
+            if let Some(token) = self.token {
+                query.append_pair("token", token);
+            }
+        }
+        format!("{}?{}", url.path(), url.query().unwrap_or_default())
+    }
+
+    /// 发送端给出的文件名对应的相对路径
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        local_path(name, self.sync.is_some())
+    }
+}
+
+/// 暂存结果
+struct Staged {
+    /// 下载到暂存目录、等待移入输出目录的文件
+    files: Vec<PathBuf>,
+    /// 输出目录中已有且内容相同、未重新下载的文件
+    unchanged: Vec<PathBuf>,
+}
+
+/// 输出目录中名称、大小和 SHA-256 都与发送端相同的文件（下标），增量同步时跳过
+///
+/// 没有摘要的文件总是下载；`nested` 见 [`local_path`]。
+fn unchanged_files(output_dir: &Path, file_infos: &[FileInfo], nested: bool) -> Vec<usize> {
+    file_infos
+        .iter()
+        .enumerate()
+        .filter(|(_, info)| {
+            let (Some(name), Some(expected)) =
+                (local_path(&info.name, nested), info.sha256.as_deref())
+            else {
+                return false;
             };
+            let path = output_dir.join(name);
+            std::fs::metadata(&path).is_ok_and(|m| m.is_file() && m.len() == info.size)
+                && sha256_file(&path).is_ok_and(|actual| actual.eq_ignore_ascii_case(expected))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// 发送端给出的文件名可以直接作为路径的最后一段时返回它（不含分隔符，不是 `.`/`..`）
+fn safe_file_name(name: &str) -> Option<&str> {
+    Path::new(name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .filter(|n| *n == name)
+}
+
+/// 发送端给出的文件名对应的相对路径，不安全时为 None
+///
+/// 文件夹同步（`nested`）时允许 `/` 分隔的多级路径，每一级都必须是 [`safe_file_name`]。
+fn local_path(name: &str, nested: bool) -> Option<PathBuf> {
+    if !nested {
+        return safe_file_name(name).map(PathBuf::from);
+    }
+    name.split('/')
+        .map(safe_file_name)
+        .collect::<Option<PathBuf>>()
+}
+
+/// 暂存文件相对 `files_dir` 的名称（`/` 分隔），与 sendRequest 中的文件名对应
+fn staged_name(files_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(files_dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 连接中断后最多连续续传的次数（每成功收到数据后重新计数）
+const MAX_RESUME_ATTEMPTS: u32 = 3;
 
-            if is_dir {
+/// 把 HTTP 响应体逐块写入文件，`on_received` 收到累计的字节数
+///
+/// 暂停期间不读取响应体；读取出错且发送端支持 `Range` 时，从已写入的位置重新请求。
+async fn download_to_file(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    control: &TransferControl,
+    mut on_received: impl FnMut(u64),
+) -> anyhow::Result<()> {
+    let mut paused = control.subscribe();
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let mut file = File::create(path).await?;
+    // 已收到的字节数（含尚未写盘的部分），续传从这里开始
+    let mut written: u64 = 0;
+    let mut attempts = 0;
+    // 慢链路上尽快落盘，快链路上攒成大块减少写入次数
+    let mut chunker = AdaptiveChunker::new();
+    let mut pending = Vec::with_capacity(chunker.chunk_size());
+    chunker.record(0);
+    loop {
+        control::wait_resumed(&mut paused).await;
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) if attempts < MAX_RESUME_ATTEMPTS && accepts_ranges(&response) => {
+                attempts += 1;
+                warn!(
+                    "Download interrupted at {} bytes ({}), resuming",
+                    written, e
+                );
+                response = resume_request(client, url, written).await?;
                 continue;
             }
+            Err(e) => return Err(e.into()),
+        };
+        chunker.record(chunk.len());
+        pending.extend_from_slice(&chunk);
+        written += chunk.len() as u64;
+        on_received(written);
+        attempts = 0;
+        if pending.len() >= chunker.chunk_size() {
+            file.write_all(&pending).await?;
+            pending.clear();
+        }
+    }
+    file.write_all(&pending).await?;
+    file.flush().await?;
+    if let Some(rate) = chunker.bytes_per_sec() {
+        debug!("Download finished at ~{} KiB/s", rate / 1024);
+    }
+    Ok(())
+}
 
-            let output_path = self.output_dir.join(filename);
-            let mut output_file = File::create(&output_path).await?;
-            output_file.write_all(&buffer).await?;
+/// 发送端是否声明支持按字节续传
+fn accepts_ranges(response: &reqwest::Response) -> bool {
+    response
+        .headers()
+        .get(reqwest::header::ACCEPT_RANGES)
+        .is_some_and(|v| v.as_bytes() == b"bytes")
+}
 
-            received += buffer.len() as u64;
-            callback.on_progress(received, total_size);
+/// 请求 `offset` 之后的部分，要求发送端返回 206
+async fn resume_request(
+    client: &reqwest::Client,
+    url: &str,
+    offset: u64,
+) -> anyhow::Result<reqwest::Response> {
+    let response = client
+        .get(url)
+        .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+        .send()
+        .await?
+        .error_for_status()?;
+    if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        anyhow::bail!("Sender ignored range request ({})", response.status());
+    }
+    Ok(response)
+}
 
-            files.push(output_path);
+/// 校验暂存的文件大小
+///
+/// 有逐文件元数据时逐个比对，否则只比对总大小。
+fn verify_staged(
+    files: &[PathBuf],
+    files_dir: &Path,
+    file_infos: &[FileInfo],
+    total_size: u64,
+) -> anyhow::Result<()> {
+    let mut actual_total: u64 = 0;
+    for path in files {
+        let size = std::fs::metadata(path)?.len();
+        actual_total += size;
+
+        let name = staged_name(files_dir, path);
+        let expected = file_infos.iter().find(|info| info.name == name);
+        if let Some(info) = expected.filter(|info| info.size != size) {
+            anyhow::bail!(
+                "Size mismatch for {}: expected {} bytes, got {}",
+                name,
+                info.size,
+                size
+            );
         }
+    }
+
+    if file_infos.is_empty() && actual_total != total_size {
+        anyhow::bail!(
+            "Size mismatch: expected {} bytes in total, got {}",
+            total_size,
+            actual_total
+        );
+    }
+
+    Ok(())
+}
+
+/// 按发送端给出的 SHA-256 校验暂存的文件（没有摘要的文件跳过）
+fn verify_hashes(
+    files: &[PathBuf],
+    files_dir: &Path,
+    file_infos: &[FileInfo],
+) -> anyhow::Result<()> {
+    for path in files {
+        let name = staged_name(files_dir, path);
+        let Some(expected) = file_infos
+            .iter()
+            .find(|info| info.name == name)
+            .and_then(|info| info.sha256.as_deref())
+        else {
+            continue;
+        };
+        let actual = sha256_file(path)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!(
+                "SHA-256 mismatch for {}: expected {}, got {}",
+                name,
+                expected,
+                actual
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 把校验通过的文件原子地移入输出目录（同一文件系统内 rename），不覆盖已有文件
+async fn commit_staged(staged: &[PathBuf], output_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::with_capacity(staged.len());
+    for path in staged {
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let target = unique_path(output_dir, &name.to_string_lossy());
+        tokio::fs::rename(path, &target).await?;
+        files.push(target);
+    }
+    Ok(files)
+}
+
+/// 文件夹同步：把 `files_dir` 下暂存的文件按相对路径移入 `output_dir`，覆盖同名文件
+async fn commit_replacing(
+    staged: &[PathBuf],
+    files_dir: &Path,
+    output_dir: &Path,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::with_capacity(staged.len());
+    for path in staged {
+        let Ok(relative) = path.strip_prefix(files_dir) else {
+            continue;
+        };
+        let target = output_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(path, &target).await?;
+        files.push(target);
+    }
+    Ok(files)
+}
+
+/// 删除 `dir` 下相对路径不在 `keep` 中的文件（含符号链接），再删除因此变空的子目录
+///
+/// 返回删除的文件数。
+fn remove_unlisted(dir: &Path, keep: &HashSet<PathBuf>) -> std::io::Result<usize> {
+    fn walk(
+        root: &Path,
+        dir: &Path,
+        keep: &HashSet<PathBuf>,
+        removed: &mut usize,
+    ) -> std::io::Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.symlink_metadata()?.is_dir() {
+                walk(root, &path, keep, removed)?;
+                // 目录中还有文件时删除失败，保留即可
+                let _ = std::fs::remove_dir(&path);
+            } else if !keep.contains(path.strip_prefix(root).unwrap_or(&path)) {
+                debug!("Removing {:?}", path);
+                std::fs::remove_file(&path)?;
+                *removed += 1;
+            }
+        }
+        Ok(())
+    }
 
+    let mut removed = 0;
+    walk(dir, dir, keep, &mut removed)?;
+    Ok(removed)
+}
+
+/// 把校验通过的文件加密写入输出目录，暂存的明文随暂存目录一起删除
+async fn commit_encrypted(
+    staged: &[PathBuf],
+    output_dir: &Path,
+    key: &AtRestKey,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let staged = staged.to_vec();
+    let output_dir = output_dir.to_path_buf();
+    let key = key.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut files = Vec::with_capacity(staged.len());
+        for path in &staged {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let suffix = format!(".{}", at_rest::ENCRYPTED_EXTENSION);
+            let target = unique_path_with_suffix(&output_dir, &name.to_string_lossy(), &suffix);
+            at_rest::encrypt_file(&key, path, &target)?;
+            files.push(target);
+        }
+        info!("Encrypted {} received file(s) at rest", files.len());
         Ok(files)
+    })
+    .await?
+}
+
+/// 发送端名称用作目录名时的最大字符数
+const MAX_SENDER_DIR_CHARS: usize = 64;
+
+/// 按发送端分类时的保存目录：`<output_dir>/<发送端名称>/<date>`
+///
+/// 名称中的路径分隔符和控制字符替换为 `_`，去掉开头的 `.`，为空时用 `Unknown`。
+fn sender_dir(output_dir: &Path, sender_name: &str, date: &str) -> PathBuf {
+    let name: String = sender_name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .take(MAX_SENDER_DIR_CHARS)
+        .collect();
+    let name = name.trim_start_matches('.').trim();
+    let name = if name.is_empty() { "Unknown" } else { name };
+    output_dir.join(name).join(date)
+}
+
+/// 本地时区的当天日期 `YYYY-MM-DD`
+fn local_date() -> String {
+    // SAFETY: time 为空指针时只返回当前时间；tm 由 localtime_r 填充，不共享静态缓冲区
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return "unknown-date".to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday
+    )
+}
+
+/// 除文件本身外额外预留的空间
+const SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+
+/// 接收目录所在文件系统空间不足
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "insufficient space: {:.1} MiB missing ({} bytes required, {} bytes available)",
+    (.required - .available) as f64 / 1024.0 / 1024.0,
+    .required,
+    .available
+)]
+pub struct InsufficientSpace {
+    pub required: u64,
+    pub available: u64,
+}
+
+/// 检查 `dir` 所在文件系统是否放得下 `total_size` 字节的传输
+///
+/// 下载的 ZIP 在解压完成前会与解压出的文件同时存在，因此需要两倍空间加余量。
+/// 无法查询可用空间时不拒绝传输。
+pub(crate) fn check_free_space(dir: &Path, total_size: u64) -> Result<(), InsufficientSpace> {
+    let required = total_size.saturating_mul(2).saturating_add(SPACE_MARGIN);
+    match available_space(dir) {
+        Ok(available) if available < required => Err(InsufficientSpace {
+            required,
+            available,
+        }),
+        Ok(_) => Ok(()),
+        Err(e) => {
+            warn!("Failed to query free space of {:?}: {}", dir, e);
+            Ok(())
+        }
+    }
+}
+
+/// 通过 statvfs 获取非特权用户可用的字节数
+fn available_space(dir: &Path) -> std::io::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(dir.as_os_str().as_bytes())?;
+    // SAFETY: c_path 是合法的 C 字符串，stat 由 statvfs 填充
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// 解压时每次读写的块大小
+const EXTRACT_CHUNK_SIZE: usize = 256 * 1024;
+
+/// 逐条目、逐块地把 ZIP 解压到 `output_dir`
+///
+/// zip crate 读取时自动识别 ZIP64 扩展，单个条目超过 4 GiB 也不会整块读入内存。
+/// `on_progress` 收到的是累计写入的字节数。
+fn extract_zip_blocking(
+    zip_path: &Path,
+    output_dir: &Path,
+    file_infos: &[FileInfo],
+    restore_exec: bool,
+    on_progress: impl Fn(u64),
+) -> anyhow::Result<Vec<PathBuf>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(zip_path)?);
+    let mut archive = zip::ZipArchive::new(reader)?;
+
+    let mut received: u64 = 0;
+    let mut files = Vec::new();
+    let mut buffer = vec![0u8; EXTRACT_CHUNK_SIZE];
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        let entry_name = entry.name().to_string();
+        let filename = entry_name.split('/').next_back().unwrap_or(&entry_name);
+        let output_path = output_dir.join(filename);
+
+        let mut output = std::fs::File::create(&output_path)?;
+        loop {
+            let n = entry.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            output.write_all(&buffer[..n])?;
+            received += n as u64;
+            on_progress(received);
+        }
+        drop(output);
+
+        if let Some(info) = file_info_for_entry(&entry_name, file_infos) {
+            // 元数据恢复失败不影响文件本身
+            if let Err(e) = apply_metadata(&output_path, info, restore_exec) {
+                warn!("Failed to restore metadata for {:?}: {}", output_path, e);
+            }
+        }
+
+        files.push(output_path);
+    }
+
+    Ok(files)
+}
+
+/// 根据 ZIP 条目名 `<index>/<name>` 找到对应的文件元数据
+///
+/// 索引缺失或越界时按文件名匹配。
+fn file_info_for_entry<'a>(entry_name: &str, infos: &'a [FileInfo]) -> Option<&'a FileInfo> {
+    let (index, name) = entry_name.split_once('/').unwrap_or(("", entry_name));
+    index
+        .parse::<usize>()
+        .ok()
+        .and_then(|i| infos.get(i))
+        .filter(|info| info.name == name)
+        .or_else(|| infos.iter().find(|info| info.name == name))
+}
+
+/// 恢复修改时间，并按需补上可执行位
+fn apply_metadata(path: &Path, info: &FileInfo, restore_exec: bool) -> std::io::Result<()> {
+    if info.modified_time > 0 {
+        let mtime = UNIX_EPOCH + Duration::from_millis(info.modified_time);
+        std::fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(mtime)?;
+    }
+
+    let exec_bits = info.mode.map_or(0, |m| m & 0o111);
+    if restore_exec && exec_bits != 0 {
+        let mut perms = std::fs::metadata(path)?.permissions();
+        perms.set_mode(perms.mode() | exec_bits);
+        std::fs::set_permissions(path, perms)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    fn info(name: &str) -> FileInfo {
+        FileInfo {
+            name: name.to_string(),
+            size: 1,
+            modified_time: 1_700_000_000_000,
+            mime_type: None,
+            mode: Some(0o755),
+            sha256: None,
+        }
+    }
+
+    #[test]
+    fn test_download_plan_path() {
+        let negotiated = Capabilities::none();
+        let plan = DownloadPlan {
+            task_id: "task1",
+            token: Some("abc123"),
+            total_size: 0,
+            file_infos: &[],
+            negotiated: &negotiated,
+            sync: None,
+        };
+        assert_eq!(
+            plan.path("/file", Some(2)),
+            "/file?taskId=task1&index=2&token=abc123"
+        );
+
+        // 对端给的 taskId 不能注入其他参数
+        let plan = DownloadPlan {
+            task_id: "x&token=evil#",
+            token: None,
+            ..plan
+        };
+        assert_eq!(
+            plan.path("/download", None),
+            "/download?taskId=x%26token%3Devil%23"
+        );
+    }
+
+    #[test]
+    fn test_file_info_for_entry() {
+        let infos = vec![info("a.txt"), info("b.sh")];
+
+        assert_eq!(file_info_for_entry("1/b.sh", &infos).unwrap().name, "b.sh");
+        // 索引与名称不符时按名称查找
+        assert_eq!(file_info_for_entry("0/b.sh", &infos).unwrap().name, "b.sh");
+        assert_eq!(file_info_for_entry("a.txt", &infos).unwrap().name, "a.txt");
+        assert!(file_info_for_entry("2/c.txt", &infos).is_none());
+    }
+
+    #[test]
+    fn test_extract_zip_blocking_streams_in_chunks() {
+        let dir = temp_dir("zip");
+        let zip_path = dir.join("in.zip");
+
+        // 比一个块大，确认进度按块上报
+        let data = vec![7u8; EXTRACT_CHUNK_SIZE * 2 + 1];
+        {
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Stored)
+                .large_file(true);
+            zip.start_file("0/big.bin", options).unwrap();
+            zip.write_all(&data).unwrap();
+            zip.finish().unwrap();
+        }
+
+        let progress = std::cell::RefCell::new(Vec::new());
+        let files = extract_zip_blocking(&zip_path, &dir, &[], false, |n| {
+            progress.borrow_mut().push(n);
+        })
+        .unwrap();
+
+        assert_eq!(files, vec![dir.join("big.bin")]);
+        assert_eq!(std::fs::read(&files[0]).unwrap(), data);
+        let progress = progress.into_inner();
+        assert!(progress.len() >= 3);
+        assert_eq!(progress.last().copied(), Some(data.len() as u64));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_verify_staged() {
+        let dir = temp_dir("stage");
+        let path = dir.join("a.txt");
+        std::fs::write(&path, b"x").unwrap();
+        let files = vec![path];
+
+        // info("a.txt") 声明 1 字节
+        assert!(verify_staged(&files, &dir, &[info("a.txt")], 1).is_ok());
+        assert!(verify_staged(&files, &dir, &[], 1).is_ok());
+        // 连接中断导致的截断
+        assert!(verify_staged(&files, &dir, &[], 2).is_err());
+        let mut truncated = info("a.txt");
+        truncated.size = 2;
+        assert!(verify_staged(&files, &dir, &[truncated], 2).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_verify_hashes() {
+        let dir = temp_dir("hash");
+        let path = dir.join("a.txt");
+        std::fs::write(&path, b"abc").unwrap();
+        let files = vec![path];
+
+        let mut expected = info("a.txt");
+        expected.sha256 =
+            Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".to_string());
+        assert!(verify_hashes(&files, &dir, &[expected.clone()]).is_ok());
+        // 没有摘要时不校验
+        assert!(verify_hashes(&files, &dir, &[info("a.txt")]).is_ok());
+        expected.sha256 = Some("00".repeat(32));
+        assert!(verify_hashes(&files, &dir, &[expected]).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_unchanged_files() {
+        let dir = temp_dir("delta");
+        std::fs::write(dir.join("same.txt"), b"abc").unwrap();
+        std::fs::write(dir.join("changed.txt"), b"abd").unwrap();
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let with_hash = |name: &str| FileInfo {
+            size: 3,
+            sha256: Some(abc.to_string()),
+            ..info(name)
+        };
+        let infos = vec![
+            with_hash("same.txt"),
+            with_hash("changed.txt"),
+            with_hash("missing.txt"),
+            // 没有摘要时总是下载
+            FileInfo {
+                size: 3,
+                ..info("same.txt")
+            },
+            with_hash("../same.txt"),
+        ];
+        assert_eq!(unchanged_files(&dir, &infos, false), vec![0]);
+
+        assert_eq!(safe_file_name("a.txt"), Some("a.txt"));
+        assert_eq!(safe_file_name(".."), None);
+        assert_eq!(safe_file_name("x/a.txt"), None);
+        assert_eq!(local_path("x/a.txt", false), None);
+        assert_eq!(local_path("x/a.txt", true), Some(PathBuf::from("x/a.txt")));
+        assert_eq!(local_path("x/../a.txt", true), None);
+        assert_eq!(local_path("/a.txt", true), None);
+        assert_eq!(local_path("x//a.txt", true), None);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_remove_unlisted() {
+        let dir = temp_dir("sync");
+        std::fs::create_dir_all(dir.join("keep")).unwrap();
+        std::fs::create_dir_all(dir.join("gone/deeper")).unwrap();
+        std::fs::write(dir.join("keep/a.txt"), b"a").unwrap();
+        std::fs::write(dir.join("keep/b.txt"), b"b").unwrap();
+        std::fs::write(dir.join("gone/deeper/c.txt"), b"c").unwrap();
+
+        let keep = HashSet::from([PathBuf::from("keep/a.txt")]);
+        assert_eq!(remove_unlisted(&dir, &keep).unwrap(), 2);
+        assert!(dir.join("keep/a.txt").exists());
+        assert!(!dir.join("keep/b.txt").exists());
+        // 变空的目录一并删除
+        assert!(!dir.join("gone").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_check_free_space() {
+        let dir = std::env::temp_dir();
+        assert!(available_space(&dir).unwrap() > 0);
+        assert!(check_free_space(&dir, 0).is_ok());
+
+        let err = check_free_space(&dir, u64::MAX / 4).unwrap_err();
+        assert!(err.required > err.available);
+        assert!(err.to_string().starts_with("insufficient space"));
+    }
+
+    #[test]
+    fn test_apply_metadata() {
+        let dir = temp_dir("meta");
+        let path = dir.join("b.sh");
+        std::fs::write(&path, b"#!/bin/sh").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        apply_metadata(&path, &info("b.sh"), true).unwrap();
+
+        let meta = std::fs::metadata(&path).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o755);
+        let mtime = meta.modified().unwrap().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(mtime.as_millis(), 1_700_000_000_000);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_sender_dir() {
+        let out = Path::new("/tmp/out");
+        assert_eq!(
+            sender_dir(out, "Xiaomi 13", "2024-06-01"),
+            out.join("Xiaomi 13").join("2024-06-01")
+        );
+        assert_eq!(
+            sender_dir(out, "../a/b\\c\u{1}d", "2024-06-01"),
+            out.join("_a_b_c_d").join("2024-06-01")
+        );
+        assert_eq!(
+            sender_dir(out, " .. ", "2024-06-01"),
+            out.join("Unknown").join("2024-06-01")
+        );
+        let long = "x".repeat(200);
+        let dir = sender_dir(out, &long, "d");
+        let name = dir.parent().unwrap().file_name().unwrap();
+        assert_eq!(name.len(), MAX_SENDER_DIR_CHARS);
+
+        let date = local_date();
+        assert_eq!(date.len(), 10);
+        assert_eq!(date.as_bytes()[4], b'-');
+    }
+
+    #[tokio::test]
+    async fn test_commit_staged_keeps_existing_files() {
+        let dir = temp_dir("commit");
+        let staging = dir.join("staging");
+        std::fs::create_dir_all(&staging).unwrap();
+        std::fs::write(dir.join("a.txt"), b"old").unwrap();
+        std::fs::write(staging.join("a.txt"), b"new").unwrap();
+
+        let files = commit_staged(&[staging.join("a.txt")], &dir).await.unwrap();
+
+        assert_eq!(files, vec![dir.join("a (1).txt")]);
+        assert_eq!(std::fs::read(dir.join("a.txt")).unwrap(), b"old");
+        assert_eq!(std::fs::read(dir.join("a (1).txt")).unwrap(), b"new");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_reachable_falls_back() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let dir = std::env::temp_dir();
+
+        // 监听在 127.0.0.1 上，127.0.0.2 的同一端口会拒绝连接
+        let client = ReceiverClient::new("127.0.0.2", port, dir.clone())
+            .with_tls(false)
+            .with_fallback_hosts(vec!["127.0.0.2".to_string(), "127.0.0.1".to_string()])
+            .ensure_reachable()
+            .await
+            .unwrap();
+        assert_eq!(client.host, "127.0.0.1");
+
+        drop(listener);
+        let err = ReceiverClient::new("127.0.0.1", port, dir)
+            .with_tls(false)
+            .ensure_reachable()
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.port, port);
+        assert_eq!(
+            err.attempts,
+            vec![("127.0.0.1".to_string(), ProbeFailure::Refused)]
+        );
+        assert!(err.to_string().contains("127.0.0.1 (connection refused)"));
     }
 }
@@ -5,7 +5,25 @@
 //! # 功能
 //!
 //! - HTTPS WebSocket 用于协商和状态同步
-//! - HTTPS GET /download 用于 ZIP 文件下载
+//! - HTTPS GET /download 用于 ZIP 文件下载（支持 `Range: bytes=N-` 续传）
+//! - GET /info 返回支持的协议版本（[`TransferServer::with_max_version`] 启用
+//!   [`PROTOCOL_V2`] 后，接收端取过 `/info` 即改走信息优先流程：由接收端发起版本协商，
+//!   接收端同意后推送下载令牌，`/download` 必须带上该令牌）
+//! - 任务设置了 [`TransferTask::auth_token`] 时，`/websocket` 和 `/download` 都要求
+//!   `Authorization: Bearer <令牌>`，令牌随加密的 P2P 信息发给接收端，
+//!   其他接入热点的设备猜中任务 ID 也无法下载
+//! - 每个 IP 同时进行的请求数和全局同时进行的下载数有上限（[`TransferServer::with_limits`]），
+//!   超出时返回 429
+//! - 版本协商时交换扩展能力（[`Capabilities`]），接收端也是 cattysend 时：
+//!   sendRequest 带上逐文件的 SHA-256，文本类文件在 ZIP 中用 Deflate 压缩；
+//!   协商了 `raw_files` 时接收端改用 `GET /file?taskId=..&index=N` 逐个下载原始文件；
+//!   任务带有 [`TransferTask::sync`] 时 sendRequest 带上文件夹同步请求，
+//!   接收端不支持 `raw_files` 则直接结束传输
+//! - 任务带有 [`TransferTask::tls`] 时改用 HTTPS，证书指纹随 P2P 信息发给 cattysend 接收端
+//! - [`TransferServer::router`] 不监听端口，把路由交给宿主程序挂到自己的 axum 应用中
+//! - 一个服务器可以同时提供多个任务（[`TransferServer::add_task`]），按任务 ID 区分：
+//!   `/download` 和 `/file` 本来就带 `taskId`，`/websocket` 和 `/info` 按查询参数 `taskId`、
+//!   访问令牌或加入顺序选择任务
 //!
 //! # 协议
 //!
@@ -13,29 +31,70 @@
 
 use log::{debug, error, info, warn};
 
-use crate::transfer::protocol::WsMessage;
+use crate::config::PortRange;
+use crate::crypto::TlsIdentity;
+use crate::transfer::adaptive::AdaptiveChunker;
+use crate::transfer::control::{STATUS_PAUSED, STATUS_RESUMED};
+use crate::transfer::limit::{ConnectionGuard, ConnectionLimiter, ConnectionLimits, DownloadGuard};
+use crate::transfer::port::bind_listener;
+use crate::transfer::protocol::{
+    COMPRESSION_DEFLATE, Capabilities, DownloadToken, HASH_SHA256, PROTOCOL_V1, PROTOCOL_V2,
+    SUPPORTED_VERSIONS, SenderInfo, SyncRequest, WsMessage,
+};
+use crate::transfer::{FileInfo, sha256_file};
 use axum::{
     Router,
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    body::{Body, Bytes},
+    extract::ws::{Message as WsFrame, WebSocket, WebSocketUpgrade},
+    extract::{ConnectInfo, Extension, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Json},
     routing::get,
 };
 use futures_util::{SinkExt, StreamExt};
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::SeekFrom;
 use std::io::Write;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::net::SocketAddr;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio::net::TcpListener;
 use tokio::sync::{Mutex, broadcast};
+use tokio_native_tls::TlsAcceptor;
 use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 
 #[derive(Deserialize)]
 pub struct DownloadQuery {
     #[serde(rename = "taskId")]
     pub task_id: String,
+    /// 信息优先流程中推送给接收端的下载令牌
+    pub token: Option<String>,
+}
+
+/// `/websocket` 和 `/info` 的查询参数（可选，CatShare 接收端不带）
+#[derive(Deserialize)]
+pub struct TaskQuery {
+    #[serde(rename = "taskId")]
+    pub task_id: Option<String>,
+}
+
+/// `/file` 的查询参数
+#[derive(Deserialize)]
+pub struct FileQuery {
+    #[serde(rename = "taskId")]
+    pub task_id: String,
+    /// 文件在 sendRequest 中的下标
+    pub index: usize,
+    pub token: Option<String>,
 }
 
 /// 传输任务
@@ -45,6 +104,20 @@ pub struct TransferTask {
     pub files: Vec<FileEntry>,
     pub sender_id: String,
     pub sender_name: String,
+    /// 握手后得到的验证码，随 sendRequest 发给接收端核对
+    ///
+    /// 服务要先于握手启动（端口写在 P2P 信息里），所以在提供任务之后才填入。
+    pub verification_code: Arc<OnceLock<String>>,
+    /// 访问令牌，设置后拒绝不带 `Authorization: Bearer <令牌>` 的请求
+    ///
+    /// 只能对 cattysend 接收端设置（写在 P2pInfo 的 `authToken` 中），CatShare 不会带上它。
+    pub auth_token: Option<String>,
+    /// 设置后用这张证书提供 HTTPS（指纹写在 P2pInfo 的 `certSha256` 中，供接收端固定）
+    ///
+    /// 同样只对 cattysend 接收端设置；CatShare 接收端连接的仍是原来的服务。
+    pub tls: Option<Arc<TlsIdentity>>,
+    /// 文件夹同步（文件名为相对路径），要求接收端支持 `raw_files`，否则直接结束传输
+    pub sync: Option<SyncRequest>,
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +126,56 @@ pub struct FileEntry {
     pub name: String,
     pub size: u64,
     pub mime_type: String,
+    /// 修改时间（Unix 毫秒，0 表示未知）
+    pub modified_time: u64,
+    /// Unix 权限位
+    pub mode: Option<u32>,
+    /// 已知的 SHA-256（文件夹同步时来自同步记录），协商了摘要扩展时不再重新计算
+    pub sha256: Option<String>,
+}
+
+impl FileEntry {
+    /// 读取本地文件的大小、修改时间、权限并猜测 MIME 类型
+    pub async fn from_path(path: &Path) -> std::io::Result<Self> {
+        let metadata = tokio::fs::metadata(path).await?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let modified_time = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_millis() as u64);
+
+        // 猜测 MIME 类型
+        let mime_type = mime_guess::from_path(path)
+            .first()
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            name,
+            size: metadata.len(),
+            mime_type,
+            modified_time,
+            mode: Some(metadata.permissions().mode()),
+            sha256: None,
+        })
+    }
+
+    /// 随 sendRequest 发送给接收端的文件元数据
+    pub fn info(&self) -> FileInfo {
+        FileInfo {
+            name: self.name.clone(),
+            size: self.size,
+            modified_time: self.modified_time,
+            mime_type: Some(self.mime_type.clone()),
+            mode: self.mode,
+            sha256: None,
+        }
+    }
 }
 
 /// 传输状态
@@ -61,65 +184,377 @@ pub enum TransferStatus {
     Pending,
     Accepted,
     Rejected(String),
-    Transferring { progress: f64 },
+    Transferring {
+        progress: f64,
+    },
+    /// 接收端暂停了下载
+    Paused,
+    /// 接收端恢复了下载
+    Resumed,
     Completed,
     Failed(String),
 }
 
-/// 服务器状态
+/// 服务器状态（所有任务共用协议版本、连接数限制和扩展能力）
 pub struct TransferServerState {
+    /// 正在提供的任务，按任务 ID 索引
+    pub tasks: HashMap<String, TaskState>,
+    /// 支持的最高协议版本
+    pub max_version: u32,
+    /// 每个 IP 的连接数和全局下载数
+    pub limiter: Arc<ConnectionLimiter>,
+    /// 本端提供的扩展能力
+    pub capabilities: Capabilities,
+    /// 启动后是否使用 HTTPS（之后加入的任务必须一致），未启动时为 None
+    https: Option<bool>,
+    /// 下一个加入的任务的序号
+    next_seq: u64,
+}
+
+/// 一个任务的传输状态
+pub struct TaskState {
     pub task: TransferTask,
     pub status_tx: broadcast::Sender<TransferStatus>,
+    /// 第一次下载时生成在磁盘上的 ZIP，续传时复用，保证各次请求的字节一致
+    pub zip: Option<ZipFile>,
+    /// 接收端已通过 `/info` 选择信息优先流程
+    pub info_first: bool,
+    /// 已推送的下载令牌，设置后 `/download` 必须带上
+    pub download_token: Option<String>,
+    /// 与接收端协商出的扩展能力（接收端是 CatShare 时为 [`Capabilities::none`]）
+    pub negotiated: Capabilities,
+    /// 已有 WebSocket 会话（没有任务 ID 和令牌的连接优先分给还没有会话的任务）
+    pub claimed: bool,
+    /// 加入的顺序
+    seq: u64,
+}
+
+impl TransferServerState {
+    fn empty() -> Self {
+        Self {
+            tasks: HashMap::new(),
+            max_version: PROTOCOL_V1,
+            limiter: ConnectionLimiter::new(ConnectionLimits::default()),
+            capabilities: Capabilities::default(),
+            https: None,
+            next_seq: 0,
+        }
+    }
+
+    /// 加入任务，返回它的状态订阅
+    fn insert(&mut self, task: TransferTask) -> broadcast::Receiver<TransferStatus> {
+        let (status_tx, status_rx) = broadcast::channel(16);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.tasks.insert(
+            task.task_id.clone(),
+            TaskState {
+                task,
+                status_tx,
+                zip: None,
+                info_first: false,
+                download_token: None,
+                negotiated: Capabilities::none(),
+                claimed: false,
+                seq,
+            },
+        );
+        status_rx
+    }
+
+    /// 为没有指明下载哪个任务的请求（`/websocket`、`/info`）选择任务
+    ///
+    /// 依次为查询参数中的任务 ID、访问令牌匹配的任务；都没有时（CatShare 接收端两者都不带）
+    /// 取最早加入的无令牌任务，还没有 WebSocket 会话的优先。
+    fn pick_task(
+        &self,
+        task_id: Option<&str>,
+        authorization: Option<&HeaderValue>,
+    ) -> Result<&TaskState, (StatusCode, &'static str)> {
+        if let Some(task_id) = task_id {
+            return self.authorized_task(task_id, authorization);
+        }
+        let token = bearer_token(authorization);
+        if let Some(task) = self
+            .tasks
+            .values()
+            .find(|t| t.task.auth_token.is_some() && t.task.auth_token.as_deref() == token)
+        {
+            return Ok(task);
+        }
+        if let Some(task) = self
+            .tasks
+            .values()
+            .filter(|t| t.task.auth_token.is_none())
+            .min_by_key(|t| (t.claimed, t.seq))
+        {
+            return Ok(task);
+        }
+        if self.tasks.is_empty() {
+            Err((StatusCode::NOT_FOUND, "Task not found"))
+        } else {
+            Err((StatusCode::UNAUTHORIZED, "Invalid auth token"))
+        }
+    }
+
+    /// 为新的 WebSocket 会话选择任务（规则同 [`Self::pick_task`]）并标记为已有会话，返回任务 ID
+    ///
+    /// 选择和标记在同一次加锁中完成，同时到来的两个连接不会选中同一个未占用的任务。
+    fn claim_task(
+        &mut self,
+        task_id: Option<&str>,
+        authorization: Option<&HeaderValue>,
+    ) -> Result<String, (StatusCode, &'static str)> {
+        let task_id = self.pick_task(task_id, authorization)?.task.task_id.clone();
+        if let Some(task) = self.tasks.get_mut(&task_id) {
+            task.claimed = true;
+        }
+        debug!("WebSocket session for task {}", task_id);
+        Ok(task_id)
+    }
+
+    /// 按任务 ID 查找任务并检查访问令牌
+    ///
+    /// 先检查访问令牌：请求对任何任务都没有授权时返回 401，无从判断任务 ID 是否存在。
+    fn authorized_task(
+        &self,
+        task_id: &str,
+        authorization: Option<&HeaderValue>,
+    ) -> Result<&TaskState, (StatusCode, &'static str)> {
+        let authorized = |t: &TaskState| is_authorized(t.task.auth_token.as_deref(), authorization);
+        match self.tasks.get(task_id) {
+            Some(task) if authorized(task) => Ok(task),
+            Some(_) => Err((StatusCode::UNAUTHORIZED, "Invalid auth token")),
+            None if self.tasks.values().any(authorized) || self.tasks.is_empty() => {
+                Err((StatusCode::NOT_FOUND, "Task not found"))
+            }
+            None => Err((StatusCode::UNAUTHORIZED, "Invalid auth token")),
+        }
+    }
 }
 
 /// 传输服务器
+///
+/// 一个服务器可以同时或先后提供多个任务（[`Self::add_task`] / [`Self::remove_task`]），
+/// 共用同一个端口，不必为每个任务重新监听。
 pub struct TransferServer {
     port: u16,
+    /// 监听端口范围（`None` 为随机端口）
+    ports: Option<PortRange>,
+    /// 预先绑定的监听端口，设置后不再按 `ports` 选择
+    listener: Option<TcpListener>,
+    max_version: u32,
+    limits: ConnectionLimits,
+    capabilities: Capabilities,
+    /// HTTPS 证书（默认取初始任务的 [`TransferTask::tls`]）
+    tls: Option<Arc<TlsIdentity>>,
+    /// [`Self::new`] 时传入的任务，[`Self::subscribe_status`] 订阅它的状态
+    task_id: Option<String>,
     state: Arc<Mutex<TransferServerState>>,
 }
 
 impl TransferServer {
     pub fn new(task: TransferTask) -> Self {
-        let (status_tx, _) = broadcast::channel(16);
+        let task_id = task.task_id.clone();
+        let tls = task.tls.clone();
+        let mut state = TransferServerState::empty();
+        state.insert(task);
+        Self {
+            task_id: Some(task_id),
+            tls,
+            ..Self::with_state(state)
+        }
+    }
+
+    /// 没有任务的服务器，启动后用 [`Self::add_task`] 加入任务
+    pub fn empty() -> Self {
+        Self::with_state(TransferServerState::empty())
+    }
 
+    fn with_state(state: TransferServerState) -> Self {
         Self {
             port: 0, // 使用随机端口
-            state: Arc::new(Mutex::new(TransferServerState { task, status_tx })),
+            ports: None,
+            listener: None,
+            max_version: PROTOCOL_V1,
+            limits: ConnectionLimits::default(),
+            capabilities: Capabilities::default(),
+            tls: None,
+            task_id: None,
+            state: Arc::new(Mutex::new(state)),
         }
     }
 
+    /// 在指定范围内选择监听端口（端口被占用时依次尝试下一个）
+    pub fn with_ports(mut self, ports: Option<PortRange>) -> Self {
+        self.ports = ports;
+        self
+    }
+
+    /// 使用已经绑定的监听端口（例如热备时提前绑定的），代替 [`Self::with_ports`]
+    pub fn with_listener(mut self, listener: TcpListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// 支持的最高协议版本（默认 [`PROTOCOL_V1`]，只走发送端发起协商的流程）
+    pub fn with_max_version(mut self, version: u32) -> Self {
+        self.max_version = version;
+        self
+    }
+
+    /// 每个 IP 的请求数和全局下载数上限（默认 [`ConnectionLimits::default`]）
+    pub fn with_limits(mut self, limits: ConnectionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// 向接收端提供的扩展能力（默认 [`Capabilities::default`]，[`Capabilities::none`] 关闭扩展）
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// 使用 `identity` 提供 HTTPS（默认取初始任务的 [`TransferTask::tls`]，没有时为 HTTP）
+    pub fn with_tls(mut self, identity: Option<Arc<TlsIdentity>>) -> Self {
+        self.tls = identity;
+        self
+    }
+
     /// 获取分配的端口
     pub fn port(&self) -> u16 {
         self.port
     }
 
-    /// 订阅传输状态更新
+    /// 订阅初始任务的传输状态更新（没有初始任务时立即关闭）
     pub fn subscribe_status(&self) -> broadcast::Receiver<TransferStatus> {
         let state = self.state.blocking_lock();
-        state.status_tx.subscribe()
+        initial_status(&state, self.task_id.as_deref())
     }
 
-    /// 异步订阅传输状态更新
+    /// 异步订阅初始任务的传输状态更新
     pub async fn subscribe_status_async(&self) -> broadcast::Receiver<TransferStatus> {
         let state = self.state.lock().await;
-        state.status_tx.subscribe()
+        initial_status(&state, self.task_id.as_deref())
     }
 
-    /// 启动服务器（HTTP 版本，用于测试）
-    pub async fn start(&mut self) -> anyhow::Result<u16> {
-        let state = self.state.clone();
+    /// 订阅任务的传输状态更新（任务不存在时为 None）
+    pub async fn subscribe_task(
+        &self,
+        task_id: &str,
+    ) -> Option<broadcast::Receiver<TransferStatus>> {
+        let state = self.state.lock().await;
+        state.tasks.get(task_id).map(|t| t.status_tx.subscribe())
+    }
 
-        let app = Router::new()
+    /// 加入任务（服务器启动前后都可以），返回它的状态订阅
+    ///
+    /// 任务 ID 已存在，或服务器已启动而任务的 HTTPS 设置与服务器不同时返回错误。
+    pub async fn add_task(
+        &self,
+        task: TransferTask,
+    ) -> anyhow::Result<broadcast::Receiver<TransferStatus>> {
+        let mut state = self.state.lock().await;
+        if state.tasks.contains_key(&task.task_id) {
+            anyhow::bail!("Task {} is already being served", task.task_id);
+        }
+        if let Some(https) = state.https
+            && https != task.tls.is_some()
+        {
+            anyhow::bail!(
+                "Task {} does not match the server scheme ({})",
+                task.task_id,
+                if https { "HTTPS" } else { "HTTP" }
+            );
+        }
+        info!("Serving task {}", task.task_id);
+        Ok(state.insert(task))
+    }
+
+    /// 移除任务，之后对它的请求返回 404（已经开始的下载不受影响）
+    pub async fn remove_task(&self, task_id: &str) -> bool {
+        let removed = self.state.lock().await.tasks.remove(task_id).is_some();
+        if removed {
+            debug!("Task {} removed", task_id);
+        }
+        removed
+    }
+
+    /// 正在提供的任务 ID
+    pub async fn task_ids(&self) -> Vec<String> {
+        self.state.lock().await.tasks.keys().cloned().collect()
+    }
+
+    /// 把构建时的设置写入共享状态
+    async fn apply_settings(&self) {
+        let mut state = self.state.lock().await;
+        state.max_version = self.max_version;
+        state.limiter = ConnectionLimiter::new(self.limits);
+        state.capabilities = self.capabilities.clone();
+    }
+
+    /// `/websocket`、`/download`、`/file`、`/info` 路由，带连接数限制
+    fn routes(&self) -> Router {
+        let state = self.state.clone();
+        Router::new()
+            .route("/websocket", get(websocket_handler))
             .route("/download", get(download_handler))
-            .with_state(state);
+            .route("/file", get(file_handler))
+            .route("/info", get(info_handler))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_connections,
+            ))
+            .with_state(state)
+    }
+
+    /// 不监听端口，返回路由供宿主程序挂到自己的 axum 应用中（共用端口、TLS 和中间件）
+    ///
+    /// 服务器本身仍可继续 [`Self::add_task`] / [`Self::remove_task`]。宿主程序负责 TLS
+    /// （证书指纹要与 P2P 信息中的一致），并且要用
+    /// `into_make_service_with_connect_info::<SocketAddr>()` 提供服务：连接数限制按对端 IP 计算。
+    /// 接收端总是访问根路径下的 `/websocket` 等，挂在前缀下（`Router::nest`）时要由宿主转发。
+    pub async fn router(&self) -> Router {
+        self.apply_settings().await;
+        self.routes()
+    }
+
+    /// 同 [`Self::router`]，之后不再需要管理任务时使用
+    pub async fn into_router(self) -> Router {
+        self.router().await
+    }
 
-        let listener = TcpListener::bind("0.0.0.0:0").await?;
+    /// 启动服务器（`/websocket`、`/download`、`/file` 共用一个端口）
+    ///
+    /// 设置了证书（[`Self::with_tls`] 或初始任务的 [`TransferTask::tls`]）时使用 HTTPS，否则为 HTTP。
+    pub async fn start(&mut self) -> anyhow::Result<u16> {
+        self.apply_settings().await;
+        let acceptor = match &self.tls {
+            Some(identity) => Some(identity.acceptor()?),
+            None => None,
+        };
+        self.state.lock().await.https = Some(acceptor.is_some());
+        let app = self.routes();
+
+        let listener = match self.listener.take() {
+            Some(listener) => listener,
+            None => bind_listener(self.ports).await?,
+        };
         let port = listener.local_addr()?.port();
         self.port = port;
 
-        info!("Transfer server listening on port {}", port);
+        info!(
+            "Transfer server listening on port {} ({})",
+            port,
+            if acceptor.is_some() { "HTTPS" } else { "HTTP" }
+        );
 
         tokio::spawn(async move {
+            if let Some(acceptor) = acceptor {
+                serve_tls(listener, app, acceptor).await;
+                return;
+            }
+            let app = app.into_make_service_with_connect_info::<SocketAddr>();
             if let Err(e) = axum::serve(listener, app).await {
                 error!("Server error: {}", e);
             }
@@ -130,20 +565,31 @@ impl TransferServer {
 
     /// 启动 WebSocket + HTTP 服务器
     pub async fn start_with_websocket(&mut self) -> anyhow::Result<u16> {
+        self.apply_settings().await;
+        self.state.lock().await.https = Some(false);
         let state = self.state.clone();
         let state_for_ws = self.state.clone();
 
         // HTTP 服务器
         let app = Router::new()
             .route("/download", get(download_handler))
+            .route("/info", get(info_handler))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_connections,
+            ))
             .with_state(state);
 
-        let http_listener = TcpListener::bind("0.0.0.0:0").await?;
+        let http_listener = match self.listener.take() {
+            Some(listener) => listener,
+            None => bind_listener(self.ports).await?,
+        };
         let port = http_listener.local_addr()?.port();
         self.port = port;
 
         // 启动 HTTP 服务器
         tokio::spawn(async move {
+            let app = app.into_make_service_with_connect_info::<SocketAddr>();
             if let Err(e) = axum::serve(http_listener, app).await {
                 error!("HTTP Server error: {}", e);
             }
@@ -155,9 +601,18 @@ impl TransferServer {
         let ws_port = ws_listener.local_addr()?.port();
 
         tokio::spawn(async move {
-            while let Ok((stream, _)) = ws_listener.accept().await {
+            while let Ok((stream, peer)) = ws_listener.accept().await {
                 let state = state_for_ws.clone();
+                let limiter = state.lock().await.limiter.clone();
+                let Some(guard) = limiter.try_connect(peer.ip()) else {
+                    warn!(
+                        "Too many connections from {}, dropping WebSocket",
+                        peer.ip()
+                    );
+                    continue;
+                };
                 tokio::spawn(async move {
+                    let _guard = guard;
                     if let Err(e) = handle_websocket_connection(stream, state).await {
                         error!("WebSocket error: {}", e);
                     }
@@ -174,19 +629,96 @@ impl TransferServer {
     }
 }
 
-/// 处理 WebSocket 连接
+/// 初始任务的状态订阅；没有初始任务或它已被移除时返回一个已关闭的订阅
+fn initial_status(
+    state: &TransferServerState,
+    task_id: Option<&str>,
+) -> broadcast::Receiver<TransferStatus> {
+    match task_id.and_then(|id| state.tasks.get(id)) {
+        Some(task) => task.status_tx.subscribe(),
+        None => broadcast::channel(1).1,
+    }
+}
+
+/// 在 TLS 之上提供 `app`（axum 0.7 的 `serve` 只接受明文 TCP）
+///
+/// 每个连接先完成 TLS 握手，再交给 hyper；`ConnectInfo` 由每个连接的 Extension 层提供，
+/// 连接数限制与明文服务一致。
+async fn serve_tls(listener: TcpListener, app: Router, acceptor: TlsAcceptor) {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto;
+    use hyper_util::service::TowerToHyperService;
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                error!("Accept error: {}", e);
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(app.clone().layer(Extension(ConnectInfo(peer))));
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    debug!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// 处理 WebSocket 连接（独立端口，见 [`TransferServer::start_with_websocket`]）
 async fn handle_websocket_connection(
     stream: tokio::net::TcpStream,
     state: Arc<Mutex<TransferServerState>>,
 ) -> anyhow::Result<()> {
-    let ws_stream = tokio_tungstenite::accept_async(stream).await?;
+    // 握手回调是同步的，只记下选择任务所需的请求信息，握手后加锁选择
+    let mut selector = None;
+    let check = |request: &Request, response: Response| {
+        selector = Some((
+            query_task_id(request.uri()),
+            request.headers().get(header::AUTHORIZATION).cloned(),
+        ));
+        Ok(response)
+    };
+    let ws_stream = tokio_tungstenite::accept_hdr_async(stream, check).await?;
+    let Some((task_id, authorization)) = selector else {
+        return Ok(());
+    };
     let (mut write, mut read) = ws_stream.split();
+    let claimed = state
+        .lock()
+        .await
+        .claim_task(task_id.as_deref(), authorization.as_ref());
+    let task_id = match claimed {
+        Ok(task_id) => task_id,
+        Err((_, reason)) => {
+            warn!("WebSocket session rejected: {}", reason);
+            let close = CloseFrame {
+                code: CloseCode::Policy,
+                reason: reason.into(),
+            };
+            let _ = write.send(Message::Close(Some(close))).await;
+            return Ok(());
+        }
+    };
+    let mut session = WsSession::new(state, task_id);
 
-    let mut msg_id: u32 = 0;
-
-    // 发送版本协商
-    let ver_msg = WsMessage::version_negotiation(msg_id);
-    write.send(Message::Text(ver_msg.to_string())).await?;
+    // 发送版本协商（信息优先流程由接收端发起）
+    if let Some(greeting) = session.greeting().await {
+        write.send(Message::Text(greeting)).await?;
+    }
 
     // 处理消息
     while let Some(msg) = read.next().await {
@@ -200,11 +732,303 @@ async fn handle_websocket_connection(
             _ => continue,
         };
 
-        let ws_msg = match WsMessage::parse(&msg) {
+        let step = session.handle(&msg).await;
+        for reply in step.replies {
+            write.send(Message::Text(reply)).await?;
+        }
+        if step.finished {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// `/websocket` 路由（与 `/download` 共用端口）
+///
+/// 任务按 [`TransferServerState::claim_task`] 选择
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    Query(query): Query<TaskQuery>,
+    State(state): State<Arc<Mutex<TransferServerState>>>,
+    Extension(connection): Extension<ConnectionPermit>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let claimed = state
+        .lock()
+        .await
+        .claim_task(query.task_id.as_deref(), headers.get(header::AUTHORIZATION));
+    let task_id = match claimed {
+        Ok(task_id) => task_id,
+        Err((status, reason)) => {
+            warn!("WebSocket upgrade rejected: {}", reason);
+            return (status, reason).into_response();
+        }
+    };
+    ws.on_upgrade(|socket| async move {
+        // WebSocket 持续期间一直占用连接名额
+        let _connection = connection;
+        if let Err(e) = handle_axum_websocket(socket, state, task_id).await {
+            error!("WebSocket error: {}", e);
+        }
+    })
+    .into_response()
+}
+
+/// 请求占用的连接名额，响应体发送完毕后释放（WebSocket 在连接关闭后释放）
+#[derive(Clone)]
+struct ConnectionPermit {
+    _guard: Arc<ConnectionGuard>,
+}
+
+/// 限制每个 IP 同时进行的请求数，超出时返回 429
+async fn limit_connections(
+    State(state): State<Arc<Mutex<TransferServerState>>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: axum::extract::Request,
+    next: Next,
+) -> axum::response::Response {
+    let limiter = state.lock().await.limiter.clone();
+    let Some(guard) = limiter.try_connect(peer.ip()) else {
+        warn!(
+            "Too many connections from {} (limit {}), rejecting {}",
+            peer.ip(),
+            limiter.limits().per_ip,
+            request.uri().path()
+        );
+        return (StatusCode::TOO_MANY_REQUESTS, "Too many connections").into_response();
+    };
+    let permit = ConnectionPermit {
+        _guard: Arc::new(guard),
+    };
+    request.extensions_mut().insert(permit.clone());
+    let response = next.run(request).await;
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        // 升级后的连接由 WebSocket 处理函数持有名额
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        let _ = &permit;
+        chunk
+    });
+    axum::response::Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// 请求是否带有任务要求的访问令牌（任务没有令牌时总是通过）
+fn is_authorized(expected: Option<&str>, authorization: Option<&HeaderValue>) -> bool {
+    let Some(expected) = expected else {
+        return true;
+    };
+    bearer_token(authorization).is_some_and(|token| token == expected)
+}
+
+/// `Authorization: Bearer <令牌>` 中的令牌
+fn bearer_token(authorization: Option<&HeaderValue>) -> Option<&str> {
+    authorization
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// 查询字符串中的 `taskId`（独立端口的 WebSocket 没有经过 axum 的解析）
+fn query_task_id(uri: &axum::http::Uri) -> Option<String> {
+    Query::<TaskQuery>::try_from_uri(uri)
+        .ok()
+        .and_then(|Query(q)| q.task_id)
+}
+
+async fn handle_axum_websocket(
+    socket: WebSocket,
+    state: Arc<Mutex<TransferServerState>>,
+    task_id: String,
+) -> anyhow::Result<()> {
+    let (mut write, mut read) = socket.split();
+    let mut session = WsSession::new(state, task_id);
+
+    if let Some(greeting) = session.greeting().await {
+        write.send(WsFrame::Text(greeting)).await?;
+    }
+
+    while let Some(msg) = read.next().await {
+        let msg = match msg {
+            Ok(WsFrame::Text(text)) => text,
+            Ok(WsFrame::Close(_)) => break,
+            Err(e) => {
+                error!("WebSocket read error: {}", e);
+                break;
+            }
+            _ => continue,
+        };
+
+        let step = session.handle(&msg).await;
+        for reply in step.replies {
+            write.send(WsFrame::Text(reply)).await?;
+        }
+        if step.finished {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// 一次 WebSocket 协商的协议状态（与具体 WebSocket 实现无关）
+struct WsSession {
+    state: Arc<Mutex<TransferServerState>>,
+    /// 本次会话传输的任务
+    task_id: String,
+    msg_id: u32,
+}
+
+/// 处理一条消息的结果
+struct WsStep {
+    /// 需要回复的消息
+    replies: Vec<String>,
+    /// 会话是否结束
+    finished: bool,
+}
+
+impl WsSession {
+    /// 为已选中的任务（[`TransferServerState::claim_task`]）开始一次会话
+    ///
+    /// 会话只保存任务 ID，每次用到任务时在共享状态中查找
+    fn new(state: Arc<Mutex<TransferServerState>>, task_id: String) -> Self {
+        Self {
+            state,
+            task_id,
+            msg_id: 0,
+        }
+    }
+
+    /// 任务及协商出的能力的副本（任务已被移除时为 None）
+    async fn task(&self) -> Option<(TransferTask, Capabilities)> {
+        let s = self.state.lock().await;
+        let task = s.tasks.get(&self.task_id)?;
+        Some((task.task.clone(), task.negotiated.clone()))
+    }
+
+    /// 广播本任务的状态
+    async fn report(&self, status: TransferStatus) {
+        if let Some(task) = self.state.lock().await.tasks.get(&self.task_id) {
+            let _ = task.status_tx.send(status);
+        }
+    }
+
+    /// 接收端是否通过 `/info` 选择了信息优先流程
+    async fn info_first(&self) -> bool {
+        let s = self.state.lock().await;
+        s.tasks.get(&self.task_id).is_some_and(|t| t.info_first)
+    }
+
+    /// 连接建立后发送的版本协商；信息优先流程中等接收端发起，返回 `None`
+    async fn greeting(&self) -> Option<String> {
+        let s = self.state.lock().await;
+        if s.tasks.get(&self.task_id).map_or(true, |t| t.info_first) {
+            return None;
+        }
+        let greeting =
+            WsMessage::version_negotiation(self.msg_id).with_capabilities(&s.capabilities);
+        Some(greeting.to_string())
+    }
+
+    /// 按接收端在版本协商中给出的能力确定启用的扩展
+    async fn negotiate(&self, msg: &WsMessage) {
+        let mut s = self.state.lock().await;
+        let negotiated = s.capabilities.negotiate(msg.capabilities().as_ref());
+        if negotiated != Capabilities::none() {
+            debug!("Negotiated capabilities: {:?}", negotiated);
+        }
+        if let Some(task) = s.tasks.get_mut(&self.task_id) {
+            task.negotiated = negotiated;
+        }
+    }
+
+    /// 版本协商完成后发送的传输请求
+    async fn send_request(&mut self, task: TransferTask, negotiated: Capabilities) -> String {
+        self.msg_id += 1;
+        let mut files: Vec<FileInfo> = task.files.iter().map(FileEntry::info).collect();
+        if negotiated.hashes_with(HASH_SHA256) {
+            hash_files(&task.files, &mut files).await;
+        }
+
+        let total_size: u64 = task.files.iter().map(|f| f.size).sum();
+        // 文件夹同步时文件名是相对路径，显示目录名
+        let file_name = match &task.sync {
+            Some(sync) => sync.folder.clone(),
+            None => task
+                .files
+                .first()
+                .map(|f| f.name.clone())
+                .unwrap_or_default(),
+        };
+
+        let mut payload = serde_json::json!({
+            "taskId": task.task_id,
+            "id": task.task_id,
+            "senderId": task.sender_id,
+            "senderName": task.sender_name,
+            "fileName": file_name,
+            "mimeType": task.files.first().map(|f| &f.mime_type).unwrap_or(&"application/octet-stream".to_string()),
+            "fileCount": task.files.len(),
+            "totalSize": total_size,
+            "files": files
+        });
+        if let Some(code) = task.verification_code.get() {
+            payload["verificationCode"] = code.as_str().into();
+        }
+        if let Some(sync) = &task.sync {
+            payload["sync"] = serde_json::json!(sync);
+        }
+        WsMessage::action(self.msg_id, "sendRequest", Some(payload)).to_string()
+    }
+
+    /// 版本协商后发送传输请求；文件夹同步而接收端不支持原始文件下载时改为结束传输
+    async fn request_transfer(&mut self, step: &mut WsStep) {
+        let Some((task, negotiated)) = self.task().await else {
+            warn!("Task {} was removed during negotiation", self.task_id);
+            step.finished = true;
+            return;
+        };
+        if task.sync.is_none() || negotiated.raw_files {
+            step.replies.push(self.send_request(task, negotiated).await);
+            return;
+        }
+        warn!("Receiver does not support folder sync");
+        self.msg_id += 1;
+        step.replies
+            .push(WsMessage::status(self.msg_id, &task.task_id, 3, "sync unsupported").to_string());
+        self.report(TransferStatus::Failed(
+            "receiver does not support folder sync".to_string(),
+        ))
+        .await;
+        step.finished = true;
+    }
+
+    /// 信息优先流程中接收端同意后推送下载令牌（任务已被移除时为 None）
+    async fn download_token(&mut self) -> Option<String> {
+        self.msg_id += 1;
+        let mut s = self.state.lock().await;
+        let task = s.tasks.get_mut(&self.task_id)?;
+        let token = DownloadToken {
+            task_id: task.task.task_id.clone(),
+            token: uuid::Uuid::new_v4().simple().to_string(),
+        };
+        task.download_token = Some(token.token.clone());
+        Some(WsMessage::download_token(self.msg_id, &token).to_string())
+    }
+
+    async fn handle(&mut self, msg: &str) -> WsStep {
+        let mut step = WsStep {
+            replies: Vec::new(),
+            finished: false,
+        };
+
+        let ws_msg = match WsMessage::parse(msg) {
             Some(m) => m,
             None => {
                 warn!("Invalid WebSocket message: {}", msg);
-                continue;
+                return step;
             }
         };
 
@@ -217,40 +1041,41 @@ async fn handle_websocket_connection(
             "ack" => {
                 if ws_msg.name == "versionNegotiation" {
                     // 版本协商完成，发送传输请求
-                    msg_id += 1;
-                    let task = {
-                        let s = state.lock().await;
-                        s.task.clone()
-                    };
-
-                    let total_size: u64 = task.files.iter().map(|f| f.size).sum();
-                    let file_name = task
-                        .files
-                        .first()
-                        .map(|f| f.name.clone())
-                        .unwrap_or_default();
-
-                    let send_req = WsMessage::action(
-                        msg_id,
-                        "sendRequest",
-                        Some(serde_json::json!({
-                            "taskId": task.task_id,
-                            "id": task.task_id,
-                            "senderId": task.sender_id,
-                            "senderName": task.sender_name,
-                            "fileName": file_name,
-                            "mimeType": task.files.first().map(|f| &f.mime_type).unwrap_or(&"application/octet-stream".to_string()),
-                            "fileCount": task.files.len(),
-                            "totalSize": total_size
-                        })),
-                    );
-                    write.send(Message::Text(send_req.to_string())).await?;
+                    self.negotiate(&ws_msg).await;
+                    self.request_transfer(&mut step).await;
+                } else if ws_msg.name == "sendRequest" && self.info_first().await {
+                    match self.download_token().await {
+                        Some(token) => step.replies.push(token),
+                        None => step.finished = true,
+                    }
                 }
             }
+            "action" if ws_msg.name == "versionNegotiation" => {
+                // 信息优先流程：接收端发起协商，确认版本后发送传输请求
+                self.negotiate(&ws_msg).await;
+                let (max_version, capabilities) = {
+                    let s = self.state.lock().await;
+                    (s.max_version, s.capabilities.clone())
+                };
+                let version = ws_msg
+                    .payload
+                    .as_ref()
+                    .and_then(|p| p.get("version"))
+                    .and_then(|v| v.as_u64())
+                    .map_or(PROTOCOL_V1, |v| v.min(u64::from(max_version)) as u32);
+                let ack = WsMessage::ack(
+                    ws_msg.id,
+                    "versionNegotiation",
+                    Some(serde_json::json!({ "version": version })),
+                )
+                .with_capabilities(&capabilities);
+                step.replies.push(ack.to_string());
+                self.request_transfer(&mut step).await;
+            }
             "action" => {
                 // 发送 ACK
                 let ack = WsMessage::ack(ws_msg.id, &ws_msg.name, None);
-                write.send(Message::Text(ack.to_string())).await?;
+                step.replies.push(ack.to_string());
 
                 if ws_msg.name == "status"
                     && let Some(payload) = &ws_msg.payload
@@ -259,8 +1084,8 @@ async fn handle_websocket_connection(
                     if status_type == 1 {
                         // 传输完成
                         info!("Transfer completed successfully");
-                        let _ = state.lock().await.status_tx.send(TransferStatus::Completed);
-                        break;
+                        self.report(TransferStatus::Completed).await;
+                        step.finished = true;
                     } else if status_type == 3 {
                         // 用户拒绝
                         info!("Transfer rejected by receiver");
@@ -268,73 +1093,471 @@ async fn handle_websocket_connection(
                             .get("reason")
                             .and_then(|v| v.as_str())
                             .unwrap_or("rejected");
-                        let _ = state
-                            .lock()
-                            .await
-                            .status_tx
-                            .send(TransferStatus::Rejected(reason.to_string()));
-                        break;
+                        self.report(TransferStatus::Rejected(reason.to_string()))
+                            .await;
+                        step.finished = true;
+                    } else if status_type == i64::from(STATUS_PAUSED) {
+                        info!("Transfer paused by receiver");
+                        self.report(TransferStatus::Paused).await;
+                    } else if status_type == i64::from(STATUS_RESUMED) {
+                        info!("Transfer resumed by receiver");
+                        self.report(TransferStatus::Resumed).await;
                     }
                 }
             }
             _ => {}
         }
-    }
 
-    Ok(())
+        step
+    }
 }
 
 /// 文件下载处理器
 async fn download_handler(
     Query(query): Query<DownloadQuery>,
     State(state): State<Arc<Mutex<TransferServerState>>>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    let task = {
-        let s = state.lock().await;
-        if s.task.task_id != query.task_id {
+    let (file, size, status_tx, download) = {
+        let mut s = state.lock().await;
+        let download = match check_download(&s, &headers, &query.task_id, query.token.as_ref()) {
+            Ok(download) => download,
+            Err(rejection) => return rejection.into_response(),
+        };
+        info!("Download request for task_id={}", query.task_id);
+        let Some(t) = s.tasks.get_mut(&query.task_id) else {
             return (StatusCode::NOT_FOUND, "Task not found").into_response();
+        };
+
+        // 创建 ZIP 文件
+        let zip = match t.zip.take() {
+            Some(zip) => zip,
+            None => match create_zip(&t.task.files, t.negotiated.compresses(COMPRESSION_DEFLATE))
+                .await
+            {
+                Ok(zip) => zip,
+                Err(e) => {
+                    error!("Failed to create ZIP: {}", e);
+                    return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create ZIP")
+                        .into_response();
+                }
+            },
+        };
+        // 任务移除时 ZIP 随之删除，已打开的句柄仍能读完
+        let file = File::open(zip.path()).await;
+        let size = zip.size();
+        t.zip = Some(zip);
+        match file {
+            Ok(file) => (file, size, t.status_tx.clone(), download),
+            Err(e) => {
+                error!("Failed to open ZIP: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to open ZIP").into_response();
+            }
         }
-        s.task.clone()
     };
 
-    info!("Download request for task_id={}", task.task_id);
+    let span = ProgressSpan {
+        before: 0,
+        total: size,
+    };
+    let mut response = ranged_file_response(
+        file,
+        size,
+        &headers,
+        "application/zip",
+        span,
+        status_tx,
+        download,
+    )
+    .await;
+    response.headers_mut().insert(
+        header::CONTENT_DISPOSITION,
+        HeaderValue::from_static("attachment; filename=\"files.zip\""),
+    );
+    response
+}
 
-    // 创建 ZIP 文件
-    match create_zip_response(&task.files).await {
-        Ok(data) => {
-            let headers = [
-                ("Content-Type", "application/zip"),
-                ("Content-Disposition", "attachment; filename=\"files.zip\""),
-            ];
-            (headers, data).into_response()
+/// 原始文件下载（需双方协商了 `raw_files`，见 [`Capabilities::raw_files`]）
+///
+/// 与 `/download` 的鉴权、令牌和并发限制相同，同样支持 `Range: bytes=N-` 续传。
+async fn file_handler(
+    Query(query): Query<FileQuery>,
+    State(state): State<Arc<Mutex<TransferServerState>>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (file, span, status_tx, download) = {
+        let s = state.lock().await;
+        let download = match check_download(&s, &headers, &query.task_id, query.token.as_ref()) {
+            Ok(download) => download,
+            Err(rejection) => return rejection.into_response(),
+        };
+        let Some(t) = s.tasks.get(&query.task_id) else {
+            return (StatusCode::NOT_FOUND, "Task not found").into_response();
+        };
+        if !t.negotiated.raw_files {
+            return (StatusCode::NOT_FOUND, "Raw files not negotiated").into_response();
         }
+        let Some(file) = t.task.files.get(query.index).cloned() else {
+            return (StatusCode::NOT_FOUND, "File not found").into_response();
+        };
+        let span = ProgressSpan {
+            before: t.task.files[..query.index].iter().map(|f| f.size).sum(),
+            total: t.task.files.iter().map(|f| f.size).sum(),
+        };
+        (file, span, t.status_tx.clone(), download)
+    };
+    debug!("File request #{} ({})", query.index, file.name);
+
+    // 长度按磁盘上的实际大小计算，Content-Range 与发送的内容一致
+    let opened = match File::open(&file.path).await {
+        Ok(opened) => opened
+            .metadata()
+            .await
+            .map(|metadata| (opened, metadata.len())),
+        Err(e) => Err(e),
+    };
+    let (opened, total) = match opened {
+        Ok(opened) => opened,
         Err(e) => {
-            error!("Failed to create ZIP: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create ZIP").into_response()
+            error!("Failed to open {:?}: {}", file.path, e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response();
         }
+    };
+    ranged_file_response(
+        opened,
+        total,
+        &headers,
+        &file.mime_type,
+        span,
+        status_tx,
+        download,
+    )
+    .await
+}
+
+/// `/download` 和 `/file` 共同的检查，通过时返回下载名额
+///
+/// 先检查访问令牌，未授权的请求无从判断任务 ID 是否存在。
+fn check_download(
+    s: &TransferServerState,
+    headers: &HeaderMap,
+    task_id: &str,
+    token: Option<&String>,
+) -> Result<DownloadGuard, (StatusCode, &'static str)> {
+    let task = s
+        .authorized_task(task_id, headers.get(header::AUTHORIZATION))
+        .inspect_err(|(status, _)| {
+            if *status == StatusCode::UNAUTHORIZED {
+                warn!("Download request without a valid auth token");
+            }
+        })?;
+    if let Some(expected) = &task.download_token
+        && token != Some(expected)
+    {
+        warn!("Download request with missing or wrong token");
+        return Err((StatusCode::FORBIDDEN, "Invalid download token"));
     }
+    s.limiter.try_download().ok_or_else(|| {
+        warn!(
+            "Too many concurrent downloads (limit {}), rejecting",
+            s.limiter.limits().downloads
+        );
+        (StatusCode::TOO_MANY_REQUESTS, "Too many downloads")
+    })
 }
 
-async fn create_zip_response(files: &[FileEntry]) -> anyhow::Result<Vec<u8>> {
-    let mut buffer = Vec::new();
+/// 按请求中的 `Range` 返回长度为 `total` 的 `file` 的全部或后半部分，内容从磁盘分块读取
+async fn ranged_file_response(
+    mut file: File,
+    total: u64,
+    request: &HeaderMap,
+    content_type: &str,
+    span: ProgressSpan,
+    status_tx: broadcast::Sender<TransferStatus>,
+    download: DownloadGuard,
+) -> axum::response::Response {
+    // 其他形式的 Range 按 HTTP 规范忽略，返回完整内容
+    let offset = request
+        .get(header::RANGE)
+        .and_then(|v| parse_range(v.to_str().ok()))
+        .unwrap_or(0);
+    if offset > 0 && offset >= total {
+        return (
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(header::CONTENT_RANGE, format!("bytes */{}", total))],
+        )
+            .into_response();
+    }
+    if let Err(e) = file.seek(SeekFrom::Start(offset)).await {
+        error!("Failed to seek to byte {}: {}", offset, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response();
+    }
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::ACCEPT_RANGES, "bytes".to_string()),
+    ];
+    let body = file_progress_body(file, offset, total, span, status_tx, download);
+    if offset == 0 {
+        (headers, body).into_response()
+    } else {
+        info!("Resuming download at byte {}", offset);
+        let range = format!("bytes {}-{}/{}", offset, total - 1, total);
+        (
+            StatusCode::PARTIAL_CONTENT,
+            headers,
+            [(header::CONTENT_RANGE, range)],
+            body,
+        )
+            .into_response()
+    }
+}
 
+/// `/info`：告诉接收端支持的协议版本
+///
+/// 支持 [`PROTOCOL_V2`] 时，接收端取过 `/info` 就说明它会主动发起版本协商。
+/// 任务按 [`TransferServerState::pick_task`] 选择，与随后的 WebSocket 选中同一个任务；
+/// 没有可选的任务时只返回协议版本。
+async fn info_handler(
+    Query(query): Query<TaskQuery>,
+    State(state): State<Arc<Mutex<TransferServerState>>>,
+    headers: HeaderMap,
+) -> Json<SenderInfo> {
+    let mut s = state.lock().await;
+    let max_version = s.max_version;
+    let task_id = s
+        .pick_task(query.task_id.as_deref(), headers.get(header::AUTHORIZATION))
+        .map(|t| t.task.task_id.clone())
+        .ok();
+    let mut task = task_id.and_then(|id| s.tasks.get_mut(&id));
+    if max_version >= PROTOCOL_V2
+        && let Some(task) = task.as_deref_mut()
     {
-        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(&mut buffer));
-        let options = zip::write::SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::Stored);
+        task.info_first = true;
+    }
+    Json(SenderInfo {
+        version: max_version,
+        versions: SUPPORTED_VERSIONS
+            .iter()
+            .copied()
+            .filter(|&v| v <= max_version)
+            .collect(),
+        task_id: task.as_ref().map(|t| t.task.task_id.clone()),
+        sender_name: task.as_ref().map(|t| t.task.sender_name.clone()),
+    })
+}
+
+/// 解析 `Range: bytes=N-`，只支持从某个位置到末尾（续传只需要这一种）
+fn parse_range(value: Option<&str>) -> Option<u64> {
+    let start = value?.strip_prefix("bytes=")?.strip_suffix('-')?;
+    start.trim().parse().ok()
+}
+
+/// 响应体在整个任务中的位置（字节），用于换算 `Transferring` 进度
+#[derive(Debug, Clone, Copy)]
+struct ProgressSpan {
+    /// 此前各文件的总大小（ZIP 为 0）
+    before: u64,
+    /// 整个任务的大小（ZIP 为 ZIP 本身的大小）
+    total: u64,
+}
+
+/// 从 `file` 的 `offset` 处分块发送到 `total` 为止，并在发送过程中广播 `Transferring` 进度
+///
+/// 每次只读出一块，内存占用与文件大小无关。
+/// 块大小和进度间隔由 [`AdaptiveChunker`] 按实测吞吐量调整：相邻两次取块的间隔
+/// 近似于上一块写入套接字的时间。最后一块总会广播进度。`download` 名额在响应体
+/// 发送完毕或连接断开时归还。
+fn file_progress_body(
+    file: File,
+    offset: u64,
+    total: u64,
+    span: ProgressSpan,
+    status_tx: broadcast::Sender<TransferStatus>,
+    download: DownloadGuard,
+) -> Body {
+    let reading = FileChunks {
+        file,
+        position: offset,
+        total,
+        last_len: 0,
+        chunker: AdaptiveChunker::new(),
+        last_progress: None,
+        span,
+        status_tx,
+        _download: download,
+    };
+    let chunks = futures_util::stream::unfold(reading, |mut r| async move {
+        r.chunker.record(r.last_len);
+        if r.position >= r.total {
+            return None;
+        }
+        let len = (r.chunker.chunk_size() as u64).min(r.total - r.position) as usize;
+        let mut chunk = vec![0; len];
+        if let Err(e) = r.file.read_exact(&mut chunk).await {
+            // 文件在发送过程中被截断，结束响应体
+            error!("Failed to read at byte {}: {}", r.position, e);
+            r.position = r.total;
+            return Some((Err(e), r));
+        }
+        r.position += len as u64;
+        r.report_progress();
+        r.last_len = len;
+        Some((Ok(Bytes::from(chunk)), r))
+    });
+    Body::from_stream(chunks)
+}
+
+/// [`file_progress_body`] 的读取状态
+struct FileChunks {
+    file: File,
+    /// 下一块的起始位置
+    position: u64,
+    total: u64,
+    last_len: usize,
+    chunker: AdaptiveChunker,
+    last_progress: Option<Instant>,
+    span: ProgressSpan,
+    status_tx: broadcast::Sender<TransferStatus>,
+    /// 响应体发送完毕或连接断开时归还
+    _download: DownloadGuard,
+}
+
+impl FileChunks {
+    /// 到了进度间隔或已是最后一块时广播 `Transferring`
+    fn report_progress(&mut self) {
+        let now = Instant::now();
+        let due = self.last_progress.map_or(true, |t| {
+            now.duration_since(t) >= self.chunker.progress_interval()
+        });
+        if due || self.position == self.total {
+            self.last_progress = Some(now);
+            let sent = self.span.before + self.position;
+            let _ = self.status_tx.send(TransferStatus::Transferring {
+                progress: sent as f64 / self.span.total.max(1) as f64,
+            });
+        }
+    }
+}
+
+/// 生成在临时目录中的 ZIP，drop 时删除
+///
+/// 删除只是解除链接，删除前已打开的句柄仍能读完。
+#[derive(Debug)]
+pub struct ZipFile {
+    path: PathBuf,
+    size: u64,
+}
+
+impl ZipFile {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl Drop for ZipFile {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            warn!("Failed to remove {:?}: {}", self.path, e);
+        }
+    }
+}
+
+/// 把任务文件打包成 `/download` 返回的 ZIP（条目名 `序号/文件名`，不压缩）
+///
+/// 公开给基准测试使用。
+pub async fn create_zip_response(files: &[FileEntry]) -> anyhow::Result<ZipFile> {
+    create_zip(files, false).await
+}
+
+/// 同 [`create_zip_response`]；`deflate` 为真时文本类文件用 Deflate 压缩（需双方协商）
+async fn create_zip(files: &[FileEntry], deflate: bool) -> anyhow::Result<ZipFile> {
+    let files = files.to_vec();
+    tokio::task::spawn_blocking(move || create_zip_blocking(&files, deflate)).await?
+}
+
+/// 逐个文件流式写入临时目录中的 ZIP，内存占用与文件大小无关
+fn create_zip_blocking(files: &[FileEntry], deflate: bool) -> anyhow::Result<ZipFile> {
+    let path = std::env::temp_dir().join(format!("cattysend-{}.zip", uuid::Uuid::new_v4()));
+    // 打包的文件只对本用户可见
+    let out = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)?;
+    // 之后出错时 drop 会删除半成品
+    let mut zip_file = ZipFile { path, size: 0 };
+
+    {
+        let mut zip = zip::ZipWriter::new(std::io::BufWriter::new(out));
 
         for (i, file) in files.iter().enumerate() {
             let entry_name = format!("{}/{}", i, file.name);
+            let method = if deflate && is_compressible(&file.mime_type) {
+                zip::CompressionMethod::Deflated
+            } else {
+                zip::CompressionMethod::Stored
+            };
+            let options = zip::write::SimpleFileOptions::default().compression_method(method);
+            let options = match file.mode {
+                Some(mode) => options.unix_permissions(mode),
+                None => options,
+            }
+            // 超过 4 GiB 的条目需要 ZIP64 扩展
+            .large_file(file.size >= u64::from(u32::MAX));
             zip.start_file(&entry_name, options)?;
 
-            let mut f = File::open(&file.path).await?;
-            let mut contents = Vec::new();
-            f.read_to_end(&mut contents).await?;
-            zip.write_all(&contents)?;
+            let mut f = std::fs::File::open(&file.path)?;
+            std::io::copy(&mut f, &mut zip)?;
         }
 
-        zip.finish()?;
+        let mut out = zip.finish()?;
+        out.flush()?;
+        zip_file.size = out.get_ref().metadata()?.len();
     }
 
-    Ok(buffer)
+    Ok(zip_file)
+}
+
+/// 压缩有明显收益的类型；图片、视频、压缩包等已经压缩过，再压缩只会拖慢传输
+fn is_compressible(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json"
+                | "application/xml"
+                | "application/javascript"
+                | "application/x-sh"
+                | "image/svg+xml"
+        )
+}
+
+/// 在阻塞线程中计算各文件的 SHA-256 写入 `infos`，读取失败的文件不带摘要
+///
+/// 已带有摘要（[`FileEntry::sha256`]）的文件不再读取。
+async fn hash_files(files: &[FileEntry], infos: &mut [FileInfo]) {
+    let files = files.to_vec();
+    let hashes = tokio::task::spawn_blocking(move || {
+        files
+            .iter()
+            .map(|file| match &file.sha256 {
+                Some(hash) => Some(hash.clone()),
+                None => match sha256_file(&file.path) {
+                    Ok(hash) => Some(hash),
+                    Err(e) => {
+                        warn!("Failed to hash {:?}: {}", file.path, e);
+                        None
+                    }
+                },
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+    for (info, hash) in infos.iter_mut().zip(hashes) {
+        info.sha256 = hash;
+    }
 }
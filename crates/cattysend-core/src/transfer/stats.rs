@@ -0,0 +1,159 @@
+//! 传输统计
+//!
+//! ZIP 内的文件按顺序传输，因此只需累计字节数就能推算出每个文件的进度。
+//! [`StatsTracker`] 按固定间隔采样，得到的速度序列可以直接画成速度曲线。
+
+use std::time::{Duration, Instant};
+
+/// 统计采样间隔
+pub const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 单个文件的进度
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileProgress {
+    pub name: String,
+    pub size: u64,
+    pub transferred: u64,
+}
+
+impl FileProgress {
+    /// 完成比例 (0.0 ~ 1.0)，空文件视为已完成
+    pub fn fraction(&self) -> f64 {
+        if self.size == 0 {
+            1.0
+        } else {
+            self.transferred as f64 / self.size as f64
+        }
+    }
+}
+
+/// 某一时刻的传输统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferStats {
+    /// 各文件进度，顺序与传输顺序一致
+    pub files: Vec<FileProgress>,
+    pub transferred: u64,
+    pub total: u64,
+    /// 上一个采样周期内的平均速度（字节/秒）
+    pub bytes_per_sec: u64,
+    /// 自开始传输以来的时间
+    pub elapsed: Duration,
+}
+
+/// 把累计字节数转换为定期的 [`TransferStats`]
+#[derive(Debug)]
+pub struct StatsTracker {
+    files: Vec<(String, u64)>,
+    total: u64,
+    start: Instant,
+    last_sample: Instant,
+    last_bytes: u64,
+    finished: bool,
+}
+
+impl StatsTracker {
+    /// `files` 为 (文件名, 大小)，顺序与传输顺序一致
+    pub fn new(files: impl IntoIterator<Item = (String, u64)>) -> Self {
+        let files: Vec<_> = files.into_iter().collect();
+        let total = files.iter().map(|(_, size)| size).sum();
+        let now = Instant::now();
+        Self {
+            files,
+            total,
+            start: now,
+            last_sample: now,
+            last_bytes: 0,
+            finished: false,
+        }
+    }
+
+    /// 更新累计字节数
+    ///
+    /// 距上次采样不足 [`STATS_INTERVAL`] 时返回 `None`；传输完成时总会返回一次。
+    pub fn update(&mut self, transferred: u64) -> Option<TransferStats> {
+        self.update_at(transferred, Instant::now())
+    }
+
+    fn update_at(&mut self, transferred: u64, now: Instant) -> Option<TransferStats> {
+        let done = transferred >= self.total;
+        let elapsed = now.saturating_duration_since(self.last_sample);
+        // 完成的那一次不受采样间隔限制
+        let final_sample = done && !self.finished;
+        if elapsed < STATS_INTERVAL && !final_sample {
+            return None;
+        }
+        self.finished |= done;
+
+        let secs = elapsed.as_secs_f64().max(0.001);
+        let bytes_per_sec = (transferred.saturating_sub(self.last_bytes) as f64 / secs) as u64;
+        self.last_sample = now;
+        self.last_bytes = transferred;
+
+        Some(TransferStats {
+            files: self.file_progress(transferred),
+            transferred,
+            total: self.total,
+            bytes_per_sec,
+            elapsed: now.saturating_duration_since(self.start),
+        })
+    }
+
+    fn file_progress(&self, transferred: u64) -> Vec<FileProgress> {
+        let mut remaining = transferred;
+        self.files
+            .iter()
+            .map(|(name, size)| {
+                let done = remaining.min(*size);
+                remaining -= done;
+                FileProgress {
+                    name: name.clone(),
+                    size: *size,
+                    transferred: done,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> StatsTracker {
+        StatsTracker::new([("a".to_string(), 100), ("b".to_string(), 50)])
+    }
+
+    #[test]
+    fn test_file_progress_follows_transfer_order() {
+        let t = tracker();
+        let files = t.file_progress(120);
+        assert_eq!(files[0].transferred, 100);
+        assert_eq!(files[1].transferred, 20);
+        assert!((files[1].fraction() - 0.4).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_update_samples_once_per_interval() {
+        let mut t = tracker();
+        let start = t.start;
+
+        assert!(
+            t.update_at(10, start + Duration::from_millis(500))
+                .is_none()
+        );
+
+        let stats = t.update_at(60, start + Duration::from_secs(2)).unwrap();
+        assert_eq!(stats.bytes_per_sec, 30);
+        assert_eq!(stats.total, 150);
+
+        // 完成时立即上报，之后恢复按间隔采样
+        let stats = t
+            .update_at(150, start + Duration::from_millis(2100))
+            .unwrap();
+        assert_eq!(stats.transferred, 150);
+        assert!(
+            t.update_at(150, start + Duration::from_millis(2200))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! 传输层抽象
+//!
+//! 工作流只依赖 [`TransferTransport`]：发送端提供传输任务，接收端连接发送端并下载。
+//! 默认实现 [`HttpTransport`] 即现有的 HTTP(S) + WebSocket 协议栈（CatShare 兼容）；
+//! [`WarmHttpTransport`] 是同一协议栈，但可以提前绑定下一次传输的监听端口（热备）。
+//! 其他传输方式（例如面向高丢包 2.4GHz 链路的 QUIC/HTTP3）只需实现同一个 trait，
+//! 再通过 `Sender::with_transport` / `Receiver::with_transport` 注入，无需修改工作流。
+
+use crate::config::PortRange;
+use crate::crypto::AtRestKey;
+use crate::transfer::receiver_client::WsStream;
+use crate::transfer::{
+    ReceiverCallback, ReceiverClient, TransferControl, TransferServer, TransferStatus,
+    TransferTask, bind_listener,
+};
+use async_trait::async_trait;
+use log::debug;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+/// 字节传输后端
+#[async_trait]
+pub trait TransferTransport: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 提供传输任务（发送端），端口在 `ports` 范围内选择，`None` 时为随机端口
+    async fn serve(
+        &self,
+        task: TransferTask,
+        ports: Option<PortRange>,
+    ) -> anyhow::Result<ServedTask>;
+
+    /// 连接发送端（接收端）
+    ///
+    /// 只包含建立连接的阶段，便于调用方按重试策略重试
+    async fn connect(&self, target: &TransferTarget)
+    -> anyhow::Result<Box<dyn TransferConnection>>;
+}
+
+/// 已建立的接收端连接
+#[async_trait]
+pub trait TransferConnection: Send {
+    /// 完成协商并下载文件
+    async fn receive(
+        self: Box<Self>,
+        callback: &dyn ReceiverCallback,
+    ) -> anyhow::Result<Vec<PathBuf>>;
+}
+
+/// 发送端正在提供的任务
+pub struct ServedTask {
+    /// 监听端口（写入 P2P 信息发给接收端）
+    pub port: u16,
+    /// 传输状态更新
+    pub status: broadcast::Receiver<TransferStatus>,
+}
+
+/// 接收端要连接的发送端
+#[derive(Debug, Clone)]
+pub struct TransferTarget {
+    pub host: String,
+    /// `host` 不可达时依次尝试的备选地址（见 [`WifiBackend::sender_candidates`](crate::wifi::WifiBackend::sender_candidates)）
+    pub fallback_hosts: Vec<String>,
+    pub port: u16,
+    /// 是否使用 TLS（CatShare 发送端使用 HTTPS）
+    pub tls: bool,
+    /// 文件保存目录
+    pub output_dir: PathBuf,
+    /// 是否恢复发送端文件的可执行位
+    pub restore_permissions: bool,
+    /// 静态加密密钥，设置后文件以加密形式保存
+    pub encryption: Option<AtRestKey>,
+    /// 下载的暂停/恢复句柄
+    pub control: TransferControl,
+    /// 是否按发送端名称和日期分到子目录
+    pub sort_by_sender: bool,
+    /// P2P 信息中的访问令牌（CatShare 发送端没有）
+    pub auth_token: Option<String>,
+    /// P2P 信息中的证书指纹，设置后使用 TLS 并只接受该证书（CatShare 发送端没有）
+    pub pinned_cert: Option<String>,
+    /// 文件夹同步时是否允许删除发送端已没有的文件
+    pub allow_sync_delete: bool,
+}
+
+/// HTTP(S) + WebSocket 传输（默认，CatShare 兼容）
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HttpTransport;
+
+#[async_trait]
+impl TransferTransport for HttpTransport {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn serve(
+        &self,
+        task: TransferTask,
+        ports: Option<PortRange>,
+    ) -> anyhow::Result<ServedTask> {
+        let mut server = TransferServer::new(task).with_ports(ports);
+        let port = server.start().await?;
+        let status = server.subscribe_status_async().await;
+        Ok(ServedTask { port, status })
+    }
+
+    async fn connect(
+        &self,
+        target: &TransferTarget,
+    ) -> anyhow::Result<Box<dyn TransferConnection>> {
+        let client = ReceiverClient::new(&target.host, target.port, target.output_dir.clone())
+            .with_tls(target.tls)
+            .with_restore_permissions(target.restore_permissions)
+            .with_encryption(target.encryption.clone())
+            .with_control(target.control.clone())
+            .with_sort_by_sender(target.sort_by_sender)
+            .with_auth_token(target.auth_token.clone())
+            .with_pinned_cert(target.pinned_cert.clone())
+            .with_sync_delete(target.allow_sync_delete)
+            .with_fallback_hosts(target.fallback_hosts.clone())
+            .ensure_reachable()
+            .await?;
+        let version = client.detect_protocol().await;
+        let client = client.with_protocol(version);
+        let ws_stream = client.connect().await?;
+        Ok(Box::new(HttpConnection { client, ws_stream }))
+    }
+}
+
+/// 预先绑定监听端口的 HTTP(S) 传输（热备）
+///
+/// [`Self::prepare`] 提前在端口范围内监听，下一次 [`TransferTransport::serve`] 的端口范围相同时
+/// 直接在这个端口上提供任务，接收端此前发起的连接排在监听队列里，不会被拒绝。
+/// 端口只用一次，之后按需绑定，直到再次调用 [`Self::prepare`]。
+#[derive(Debug, Default)]
+pub struct WarmHttpTransport {
+    /// 预先绑定的端口及其端口范围
+    listener: Mutex<Option<(Option<PortRange>, TcpListener)>>,
+}
+
+impl WarmHttpTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在 `ports` 范围内（`None` 时为随机端口）绑定下一次传输的端口，替换之前绑定的
+    pub async fn prepare(&self, ports: Option<PortRange>) -> anyhow::Result<u16> {
+        let listener = bind_listener(ports).await?;
+        let port = listener.local_addr()?.port();
+        *self.listener.lock().unwrap() = Some((ports, listener));
+        debug!("Transfer port {} prepared", port);
+        Ok(port)
+    }
+
+    /// 预先绑定的端口（没有时为 None）
+    pub fn prepared_port(&self) -> Option<u16> {
+        let guard = self.listener.lock().unwrap();
+        let (_, listener) = guard.as_ref()?;
+        listener.local_addr().ok().map(|addr| addr.port())
+    }
+
+    /// 取出范围为 `ports` 的预绑定端口，范围不同时丢弃
+    fn take_listener(&self, ports: Option<PortRange>) -> Option<TcpListener> {
+        match self.listener.lock().unwrap().take() {
+            Some((prepared, listener)) if prepared == ports => Some(listener),
+            Some(_) => {
+                debug!("Prepared transfer port is outside {:?}, rebinding", ports);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+#[async_trait]
+impl TransferTransport for WarmHttpTransport {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn serve(
+        &self,
+        task: TransferTask,
+        ports: Option<PortRange>,
+    ) -> anyhow::Result<ServedTask> {
+        let Some(listener) = self.take_listener(ports) else {
+            return HttpTransport.serve(task, ports).await;
+        };
+        let mut server = TransferServer::new(task).with_listener(listener);
+        let port = server.start().await?;
+        let status = server.subscribe_status_async().await;
+        Ok(ServedTask { port, status })
+    }
+
+    async fn connect(
+        &self,
+        target: &TransferTarget,
+    ) -> anyhow::Result<Box<dyn TransferConnection>> {
+        HttpTransport.connect(target).await
+    }
+}
+
+struct HttpConnection {
+    client: ReceiverClient,
+    ws_stream: WsStream,
+}
+
+#[async_trait]
+impl TransferConnection for HttpConnection {
+    async fn receive(
+        self: Box<Self>,
+        callback: &dyn ReceiverCallback,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let HttpConnection { client, ws_stream } = *self;
+        client.receive(ws_stream, callback).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, OnceLock};
+
+    fn task() -> TransferTask {
+        TransferTask {
+            task_id: "warm".to_string(),
+            files: Vec::new(),
+            sender_id: "0000".to_string(),
+            sender_name: "test".to_string(),
+            verification_code: Arc::new(OnceLock::new()),
+            auth_token: None,
+            tls: None,
+            sync: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warm_transport_serves_on_prepared_port() {
+        let transport = WarmHttpTransport::new();
+        let port = transport.prepare(None).await.unwrap();
+        assert_eq!(transport.prepared_port(), Some(port));
+
+        let served = transport.serve(task(), None).await.unwrap();
+        assert_eq!(served.port, port);
+        // 端口只用一次
+        assert_eq!(transport.prepared_port(), None);
+
+        // 端口范围不同时丢弃预绑定的端口
+        transport.prepare(None).await.unwrap();
+        let taken = TcpListener::bind("0.0.0.0:0").await.unwrap();
+        let free = taken.local_addr().unwrap().port();
+        drop(taken);
+        let range = PortRange::new(free, free);
+        let served = transport.serve(task(), Some(range)).await.unwrap();
+        assert_eq!(served.port, free);
+        assert_eq!(transport.prepared_port(), None);
+    }
+}
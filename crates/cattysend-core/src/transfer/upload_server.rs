@@ -0,0 +1,256 @@
+//! 接收端上传服务（反向传输）
+//!
+//! 接收端接入发送端热点后，可以额外开启一个 HTTP 服务，让同一网络内的对端用
+//! `PUT /upload?name=<文件名>` 把文件推回来，无需重新走一遍 BLE 发现和握手，
+//! 从而在一次会话内完成双向互传。
+//!
+//! 这是 Cattysend 自有的扩展，CatShare 不会使用此端点。
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::put,
+};
+use futures_util::StreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// 上传请求参数
+#[derive(Deserialize)]
+pub struct UploadQuery {
+    pub name: String,
+}
+
+/// 上传成功的响应
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UploadResponse {
+    /// 实际保存的文件名（重名时会被改写）
+    pub name: String,
+    pub size: u64,
+}
+
+/// 上传服务（接收端）
+pub struct UploadServer {
+    output_dir: PathBuf,
+    port: u16,
+}
+
+struct UploadState {
+    output_dir: PathBuf,
+    upload_tx: mpsc::Sender<PathBuf>,
+}
+
+impl UploadServer {
+    pub fn new(output_dir: PathBuf) -> Self {
+        Self {
+            output_dir,
+            port: 0, // 使用随机端口
+        }
+    }
+
+    /// 监听指定端口（对端需要事先知道端口号）
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// 启动服务，返回句柄和已保存文件的通知通道
+    pub async fn start(self) -> anyhow::Result<(UploadServerHandle, mpsc::Receiver<PathBuf>)> {
+        let (upload_tx, upload_rx) = mpsc::channel(16);
+        let state = Arc::new(UploadState {
+            output_dir: self.output_dir,
+            upload_tx,
+        });
+
+        let app = Router::new()
+            .route("/upload", put(upload_handler))
+            .with_state(state);
+
+        let listener = TcpListener::bind(("0.0.0.0", self.port)).await?;
+        let port = listener.local_addr()?.port();
+        info!("Upload server listening on port {}", port);
+
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                warn!("Upload server error: {}", e);
+            }
+        });
+
+        Ok((UploadServerHandle { port, task }, upload_rx))
+    }
+}
+
+/// 上传服务句柄，drop 时停止监听
+pub struct UploadServerHandle {
+    port: u16,
+    task: JoinHandle<()>,
+}
+
+impl UploadServerHandle {
+    /// 监听端口
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for UploadServerHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn upload_handler(
+    State(state): State<Arc<UploadState>>,
+    Query(query): Query<UploadQuery>,
+    body: Body,
+) -> impl IntoResponse {
+    let Some(name) = sanitize_file_name(&query.name) else {
+        return (StatusCode::BAD_REQUEST, "Invalid file name").into_response();
+    };
+    let target = unique_path(&state.output_dir, name);
+    let partial = target.with_file_name(format!(
+        ".{}.part",
+        target.file_name().unwrap_or_default().to_string_lossy()
+    ));
+
+    match write_body(&partial, body).await {
+        Ok(size) => {
+            if let Err(e) = tokio::fs::rename(&partial, &target).await {
+                warn!("Failed to save upload {:?}: {}", target, e);
+                let _ = tokio::fs::remove_file(&partial).await;
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            info!("Received upload {:?} ({} bytes)", target, size);
+            let name = target
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let _ = state.upload_tx.send(target).await;
+            (StatusCode::CREATED, Json(UploadResponse { name, size })).into_response()
+        }
+        Err(e) => {
+            warn!("Upload of {:?} failed: {}", target, e);
+            let _ = tokio::fs::remove_file(&partial).await;
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// 把请求体流式写入文件，返回写入的字节数
+async fn write_body(path: &Path, body: Body) -> anyhow::Result<u64> {
+    let mut file = tokio::fs::File::create(path).await?;
+    let mut stream = body.into_data_stream();
+    let mut size = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        size += chunk.len() as u64;
+    }
+    file.flush().await?;
+    Ok(size)
+}
+
+/// 把对端提供的文件名限制为单个路径分量，拒绝空名、`.`、`..` 和隐藏文件
+fn sanitize_file_name(name: &str) -> Option<&str> {
+    let name = name.rsplit(['/', '\\']).next()?.trim();
+    if name.is_empty() || name.starts_with('.') {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// `dir/name`，已存在时改用 `name (1).ext`、`name (2).ext`……
+pub(crate) fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    unique_path_with_suffix(dir, name, "")
+}
+
+/// 同 [`unique_path`]，但保存的文件名是 `name` 后接 `suffix`（如加密文件的扩展名），
+/// 序号加在 `name` 的扩展名之前
+pub(crate) fn unique_path_with_suffix(dir: &Path, name: &str, suffix: &str) -> PathBuf {
+    let candidate = |name: &str| dir.join(format!("{}{}", name, suffix));
+    let path = candidate(name);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| candidate(&format!("{} ({}){}", stem, n, ext)))
+        .find(|p| !p.exists())
+        .unwrap_or(path)
+}
+
+/// 把本地文件上传到对端的上传服务
+pub async fn upload_file(host: &str, port: u16, path: &Path) -> anyhow::Result<UploadResponse> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Not a file: {:?}", path))?
+        .to_string_lossy()
+        .to_string();
+    let file = tokio::fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+    let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+
+    let response = reqwest::Client::new()
+        .put(format!("http://{}:{}/upload", host, port))
+        .query(&[("name", name.as_str())])
+        .header(reqwest::header::CONTENT_LENGTH, size)
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("photo.jpg"), Some("photo.jpg"));
+        assert_eq!(sanitize_file_name("../../etc/passwd"), Some("passwd"));
+        assert_eq!(sanitize_file_name("C:\\tmp\\a.txt"), Some("a.txt"));
+        assert_eq!(sanitize_file_name(".."), None);
+        assert_eq!(sanitize_file_name(".bashrc"), None);
+        assert_eq!(sanitize_file_name("dir/"), None);
+    }
+
+    #[tokio::test]
+    async fn test_upload_roundtrip() {
+        let root = temp_dir("upload");
+        let (src, out) = (root.join("src"), root.join("out"));
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::create_dir_all(&out).unwrap();
+        let file = src.join("notes.txt");
+        std::fs::write(&file, b"hello").unwrap();
+        std::fs::write(out.join("notes.txt"), b"existing").unwrap();
+
+        let (handle, mut rx) = UploadServer::new(out.clone()).start().await.unwrap();
+        let response = upload_file("127.0.0.1", handle.port(), &file)
+            .await
+            .unwrap();
+        assert_eq!(response.name, "notes (1).txt");
+        assert_eq!(response.size, 5);
+
+        let saved = rx.recv().await.unwrap();
+        assert_eq!(saved, out.join("notes (1).txt"));
+        assert_eq!(std::fs::read(&saved).unwrap(), b"hello");
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
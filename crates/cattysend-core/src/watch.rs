@@ -0,0 +1,159 @@
+//! 目录中最新文件的查找与监视
+//!
+//! 用于 "分享最新截图" 一类的流程：
+//!
+//! - [`latest_file`]：一次性扫描目录，返回修改时间最新的文件
+//! - `LatestFileWatcher`（`watch` feature）：基于 notify 持续监视目录，
+//!   新文件写入后通过 `tokio::sync::watch` 通知订阅者
+//!
+//! 隐藏文件（以 `.` 开头，截图工具常用作写入中的临时文件）和子目录会被忽略。
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 返回 `dir` 中修改时间最新的普通文件
+///
+/// 目录为空时返回 `Ok(None)`。
+pub fn latest_file(dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    let mut latest: Option<(SystemTime, PathBuf)> = None;
+
+    for entry in std::fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if !is_candidate(&path) {
+            continue;
+        }
+        let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+            continue;
+        };
+        if latest.as_ref().map_or(true, |(t, _)| modified > *t) {
+            latest = Some((modified, path));
+        }
+    }
+
+    Ok(latest.map(|(_, path)| path))
+}
+
+/// 是否是可分享的文件（非隐藏的普通文件）
+fn is_candidate(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map_or(true, |n| n.starts_with('.'));
+    !hidden && path.is_file()
+}
+
+#[cfg(feature = "watch")]
+pub use watcher::LatestFileWatcher;
+
+#[cfg(feature = "watch")]
+mod watcher {
+    use super::{is_candidate, latest_file};
+    use log::warn;
+    use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+    use std::path::PathBuf;
+    use tokio::sync::watch;
+
+    /// 持续监视目录中最新的文件
+    ///
+    /// 丢弃后停止监视。
+    pub struct LatestFileWatcher {
+        rx: watch::Receiver<Option<PathBuf>>,
+        _watcher: RecommendedWatcher,
+    }
+
+    impl LatestFileWatcher {
+        /// 开始监视 `dir`（不递归），初始值为当前最新的文件
+        pub fn new(dir: impl Into<PathBuf>) -> notify::Result<Self> {
+            let dir = dir.into();
+            let (tx, rx) = watch::channel(latest_file(&dir).ok().flatten());
+
+            let scan_dir = dir.clone();
+            let mut watcher =
+                notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                    let event = match res {
+                        Ok(event) => event,
+                        Err(e) => {
+                            warn!("File watcher error: {}", e);
+                            return;
+                        }
+                    };
+                    match event.kind {
+                        EventKind::Create(_) | EventKind::Modify(_) => {
+                            for path in event.paths.into_iter().filter(|p| is_candidate(p)) {
+                                // 写入过程中会连续收到 Modify，同一文件只通知一次
+                                tx.send_if_modified(|latest| {
+                                    if latest.as_ref() == Some(&path) {
+                                        false
+                                    } else {
+                                        *latest = Some(path);
+                                        true
+                                    }
+                                });
+                            }
+                        }
+                        EventKind::Remove(_) => {
+                            let removed =
+                                event.paths.iter().any(|p| tx.borrow().as_ref() == Some(p));
+                            if removed {
+                                tx.send_replace(latest_file(&scan_dir).ok().flatten());
+                            }
+                        }
+                        _ => {}
+                    }
+                })?;
+            watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+            Ok(Self {
+                rx,
+                _watcher: watcher,
+            })
+        }
+
+        /// 当前最新的文件
+        pub fn latest(&self) -> Option<PathBuf> {
+            self.rx.borrow().clone()
+        }
+
+        /// 订阅最新文件的变化
+        pub fn subscribe(&self) -> watch::Receiver<Option<PathBuf>> {
+            self.rx.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use std::time::Duration;
+
+    #[test]
+    fn test_latest_file() {
+        let dir = temp_dir("watch");
+        std::fs::create_dir_all(dir.join("subdir")).unwrap();
+        assert_eq!(latest_file(&dir).unwrap(), None);
+
+        let old = dir.join("old.png");
+        let new = dir.join("new.png");
+        std::fs::write(&old, b"old").unwrap();
+        std::fs::write(&new, b"new").unwrap();
+        std::fs::write(dir.join(".partial.png"), b"tmp").unwrap();
+
+        let set_mtime = |path: &Path, secs: u64| {
+            std::fs::File::options()
+                .write(true)
+                .open(path)
+                .unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        set_mtime(&old, 1_000);
+        set_mtime(&new, 2_000);
+        assert_eq!(latest_file(&dir).unwrap(), Some(new.clone()));
+
+        set_mtime(&old, 3_000);
+        assert_eq!(latest_file(&dir).unwrap(), Some(old));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
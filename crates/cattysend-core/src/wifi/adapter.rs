@@ -0,0 +1,85 @@
+//! 热点网卡的选择
+//!
+//! 有多块 WiFi 网卡时，热点优先建在空闲的网卡上，主网卡上的连接保持不断。
+//! 候选网卡按 [`Adapter::score`] 排序：空闲 > 支持 P2P > 支持 5GHz。
+//!
+//! 设置 `hotspot_interface` 显式指定热点网卡时不做挑选。
+
+/// 一块 WiFi 网卡
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Adapter {
+    /// 接口名 (如 wlan1)
+    pub interface: String,
+    /// 没有活动连接
+    pub idle: bool,
+    /// NetworkManager 为它创建了 WiFi-P2P 设备
+    pub p2p: bool,
+    /// 支持 5GHz 频段
+    pub supports_5ghz: bool,
+}
+
+impl Adapter {
+    /// 作为热点网卡的优先级，越大越好；每一项都比排在它后面的各项加起来更重要
+    pub fn score(&self) -> u8 {
+        (u8::from(self.idle) << 2) | (u8::from(self.p2p) << 1) | u8::from(self.supports_5ghz)
+    }
+}
+
+/// 按 [`Adapter::score`] 从高到低排序，分数相同时保持原顺序
+pub fn rank_adapters(mut adapters: Vec<Adapter>) -> Vec<Adapter> {
+    adapters.sort_by_key(|a| std::cmp::Reverse(a.score()));
+    adapters
+}
+
+/// 在 `exclude`（已被占用的网卡）之外挑选最适合建热点的空闲网卡
+pub fn pick_hotspot_adapter(adapters: Vec<Adapter>, exclude: &str) -> Option<Adapter> {
+    rank_adapters(adapters)
+        .into_iter()
+        .find(|a| a.idle && a.interface != exclude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(interface: &str, idle: bool, p2p: bool, supports_5ghz: bool) -> Adapter {
+        Adapter {
+            interface: interface.to_string(),
+            idle,
+            p2p,
+            supports_5ghz,
+        }
+    }
+
+    #[test]
+    fn test_pick_hotspot_adapter() {
+        let adapters = vec![
+            adapter("wlan0", false, true, true),
+            adapter("wlan1", true, false, true),
+            adapter("wlan2", true, true, false),
+            adapter("wlan3", false, false, false),
+        ];
+        let ranked: Vec<_> = rank_adapters(adapters.clone())
+            .into_iter()
+            .map(|a| a.interface)
+            .collect();
+        assert_eq!(ranked, ["wlan2", "wlan1", "wlan0", "wlan3"]);
+
+        // P2P 优先于 5GHz
+        assert_eq!(
+            pick_hotspot_adapter(adapters.clone(), "wlan0").map(|a| a.interface),
+            Some("wlan2".to_string())
+        );
+        assert_eq!(
+            pick_hotspot_adapter(adapters, "wlan2").map(|a| a.interface),
+            Some("wlan1".to_string())
+        );
+
+        // 没有空闲网卡时不换
+        let busy = vec![
+            adapter("wlan0", false, true, true),
+            adapter("wlan1", false, true, true),
+        ];
+        assert_eq!(pick_hotspot_adapter(busy, "wlan0"), None);
+    }
+}
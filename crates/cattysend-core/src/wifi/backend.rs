@@ -0,0 +1,268 @@
+//! WiFi 后端抽象
+//!
+//! 工作流只依赖 [`WifiBackend`]：创建/关闭热点、放行传输端口、连接/断开热点、查询本机 IP。
+//! 目前只有基于 NetworkManager 的 Linux 实现 [`LinuxWifiBackend`]，
+//! 其他平台只需实现同一个 trait，无需修改工作流。
+
+use crate::discovery::host::{self, HostAdvertisement};
+use crate::firewall::{self, HotspotIsolation, PortAccess};
+use crate::wifi::command::CommandRunner;
+use crate::wifi::helper;
+use crate::wifi::sender_addr;
+use crate::wifi::station_monitor::{StationSnapshot, spawn_station_monitor};
+use crate::wifi::{
+    CredentialPolicy, InterfaceBusy, P2pConfig, P2pInfo, StationInfo, WiFiP2pReceiver,
+    WiFiP2pSender,
+};
+use async_trait::async_trait;
+use log::{debug, warn};
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc};
+
+/// WiFi 后端
+#[async_trait]
+pub trait WifiBackend: Send + Sync {
+    /// 后端名称（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 按 `credentials` 创建热点（发送端），返回未加密的 P2pInfo
+    ///
+    /// 实际使用的认证方式写在返回值的 `security` 中，可能弱于 `credentials` 的要求
+    async fn create_hotspot(
+        &self,
+        port: i32,
+        credentials: &CredentialPolicy,
+    ) -> anyhow::Result<P2pInfo>;
+
+    /// 关闭热点
+    async fn stop_hotspot(&self) -> anyhow::Result<()>;
+
+    /// 按 `credentials` 预先准备热点但不开启（热备），下次策略相同的
+    /// [`Self::create_hotspot`] 只需开启它
+    ///
+    /// 默认不做任何准备
+    async fn prepare_hotspot(&self, _credentials: &CredentialPolicy) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 创建热点会断开的现有 WiFi 连接（发送端，创建热点前调用）
+    ///
+    /// 默认认为不会断开任何连接
+    async fn busy_interface(&self) -> Option<InterfaceBusy> {
+        None
+    }
+
+    /// 热点改在 `interface` 上创建（[`InterfaceBusy::alternative`]），热点关闭后恢复
+    fn use_hotspot_interface(&self, _interface: &str) -> anyhow::Result<()> {
+        anyhow::bail!("{} cannot switch the hotspot interface", self.name())
+    }
+
+    /// 热点关闭后重新连接 `busy` 中的网络
+    async fn restore_after_hotspot(&self, _busy: &InterfaceBusy) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// 让接入热点的客户端能连上本机 TCP 端口（发送端，热点创建之后调用）
+    ///
+    /// 实现可以同时把客户端限制在这个端口上，热点关闭时解除。默认认为没有防火墙
+    async fn allow_port(&self, _port: u16) -> PortAccess {
+        PortAccess::Allowed
+    }
+
+    /// 监听接入热点的客户端（发送端）
+    ///
+    /// 返回 `None` 表示后端无法检测客户端接入
+    fn watch_stations(&self) -> Option<mpsc::Receiver<StationInfo>> {
+        None
+    }
+
+    /// 连接到对方热点（接收端），返回分配到的本机 IP
+    async fn connect(&self, info: &P2pInfo) -> anyhow::Result<String>;
+
+    /// 接入热点后发送端的 IP（接收端），`local_ip` 为 [`Self::connect`] 的返回值
+    ///
+    /// 默认取本机网段的 `.1`
+    async fn sender_ip(&self, _info: &P2pInfo, local_ip: &str) -> String {
+        sender_addr::guess_gateway(local_ip)
+    }
+
+    /// 发送端的所有候选 IP（接收端），首选地址在前；首选地址不可达时依次尝试其余地址
+    ///
+    /// 默认只有 [`Self::sender_ip`]
+    async fn sender_candidates(&self, info: &P2pInfo, local_ip: &str) -> Vec<String> {
+        vec![self.sender_ip(info, local_ip).await]
+    }
+
+    /// 断开热点连接并清理
+    async fn disconnect(&self) -> anyhow::Result<()>;
+
+    /// 当前链路上的本机 IP（已连接时为客户端 IP，否则为热点 IP）
+    async fn get_ip(&self) -> anyhow::Result<String>;
+
+    /// 连接热点后原有 WiFi 是否仍然保持
+    async fn is_dual_connected(&self) -> bool {
+        false
+    }
+
+    /// 网卡能否建立或接入 WPA3-SAE 热点（接收端据此设置广播中的能力位）
+    fn supports_wpa3(&self) -> bool {
+        false
+    }
+}
+
+/// 基于 NetworkManager / wpa_supplicant 的 Linux 实现
+pub struct LinuxWifiBackend {
+    sender: WiFiP2pSender,
+    receiver: Mutex<WiFiP2pReceiver>,
+    /// 热点存在期间的 `catshare.local` 广播
+    host: Mutex<Option<HostAdvertisement>>,
+    /// 热点存在期间的客户端隔离规则
+    isolation: Mutex<Option<HotspotIsolation>>,
+    /// 创建热点之前已有的客户端，见 [`StationSnapshot`]
+    stations_before: std::sync::Mutex<StationSnapshot>,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl LinuxWifiBackend {
+    pub fn new(interface: &str) -> Self {
+        Self {
+            sender: WiFiP2pSender::new(interface),
+            receiver: Mutex::new(WiFiP2pReceiver::new(interface)),
+            host: Mutex::new(None),
+            isolation: Mutex::new(None),
+            stations_before: Default::default(),
+            runner: helper::default_runner(),
+        }
+    }
+
+    /// 使用指定的热点配置
+    pub fn with_config(config: P2pConfig) -> Self {
+        let receiver = WiFiP2pReceiver::new(&config.interface);
+        Self {
+            sender: WiFiP2pSender::with_config(config),
+            receiver: Mutex::new(receiver),
+            host: Mutex::new(None),
+            isolation: Mutex::new(None),
+            stations_before: Default::default(),
+            runner: helper::default_runner(),
+        }
+    }
+
+    /// 替换外部命令执行器（同时用于热点、连接和隔离规则）
+    pub fn with_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.sender = self.sender.with_runner(runner.clone());
+        self.receiver = Mutex::new(self.receiver.into_inner().with_runner(runner.clone()));
+        self.runner = runner;
+        self
+    }
+
+    /// 在热点上发布 `catshare.local`，失败只记日志（接收端会回退到地址推断）
+    fn advertise_host(&self, port: i32) -> Option<HostAdvertisement> {
+        let ip = self.sender.get_hotspot_ip().ok()?.parse().ok()?;
+        host::advertise_sender(ip, port as u16)
+            .inspect_err(|e| warn!("Failed to advertise sender hostname: {}", e))
+            .ok()
+    }
+}
+
+#[async_trait]
+impl WifiBackend for LinuxWifiBackend {
+    fn name(&self) -> &'static str {
+        "networkmanager"
+    }
+
+    async fn create_hotspot(
+        &self,
+        port: i32,
+        credentials: &CredentialPolicy,
+    ) -> anyhow::Result<P2pInfo> {
+        *self.stations_before.lock().unwrap() = StationSnapshot::take();
+        let info = self.sender.create_group(port, credentials).await?;
+        *self.host.lock().await = self.advertise_host(port);
+        Ok(info)
+    }
+
+    async fn stop_hotspot(&self) -> anyhow::Result<()> {
+        self.host.lock().await.take();
+        self.isolation.lock().await.take();
+        self.sender.stop_group().await
+    }
+
+    async fn prepare_hotspot(&self, credentials: &CredentialPolicy) -> anyhow::Result<()> {
+        self.sender.prepare_group(credentials).await
+    }
+
+    async fn busy_interface(&self) -> Option<InterfaceBusy> {
+        self.sender
+            .busy_connection()
+            .await
+            .inspect_err(|e| debug!("Failed to check the hotspot interface: {}", e))
+            .ok()
+            .flatten()
+    }
+
+    fn use_hotspot_interface(&self, interface: &str) -> anyhow::Result<()> {
+        self.sender.use_interface(interface);
+        Ok(())
+    }
+
+    async fn restore_after_hotspot(&self, busy: &InterfaceBusy) -> anyhow::Result<()> {
+        self.sender.restore_after(busy).await
+    }
+
+    async fn allow_port(&self, port: u16) -> PortAccess {
+        let access = firewall::allow_port(&self.sender.interface(), port).await;
+
+        // 先删除旧规则再建新规则：两者共用同一张 nftables 表
+        let mut isolation = self.isolation.lock().await;
+        isolation.take();
+        let interface = self.sender.hotspot_interface();
+        *isolation = firewall::isolate_hotspot(self.runner.clone(), &interface, port)
+            .inspect_err(|e| warn!("Hotspot clients are not isolated: {}", e))
+            .ok();
+
+        access
+    }
+
+    fn watch_stations(&self) -> Option<mpsc::Receiver<StationInfo>> {
+        let snapshot = self.stations_before.lock().unwrap().clone();
+        Some(spawn_station_monitor(self.sender.interface(), snapshot))
+    }
+
+    async fn connect(&self, info: &P2pInfo) -> anyhow::Result<String> {
+        self.receiver.lock().await.connect(info).await
+    }
+
+    async fn sender_ip(&self, info: &P2pInfo, local_ip: &str) -> String {
+        self.receiver.lock().await.sender_ip(info, local_ip).await
+    }
+
+    async fn sender_candidates(&self, info: &P2pInfo, local_ip: &str) -> Vec<String> {
+        self.receiver
+            .lock()
+            .await
+            .sender_candidates(info, local_ip)
+            .await
+    }
+
+    async fn disconnect(&self) -> anyhow::Result<()> {
+        self.receiver.lock().await.disconnect().await
+    }
+
+    async fn get_ip(&self) -> anyhow::Result<String> {
+        let receiver = self.receiver.lock().await;
+        if receiver.is_connected().await {
+            receiver.get_interface_ip(receiver.active_interface())
+        } else {
+            self.sender.get_hotspot_ip()
+        }
+    }
+
+    async fn is_dual_connected(&self) -> bool {
+        self.receiver.lock().await.is_dual_connected().await
+    }
+
+    fn supports_wpa3(&self) -> bool {
+        self.sender.supports_wpa3()
+    }
+}
@@ -0,0 +1,57 @@
+//! 热点接口被占用
+//!
+//! 单网卡在 NetworkManager 中建立 AP 模式热点时会断开该网卡上现有的 WiFi 连接。
+//! 发送端在创建热点前检查（[`WifiBackend::busy_interface`](crate::wifi::WifiBackend::busy_interface)），
+//! 通过 [`SendProgressCallback::on_interface_busy`](crate::SendProgressCallback::on_interface_busy)
+//! 告知用户，并按 [`BusyInterfacePolicy`] 处理。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 热点接口上会被断开的连接
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceBusy {
+    /// 热点接口
+    pub interface: String,
+    /// NetworkManager 连接名
+    pub connection: String,
+    /// 连接的 WiFi 网络，读取不到时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssid: Option<String>,
+    /// 可以代替使用的空闲网卡
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alternative: Option<String>,
+}
+
+impl InterfaceBusy {
+    /// 显示给用户的网络名称
+    pub fn network(&self) -> &str {
+        self.ssid.as_deref().unwrap_or(&self.connection)
+    }
+}
+
+impl fmt::Display for InterfaceBusy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is connected to {}; creating a hotspot on it will disconnect it",
+            self.interface,
+            self.network()
+        )
+    }
+}
+
+impl std::error::Error for InterfaceBusy {}
+
+/// 热点接口被占用时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusyInterfacePolicy {
+    /// 有空闲网卡时在空闲网卡上创建热点，没有时同 `RestoreAfter`
+    #[default]
+    SecondAdapter,
+    /// 照常创建热点，热点关闭后重新连接原来的网络
+    RestoreAfter,
+    /// 不创建热点，发送失败并返回 [`InterfaceBusy`]
+    Abort,
+}
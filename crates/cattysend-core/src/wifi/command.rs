@@ -0,0 +1,185 @@
+//! 外部命令抽象
+//!
+//! 热点和连接的备用路径依赖 `nmcli`、`wpa_cli`、`ip` 等命令。[`WiFiP2pSender`] 和
+//! [`WiFiP2pReceiver`] 通过 [`CommandRunner`] 调用它们，默认为 [`SystemRunner`]
+//! （没有特权时为 [`HelperRunner`]，见 [`helper::default_runner`]）；测试中注入 [`FakeRunner`] 即可模拟不同硬件上的命令输出，不需要真实网卡。
+//!
+//! [`WiFiP2pSender`]: crate::wifi::WiFiP2pSender
+//! [`WiFiP2pReceiver`]: crate::wifi::WiFiP2pReceiver
+//! [`HelperRunner`]: crate::wifi::helper::HelperRunner
+//! [`helper::default_runner`]: crate::wifi::helper::default_runner
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+
+/// 命令执行结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// 退出码是否为 0
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// 外部命令执行器
+pub trait CommandRunner: Send + Sync {
+    /// 运行 `program args...` 并等待结束；程序不存在等启动失败时返回 `Err`
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// 直接执行系统命令
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = std::process::Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// 返回预置输出的执行器（测试用）
+///
+/// 以命令行前缀匹配预置输出（最长前缀优先），没有匹配时按"命令不存在"处理。
+/// 每次调用的完整命令行都会被记录，可用 [`Self::calls`] 检查。
+#[derive(Debug, Default)]
+pub struct FakeRunner {
+    outputs: HashMap<String, CommandOutput>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl FakeRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以 `command` 开头的命令成功并输出 `stdout`
+    pub fn with_output(mut self, command: &str, stdout: &str) -> Self {
+        self.outputs.insert(
+            command.to_string(),
+            CommandOutput {
+                success: true,
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            },
+        );
+        self
+    }
+
+    /// 以 `command` 开头的命令失败并输出 `stderr`
+    pub fn with_failure(mut self, command: &str, stderr: &str) -> Self {
+        self.outputs.insert(
+            command.to_string(),
+            CommandOutput {
+                success: false,
+                stdout: String::new(),
+                stderr: stderr.to_string(),
+            },
+        );
+        self
+    }
+
+    /// 单接口网卡：`wlan0` 直接切换到 AP 模式（NetworkManager 共享热点）
+    pub fn single_interface() -> Self {
+        Self::new()
+            .with_output("ip -o addr show wlan0", fixtures::IP_ADDR_WLAN0_AP)
+            .with_output("ip -o addr show", fixtures::IP_ADDR_SINGLE_INTERFACE)
+            .with_output("nmcli", "")
+            .with_failure("wpa_cli", fixtures::WPA_CLI_NO_P2P)
+    }
+
+    /// 多接口网卡：`wlan0` 保持原有连接，P2P 组在独立的 `p2p-wlan0-0` 上（wpa_cli）
+    pub fn multi_interface() -> Self {
+        Self::new()
+            .with_output("ip -o addr show wlan0", fixtures::IP_ADDR_WLAN0_STATION)
+            .with_output("ip -o addr show", fixtures::IP_ADDR_MULTI_INTERFACE)
+            .with_output("nmcli", "")
+            .with_output("wpa_cli", "OK\n")
+    }
+
+    /// 已执行的命令行
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl CommandRunner for FakeRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let line = std::iter::once(program)
+            .chain(args.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.calls.lock().unwrap().push(line.clone());
+        self.outputs
+            .iter()
+            .filter(|(prefix, _)| line.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, output)| output.clone())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, program.to_string()))
+    }
+}
+
+/// 从 `ip -o addr show` 的输出中取 `interface` 的第一个 IPv4 地址
+pub(crate) fn interface_ipv4(output: &str, interface: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [_, iface, "inet", cidr, ..] if iface == interface => {
+                cidr.split('/').next().map(str::to_string)
+            }
+            _ => None,
+        }
+    })
+}
+
+/// 从 `ip -o addr show` 的输出中找地址为 `ip` 的接口，返回接口名和网络前缀长度
+pub(crate) fn interface_with_ipv4(output: &str, ip: Ipv4Addr) -> Option<(String, u8)> {
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [_, iface, "inet", cidr, ..] = fields[..] else {
+            return None;
+        };
+        let (addr, prefix) = cidr.split_once('/')?;
+        if addr.parse::<Ipv4Addr>().ok()? != ip {
+            return None;
+        }
+        Some((iface.to_string(), prefix.parse().ok()?))
+    })
+}
+
+/// 预置的命令输出
+pub mod fixtures {
+    /// 单接口：`wlan0` 为 NetworkManager 热点
+    pub const IP_ADDR_SINGLE_INTERFACE: &str = "\
+1: lo    inet 127.0.0.1/8 scope host lo\\       valid_lft forever preferred_lft forever
+3: wlan0    inet 10.42.0.1/24 brd 10.42.0.255 scope global noprefixroute wlan0\\       valid_lft forever preferred_lft forever
+";
+
+    /// 单接口：`ip -o addr show wlan0`
+    pub const IP_ADDR_WLAN0_AP: &str = "\
+3: wlan0    inet 10.42.0.1/24 brd 10.42.0.255 scope global noprefixroute wlan0\\       valid_lft forever preferred_lft forever
+";
+
+    /// 多接口：有线网、保持连接的 `wlan0` 和 P2P 组接口
+    pub const IP_ADDR_MULTI_INTERFACE: &str = "\
+1: lo    inet 127.0.0.1/8 scope host lo\\       valid_lft forever preferred_lft forever
+2: eth0    inet 192.168.1.10/24 brd 192.168.1.255 scope global dynamic eth0\\       valid_lft 86000sec preferred_lft 86000sec
+3: wlan0    inet 192.168.1.23/24 brd 192.168.1.255 scope global dynamic wlan0\\       valid_lft 86000sec preferred_lft 86000sec
+7: p2p-wlan0-0    inet 192.168.49.1/24 brd 192.168.49.255 scope global p2p-wlan0-0\\       valid_lft forever preferred_lft forever
+";
+
+    /// 多接口：`ip -o addr show wlan0`
+    pub const IP_ADDR_WLAN0_STATION: &str = "\
+3: wlan0    inet 192.168.1.23/24 brd 192.168.1.255 scope global dynamic wlan0\\       valid_lft 86000sec preferred_lft 86000sec
+";
+
+    /// 不支持 P2P 的 wpa_supplicant
+    pub const WPA_CLI_NO_P2P: &str = "FAIL\n";
+}
@@ -0,0 +1,265 @@
+//! 热点凭据策略
+//!
+//! 默认与 CatShare 相同：SSID 为前缀加 8 位小写字母数字，口令为 8 位小写字母数字，WPA2-PSK。
+//! 可以在设置中加长口令、扩大字符集或启用 WPA3-SAE：
+//!
+//! - 口令长度在 WPA2 口令规则（8-63 个字符）之内，对所有接收端都适用
+//! - 标点符号只交给 cattysend 接收端；CatShare 只用过字母数字口令，其他字符未经验证
+//! - WPA3-SAE 要求接收端能接入 SAE 热点，见 [`CAPABILITY_WPA3`](crate::ble::CAPABILITY_WPA3)
+//!
+//! 对端不满足时由 [`CredentialPolicy::for_peer`] 降级，不会因为设置而无法连接 CatShare 设备。
+//! 本机网卡是否支持 WPA3 由 [`probe_wpa3`] 检测，不支持时同样退回 WPA2-PSK。
+
+use crate::ble::DiscoveredDevice;
+use crate::wifi::command::CommandRunner;
+use serde::{Deserialize, Serialize};
+
+/// SSID 随机部分的长度
+const SSID_SUFFIX_LEN: usize = 8;
+
+/// 小写字母和数字
+const LOWERCASE: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// 大小写字母和数字
+const ALPHANUMERIC: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// 字母数字加标点（不含空白、引号和反斜杠，口令要经过 wpa_cli 参数和配置文件）
+const SYMBOLS: &[u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+/// 口令字符集
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PskCharset {
+    /// 小写字母和数字（与 CatShare 相同）
+    #[default]
+    Lowercase,
+    /// 大小写字母和数字
+    Alphanumeric,
+    /// 字母数字加标点，仅用于 cattysend 接收端
+    Symbols,
+}
+
+impl PskCharset {
+    fn chars(self) -> &'static [u8] {
+        match self {
+            PskCharset::Lowercase => LOWERCASE,
+            PskCharset::Alphanumeric => ALPHANUMERIC,
+            PskCharset::Symbols => SYMBOLS,
+        }
+    }
+}
+
+/// 热点的认证方式
+///
+/// 写在 P2pInfo 的 `security` 字段中；WPA2-PSK 时省略该字段，与 CatShare 的格式相同。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotspotSecurity {
+    #[default]
+    Wpa2Psk,
+    Wpa3Sae,
+}
+
+impl HotspotSecurity {
+    /// NetworkManager 连接配置中的 `key-mgmt`
+    pub fn key_mgmt(self) -> &'static str {
+        match self {
+            HotspotSecurity::Wpa2Psk => "wpa-psk",
+            HotspotSecurity::Wpa3Sae => "sae",
+        }
+    }
+}
+
+/// 对端对热点凭据的支持情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PeerSupport {
+    /// 对端是 cattysend，接受任意合法的 WPA2 口令
+    pub cattysend: bool,
+    /// 对端能接入 WPA3-SAE 热点
+    pub wpa3: bool,
+}
+
+impl PeerSupport {
+    /// CatShare 设备（或无法确认的对端）
+    pub const CATSHARE: PeerSupport = PeerSupport {
+        cattysend: false,
+        wpa3: false,
+    };
+
+    /// 扫描到的接收端：广播中的 WPA3 位只有 cattysend 会设置，局域网发现的也只有 cattysend
+    pub fn of_device(device: &DiscoveredDevice) -> Self {
+        Self {
+            cattysend: device.supports_wpa3 || device.lan_endpoint.is_some(),
+            wpa3: device.supports_wpa3,
+        }
+    }
+}
+
+/// 热点凭据策略
+///
+/// 配置文件中写作 `hotspot_credentials = { psk_length = 16, charset = "alphanumeric", wpa3 = true }`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CredentialPolicy {
+    /// 口令长度，超出 8-63 时取最近的边界
+    pub psk_length: usize,
+    /// 口令字符集
+    pub charset: PskCharset,
+    /// 对端支持时使用 WPA3-SAE
+    pub wpa3: bool,
+    /// 只建 2.4GHz 热点（按对端的兼容性表设置，不写入配置文件），见 [`crate::quirks`]
+    #[serde(skip)]
+    pub only_2ghz: bool,
+}
+
+impl Default for CredentialPolicy {
+    fn default() -> Self {
+        Self {
+            psk_length: Self::MIN_PSK_LEN,
+            charset: PskCharset::default(),
+            wpa3: false,
+            only_2ghz: false,
+        }
+    }
+}
+
+impl CredentialPolicy {
+    /// WPA2 口令的最短长度
+    pub const MIN_PSK_LEN: usize = 8;
+    /// WPA2 口令的最长长度
+    pub const MAX_PSK_LEN: usize = 63;
+
+    /// 对 `peer` 实际使用的策略：对端不支持的选项降级为 CatShare 兼容的取值
+    pub fn for_peer(&self, peer: PeerSupport) -> Self {
+        let charset = match self.charset {
+            PskCharset::Symbols if !peer.cattysend => PskCharset::Alphanumeric,
+            charset => charset,
+        };
+        Self {
+            psk_length: self.psk_length.clamp(Self::MIN_PSK_LEN, Self::MAX_PSK_LEN),
+            charset,
+            wpa3: self.wpa3 && peer.wpa3,
+            only_2ghz: self.only_2ghz,
+        }
+    }
+
+    /// 热点的认证方式
+    pub fn security(&self) -> HotspotSecurity {
+        if self.wpa3 {
+            HotspotSecurity::Wpa3Sae
+        } else {
+            HotspotSecurity::Wpa2Psk
+        }
+    }
+
+    /// 生成随机 SSID 和口令
+    pub fn generate(&self, ssid_prefix: &str) -> (String, String) {
+        let ssid = format!(
+            "{}{}",
+            ssid_prefix,
+            random_string(LOWERCASE, SSID_SUFFIX_LEN)
+        );
+        let length = self.psk_length.clamp(Self::MIN_PSK_LEN, Self::MAX_PSK_LEN);
+        (ssid, random_string(self.charset.chars(), length))
+    }
+}
+
+/// 网卡能否建立或接入 WPA3-Personal 网络
+///
+/// 通过 `wpa_cli get_capability` 查询：需要 SAE 密钥管理，以及 PMF 所需的 BIP-CMAC-128。
+/// wpa_supplicant 未运行或命令失败时视为不支持。
+pub fn probe_wpa3(runner: &dyn CommandRunner, interface: &str) -> bool {
+    let capability = |field: &str| {
+        runner
+            .run("wpa_cli", &["-i", interface, "get_capability", field])
+            .ok()
+            .filter(|output| output.success)
+            .map(|output| output.stdout)
+            .unwrap_or_default()
+    };
+    has_capability(&capability("key_mgmt"), "SAE")
+        && has_capability(&capability("group_mgmt"), "AES-128-CMAC")
+}
+
+/// `get_capability` 的输出是空格分隔的列表，失败时为 `FAIL`
+fn has_capability(output: &str, name: &str) -> bool {
+    output.split_whitespace().any(|c| c == name)
+}
+
+fn random_string(chars: &[u8], len: usize) -> String {
+    (0..len)
+        .map(|_| chars[rand::random::<usize>() % chars.len()] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wifi::wpa;
+
+    #[test]
+    fn test_default_matches_catshare() {
+        let (ssid, psk) = CredentialPolicy::default().generate("DIRECT-");
+        assert_eq!(ssid.len(), 15);
+        assert_eq!(psk.len(), 8);
+        assert!(psk.bytes().all(|b| LOWERCASE.contains(&b)));
+    }
+
+    #[test]
+    fn test_length_is_clamped() {
+        let policy = CredentialPolicy {
+            psk_length: 100,
+            ..Default::default()
+        };
+        assert_eq!(policy.generate("DIRECT-").1.len(), 63);
+
+        let policy = CredentialPolicy {
+            psk_length: 4,
+            ..Default::default()
+        };
+        assert_eq!(policy.generate("DIRECT-").1.len(), 8);
+    }
+
+    #[test]
+    fn test_symbols_are_safe_for_wpa_cli() {
+        let policy = CredentialPolicy {
+            psk_length: 63,
+            charset: PskCharset::Symbols,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            let (ssid, psk) = policy.generate("DIRECT-");
+            assert!(wpa::group_add_arg(&ssid, &psk).is_ok(), "{}", psk);
+        }
+    }
+
+    #[test]
+    fn test_stronger_options_gated_by_peer() {
+        let policy = CredentialPolicy {
+            psk_length: 20,
+            charset: PskCharset::Symbols,
+            wpa3: true,
+            ..Default::default()
+        };
+
+        let catshare = policy.for_peer(PeerSupport::CATSHARE);
+        assert_eq!(catshare.psk_length, 20);
+        assert_eq!(catshare.charset, PskCharset::Alphanumeric);
+        assert_eq!(catshare.security(), HotspotSecurity::Wpa2Psk);
+
+        let peer = PeerSupport {
+            cattysend: true,
+            wpa3: false,
+        };
+        assert_eq!(policy.for_peer(peer).charset, PskCharset::Symbols);
+        assert!(!policy.for_peer(peer).wpa3);
+
+        let peer = PeerSupport {
+            cattysend: true,
+            wpa3: true,
+        };
+        assert_eq!(policy.for_peer(peer), policy);
+        assert_eq!(policy.for_peer(peer).security(), HotspotSecurity::Wpa3Sae);
+    }
+}
@@ -0,0 +1,695 @@
+//! 特权助手（`cattysend-helper`）
+//!
+//! 少数网络操作需要 `CAP_NET_ADMIN`：用 wpa_cli 创建/删除 P2P 组（双连接时的独立
+//! P2P 接口），以及用 nft 隔离热点客户端。这些命令交给一个带 capability（或 setuid root）
+//! 的小程序执行，守护进程、TUI 和 GUI 本身可以完全不带特权运行。
+//!
+//! # 协议
+//!
+//! 每次操作启动一次 helper：标准输入写入一行 JSON 的 [`HelperRequest`]，
+//! 标准输出读回一行 JSON 的 [`CommandOutput`]。helper 只接受 [`HelperRequest`]
+//! 列出的几种操作，参数经过 [`HelperRequest::validate`] 校验后由 helper 自己拼出命令行，
+//! 调用方无法借它执行任意命令。
+//!
+//! # 授权
+//!
+//! helper 带着 `CAP_NET_ADMIN`，所以不能谁来都执行：
+//!
+//! - 调用方必须是 root 或 [`HELPER_GROUP`] 组的成员（[`authorize_caller`]），
+//!   `cargo xtask install` 同时把 helper 装成 `root:cattysend 0750`，组外用户连执行都不行；
+//! - 操作的接口必须是 cattysend 会用到的接口（[`HelperRequest::authorize`]）：
+//!   建组/删组只能在无线网卡或 `p2p-*` 接口上，隔离规则只能加在 `p2p-*` 接口或
+//!   NetworkManager 当前作为热点（AP 模式）的接口上。
+//!
+//! 客户端一侧是 [`HelperRunner`]：识别出特权命令时转给 helper，其他命令（`ip`、`nmcli`）
+//! 照常直接执行。[`default_runner`] 在进程自身没有 `CAP_NET_ADMIN` 且找到 helper 时使用它。
+
+use crate::diagnostics::{self, CAP_NET_ADMIN};
+use crate::firewall::{self, ISOLATION_TABLE};
+use crate::wifi::command::{CommandOutput, CommandRunner, SystemRunner};
+use crate::wifi::wpa;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::Arc;
+
+/// helper 的可执行文件名
+pub const HELPER_BIN: &str = "cattysend-helper";
+
+/// 覆盖 helper 路径的环境变量
+pub const HELPER_ENV: &str = "CATTYSEND_HELPER";
+
+/// helper 执行命令时使用的 PATH（不继承调用方的环境）
+const SAFE_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// 允许调用 helper 的用户组
+pub const HELPER_GROUP: &str = "cattysend";
+
+/// 网络接口名的最大长度（IFNAMSIZ - 1）
+const MAX_INTERFACE_LEN: usize = 15;
+
+/// P2P 组接口（`p2p-wlan0-0`）和 P2P 设备接口（`p2p-dev-wlan0`）的前缀
+const P2P_INTERFACE_PREFIX: &str = "p2p-";
+
+/// 网络接口的 sysfs 目录
+const SYSFS_NET: &str = "/sys/class/net";
+
+/// helper 接受的操作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HelperRequest {
+    /// `wpa_cli -i <interface> p2p_group_add "persistent ssid=<ssid> passphrase=<passphrase>"`
+    P2pGroupAdd {
+        interface: String,
+        ssid: String,
+        passphrase: String,
+    },
+    /// `wpa_cli -i <interface> p2p_group_remove *`
+    P2pGroupRemove { interface: String },
+    /// `nft <隔离规则>`，规则见 [`firewall::isolate_hotspot`]
+    IsolateHotspot { interface: String, port: u16 },
+    /// `nft delete table inet cattysend_hotspot`
+    RemoveIsolation,
+}
+
+impl HelperRequest {
+    /// 识别需要特权的命令行，其他命令返回 None
+    ///
+    /// 与 [`Self::command`] 互逆：只有 helper 能原样重建的命令才会被识别。
+    pub fn from_command(program: &str, args: &[&str]) -> Option<Self> {
+        let request = match (program, args) {
+            ("wpa_cli", ["-i", interface, "p2p_group_add", spec]) => {
+                let (ssid, passphrase) = wpa::parse_group_add_arg(spec)?;
+                Self::P2pGroupAdd {
+                    interface: interface.to_string(),
+                    ssid: ssid.to_string(),
+                    passphrase: passphrase.to_string(),
+                }
+            }
+            ("wpa_cli", ["-i", interface, "p2p_group_remove", "*"]) => Self::P2pGroupRemove {
+                interface: interface.to_string(),
+            },
+            ("nft", ["delete", "table", "inet", table]) if *table == ISOLATION_TABLE => {
+                Self::RemoveIsolation
+            }
+            ("nft", [script]) => {
+                let (_, rest) = script.split_once("iifname \"")?;
+                let (interface, _) = rest.split_once('"')?;
+                let (_, rest) = script.split_once(" tcp dport ")?;
+                let port = rest.split_whitespace().next()?.parse().ok()?;
+                if firewall::isolation_ruleset(interface, port) != *script {
+                    return None;
+                }
+                Self::IsolateHotspot {
+                    interface: interface.to_string(),
+                    port,
+                }
+            }
+            _ => return None,
+        };
+        let (rebuilt_program, rebuilt_args) = request.command();
+        (rebuilt_program == program && rebuilt_args == args).then_some(request)
+    }
+
+    /// 校验参数，helper 执行前调用
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::P2pGroupAdd {
+                interface,
+                ssid,
+                passphrase,
+            } => {
+                validate_interface(interface)?;
+                wpa::group_add_arg(ssid, passphrase).map_err(|e| e.to_string())?;
+                Ok(())
+            }
+            Self::P2pGroupRemove { interface } => validate_interface(interface),
+            Self::IsolateHotspot { interface, port } => {
+                validate_interface(interface)?;
+                if *port == 0 {
+                    return Err("invalid port: 0".to_string());
+                }
+                Ok(())
+            }
+            Self::RemoveIsolation => Ok(()),
+        }
+    }
+
+    /// 检查请求操作的接口是否属于 cattysend，helper 在 [`Self::validate`] 之后调用
+    ///
+    /// `sysfs` 是 `/sys/class/net`，`runner` 用来向 NetworkManager 查询接口的模式。
+    pub fn authorize(&self, runner: &dyn CommandRunner, sysfs: &Path) -> Result<(), String> {
+        match self {
+            Self::P2pGroupAdd { interface, .. } | Self::P2pGroupRemove { interface } => {
+                if is_p2p_interface(interface) || is_wireless(sysfs, interface) {
+                    Ok(())
+                } else {
+                    Err(format!("not a wireless interface: {}", interface))
+                }
+            }
+            Self::IsolateHotspot { interface, .. } => {
+                if is_p2p_interface(interface) || is_access_point(runner, interface) {
+                    Ok(())
+                } else {
+                    Err(format!("not a hotspot interface: {}", interface))
+                }
+            }
+            Self::RemoveIsolation => Ok(()),
+        }
+    }
+
+    /// 对应的命令行
+    pub fn command(&self) -> (&'static str, Vec<String>) {
+        match self {
+            Self::P2pGroupAdd {
+                interface,
+                ssid,
+                passphrase,
+            } => (
+                "wpa_cli",
+                vec![
+                    "-i".to_string(),
+                    interface.clone(),
+                    "p2p_group_add".to_string(),
+                    format!("persistent ssid={} passphrase={}", ssid, passphrase),
+                ],
+            ),
+            Self::P2pGroupRemove { interface } => (
+                "wpa_cli",
+                vec![
+                    "-i".to_string(),
+                    interface.clone(),
+                    "p2p_group_remove".to_string(),
+                    "*".to_string(),
+                ],
+            ),
+            Self::IsolateHotspot { interface, port } => {
+                ("nft", vec![firewall::isolation_ruleset(interface, *port)])
+            }
+            Self::RemoveIsolation => (
+                "nft",
+                ["delete", "table", "inet", ISOLATION_TABLE]
+                    .map(str::to_string)
+                    .to_vec(),
+            ),
+        }
+    }
+}
+
+fn validate_interface(interface: &str) -> Result<(), String> {
+    let valid = !interface.is_empty()
+        && interface.len() <= MAX_INTERFACE_LEN
+        && !interface.starts_with('-')
+        && interface
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid interface name: {:?}", interface))
+    }
+}
+
+fn is_p2p_interface(interface: &str) -> bool {
+    interface.starts_with(P2P_INTERFACE_PREFIX)
+}
+
+/// 接口是否是无线网卡（sysfs 下有 `wireless` 或 `phy80211`）
+fn is_wireless(sysfs: &Path, interface: &str) -> bool {
+    let dir = sysfs.join(interface);
+    dir.join("wireless").exists() || dir.join("phy80211").exists()
+}
+
+/// 接口当前激活的 NetworkManager 连接是否是 AP 模式（热点）
+fn is_access_point(runner: &dyn CommandRunner, interface: &str) -> bool {
+    let query = |args: &[&str]| {
+        runner
+            .run("nmcli", args)
+            .ok()
+            .filter(|output| output.success)
+            .map(|output| output.stdout.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let Some(connection) = query(&[
+        "-e",
+        "no",
+        "-g",
+        "GENERAL.CONNECTION",
+        "device",
+        "show",
+        interface,
+    ]) else {
+        return false;
+    };
+    query(&[
+        "-e",
+        "no",
+        "-g",
+        "802-11-wireless.mode",
+        "connection",
+        "show",
+        "id",
+        &connection,
+    ])
+    .is_some_and(|mode| mode == "ap")
+}
+
+/// 检查调用方：只有 root 和 [`HELPER_GROUP`] 组的成员可以使用 helper
+///
+/// 看的是真实 uid/gid，setuid 或文件 capability 都不会改变它们。
+pub fn authorize_caller() -> Result<(), String> {
+    // SAFETY: getuid/getgid 总是成功
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let helper_gid = group_id(HELPER_GROUP);
+    let mut groups = supplementary_groups().map_err(|e| format!("getgroups: {}", e))?;
+    groups.push(gid);
+    if caller_allowed(uid, &groups, helper_gid) {
+        return Ok(());
+    }
+    match helper_gid {
+        Some(_) => Err(format!("uid {} is not in the {} group", uid, HELPER_GROUP)),
+        None => Err(format!("group {} does not exist", HELPER_GROUP)),
+    }
+}
+
+fn caller_allowed(
+    uid: libc::uid_t,
+    groups: &[libc::gid_t],
+    helper_gid: Option<libc::gid_t>,
+) -> bool {
+    uid == 0 || helper_gid.is_some_and(|helper_gid| groups.contains(&helper_gid))
+}
+
+fn group_id(name: &str) -> Option<libc::gid_t> {
+    let name = std::ffi::CString::new(name).ok()?;
+    // SAFETY: name 以 NUL 结尾；返回的结构在下一次 getgr* 调用前有效，这里立即读出 gid
+    let group = unsafe { libc::getgrnam(name.as_ptr()) };
+    if group.is_null() {
+        return None;
+    }
+    // SAFETY: 上面已检查非空
+    Some(unsafe { (*group).gr_gid })
+}
+
+fn supplementary_groups() -> io::Result<Vec<libc::gid_t>> {
+    // SAFETY: size 为 0 时只返回附加组的数量
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut groups = vec![0; count as usize];
+    // SAFETY: groups 有 count 个元素
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    if count < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    groups.truncate(count as usize);
+    Ok(groups)
+}
+
+/// 通过 helper 执行特权命令的执行器，其他命令直接执行
+#[derive(Debug, Clone)]
+pub struct HelperRunner {
+    helper: PathBuf,
+}
+
+impl HelperRunner {
+    pub fn new(helper: impl Into<PathBuf>) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+
+    /// helper 的路径
+    pub fn helper(&self) -> &Path {
+        &self.helper
+    }
+
+    fn call(&self, request: &HelperRequest) -> io::Result<CommandOutput> {
+        let mut child = Command::new(&self.helper)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let line = serde_json::to_string(request).map_err(io::Error::other)?;
+            writeln!(stdin, "{}", line)?;
+        }
+        let output = child.wait_with_output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        serde_json::from_str(stdout.trim()).map_err(|_| {
+            io::Error::other(format!(
+                "{} failed: {}",
+                HELPER_BIN,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        })
+    }
+}
+
+impl CommandRunner for HelperRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        match HelperRequest::from_command(program, args) {
+            Some(request) => self.call(&request),
+            None => SystemRunner.run(program, args),
+        }
+    }
+}
+
+/// 查找 helper：先看 `CATTYSEND_HELPER`，再看与当前程序同目录的 `cattysend-helper`
+pub fn locate() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(HELPER_ENV).filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let exe = std::env::current_exe().ok()?;
+    let path = exe.parent()?.join(HELPER_BIN);
+    path.is_file().then_some(path)
+}
+
+/// helper 是否带有特权（setuid root，或设置了文件 capability）
+pub fn is_privileged(path: &Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    if metadata.uid() == 0 && metadata.mode() & 0o4000 != 0 {
+        return true;
+    }
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_encoded_bytes()) else {
+        return false;
+    };
+    // SAFETY: 两个字符串都以 NUL 结尾；value 为空指针、size 为 0 时只查询属性长度
+    let len = unsafe {
+        libc::getxattr(
+            path.as_ptr(),
+            c"security.capability".as_ptr(),
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    len > 0
+}
+
+/// 进程默认使用的命令执行器
+///
+/// 进程自己有 `CAP_NET_ADMIN`（root 或 setcap）时直接执行；否则找到 helper 就经由 helper。
+pub fn default_runner() -> Arc<dyn CommandRunner> {
+    let has_net_admin =
+        diagnostics::effective_capabilities().is_some_and(|caps| caps & (1 << CAP_NET_ADMIN) != 0);
+    match locate() {
+        Some(helper) if !has_net_admin => Arc::new(HelperRunner::new(helper)),
+        _ => Arc::new(SystemRunner),
+    }
+}
+
+/// helper 的入口：从标准输入读一个请求，执行后把结果写到标准输出
+pub fn serve_stdio() -> ExitCode {
+    if let Err(message) = authorize_caller() {
+        eprintln!("{}: {}", HELPER_BIN, message);
+        let output = CommandOutput {
+            success: false,
+            stdout: String::new(),
+            stderr: message,
+        };
+        if let Ok(line) = serde_json::to_string(&output) {
+            let _ = writeln!(io::stdout(), "{}", line);
+        }
+        return ExitCode::FAILURE;
+    }
+    if let Err(e) = raise_ambient_net_admin() {
+        // setuid root 时子进程本来就有全部权限，这里失败不影响
+        eprintln!(
+            "{}: could not raise ambient CAP_NET_ADMIN: {}",
+            HELPER_BIN, e
+        );
+    }
+    let output = match serve(io::stdin().lock()) {
+        Ok(output) => output,
+        Err(message) => CommandOutput {
+            success: false,
+            stdout: String::new(),
+            stderr: message,
+        },
+    };
+    let Ok(line) = serde_json::to_string(&output) else {
+        return ExitCode::FAILURE;
+    };
+    if writeln!(io::stdout(), "{}", line).is_err() || !output.success {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+fn serve(mut input: impl BufRead) -> Result<CommandOutput, String> {
+    let mut line = String::new();
+    input
+        .read_line(&mut line)
+        .map_err(|e| format!("failed to read request: {}", e))?;
+    let request: HelperRequest =
+        serde_json::from_str(&line).map_err(|e| format!("invalid request: {}", e))?;
+    request.validate()?;
+    request.authorize(&SystemRunner, Path::new(SYSFS_NET))?;
+
+    let (program, args) = request.command();
+    let output = Command::new(program)
+        .args(&args)
+        .env_clear()
+        .env("PATH", SAFE_PATH)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    Ok(CommandOutput {
+        success: output.status.success(),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    })
+}
+
+/// 把 `CAP_NET_ADMIN` 放进 ambient 集合，让 helper 启动的 wpa_cli、nft 继承它
+///
+/// 文件 capability 只作用于 helper 自身，exec 出的子进程不会自动获得。
+fn raise_ambient_net_admin() -> io::Result<()> {
+    const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+    #[repr(C)]
+    struct Header {
+        version: u32,
+        pid: libc::c_int,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    struct Data {
+        effective: u32,
+        permitted: u32,
+        inheritable: u32,
+    }
+
+    let mut header = Header {
+        version: LINUX_CAPABILITY_VERSION_3,
+        pid: 0,
+    };
+    let mut data = [Data::default(); 2];
+    // SAFETY: version 3 要求两个 Data，header 和 data 都是有效的 repr(C) 结构
+    if unsafe { libc::syscall(libc::SYS_capget, &mut header, data.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if data[0].permitted & (1 << CAP_NET_ADMIN) == 0 {
+        return Err(io::Error::other("CAP_NET_ADMIN is not permitted"));
+    }
+    data[0].inheritable |= 1 << CAP_NET_ADMIN;
+    // SAFETY: 同上
+    if unsafe { libc::syscall(libc::SYS_capset, &mut header, data.as_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: PR_CAP_AMBIENT_RAISE 只读取整数参数
+    let raised = unsafe {
+        libc::prctl(
+            libc::PR_CAP_AMBIENT,
+            libc::PR_CAP_AMBIENT_RAISE as libc::c_ulong,
+            CAP_NET_ADMIN as libc::c_ulong,
+            0 as libc::c_ulong,
+            0 as libc::c_ulong,
+        )
+    };
+    if raised != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+    use crate::wifi::command::FakeRunner;
+
+    fn round_trip(program: &str, args: &[&str]) -> Option<HelperRequest> {
+        let request = HelperRequest::from_command(program, args)?;
+        let json = serde_json::to_string(&request).unwrap();
+        Some(serde_json::from_str(&json).unwrap())
+    }
+
+    #[test]
+    fn test_recognizes_privileged_commands() {
+        let add = round_trip(
+            "wpa_cli",
+            &[
+                "-i",
+                "wlan0",
+                "p2p_group_add",
+                "persistent ssid=DIRECT-ab12cd34 passphrase=x9y8z7w6",
+            ],
+        );
+        assert_eq!(
+            add,
+            Some(HelperRequest::P2pGroupAdd {
+                interface: "wlan0".to_string(),
+                ssid: "DIRECT-ab12cd34".to_string(),
+                passphrase: "x9y8z7w6".to_string(),
+            })
+        );
+        assert_eq!(
+            round_trip("wpa_cli", &["-i", "wlan0", "p2p_group_remove", "*"]),
+            Some(HelperRequest::P2pGroupRemove {
+                interface: "wlan0".to_string()
+            })
+        );
+
+        let script = firewall::isolation_ruleset("p2p-wlan0-0", 45678);
+        assert_eq!(
+            round_trip("nft", &[script.as_str()]),
+            Some(HelperRequest::IsolateHotspot {
+                interface: "p2p-wlan0-0".to_string(),
+                port: 45678,
+            })
+        );
+        assert_eq!(
+            round_trip("nft", &["delete", "table", "inet", ISOLATION_TABLE]),
+            Some(HelperRequest::RemoveIsolation)
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_commands() {
+        assert_eq!(
+            HelperRequest::from_command("ip", &["-o", "addr", "show"]),
+            None
+        );
+        assert_eq!(
+            HelperRequest::from_command("nmcli", &["connection", "delete", "x"]),
+            None
+        );
+        assert_eq!(HelperRequest::from_command("nft", &["flush ruleset"]), None);
+        assert_eq!(
+            HelperRequest::from_command("nft", &["delete", "table", "inet", "filter"]),
+            None
+        );
+        // 在隔离规则后面追加的内容不能混进去
+        let script = format!(
+            "{}\nflush ruleset",
+            firewall::isolation_ruleset("wlan0", 45678)
+        );
+        assert_eq!(HelperRequest::from_command("nft", &[script.as_str()]), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let add = |interface: &str, ssid: &str, passphrase: &str| HelperRequest::P2pGroupAdd {
+            interface: interface.to_string(),
+            ssid: ssid.to_string(),
+            passphrase: passphrase.to_string(),
+        };
+        assert!(
+            add("wlan0", "DIRECT-ab12cd34", "x9y8z7w6")
+                .validate()
+                .is_ok()
+        );
+        assert!(add("-h", "DIRECT-ab", "x9y8z7w6").validate().is_err());
+        assert!(
+            add("wlan0;reboot", "DIRECT-ab", "x9y8z7w6")
+                .validate()
+                .is_err()
+        );
+        assert!(
+            add("a-very-long-interface", "DIRECT-ab", "x9y8z7w6")
+                .validate()
+                .is_err()
+        );
+        assert!(add("wlan0", "DIRECT ab", "x9y8z7w6").validate().is_err());
+        assert!(add("wlan0", "DIRECT-ab", "short").validate().is_err());
+        assert!(add("wlan0", "DIRECT-ab", "x9y8\"z7w6").validate().is_err());
+
+        let isolate = HelperRequest::IsolateHotspot {
+            interface: "p2p-wlan0-0".to_string(),
+            port: 0,
+        };
+        assert!(isolate.validate().is_err());
+    }
+
+    #[test]
+    fn test_authorize_interfaces() {
+        let sysfs = temp_dir("helper-sysfs");
+        std::fs::create_dir_all(sysfs.join("wlan0/wireless")).unwrap();
+        std::fs::create_dir_all(sysfs.join("eth0")).unwrap();
+        let runner = FakeRunner::new()
+            .with_output(
+                "nmcli -e no -g GENERAL.CONNECTION device show wlan1",
+                "Hotspot\n",
+            )
+            .with_output(
+                "nmcli -e no -g 802-11-wireless.mode connection show id Hotspot",
+                "ap\n",
+            )
+            .with_output(
+                "nmcli -e no -g GENERAL.CONNECTION device show wlan0",
+                "Home\n",
+            )
+            .with_output(
+                "nmcli -e no -g 802-11-wireless.mode connection show id Home",
+                "infrastructure\n",
+            );
+        let remove = |interface: &str| HelperRequest::P2pGroupRemove {
+            interface: interface.to_string(),
+        };
+        let isolate = |interface: &str| HelperRequest::IsolateHotspot {
+            interface: interface.to_string(),
+            port: 45678,
+        };
+
+        assert!(remove("wlan0").authorize(&runner, &sysfs).is_ok());
+        assert!(remove("p2p-dev-wlan0").authorize(&runner, &sysfs).is_ok());
+        assert!(remove("eth0").authorize(&runner, &sysfs).is_err());
+        assert!(remove("lo").authorize(&runner, &sysfs).is_err());
+
+        assert!(isolate("p2p-wlan0-0").authorize(&runner, &sysfs).is_ok());
+        assert!(isolate("wlan1").authorize(&runner, &sysfs).is_ok());
+        // 连着普通 Wi-Fi 的网卡和有线网卡都不能被隔离
+        assert!(isolate("wlan0").authorize(&runner, &sysfs).is_err());
+        assert!(isolate("eth0").authorize(&runner, &sysfs).is_err());
+
+        assert!(
+            HelperRequest::RemoveIsolation
+                .authorize(&runner, &sysfs)
+                .is_ok()
+        );
+        let _ = std::fs::remove_dir_all(&sysfs);
+    }
+
+    #[test]
+    fn test_caller_allowed() {
+        assert!(caller_allowed(0, &[0], None));
+        assert!(caller_allowed(1000, &[1000, 970], Some(970)));
+        assert!(!caller_allowed(1000, &[1000, 27], Some(970)));
+        // 没有 cattysend 组时只有 root 可以用
+        assert!(!caller_allowed(1000, &[1000], None));
+    }
+
+    #[test]
+    fn test_serve_rejects_invalid_request() {
+        let line = r#"{"op":"p2p_group_remove","interface":"wlan0 -h"}"#;
+        assert!(serve(line.as_bytes()).is_err());
+        assert!(serve(&b"{\"op\":\"shell\"}"[..]).is_err());
+    }
+}
@@ -4,31 +4,66 @@
 //!
 //! # 模块
 //!
+//! - `adapter`: 多块网卡时热点网卡的挑选
+//! - `backend`: WiFi 后端抽象，工作流通过它操作热点
+//! - `busy`: 热点接口已连接 WiFi 时的检测结果和处理方式
+//! - `command`: 外部命令（nmcli / wpa_cli / ip）执行抽象，测试时可注入假实现
+//! - `credentials`: 热点 SSID/口令的生成策略和 WPA3 的启用条件
+//! - `helper`: 特权助手 `cattysend-helper`，代为执行需要 CAP_NET_ADMIN 的命令
 //! - `nm_dbus`: NetworkManager D-Bus 客户端 (推荐)
+//! - `orphans`: 进程崩溃后残留的热点连接和 P2P 组的清理
 //! - `p2p_sender`: P2P 热点创建（发送端）
 //! - `p2p_receiver`: P2P 连接（接收端）
+//! - `sender_addr`: 接入热点后查找发送端 IP（接收端）
+//! - `station_monitor`: 热点客户端接入监控（发送端）
+//! - `wpa`: wpa_cli 参数的序列化
 //!
 //! # P2pInfo
 //!
 //! 核心数据结构，用于在 BLE 握手时交换 WiFi 连接信息。
 //! 敏感字段（SSID、PSK、MAC）可以使用 AES-CTR 加密。
+//!
+//! 对端发来的 P2pInfo 解密后先经 [`P2pInfo::validate`] 校验，
+//! 不合法的值不会被交给 NetworkManager / nmcli。
 
+pub mod adapter;
+pub mod backend;
+pub mod busy;
+pub mod command;
+pub mod credentials;
+pub mod helper;
 pub mod nm_dbus;
+pub mod orphans;
 pub mod p2p_receiver;
 pub mod p2p_sender;
+pub mod sender_addr;
+pub mod station_monitor;
+pub mod wpa;
 
 #[cfg(test)]
 mod tests;
 
-pub use nm_dbus::NmClient;
+pub use adapter::{Adapter, pick_hotspot_adapter, rank_adapters};
+pub use backend::{LinuxWifiBackend, WifiBackend};
+pub use busy::{BusyInterfacePolicy, InterfaceBusy};
+pub use command::{CommandRunner, FakeRunner, SystemRunner};
+pub use credentials::{CredentialPolicy, HotspotSecurity, PeerSupport, PskCharset, probe_wpa3};
+pub use helper::HelperRunner;
+pub use nm_dbus::{NmActivationFailed, NmClient, NmPermissionDenied};
+pub use orphans::{OrphanCleanup, cleanup_orphans};
 pub use p2p_receiver::{P2pReceiverConfig, WiFiP2pReceiver};
 pub use p2p_sender::{P2pConfig, WiFiP2pSender};
+pub use station_monitor::StationInfo;
+
+use std::net::IpAddr;
 
 /// 检查进程是否具有必要的权限
 ///
 /// 返回 (has_nmcli, has_net_raw)
 /// - has_nmcli: 系统中是否安装了 NetworkManager (nmcli)
 /// - has_net_raw: 是否有 CAP_NET_RAW (用于 BLE 扫描)
+///
+/// 完整的环境检查见 [`crate::diagnostics::run`]。
 pub fn check_capabilities() -> (bool, bool) {
     let mut has_nmcli = false;
     let mut has_net_raw = false;
@@ -41,16 +76,8 @@ pub fn check_capabilities() -> (bool, bool) {
     }
 
     // 检查 CAP_NET_RAW (用于 BLE 扫描)
-    if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
-        for line in status.lines() {
-            if line.starts_with("CapEff:")
-                && let Some(hex) = line.split_whitespace().nth(1)
-                && let Ok(caps) = u64::from_str_radix(hex, 16)
-            {
-                // CAP_NET_RAW = 13
-                has_net_raw = (caps & (1 << 13)) != 0;
-            }
-        }
+    if let Some(caps) = crate::diagnostics::effective_capabilities() {
+        has_net_raw = (caps & (1 << crate::diagnostics::CAP_NET_RAW)) != 0;
     }
 
     // 检查 nmcli 是否可用
@@ -64,6 +91,33 @@ pub fn check_capabilities() -> (bool, bool) {
     (has_nmcli, has_net_raw)
 }
 
+/// P2pInfo 校验错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum P2pInfoError {
+    #[error("invalid SSID: {0:?}")]
+    InvalidSsid(String),
+
+    /// 不在错误信息中带出密码
+    #[error("invalid PSK: must be 8-63 printable ASCII characters")]
+    InvalidPsk,
+
+    #[error("invalid MAC address: {0:?}")]
+    InvalidMac(String),
+
+    #[error("invalid port: {0}")]
+    InvalidPort(i32),
+
+    #[error("invalid host: {0:?}")]
+    InvalidHost(String),
+
+    /// 不在错误信息中带出令牌
+    #[error("invalid auth token: must be 1-64 ASCII letters or digits")]
+    InvalidAuthToken,
+
+    #[error("invalid certificate fingerprint: {0:?}")]
+    InvalidCertFingerprint(String),
+}
+
 /// P2pInfo - 与 CatShare 的 P2pInfo 完全兼容
 ///
 /// CatShare Kotlin 定义:
@@ -88,6 +142,10 @@ pub fn check_capabilities() -> (bool, bool) {
 /// - `port`: HTTPS 服务端口
 /// - `key`: 发送端 ECDH 公钥（用于解密上述字段）
 /// - `cat_share`: 协议版本号
+/// - `host`: 发送端局域网 IP（仅局域网直连模式，可加密；CatShare 会忽略此字段）
+/// - `security`: 热点的认证方式，WPA2-PSK 时省略（cattysend 扩展，见 [`HotspotSecurity`]）
+/// - `auth_token`: 传输服务的访问令牌（可加密；cattysend 扩展，见 [`TransferTask::auth_token`](crate::transfer::TransferTask::auth_token)）
+/// - `cert_sha256`: 传输服务 TLS 证书的 SHA-256 指纹（可加密；cattysend 扩展，见 [`crate::crypto::tls`]）
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct P2pInfo {
@@ -101,6 +159,14 @@ pub struct P2pInfo {
     pub key: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cat_share: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<HotspotSecurity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cert_sha256: Option<String>,
 }
 
 impl P2pInfo {
@@ -114,9 +180,41 @@ impl P2pInfo {
             port,
             key: None,
             cat_share: Some(1),
+            host: None,
+            security: None,
+            auth_token: None,
+            cert_sha256: None,
         }
     }
 
+    /// 创建局域网直连模式的 P2pInfo
+    ///
+    /// 双方已在同一网络时不创建热点，ssid/psk 留空，接收端直接连接 `host:port`
+    pub fn lan_direct(host: String, mac: String, port: i32) -> Self {
+        Self {
+            host: Some(host),
+            ..Self::new(String::new(), String::new(), mac, port)
+        }
+    }
+
+    /// 是否为局域网直连模式（无需连接热点）
+    pub fn is_lan_direct(&self) -> bool {
+        self.host.is_some()
+    }
+
+    /// 创建建组请求（反向模式）
+    ///
+    /// ssid/psk 留空且没有 host，表示发送端不建热点、请接收端创建 P2P 组，
+    /// 接收端通过 P2P 特征的通知回复自己的 P2pInfo
+    pub fn group_request(mac: String) -> Self {
+        Self::new(String::new(), String::new(), mac, 0)
+    }
+
+    /// 是否为建组请求（见 [`Self::group_request`]）
+    pub fn is_group_request(&self) -> bool {
+        self.ssid.is_empty() && self.host.is_none()
+    }
+
     /// 创建带加密字段的 P2pInfo
     ///
     /// # 参数
@@ -143,11 +241,89 @@ impl P2pInfo {
             port,
             key: Some(sender_public_key),
             cat_share: Some(1),
+            host: None,
+            security: None,
+            auth_token: None,
+            cert_sha256: None,
         }
     }
 
+    /// 热点的认证方式（字段缺省时为 WPA2-PSK）
+    pub fn security(&self) -> HotspotSecurity {
+        self.security.unwrap_or_default()
+    }
+
     /// 获取发送端的 HTTPS 地址
     pub fn get_server_url(&self, host_ip: &str) -> String {
         format!("https://{}:{}", host_ip, self.port)
     }
+
+    /// 校验解密后的字段，连接热点或回复建组请求前调用
+    ///
+    /// - SSID: 1-32 字节，不含控制字符，不以 `-` 开头（避免被 nmcli 当作选项）
+    /// - PSK: 8-63 个可见 ASCII 字符（WPA2 口令的长度范围）
+    /// - MAC: `XX:XX:XX:XX:XX:XX`
+    /// - 端口: 1-65535（建组请求为 0）
+    /// - 访问令牌: 1-64 个 ASCII 字母数字（会被放进 HTTP 请求头）
+    /// - 证书指纹: 64 个十六进制字符
+    /// - 局域网直连只检查 host 是否为 IP 地址，ssid/psk 不会被使用
+    pub fn validate(&self) -> Result<(), P2pInfoError> {
+        if !is_mac_address(&self.mac) {
+            return Err(P2pInfoError::InvalidMac(self.mac.clone()));
+        }
+        if let Some(token) = &self.auth_token
+            && !((1..=64).contains(&token.len())
+                && token.bytes().all(|b| b.is_ascii_alphanumeric()))
+        {
+            return Err(P2pInfoError::InvalidAuthToken);
+        }
+        if let Some(fingerprint) = &self.cert_sha256
+            && !(fingerprint.len() == 64 && fingerprint.bytes().all(|b| b.is_ascii_hexdigit()))
+        {
+            return Err(P2pInfoError::InvalidCertFingerprint(fingerprint.clone()));
+        }
+        if self.is_group_request() {
+            if !self.psk.is_empty() {
+                return Err(P2pInfoError::InvalidPsk);
+            }
+            return match self.port {
+                0..=65535 => Ok(()),
+                port => Err(P2pInfoError::InvalidPort(port)),
+            };
+        }
+        if !(1..=65535).contains(&self.port) {
+            return Err(P2pInfoError::InvalidPort(self.port));
+        }
+        if let Some(host) = &self.host {
+            return match host.parse::<IpAddr>() {
+                Ok(_) => Ok(()),
+                Err(_) => Err(P2pInfoError::InvalidHost(host.clone())),
+            };
+        }
+
+        let ssid_valid = (1..=32).contains(&self.ssid.len())
+            && !self.ssid.starts_with('-')
+            && !self.ssid.chars().any(char::is_control);
+        if !ssid_valid {
+            return Err(P2pInfoError::InvalidSsid(self.ssid.clone()));
+        }
+        let psk_valid = (8..=63).contains(&self.psk.len())
+            && self.psk.bytes().all(|b| (b' '..=b'~').contains(&b));
+        if !psk_valid {
+            return Err(P2pInfoError::InvalidPsk);
+        }
+        Ok(())
+    }
+}
+
+/// `XX:XX:XX:XX:XX:XX` 格式的 MAC 地址（大小写均可）
+fn is_mac_address(mac: &str) -> bool {
+    let mut parts = 0;
+    for part in mac.split(':') {
+        parts += 1;
+        if part.len() != 2 || !part.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return false;
+        }
+    }
+    parts == 6
 }
@@ -20,23 +20,78 @@
 //! let devices = client.get_wifi_devices().await?;
 //!
 //! // 创建热点
-//! let conn = client.create_hotspot("DIRECT-abc", "password123", "a").await?;
+//! let conn = client
+//!     .create_hotspot("DIRECT-abc", "password123", "a", "wlan0", HotspotSecurity::Wpa2Psk)
+//!     .await?;
 //!
 //! // 激活连接
 //! client.activate_connection(&conn, &device).await?;
 //! ```
+//!
+//! # polkit 授权
+//!
+//! 创建和激活连接的调用带有 `ALLOW_INTERACTIVE_AUTH` 标志：策略要求认证时，
+//! 桌面会话中的 polkit 代理会弹出认证对话框，而不是直接拒绝。没有代理（例如守护进程
+//! 在后台运行）、用户取消或策略直接拒绝时返回 [`NmPermissionDenied`]，其中带有所需的授权名。
 
 use std::collections::HashMap;
+use std::fmt;
 use std::time::Duration;
 
 use std::ops::Deref;
 
 use anyhow::{Context, Result};
-use log::{debug, info};
+use futures_util::StreamExt;
+use log::{debug, info, warn};
 use zbus::Connection;
 use zbus::proxy;
 use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
 
+use crate::wifi::HotspotSecurity;
+use crate::wifi::adapter::Adapter;
+
+/// 修改系统连接配置的 polkit 授权（创建热点和连接配置）
+pub const PERMISSION_MODIFY_SYSTEM: &str = "org.freedesktop.NetworkManager.settings.modify.system";
+/// 激活、停用连接的 polkit 授权
+pub const PERMISSION_NETWORK_CONTROL: &str = "org.freedesktop.NetworkManager.network-control";
+
+/// NetworkManager 按 polkit 策略拒绝了操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NmPermissionDenied {
+    /// 所需的 polkit 授权
+    pub permission: &'static str,
+    /// NetworkManager 返回的错误信息
+    pub message: String,
+}
+
+impl fmt::Display for NmPermissionDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "NetworkManager denied the request (polkit permission {} required): {}",
+            self.permission, self.message
+        )
+    }
+}
+
+impl std::error::Error for NmPermissionDenied {}
+
+/// NetworkManager 的 `*.PermissionDenied` 错误转为 [`NmPermissionDenied`]，其他错误附加 `context`
+fn nm_error(error: zbus::Error, permission: &'static str, context: &'static str) -> anyhow::Error {
+    match &error {
+        zbus::Error::MethodError(name, message, _)
+            if name.as_str().ends_with(".PermissionDenied") =>
+        {
+            NmPermissionDenied {
+                permission,
+                message: message.clone().unwrap_or_default(),
+            }
+            .into()
+        }
+        _ => anyhow::Error::new(error).context(context),
+    }
+}
+
 // NetworkManager D-Bus 路径由 zbus proxy 宏自动处理
 
 /// NetworkManager 主接口代理
@@ -50,6 +105,7 @@ trait NetworkManager {
     fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
 
     /// 激活连接
+    #[zbus(allow_interactive_auth)]
     fn activate_connection(
         &self,
         connection: &ObjectPath<'_>,
@@ -61,6 +117,7 @@ trait NetworkManager {
     fn deactivate_connection(&self, active_connection: &ObjectPath<'_>) -> zbus::Result<()>;
 
     /// 添加并激活连接 (简化版)
+    #[zbus(allow_interactive_auth)]
     fn add_and_activate_connection(
         &self,
         connection: HashMap<&str, HashMap<&str, Value<'_>>>,
@@ -89,6 +146,7 @@ trait NetworkManager {
 )]
 trait NmSettings {
     /// 添加新连接
+    #[zbus(allow_interactive_auth)]
     fn add_connection(
         &self,
         connection: HashMap<&str, HashMap<&str, Value<'_>>>,
@@ -156,6 +214,25 @@ trait NmDeviceWireless {
 
     /// 获取所有接入点
     fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// 当前连接的接入点（未连接时为 `/`）
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+
+    /// 网卡能力（`NMDeviceWifiCapabilities` 位掩码）
+    #[zbus(property)]
+    fn wireless_capabilities(&self) -> zbus::Result<u32>;
+}
+
+/// NetworkManager.AccessPoint 接口代理
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait NmAccessPoint {
+    /// SSID（原始字节）
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
 }
 
 /// NetworkManager.Connection.Active 接口代理
@@ -168,13 +245,25 @@ trait NmActiveConnection {
     #[zbus(property)]
     fn id(&self) -> zbus::Result<String>;
 
+    /// 连接配置的对象路径
+    #[zbus(property, name = "Connection")]
+    fn settings_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
     /// 连接状态
     #[zbus(property)]
     fn state(&self) -> zbus::Result<u32>;
 
+    /// 连接状态变化，带有变化原因（与 `state` 属性的变化通知区分开命名）
+    #[zbus(signal, name = "StateChanged")]
+    fn activation_state_changed(&self, state: u32, reason: u32) -> zbus::Result<()>;
+
     /// IP4 配置对象路径
     #[zbus(property)]
     fn ip4_config(&self) -> zbus::Result<OwnedObjectPath>;
+
+    /// DHCPv4 配置对象路径
+    #[zbus(property)]
+    fn dhcp4_config(&self) -> zbus::Result<OwnedObjectPath>;
 }
 
 /// NetworkManager.IP4Config 接口代理
@@ -188,6 +277,17 @@ trait NmIp4Config {
     fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
 }
 
+/// NetworkManager.DHCP4Config 接口代理
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.DHCP4Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait NmDhcp4Config {
+    /// DHCP 租约选项（值均为字符串）
+    #[zbus(property)]
+    fn options(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
 // ============================================================================
 // 高层封装
 // ============================================================================
@@ -198,6 +298,11 @@ pub mod device_type {
     pub const WIFI_P2P: u32 = 30;
 }
 
+/// WiFi 网卡能力位（`NMDeviceWifiCapabilities`）
+pub mod wifi_capability {
+    pub const FREQ_5GHZ: u32 = 0x400;
+}
+
 /// 设备状态常量
 pub mod device_state {
     pub const DISCONNECTED: u32 = 30;
@@ -224,6 +329,83 @@ pub mod active_connection_state {
     }
 }
 
+/// 连接状态变化原因（`NMActiveConnectionStateReason`）
+pub mod active_connection_state_reason {
+    pub const UNKNOWN: u32 = 0;
+    pub const NONE: u32 = 1;
+    pub const USER_DISCONNECTED: u32 = 2;
+    pub const DEVICE_DISCONNECTED: u32 = 3;
+    pub const SERVICE_STOPPED: u32 = 4;
+    pub const IP_CONFIG_INVALID: u32 = 5;
+    pub const CONNECT_TIMEOUT: u32 = 6;
+    pub const SERVICE_START_TIMEOUT: u32 = 7;
+    pub const SERVICE_START_FAILED: u32 = 8;
+    pub const NO_SECRETS: u32 = 9;
+    pub const LOGIN_FAILED: u32 = 10;
+    pub const CONNECTION_REMOVED: u32 = 11;
+    pub const DEPENDENCY_FAILED: u32 = 12;
+    pub const DEVICE_REALIZE_FAILED: u32 = 13;
+    pub const DEVICE_REMOVED: u32 = 14;
+
+    pub fn name(reason: u32) -> &'static str {
+        match reason {
+            UNKNOWN => "unknown",
+            NONE => "none",
+            USER_DISCONNECTED => "user-disconnected",
+            DEVICE_DISCONNECTED => "device-disconnected",
+            SERVICE_STOPPED => "service-stopped",
+            IP_CONFIG_INVALID => "ip-config-invalid",
+            CONNECT_TIMEOUT => "connect-timeout",
+            SERVICE_START_TIMEOUT => "service-start-timeout",
+            SERVICE_START_FAILED => "service-start-failed",
+            NO_SECRETS => "no-secrets",
+            LOGIN_FAILED => "login-failed",
+            CONNECTION_REMOVED => "connection-removed",
+            DEPENDENCY_FAILED => "dependency-failed",
+            DEVICE_REALIZE_FAILED => "device-realize-failed",
+            DEVICE_REMOVED => "device-removed",
+            _ => "invalid",
+        }
+    }
+}
+
+/// 连接没能激活（或激活后又断开），带有 NetworkManager 给出的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NmActivationFailed {
+    /// 最后的连接状态（见 [`active_connection_state`]）
+    pub state: u32,
+    /// 变化原因（见 [`active_connection_state_reason`]）
+    pub reason: u32,
+}
+
+impl NmActivationFailed {
+    /// 认证失败（通常是口令错误），用同样的口令重试不会成功
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(
+            self.reason,
+            active_connection_state_reason::NO_SECRETS
+                | active_connection_state_reason::LOGIN_FAILED
+        )
+    }
+}
+
+impl fmt::Display for NmActivationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Connection failed to activate (state: {}, reason: {})",
+            active_connection_state::name(self.state),
+            active_connection_state_reason::name(self.reason)
+        )?;
+        if self.is_auth_failure() {
+            write!(f, ", wrong password?")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NmActivationFailed {}
+
 /// WiFi 设备信息
 #[derive(Debug, Clone)]
 pub struct WifiDevice {
@@ -320,6 +502,32 @@ impl NmClient {
         }
     }
 
+    /// 所有 WiFi 网卡及其作为热点网卡的条件，见 [`crate::wifi::adapter`]
+    pub async fn adapters(&self) -> Result<Vec<Adapter>> {
+        let devices = self.get_wifi_devices().await?;
+        let mut adapters = Vec::new();
+        for device in devices
+            .iter()
+            .filter(|d| d.device_type == device_type::WIFI)
+        {
+            let p2p_interface = format!("p2p-dev-{}", device.interface);
+            let wireless = NmDeviceWirelessProxy::builder(&self.connection)
+                .path(&device.path)?
+                .build()
+                .await?;
+            let capabilities = wireless.wireless_capabilities().await.unwrap_or(0);
+            adapters.push(Adapter {
+                interface: device.interface.clone(),
+                idle: !device.is_active,
+                p2p: devices.iter().any(|d| {
+                    d.device_type == device_type::WIFI_P2P && d.interface == p2p_interface
+                }),
+                supports_5ghz: capabilities & wifi_capability::FREQ_5GHZ != 0,
+            });
+        }
+        Ok(adapters)
+    }
+
     /// 创建 WiFi 热点连接配置
     pub async fn create_hotspot(
         &self,
@@ -327,16 +535,24 @@ impl NmClient {
         password: &str,
         band: &str,
         interface: &str,
+        security: HotspotSecurity,
     ) -> Result<OwnedObjectPath> {
         let settings = NmSettingsProxy::new(&self.connection).await?;
 
         // 构建连接配置
-        let connection_settings = self.build_hotspot_settings(ssid, password, band, interface);
+        let connection_settings =
+            self.build_hotspot_settings(ssid, password, band, interface, security);
 
         let conn_path = settings
             .add_connection(connection_settings)
             .await
-            .context("Failed to create hotspot connection")?;
+            .map_err(|e| {
+                nm_error(
+                    e,
+                    PERMISSION_MODIFY_SYSTEM,
+                    "Failed to create hotspot connection",
+                )
+            })?;
 
         info!("Created hotspot connection: {:?}", conn_path);
         Ok(conn_path)
@@ -349,6 +565,7 @@ impl NmClient {
         password: &'a str,
         band: &'a str,
         interface: &'a str,
+        security: HotspotSecurity,
     ) -> HashMap<&'a str, HashMap<&'a str, Value<'a>>> {
         let mut settings: HashMap<&str, HashMap<&str, Value>> = HashMap::new();
 
@@ -373,8 +590,12 @@ impl NmClient {
 
         // 802-11-wireless-security 部分
         let mut wireless_security: HashMap<&str, Value> = HashMap::new();
-        wireless_security.insert("key-mgmt", Value::Str("wpa-psk".into()));
+        wireless_security.insert("key-mgmt", Value::Str(security.key_mgmt().into()));
         wireless_security.insert("psk", Value::Str(password.into()));
+        if security == HotspotSecurity::Wpa3Sae {
+            // WPA3-Personal 要求 PMF（3 = required）
+            wireless_security.insert("pmf", Value::I32(3));
+        }
         settings.insert("802-11-wireless-security", wireless_security);
 
         // ipv4 部分 (共享模式 - 自动 DHCP)
@@ -396,15 +617,23 @@ impl NmClient {
         ssid: &str,
         password: &str,
         interface: Option<&str>,
+        security: HotspotSecurity,
     ) -> Result<OwnedObjectPath> {
         let settings = NmSettingsProxy::new(&self.connection).await?;
 
-        let connection_settings = self.build_wifi_client_settings(ssid, password, interface);
+        let connection_settings =
+            self.build_wifi_client_settings(ssid, password, interface, security);
 
         let conn_path = settings
             .add_connection(connection_settings)
             .await
-            .context("Failed to create WiFi connection")?;
+            .map_err(|e| {
+                nm_error(
+                    e,
+                    PERMISSION_MODIFY_SYSTEM,
+                    "Failed to create WiFi connection",
+                )
+            })?;
 
         info!("Created WiFi connection: {:?}", conn_path);
         Ok(conn_path)
@@ -416,6 +645,7 @@ impl NmClient {
         ssid: &'a str,
         password: &'a str,
         interface: Option<&'a str>,
+        security: HotspotSecurity,
     ) -> HashMap<&'a str, HashMap<&'a str, Value<'a>>> {
         let mut settings: HashMap<&str, HashMap<&str, Value>> = HashMap::new();
 
@@ -438,7 +668,7 @@ impl NmClient {
 
         // 802-11-wireless-security 部分
         let mut wireless_security: HashMap<&str, Value> = HashMap::new();
-        wireless_security.insert("key-mgmt", Value::Str("wpa-psk".into()));
+        wireless_security.insert("key-mgmt", Value::Str(security.key_mgmt().into()));
         wireless_security.insert("psk", Value::Str(password.into()));
         settings.insert("802-11-wireless-security", wireless_security);
 
@@ -471,7 +701,13 @@ impl NmClient {
                 &ObjectPath::from_static_str_unchecked("/"),
             )
             .await
-            .context("Failed to activate connection")?;
+            .map_err(|e| {
+                nm_error(
+                    e,
+                    PERMISSION_NETWORK_CONTROL,
+                    "Failed to activate connection",
+                )
+            })?;
 
         info!("Activated connection: {:?}", active_conn);
         Ok(active_conn)
@@ -508,10 +744,7 @@ impl NmClient {
                 .await?;
 
             if let Ok(conn_settings) = conn.get_settings().await
-                && let Some(connection_section) = conn_settings.get("connection")
-                && let Some(id_value) = connection_section.get("id")
-                && let Value::Str(id_str) = id_value.deref()
-                && id_str.as_str() == name
+                && connection_id(&conn_settings).as_deref() == Some(name)
             {
                 conn.delete().await?;
                 debug!("Deleted connection by name: {}", name);
@@ -522,6 +755,34 @@ impl NmClient {
         Ok(false)
     }
 
+    /// 删除名称以 `prefix` 开头的所有连接（活动的连接随之断开），返回删除的连接名
+    pub async fn delete_connections_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        let settings = NmSettingsProxy::new(&self.connection).await?;
+        let mut deleted = Vec::new();
+
+        for conn_path in settings.list_connections().await? {
+            let conn = NmConnectionProxy::builder(&self.connection)
+                .path(&conn_path)?
+                .build()
+                .await?;
+
+            if let Ok(conn_settings) = conn.get_settings().await
+                && let Some(id) = connection_id(&conn_settings)
+                && id.starts_with(prefix)
+            {
+                match conn.delete().await {
+                    Ok(()) => {
+                        debug!("Deleted connection: {}", id);
+                        deleted.push(id);
+                    }
+                    Err(e) => warn!("Failed to delete connection {}: {}", id, e),
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
     /// 触发 WiFi 扫描
     pub async fn request_wifi_scan(&self, device: &WifiDevice) -> Result<()> {
         let wireless = NmDeviceWirelessProxy::builder(&self.connection)
@@ -538,103 +799,163 @@ impl NmClient {
     }
 
     /// 等待连接激活（不等待IP配置，适用于热点模式）
+    ///
+    /// 订阅连接的 `StateChanged` 信号而不是轮询；激活失败时返回 [`NmActivationFailed`]。
     pub async fn wait_for_activation(
         &self,
         active_connection: &ObjectPath<'_>,
         timeout: Duration,
     ) -> Result<()> {
-        let start = std::time::Instant::now();
-        let mut last_state = 0u32;
-
-        loop {
-            if start.elapsed() > timeout {
-                return Err(anyhow::anyhow!(
-                    "Timeout waiting for connection activation (last state: {})",
-                    active_connection_state::name(last_state)
-                ));
-            }
-
-            let active = NmActiveConnectionProxy::builder(&self.connection)
-                .path(active_connection)?
-                .build()
-                .await?;
-
-            let state = active.state().await.unwrap_or(0);
-
-            // 状态变化时记录日志
-            if state != last_state {
-                debug!(
-                    "Connection state changed: {} -> {}",
-                    active_connection_state::name(last_state),
-                    active_connection_state::name(state)
-                );
-                last_state = state;
-            }
-
-            match state {
-                active_connection_state::ACTIVATED => {
-                    info!("Connection activated successfully");
-                    return Ok(());
-                }
-                active_connection_state::DEACTIVATED | active_connection_state::DEACTIVATING => {
-                    return Err(anyhow::anyhow!(
-                        "Connection failed to activate (state: {})",
-                        active_connection_state::name(state)
-                    ));
-                }
-                _ => {
-                    // UNKNOWN, ACTIVATING - 继续等待
-                }
-            }
-
-            tokio::time::sleep(Duration::from_millis(500)).await;
-        }
+        let active = NmActiveConnectionProxy::builder(&self.connection)
+            .path(active_connection)?
+            .build()
+            .await?;
+        tokio::time::timeout(timeout, activated(&active))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timeout waiting for connection activation"))??;
+        info!("Connection activated successfully");
+        Ok(())
     }
 
     /// 等待连接激活并获取 IP
+    ///
+    /// 激活后等待 `Ip4Config` 对象和其中的地址出现，由属性变化通知驱动。
     pub async fn wait_for_ip(
         &self,
         active_connection: &ObjectPath<'_>,
         timeout: Duration,
     ) -> Result<String> {
-        let start = std::time::Instant::now();
+        let active = NmActiveConnectionProxy::builder(&self.connection)
+            .path(active_connection)?
+            .build()
+            .await?;
+        tokio::time::timeout(timeout, self.first_ip(&active))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timeout waiting for IP address"))?
+    }
 
+    async fn first_ip(&self, active: &NmActiveConnectionProxy<'_>) -> Result<String> {
+        // 先订阅再读取当前值，两者之间的变化不会丢失
+        let mut config_changes = active.receive_ip4_config_changed().await;
+        let mut state_changes = active.receive_activation_state_changed().await?;
+        activated(active).await?;
         loop {
-            if start.elapsed() > timeout {
-                return Err(anyhow::anyhow!("Timeout waiting for IP address"));
+            let ip4_path = active.ip4_config().await?;
+            let mut address_changes = None;
+            if ip4_path.as_str() != "/" {
+                let ip4 = NmIp4ConfigProxy::builder(&self.connection)
+                    .path(&ip4_path)?
+                    .build()
+                    .await?;
+                let changes = ip4.receive_address_data_changed().await;
+                if let Some(ip) = first_address(&ip4).await {
+                    return Ok(ip);
+                }
+                address_changes = Some(changes);
             }
-
-            let active = NmActiveConnectionProxy::builder(&self.connection)
-                .path(active_connection)?
-                .build()
-                .await?;
-
-            let state = active.state().await.unwrap_or(0);
-
-            if state == active_connection_state::ACTIVATED {
-                // 获取 IP4 配置
-                if let Ok(ip4_path) = active.ip4_config().await
-                    && ip4_path.as_str() != "/"
-                {
-                    let ip4 = NmIp4ConfigProxy::builder(&self.connection)
-                        .path(&ip4_path)?
-                        .build()
-                        .await?;
-
-                    if let Ok(addresses) = ip4.address_data().await {
-                        for addr in addresses {
-                            if let Some(address_value) = addr.get("address")
-                                && let Value::Str(ip_str) = address_value.deref()
-                            {
-                                return Ok(ip_str.to_string());
-                            }
+            let address_changed = async {
+                match &mut address_changes {
+                    Some(changes) => changes.next().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                Some(_) = config_changes.next() => {}
+                Some(_) = address_changed => {}
+                Some(signal) = state_changes.next() => {
+                    // 激活前的状态变化也在流中，只关心断开
+                    let args = signal.args()?;
+                    if is_down(*args.state()) {
+                        return Err(NmActivationFailed {
+                            state: *args.state(),
+                            reason: *args.reason(),
                         }
+                        .into());
                     }
                 }
+                else => anyhow::bail!("NetworkManager signal stream closed"),
             }
+        }
+    }
 
-            tokio::time::sleep(Duration::from_millis(500)).await;
+    /// DHCP 服务器地址（租约中的 server identifier，没有时取第一个 router）
+    pub async fn dhcp_server(&self, active_connection: &ObjectPath<'_>) -> Result<Option<String>> {
+        let active = NmActiveConnectionProxy::builder(&self.connection)
+            .path(active_connection)?
+            .build()
+            .await?;
+        let dhcp_path = active.dhcp4_config().await?;
+        if dhcp_path.as_str() == "/" {
+            return Ok(None);
         }
+
+        let dhcp = NmDhcp4ConfigProxy::builder(&self.connection)
+            .path(&dhcp_path)?
+            .build()
+            .await?;
+        let options = dhcp.options().await?;
+        let option = |key: &str| match options.get(key).map(|v| v.deref()) {
+            Some(Value::Str(s)) => Some(s.to_string()),
+            _ => None,
+        };
+        Ok(option("dhcp_server_identifier").or_else(|| {
+            option("routers").and_then(|r| r.split_whitespace().next().map(str::to_string))
+        }))
+    }
+
+    /// 各 WiFi 设备当前连接的网络的 SSID
+    pub async fn connected_ssids(&self) -> Result<Vec<String>> {
+        let mut ssids = Vec::new();
+        for device in self.get_wifi_devices().await? {
+            if device.device_type != device_type::WIFI {
+                continue;
+            }
+            if let Some(ssid) = self.active_ssid(&device).await? {
+                ssids.push(ssid);
+            }
+        }
+        Ok(ssids)
+    }
+
+    /// 设备当前连接的网络的 SSID，未连接时为 None
+    pub async fn active_ssid(&self, device: &WifiDevice) -> Result<Option<String>> {
+        let wireless = NmDeviceWirelessProxy::builder(&self.connection)
+            .path(&device.path)?
+            .build()
+            .await?;
+        let ap_path = wireless.active_access_point().await?;
+        if ap_path.as_str() == "/" {
+            return Ok(None);
+        }
+        let ap = NmAccessPointProxy::builder(&self.connection)
+            .path(&ap_path)?
+            .build()
+            .await?;
+        let ssid = ap.ssid().await?;
+        Ok(Some(String::from_utf8_lossy(&ssid).into_owned()))
+    }
+
+    /// 设备上的活动连接：连接名和连接配置的对象路径，未连接时为 None
+    pub async fn device_connection(
+        &self,
+        device: &WifiDevice,
+    ) -> Result<Option<(String, OwnedObjectPath)>> {
+        let dev = NmDeviceProxy::builder(&self.connection)
+            .path(&device.path)?
+            .build()
+            .await?;
+        let active_path = dev.active_connection().await?;
+        if active_path.as_str() == "/" {
+            return Ok(None);
+        }
+        let active = NmActiveConnectionProxy::builder(&self.connection)
+            .path(&active_path)?
+            .build()
+            .await?;
+        Ok(Some((
+            active.id().await?,
+            active.settings_connection().await?,
+        )))
     }
 
     /// 断开设备连接
@@ -651,6 +972,63 @@ impl NmClient {
     }
 }
 
+/// 等到连接进入 ACTIVATED，进入 DEACTIVATING/DEACTIVATED 时返回失败原因
+async fn activated(active: &NmActiveConnectionProxy<'_>) -> Result<()> {
+    // 先订阅再读取当前状态，两者之间的变化不会丢失
+    let mut changes = active.receive_activation_state_changed().await?;
+    let mut state = active
+        .state()
+        .await
+        .unwrap_or(active_connection_state::UNKNOWN);
+    let mut reason = active_connection_state_reason::UNKNOWN;
+    loop {
+        debug!(
+            "Connection state: {} (reason: {})",
+            active_connection_state::name(state),
+            active_connection_state_reason::name(reason)
+        );
+        if state == active_connection_state::ACTIVATED {
+            return Ok(());
+        }
+        if is_down(state) {
+            return Err(NmActivationFailed { state, reason }.into());
+        }
+        // UNKNOWN, ACTIVATING - 继续等待
+        let signal = changes
+            .next()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("NetworkManager signal stream closed"))?;
+        let args = signal.args()?;
+        state = *args.state();
+        reason = *args.reason();
+    }
+}
+
+fn is_down(state: u32) -> bool {
+    matches!(
+        state,
+        active_connection_state::DEACTIVATING | active_connection_state::DEACTIVATED
+    )
+}
+
+/// 连接配置中的连接名（`connection.id`）
+fn connection_id(settings: &HashMap<String, HashMap<String, OwnedValue>>) -> Option<String> {
+    match settings.get("connection")?.get("id")?.deref() {
+        Value::Str(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// IP4 配置中的第一个地址
+async fn first_address(ip4: &NmIp4ConfigProxy<'_>) -> Option<String> {
+    ip4.address_data().await.ok()?.into_iter().find_map(|addr| {
+        match addr.get("address").map(|v| v.deref()) {
+            Some(Value::Str(ip)) => Some(ip.to_string()),
+            _ => None,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
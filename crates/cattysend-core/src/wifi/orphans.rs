@@ -0,0 +1,140 @@
+//! 残留热点的清理
+//!
+//! 热点和接入热点的连接正常情况下在传输结束时删除；进程崩溃或被杀掉后，
+//! NetworkManager 中会留下 `cattysend-hotspot-*` / `cattysend-wifi-*` 连接，
+//! wpa_cli 创建的 P2P 组接口（`p2p-<接口>-N`）也不会消失。守护进程启动时调用
+//! [`cleanup_orphans`] 把它们清理掉。
+
+use crate::wifi::command::CommandRunner;
+use crate::wifi::helper;
+use crate::wifi::nm_dbus::NmClient;
+use log::{debug, warn};
+
+/// cattysend 创建的 NetworkManager 连接的名称前缀
+pub const CONNECTION_PREFIX: &str = "cattysend-";
+
+/// 清理掉的残留
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrphanCleanup {
+    /// 删除的 NetworkManager 连接
+    pub connections: Vec<String>,
+    /// 移除的 P2P 组接口
+    pub groups: Vec<String>,
+}
+
+impl OrphanCleanup {
+    pub fn is_empty(&self) -> bool {
+        self.connections.is_empty() && self.groups.is_empty()
+    }
+}
+
+/// 删除残留的 cattysend 连接和 P2P 组
+///
+/// 会断开正在使用的热点，只应在没有传输进行时调用（例如守护进程启动时）。
+/// NetworkManager 不可用时只清理 P2P 组。
+pub async fn cleanup_orphans() -> OrphanCleanup {
+    let connections = match NmClient::new().await {
+        Ok(client) => client
+            .delete_connections_with_prefix(CONNECTION_PREFIX)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to list NetworkManager connections: {}", e);
+                Vec::new()
+            }),
+        Err(e) => {
+            debug!(
+                "NetworkManager unavailable, skipping connection cleanup: {}",
+                e
+            );
+            Vec::new()
+        }
+    };
+    let runner = helper::default_runner();
+    let groups = remove_p2p_groups(runner.as_ref());
+    OrphanCleanup {
+        connections,
+        groups,
+    }
+}
+
+/// 用 wpa_cli 移除所有 P2P 组接口，返回成功移除的接口
+///
+/// 与 [`WiFiP2pSender::stop_group`](crate::wifi::WiFiP2pSender::stop_group) 一样使用
+/// `p2p_group_remove *`（特权助手只允许这种形式），按所属接口逐个移除。
+pub fn remove_p2p_groups(runner: &dyn CommandRunner) -> Vec<String> {
+    let output = match runner.run("ip", &["-o", "addr", "show"]) {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("Failed to list interfaces: {}", e);
+            return Vec::new();
+        }
+    };
+    let groups = group_interfaces(&output.stdout);
+    let mut parents: Vec<&str> = groups.iter().map(|(parent, _)| *parent).collect();
+    parents.sort_unstable();
+    parents.dedup();
+
+    let mut removed = Vec::new();
+    for parent in parents {
+        match runner.run("wpa_cli", &["-i", parent, "p2p_group_remove", "*"]) {
+            Ok(output) if output.success => removed.extend(
+                groups
+                    .iter()
+                    .filter(|(p, _)| *p == parent)
+                    .map(|(_, group)| group.to_string()),
+            ),
+            Ok(output) => warn!(
+                "Failed to remove P2P groups on {}: {}",
+                parent,
+                output.stderr.trim()
+            ),
+            Err(e) => warn!("Failed to remove P2P groups on {}: {}", parent, e),
+        }
+    }
+    removed
+}
+
+/// `ip -o addr show` 输出中的 P2P 组接口，返回 (所属接口, 组接口)
+fn group_interfaces(output: &str) -> Vec<(&str, &str)> {
+    let mut groups: Vec<(&str, &str)> = output
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .filter_map(|iface| {
+            // p2p-dev-<接口> 是 P2P 设备本身，不是组
+            let rest = iface.strip_prefix("p2p-")?;
+            if rest.starts_with("dev-") {
+                return None;
+            }
+            let (parent, index) = rest.rsplit_once('-')?;
+            index
+                .chars()
+                .all(|c| c.is_ascii_digit())
+                .then_some((parent, iface))
+        })
+        .collect();
+    groups.dedup();
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wifi::command::{FakeRunner, fixtures};
+
+    #[test]
+    fn test_remove_p2p_groups() {
+        let runner = FakeRunner::multi_interface();
+        assert_eq!(remove_p2p_groups(&runner), vec!["p2p-wlan0-0"]);
+        assert!(
+            runner
+                .calls()
+                .contains(&"wpa_cli -i wlan0 p2p_group_remove *".to_string())
+        );
+
+        let runner = FakeRunner::single_interface();
+        assert!(remove_p2p_groups(&runner).is_empty());
+        assert!(!runner.calls().iter().any(|c| c.starts_with("wpa_cli")));
+
+        assert!(group_interfaces(fixtures::IP_ADDR_SINGLE_INTERFACE).is_empty());
+    }
+}
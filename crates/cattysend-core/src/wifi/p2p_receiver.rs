@@ -9,18 +9,24 @@
 //!
 //! # 注意事项
 //!
-//! - 连接后自动获取 DHCP 分配的 IP 地址
+//! - 连接后自动获取 DHCP 分配的 IP 地址，并记录 DHCP 服务器用于查找发送端地址
 //! - 断开时会清理相关网络配置
 
-use std::process::Command;
 use std::sync::Arc;
 use std::time::Duration;
 
 use log::{debug, info, warn};
 use tokio::sync::Mutex;
 
+use crate::discovery::host;
 use crate::wifi::P2pInfo;
-use crate::wifi::nm_dbus::NmClient;
+use crate::wifi::command::{self, CommandRunner};
+use crate::wifi::helper;
+use crate::wifi::nm_dbus::{NmActivationFailed, NmClient};
+use crate::wifi::sender_addr;
+
+/// 解析发送端主机名的超时（CatShare 发送端不发布主机名，这段时间会白等）
+const HOSTNAME_TIMEOUT: Duration = Duration::from_millis(1500);
 
 /// WiFi P2P 接收端配置
 #[derive(Debug, Clone)]
@@ -48,6 +54,8 @@ struct ActiveConnection {
     connection_name: String,
     _connection_path: Option<String>,
     used_p2p_mode: bool,
+    /// DHCP 租约中的服务器地址
+    dhcp_server: Option<String>,
 }
 
 /// WiFi P2P 接收端
@@ -55,6 +63,7 @@ pub struct WiFiP2pReceiver {
     config: P2pReceiverConfig,
     nm_client: Arc<Mutex<Option<NmClient>>>,
     active_connection: Arc<Mutex<Option<ActiveConnection>>>,
+    runner: Arc<dyn CommandRunner>,
 }
 
 impl WiFiP2pReceiver {
@@ -66,6 +75,7 @@ impl WiFiP2pReceiver {
             },
             nm_client: Arc::new(Mutex::new(None)),
             active_connection: Arc::new(Mutex::new(None)),
+            runner: helper::default_runner(),
         }
     }
 
@@ -74,9 +84,16 @@ impl WiFiP2pReceiver {
             config,
             nm_client: Arc::new(Mutex::new(None)),
             active_connection: Arc::new(Mutex::new(None)),
+            runner: helper::default_runner(),
         }
     }
 
+    /// 替换外部命令执行器（测试时注入 [`FakeRunner`](command::FakeRunner)）
+    pub fn with_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
     /// 初始化 NM 客户端
     async fn ensure_nm_client(&self) -> anyhow::Result<()> {
         let mut client = self.nm_client.lock().await;
@@ -98,7 +115,10 @@ impl WiFiP2pReceiver {
     /// 连接到 P2P 热点
     ///
     /// 返回分配的 IP 地址
+    #[tracing::instrument(skip_all, fields(ssid = %info.ssid))]
     pub async fn connect(&mut self, info: &P2pInfo) -> anyhow::Result<String> {
+        // 字段会出现在 nmcli 参数和连接名中，调用方未校验时在这里兜底
+        info.validate()?;
         info!(
             "Connecting to WiFi Direct: ssid='{}', preserve_wifi={}",
             info.ssid, self.config.preserve_wifi
@@ -110,6 +130,13 @@ impl WiFiP2pReceiver {
                 info!("Connected via NetworkManager D-Bus, IP: {}", ip);
                 return Ok(ip);
             }
+            // 口令错误时 nmcli 同样会失败，原样返回 NetworkManager 给出的原因
+            Err(e)
+                if e.downcast_ref::<NmActivationFailed>()
+                    .is_some_and(NmActivationFailed::is_auth_failure) =>
+            {
+                return Err(e);
+            }
             Err(e) => {
                 warn!("NM D-Bus connection failed: {}, trying fallback", e);
             }
@@ -147,7 +174,12 @@ impl WiFiP2pReceiver {
 
         // 创建连接
         let conn_path = client
-            .create_wifi_connection(&info.ssid, &info.psk, Some(&self.config.main_interface))
+            .create_wifi_connection(
+                &info.ssid,
+                &info.psk,
+                Some(&self.config.main_interface),
+                info.security(),
+            )
             .await?;
 
         // 查找设备
@@ -167,12 +199,21 @@ impl WiFiP2pReceiver {
             .wait_for_ip(&active_conn.as_ref(), Duration::from_secs(20))
             .await?;
 
+        let dhcp_server = client
+            .dhcp_server(&active_conn.as_ref())
+            .await
+            .unwrap_or_else(|e| {
+                debug!("Failed to read DHCP options: {}", e);
+                None
+            });
+
         // 记录活动连接
         let mut active = self.active_connection.lock().await;
         *active = Some(ActiveConnection {
             connection_name: conn_name,
             _connection_path: Some(conn_path.to_string()),
             used_p2p_mode: false,
+            dhcp_server,
         });
 
         Ok(ip)
@@ -183,21 +224,23 @@ impl WiFiP2pReceiver {
         debug!("Connecting via nmcli fallback");
 
         // 触发扫描
-        let _ = Command::new("nmcli")
-            .args([
+        let _ = self.runner.run(
+            "nmcli",
+            &[
                 "device",
                 "wifi",
                 "rescan",
                 "ifname",
                 &self.config.main_interface,
-            ])
-            .output();
+            ],
+        );
 
         tokio::time::sleep(Duration::from_secs(2)).await;
 
         // 尝试连接
-        let output = Command::new("nmcli")
-            .args([
+        let output = self.runner.run(
+            "nmcli",
+            &[
                 "device",
                 "wifi",
                 "connect",
@@ -206,12 +249,14 @@ impl WiFiP2pReceiver {
                 &info.psk,
                 "ifname",
                 &self.config.main_interface,
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("nmcli connection failed: {}", err));
+            ],
+        )?;
+
+        if !output.success {
+            return Err(anyhow::anyhow!(
+                "nmcli connection failed: {}",
+                output.stderr
+            ));
         }
 
         // 记录活动连接
@@ -220,6 +265,7 @@ impl WiFiP2pReceiver {
             connection_name: info.ssid.clone(),
             _connection_path: None,
             used_p2p_mode: false,
+            dhcp_server: None,
         });
 
         // 等待并获取 IP
@@ -245,32 +291,19 @@ impl WiFiP2pReceiver {
             }
 
             // 也尝试 nmcli 删除（备用）
-            let _ = Command::new("nmcli")
-                .args(["connection", "delete", &conn.connection_name])
-                .output();
+            let _ = self
+                .runner
+                .run("nmcli", &["connection", "delete", &conn.connection_name]);
         }
 
         Ok(())
     }
 
     /// 获取接口 IP 地址
-    fn get_interface_ip(&self, interface: &str) -> anyhow::Result<String> {
-        let output = Command::new("ip")
-            .args(["-o", "addr", "show", interface])
-            .output()?;
-
-        let stdout = String::from_utf8(output.stdout)?;
-        for line in stdout.lines() {
-            if line.contains("inet ") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if let Some(pos) = parts.iter().position(|&s| s == "inet")
-                    && let Some(ip) = parts
-                        .get(pos + 1)
-                        .and_then(|ip_range| ip_range.split('/').next())
-                {
-                    return Ok(ip.to_string());
-                }
-            }
+    pub(crate) fn get_interface_ip(&self, interface: &str) -> anyhow::Result<String> {
+        let output = self.runner.run("ip", &["-o", "addr", "show", interface])?;
+        if let Some(ip) = command::interface_ipv4(&output.stdout, interface) {
+            return Ok(ip);
         }
         Err(anyhow::anyhow!(
             "Could not find IP address for {}",
@@ -278,6 +311,48 @@ impl WiFiP2pReceiver {
         ))
     }
 
+    /// 查找发送端（热点）的 IP
+    ///
+    /// 即 [`Self::sender_candidates`] 中的第一个
+    pub async fn sender_ip(&self, info: &P2pInfo, local_ip: &str) -> String {
+        self.sender_candidates(info, local_ip)
+            .await
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| sender_addr::guess_gateway(local_ip))
+    }
+
+    /// 发送端（热点）的候选 IP，可能性大的在前
+    ///
+    /// 发送端发布的 `catshare.local` 排在最前，其后为 [`sender_addr::candidates`]
+    pub async fn sender_candidates(&self, info: &P2pInfo, local_ip: &str) -> Vec<String> {
+        let mut found = Vec::new();
+        if let Ok(local) = local_ip.parse()
+            && let Some(ip) = host::resolve_sender(local, HOSTNAME_TIMEOUT).await
+        {
+            info!("Sender address from {}: {}", host::SENDER_HOSTNAME, ip);
+            found.push(ip.to_string());
+        }
+
+        let dhcp_server = self
+            .active_connection
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|a| a.dhcp_server.clone());
+        for ip in sender_addr::candidates(
+            self.active_interface(),
+            local_ip,
+            &info.mac,
+            dhcp_server.as_deref(),
+        ) {
+            if !found.contains(&ip) {
+                found.push(ip);
+            }
+        }
+        found
+    }
+
     /// 检查是否已连接
     pub async fn is_connected(&self) -> bool {
         let active = self.active_connection.lock().await;
@@ -302,9 +377,9 @@ impl Drop for WiFiP2pReceiver {
         if let Ok(active) = self.active_connection.try_lock()
             && let Some(conn) = active.as_ref()
         {
-            let _ = Command::new("nmcli")
-                .args(["connection", "delete", &conn.connection_name])
-                .output();
+            let _ = self
+                .runner
+                .run("nmcli", &["connection", "delete", &conn.connection_name]);
         }
     }
 }
@@ -11,21 +11,36 @@
 //!
 //! - 使用 NM 时不需要额外权限（依赖 PolicyKit）
 //! - 5GHz 频段优先（更快速度）
+//! - 热点接口已连接 WiFi 时，创建热点会断开它：先用 [`WiFiP2pSender::busy_connection`]
+//!   检查，再决定换用空闲网卡（[`WiFiP2pSender::use_interface`]，按
+//!   [`pick_hotspot_adapter`] 挑选）或在热点关闭后恢复（[`WiFiP2pSender::restore_after`]）
+//! - 热备（[`WiFiP2pSender::prepare_group`]）预先在 NM 中添加热点连接但不激活，
+//!   下次 [`WiFiP2pSender::create_group`] 的策略和网卡都相同时只需激活，省去生成配置的时间
 
-use std::process::Command;
 use std::sync::Arc;
 use std::time::Duration;
 
 use log::{debug, info, warn};
 use tokio::sync::Mutex;
 
+use zbus::zvariant::OwnedObjectPath;
+
 use crate::wifi::P2pInfo;
-use crate::wifi::nm_dbus::NmClient;
+use crate::wifi::adapter::pick_hotspot_adapter;
+use crate::wifi::busy::InterfaceBusy;
+use crate::wifi::command::{self, CommandRunner};
+use crate::wifi::credentials::{self, CredentialPolicy, HotspotSecurity};
+use crate::wifi::helper;
+use crate::wifi::nm_dbus::{NmClient, NmPermissionDenied, WifiDevice};
+use crate::wifi::orphans::CONNECTION_PREFIX;
+use crate::wifi::wpa;
 
 /// WiFi P2P 配置
 pub struct P2pConfig {
     /// 网络接口名称 (通常是 wlan0)
     pub interface: String,
+    /// 显式指定的热点网卡，设置后代替 `interface` 且不再挑选空闲网卡
+    pub hotspot_interface: Option<String>,
     /// SSID 前缀
     pub ssid_prefix: String,
     /// 是否使用 5GHz
@@ -36,6 +51,7 @@ impl Default for P2pConfig {
     fn default() -> Self {
         Self {
             interface: "wlan0".to_string(),
+            hotspot_interface: None,
             ssid_prefix: "DIRECT-".to_string(),
             use_5ghz: true,
         }
@@ -48,10 +64,29 @@ struct ActiveHotspot {
     _connection_path: Option<String>,
 }
 
+/// 已添加但未激活的热点连接（见 [`WiFiP2pSender::prepare_group`]）
+struct PreparedHotspot {
+    /// 创建时的凭据策略，与本次热点的策略相同才能复用
+    policy: CredentialPolicy,
+    interface: String,
+    ssid: String,
+    psk: String,
+    security: HotspotSecurity,
+    connection_name: String,
+    connection_path: OwnedObjectPath,
+}
+
 pub struct WiFiP2pSender {
     config: P2pConfig,
     nm_client: Arc<Mutex<Option<NmClient>>>,
     active_hotspot: Arc<Mutex<Option<ActiveHotspot>>>,
+    /// 热备的热点连接
+    prepared: Mutex<Option<PreparedHotspot>>,
+    /// 本次热点改用的网卡（见 [`Self::use_interface`]）
+    interface_override: std::sync::Mutex<Option<String>>,
+    /// 热点关闭后要重新激活的连接（见 [`Self::restore_after`]）
+    restore: Mutex<Option<(WifiDevice, OwnedObjectPath)>>,
+    runner: Arc<dyn CommandRunner>,
 }
 
 impl WiFiP2pSender {
@@ -63,6 +98,10 @@ impl WiFiP2pSender {
             },
             nm_client: Arc::new(Mutex::new(None)),
             active_hotspot: Arc::new(Mutex::new(None)),
+            prepared: Mutex::new(None),
+            interface_override: std::sync::Mutex::new(None),
+            restore: Mutex::new(None),
+            runner: helper::default_runner(),
         }
     }
 
@@ -71,9 +110,19 @@ impl WiFiP2pSender {
             config,
             nm_client: Arc::new(Mutex::new(None)),
             active_hotspot: Arc::new(Mutex::new(None)),
+            prepared: Mutex::new(None),
+            interface_override: std::sync::Mutex::new(None),
+            restore: Mutex::new(None),
+            runner: helper::default_runner(),
         }
     }
 
+    /// 替换外部命令执行器（测试时注入 [`FakeRunner`](command::FakeRunner)）
+    pub fn with_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
     /// 初始化 NM 客户端
     async fn ensure_nm_client(&self) -> anyhow::Result<()> {
         let mut client = self.nm_client.lock().await;
@@ -92,53 +141,59 @@ impl WiFiP2pSender {
         Ok(())
     }
 
-    /// 生成随机 SSID 和 PSK
-    fn generate_credentials(&self) -> (String, String) {
-        let random_chars: String = (0..8)
-            .map(|_| {
-                let idx = rand::random::<usize>() % 36;
-                if idx < 10 {
-                    (b'0' + idx as u8) as char
-                } else {
-                    (b'a' + (idx - 10) as u8) as char
-                }
-            })
-            .collect();
-
-        let ssid = format!("{}{}", self.config.ssid_prefix, random_chars);
-        let psk: String = (0..8)
-            .map(|_| {
-                let idx = rand::random::<usize>() % 36;
-                if idx < 10 {
-                    (b'0' + idx as u8) as char
-                } else {
-                    (b'a' + (idx - 10) as u8) as char
-                }
-            })
-            .collect();
-
-        (ssid, psk)
+    /// 按 `policy` 生成随机 SSID 和 PSK
+    fn generate_credentials(&self, policy: &CredentialPolicy) -> (String, String) {
+        policy.generate(&self.config.ssid_prefix)
     }
 
     /// 创建 WiFi P2P 组（热点模式）
     ///
-    /// 返回 P2P 信息，包含 SSID、密码和端口
-    pub async fn create_group(&self, port: i32) -> anyhow::Result<P2pInfo> {
-        let (ssid, psk) = self.generate_credentials();
-
+    /// 返回 P2P 信息，包含 SSID、密码和端口。`policy` 应已按对端降级
+    /// （见 [`CredentialPolicy::for_peer`]）；网卡不支持 SAE/PMF 或 NM 无法激活 WPA3 热点时
+    /// 退回 WPA2-PSK，wpa_cli 创建的 P2P 组也总是 WPA2-PSK。
+    /// 有策略相同的热备连接（[`Self::prepare_group`]）时直接激活它。
+    #[tracing::instrument(skip_all, fields(port = port))]
+    pub async fn create_group(
+        &self,
+        port: i32,
+        policy: &CredentialPolicy,
+    ) -> anyhow::Result<P2pInfo> {
         // 获取 MAC 地址
         let mac = self.get_mac_address()?;
 
+        if let Some(info) = self.activate_prepared(port, policy, &mac).await {
+            return Ok(info);
+        }
+
+        let (ssid, psk) = self.generate_credentials(policy);
+        let mut security = self.security_for(policy);
+
         // 尝试使用 NmClient (D-Bus) 创建热点
-        match self.create_hotspot_nm(&ssid, &psk).await {
+        let band = self.band_for(policy);
+        let mut result = self.create_hotspot_nm(&ssid, &psk, band, security).await;
+        if security == HotspotSecurity::Wpa3Sae
+            && let Err(e) = &result
+            && !e.is::<NmPermissionDenied>()
+        {
+            // 驱动声明支持 SAE，但 AP 模式下不一定能用
+            warn!("WPA3 hotspot failed: {}, retrying with WPA2-PSK", e);
+            security = HotspotSecurity::Wpa2Psk;
+            result = self.create_hotspot_nm(&ssid, &psk, band, security).await;
+        }
+        match result {
             Ok(_) => {
-                info!("Hotspot created via NetworkManager D-Bus");
+                info!("Hotspot created via NetworkManager D-Bus ({:?})", security);
             }
             Err(e) => {
                 warn!("NM D-Bus hotspot failed: {}, trying wpa_cli", e);
+                security = HotspotSecurity::Wpa2Psk;
                 // 退回到 wpa_cli
                 if let Err(wpa_err) = self.create_p2p_group_wpa(&ssid, &psk).await {
                     warn!("wpa_cli also failed: {}", wpa_err);
+                    // 授权被拒时原样返回，前端据此说明需要的 polkit 授权
+                    if e.is::<NmPermissionDenied>() {
+                        return Err(e);
+                    }
                     return Err(anyhow::anyhow!(
                         "Failed to create hotspot: NM={}, wpa_cli={}",
                         e,
@@ -148,11 +203,146 @@ impl WiFiP2pSender {
             }
         }
 
-        Ok(P2pInfo::new(ssid, psk, mac, port))
+        let mut info = P2pInfo::new(ssid, psk, mac, port);
+        if security != HotspotSecurity::Wpa2Psk {
+            info.security = Some(security);
+        }
+        Ok(info)
+    }
+
+    /// 预先按 `policy` 在 NM 中添加热点连接但不激活（热备），替换之前准备的连接
+    ///
+    /// 未激活的连接不会发出信标，接收端看不到这个热点。只支持 NetworkManager，
+    /// wpa_cli 的 P2P 组无法预先创建。
+    pub async fn prepare_group(&self, policy: &CredentialPolicy) -> anyhow::Result<()> {
+        self.discard_prepared().await;
+
+        let (ssid, psk) = self.generate_credentials(policy);
+        let security = self.security_for(policy);
+        let band = self.band_for(policy);
+        let (connection_name, connection_path) =
+            self.add_hotspot_nm(&ssid, &psk, band, security).await?;
+        info!(
+            "Hotspot connection {} prepared ({:?}, band {})",
+            connection_name, security, band
+        );
+        *self.prepared.lock().await = Some(PreparedHotspot {
+            policy: *policy,
+            interface: self.interface(),
+            ssid,
+            psk,
+            security,
+            connection_name,
+            connection_path,
+        });
+        Ok(())
+    }
+
+    /// 删除热备的热点连接（没有时什么也不做）
+    pub async fn discard_prepared(&self) {
+        if let Some(prepared) = self.prepared.lock().await.take() {
+            self.delete_connection_nm(&prepared.connection_name).await;
+        }
+    }
+
+    /// 删除 NM 中的连接，失败只忽略（残留的连接下次启动时由 [`crate::wifi::cleanup_orphans`] 清理）
+    async fn delete_connection_nm(&self, name: &str) {
+        if self.ensure_nm_client().await.is_err() {
+            return;
+        }
+        let client_guard = self.nm_client.lock().await;
+        if let Some(client) = client_guard.as_ref() {
+            let _ = client.delete_connection_by_name(name).await;
+        }
+    }
+
+    /// 激活热备的连接，策略或网卡不同、激活失败时删除它并返回 None（改走完整流程）
+    async fn activate_prepared(
+        &self,
+        port: i32,
+        policy: &CredentialPolicy,
+        mac: &str,
+    ) -> Option<P2pInfo> {
+        let prepared = self.prepared.lock().await.take()?;
+        if prepared.policy != *policy || prepared.interface != self.interface() {
+            debug!(
+                "Prepared hotspot {} does not match this send, discarding",
+                prepared.connection_name
+            );
+            self.delete_connection_nm(&prepared.connection_name).await;
+            return None;
+        }
+
+        let PreparedHotspot {
+            ssid,
+            psk,
+            security,
+            connection_name,
+            connection_path,
+            ..
+        } = prepared;
+        if let Err(e) = self
+            .activate_hotspot_nm(connection_name.clone(), connection_path)
+            .await
+        {
+            warn!("Prepared hotspot failed to activate: {}, recreating", e);
+            self.delete_connection_nm(&connection_name).await;
+            return None;
+        }
+        info!(
+            "Hotspot activated from prepared connection ({:?})",
+            security
+        );
+
+        let mut info = P2pInfo::new(ssid, psk, mac.to_string(), port);
+        if security != HotspotSecurity::Wpa2Psk {
+            info.security = Some(security);
+        }
+        Some(info)
+    }
+
+    /// `policy` 要求的认证方式，网卡不支持 SAE/PMF 时退回 WPA2-PSK
+    fn security_for(&self, policy: &CredentialPolicy) -> HotspotSecurity {
+        let security = policy.security();
+        if security == HotspotSecurity::Wpa3Sae && !self.supports_wpa3() {
+            info!(
+                "{} does not support SAE/PMF, falling back to WPA2-PSK",
+                self.interface()
+            );
+            return HotspotSecurity::Wpa2Psk;
+        }
+        security
+    }
+
+    /// 热点频段（NM 的 `band` 取值）
+    fn band_for(&self, policy: &CredentialPolicy) -> &'static str {
+        if self.config.use_5ghz && !policy.only_2ghz {
+            "a"
+        } else {
+            "bg"
+        }
     }
 
     /// 使用 NetworkManager D-Bus 创建热点
-    async fn create_hotspot_nm(&self, ssid: &str, psk: &str) -> anyhow::Result<()> {
+    async fn create_hotspot_nm(
+        &self,
+        ssid: &str,
+        psk: &str,
+        band: &str,
+        security: HotspotSecurity,
+    ) -> anyhow::Result<()> {
+        let (conn_name, conn_path) = self.add_hotspot_nm(ssid, psk, band, security).await?;
+        self.activate_hotspot_nm(conn_name, conn_path).await
+    }
+
+    /// 在 NM 中添加热点连接（不激活），返回连接名和路径
+    async fn add_hotspot_nm(
+        &self,
+        ssid: &str,
+        psk: &str,
+        band: &str,
+        security: HotspotSecurity,
+    ) -> anyhow::Result<(String, OwnedObjectPath)> {
         self.ensure_nm_client().await?;
 
         let client_guard = self.nm_client.lock().await;
@@ -167,18 +357,33 @@ impl WiFiP2pSender {
         );
         let _ = client.delete_connection_by_name(&conn_name).await;
 
-        let band = if self.config.use_5ghz { "a" } else { "bg" };
-
         // 创建热点连接配置
+        let interface = self.interface();
         let conn_path = client
-            .create_hotspot(ssid, psk, band, &self.config.interface)
+            .create_hotspot(ssid, psk, band, &interface, security)
             .await?;
+        Ok((conn_name, conn_path))
+    }
+
+    /// 激活 NM 中的热点连接并等待完成
+    async fn activate_hotspot_nm(
+        &self,
+        conn_name: String,
+        conn_path: OwnedObjectPath,
+    ) -> anyhow::Result<()> {
+        self.ensure_nm_client().await?;
+
+        let client_guard = self.nm_client.lock().await;
+        let client = client_guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("NM client not initialized"))?;
 
         // 查找设备
+        let interface = self.interface();
         let device = client
-            .find_wifi_device(Some(&self.config.interface))
+            .find_wifi_device(Some(&interface))
             .await?
-            .ok_or_else(|| anyhow::anyhow!("WiFi device {} not found", self.config.interface))?;
+            .ok_or_else(|| anyhow::anyhow!("WiFi device {} not found", interface))?;
 
         // 激活连接
         let active_conn = client
@@ -203,18 +408,17 @@ impl WiFiP2pSender {
 
     /// 使用 wpa_cli 创建 P2P 组 (备用方案)
     async fn create_p2p_group_wpa(&self, ssid: &str, psk: &str) -> anyhow::Result<()> {
-        let output = Command::new("wpa_cli")
-            .args([
-                "-i",
-                &self.config.interface,
-                "p2p_group_add",
-                &format!("persistent ssid={} passphrase={}", ssid, psk),
-            ])
-            .output()?;
-
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("wpa_cli p2p_group_add failed: {}", err));
+        // SSID 前缀可配置，含空格等字符时不能交给 wpa_cli
+        let arg = wpa::group_add_arg(ssid, psk)?;
+        let output = self
+            .runner
+            .run("wpa_cli", &["-i", &self.interface(), "p2p_group_add", &arg])?;
+
+        if !output.success {
+            return Err(anyhow::anyhow!(
+                "wpa_cli p2p_group_add failed: {}",
+                output.stderr
+            ));
         }
 
         // 等待组创建完成
@@ -249,23 +453,124 @@ impl WiFiP2pSender {
         }
 
         // 也尝试 wpa_cli 停止（兼容性）
-        let _ = Command::new("wpa_cli")
-            .args(["-i", &self.config.interface, "p2p_group_remove", "*"])
-            .output();
+        let _ = self.runner.run(
+            "wpa_cli",
+            &["-i", &self.interface(), "p2p_group_remove", "*"],
+        );
+
+        // 重新连接被热点断开的网络
+        if let Some((device, connection)) = self.restore.lock().await.take()
+            && let Ok(()) = self.ensure_nm_client().await
+        {
+            let client_guard = self.nm_client.lock().await;
+            if let Some(client) = client_guard.as_ref() {
+                match client
+                    .activate_connection(&connection.as_ref(), &device)
+                    .await
+                {
+                    Ok(_) => info!("Restored previous connection on {}", device.interface),
+                    Err(e) => warn!(
+                        "Failed to restore previous connection on {}: {}",
+                        device.interface, e
+                    ),
+                }
+            }
+        }
+        self.interface_override.lock().unwrap().take();
 
         Ok(())
     }
 
+    /// 热点接口上已有的 WiFi 连接（创建热点会断开它），没有时为 None
+    ///
+    /// 同时按 [`pick_hotspot_adapter`] 挑选一块空闲网卡作为 [`InterfaceBusy::alternative`]
+    /// （显式指定了热点网卡时不挑选）。cattysend 自己的连接（残留的热点等）不算占用。
+    pub async fn busy_connection(&self) -> anyhow::Result<Option<InterfaceBusy>> {
+        self.ensure_nm_client().await?;
+        let client_guard = self.nm_client.lock().await;
+        let client = client_guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("NM client not initialized"))?;
+
+        let interface = self.interface();
+        let devices = client.get_wifi_devices().await?;
+        let Some(device) = devices.iter().find(|d| d.interface == interface) else {
+            return Ok(None);
+        };
+        let Some((connection, _)) = client.device_connection(device).await? else {
+            return Ok(None);
+        };
+        if connection.starts_with(CONNECTION_PREFIX) {
+            return Ok(None);
+        }
+
+        let ssid = client.active_ssid(device).await.ok().flatten();
+        let alternative = if self.config.hotspot_interface.is_some() {
+            None
+        } else {
+            pick_hotspot_adapter(client.adapters().await?, &interface).map(|a| a.interface)
+        };
+        Ok(Some(InterfaceBusy {
+            interface,
+            connection,
+            ssid,
+            alternative,
+        }))
+    }
+
+    /// 在 `interface` 上创建热点，直到 [`Self::stop_group`]
+    pub fn use_interface(&self, interface: &str) {
+        info!("Using {} for the hotspot", interface);
+        *self.interface_override.lock().unwrap() = Some(interface.to_string());
+    }
+
+    /// 在 [`Self::stop_group`] 时重新激活 `busy` 中的连接
+    pub async fn restore_after(&self, busy: &InterfaceBusy) -> anyhow::Result<()> {
+        self.ensure_nm_client().await?;
+        let client_guard = self.nm_client.lock().await;
+        let client = client_guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("NM client not initialized"))?;
+
+        let device = client
+            .find_wifi_device(Some(&busy.interface))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("WiFi device {} not found", busy.interface))?;
+        let Some((_, connection)) = client.device_connection(&device).await? else {
+            return Ok(());
+        };
+        *self.restore.lock().await = Some((device, connection));
+        Ok(())
+    }
+
+    /// 热点网卡是否支持 WPA3-SAE，见 [`credentials::probe_wpa3`]
+    pub fn supports_wpa3(&self) -> bool {
+        credentials::probe_wpa3(self.runner.as_ref(), &self.interface())
+    }
+
+    /// 热点所在的网络接口
+    ///
+    /// [`Self::use_interface`] 指定的网卡优先，其次是 [`P2pConfig::hotspot_interface`]
+    pub fn interface(&self) -> String {
+        self.interface_override
+            .lock()
+            .unwrap()
+            .clone()
+            .or_else(|| self.config.hotspot_interface.clone())
+            .unwrap_or_else(|| self.config.interface.clone())
+    }
+
     /// 获取接口 MAC 地址
     fn get_mac_address(&self) -> anyhow::Result<String> {
         // 尝试从 sysfs 读取
-        let path = format!("/sys/class/net/{}/address", self.config.interface);
+        let interface = self.interface();
+        let path = format!("/sys/class/net/{}/address", interface);
         if let Ok(mac) = std::fs::read_to_string(&path) {
             return Ok(mac.trim().to_uppercase());
         }
 
         // 尝试读取 p2p 接口
-        let p2p_path = format!("/sys/class/net/p2p-dev-{}/address", self.config.interface);
+        let p2p_path = format!("/sys/class/net/p2p-dev-{}/address", interface);
         if let Ok(mac) = std::fs::read_to_string(&p2p_path) {
             return Ok(mac.trim().to_uppercase());
         }
@@ -274,23 +579,27 @@ impl WiFiP2pSender {
         Ok("02:00:00:00:00:00".to_string())
     }
 
+    /// 热点实际所在的接口
+    ///
+    /// wpa_cli 创建的 P2P 组在独立的 p2p-<接口>-N 上，此时原接口仍属于原有连接
+    pub fn hotspot_interface(&self) -> String {
+        let interface = self.interface();
+        self.runner
+            .run("ip", &["-o", "addr", "show"])
+            .ok()
+            .and_then(|output| group_interface(&output.stdout, &interface).map(str::to_string))
+            .unwrap_or(interface)
+    }
+
     /// 获取热点的 IP 地址
     pub fn get_hotspot_ip(&self) -> anyhow::Result<String> {
         // 通常热点的 IP 是 10.42.0.1 (nmcli) 或 192.168.49.1 (wpa_supplicant)
-        let output = Command::new("ip").args(["-o", "addr", "show"]).output()?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            if line.contains(&self.config.interface) && line.contains("inet ") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if let Some(pos) = parts.iter().position(|&s| s == "inet")
-                    && let Some(ip) = parts
-                        .get(pos + 1)
-                        .and_then(|ip_cidr| ip_cidr.split('/').next())
-                {
-                    return Ok(ip.to_string());
-                }
-            }
+        let output = self.runner.run("ip", &["-o", "addr", "show"])?;
+
+        let interface = self.interface();
+        let interface = group_interface(&output.stdout, &interface).unwrap_or(&interface);
+        if let Some(ip) = command::interface_ipv4(&output.stdout, interface) {
+            return Ok(ip);
         }
 
         // 返回默认 IP
@@ -298,6 +607,15 @@ impl WiFiP2pSender {
     }
 }
 
+/// 在 `ip -o addr show` 的输出中查找 `interface` 的 P2P 组接口
+fn group_interface<'a>(output: &'a str, interface: &str) -> Option<&'a str> {
+    let prefix = format!("p2p-{}-", interface);
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .find(|iface| iface.starts_with(&prefix))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,7 +623,7 @@ mod tests {
     #[test]
     fn test_generate_credentials() {
         let sender = WiFiP2pSender::new("wlan0");
-        let (ssid, psk) = sender.generate_credentials();
+        let (ssid, psk) = sender.generate_credentials(&CredentialPolicy::default());
 
         assert!(ssid.starts_with("DIRECT-"));
         assert_eq!(ssid.len(), 15); // "DIRECT-" (7) + 8 chars
@@ -0,0 +1,226 @@
+//! 发送端（热点 / P2P GO）地址发现（接收端）
+//!
+//! 热点的地址因实现而异：NetworkManager 共享热点是 10.42.0.1，
+//! wpa_cli 创建的 P2P 组在 192.168.49.x 中，GO 不一定是 `.1`。接入热点后按以下顺序查找：
+//!
+//! 1. DHCP 租约中的服务器地址（NetworkManager 连接时记录）
+//! 2. 该接口上路由的网关（`/proc/net/route`）
+//! 3. 邻居表中 MAC 与 [`P2pInfo::mac`](crate::wifi::P2pInfo) 相符的条目（`/proc/net/arp`）
+//! 4. 本机地址所在网段的 `.1`
+
+use log::{debug, info};
+use std::net::Ipv4Addr;
+
+const ROUTE_TABLE: &str = "/proc/net/route";
+const NEIGHBOR_TABLE: &str = "/proc/net/arp";
+
+/// 查找发送端 IP
+///
+/// `dhcp_server` 为 DHCP 租约中的服务器地址（没有时传 `None`），
+/// `go_mac` 为 P2P 信息中发送端的 MAC。
+pub fn resolve(interface: &str, local_ip: &str, go_mac: &str, dhcp_server: Option<&str>) -> String {
+    candidates(interface, local_ip, go_mac, dhcp_server)
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| guess_gateway(local_ip))
+}
+
+/// 按上述顺序列出所有可能的发送端 IP（去重，最后一个总是推断的 `.1`）
+///
+/// 首选地址不可达时，接收端依次尝试其余候选。
+pub fn candidates(
+    interface: &str,
+    local_ip: &str,
+    go_mac: &str,
+    dhcp_server: Option<&str>,
+) -> Vec<String> {
+    let mut found = Vec::new();
+    if let Some(server) = dhcp_server.and_then(|s| s.parse::<Ipv4Addr>().ok()) {
+        info!("Sender address from DHCP server: {}", server);
+        found.push(server.to_string());
+    }
+
+    if let Ok(table) = std::fs::read_to_string(ROUTE_TABLE)
+        && let Some(gateway) = parse_route_gateway(&table, interface)
+    {
+        info!("Sender address from gateway of {}: {}", interface, gateway);
+        found.push(gateway.to_string());
+    }
+
+    if let Ok(table) = std::fs::read_to_string(NEIGHBOR_TABLE)
+        && let Some(neighbor) = parse_neighbor(&table, interface, go_mac)
+    {
+        info!(
+            "Sender address from neighbor table ({}): {}",
+            go_mac, neighbor
+        );
+        found.push(neighbor.to_string());
+    }
+
+    let guess = guess_gateway(local_ip);
+    if found.is_empty() {
+        debug!("Sender address not found, guessing {}", guess);
+    }
+    found.push(guess);
+    dedup(found)
+}
+
+/// 去掉重复的地址，保留首次出现的顺序
+fn dedup(addrs: Vec<String>) -> Vec<String> {
+    let mut unique = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        if !unique.contains(&addr) {
+            unique.push(addr);
+        }
+    }
+    unique
+}
+
+/// 从本机 IP 推断网关（同网段的 `.1`）
+pub fn guess_gateway(local_ip: &str) -> String {
+    match local_ip.parse::<Ipv4Addr>() {
+        Ok(ip) => {
+            let [a, b, c, _] = ip.octets();
+            Ipv4Addr::new(a, b, c, 1).to_string()
+        }
+        Err(_) => "192.168.49.1".to_string(),
+    }
+}
+
+/// 解析 `/proc/net/route`，取 `interface` 上的网关（优先默认路由）
+///
+/// 地址以小端十六进制表示，如 `0131A8C0` 即 192.168.49.1。
+fn parse_route_gateway(table: &str, interface: &str) -> Option<Ipv4Addr> {
+    let mut fallback = None;
+    for line in table.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [iface, destination, gateway, ..] = fields[..] else {
+            continue;
+        };
+        if iface != interface {
+            continue;
+        }
+        let Ok(gateway) = u32::from_str_radix(gateway, 16) else {
+            continue;
+        };
+        if gateway == 0 {
+            continue;
+        }
+        let gateway = Ipv4Addr::from(gateway.to_le_bytes());
+        if destination == "00000000" {
+            return Some(gateway);
+        }
+        fallback.get_or_insert(gateway);
+    }
+    fallback
+}
+
+/// 解析 `/proc/net/arp`，找 `interface` 上 MAC 为 `mac` 的邻居
+fn parse_neighbor(table: &str, interface: &str, mac: &str) -> Option<Ipv4Addr> {
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [ip, _hw_type, _flags, hw_addr, _mask, device] = fields[..] else {
+            return None;
+        };
+        (device == interface && same_station(hw_addr, mac))
+            .then(|| ip.parse().ok())
+            .flatten()
+    })
+}
+
+/// 两个 MAC 是否属于同一设备
+///
+/// P2P 信息里通常是 P2P 设备地址，GO 接口地址可能只多了"本地管理"位，比较时忽略该位。
+fn same_station(a: &str, b: &str) -> bool {
+    let (Some(a), Some(b)) = (parse_mac(a), parse_mac(b)) else {
+        return false;
+    };
+    a != [0; 6] && a[0] | 0x02 == b[0] | 0x02 && a[1..] == b[1..]
+}
+
+fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let mut bytes = [0u8; 6];
+    let mut parts = mac.split(':');
+    for byte in &mut bytes {
+        *byte = u8::from_str_radix(parts.next()?, 16).ok()?;
+    }
+    parts.next().is_none().then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTES: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+wlan0\t0031A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+wlan0\t00000000\t3131A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+";
+
+    const NEIGHBORS: &str = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         11:22:33:44:55:66     *        eth0
+192.168.49.7     0x1         0x0         00:00:00:00:00:00     *        wlan0
+192.168.49.49    0x1         0x2         ba:dc:0f:fe:e0:01     *        wlan0
+";
+
+    #[test]
+    fn test_route_gateway_for_interface() {
+        assert_eq!(
+            parse_route_gateway(ROUTES, "wlan0"),
+            Some(Ipv4Addr::new(192, 168, 49, 49))
+        );
+        assert_eq!(
+            parse_route_gateway(ROUTES, "eth0"),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(parse_route_gateway(ROUTES, "wlan1"), None);
+    }
+
+    #[test]
+    fn test_neighbor_matches_go_mac() {
+        // 只差"本地管理"位也算同一设备
+        assert_eq!(
+            parse_neighbor(NEIGHBORS, "wlan0", "B8:DC:0F:FE:E0:01"),
+            Some(Ipv4Addr::new(192, 168, 49, 49))
+        );
+        assert_eq!(
+            parse_neighbor(NEIGHBORS, "wlan0", "11:22:33:44:55:66"),
+            None
+        );
+        assert_eq!(
+            parse_neighbor(NEIGHBORS, "wlan0", "00:00:00:00:00:00"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_guess_gateway() {
+        assert_eq!(guess_gateway("192.168.49.23"), "192.168.49.1");
+        assert_eq!(guess_gateway("127.0.0.1"), "127.0.0.1");
+        assert_eq!(guess_gateway("not-an-ip"), "192.168.49.1");
+    }
+
+    #[test]
+    fn test_dhcp_server_takes_precedence() {
+        assert_eq!(
+            resolve("wlan0", "192.168.49.23", "", Some("192.168.49.200")),
+            "192.168.49.200"
+        );
+    }
+
+    #[test]
+    fn test_candidates_end_with_guess() {
+        let found = candidates(
+            "cattysend-none0",
+            "192.168.49.23",
+            "",
+            Some("192.168.49.200"),
+        );
+        assert_eq!(found, ["192.168.49.200", "192.168.49.1"]);
+        // 与推断地址相同时不重复
+        let found = candidates("cattysend-none0", "192.168.49.23", "", Some("192.168.49.1"));
+        assert_eq!(found, ["192.168.49.1"]);
+    }
+}
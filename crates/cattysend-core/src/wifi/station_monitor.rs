@@ -0,0 +1,343 @@
+//! 热点客户端监控（发送端）
+//!
+//! 首选 wpa_supplicant 的 D-Bus 信号：NetworkManager 的热点和 wpa_cli 创建的 P2P 组
+//! 都由 wpa_supplicant 负责 AP 侧的关联，客户端完成认证时发出 `StaAuthorized`
+//! （NetworkManager 本身不在 D-Bus 上公开 AP 模式下已关联的站点）。
+//! 信号只带 MAC，IP 从下面两个来源取；wpa_supplicant 不在 D-Bus 上时只靠轮询它们：
+//!
+//! 1. NM 共享连接启动的 dnsmasq 租约文件
+//!    (`/var/lib/NetworkManager/dnsmasq-<iface>.leases`)，已过期的租约不算
+//! 2. `/proc/net/arp`（覆盖 wpa_cli 创建的 `p2p-<iface>-N` 组接口，
+//!    以及对端使用静态 IP 的情况）
+//!
+//! 租约文件和 ARP 表会留着上一次热点的条目，所以创建热点之前先用 [`StationSnapshot::take`]
+//! 记下已有的客户端：快照里的客户端只有收到 `StaAuthorized` 之后才算接入。
+
+use futures_util::StreamExt;
+use log::debug;
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use zbus::{Connection, MatchRule, MessageStream, proxy};
+
+/// 轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ARP 表中表示条目已完成解析的标志位 (ATF_COM)
+const ATF_COM: u32 = 0x2;
+
+/// NetworkManager 存放 dnsmasq 租约文件的目录
+const NM_STATE_DIR: &str = "/var/lib/NetworkManager";
+
+const ARP_TABLE: &str = "/proc/net/arp";
+
+const WPA_SUPPLICANT_SERVICE: &str = "fi.w1.wpa_supplicant1";
+
+const WPA_SUPPLICANT_INTERFACE: &str = "fi.w1.wpa_supplicant1.Interface";
+
+/// wpa_supplicant 网络接口代理
+#[proxy(
+    interface = "fi.w1.wpa_supplicant1.Interface",
+    default_service = "fi.w1.wpa_supplicant1"
+)]
+trait SupplicantInterface {
+    /// 网络接口名
+    #[zbus(property)]
+    fn ifname(&self) -> zbus::Result<String>;
+}
+
+/// 已接入热点的客户端
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StationInfo {
+    /// 客户端 MAC 地址（大写）
+    pub mac: String,
+    /// 客户端 IP 地址
+    pub ip: String,
+}
+
+/// 创建热点之前已出现在租约文件和 ARP 表中的客户端
+#[derive(Debug, Clone, Default)]
+pub struct StationSnapshot {
+    macs: HashSet<String>,
+}
+
+impl StationSnapshot {
+    /// 读取所有接口的租约文件和整张 ARP 表（热点接口此时可能还没确定）
+    pub fn take() -> Self {
+        let now = unix_now();
+        let mut stations = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(NM_STATE_DIR) {
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if !(name.starts_with("dnsmasq-") && name.ends_with(".leases")) {
+                    continue;
+                }
+                if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                    stations.extend(parse_dnsmasq_leases(&content, now));
+                }
+            }
+        }
+        if let Ok(content) = std::fs::read_to_string(ARP_TABLE) {
+            stations.extend(parse_arp_table(&content, |_| true));
+        }
+        Self::from_stations(stations)
+    }
+
+    fn from_stations(stations: impl IntoIterator<Item = StationInfo>) -> Self {
+        Self {
+            macs: stations.into_iter().map(|station| station.mac).collect(),
+        }
+    }
+}
+
+/// 判断哪些客户端是新接入的
+#[derive(Debug, Default)]
+struct StationTracker {
+    snapshot: StationSnapshot,
+    /// 收到过 `StaAuthorized` 的 MAC
+    authorized: HashSet<String>,
+    /// 已经上报过的 MAC
+    reported: HashSet<String>,
+}
+
+impl StationTracker {
+    fn new(snapshot: StationSnapshot) -> Self {
+        Self {
+            snapshot,
+            ..Default::default()
+        }
+    }
+
+    fn authorize(&mut self, mac: String) {
+        self.authorized.insert(mac);
+    }
+
+    /// 从本次轮询结果中挑出需要上报的客户端
+    fn joined(&mut self, stations: Vec<StationInfo>) -> Vec<StationInfo> {
+        stations
+            .into_iter()
+            .filter(|station| {
+                let stale = self.snapshot.macs.contains(&station.mac)
+                    && !self.authorized.contains(&station.mac);
+                !stale && self.reported.insert(station.mac.clone())
+            })
+            .collect()
+    }
+}
+
+/// 启动热点客户端监控
+///
+/// `snapshot` 为创建热点之前取的快照。每发现一个新客户端发送一次事件；
+/// 接收端 drop 后后台任务自动退出。
+pub fn spawn_station_monitor(
+    interface: String,
+    snapshot: StationSnapshot,
+) -> mpsc::Receiver<StationInfo> {
+    let (tx, rx) = mpsc::channel(8);
+    let lease_path = format!("{}/dnsmasq-{}.leases", NM_STATE_DIR, interface);
+    let group_prefix = format!("p2p-{}-", interface);
+
+    tokio::spawn(async move {
+        let (authorized_tx, mut authorized_rx) = mpsc::channel(8);
+        let signals = tokio::spawn({
+            let interface = interface.clone();
+            async move {
+                if let Err(e) = forward_authorized(&interface, authorized_tx).await {
+                    debug!("wpa_supplicant station signals unavailable: {}", e);
+                }
+            }
+        });
+        let mut tracker = StationTracker::new(snapshot);
+        let mut ticker = tokio::time::interval(POLL_INTERVAL);
+
+        while !tx.is_closed() {
+            // 收到信号后立即轮询一次，取该客户端的 IP
+            tokio::select! {
+                _ = ticker.tick() => {}
+                Some(mac) = authorized_rx.recv() => {
+                    debug!("Station authorized on hotspot: {}", mac);
+                    tracker.authorize(mac);
+                }
+            }
+
+            let mut stations = tokio::fs::read_to_string(&lease_path)
+                .await
+                .map(|content| parse_dnsmasq_leases(&content, unix_now()))
+                .unwrap_or_default();
+            if let Ok(content) = tokio::fs::read_to_string(ARP_TABLE).await {
+                stations.extend(parse_arp_table(&content, |dev| {
+                    dev == interface || dev.starts_with(&group_prefix)
+                }));
+            }
+
+            for station in tracker.joined(stations) {
+                debug!("Station joined hotspot: {} ({})", station.mac, station.ip);
+                if tx.send(station).await.is_err() {
+                    break;
+                }
+            }
+        }
+        signals.abort();
+    });
+
+    rx
+}
+
+/// 转发 `interface` 及其 P2P 组接口上的 `StaAuthorized` 信号（大写 MAC）
+async fn forward_authorized(interface: &str, tx: mpsc::Sender<String>) -> zbus::Result<()> {
+    let connection = Connection::system().await?;
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(WPA_SUPPLICANT_SERVICE)?
+        .interface(WPA_SUPPLICANT_INTERFACE)?
+        .member("StaAuthorized")?
+        .build();
+    let mut messages = MessageStream::for_match_rule(rule, &connection, None).await?;
+    let group_prefix = format!("p2p-{}-", interface);
+
+    while let Some(message) = messages.next().await {
+        let message = message?;
+        let header = message.header();
+        let Some(path) = header.path() else {
+            continue;
+        };
+        let ifname = SupplicantInterfaceProxy::builder(&connection)
+            .path(path.to_owned())?
+            .build()
+            .await?
+            .ifname()
+            .await?;
+        if ifname != interface && !ifname.starts_with(&group_prefix) {
+            continue;
+        }
+        let mac: String = message.body().deserialize()?;
+        if tx.send(mac.to_uppercase()).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 解析 dnsmasq 租约文件，跳过在 `now`（Unix 秒）之前过期的租约
+///
+/// 每行格式: `<expiry> <mac> <ip> <hostname> <client-id>`，`expiry` 为 0 表示永不过期
+pub fn parse_dnsmasq_leases(content: &str, now: u64) -> Vec<StationInfo> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let expiry: u64 = fields.next()?.parse().ok()?;
+            if expiry != 0 && expiry < now {
+                return None;
+            }
+            let mac = fields.next()?;
+            let ip = fields.next()?;
+            Some(StationInfo {
+                mac: mac.to_uppercase(),
+                ip: ip.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// 解析 `/proc/net/arp`，只保留已完成解析且设备名满足 `matches_device` 的条目
+///
+/// 格式: `IP address  HW type  Flags  HW address  Mask  Device`
+pub fn parse_arp_table(content: &str, matches_device: impl Fn(&str) -> bool) -> Vec<StationInfo> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [ip, _hw_type, flags, mac, _mask, device] = fields[..] else {
+                return None;
+            };
+            let flags = u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok()?;
+            if flags & ATF_COM == 0 || !matches_device(device) {
+                return None;
+            }
+            Some(StationInfo {
+                mac: mac.to_uppercase(),
+                ip: ip.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dnsmasq_leases() {
+        let content = "1700000000 aa:bb:cc:dd:ee:ff 10.42.0.23 phone 01:aa:bb:cc:dd:ee:ff\n\
+                       1700000100 11:22:33:44:55:66 10.42.0.57 * *\n\
+                       0 de:ad:be:ef:00:01 10.42.0.88 * *\n";
+        let stations = parse_dnsmasq_leases(content, 1_600_000_000);
+        assert_eq!(stations.len(), 3);
+        assert_eq!(stations[0].mac, "AA:BB:CC:DD:EE:FF");
+        assert_eq!(stations[0].ip, "10.42.0.23");
+
+        // 过期的租约跳过，永不过期的保留
+        let stations = parse_dnsmasq_leases(content, 1_700_000_050);
+        let macs: Vec<_> = stations.iter().map(|s| s.mac.as_str()).collect();
+        assert_eq!(macs, ["11:22:33:44:55:66", "DE:AD:BE:EF:00:01"]);
+    }
+
+    #[test]
+    fn test_tracker_ignores_snapshot_until_authorized() {
+        let station = |mac: &str, ip: &str| StationInfo {
+            mac: mac.to_string(),
+            ip: ip.to_string(),
+        };
+        let old = station("AA:AA:AA:AA:AA:AA", "10.42.0.23");
+        let new = station("BB:BB:BB:BB:BB:BB", "10.42.0.57");
+        let mut tracker = StationTracker::new(StationSnapshot::from_stations([old.clone()]));
+
+        // 上一次热点留下的条目不算接入，新客户端只上报一次
+        assert_eq!(
+            tracker.joined(vec![old.clone(), new.clone()]),
+            vec![new.clone()]
+        );
+        assert!(tracker.joined(vec![old.clone(), new.clone()]).is_empty());
+
+        // 同一台设备重新认证后才上报
+        tracker.authorize(old.mac.clone());
+        assert_eq!(tracker.joined(vec![old.clone()]), vec![old]);
+    }
+
+    #[test]
+    fn test_parse_arp_table_filters_device_and_incomplete() {
+        let content = "\
+IP address       HW type     Flags       HW address            Mask     Device
+10.42.0.23       0x1         0x2         aa:bb:cc:dd:ee:ff     *        wlan0
+192.168.1.1      0x1         0x2         11:22:33:44:55:66     *        eth0
+10.42.0.99       0x1         0x0         00:00:00:00:00:00     *        wlan0
+192.168.49.12    0x1         0x2         12:34:56:78:9a:bc     *        p2p-wlan0-0
+";
+        let stations = parse_arp_table(content, |dev| {
+            dev == "wlan0" || dev.starts_with("p2p-wlan0-")
+        });
+        assert_eq!(
+            stations,
+            vec![
+                StationInfo {
+                    mac: "AA:BB:CC:DD:EE:FF".to_string(),
+                    ip: "10.42.0.23".to_string(),
+                },
+                StationInfo {
+                    mac: "12:34:56:78:9A:BC".to_string(),
+                    ip: "192.168.49.12".to_string(),
+                },
+            ]
+        );
+    }
+}
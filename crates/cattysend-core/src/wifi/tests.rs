@@ -3,6 +3,7 @@
 //! 包含 NmClient D-Bus 客户端和 P2P 模块的单元测试
 
 use super::*;
+use std::sync::Arc;
 
 // ============================================================================
 // P2pInfo 测试
@@ -95,6 +96,179 @@ fn test_p2p_info_with_encryption() {
     assert_eq!(info.cat_share, Some(1));
 }
 
+/// 验证局域网直连模式的 host 字段只在需要时出现
+#[test]
+fn test_p2p_info_lan_direct() {
+    let info = P2pInfo::lan_direct("192.168.1.20".to_string(), "MAC".to_string(), 8443);
+    assert!(info.is_lan_direct());
+    assert!(info.ssid.is_empty());
+
+    let json = serde_json::to_string(&info).unwrap();
+    assert!(json.contains("\"host\":\"192.168.1.20\""));
+
+    let legacy = P2pInfo::new(
+        "SSID".to_string(),
+        "PSK".to_string(),
+        "MAC".to_string(),
+        8443,
+    );
+    assert!(!legacy.is_lan_direct());
+    assert!(!serde_json::to_string(&legacy).unwrap().contains("\"host\""));
+}
+
+/// 验证建组请求与热点、局域网直连模式可以区分
+#[test]
+fn test_p2p_info_group_request() {
+    let request = P2pInfo::group_request("MAC".to_string());
+    assert!(request.is_group_request());
+    assert!(!request.is_lan_direct());
+
+    let lan = P2pInfo::lan_direct("192.168.1.20".to_string(), "MAC".to_string(), 8443);
+    assert!(!lan.is_group_request());
+
+    let hotspot = P2pInfo::new(
+        "SSID".to_string(),
+        "PSK".to_string(),
+        "MAC".to_string(),
+        8443,
+    );
+    assert!(!hotspot.is_group_request());
+}
+
+/// 验证对端 P2pInfo 的字段校验
+#[test]
+fn test_p2p_info_validate() {
+    let valid = |ssid: &str, psk: &str| {
+        P2pInfo::new(
+            ssid.to_string(),
+            psk.to_string(),
+            "aa:BB:cc:00:11:22".to_string(),
+            8443,
+        )
+        .validate()
+    };
+    assert_eq!(valid("DIRECT-ab-小米手机", "x9y8z7w6"), Ok(()));
+    assert_eq!(valid(&"S".repeat(32), &"p".repeat(63)), Ok(()));
+
+    // 空 SSID 会被当作建组请求，不能带密码
+    assert_eq!(valid("", "x9y8z7w6"), Err(P2pInfoError::InvalidPsk));
+    assert!(matches!(
+        valid(&"S".repeat(33), "x9y8z7w6"),
+        Err(P2pInfoError::InvalidSsid(_))
+    ));
+    assert!(matches!(
+        valid("--help", "x9y8z7w6"),
+        Err(P2pInfoError::InvalidSsid(_))
+    ));
+    assert!(matches!(
+        valid("DIRECT\nab", "x9y8z7w6"),
+        Err(P2pInfoError::InvalidSsid(_))
+    ));
+    assert_eq!(valid("DIRECT-ab", "short"), Err(P2pInfoError::InvalidPsk));
+    assert_eq!(
+        valid("DIRECT-ab", &"p".repeat(64)),
+        Err(P2pInfoError::InvalidPsk)
+    );
+    assert_eq!(
+        valid("DIRECT-ab", "密码密码密码密码"),
+        Err(P2pInfoError::InvalidPsk)
+    );
+    assert_eq!(
+        valid("DIRECT-ab", "x9y8z7w6\t"),
+        Err(P2pInfoError::InvalidPsk)
+    );
+
+    for mac in [
+        "",
+        "MAC",
+        "00:11:22:33:44",
+        "00:11:22:33:44:55:66",
+        "00-11-22-33-44-55",
+        "0g:11:22:33:44:55",
+    ] {
+        let info = P2pInfo::new(
+            "DIRECT-ab".to_string(),
+            "x9y8z7w6".to_string(),
+            mac.to_string(),
+            8443,
+        );
+        assert_eq!(
+            info.validate(),
+            Err(P2pInfoError::InvalidMac(mac.to_string()))
+        );
+    }
+
+    for port in [0, -1, 65536] {
+        let info = P2pInfo::new(
+            "DIRECT-ab".to_string(),
+            "x9y8z7w6".to_string(),
+            "00:11:22:33:44:55".to_string(),
+            port,
+        );
+        assert_eq!(info.validate(), Err(P2pInfoError::InvalidPort(port)));
+    }
+}
+
+/// 局域网直连只要求 host 是 IP，建组请求端口为 0
+#[test]
+fn test_p2p_info_validate_lan_and_group_request() {
+    let mac = "00:11:22:33:44:55".to_string();
+    assert_eq!(
+        P2pInfo::lan_direct("192.168.1.20".to_string(), mac.clone(), 8443).validate(),
+        Ok(())
+    );
+    assert_eq!(
+        P2pInfo::lan_direct("fe80::1".to_string(), mac.clone(), 8443).validate(),
+        Ok(())
+    );
+    assert!(matches!(
+        P2pInfo::lan_direct("evil.example; rm -rf /".to_string(), mac.clone(), 8443).validate(),
+        Err(P2pInfoError::InvalidHost(_))
+    ));
+
+    assert_eq!(P2pInfo::group_request(mac.clone()).validate(), Ok(()));
+    let mut request = P2pInfo::group_request(mac);
+    request.psk = "x9y8z7w6".to_string();
+    assert_eq!(request.validate(), Err(P2pInfoError::InvalidPsk));
+}
+
+/// 访问令牌会被放进 HTTP 请求头，只接受字母数字
+#[test]
+fn test_p2p_info_validate_auth_token() {
+    let mut info = P2pInfo::new(
+        "DIRECT-ab12cd34".to_string(),
+        "x9y8z7w6".to_string(),
+        "00:11:22:33:44:55".to_string(),
+        8443,
+    );
+    info.auth_token = Some(uuid::Uuid::new_v4().simple().to_string());
+    assert_eq!(info.validate(), Ok(()));
+
+    for token in ["", "abc\r\nX-Evil: 1", "a b", &"a".repeat(65)] {
+        info.auth_token = Some(token.to_string());
+        assert_eq!(info.validate(), Err(P2pInfoError::InvalidAuthToken));
+    }
+}
+
+/// 证书指纹必须是完整的 SHA-256 十六进制
+#[test]
+fn test_p2p_info_validate_cert_fingerprint() {
+    let mut info = P2pInfo::new(
+        "DIRECT-ab12cd34".to_string(),
+        "x9y8z7w6".to_string(),
+        "00:11:22:33:44:55".to_string(),
+        8443,
+    );
+    info.cert_sha256 = Some("0f".repeat(32));
+    assert_eq!(info.validate(), Ok(()));
+
+    info.cert_sha256 = Some("0f".repeat(31));
+    assert!(matches!(
+        info.validate(),
+        Err(P2pInfoError::InvalidCertFingerprint(_))
+    ));
+}
+
 /// 验证 get_server_url 方法
 #[test]
 fn test_p2p_info_get_server_url() {
@@ -136,6 +310,7 @@ fn test_wifi_p2p_sender_new() {
 fn test_wifi_p2p_sender_with_config() {
     let config = P2pConfig {
         interface: "wlp3s0".to_string(),
+        hotspot_interface: None,
         ssid_prefix: "CAT-".to_string(),
         use_5ghz: false,
     };
@@ -180,6 +355,109 @@ fn test_check_capabilities() {
     // 不做断言，因为测试环境可能不同
 }
 
+// ============================================================================
+// CommandRunner 测试
+// ============================================================================
+
+#[test]
+fn test_hotspot_ip_single_interface() {
+    let sender = WiFiP2pSender::new("wlan0").with_runner(Arc::new(FakeRunner::single_interface()));
+
+    assert_eq!(sender.get_hotspot_ip().unwrap(), "10.42.0.1");
+}
+
+#[test]
+fn test_hotspot_ip_prefers_p2p_group_interface() {
+    // wlan0 上的 192.168.1.23 属于原有连接，热点地址在 p2p-wlan0-0 上
+    let sender = WiFiP2pSender::new("wlan0").with_runner(Arc::new(FakeRunner::multi_interface()));
+
+    assert_eq!(sender.get_hotspot_ip().unwrap(), "192.168.49.1");
+}
+
+#[test]
+fn test_hotspot_ip_matches_whole_interface_name() {
+    // wlan1 不能匹配到 wlan10 的地址
+    let runner = FakeRunner::new().with_output(
+        "ip -o addr show",
+        "4: wlan10    inet 192.168.7.2/24 scope global wlan10\n",
+    );
+    let sender = WiFiP2pSender::new("wlan1").with_runner(Arc::new(runner));
+
+    assert_eq!(sender.get_hotspot_ip().unwrap(), "10.42.0.1");
+}
+
+#[test]
+fn test_hotspot_ip_without_ip_command() {
+    let sender = WiFiP2pSender::new("wlan0").with_runner(Arc::new(FakeRunner::new()));
+
+    assert!(sender.get_hotspot_ip().is_err());
+}
+
+#[tokio::test]
+async fn test_stop_group_removes_wpa_group() {
+    let runner = Arc::new(FakeRunner::multi_interface());
+    let sender = WiFiP2pSender::new("wlan0").with_runner(runner.clone());
+
+    sender.stop_group().await.unwrap();
+
+    assert_eq!(runner.calls(), ["wpa_cli -i wlan0 p2p_group_remove *"]);
+}
+
+#[test]
+fn test_probe_wpa3() {
+    let runner = FakeRunner::new()
+        .with_output(
+            "wpa_cli -i wlan0 get_capability key_mgmt",
+            "NONE IEEE8021X WPA-PSK WPA-EAP SAE\n",
+        )
+        .with_output(
+            "wpa_cli -i wlan0 get_capability group_mgmt",
+            "AES-128-CMAC BIP-GMAC-128\n",
+        );
+    let sender = WiFiP2pSender::new("wlan0").with_runner(Arc::new(runner));
+    assert!(sender.supports_wpa3());
+
+    // 支持 SAE 但没有 PMF
+    let runner = FakeRunner::new()
+        .with_output("wpa_cli -i wlan0 get_capability key_mgmt", "WPA-PSK SAE\n")
+        .with_output("wpa_cli -i wlan0 get_capability group_mgmt", "FAIL\n");
+    assert!(!probe_wpa3(&runner, "wlan0"));
+
+    // SAE-EXT-KEY 不能当成 SAE
+    let runner = FakeRunner::new()
+        .with_output(
+            "wpa_cli -i wlan0 get_capability key_mgmt",
+            "WPA-PSK SAE-EXT-KEY\n",
+        )
+        .with_output(
+            "wpa_cli -i wlan0 get_capability group_mgmt",
+            "AES-128-CMAC\n",
+        );
+    assert!(!probe_wpa3(&runner, "wlan0"));
+
+    // 没有 wpa_cli
+    assert!(!probe_wpa3(&FakeRunner::new(), "wlan0"));
+}
+
+#[test]
+fn test_receiver_interface_ip() {
+    let receiver =
+        WiFiP2pReceiver::new("wlan0").with_runner(Arc::new(FakeRunner::multi_interface()));
+    assert_eq!(receiver.get_interface_ip("wlan0").unwrap(), "192.168.1.23");
+
+    let receiver =
+        WiFiP2pReceiver::new("wlan0").with_runner(Arc::new(FakeRunner::single_interface()));
+    assert_eq!(receiver.get_interface_ip("wlan0").unwrap(), "10.42.0.1");
+}
+
+#[test]
+fn test_receiver_interface_ip_missing() {
+    let runner = FakeRunner::new().with_output("ip -o addr show", "");
+    let receiver = WiFiP2pReceiver::new("wlan0").with_runner(Arc::new(runner));
+
+    assert!(receiver.get_interface_ip("wlan0").is_err());
+}
+
 // ============================================================================
 // Mock 测试辅助 (供其他测试模块使用)
 // ============================================================================
@@ -293,7 +571,12 @@ mod nm_dbus_tests {
 
         // 创建连接配置
         let conn_path = client
-            .create_wifi_connection("TestSSID", "testpassword", None)
+            .create_wifi_connection(
+                "TestSSID",
+                "testpassword",
+                None,
+                crate::wifi::HotspotSecurity::Wpa2Psk,
+            )
             .await
             .unwrap();
 
@@ -0,0 +1,89 @@
+//! wpa_cli 参数的序列化
+//!
+//! SSID 和口令可能来自对端或用户配置（如 [`P2pConfig::ssid_prefix`](crate::wifi::P2pConfig)），
+//! 不能直接用 `format!` 拼进命令参数：wpa_cli 把参数用空格拼成一条控制命令，
+//! `p2p_group_add` 的参数只接受不含空白和引号的值，其他值直接拒绝
+//! （wpa_supplicant 的 `ssid=` 参数没有转义语法）。
+
+/// SSID 最大长度（字节）
+const MAX_SSID_LEN: usize = 32;
+
+/// 序列化错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WpaError {
+    #[error("SSID must be 1-32 bytes, got {0}")]
+    SsidLength(usize),
+
+    #[error("passphrase must be 8-63 printable ASCII characters")]
+    Passphrase,
+
+    #[error("value cannot be passed to wpa_cli: {0:?}")]
+    UnsafeArgument(String),
+}
+
+/// `wpa_cli p2p_group_add` 的参数：`persistent ssid=<ssid> passphrase=<口令>`
+pub fn group_add_arg(ssid: &str, passphrase: &str) -> Result<String, WpaError> {
+    if !(1..=MAX_SSID_LEN).contains(&ssid.len()) {
+        return Err(WpaError::SsidLength(ssid.len()));
+    }
+    if !is_passphrase(passphrase) {
+        return Err(WpaError::Passphrase);
+    }
+    for value in [ssid, passphrase] {
+        if !is_plain_token(value) {
+            return Err(WpaError::UnsafeArgument(value.to_string()));
+        }
+    }
+    Ok(format!(
+        "persistent ssid={} passphrase={}",
+        ssid, passphrase
+    ))
+}
+
+/// 从 [`group_add_arg`] 生成的参数中取回 (SSID, 口令)
+pub fn parse_group_add_arg(arg: &str) -> Option<(&str, &str)> {
+    let (ssid, passphrase) = arg
+        .strip_prefix("persistent ssid=")?
+        .split_once(" passphrase=")?;
+    group_add_arg(ssid, passphrase).ok()?;
+    Some((ssid, passphrase))
+}
+
+fn is_passphrase(passphrase: &str) -> bool {
+    (8..=63).contains(&passphrase.len()) && passphrase.bytes().all(|b| (b' '..=b'~').contains(&b))
+}
+
+/// 只含可见 ASCII 字符且没有引号（wpa_cli 按空格和引号切分参数）
+fn is_plain_token(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|b| b.is_ascii_graphic() && !matches!(b, b'"' | b'\'' | b'\\'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_add_arg() {
+        let arg = group_add_arg("DIRECT-ab12cd34", "x9y8z7w6").unwrap();
+        assert_eq!(arg, "persistent ssid=DIRECT-ab12cd34 passphrase=x9y8z7w6");
+        assert_eq!(
+            parse_group_add_arg(&arg),
+            Some(("DIRECT-ab12cd34", "x9y8z7w6"))
+        );
+
+        // 空格会让 wpa_supplicant 把后半段当成新的参数
+        for ssid in ["DIRECT ab", "DIRECT-ab passphrase=evil1234", "a\"b", "a\nb"] {
+            assert!(matches!(
+                group_add_arg(ssid, "x9y8z7w6"),
+                Err(WpaError::UnsafeArgument(_))
+            ));
+        }
+        assert!(group_add_arg("DIRECT-ab", "x9y8 z7w6").is_err());
+        assert_eq!(
+            parse_group_add_arg("persistent ssid=a b passphrase=x9y8z7w6"),
+            None
+        );
+    }
+}
@@ -1,12 +1,32 @@
 //! 工作流模块
 //!
-//! 提供高层 API 封装完整的发送/接收流程
+//! 提供高层 API 封装完整的发送/接收流程，以及链路建立后可双向传输的会话，
+//! 发送端还可以提前准备热点和传输端口（[`Standby`]）
+//!
+//! 每次发送/接收开始时生成一个会话 ID，通过 `on_started` 回调交给调用方，
+//! 同时记录在工作流的 tracing span 上，用于把事件、日志和 IPC 消息对应起来。
 
 pub mod receiver;
 pub mod sender;
+pub mod session;
+pub mod standby;
+pub mod state;
 
 pub use receiver::{
-    ReceiveEvent, ReceiveOptions, ReceiveProgressCallback, ReceiveRequest, Receiver,
+    ReceiveEvent, ReceiveOptions, ReceivePhase, ReceiveProgressCallback, ReceiveRequest, Receiver,
     SimpleReceiveCallback,
 };
-pub use sender::{SendEvent, SendOptions, SendProgressCallback, Sender, SimpleSendCallback};
+pub use sender::{
+    Failure, RetryAttempt, RetryPolicy, SendEvent, SendOptions, SendPhase, SendProgressCallback,
+    Sender, SimpleSendCallback, TransferMode,
+};
+pub use session::{IncomingTransfer, Session, SessionListener};
+pub use standby::Standby;
+pub use state::{InvalidTransition, Phase, StateMachine, WorkflowState};
+
+/// 生成会话 ID 并记录到当前 span（工作流函数在 `instrument` 中声明了空的 `session_id` 字段）
+pub(crate) fn start_session() -> String {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    tracing::Span::current().record("session_id", session_id.as_str());
+    session_id
+}
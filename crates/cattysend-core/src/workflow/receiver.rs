@@ -5,29 +5,124 @@
 //! 2. 接收 P2P 信息
 //! 3. 连接到发送端 WiFi 热点
 //! 4. 通过 HTTP/WebSocket 接收文件
+//! 5. （可选）等待对端通过 `PUT /upload` 反向上传文件
+//!
+//! [`Receiver::start`] 处理一个发送端后返回，[`Receiver::serve`] 持续接收多个发送端；
+//! 需要在一次连接中双向传输时使用 [`Receiver::open_session`]。
+//! 下载过程中可以通过 [`Receiver::pause`] / [`Receiver::resume`] 暂停和恢复。
+//!
+//! 发送端使用反向模式（[`TransferMode::JoinReceiver`](crate::TransferMode::JoinReceiver)）时
+//! 写入的是建组请求：接收端自己创建热点，把热点的 P2P 信息经 P2P 特征的通知发回，
+//! 再等待发送端接入后通过 `PUT /upload` 上传文件（设置 `group_owner = false` 时拒绝）。
+//!
+//! 蓝牙不可用时，[`Receiver::receive_payload`] 接收扫描或粘贴得到的引导载荷
+//! （见 [`bootstrap`]），之后的流程与 BLE 相同。
+//!
+//! [`Receiver::start`] 和 [`Receiver::handle_p2p_event`] 的进度按 [`ReceivePhase`]
+//! 记录在状态机中，可以通过 [`Receiver::state`] 订阅。
+//!
+//! 连接发送端时按 P2P 信息中的 catShare 版本查 [`QuirksTable`]（见 [`crate::quirks`]），
+//! 修正端口偏移、推迟 WebSocket 连接并调整重试次数。
+//!
+//! [`Receiver::with_cancellation`] 的令牌取消后，广播、接入热点和下载都会立即结束，
+//! 已接入的发送端网络随之断开。
 
-use crate::ble::GattServer;
-use crate::crypto::BleSecurityPersistent;
-use crate::transfer::{ReceiverCallback, ReceiverClient, SendRequest};
-use crate::wifi::WiFiP2pReceiver;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use crate::ble::gatt::GattHandler;
+use crate::ble::pairing::{PairingAgent, PairingPrompt, PairingRequest};
+use crate::ble::{
+    DeviceInfo, GattConnectionEvent, GattServer, GattServerHandle, LegacyAdvConfig, P2pNotifier,
+    P2pReceiveEvent, ReceiverState, StatusNotifier,
+};
+use crate::cancel::{self, CONNECT_TIMEOUT, CancellationToken};
+use crate::config::PowerProfile;
+use crate::crypto::{AtRestKey, BleSecurityPersistent, identity};
+use crate::discovery::{DiscoveryMethod, LanAdvertiser, LanAdvertiserHandle, bootstrap};
+use crate::power::PowerState;
+use crate::quirks::{Quirks, QuirksTable};
+use crate::transfer::{
+    HttpTransport, ReceiverCallback, SendRequest, StatsTracker, TransferControl, TransferStats,
+    TransferTarget, TransferTransport, UploadServer,
+};
+use crate::wifi::{CredentialPolicy, LinuxWifiBackend, PeerSupport, WifiBackend};
+use crate::workflow::sender::RetryPolicy;
+use crate::workflow::session::Session;
+use crate::workflow::start_session;
+use crate::workflow::state::{StateMachine, WorkflowState};
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tracing::Instrument;
 
 /// 接收进度回调
 pub trait ReceiveProgressCallback: Send + Sync {
+    /// 工作流开始，之后的事件都属于 `session_id` 这次会话
+    fn on_started(&self, _session_id: &str) {}
     /// 状态更新
     fn on_status(&self, status: &str);
+    /// 发送端通过蓝牙连接或断开（早于 P2P 信息到达）
+    fn on_ble_connection(&self, _event: &GattConnectionEvent) {}
+    /// 与发送端协商出的验证码（P2P 信息未加密时不会调用），应显示给用户与发送端核对
+    fn on_verification_code(&self, _code: &str) {}
     /// 收到发送请求，返回是否接受
     fn on_request(&self, request: &ReceiveRequest) -> bool;
+    /// 发送端要求配对；返回 `true` 同意数字比较，只显示的提示忽略返回值
+    ///
+    /// 默认拒绝需要确认的配对，Just-Works 配对不经过这里。
+    fn on_pairing(&self, _prompt: &PairingPrompt) -> bool {
+        false
+    }
     /// 进度更新
     fn on_progress(&self, received: u64, total: u64);
+    /// 传输统计（逐文件进度和速度，约每秒一次）
+    fn on_stats(&self, _stats: &TransferStats) {}
+    /// 下载暂停（`true`）或恢复（`false`）
+    fn on_paused(&self, _paused: bool) {}
     /// 接收完成
     fn on_complete(&self, files: Vec<PathBuf>);
+    /// 对端反向上传了一个文件
+    fn on_upload(&self, _path: &Path) {}
     /// 接收失败
     fn on_error(&self, error: &str);
 }
 
+/// 接收流程的阶段
+///
+/// 按先后顺序排列，允许的转换见 [`Phase`](crate::workflow::Phase) 实现。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceivePhase {
+    /// 广播中，等待发送端写入 P2P 信息
+    Advertising,
+    /// 接入发送端的热点（局域网直连时直接跳过）
+    JoiningNetwork,
+    /// 连接发送端的传输服务
+    Connecting,
+    /// 已接受传输，正在下载
+    Receiving,
+    /// 等待对端反向上传
+    WaitingForUploads,
+    /// 接收完成
+    Done,
+}
+
+impl ReceivePhase {
+    /// 全部阶段，按先后顺序
+    pub const ALL: [ReceivePhase; 6] = [
+        ReceivePhase::Advertising,
+        ReceivePhase::JoiningNetwork,
+        ReceivePhase::Connecting,
+        ReceivePhase::Receiving,
+        ReceivePhase::WaitingForUploads,
+        ReceivePhase::Done,
+    ];
+}
+
 /// 接收请求信息
 #[derive(Debug, Clone)]
 pub struct ReceiveRequest {
@@ -35,6 +130,8 @@ pub struct ReceiveRequest {
     pub file_name: String,
     pub file_count: u32,
     pub total_size: u64,
+    /// 本端算出的验证码，与发送端显示的一致才是目标设备
+    pub verification_code: Option<String>,
 }
 
 /// 接收选项
@@ -51,6 +148,35 @@ pub struct ReceiveOptions {
     pub brand_id: crate::config::BrandId,
     /// 是否支持 5GHz
     pub supports_5ghz: bool,
+    /// 电源策略（决定广播间隔和占空比）
+    pub power_profile: PowerProfile,
+    /// 使用电池供电时改用 [`PowerProfile::Battery`]（见 [`crate::power`]）
+    pub battery_saver: bool,
+    /// 发现方式（BLE / 局域网 mDNS / 两者）
+    pub discovery: DiscoveryMethod,
+    /// WebSocket 连接的重试策略
+    pub retry: RetryPolicy,
+    /// 是否通过 TLS 连接发送端（CatShare 发送端使用 HTTPS）
+    pub use_tls: bool,
+    /// 是否恢复发送端文件的可执行位（修改时间总是恢复）
+    pub restore_permissions: bool,
+    /// 反向上传端口，设置后允许对端在同一会话内通过 `PUT /upload` 推送文件（0 表示随机端口）
+    pub upload_port: Option<u16>,
+    /// 接收完成后继续等待反向上传的时间，每收到一个文件重新计时
+    pub upload_idle_timeout: Duration,
+    /// 静态加密密钥，设置后收到的文件加密保存为 `<文件名>.cattyenc`，
+    /// 用 `cattysend-cli decrypt` 还原（共用机器上使用）
+    pub encryption_key: Option<AtRestKey>,
+    /// [`Receiver::serve`] 同时处理的发送端数（默认 1，即逐个处理）
+    pub max_sessions: usize,
+    /// [`Receiver::serve`] 中每个会话保存到输出目录下的独立子目录（`max_sessions` 大于 1 时总是如此）
+    pub session_dirs: bool,
+    /// 按发送端分类保存到 `<输出目录>/<发送端名称>/<YYYY-MM-DD>/`，与已有文件重名时自动改名
+    pub sort_by_sender: bool,
+    /// 发送端的文件夹同步请求删除时，删除目标目录中发送端已没有的文件（见 [`crate::sync`]）
+    pub allow_sync_delete: bool,
+    /// 接受发送端的建组请求（反向模式）：由本机创建热点，接收发送端上传的文件
+    pub group_owner: bool,
 }
 
 impl Default for ReceiveOptions {
@@ -64,6 +190,20 @@ impl Default for ReceiveOptions {
             auto_accept: false,
             brand_id: crate::config::BrandId::Xiaomi,
             supports_5ghz: true,
+            power_profile: PowerProfile::default(),
+            battery_saver: true,
+            discovery: DiscoveryMethod::default(),
+            retry: RetryPolicy::default(),
+            use_tls: true,
+            restore_permissions: false,
+            upload_port: None,
+            upload_idle_timeout: Duration::from_secs(60),
+            encryption_key: None,
+            max_sessions: 1,
+            session_dirs: false,
+            sort_by_sender: false,
+            allow_sync_delete: false,
+            group_owner: true,
         }
     }
 }
@@ -71,102 +211,702 @@ impl Default for ReceiveOptions {
 /// 接收端工作流
 pub struct Receiver {
     options: ReceiveOptions,
+    wifi: Arc<dyn WifiBackend>,
+    transport: Arc<dyn TransferTransport>,
     security: Arc<BleSecurityPersistent>,
+    control: TransferControl,
+    /// `start` / `handle_p2p_event` 的状态（`serve` 的会话并发进行，只记录广播阶段）
+    state: StateMachine<ReceivePhase>,
+    cancel: CancellationToken,
+    /// 厂商兼容性表
+    quirks: QuirksTable,
 }
 
 impl Receiver {
     pub fn new(options: ReceiveOptions) -> anyhow::Result<Self> {
         let security = Arc::new(BleSecurityPersistent::new()?);
-        Ok(Self { options, security })
+        let wifi = Arc::new(LinuxWifiBackend::new(&options.wifi_interface));
+        Ok(Self {
+            options,
+            wifi,
+            transport: Arc::new(HttpTransport),
+            security,
+            control: TransferControl::new(),
+            state: StateMachine::new(),
+            cancel: CancellationToken::new(),
+            quirks: QuirksTable::load(),
+        })
+    }
+
+    /// 替换 WiFi 后端
+    pub fn with_wifi_backend(mut self, wifi: Arc<dyn WifiBackend>) -> Self {
+        self.wifi = wifi;
+        self
+    }
+
+    /// 替换传输层（默认 HTTP(S) + WebSocket）
+    pub fn with_transport(mut self, transport: Arc<dyn TransferTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// 使用指定的密钥对（默认每次创建时随机生成）
+    pub fn with_security(mut self, security: Arc<BleSecurityPersistent>) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// 使用外部创建的暂停/恢复句柄（默认每个 `Receiver` 独立一个）
+    pub fn with_control(mut self, control: TransferControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// `token` 取消时中断广播、接入网络和下载
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// 使用指定的兼容性表（默认为内置规则加上覆盖文件）
+    pub fn with_quirks(mut self, quirks: QuirksTable) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// 暂停下载
+    ///
+    /// 作用于所有进行中的会话，之后开始的会话也会停在下载阶段，直到 [`Self::resume`]。
+    /// 发送端等待传输完成的超时仍在计时。
+    pub fn pause(&self) {
+        self.control.pause();
     }
 
-    /// 开始接收模式
+    /// 恢复下载
+    pub fn resume(&self) {
+        self.control.resume();
+    }
+
+    /// 暂停/恢复句柄，可以交给不持有 `Receiver` 的界面代码
+    pub fn control(&self) -> TransferControl {
+        self.control.clone()
+    }
+
+    /// 订阅接收状态
+    pub fn state(&self) -> watch::Receiver<WorkflowState<ReceivePhase>> {
+        self.state.subscribe()
+    }
+
+    /// 发送端 catShare 版本对应的兼容性调整（接收端不知道发送端的品牌）
+    fn quirks_for(&self, version: Option<i32>) -> Quirks {
+        let quirks = self.quirks.lookup(None, version);
+        if quirks != Quirks::default() {
+            debug!("Applying quirks for catShare {:?}: {:?}", version, quirks);
+        }
+        quirks
+    }
+
+    /// 开始新一轮流程
+    fn begin(&self, phase: ReceivePhase) {
+        if let Err(e) = self.state.start(phase) {
+            warn!("{}", e);
+        }
+    }
+
+    /// 在取消令牌的约束下运行 `flow`；取消时断开发送端的网络
+    async fn cancellable<T>(
+        &self,
+        flow: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let interrupted = match cancel::run(&self.cancel, None, flow).await {
+            Ok(result) => return result,
+            Err(interrupted) => interrupted,
+        };
+        if let Err(e) = self.wifi.disconnect().await {
+            warn!("Cleanup after cancellation failed: {}", e);
+        }
+        Err(interrupted.into())
+    }
+
+    /// 开始接收模式，处理一个发送端后返回
+    ///
+    /// 需要连续接收多个发送端时使用 [`Self::serve`]。
+    #[tracing::instrument(
+        skip_all,
+        fields(session_id = tracing::field::Empty, device_name = %self.options.device_name)
+    )]
     pub async fn start<C: ReceiveProgressCallback>(
         &self,
         callback: &C,
     ) -> anyhow::Result<Vec<PathBuf>> {
-        callback.on_status("启动接收模式...");
+        callback.on_started(&start_session());
+        callback.on_status(&crate::tr!("workflow.receive.starting"));
+        self.begin(ReceivePhase::Advertising);
 
+        let result = self
+            .cancellable(async {
+                let mut listener = self.listen(callback).await?;
+                let p2p_event = listener.next_event(callback).await?;
+
+                // 传输期间对其他发送端显示为忙碌，结束后（无论成败）恢复空闲
+                listener.set_state(ReceiverState::Busy).await;
+                let result = self
+                    .receive_into(
+                        p2p_event,
+                        callback,
+                        &self.options.output_dir,
+                        listener.notifiers(),
+                        Some(&self.state),
+                    )
+                    .await;
+                listener.set_state(ReceiverState::Idle).await;
+                result
+            })
+            .await;
+        if result.is_err() {
+            self.state.fail();
+        }
+        result
+    }
+
+    /// 持续接收模式：广播一直开启，每个发送端的 P2P 信息启动一个独立会话
+    ///
+    /// - 最多同时进行 `max_sessions` 个会话，达到上限时对外显示忙碌，新的 P2P 信息留到有空位再处理
+    /// - 需要接入发送端热点的会话共用一块网卡，彼此仍排队进行；局域网直连的会话可以真正并发
+    /// - `new_session` 按会话 ID 为每个会话创建独立的回调，会话失败只通过该回调的 `on_error` 上报
+    /// - `callback` 接收与具体会话无关的事件（广播状态、蓝牙连接）
+    ///
+    /// 只有广播本身出错或被取消时返回；停止服务时也可以直接 drop 返回的 future。
+    #[tracing::instrument(skip_all, fields(device_name = %self.options.device_name))]
+    pub async fn serve<C, S, F>(&self, callback: &C, new_session: F) -> anyhow::Result<()>
+    where
+        C: ReceiveProgressCallback,
+        S: ReceiveProgressCallback,
+        F: FnMut(&str) -> S,
+    {
+        callback.on_status(&crate::tr!("workflow.receive.starting_continuous"));
+        self.begin(ReceivePhase::Advertising);
+        self.cancellable(self.serve_sessions(callback, new_session))
+            .await
+    }
+
+    async fn serve_sessions<C, S, F>(&self, callback: &C, mut new_session: F) -> anyhow::Result<()>
+    where
+        C: ReceiveProgressCallback,
+        S: ReceiveProgressCallback,
+        F: FnMut(&str) -> S,
+    {
+        let max_sessions = self.options.max_sessions.max(1);
+        let mut listener = self.listen(callback).await?;
+        let link = tokio::sync::Mutex::new(());
+        let mut sessions = FuturesUnordered::new();
+
+        loop {
+            tokio::select! {
+                event = listener.next_event(callback), if sessions.len() < max_sessions => {
+                    let p2p_event = event?;
+                    let session_id = uuid::Uuid::new_v4().to_string();
+                    let session_callback = new_session(&session_id);
+                    session_callback.on_started(&session_id);
+                    let output_dir = self.session_dir(&session_id);
+                    let span = tracing::info_span!("session", session_id = %session_id);
+                    let notifiers = listener.notifiers();
+                    sessions.push(
+                        self.run_session(p2p_event, session_callback, output_dir, notifiers, &link)
+                            .instrument(span),
+                    );
+                    if sessions.len() == max_sessions {
+                        listener.set_state(ReceiverState::Busy).await;
+                    }
+                }
+                Some(()) = sessions.next(), if !sessions.is_empty() => {
+                    if sessions.len() + 1 == max_sessions {
+                        listener.set_state(ReceiverState::Idle).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `serve` 中一个发送端的完整会话
+    async fn run_session<S: ReceiveProgressCallback>(
+        &self,
+        p2p_event: P2pReceiveEvent,
+        callback: S,
+        output_dir: PathBuf,
+        notifiers: Notifiers,
+        link: &tokio::sync::Mutex<()>,
+    ) {
+        // 接入热点会切换网卡连接，同一时间只能有一个会话这样做
+        let _link = if p2p_event.p2p_info.is_lan_direct() {
+            None
+        } else {
+            Some(link.lock().await)
+        };
+        let result = match tokio::fs::create_dir_all(&output_dir).await {
+            Ok(()) => {
+                self.receive_into(p2p_event, &callback, &output_dir, notifiers, None)
+                    .await
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = result {
+            warn!("Receive session failed: {}", e);
+            callback.on_error(&e.to_string());
+        }
+    }
+
+    /// `serve` 中会话的保存目录：需要隔离时为输出目录下的 `session-<会话 ID 前 8 位>`
+    fn session_dir(&self, session_id: &str) -> PathBuf {
+        if self.options.session_dirs || self.options.max_sessions > 1 {
+            let short = session_id.get(..8).unwrap_or(session_id);
+            self.options.output_dir.join(format!("session-{}", short))
+        } else {
+            self.options.output_dir.clone()
+        }
+    }
+
+    /// 启动 GATT Server 和/或局域网广播
+    async fn listen<C: ReceiveProgressCallback>(&self, callback: &C) -> anyhow::Result<Listener> {
         // 获取 MAC 地址
         let mac = self.get_mac_address();
+        let supports_wpa3 = self.wifi.supports_wpa3();
+        let power_profile = if self.options.battery_saver {
+            PowerState::read()
+                .await
+                .effective_profile(self.options.power_profile)
+        } else {
+            self.options.power_profile
+        };
+        debug!("Advertising with power profile {}", power_profile.name());
 
         // 启动 GATT Server
+        let (agent, pairing_rx) = PairingAgent::with_prompts();
         let mut gatt_server = GattServer::new(
-            mac,
+            mac.clone(),
             self.options.device_name.clone(),
             self.security.get_public_key().to_string(),
         )?
         .with_security(self.security.clone())
         .with_brand(self.options.brand_id)
-        .with_5ghz_support(self.options.supports_5ghz);
-        let mut p2p_rx = gatt_server.take_p2p_receiver().unwrap();
+        .with_5ghz_support(self.options.supports_5ghz)
+        .with_wpa3_support(supports_wpa3)
+        .with_adv_config(LegacyAdvConfig::from_profile(power_profile))
+        .with_pairing(agent);
+        let ble_rx = gatt_server.take_p2p_receiver().unwrap();
 
-        let _handle = gatt_server.start().await?;
+        let mut gatt = if self.options.discovery.uses_ble() {
+            Some(gatt_server.start().await?)
+        } else {
+            None
+        };
+        let connection_rx = gatt.as_mut().and_then(|h| h.take_connection_events());
 
-        callback.on_status(&format!(
-            "正在广播为 '{}'，等待发送端连接...",
-            self.options.device_name
-        ));
+        // 局域网发现：与 GATT 使用相同的 sender ID 和 DeviceInfo
+        let (lan, lan_rx) = if self.options.discovery.uses_lan() {
+            let device_info = DeviceInfo::new(self.security.get_public_key().to_string(), mac);
+            let (handle, rx) = LanAdvertiser::new(
+                self.options.device_name.clone(),
+                gatt_server.sender_id().to_string(),
+                device_info,
+            )
+            .with_security(self.security.clone())
+            .with_brand(self.options.brand_id)
+            .with_5ghz_support(self.options.supports_5ghz)
+            .with_wpa3_support(supports_wpa3)
+            .start()
+            .await?;
+            (Some(handle), Some(rx))
+        } else {
+            (None, None)
+        };
 
-        // 等待 P2P 信息
-        let p2p_event = p2p_rx
-            .recv()
-            .await
-            .ok_or_else(|| anyhow::anyhow!("P2P channel closed"))?;
+        callback.on_status(&crate::tr!(
+            "workflow.receive.advertising",
+            name = self.options.device_name
+        ));
 
-        // P2P 信息已由 GattServer 自动解密（如果提供了公钥）
-        let p2p_info = p2p_event.p2p_info;
+        Ok(Listener {
+            ble_rx: gatt.is_some().then_some(ble_rx),
+            pairing_rx: gatt.is_some().then_some(pairing_rx),
+            gatt,
+            lan,
+            lan_rx,
+            connection_rx,
+        })
+    }
 
-        if p2p_event.sender_public_key.is_some() {
-            callback.on_status("已接收并解密 P2P 信息");
-        } else {
-            callback.on_status("已接收 P2P 信息");
+    /// 处理收到的 P2P 信息：连接发送端并接收文件
+    ///
+    /// `start` 在 BLE/局域网握手完成后调用此方法；
+    /// 自带握手通道的调用方（如测试）也可以直接调用。
+    #[tracing::instrument(skip_all, fields(ssid = %p2p_event.p2p_info.ssid, port = p2p_event.p2p_info.port))]
+    pub async fn handle_p2p_event<C: ReceiveProgressCallback>(
+        &self,
+        p2p_event: P2pReceiveEvent,
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        self.begin(ReceivePhase::JoiningNetwork);
+        let result = self
+            .cancellable(self.receive_into(
+                p2p_event,
+                callback,
+                &self.options.output_dir,
+                Notifiers::default(),
+                Some(&self.state),
+            ))
+            .await;
+        if result.is_err() {
+            self.state.fail();
         }
+        result
+    }
 
-        callback.on_status(&format!("连接到 WiFi: {}", p2p_info.ssid));
-
-        // 连接到 WiFi P2P 热点（支持双连接）
-        let mut wifi_receiver = WiFiP2pReceiver::new(&self.options.wifi_interface);
-        let local_ip = wifi_receiver.connect(&p2p_info).await?;
+    /// 从发送端二维码中的引导载荷接收文件
+    ///
+    /// 载荷用本机身份密钥解密，因此发送端加密时用的公钥必须来自 [`Self::with_security`]
+    /// 给出的同一身份。
+    #[tracing::instrument(skip_all, fields(session_id = tracing::field::Empty))]
+    pub async fn receive_payload<C: ReceiveProgressCallback>(
+        &self,
+        payload: &str,
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        callback.on_started(&start_session());
+        let p2p_event = bootstrap::decode(payload, &self.security)?;
+        self.handle_p2p_event(p2p_event, callback).await
+    }
 
-        // 显示连接状态
-        if wifi_receiver.is_dual_connected().await {
-            callback.on_status(&format!("✅ 已连接（双连接模式），本地 IP: {}", local_ip));
-        } else {
-            callback.on_status(&format!("✅ 已连接，本地 IP: {}", local_ip));
+    /// 接入发送端网络并把文件接收到 `output_dir`
+    ///
+    /// `notifiers.status` 存在时，对传输请求的接受/拒绝会通过 STATUS 通知推送给发送端；
+    /// 建组请求交给 [`Self::host_group`]。`state` 存在时按阶段推进（失败由调用方标记）。
+    async fn receive_into<C: ReceiveProgressCallback>(
+        &self,
+        p2p_event: P2pReceiveEvent,
+        callback: &C,
+        output_dir: &Path,
+        notifiers: Notifiers,
+        state: Option<&StateMachine<ReceivePhase>>,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        if p2p_event.p2p_info.is_group_request() {
+            return self
+                .host_group(&p2p_event, callback, output_dir, notifiers.p2p, state)
+                .await;
         }
+        let status = notifiers.status;
+        advance(state, ReceivePhase::JoiningNetwork);
+        let (sender_ip, fallback_hosts) = self.join_link(&p2p_event, callback).await?;
+        let verification_code = self.verification_code(&p2p_event);
+        let p2p_info = p2p_event.p2p_info;
+        let quirks = self.quirks_for(p2p_info.cat_share);
+        let port = quirks.port(p2p_info.port as u16);
 
-        // 计算发送端 IP (通常是网关)
-        let sender_ip = self.get_gateway_ip(&local_ip);
+        // 反向上传服务需在接入网络后、对端开始推送前启动
+        let upload = match self.options.upload_port {
+            Some(port) => {
+                let (handle, rx) = UploadServer::new(output_dir.to_path_buf())
+                    .with_port(port)
+                    .start()
+                    .await?;
+                callback.on_status(&crate::tr!(
+                    "workflow.receive.upload_enabled",
+                    port = handle.port()
+                ));
+                Some((handle, rx))
+            }
+            None => None,
+        };
 
-        callback.on_status(&format!(
-            "连接到 WebSocket: wss://{}:{}/websocket",
-            sender_ip, p2p_info.port
+        callback.on_status(&crate::tr!(
+            "workflow.receive.connecting",
+            ip = sender_ip,
+            port = port,
+            transport = self.transport.name()
         ));
 
         // 创建接收适配器
         let adapter = ReceiverCallbackAdapter {
             callback,
             auto_accept: self.options.auto_accept,
+            verification_code,
+            tracker: Mutex::new(None),
+            status,
+            state,
         };
 
         // 接收文件
-        let client = ReceiverClient::new(
-            &sender_ip,
-            p2p_info.port as u16,
-            self.options.output_dir.clone(),
-        );
+        let target = TransferTarget {
+            host: sender_ip,
+            fallback_hosts,
+            port,
+            tls: self.options.use_tls,
+            output_dir: output_dir.to_path_buf(),
+            restore_permissions: self.options.restore_permissions,
+            encryption: self.options.encryption_key.clone(),
+            control: self.control.clone(),
+            sort_by_sender: self.options.sort_by_sender,
+            auth_token: p2p_info.auth_token.clone(),
+            pinned_cert: p2p_info.cert_sha256.clone(),
+            allow_sync_delete: self.options.allow_sync_delete,
+        };
 
-        let files = client.start(&adapter).await?;
+        // 刚接入热点时发送端可能还不可达，连接阶段按策略重试
+        advance(state, ReceivePhase::Connecting);
+        if let Some(delay) = quirks.websocket_delay() {
+            tokio::time::sleep(delay).await;
+        }
+        let connection = quirks
+            .retry(self.options.retry)
+            .run(
+                "connect",
+                || self.transport.connect(&target),
+                |r| callback.on_status(&r.to_string()),
+            )
+            .await?;
+        let mut files = connection.receive(&adapter).await?;
+
+        if let Some((_handle, rx)) = upload {
+            advance(state, ReceivePhase::WaitingForUploads);
+            files.extend(self.wait_for_uploads(rx, callback).await);
+        }
 
         // 断开 WiFi 并清理虚拟接口
-        wifi_receiver.disconnect().await?;
+        if !p2p_info.is_lan_direct() {
+            self.wifi.disconnect().await?;
+        }
 
+        advance(state, ReceivePhase::Done);
         callback.on_complete(files.clone());
 
         Ok(files)
     }
 
+    /// 反向模式：本机创建热点，经 P2P 特征的通知把热点信息发给发送端，再接收它上传的文件
+    ///
+    /// 热点信息用与发送端的会话密钥加密（发送端没有公钥时为明文），端口为上传服务的端口。
+    #[tracing::instrument(skip_all)]
+    async fn host_group<C: ReceiveProgressCallback>(
+        &self,
+        p2p_event: &P2pReceiveEvent,
+        callback: &C,
+        output_dir: &Path,
+        notifier: Option<P2pNotifier>,
+        state: Option<&StateMachine<ReceivePhase>>,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        if !self.options.group_owner {
+            anyhow::bail!("已拒绝发送端的建组请求（group_owner 已关闭）");
+        }
+        let notifier = notifier.ok_or_else(|| anyhow::anyhow!("建组请求只能通过 BLE 回复"))?;
+        callback.on_status(&crate::tr!("workflow.receive.group_requested"));
+        let verification_code = self.verification_code(p2p_event);
+        if let Some(code) = &verification_code {
+            callback.on_verification_code(code);
+        }
+
+        // 建组请求不带文件清单，只能按发送端询问
+        let request = ReceiveRequest {
+            sender_name: p2p_event.p2p_info.id.clone().unwrap_or_default(),
+            file_name: String::new(),
+            file_count: 0,
+            total_size: 0,
+            verification_code,
+        };
+        if !self.options.auto_accept && !callback.on_request(&request) {
+            anyhow::bail!("已拒绝发送端的建组请求");
+        }
+
+        advance(state, ReceivePhase::JoiningNetwork);
+        let (upload, rx) = UploadServer::new(output_dir.to_path_buf())
+            .with_port(self.options.upload_port.unwrap_or(0))
+            .start()
+            .await?;
+        let port = upload.port();
+        callback.on_status(&crate::tr!("workflow.creating_hotspot"));
+        let credentials = CredentialPolicy::default().for_peer(PeerSupport::CATSHARE);
+        let group = self.wifi.create_hotspot(port as i32, &credentials).await?;
+        let port_access = self.wifi.allow_port(port).await;
+
+        let result = async {
+            let info = match p2p_event.sender_public_key.as_deref() {
+                Some(key) => {
+                    let cipher = self.security.derive_session_key(key)?;
+                    let id = identity::sender_id(self.security.get_public_key());
+                    GattHandler::encrypt_p2p_info(
+                        &group,
+                        &cipher,
+                        &id,
+                        self.security.get_public_key(),
+                    )?
+                }
+                None => group.clone(),
+            };
+            if !notifier.notify(serde_json::to_vec(&info)?) {
+                anyhow::bail!("发送端没有订阅 P2P 通知，无法回复建组请求");
+            }
+            callback.on_status(&crate::tr!(
+                "workflow.receive.hotspot_created",
+                ssid = group.ssid
+            ));
+            advance(state, ReceivePhase::Connecting);
+            advance(state, ReceivePhase::WaitingForUploads);
+            let files = self.wait_for_uploads(rx, callback).await;
+            if files.is_empty() {
+                anyhow::bail!("发送端没有接入热点或没有上传文件");
+            }
+            Ok(files)
+        }
+        .await;
+
+        drop(upload);
+        port_access.close().await;
+        if let Err(e) = self.wifi.stop_hotspot().await {
+            warn!("Failed to stop hotspot: {}", e);
+        }
+        let files = result?;
+        advance(state, ReceivePhase::Done);
+        callback.on_complete(files.clone());
+        Ok(files)
+    }
+
+    /// 与发送端建立双向传输会话
+    ///
+    /// 与 [`Self::handle_p2p_event`] 一样接入发送端网络，但不等待发送端推送，
+    /// 而是返回保持连接的 [`Session`]。会话结束后需调用 [`Self::disconnect`]。
+    #[tracing::instrument(
+        skip_all,
+        fields(session_id = tracing::field::Empty, ssid = %p2p_event.p2p_info.ssid, port = p2p_event.p2p_info.port)
+    )]
+    pub async fn open_session<C: ReceiveProgressCallback>(
+        &self,
+        p2p_event: P2pReceiveEvent,
+        callback: &C,
+    ) -> anyhow::Result<Session> {
+        callback.on_started(&start_session());
+        let quirks = self.quirks_for(p2p_event.p2p_info.cat_share);
+        let port = quirks.port(p2p_event.p2p_info.port as u16);
+        self.cancellable(async {
+            let (sender_ip, fallback_hosts) = self.join_link(&p2p_event, callback).await?;
+            if let Some(delay) = quirks.websocket_delay() {
+                tokio::time::sleep(delay).await;
+            }
+            quirks
+                .retry(self.options.retry)
+                .run(
+                    "websocket",
+                    || {
+                        Session::connect(
+                            &sender_ip,
+                            &fallback_hosts,
+                            port,
+                            self.options.use_tls,
+                            &self.options.device_name,
+                        )
+                    },
+                    |r| callback.on_status(&r.to_string()),
+                )
+                .await
+        })
+        .await
+    }
+
+    /// 断开发送端的 WiFi 热点并清理虚拟接口
+    pub async fn disconnect(&self) -> anyhow::Result<()> {
+        self.wifi.disconnect().await
+    }
+
+    /// 按 P2P 信息接入发送端所在网络，返回发送端的首选 IP 和首选 IP 不可达时依次尝试的备选 IP
+    #[tracing::instrument(skip_all)]
+    async fn join_link<C: ReceiveProgressCallback>(
+        &self,
+        p2p_event: &P2pReceiveEvent,
+        callback: &C,
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        // P2P 信息已由 GattServer 自动解密（如果提供了公钥）
+        let p2p_info = &p2p_event.p2p_info;
+
+        if p2p_event.sender_public_key.is_some() {
+            callback.on_status(&crate::tr!("workflow.receive.p2p_decrypted"));
+            if let Some(code) = self.verification_code(p2p_event) {
+                callback.on_verification_code(&code);
+            }
+        } else {
+            callback.on_status(&crate::tr!("workflow.receive.p2p_received"));
+        }
+
+        // 局域网直连模式：发送端已在当前网络提供服务，无需连接热点
+        if let Some(host) = &p2p_info.host {
+            callback.on_status(&crate::tr!("workflow.receive.lan_direct", host = host));
+            return Ok(host.clone());
+        }
+
+        callback.on_status(&crate::tr!(
+            "workflow.receive.joining_wifi",
+            ssid = p2p_info.ssid
+        ));
+
+        // 连接到 WiFi P2P 热点（支持双连接）
+        let joined = cancel::run(
+            &self.cancel,
+            Some(CONNECT_TIMEOUT),
+            self.wifi.connect(p2p_info),
+        )
+        .await;
+        let local_ip = match joined {
+            Ok(joined) => joined?,
+            Err(interrupted) => {
+                // 超时时 NetworkManager 可能仍在激活连接
+                let _ = self.wifi.disconnect().await;
+                return Err(interrupted.into());
+            }
+        };
+
+        // 显示连接状态
+        if self.wifi.is_dual_connected().await {
+            callback.on_status(&crate::tr!(
+                "workflow.receive.connected_dual",
+                ip = local_ip
+            ));
+        } else {
+            callback.on_status(&crate::tr!("workflow.receive.connected", ip = local_ip));
+        }
+
+        let mut sender_ips = self.wifi.sender_candidates(p2p_info, &local_ip).await;
+        if sender_ips.is_empty() {
+            sender_ips.push(self.wifi.sender_ip(p2p_info, &local_ip).await);
+        }
+        callback.on_status(&crate::tr!(
+            "workflow.receive.sender_addresses",
+            addresses = sender_ips.join(" / ")
+        ));
+        let sender_ip = sender_ips.remove(0);
+        Ok((sender_ip, sender_ips))
+    }
+
+    /// 与发送端的验证码（P2P 信息未加密时为 `None`）
+    fn verification_code(&self, p2p_event: &P2pReceiveEvent) -> Option<String> {
+        let key = p2p_event.sender_public_key.as_deref()?;
+        let cipher = self.security.derive_session_key(key).ok()?;
+        Some(cipher.verification_code())
+    }
+
+    /// 等待对端反向上传，`upload_idle_timeout` 内没有新文件完成即结束
+    async fn wait_for_uploads<C: ReceiveProgressCallback>(
+        &self,
+        mut rx: mpsc::Receiver<PathBuf>,
+        callback: &C,
+    ) -> Vec<PathBuf> {
+        callback.on_status(&crate::tr!("workflow.receive.waiting_uploads"));
+        let mut uploaded = Vec::new();
+        while let Ok(Some(path)) =
+            tokio::time::timeout(self.options.upload_idle_timeout, rx.recv()).await
+        {
+            callback.on_upload(&path);
+            uploaded.push(path);
+        }
+        uploaded
+    }
+
     /// 获取 MAC 地址
     fn get_mac_address(&self) -> String {
         let path = format!("/sys/class/net/{}/address", self.options.wifi_interface);
@@ -174,27 +914,120 @@ impl Receiver {
             .map(|s| s.trim().to_uppercase())
             .unwrap_or_else(|_| "02:00:00:00:00:00".to_string())
     }
+}
 
-    /// 从本地 IP 推断网关 IP
-    fn get_gateway_ip(&self, local_ip: &str) -> String {
-        // 通常网关是 x.x.x.1
-        let parts: Vec<&str> = local_ip.split('.').collect();
-        if parts.len() == 4 {
-            format!("{}.{}.{}.1", parts[0], parts[1], parts[2])
-        } else {
-            "192.168.49.1".to_string()
+/// 接收端广播（GATT 和/或局域网），持续产生发送端写入的 P2P 信息
+struct Listener {
+    gatt: Option<GattServerHandle>,
+    lan: Option<LanAdvertiserHandle>,
+    ble_rx: Option<mpsc::Receiver<P2pReceiveEvent>>,
+    lan_rx: Option<mpsc::Receiver<P2pReceiveEvent>>,
+    connection_rx: Option<mpsc::Receiver<GattConnectionEvent>>,
+    pairing_rx: Option<mpsc::Receiver<PairingRequest>>,
+}
+
+impl Listener {
+    /// 等待下一个发送端的 P2P 信息（来自 BLE 或局域网），期间转发蓝牙连接事件、
+    /// 回答配对提示（配对发生在发送端写入 P2P 信息之前）
+    async fn next_event<C: ReceiveProgressCallback>(
+        &mut self,
+        callback: &C,
+    ) -> anyhow::Result<P2pReceiveEvent> {
+        let Self {
+            ble_rx,
+            lan_rx,
+            connection_rx,
+            pairing_rx,
+            ..
+        } = self;
+        loop {
+            tokio::select! {
+                Some(event) = async { ble_rx.as_mut()?.recv().await }, if ble_rx.is_some() => return Ok(event),
+                Some(event) = async { lan_rx.as_mut()?.recv().await }, if lan_rx.is_some() => return Ok(event),
+                event = async { connection_rx.as_mut()?.recv().await }, if connection_rx.is_some() => {
+                    match event {
+                        Some(event) => callback.on_ble_connection(&event),
+                        None => *connection_rx = None,
+                    }
+                }
+                Some(request) = async { pairing_rx.as_mut()?.recv().await }, if pairing_rx.is_some() => {
+                    let accept = callback.on_pairing(&request.prompt);
+                    request.respond(accept);
+                }
+                else => return Err(anyhow::anyhow!("P2P channel closed")),
+            }
         }
     }
+
+    /// GATT 服务的通知推送端（未开启 BLE 时为空）
+    fn notifiers(&self) -> Notifiers {
+        Notifiers {
+            status: self.gatt.as_ref().map(GattServerHandle::status_notifier),
+            p2p: self.gatt.as_ref().map(GattServerHandle::p2p_notifier),
+        }
+    }
+
+    /// 同步更新 GATT 和局域网握手返回的 DeviceInfo 状态
+    async fn set_state(&self, state: ReceiverState) {
+        if let Some(gatt) = &self.gatt {
+            gatt.set_state(state).await;
+        }
+        if let Some(lan) = &self.lan {
+            lan.set_state(state);
+        }
+    }
+}
+
+/// 会话可用的 GATT 通知推送端
+#[derive(Clone, Default)]
+struct Notifiers {
+    /// STATUS 特征：接受/拒绝传输请求
+    status: Option<StatusNotifier>,
+    /// P2P 特征：回复建组请求
+    p2p: Option<P2pNotifier>,
+}
+
+/// 推进可选的状态机，非法转换只记录日志
+fn advance(state: Option<&StateMachine<ReceivePhase>>, phase: ReceivePhase) {
+    if let Some(Err(e)) = state.map(|s| s.advance(phase)) {
+        warn!("{}", e);
+    }
 }
 
 /// 接收回调适配器
 struct ReceiverCallbackAdapter<'a, C: ReceiveProgressCallback> {
     callback: &'a C,
     auto_accept: bool,
+    /// 本端验证码
+    verification_code: Option<String>,
+    tracker: Mutex<Option<StatsTracker>>,
+    status: Option<StatusNotifier>,
+    state: Option<&'a StateMachine<ReceivePhase>>,
 }
 
-impl<C: ReceiveProgressCallback> ReceiverCallback for ReceiverCallbackAdapter<'_, C> {
-    fn on_send_request(&self, request: &SendRequest) -> bool {
+impl<C: ReceiveProgressCallback> ReceiverCallbackAdapter<'_, C> {
+    fn decide(&self, request: &SendRequest) -> bool {
+        // CatShare 不发送逐文件信息时，整个任务按一个文件统计
+        let tracker = if request.files.is_empty() {
+            StatsTracker::new([(request.file_name.clone(), request.total_size)])
+        } else {
+            StatsTracker::new(request.files.iter().map(|f| (f.name.clone(), f.size)))
+        };
+        *self.tracker.lock().unwrap() = Some(tracker);
+
+        // 发送端带了验证码却与本端不一致，说明两端的共享密钥不同，即使自动接受也拒绝
+        if let (Some(local), Some(remote)) = (&self.verification_code, &request.verification_code)
+            && local != remote
+        {
+            warn!(
+                "Verification code mismatch (local {}, sender {}), rejecting",
+                local, remote
+            );
+            self.callback
+                .on_status(&crate::tr!("workflow.receive.code_mismatch"));
+            return false;
+        }
+
         if self.auto_accept {
             return true;
         }
@@ -204,13 +1037,44 @@ impl<C: ReceiveProgressCallback> ReceiverCallback for ReceiverCallbackAdapter<'_
             file_name: request.file_name.clone(),
             file_count: request.file_count,
             total_size: request.total_size,
+            verification_code: self.verification_code.clone(),
         };
 
         self.callback.on_request(&req)
     }
+}
+
+impl<C: ReceiveProgressCallback> ReceiverCallback for ReceiverCallbackAdapter<'_, C> {
+    fn on_send_request(&self, request: &SendRequest) -> bool {
+        let accepted = self.decide(request);
+        if accepted {
+            advance(self.state, ReceivePhase::Receiving);
+        }
+        if let Some(status) = &self.status {
+            status.notify(if accepted {
+                ReceiverState::Accepted
+            } else {
+                ReceiverState::Rejected
+            });
+        }
+        accepted
+    }
 
     fn on_progress(&self, received: u64, total: u64) {
         self.callback.on_progress(received, total);
+        let stats = self
+            .tracker
+            .lock()
+            .unwrap()
+            .as_mut()
+            .and_then(|t| t.update(received));
+        if let Some(stats) = stats {
+            self.callback.on_stats(&stats);
+        }
+    }
+
+    fn on_paused(&self, paused: bool) {
+        self.callback.on_paused(paused);
     }
 
     fn on_complete(&self, files: Vec<PathBuf>) {
@@ -230,10 +1094,31 @@ pub struct SimpleReceiveCallback {
 
 #[derive(Debug, Clone)]
 pub enum ReceiveEvent {
+    /// 工作流开始（总是第一个事件）
+    Started {
+        session_id: String,
+    },
     Status(String),
+    /// 发送端蓝牙连接/断开
+    BleConnection(GattConnectionEvent),
+    /// 与发送端核对的验证码
+    VerificationCode(String),
     Request(ReceiveRequest),
-    Progress { received: u64, total: u64 },
+    /// 与发送端配对，应显示其中的数字或 PIN 码
+    Pairing(PairingPrompt),
+    Progress {
+        received: u64,
+        total: u64,
+    },
+    /// 传输统计
+    Stats(TransferStats),
+    /// 下载已暂停
+    Paused,
+    /// 下载已恢复
+    Resumed,
     Complete(Vec<PathBuf>),
+    /// 对端反向上传的文件
+    Uploaded(PathBuf),
     Error(String),
 }
 
@@ -245,23 +1130,62 @@ impl SimpleReceiveCallback {
 }
 
 impl ReceiveProgressCallback for SimpleReceiveCallback {
+    fn on_started(&self, session_id: &str) {
+        let _ = self.tx.try_send(ReceiveEvent::Started {
+            session_id: session_id.to_string(),
+        });
+    }
+
     fn on_status(&self, status: &str) {
         let _ = self.tx.try_send(ReceiveEvent::Status(status.to_string()));
     }
 
+    fn on_ble_connection(&self, event: &GattConnectionEvent) {
+        let _ = self.tx.try_send(ReceiveEvent::BleConnection(event.clone()));
+    }
+
+    fn on_verification_code(&self, code: &str) {
+        let _ = self
+            .tx
+            .try_send(ReceiveEvent::VerificationCode(code.to_string()));
+    }
+
     fn on_request(&self, request: &ReceiveRequest) -> bool {
         let _ = self.tx.try_send(ReceiveEvent::Request(request.clone()));
         self.auto_accept
     }
 
+    /// 与传输请求一样，自动接受时同意数字比较
+    fn on_pairing(&self, prompt: &PairingPrompt) -> bool {
+        let _ = self.tx.try_send(ReceiveEvent::Pairing(prompt.clone()));
+        self.auto_accept
+    }
+
     fn on_progress(&self, received: u64, total: u64) {
         let _ = self.tx.try_send(ReceiveEvent::Progress { received, total });
     }
 
+    fn on_stats(&self, stats: &TransferStats) {
+        let _ = self.tx.try_send(ReceiveEvent::Stats(stats.clone()));
+    }
+
+    fn on_paused(&self, paused: bool) {
+        let event = if paused {
+            ReceiveEvent::Paused
+        } else {
+            ReceiveEvent::Resumed
+        };
+        let _ = self.tx.try_send(event);
+    }
+
     fn on_complete(&self, files: Vec<PathBuf>) {
         let _ = self.tx.try_send(ReceiveEvent::Complete(files));
     }
 
+    fn on_upload(&self, path: &Path) {
+        let _ = self.tx.try_send(ReceiveEvent::Uploaded(path.to_path_buf()));
+    }
+
     fn on_error(&self, error: &str) {
         let _ = self.tx.try_send(ReceiveEvent::Error(error.to_string()));
     }
@@ -5,45 +5,386 @@
 //! 2. 启动 HTTP 传输服务器
 //! 3. 通过 BLE 连接接收端并发送 P2P 信息
 //! 4. 等待接收端连接和下载文件
+//!
+//! 局域网直连模式 ([`TransferMode::LanDirect`]) 跳过第 1 步，
+//! 直接在现有网络的 IP 上提供传输服务。
+//!
+//! 反向模式 ([`TransferMode::JoinReceiver`]) 由接收端建组：发送端通过 BLE 请求建组，
+//! 从 CHAR_P2P 的通知中拿到接收端的 P2P 信息，加入后把文件上传到接收端。
+//!
+//! 热点模式下传输端口会经 [`WifiBackend::allow_port`] 在防火墙中临时放行；
+//! 无法放行且接收端一直没连上时，返回 [`FirewallError`](crate::firewall::FirewallError)。
+//!
+//! 蓝牙不可用时，[`Sender::send_via_payload`] 不与接收端握手，而是把 P2P 信息编码为
+//! 引导载荷（见 [`bootstrap`]）交给调用方以二维码显示，由接收端扫描后接入。
+//!
+//! [`Sender::open_session`] 建立链路后不结束连接，返回可双向传输的 [`Session`]。
+//!
+//! [`Sender::sync_to_device`] 把整个目录同步给另一台 cattysend（见 [`crate::sync`]）。
+//!
+//! 接收端开启了反向上传时，可以在同一会话内用 [`Sender::push_files`]
+//! 把文件推给接收端（热点模式下需设置 [`SendOptions::keep_hotspot`]）。
+//!
+//! 阶段转换按 [`SendPhase`] 的转换表校验，当前状态可以通过 [`Sender::state`] 订阅。
+//!
+//! 热点频段、重试次数和 BLE 握手超时按接收端品牌查 [`QuirksTable`]（见 [`crate::quirks`]）调整。
+//!
+//! [`Sender::with_standby`] 使用提前准备好的热点连接和传输端口（见 [`crate::workflow::standby`]）。
+//!
+//! [`Sender::with_cancellation`] 的令牌取消后，扫描、握手和等待传输都会立即结束，
+//! 热点随之关闭（反向模式下离开接收端的网络）。
 
-use crate::ble::{BleClient, DiscoveredDevice};
-use crate::crypto::BleSecurityPersistent;
-use crate::transfer::{FileEntry, TransferServer, TransferTask};
-use crate::wifi::{P2pConfig, WiFiP2pSender};
+use crate::ble::pairing::{self, PairingPrompt, PairingRequest};
+use crate::ble::{
+    BleClient, BleClientError, DiscoveredDevice, GattClientBackend, HandshakeStep,
+    PendingHandshake, ScanCallback,
+};
+use crate::cancel::{self, CONNECT_TIMEOUT, CancellationToken, Interrupted};
+use crate::config::{BrandId, PortRange};
+use crate::crypto::{BleSecurityPersistent, TlsIdentity, identity};
+use crate::discovery::lan::{lan_handshake, local_ip_towards};
+use crate::discovery::{DiscoveryMethod, bootstrap, discover_devices};
+use crate::firewall::PortAccess;
+use crate::power::PowerState;
+use crate::quirks::{Quirks, QuirksTable};
+use crate::sync::SyncJob;
+use crate::transfer::{
+    FileEntry, HttpTransport, StatsTracker, SyncRequest, TransferStats, TransferTask,
+    TransferTransport, upload_file,
+};
+use crate::wifi::{
+    BusyInterfacePolicy, CredentialPolicy, InterfaceBusy, LinuxWifiBackend, NmPermissionDenied,
+    P2pConfig, P2pInfo, P2pInfoError, PeerSupport, WifiBackend,
+};
+use crate::workflow::session::{Session, SessionListener};
+use crate::workflow::standby::Standby;
+use crate::workflow::start_session;
+use crate::workflow::state::{StateMachine, WorkflowState};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::tungstenite::http::StatusCode;
+
+/// 反向模式下等待接收端建组并发回 P2P 信息的时间
+const GROUP_INFO_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// 发送进度回调
 pub trait SendProgressCallback: Send + Sync {
+    /// 工作流开始，之后的事件都属于 `session_id` 这次会话
+    fn on_started(&self, _session_id: &str) {}
     /// 状态更新
     fn on_status(&self, status: &str);
+    /// 进入新的阶段（用于步骤指示，`on_status` 的文本仅供显示）
+    fn on_phase(&self, _phase: SendPhase) {}
+    /// 传输服务已开始监听（最终选定的端口，会写入 P2P 信息）
+    fn on_listening(&self, _port: u16) {}
+    /// 与接收端协商出的验证码（接收端没有公钥时不会调用），应显示给用户与接收端核对
+    fn on_verification_code(&self, _code: &str) {}
+    /// 引导载荷已生成（仅 [`Sender::send_via_payload`]），应以二维码显示给接收端扫描
+    fn on_bootstrap_payload(&self, _payload: &str) {}
+    /// 某个阶段失败，即将重试
+    fn on_retry(&self, _retry: &RetryAttempt) {}
+    /// 接收端已接入热点
+    fn on_receiver_joined(&self, _mac: &str, _ip: &str) {}
+    /// 热点接口已连接 WiFi，创建热点会断开它；之后按 [`SendOptions::busy_interface`] 处理
+    fn on_interface_busy(&self, _busy: &InterfaceBusy) {}
+    /// BLE 握手需要配对；返回 `true` 同意数字比较，只显示的提示忽略返回值
+    ///
+    /// 默认拒绝需要确认的配对，Just-Works 配对不经过这里。
+    fn on_pairing(&self, _prompt: &PairingPrompt) -> bool {
+        false
+    }
     /// 进度更新
     fn on_progress(&self, sent: u64, total: u64);
+    /// 传输统计（逐文件进度和速度，约每秒一次）
+    fn on_stats(&self, _stats: &TransferStats) {}
+    /// 接收端暂停（`true`）或恢复（`false`）了下载
+    fn on_paused(&self, _paused: bool) {}
     /// 发送完成
     fn on_complete(&self);
     /// 发送失败
     fn on_error(&self, error: &str);
 }
 
+/// 发送流程的阶段
+///
+/// 按先后顺序排列；失败重试时可能回到较早的阶段，允许的转换见 [`Phase`](crate::workflow::Phase) 实现。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SendPhase {
+    /// 扫描接收端（[`Sender::send_to_device`] 不扫描，由调用 [`Sender::discover`] 的一方上报）
+    Scanning,
+    /// 准备链路并连接接收端（BLE 或局域网）
+    Connecting,
+    /// 读取接收端的 DeviceInfo
+    ReadingStatus,
+    /// 写入加密的 P2P 信息
+    WritingP2p,
+    /// 等待接收端接入热点并发起下载
+    WaitingForReceiverWifi,
+    /// 正在传输
+    Transferring,
+    /// 传输完成
+    Done,
+}
+
+impl SendPhase {
+    /// 全部阶段，按先后顺序
+    pub const ALL: [SendPhase; 7] = [
+        SendPhase::Scanning,
+        SendPhase::Connecting,
+        SendPhase::ReadingStatus,
+        SendPhase::WritingP2p,
+        SendPhase::WaitingForReceiverWifi,
+        SendPhase::Transferring,
+        SendPhase::Done,
+    ];
+
+    /// 在 [`Self::ALL`] 中的位置
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl From<HandshakeStep> for SendPhase {
+    fn from(step: HandshakeStep) -> Self {
+        match step {
+            HandshakeStep::ReadingStatus => SendPhase::ReadingStatus,
+            HandshakeStep::WritingP2p => SendPhase::WritingP2p,
+        }
+    }
+}
+
+/// 传输方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMode {
+    /// 创建 WiFi P2P 热点，接收端连接热点后下载（CatShare 兼容）
+    #[default]
+    Hotspot,
+    /// 双方已在同一局域网，直接使用现有网络的 IP，不创建热点
+    LanDirect,
+    /// 由接收端建组（Group Owner），发送端加入后把文件上传到接收端
+    ///
+    /// 适用于只愿意做 Group Owner 的 CatShare 设备，接收端需开启反向上传。
+    JoinReceiver,
+}
+
+/// 重试策略
+///
+/// 热点创建、BLE/局域网握手和接收端的 WebSocket 连接都按此策略重试。
+/// 第 n 次重试前等待 `initial_backoff * multiplier^(n-1)`（不超过 `max_backoff`），
+/// 再叠加 ±`jitter` 比例的随机抖动。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// 最大尝试次数（含第一次，1 表示不重试）
+    pub max_attempts: u32,
+    /// 第一次重试前的等待时间
+    pub initial_backoff: Duration,
+    /// 等待时间上限
+    pub max_backoff: Duration,
+    /// 每次重试等待时间的倍数
+    pub multiplier: f64,
+    /// 随机抖动比例 (0.0 ~ 1.0)
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+/// 一次即将进行的重试
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    /// 失败的阶段（如 "hotspot"、"handshake"、"connect"）
+    pub stage: &'static str,
+    /// 刚刚失败的是第几次尝试（从 1 开始）
+    pub attempt: u32,
+    pub max_attempts: u32,
+    /// 下一次尝试前的等待时间
+    pub delay: Duration,
+    /// 失败原因
+    pub error: String,
+}
+
+impl std::fmt::Display for RetryAttempt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = crate::tr!(
+            "workflow.retry",
+            stage = self.stage,
+            attempt = self.attempt,
+            max = self.max_attempts,
+            error = self.error,
+            delay = format!("{:.1}", self.delay.as_secs_f64())
+        );
+        f.write_str(&text)
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 第 `attempt` 次失败后的基础等待时间（不含抖动）
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = attempt.saturating_sub(1).min(16) as i32;
+        let secs = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exp);
+        Duration::from_secs_f64(secs.min(self.max_backoff.as_secs_f64()))
+    }
+
+    fn jittered(&self, attempt: u32) -> Duration {
+        let base = self.backoff(attempt).as_secs_f64();
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 + jitter * (rand::random::<f64>() * 2.0 - 1.0);
+        Duration::from_secs_f64(base * factor)
+    }
+
+    /// 按策略执行 `op`，每次重试前调用 `on_retry`
+    pub async fn run<T, F, Fut>(
+        &self,
+        stage: &'static str,
+        mut op: F,
+        mut on_retry: impl FnMut(&RetryAttempt),
+    ) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let max_attempts = self.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < max_attempts && Failure::classify(&e) == Failure::Transient => {
+                    let retry = RetryAttempt {
+                        stage,
+                        attempt,
+                        max_attempts,
+                        delay: self.jittered(attempt),
+                        error: e.to_string(),
+                    };
+                    warn!("{}", retry);
+                    on_retry(&retry);
+                    tokio::time::sleep(retry.delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    crate::metrics::failure(stage);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// 失败的性质，决定 [`RetryPolicy::run`] 是否重试
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// 超时、连接断开、热点还没就绪等，重试可能成功
+    Transient,
+    /// 重试也不会成功
+    Permanent,
+}
+
+impl Failure {
+    /// 给错误分类
+    ///
+    /// 以下错误不重试，其余一律按暂时性处理：
+    /// - 操作已被取消
+    /// - NetworkManager 拒绝了授权（重试只会反复弹出认证对话框）
+    /// - 接收端正忙，或拒绝了 P2P 信息
+    /// - P2P 信息本身无效
+    /// - 发送端以 401/403 拒绝了 WebSocket 连接（访问令牌不对）
+    pub fn classify(error: &anyhow::Error) -> Self {
+        let permanent = cancel::is_cancelled(error)
+            || error.is::<NmPermissionDenied>()
+            || error.is::<P2pInfoError>()
+            || matches!(
+                error.downcast_ref::<BleClientError>(),
+                Some(BleClientError::DeviceBusy | BleClientError::P2pRejected)
+            )
+            || matches!(
+                error.downcast_ref::<WsError>(),
+                Some(WsError::Http(response))
+                    if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+            );
+        if permanent {
+            Self::Permanent
+        } else {
+            Self::Transient
+        }
+    }
+}
+
+/// 默认的热点空闲超时：热点建好后没有接收端接入的最长时间
+pub const DEFAULT_HOTSPOT_IDLE_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+
 /// 发送选项
 pub struct SendOptions {
     /// WiFi 接口名称
     pub wifi_interface: String,
+    /// 热点使用的网卡（`None` 时使用 `wifi_interface`，被占用时按 `busy_interface` 处理）
+    pub hotspot_interface: Option<String>,
     /// 是否使用 5GHz
     pub use_5ghz: bool,
     /// 发送者名称
     pub sender_name: String,
+    /// 发现方式（BLE / 局域网 mDNS / 两者）
+    pub discovery: DiscoveryMethod,
+    /// 传输方式
+    pub transfer_mode: TransferMode,
+    /// 热点创建和握手的重试策略
+    pub retry: RetryPolicy,
+    /// 发送完成后保留热点，以便继续与接收端互传；由调用方用 [`Sender::stop_hotspot`] 关闭
+    pub keep_hotspot: bool,
+    /// 热点建好后这么久仍没有接收端接入时关闭热点并放弃发送（`None` 表示只受总超时限制）
+    pub hotspot_idle_timeout: Option<Duration>,
+    /// 传输服务的监听端口范围（`None` 为随机端口），见 [`AppSettings::transfer_ports`](crate::AppSettings::transfer_ports)
+    pub ports: Option<PortRange>,
+    /// 热点 SSID/口令策略，按接收端的支持情况降级，见 [`AppSettings::hotspot_credentials`](crate::AppSettings::hotspot_credentials)
+    pub credentials: CredentialPolicy,
+    /// 使用电池供电且电量低于该百分比时拒绝创建热点（0 表示不检查），见 [`crate::power`]
+    pub min_battery_percent: u8,
+    /// 热点接口已连接 WiFi 时的处理方式，见 [`InterfaceBusy`]
+    pub busy_interface: BusyInterfacePolicy,
 }
 
 impl Default for SendOptions {
     fn default() -> Self {
         Self {
             wifi_interface: "wlan0".to_string(),
+            hotspot_interface: None,
             use_5ghz: true,
             sender_name: hostname::get()
                 .map(|h| h.to_string_lossy().to_string())
                 .unwrap_or_else(|_| "Cattysend".to_string()),
+            discovery: DiscoveryMethod::default(),
+            transfer_mode: TransferMode::default(),
+            retry: RetryPolicy::default(),
+            keep_hotspot: false,
+            hotspot_idle_timeout: Some(DEFAULT_HOTSPOT_IDLE_TIMEOUT),
+            ports: None,
+            credentials: CredentialPolicy::default(),
+            min_battery_percent: 0,
+            busy_interface: BusyInterfacePolicy::default(),
         }
     }
 }
@@ -51,119 +392,440 @@ impl Default for SendOptions {
 /// 发送端工作流
 pub struct Sender {
     options: SendOptions,
-    wifi_sender: WiFiP2pSender,
+    wifi: Arc<dyn WifiBackend>,
+    /// BLE 客户端后端（None 表示使用平台默认后端）
+    ble_backend: Option<Arc<dyn GattClientBackend>>,
+    transport: Arc<dyn TransferTransport>,
     security: Arc<BleSecurityPersistent>,
+    /// 会话期间放行的端口，随热点一起关闭
+    session_port: Mutex<Option<PortAccess>>,
+    /// 最近一次发送的状态
+    state: StateMachine<SendPhase>,
+    cancel: CancellationToken,
+    /// 厂商兼容性表
+    quirks: QuirksTable,
+}
+
+/// 按发送选项创建的默认 WiFi 后端
+pub(crate) fn default_wifi_backend(options: &SendOptions) -> LinuxWifiBackend {
+    LinuxWifiBackend::with_config(P2pConfig {
+        interface: options.wifi_interface.clone(),
+        hotspot_interface: options.hotspot_interface.clone(),
+        use_5ghz: options.use_5ghz,
+        ..Default::default()
+    })
 }
 
 impl Sender {
     pub fn new(options: SendOptions) -> anyhow::Result<Self> {
-        let wifi_sender = WiFiP2pSender::with_config(P2pConfig {
-            interface: options.wifi_interface.clone(),
-            use_5ghz: options.use_5ghz,
-            ..Default::default()
-        });
+        let wifi = Arc::new(default_wifi_backend(&options));
 
         let security = Arc::new(BleSecurityPersistent::new()?);
 
         Ok(Self {
             options,
-            wifi_sender,
+            wifi,
+            ble_backend: None,
+            transport: Arc::new(HttpTransport),
             security,
+            session_port: Mutex::new(None),
+            state: StateMachine::new(),
+            cancel: CancellationToken::new(),
+            quirks: QuirksTable::load(),
+        })
+    }
+
+    /// 替换 WiFi 后端
+    pub fn with_wifi_backend(mut self, wifi: Arc<dyn WifiBackend>) -> Self {
+        self.wifi = wifi;
+        self
+    }
+
+    /// 使用热备的热点后端和传输端口（替换 WiFi 后端和传输层）
+    pub fn with_standby(self, standby: &Standby) -> Self {
+        self.with_wifi_backend(standby.wifi())
+            .with_transport(standby.transport())
+    }
+
+    /// 替换 BLE 客户端后端
+    pub fn with_ble_backend(mut self, backend: Arc<dyn GattClientBackend>) -> Self {
+        self.ble_backend = Some(backend);
+        self
+    }
+
+    /// 替换传输层（默认 HTTP(S) + WebSocket）
+    pub fn with_transport(mut self, transport: Arc<dyn TransferTransport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// 使用指定的密钥对（默认每次创建时随机生成）
+    pub fn with_security(mut self, security: Arc<BleSecurityPersistent>) -> Self {
+        self.security = security;
+        self
+    }
+
+    /// `token` 取消时中断正在进行的发现、发送或会话建立
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// 使用指定的兼容性表（默认为内置规则加上覆盖文件）
+    pub fn with_quirks(mut self, quirks: QuirksTable) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// 订阅发送状态（最近一次发送或会话的阶段，失败时停在失败的阶段）
+    pub fn state(&self) -> watch::Receiver<WorkflowState<SendPhase>> {
+        self.state.subscribe()
+    }
+
+    /// 按 `SendOptions::discovery` 指定的方式发现接收端
+    pub async fn discover(
+        &self,
+        timeout: Duration,
+        callback: Option<Arc<dyn ScanCallback>>,
+    ) -> anyhow::Result<Vec<DiscoveredDevice>> {
+        self.begin(SendPhase::Scanning);
+        let result =
+            discover_devices(self.options.discovery, timeout, callback, &self.cancel).await;
+        if result.is_err() {
+            self.state.fail();
+        }
+        result
+    }
+
+    /// 开始新一轮流程
+    fn begin(&self, phase: SendPhase) {
+        if let Err(e) = self.state.start(phase) {
+            warn!("{}", e);
+        }
+    }
+
+    /// 在取消令牌的约束下运行一次发送流程；取消时关闭热点或离开接收端的网络
+    async fn cancellable<T>(
+        &self,
+        flow: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<T> {
+        let interrupted = match cancel::run(&self.cancel, None, flow).await {
+            Ok(result) => return result,
+            Err(interrupted) => interrupted,
+        };
+        let cleanup = match self.options.transfer_mode {
+            TransferMode::Hotspot => self.stop_hotspot().await,
+            TransferMode::JoinReceiver => self.wifi.disconnect().await,
+            TransferMode::LanDirect => Ok(()),
+        };
+        if let Err(e) = cleanup {
+            warn!("Cleanup after cancellation failed: {}", e);
+        }
+        Err(interrupted.into())
+    }
+
+    /// 带安全上下文和取消令牌的 BLE 客户端
+    ///
+    /// 默认后端会注册配对代理，配对提示通过 [`BleClient::take_pairing_prompts`] 取出。
+    async fn ble_client(&self) -> anyhow::Result<BleClient> {
+        let client = match &self.ble_backend {
+            Some(backend) => BleClient::with_backend(Box::new(backend.clone())),
+            None => BleClient::with_pairing().await?,
+        };
+        Ok(client
+            .with_security(self.security.clone())
+            .with_cancellation(self.cancel.clone()))
+    }
+
+    /// 按兼容性表调整握手超时的 BLE 客户端
+    async fn ble_client_for(&self, quirks: &Quirks) -> anyhow::Result<BleClient> {
+        let client = self.ble_client().await?;
+        Ok(match quirks.handshake_timeout() {
+            Some(timeout) => client.with_timeout(timeout),
+            None => client,
         })
     }
 
+    /// 接收端品牌对应的兼容性调整（发送端在握手前不知道对端的 catShare 版本）
+    fn quirks_for(&self, brand: Option<BrandId>) -> Quirks {
+        let quirks = self.quirks.lookup(brand, None);
+        if quirks != Quirks::default() {
+            debug!("Applying quirks for {:?}: {:?}", brand, quirks);
+        }
+        quirks
+    }
+
+    /// 传输服务的固定证书（见 [`crate::crypto::tls`]），无法读取或生成时退回明文 HTTP
+    async fn tls_identity(&self) -> Option<Arc<TlsIdentity>> {
+        let loaded = tokio::task::spawn_blocking(TlsIdentity::load_or_create).await;
+        match loaded.map_err(anyhow::Error::from).and_then(|r| r) {
+            Ok(identity) => Some(Arc::new(identity)),
+            Err(e) => {
+                warn!("TLS certificate unavailable, serving plain HTTP: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 进入新的阶段并通知回调
+    fn enter<C: SendProgressCallback>(&self, callback: &C, phase: SendPhase) {
+        if let Err(e) = self.state.advance(phase) {
+            warn!("{}", e);
+        }
+        callback.on_phase(phase);
+    }
+
     /// 发送文件到指定设备
+    ///
+    /// 会话 ID 同时用作传输任务的 taskId，接收端日志中可以看到同一个 ID。
+    #[tracing::instrument(
+        skip_all,
+        fields(session_id = tracing::field::Empty, device = %device.name, address = %device.address)
+    )]
     pub async fn send_to_device<C: SendProgressCallback>(
         &self,
         device: &DiscoveredDevice,
         files: Vec<PathBuf>,
         callback: &C,
     ) -> anyhow::Result<()> {
-        callback.on_status("准备发送...");
+        self.send(Handoff::Device(device), Content::Files(files), callback)
+            .await
+    }
 
+    /// 把 `job` 的目录同步到指定设备，成功后保存新的同步记录
+    ///
+    /// 只支持 cattysend 接收端（需要协商出 `raw_files`），不支持反向模式。
+    #[tracing::instrument(
+        skip_all,
+        fields(session_id = tracing::field::Empty, device = %device.name, address = %device.address)
+    )]
+    pub async fn sync_to_device<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        job: &SyncJob,
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        let handoff = Handoff::Device(device);
+        if !handoff.peer_support().cattysend {
+            anyhow::bail!("接收端不是 cattysend，无法同步文件夹");
+        }
+        if self.options.transfer_mode == TransferMode::JoinReceiver {
+            anyhow::bail!("文件夹同步不支持反向模式");
+        }
+        self.send(handoff, Content::Sync(job), callback).await?;
+        // 记录保存失败只影响下次的 dry-run 和摘要缓存
+        if let Err(e) = job.save_state() {
+            warn!("Failed to save sync state: {}", e);
+        }
+        Ok(())
+    }
+
+    /// 不经过 BLE，通过引导载荷把 P2P 信息交给接收端
+    ///
+    /// 热点（或局域网服务）就绪后通过 [`SendProgressCallback::on_bootstrap_payload`]
+    /// 给出载荷，之后与 [`Self::send_to_device`] 一样等待接收端下载。
+    /// `peer_key` 为接收端公钥，给出时 P2P 信息会加密且双方都能看到验证码。
+    /// 不支持反向模式。
+    #[tracing::instrument(skip_all, fields(session_id = tracing::field::Empty))]
+    pub async fn send_via_payload<C: SendProgressCallback>(
+        &self,
+        peer_key: Option<&str>,
+        files: Vec<PathBuf>,
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        if self.options.transfer_mode == TransferMode::JoinReceiver {
+            anyhow::bail!("反向模式需要通过 BLE 连接接收端");
+        }
+        // 在创建热点之前发现公钥错误
+        if let Some(key) = peer_key {
+            self.security
+                .derive_session_key(key)
+                .map_err(|e| anyhow::anyhow!("接收端公钥无效: {}", e))?;
+        }
+        self.send(
+            Handoff::Payload { peer_key },
+            Content::Files(files),
+            callback,
+        )
+        .await
+    }
+
+    async fn send<C: SendProgressCallback>(
+        &self,
+        handoff: Handoff<'_>,
+        content: Content<'_>,
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        let session_id = start_session();
+        callback.on_started(&session_id);
+        self.begin(SendPhase::Connecting);
+        callback.on_phase(SendPhase::Connecting);
+        callback.on_status(&crate::tr!("workflow.send.preparing"));
+
+        let result = self
+            .cancellable(async {
+                if self.options.transfer_mode == TransferMode::Hotspot {
+                    PowerState::read()
+                        .await
+                        .check_hotspot(self.options.min_battery_percent)?;
+                }
+                match (self.options.transfer_mode, handoff, &content) {
+                    (
+                        TransferMode::JoinReceiver,
+                        Handoff::Device(device),
+                        Content::Files(files),
+                    ) => self.join_and_upload(device, files, callback).await,
+                    _ => {
+                        self.serve_and_wait(handoff, &content, session_id, callback)
+                            .await
+                    }
+                }
+            })
+            .await;
+        if result.is_err() {
+            self.state.fail();
+        }
+        result
+    }
+
+    /// 热点/局域网模式：启动传输服务，建立链路后等待接收端下载完成
+    async fn serve_and_wait<C: SendProgressCallback>(
+        &self,
+        handoff: Handoff<'_>,
+        content: &Content<'_>,
+        session_id: String,
+        callback: &C,
+    ) -> anyhow::Result<()> {
         // 准备文件信息
-        let mut file_entries = Vec::new();
-        let mut _total_size: u64 = 0;
-
-        for path in &files {
-            let metadata = tokio::fs::metadata(path).await?;
-            let name = path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            let size = metadata.len();
-            _total_size += size;
-
-            // 猜测 MIME 类型
-            let mime_type = mime_guess::from_path(path)
-                .first()
-                .map(|m| m.to_string())
-                .unwrap_or_else(|| "application/octet-stream".to_string());
-
-            file_entries.push(FileEntry {
-                path: path.clone(),
-                name,
-                size,
-                mime_type,
-            });
-        }
-
-        callback.on_status("创建 WiFi 热点...");
+        let (file_entries, sync) = content.entries().await?;
+        let total_size: u64 = file_entries.iter().map(|f| f.size).sum();
+
+        let mut tracker = StatsTracker::new(file_entries.iter().map(|f| (f.name.clone(), f.size)));
 
         // 创建传输任务
-        let task_id = uuid::Uuid::new_v4().to_string();
-        let sender_id = format!("{:04x}", rand::random::<u16>());
+        let task_id = session_id;
+        let sender_id = identity::sender_id(self.security.get_public_key());
 
+        let verification_code = Arc::new(OnceLock::new());
+        // CatShare 接收端不会带上访问令牌，也不认识证书指纹，只对 cattysend 接收端启用
+        let cattysend = handoff.peer_support().cattysend;
+        let auth_token = cattysend.then(|| uuid::Uuid::new_v4().simple().to_string());
+        let tls = if cattysend {
+            self.tls_identity().await
+        } else {
+            None
+        };
+        let cert_sha256 = tls.as_ref().map(|identity| identity.fingerprint());
         let task = TransferTask {
             task_id: task_id.clone(),
             files: file_entries,
             sender_id: sender_id.clone(),
             sender_name: self.options.sender_name.clone(),
+            verification_code: verification_code.clone(),
+            auth_token: auth_token.clone(),
+            tls,
+            sync,
         };
 
         // 启动传输服务器
-        let mut server = TransferServer::new(task);
-        let port = server.start().await?;
-
-        callback.on_status(&format!("服务器启动于端口 {}", port));
+        let served = self.transport.serve(task, self.options.ports).await?;
+        let port = served.port;
+        // 先订阅，避免错过链路建立期间的状态
+        let mut status_rx = served.status;
 
-        // 创建 WiFi P2P 热点
-        let p2p_info = self.wifi_sender.create_group(port as i32).await?;
+        callback.on_listening(port);
 
-        callback.on_status(&format!("热点已创建: {}", p2p_info.ssid));
-
-        // 连接到接收端 BLE 设备
-        callback.on_status("连接到接收端...");
-
-        let ble_client = BleClient::new().await?.with_security(self.security.clone());
-        let _device_info = ble_client
-            .connect_and_handshake(&device.address, &p2p_info, &sender_id)
+        let (port_access, code) = self
+            .establish_link(handoff, port, &sender_id, auth_token, cert_sha256, callback)
             .await?;
+        if let Some(code) = code {
+            let _ = verification_code.set(code);
+        }
 
-        callback.on_status("等待接收端连接...");
+        self.enter(callback, SendPhase::WaitingForReceiverWifi);
+        callback.on_status(&crate::tr!("workflow.send.waiting_receiver"));
 
-        // 订阅传输状态
-        let mut status_rx = server.subscribe_status_async().await;
+        // 监听接收端接入热点（局域网直连模式下没有热点）
+        let mut station_rx = match self.options.transfer_mode {
+            TransferMode::Hotspot => self.wifi.watch_stations(),
+            TransferMode::LanDirect | TransferMode::JoinReceiver => None,
+        };
 
         // 等待传输完成或超时
-        let timeout = std::time::Duration::from_secs(300); // 5 分钟超时
+        let timeout = Duration::from_secs(300); // 5 分钟超时
+        let mut transfer_started: Option<Instant> = None;
+        // 接收端是否连上过传输服务器
+        let mut reached = false;
+        // 接收端是否接入过热点
+        let mut joined = false;
+        // 热点看门狗：一直没有接收端接入时提前关闭热点
+        let idle_timeout = match self.options.transfer_mode {
+            TransferMode::Hotspot => self.options.hotspot_idle_timeout,
+            TransferMode::LanDirect | TransferMode::JoinReceiver => None,
+        };
+        let idle = tokio::time::sleep(idle_timeout.unwrap_or(timeout));
+        tokio::pin!(idle);
         let result = tokio::time::timeout(timeout, async {
             loop {
-                match status_rx.recv().await {
+                let status = tokio::select! {
+                    status = status_rx.recv() => status,
+                    Some(station) = async { station_rx.as_mut()?.recv().await }, if station_rx.is_some() => {
+                        callback.on_receiver_joined(&station.mac, &station.ip);
+                        joined = true;
+                        // 只关心第一个接入的客户端
+                        station_rx = None;
+                        continue;
+                    }
+                    () = &mut idle, if idle_timeout.is_some() && !joined && !reached => {
+                        crate::metrics::failure("hotspot_idle");
+                        return Err(anyhow::anyhow!(
+                            "{} 秒内没有接收端接入热点，已关闭热点",
+                            idle_timeout.unwrap_or_default().as_secs()
+                        ));
+                    }
+                };
+                if let Ok(s) = &status
+                    && !matches!(s, crate::transfer::TransferStatus::Pending)
+                {
+                    reached = true;
+                }
+                match status {
                     Ok(crate::transfer::TransferStatus::Completed) => {
-                        callback.on_status("传输完成！");
+                        if let Some(started) = transfer_started {
+                            crate::metrics::transfer_finished("send", total_size, started.elapsed());
+                        }
+                        callback.on_status(&crate::tr!("workflow.send.complete"));
                         return Ok(());
                     }
                     Ok(crate::transfer::TransferStatus::Rejected(reason)) => {
                         return Err(anyhow::anyhow!("接收端拒绝: {}", reason));
                     }
                     Ok(crate::transfer::TransferStatus::Transferring { progress }) => {
-                        let percent = (progress * 100.0) as u64;
-                        callback.on_progress(percent, 100);
+                        if transfer_started.is_none() {
+                            transfer_started = Some(Instant::now());
+                            self.enter(callback, SendPhase::Transferring);
+                        }
+                        let sent = (progress * total_size as f64) as u64;
+                        callback.on_progress(sent, total_size);
+                        if let Some(stats) = tracker.update(sent) {
+                            callback.on_stats(&stats);
+                        }
+                    }
+                    Ok(crate::transfer::TransferStatus::Paused) => {
+                        callback.on_status(&crate::tr!("workflow.send.paused"));
+                        callback.on_paused(true);
+                    }
+                    Ok(crate::transfer::TransferStatus::Resumed) => {
+                        callback.on_status(&crate::tr!("workflow.send.resumed"));
+                        callback.on_paused(false);
                     }
                     Ok(crate::transfer::TransferStatus::Failed(e)) => {
+                        crate::metrics::failure("transfer");
                         return Err(anyhow::anyhow!("传输失败: {}", e));
                     }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        // 进度事件积压，丢掉旧的即可
+                    }
                     Err(e) => {
                         // 通道关闭，可能是服务器停止
                         return Err(anyhow::anyhow!("状态通道错误: {}", e));
@@ -174,16 +836,714 @@ impl Sender {
         })
         .await;
 
-        // 清理
-        self.wifi_sender.stop_group().await?;
+        // 清理（传输失败时不保留热点）
+        let blocked = port_access.blocked().cloned();
+        port_access.close().await;
+        let keep = self.options.keep_hotspot && matches!(result, Ok(Ok(())));
+        if self.options.transfer_mode == TransferMode::Hotspot && !keep {
+            self.wifi.stop_hotspot().await?;
+        }
 
         match result {
             Ok(Ok(())) => {
+                self.enter(callback, SendPhase::Done);
                 callback.on_complete();
                 Ok(())
             }
             Ok(Err(e)) => Err(e),
-            Err(_) => Err(anyhow::anyhow!("传输超时")),
+            Err(_) => {
+                crate::metrics::failure("transfer");
+                // 接收端始终没连上时，防火墙拦截是最可能的原因
+                match blocked {
+                    Some(e) if !reached => Err(e.into()),
+                    _ => Err(anyhow::anyhow!("传输超时")),
+                }
+            }
+        }
+    }
+
+    /// 与接收端建立双向传输会话
+    ///
+    /// 链路建立方式与 [`Self::send_to_device`] 相同，但 WebSocket 连接保持打开，
+    /// 之后两端都可以通过 [`Session::send`] 发起传输。
+    /// 热点模式下会话结束后需调用 [`Self::stop_hotspot`]。
+    #[tracing::instrument(
+        skip_all,
+        fields(session_id = tracing::field::Empty, device = %device.name, address = %device.address)
+    )]
+    pub async fn open_session<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        callback: &C,
+    ) -> anyhow::Result<Session> {
+        callback.on_started(&start_session());
+        self.begin(SendPhase::Connecting);
+        let result = self
+            .cancellable(self.open_session_inner(device, callback))
+            .await;
+        if result.is_err() {
+            self.state.fail();
+        }
+        result
+    }
+
+    async fn open_session_inner<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        callback: &C,
+    ) -> anyhow::Result<Session> {
+        let listener = SessionListener::bind(self.options.ports).await?;
+        callback.on_listening(listener.port());
+        let sender_id = identity::sender_id(self.security.get_public_key());
+        let (port_access, _) = self
+            .establish_link(
+                Handoff::Device(device),
+                listener.port(),
+                &sender_id,
+                None,
+                None,
+                callback,
+            )
+            .await?;
+
+        self.enter(callback, SendPhase::WaitingForReceiverWifi);
+        callback.on_status(&crate::tr!("workflow.send.waiting_receiver"));
+        let accepted = tokio::time::timeout(
+            Duration::from_secs(60),
+            listener.accept(&self.options.sender_name),
+        )
+        .await;
+        match accepted {
+            Ok(Ok(session)) => {
+                callback.on_status(&crate::tr!("workflow.send.session_established"));
+                // 会话期间对端还会连接下载服务，端口保持放行直到关闭热点
+                *self.session_port.lock().unwrap() = Some(port_access);
+                Ok(session)
+            }
+            Ok(Err(e)) => {
+                port_access.close().await;
+                self.stop_hotspot().await?;
+                Err(e)
+            }
+            Err(_) => {
+                let blocked = port_access.blocked().cloned();
+                port_access.close().await;
+                self.stop_hotspot().await?;
+                match blocked {
+                    Some(e) => Err(e.into()),
+                    None => Err(anyhow::anyhow!("等待接收端连接超时")),
+                }
+            }
+        }
+    }
+
+    /// 创建热点前检查热点接口上的现有连接，按 [`SendOptions::busy_interface`] 处理
+    async fn check_busy_interface<C: SendProgressCallback>(
+        &self,
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        let Some(busy) = self.wifi.busy_interface().await else {
+            return Ok(());
+        };
+        warn!("{}", busy);
+        callback.on_interface_busy(&busy);
+        match (self.options.busy_interface, &busy.alternative) {
+            (BusyInterfacePolicy::Abort, _) => Err(busy.into()),
+            (BusyInterfacePolicy::SecondAdapter, Some(alternative))
+                if self.wifi.use_hotspot_interface(alternative).is_ok() =>
+            {
+                callback.on_status(&crate::tr!(
+                    "workflow.send.second_adapter",
+                    interface = busy.interface,
+                    network = busy.network(),
+                    alternative = alternative
+                ));
+                Ok(())
+            }
+            _ => {
+                if let Err(e) = self.wifi.restore_after_hotspot(&busy).await {
+                    warn!("Previous connection will not be restored: {}", e);
+                }
+                callback.on_status(&crate::tr!(
+                    "workflow.send.interface_busy",
+                    interface = busy.interface,
+                    network = busy.network()
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// 建立 P2P 链路：创建热点（或使用局域网地址），再把 `port` 上的服务通过
+    /// BLE / 局域网握手（或引导载荷）告诉接收端
+    ///
+    /// 热点模式下 BLE 握手的连接和读取 DeviceInfo 不依赖 P2P 信息，与创建热点并行，
+    /// 热点建好后只剩写入 P2P 信息一步（见 [`Self::start_hotspot_and_connect`]）。
+    ///
+    /// `auth_token` 为传输服务要求的访问令牌，`cert_sha256` 为传输服务证书的指纹，
+    /// 都随 P2P 信息一起加密发送。
+    ///
+    /// 返回传输端口在防火墙中的放行情况（调用方在传输结束后关闭）和验证码
+    #[tracing::instrument(skip_all, fields(mode = ?self.options.transfer_mode, port = port))]
+    async fn establish_link<C: SendProgressCallback>(
+        &self,
+        handoff: Handoff<'_>,
+        port: u16,
+        sender_id: &str,
+        auth_token: Option<String>,
+        cert_sha256: Option<String>,
+        callback: &C,
+    ) -> anyhow::Result<(PortAccess, Option<String>)> {
+        let mut port_access = PortAccess::Allowed;
+        let (mut p2p_info, connected) = match self.options.transfer_mode {
+            TransferMode::Hotspot => {
+                let (p2p_info, connected) = match handoff {
+                    // mDNS 发现的设备走局域网握手，只需一次请求，不必提前连接
+                    Handoff::Device(device) if device.lan_endpoint.is_none() => {
+                        let (p2p_info, connected) = self
+                            .start_hotspot_and_connect(handoff, device, port, callback)
+                            .await?;
+                        (p2p_info, Some(connected))
+                    }
+                    _ => (self.start_hotspot(handoff, port, callback).await?, None),
+                };
+                port_access = self.wifi.allow_port(port).await;
+                match &port_access {
+                    PortAccess::Opened(guard) => callback.on_status(&crate::tr!(
+                        "workflow.send.port_opened",
+                        zone = guard.zone(),
+                        port = port
+                    )),
+                    PortAccess::Blocked(e) => callback.on_status(&crate::tr!(
+                        "workflow.send.port_blocked",
+                        port = port,
+                        error = e
+                    )),
+                    PortAccess::Allowed => {}
+                }
+                (p2p_info, connected)
+            }
+            TransferMode::JoinReceiver => {
+                anyhow::bail!("反向模式不支持双向会话")
+            }
+            TransferMode::LanDirect => {
+                // CatShare 只会按 P2P 信息去连接热点，不认识局域网直连的 P2P 信息
+                if !handoff.peer_support().cattysend {
+                    anyhow::bail!("接收端不是 cattysend，不支持局域网直连，请改用热点模式");
+                }
+                // 使用通往接收端的本机地址（接收端来自 BLE 或引导载荷时取默认路由地址）
+                let lan_endpoint = match handoff {
+                    Handoff::Device(device) => device.lan_endpoint,
+                    Handoff::Payload { .. } => None,
+                };
+                let local_ip = local_ip_towards(lan_endpoint.map(|e| e.ip()))?;
+                callback.on_status(&crate::tr!(
+                    "workflow.send.lan_direct",
+                    ip = local_ip,
+                    port = port
+                ));
+                let p2p_info =
+                    P2pInfo::lan_direct(local_ip.to_string(), self.get_mac_address(), port as i32);
+                (p2p_info, None)
+            }
+        };
+        p2p_info.auth_token = auth_token;
+        p2p_info.cert_sha256 = cert_sha256;
+        // 接收端只会连接 P2P 信息里的端口，后端不能擅自改动
+        if p2p_info.port != port as i32 {
+            if let Some(connected) = connected {
+                connected.abort().await;
+            }
+            port_access.close().await;
+            if self.options.transfer_mode == TransferMode::Hotspot {
+                let _ = self.wifi.stop_hotspot().await;
+            }
+            anyhow::bail!(
+                "P2P 信息中的端口 {} 与监听端口 {} 不一致",
+                p2p_info.port,
+                port
+            );
+        }
+
+        let peer_key = match (handoff, connected) {
+            (Handoff::Device(device), Some(connected)) => {
+                self.finish_handshake(device, connected, &p2p_info, sender_id, callback)
+                    .await
+            }
+            (Handoff::Device(device), None) => {
+                self.handshake(device, &p2p_info, sender_id, callback).await
+            }
+            (Handoff::Payload { peer_key }, _) => {
+                self.enter(callback, SendPhase::WritingP2p);
+                bootstrap::encode(&p2p_info, sender_id, peer_key, &self.security).map(|payload| {
+                    callback.on_bootstrap_payload(&payload);
+                    callback.on_status(&crate::tr!("workflow.send.scan_qr"));
+                    peer_key.map(str::to_string)
+                })
+            }
+        };
+        let peer_key = match peer_key {
+            Ok(peer_key) => peer_key,
+            Err(e) => {
+                port_access.close().await;
+                if self.options.transfer_mode == TransferMode::Hotspot {
+                    // 握手失败时热点已无用，立即关闭
+                    let _ = self.wifi.stop_hotspot().await;
+                }
+                return Err(e);
+            }
+        };
+
+        // 与接收端加密 P2P 信息时用的是同一个共享密钥
+        let code = peer_key
+            .as_deref()
+            .and_then(|key| self.security.derive_session_key(key).ok())
+            .map(|cipher| cipher.verification_code());
+        if let Some(code) = &code {
+            callback.on_verification_code(code);
+        }
+        Ok((port_access, code))
+    }
+
+    /// 创建 WiFi P2P 热点，失败时恢复被断开的连接和热点接口
+    async fn start_hotspot<C: SendProgressCallback>(
+        &self,
+        handoff: Handoff<'_>,
+        port: u16,
+        callback: &C,
+    ) -> anyhow::Result<P2pInfo> {
+        self.check_busy_interface(callback).await?;
+        callback.on_status(&crate::tr!("workflow.creating_hotspot"));
+        let started = Instant::now();
+        let quirks = self.quirks_for(handoff.brand());
+        let mut credentials = self.options.credentials.for_peer(handoff.peer_support());
+        credentials.only_2ghz = !quirks.use_5ghz(true);
+        let p2p_info = match quirks
+            .retry(self.options.retry)
+            .run(
+                "hotspot",
+                || self.wifi.create_hotspot(port as i32, &credentials),
+                |r| callback.on_retry(r),
+            )
+            .await
+        {
+            Ok(p2p_info) => p2p_info,
+            Err(e) => {
+                let _ = self.wifi.stop_hotspot().await;
+                return Err(e);
+            }
+        };
+        crate::metrics::hotspot_up(started.elapsed());
+        callback.on_status(&crate::tr!(
+            "workflow.send.hotspot_created",
+            ssid = p2p_info.ssid
+        ));
+        Ok(p2p_info)
+    }
+
+    /// 同时创建热点和连接接收端（读取 DeviceInfo），两者都成功才返回
+    ///
+    /// 一方失败时清理另一方：握手失败关闭热点，热点失败断开 BLE 连接。两者都失败时
+    /// 返回热点的错误（没有热点时握手没有意义，热点的错误也更能说明问题），
+    /// 握手的错误只记录日志并通过状态告诉用户。
+    async fn start_hotspot_and_connect<C: SendProgressCallback>(
+        &self,
+        handoff: Handoff<'_>,
+        device: &DiscoveredDevice,
+        port: u16,
+        callback: &C,
+    ) -> anyhow::Result<(P2pInfo, ConnectedReceiver)> {
+        let (hotspot, connected) = tokio::join!(
+            self.start_hotspot(handoff, port, callback),
+            self.connect_receiver(device, callback)
+        );
+        match (hotspot, connected) {
+            (Ok(p2p_info), Ok(connected)) => Ok((p2p_info, connected)),
+            (Ok(_), Err(e)) => {
+                let _ = self.wifi.stop_hotspot().await;
+                Err(e)
+            }
+            (Err(e), Ok(connected)) => {
+                connected.abort().await;
+                Err(e)
+            }
+            (Err(hotspot), Err(handshake)) => {
+                warn!(
+                    "BLE handshake failed alongside the hotspot: {:#}",
+                    handshake
+                );
+                callback.on_status(&crate::tr!(
+                    "workflow.send.handshake_failed_too",
+                    error = handshake
+                ));
+                Err(hotspot)
+            }
+        }
+    }
+
+    /// 握手的前半段：连接接收端并读取 DeviceInfo，连接保持打开
+    async fn connect_receiver<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        callback: &C,
+    ) -> anyhow::Result<ConnectedReceiver> {
+        let quirks = self.quirks_for(Some(device.brand_id()));
+        let on_step = |step: HandshakeStep| self.enter(callback, step.into());
+        let started = Instant::now();
+        let mut client = self.ble_client_for(&quirks).await?;
+        let mut prompts = client.take_pairing_prompts();
+        let begin = || async {
+            self.enter(callback, SendPhase::Connecting);
+            callback.on_status(&crate::tr!("workflow.send.connecting"));
+            Ok(client.begin_handshake(&device.address, on_step).await?)
+        };
+        let pending = pairing::answer_while(
+            prompts.as_mut(),
+            |prompt| callback.on_pairing(prompt),
+            quirks
+                .retry(self.options.retry)
+                .run("handshake", begin, |r| callback.on_retry(r)),
+        )
+        .await?;
+        Ok(ConnectedReceiver {
+            client,
+            pending,
+            prompts,
+            started,
+        })
+    }
+
+    /// 握手的后半段：写入 P2P 信息，返回接收端公钥
+    ///
+    /// 连接在等待热点期间断开等原因导致写入失败时，退回完整的握手（重新连接）。
+    async fn finish_handshake<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        connected: ConnectedReceiver,
+        p2p_info: &P2pInfo,
+        sender_id: &str,
+        callback: &C,
+    ) -> anyhow::Result<Option<String>> {
+        let ConnectedReceiver {
+            client,
+            pending,
+            mut prompts,
+            started,
+        } = connected;
+        let on_step = |step: HandshakeStep| self.enter(callback, step.into());
+        let written = pairing::answer_while(
+            prompts.as_mut(),
+            |prompt| callback.on_pairing(prompt),
+            client.finish_handshake(pending, p2p_info, sender_id, on_step),
+        )
+        .await;
+        match written {
+            Ok(device_info) => {
+                crate::metrics::handshake("ble", started.elapsed());
+                Ok(device_info.key)
+            }
+            Err(e @ BleClientError::Interrupted(Interrupted::Cancelled)) => Err(e.into()),
+            Err(e) => {
+                warn!("Writing P2P info failed, reconnecting: {}", e);
+                self.handshake(device, p2p_info, sender_id, callback).await
+            }
+        }
+    }
+
+    /// 连接到接收端并发送 P2P 信息（mDNS 发现的设备走局域网握手），返回接收端公钥
+    async fn handshake<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        p2p_info: &P2pInfo,
+        sender_id: &str,
+        callback: &C,
+    ) -> anyhow::Result<Option<String>> {
+        let quirks = self.quirks_for(Some(device.brand_id()));
+        let on_step = |step: HandshakeStep| self.enter(callback, step.into());
+        let handshake = || async {
+            self.enter(callback, SendPhase::Connecting);
+            let started = Instant::now();
+            if let Some(endpoint) = device.lan_endpoint {
+                callback.on_status(&crate::tr!("workflow.send.connecting_lan"));
+                let device_info =
+                    lan_handshake(endpoint, p2p_info, sender_id, Some(&self.security), on_step)
+                        .await?;
+                crate::metrics::handshake("lan", started.elapsed());
+                Ok(device_info)
+            } else {
+                callback.on_status(&crate::tr!("workflow.send.connecting"));
+                let mut ble_client = self.ble_client_for(&quirks).await?;
+                let device_info = pairing::answer_while(
+                    ble_client.take_pairing_prompts().as_mut(),
+                    |prompt| callback.on_pairing(prompt),
+                    ble_client.connect_and_handshake_with_progress(
+                        &device.address,
+                        p2p_info,
+                        sender_id,
+                        on_step,
+                    ),
+                )
+                .await?;
+                crate::metrics::handshake("ble", started.elapsed());
+                Ok(device_info)
+            }
+        };
+        let device_info = quirks
+            .retry(self.options.retry)
+            .run("handshake", handshake, |r| callback.on_retry(r))
+            .await?;
+        Ok(device_info.key)
+    }
+
+    /// 把文件推送到接收端的反向上传服务（`PUT /upload`）
+    ///
+    /// `host` 为接收端 IP（见 [`SendProgressCallback::on_receiver_joined`]），
+    /// `port` 为接收端的 `ReceiveOptions::upload_port`。进度按文件粒度上报。
+    pub async fn push_files<C: SendProgressCallback>(
+        &self,
+        host: &str,
+        port: u16,
+        files: &[PathBuf],
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        cancel::run(
+            &self.cancel,
+            None,
+            self.upload_files(host, port, files, callback),
+        )
+        .await??;
+        callback.on_complete();
+        Ok(())
+    }
+
+    async fn upload_files<C: SendProgressCallback>(
+        &self,
+        host: &str,
+        port: u16,
+        files: &[PathBuf],
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        let mut total_size = 0;
+        for path in files {
+            total_size += tokio::fs::metadata(path).await?.len();
+        }
+
+        let mut sent = 0;
+        for path in files {
+            callback.on_status(&crate::tr!(
+                "workflow.send.uploading",
+                path = path.display()
+            ));
+            let response = upload_file(host, port, path).await?;
+            sent += response.size;
+            callback.on_progress(sent, total_size);
+        }
+        Ok(())
+    }
+
+    /// 反向模式：请求接收端建组，加入后上传文件
+    #[tracing::instrument(skip_all, fields(mode = "join_receiver"))]
+    async fn join_and_upload<C: SendProgressCallback>(
+        &self,
+        device: &DiscoveredDevice,
+        files: &[PathBuf],
+        callback: &C,
+    ) -> anyhow::Result<()> {
+        if device.lan_endpoint.is_some() {
+            anyhow::bail!("反向模式需要通过 BLE 发现的接收端");
+        }
+        let sender_id = identity::sender_id(self.security.get_public_key());
+        let mac = self.get_mac_address();
+        let quirks = self.quirks_for(Some(device.brand_id()));
+
+        let on_step = |step: HandshakeStep| self.enter(callback, step.into());
+        let handshake = || async {
+            self.enter(callback, SendPhase::Connecting);
+            callback.on_status(&crate::tr!("workflow.send.requesting_group"));
+            let started = Instant::now();
+            let mut ble_client = self.ble_client_for(&quirks).await?;
+            let joined = pairing::answer_while(
+                ble_client.take_pairing_prompts().as_mut(),
+                |prompt| callback.on_pairing(prompt),
+                ble_client.connect_and_join_with_progress(
+                    &device.address,
+                    &sender_id,
+                    &mac,
+                    GROUP_INFO_TIMEOUT,
+                    on_step,
+                ),
+            )
+            .await?;
+            crate::metrics::handshake("ble", started.elapsed());
+            Ok(joined)
+        };
+        let (device_info, group) = quirks
+            .retry(self.options.retry)
+            .run("handshake", handshake, |r| callback.on_retry(r))
+            .await?;
+
+        if let Some(cipher) = device_info
+            .key
+            .as_deref()
+            .and_then(|key| self.security.derive_session_key(key).ok())
+        {
+            callback.on_verification_code(&cipher.verification_code());
+        }
+
+        let port = u16::try_from(group.port)
+            .ok()
+            .filter(|&p| p != 0)
+            .ok_or_else(|| anyhow::anyhow!("接收端给出的端口无效: {}", group.port))?;
+
+        self.enter(callback, SendPhase::WaitingForReceiverWifi);
+        callback.on_status(&crate::tr!(
+            "workflow.send.joining_group",
+            ssid = group.ssid
+        ));
+        let joined = cancel::run(
+            &self.cancel,
+            Some(CONNECT_TIMEOUT),
+            self.wifi.connect(&group),
+        )
+        .await;
+        let local_ip = match joined {
+            Ok(joined) => joined?,
+            Err(interrupted) => {
+                // 超时时 NetworkManager 可能仍在激活连接
+                let _ = self.wifi.disconnect().await;
+                return Err(interrupted.into());
+            }
+        };
+        let host = match &group.host {
+            Some(host) => host.clone(),
+            None => self.wifi.sender_ip(&group, &local_ip).await,
+        };
+
+        self.enter(callback, SendPhase::Transferring);
+        let started = Instant::now();
+        let result = self.upload_files(&host, port, files, callback).await;
+        if let Err(e) = self.wifi.disconnect().await {
+            warn!("Failed to leave receiver group: {}", e);
+        }
+        if let Err(e) = result {
+            crate::metrics::failure("transfer");
+            return Err(e);
+        }
+
+        let total_size: u64 = files
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+        crate::metrics::transfer_finished("send", total_size, started.elapsed());
+        callback.on_status(&crate::tr!("workflow.send.complete"));
+        self.enter(callback, SendPhase::Done);
+        callback.on_complete();
+        Ok(())
+    }
+
+    /// 关闭保留的热点（见 [`SendOptions::keep_hotspot`]）
+    pub async fn stop_hotspot(&self) -> anyhow::Result<()> {
+        let port_access = self.session_port.lock().unwrap().take();
+        if let Some(port_access) = port_access {
+            port_access.close().await;
+        }
+        if self.options.transfer_mode == TransferMode::Hotspot {
+            self.wifi.stop_hotspot().await?;
+        }
+        Ok(())
+    }
+
+    /// 获取 MAC 地址
+    fn get_mac_address(&self) -> String {
+        let path = format!("/sys/class/net/{}/address", self.options.wifi_interface);
+        // 没有硬件地址的接口（如隧道）读出空字符串，接收端会拒绝
+        std::fs::read_to_string(&path)
+            .map(|s| s.trim().to_uppercase())
+            .ok()
+            .filter(|mac| !mac.is_empty())
+            .unwrap_or_else(|| "02:00:00:00:00:00".to_string())
+    }
+}
+
+/// 要发送的内容
+enum Content<'a> {
+    Files(Vec<PathBuf>),
+    /// 文件夹同步
+    Sync(&'a SyncJob),
+}
+
+impl Content<'_> {
+    /// 传输任务中的文件，以及文件夹同步时的同步请求
+    async fn entries(&self) -> anyhow::Result<(Vec<FileEntry>, Option<SyncRequest>)> {
+        match self {
+            Content::Files(files) => {
+                let mut entries = Vec::with_capacity(files.len());
+                for path in files {
+                    entries.push(FileEntry::from_path(path).await?);
+                }
+                Ok((entries, None))
+            }
+            Content::Sync(job) => {
+                let mut entries = Vec::with_capacity(job.files().len());
+                for (name, synced) in job.files() {
+                    let mut entry = FileEntry::from_path(&job.root().join(name)).await?;
+                    entry.name = name.clone();
+                    // 扫描之后又被修改的文件不沿用摘要，发送时重新计算
+                    if entry.size == synced.size && entry.modified_time == synced.modified_time {
+                        entry.sha256 = Some(synced.sha256.clone());
+                    }
+                    entries.push(entry);
+                }
+                Ok((entries, Some(job.request())))
+            }
+        }
+    }
+}
+
+/// 与创建热点并行完成的握手前半段
+struct ConnectedReceiver {
+    client: BleClient,
+    pending: PendingHandshake,
+    /// 配对提示（写入 P2P 信息时也可能需要配对）
+    prompts: Option<mpsc::Receiver<PairingRequest>>,
+    started: Instant,
+}
+
+impl ConnectedReceiver {
+    /// 不再写入 P2P 信息，断开连接
+    async fn abort(self) {
+        self.client.abort_handshake(self.pending).await;
+    }
+}
+
+/// P2P 信息交给接收端的方式
+#[derive(Clone, Copy)]
+enum Handoff<'a> {
+    /// 与扫描到的设备握手
+    Device(&'a DiscoveredDevice),
+    /// 生成引导载荷，由用户交给接收端
+    Payload { peer_key: Option<&'a str> },
+}
+
+impl Handoff<'_> {
+    /// 接收端品牌（引导载荷不带品牌）
+    fn brand(&self) -> Option<BrandId> {
+        match self {
+            Handoff::Device(device) => Some(device.brand_id()),
+            Handoff::Payload { .. } => None,
+        }
+    }
+
+    /// 接收端对热点凭据的支持情况
+    fn peer_support(&self) -> PeerSupport {
+        match self {
+            Handoff::Device(device) => PeerSupport::of_device(device),
+            // 能解析引导载荷的只有 cattysend，但不知道它能否接入 WPA3 热点
+            Handoff::Payload { .. } => PeerSupport {
+                cattysend: true,
+                wpa3: false,
+            },
         }
     }
 }
@@ -195,8 +1555,42 @@ pub struct SimpleSendCallback {
 
 #[derive(Debug, Clone)]
 pub enum SendEvent {
+    /// 工作流开始（总是第一个事件）
+    Started {
+        session_id: String,
+    },
     Status(String),
-    Progress { sent: u64, total: u64 },
+    /// 进入新的阶段
+    Phase(SendPhase),
+    /// 传输服务监听的端口
+    Listening {
+        port: u16,
+    },
+    /// 与接收端核对的验证码
+    VerificationCode(String),
+    /// 引导载荷，应以二维码显示
+    BootstrapPayload(String),
+    /// 某个阶段失败，正在等待重试
+    Retrying(RetryAttempt),
+    /// 接收端已接入热点
+    ReceiverJoined {
+        mac: String,
+        ip: String,
+    },
+    /// 创建热点会断开热点接口上的现有连接
+    InterfaceBusy(InterfaceBusy),
+    /// 与接收端配对，应显示其中的数字或 PIN 码
+    Pairing(PairingPrompt),
+    Progress {
+        sent: u64,
+        total: u64,
+    },
+    /// 传输统计
+    Stats(TransferStats),
+    /// 接收端暂停了下载
+    Paused,
+    /// 接收端恢复了下载
+    Resumed,
     Complete,
     Error(String),
 }
@@ -209,14 +1603,74 @@ impl SimpleSendCallback {
 }
 
 impl SendProgressCallback for SimpleSendCallback {
+    fn on_started(&self, session_id: &str) {
+        let _ = self.tx.try_send(SendEvent::Started {
+            session_id: session_id.to_string(),
+        });
+    }
+
     fn on_status(&self, status: &str) {
         let _ = self.tx.try_send(SendEvent::Status(status.to_string()));
     }
 
+    fn on_phase(&self, phase: SendPhase) {
+        let _ = self.tx.try_send(SendEvent::Phase(phase));
+    }
+
+    fn on_listening(&self, port: u16) {
+        let _ = self.tx.try_send(SendEvent::Listening { port });
+    }
+
+    fn on_verification_code(&self, code: &str) {
+        let _ = self
+            .tx
+            .try_send(SendEvent::VerificationCode(code.to_string()));
+    }
+
+    fn on_bootstrap_payload(&self, payload: &str) {
+        let _ = self
+            .tx
+            .try_send(SendEvent::BootstrapPayload(payload.to_string()));
+    }
+
+    fn on_retry(&self, retry: &RetryAttempt) {
+        let _ = self.tx.try_send(SendEvent::Retrying(retry.clone()));
+    }
+
+    fn on_receiver_joined(&self, mac: &str, ip: &str) {
+        let _ = self.tx.try_send(SendEvent::ReceiverJoined {
+            mac: mac.to_string(),
+            ip: ip.to_string(),
+        });
+    }
+
+    fn on_interface_busy(&self, busy: &InterfaceBusy) {
+        let _ = self.tx.try_send(SendEvent::InterfaceBusy(busy.clone()));
+    }
+
+    /// 发送由用户主动发起，同意数字比较并显示数字，由接收端的用户核对
+    fn on_pairing(&self, prompt: &PairingPrompt) -> bool {
+        let _ = self.tx.try_send(SendEvent::Pairing(prompt.clone()));
+        true
+    }
+
     fn on_progress(&self, sent: u64, total: u64) {
         let _ = self.tx.try_send(SendEvent::Progress { sent, total });
     }
 
+    fn on_stats(&self, stats: &TransferStats) {
+        let _ = self.tx.try_send(SendEvent::Stats(stats.clone()));
+    }
+
+    fn on_paused(&self, paused: bool) {
+        let event = if paused {
+            SendEvent::Paused
+        } else {
+            SendEvent::Resumed
+        };
+        let _ = self.tx.try_send(event);
+    }
+
     fn on_complete(&self) {
         let _ = self.tx.try_send(SendEvent::Complete);
     }
@@ -225,3 +1679,151 @@ impl SendProgressCallback for SimpleSendCallback {
         let _ = self.tx.try_send(SendEvent::Error(error.to_string()));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+            multiplier: 2.0,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_phase_index_matches_order() {
+        for (i, phase) in SendPhase::ALL.iter().enumerate() {
+            assert_eq!(phase.index(), i);
+        }
+        assert!(SendPhase::ALL.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            SendPhase::from(HandshakeStep::WritingP2p),
+            SendPhase::WritingP2p
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_retries_until_success() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+        let calls = AtomicU32::new(0);
+        let mut retries = Vec::new();
+
+        let result = policy
+            .run(
+                "test",
+                || async {
+                    if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                        anyhow::bail!("not yet");
+                    }
+                    Ok(42)
+                },
+                |r| retries.push(r.attempt),
+            )
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(retries, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_run_gives_up_after_max_attempts() {
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = RetryPolicy::none()
+            .run(
+                "test",
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    anyhow::bail!("always fails")
+                },
+                |_| {},
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_does_not_retry_permission_denied() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = policy
+            .run(
+                "hotspot",
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(NmPermissionDenied {
+                        permission: crate::wifi::nm_dbus::PERMISSION_MODIFY_SYSTEM,
+                        message: "Insufficient privileges".to_string(),
+                    }
+                    .into())
+                },
+                |_| {},
+            )
+            .await;
+
+        assert!(result.unwrap_err().is::<NmPermissionDenied>());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_does_not_retry_cancelled_handshake() {
+        let policy = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::default()
+        };
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = policy
+            .run(
+                "handshake",
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(crate::ble::BleClientError::from(cancel::Interrupted::Cancelled).into())
+                },
+                |_| {},
+            )
+            .await;
+
+        assert!(cancel::is_cancelled(&result.unwrap_err()));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_classify_failures() {
+        let permanent: [anyhow::Error; 4] = [
+            BleClientError::DeviceBusy.into(),
+            BleClientError::P2pRejected.into(),
+            P2pInfoError::InvalidPsk.into(),
+            anyhow::Error::from(BleClientError::DeviceBusy).context("handshake"),
+        ];
+        for error in &permanent {
+            assert_eq!(Failure::classify(error), Failure::Permanent, "{:#}", error);
+        }
+
+        let transient: [anyhow::Error; 3] = [
+            BleClientError::ConnectionFailed("le-connection-abort-by-local".to_string()).into(),
+            BleClientError::GroupTimeout.into(),
+            anyhow::anyhow!("Connection refused"),
+        ];
+        for error in &transient {
+            assert_eq!(Failure::classify(error), Failure::Transient, "{:#}", error);
+        }
+    }
+}
@@ -0,0 +1,707 @@
+//! 双向传输会话
+//!
+//! [`Sender::send_to_device`](crate::Sender::send_to_device) 和
+//! [`Receiver::start`](crate::Receiver::start) 每次连接只传一批文件，方向固定。
+//! 会话在 P2P 链路建立后保持 WebSocket 连接，两端都可以随时发起 `sendRequest`：
+//!
+//! ```ignore
+//! let mut session = sender.open_session(&device, &callback).await?;
+//! session.send(vec![path]).await?;
+//! while let Some(incoming) = session.incoming().next().await {
+//!     incoming.accept(output_dir.clone(), &callback).await?;
+//! }
+//! ```
+//!
+//! 文件仍通过 HTTP `GET /download?taskId=` 下载。创建链路的一端（主机）在 WebSocket
+//! 所在端口提供下载；另一端（客户端）另开一个下载服务，端口通过 sendRequest 的
+//! `downloadPort` 字段告诉对端。CatShare 不会在连接上反向发起 sendRequest，
+//! 与 CatShare 互通时会话退化为单向传输。
+
+use crate::config::PortRange;
+use crate::transfer::port::bind_listener;
+use crate::transfer::protocol::{SendRequest, WsMessage};
+use crate::transfer::receiver_client::check_free_space;
+use crate::transfer::sender_server::create_zip_response;
+use crate::transfer::{FileEntry, ReceiverCallback, ReceiverClient};
+use axum::{
+    Router,
+    body::Body,
+    extract::ws::{Message as WsFrame, WebSocket, WebSocketUpgrade},
+    extract::{ConnectInfo, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+};
+use futures_util::{SinkExt, Stream, StreamExt};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tokio_util::io::ReaderStream;
+
+/// 本端正在提供下载的任务（taskId → 文件）
+type TaskStore = Arc<Mutex<HashMap<String, Vec<FileEntry>>>>;
+
+/// 一条 WebSocket 连接，两个方向都以文本帧通道表示
+struct Link {
+    tx: mpsc::Sender<String>,
+    rx: mpsc::Receiver<String>,
+}
+
+/// 对端的下载服务地址
+#[derive(Clone)]
+struct Peer {
+    host: String,
+    /// sendRequest 未带 `downloadPort` 时使用的端口（对端是主机时即 WebSocket 端口）
+    default_port: Option<u16>,
+    tls: bool,
+}
+
+enum Command {
+    Send {
+        task_id: String,
+        payload: Value,
+        done: oneshot::Sender<anyhow::Result<()>>,
+    },
+    Ack {
+        id: u32,
+        name: String,
+    },
+    Status {
+        task_id: String,
+        status_type: i32,
+        reason: String,
+    },
+}
+
+/// 双向传输会话
+pub struct Session {
+    commands: mpsc::Sender<Command>,
+    incoming: mpsc::Receiver<IncomingTransfer>,
+    tasks: TaskStore,
+    local_name: String,
+    sender_id: String,
+    download_port: u16,
+    io_task: JoinHandle<()>,
+    server_task: JoinHandle<()>,
+}
+
+impl Session {
+    /// 作为客户端连接主机的 WebSocket（接收端接入热点之后）
+    ///
+    /// `host` 不可达时依次尝试 `fallback_hosts`（见 [`ReceiverClient::ensure_reachable`]）
+    pub async fn connect(
+        host: &str,
+        fallback_hosts: &[String],
+        port: u16,
+        tls: bool,
+        local_name: &str,
+    ) -> anyhow::Result<Self> {
+        let client = ReceiverClient::new(host, port, PathBuf::new())
+            .with_tls(tls)
+            .with_fallback_hosts(fallback_hosts.to_vec())
+            .ensure_reachable()
+            .await?;
+        let ws = client.connect().await?;
+
+        let listener = TcpListener::bind("0.0.0.0:0").await?;
+        let download_port = listener.local_addr()?.port();
+        let tasks = TaskStore::default();
+        let server_task = serve(listener, tasks.clone(), None);
+
+        let peer = Peer {
+            host: client.host().to_string(),
+            default_port: Some(port),
+            tls,
+        };
+        Ok(Self::start(
+            bridge_tungstenite(ws),
+            peer,
+            false,
+            local_name,
+            download_port,
+            tasks,
+            server_task,
+        ))
+    }
+
+    fn start(
+        link: Link,
+        peer: Peer,
+        greet: bool,
+        local_name: &str,
+        download_port: u16,
+        tasks: TaskStore,
+        server_task: JoinHandle<()>,
+    ) -> Self {
+        let (commands, commands_rx) = mpsc::channel(16);
+        let (incoming_tx, incoming) = mpsc::channel(4);
+        let io_task = tokio::spawn(run_io(
+            link,
+            commands_rx,
+            commands.clone(),
+            incoming_tx,
+            peer,
+            greet,
+        ));
+        Self {
+            commands,
+            incoming,
+            tasks,
+            local_name: local_name.to_string(),
+            sender_id: format!("{:04x}", rand::random::<u16>()),
+            download_port,
+            io_task,
+            server_task,
+        }
+    }
+
+    /// 向对端发起一次传输，对端接收完成后返回
+    ///
+    /// 对端拒绝、下载失败或连接断开时返回错误。
+    pub async fn send(&self, files: Vec<PathBuf>) -> anyhow::Result<()> {
+        let mut entries = Vec::with_capacity(files.len());
+        for path in &files {
+            entries.push(FileEntry::from_path(path).await?);
+        }
+
+        let task_id = uuid::Uuid::new_v4().to_string();
+        let total_size: u64 = entries.iter().map(|f| f.size).sum();
+        let first = entries.first();
+        let payload = serde_json::json!({
+            "taskId": task_id,
+            "id": task_id,
+            "senderId": self.sender_id,
+            "senderName": self.local_name,
+            "fileName": first.map(|f| f.name.as_str()).unwrap_or_default(),
+            "mimeType": first.map_or("application/octet-stream", |f| f.mime_type.as_str()),
+            "fileCount": entries.len(),
+            "totalSize": total_size,
+            "files": entries.iter().map(FileEntry::info).collect::<Vec<_>>(),
+            "downloadPort": self.download_port,
+        });
+
+        self.tasks.lock().unwrap().insert(task_id.clone(), entries);
+        let (done, done_rx) = oneshot::channel();
+        let result = async {
+            self.commands
+                .send(Command::Send {
+                    task_id: task_id.clone(),
+                    payload,
+                    done,
+                })
+                .await
+                .map_err(|_| anyhow::anyhow!("session closed"))?;
+            done_rx
+                .await
+                .map_err(|_| anyhow::anyhow!("session closed"))?
+        }
+        .await;
+        self.tasks.lock().unwrap().remove(&task_id);
+        result
+    }
+
+    /// 对端发起的传输，连接断开后结束
+    pub fn incoming(&mut self) -> impl Stream<Item = IncomingTransfer> + '_ {
+        futures_util::stream::poll_fn(move |cx| self.incoming.poll_recv(cx))
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.io_task.abort();
+        self.server_task.abort();
+    }
+}
+
+/// 等待客户端连入的会话监听（主机一端，即创建热点的一端）
+pub struct SessionListener {
+    port: u16,
+    tasks: TaskStore,
+    links: mpsc::Receiver<(IpAddr, Link)>,
+    server_task: Option<JoinHandle<()>>,
+}
+
+impl SessionListener {
+    /// 启动 `/websocket` 和 `/download`，端口在 `ports` 范围内选择，`None` 时为随机端口
+    pub async fn bind(ports: Option<PortRange>) -> anyhow::Result<Self> {
+        let listener = bind_listener(ports).await?;
+        let port = listener.local_addr()?.port();
+        let tasks = TaskStore::default();
+        let (links_tx, links) = mpsc::channel(1);
+        let server_task = serve(listener, tasks.clone(), Some(links_tx));
+        info!("Session listener on port {}", port);
+        Ok(Self {
+            port,
+            tasks,
+            links,
+            server_task: Some(server_task),
+        })
+    }
+
+    /// 监听端口（通过 P2P 信息告诉对端）
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// 等待第一个客户端连入，之后的连接会被拒绝
+    pub async fn accept(mut self, local_name: &str) -> anyhow::Result<Session> {
+        let (ip, link) = self
+            .links
+            .recv()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("session listener closed"))?;
+        info!("Session peer connected: {}", ip);
+
+        // 客户端的下载服务没有 TLS，端口总是由 sendRequest 给出
+        let peer = Peer {
+            host: ip.to_string(),
+            default_port: None,
+            tls: false,
+        };
+        let server_task = self.server_task.take().expect("server task taken twice");
+        Ok(Session::start(
+            link,
+            peer,
+            true,
+            local_name,
+            self.port,
+            self.tasks.clone(),
+            server_task,
+        ))
+    }
+}
+
+impl Drop for SessionListener {
+    fn drop(&mut self) {
+        if let Some(task) = self.server_task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// 对端发起的一次传输，需要 [`accept`](Self::accept) 或 [`reject`](Self::reject)
+pub struct IncomingTransfer {
+    request: SendRequest,
+    msg_id: u32,
+    peer: Peer,
+    port: u16,
+    commands: mpsc::Sender<Command>,
+}
+
+impl IncomingTransfer {
+    pub fn request(&self) -> &SendRequest {
+        &self.request
+    }
+
+    /// 接受并下载到 `output_dir`
+    pub async fn accept<C: ReceiverCallback>(
+        self,
+        output_dir: PathBuf,
+        callback: &C,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        let task_id = self.request.get_task_id();
+        if let Err(e) = check_free_space(&output_dir, self.request.total_size) {
+            self.reject("insufficient space").await?;
+            return Err(e.into());
+        }
+        if !callback.on_send_request(&self.request) {
+            self.reject("user refuse").await?;
+            anyhow::bail!("User rejected transfer");
+        }
+
+        self.command(Command::Ack {
+            id: self.msg_id,
+            name: "sendRequest".to_string(),
+        })
+        .await?;
+
+        let client =
+            ReceiverClient::new(&self.peer.host, self.port, output_dir).with_tls(self.peer.tls);
+        match client
+            .download(
+                &task_id,
+                self.request.total_size,
+                &self.request.files,
+                callback,
+            )
+            .await
+        {
+            Ok(files) => {
+                self.status(&task_id, 1, "ok").await?;
+                callback.on_complete(files.clone());
+                Ok(files)
+            }
+            Err(e) => {
+                let _ = self.status(&task_id, 3, "download failed").await;
+                callback.on_error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    /// 拒绝传输
+    pub async fn reject(self, reason: &str) -> anyhow::Result<()> {
+        let task_id = self.request.get_task_id();
+        self.status(&task_id, 3, reason).await
+    }
+
+    async fn status(&self, task_id: &str, status_type: i32, reason: &str) -> anyhow::Result<()> {
+        self.command(Command::Status {
+            task_id: task_id.to_string(),
+            status_type,
+            reason: reason.to_string(),
+        })
+        .await
+    }
+
+    async fn command(&self, command: Command) -> anyhow::Result<()> {
+        self.commands
+            .send(command)
+            .await
+            .map_err(|_| anyhow::anyhow!("session closed"))
+    }
+}
+
+/// 会话的消息循环：发出本端的命令，分发对端的消息
+async fn run_io(
+    mut link: Link,
+    mut commands: mpsc::Receiver<Command>,
+    commands_tx: mpsc::Sender<Command>,
+    incoming: mpsc::Sender<IncomingTransfer>,
+    peer: Peer,
+    greet: bool,
+) {
+    let mut msg_id: u32 = 0;
+    // 已发出、等待对端完成的 sendRequest
+    let mut pending: HashMap<String, oneshot::Sender<anyhow::Result<()>>> = HashMap::new();
+
+    if greet {
+        let _ = link
+            .tx
+            .send(WsMessage::version_negotiation(msg_id).to_string())
+            .await;
+    }
+
+    loop {
+        let frame = tokio::select! {
+            Some(command) = commands.recv() => match command {
+                Command::Send { task_id, payload, done } => {
+                    msg_id += 1;
+                    pending.insert(task_id, done);
+                    WsMessage::action(msg_id, "sendRequest", Some(payload))
+                }
+                Command::Ack { id, name } => WsMessage::ack(id, &name, None),
+                Command::Status { task_id, status_type, reason } => {
+                    msg_id += 1;
+                    WsMessage::status(msg_id, &task_id, status_type, &reason)
+                }
+            },
+            text = link.rx.recv() => {
+                let Some(text) = text else {
+                    break;
+                };
+                match handle_message(&text, &mut pending, &incoming, &commands_tx, &peer) {
+                    Some(reply) => reply,
+                    None => continue,
+                }
+            }
+        };
+        if link.tx.send(frame.to_string()).await.is_err() {
+            break;
+        }
+    }
+
+    debug!("Session closed with {} pending transfers", pending.len());
+    for (_, done) in pending {
+        let _ = done.send(Err(anyhow::anyhow!("session closed")));
+    }
+}
+
+/// 处理对端的一条消息，返回需要立即回复的消息
+fn handle_message(
+    text: &str,
+    pending: &mut HashMap<String, oneshot::Sender<anyhow::Result<()>>>,
+    incoming: &mpsc::Sender<IncomingTransfer>,
+    commands: &mpsc::Sender<Command>,
+    peer: &Peer,
+) -> Option<WsMessage> {
+    let Some(msg) = WsMessage::parse(text) else {
+        warn!("Invalid WebSocket message: {}", text);
+        return None;
+    };
+    debug!("Session received: type={}, name={}", msg.msg_type, msg.name);
+
+    if msg.msg_type != "action" {
+        return None;
+    }
+    match msg.name.as_str() {
+        "versionNegotiation" => Some(WsMessage::ack(
+            msg.id,
+            "versionNegotiation",
+            Some(serde_json::json!({ "version": 1, "threadLimit": 5 })),
+        )),
+        "sendRequest" => {
+            let request: SendRequest = match msg.payload.map(serde_json::from_value) {
+                Some(Ok(request)) => request,
+                _ => {
+                    warn!("Invalid sendRequest from session peer");
+                    return None;
+                }
+            };
+            let task_id = request.get_task_id();
+            let Some(port) = request.download_port.or(peer.default_port) else {
+                return Some(WsMessage::status(0, &task_id, 3, "missing download port"));
+            };
+            let transfer = IncomingTransfer {
+                request,
+                msg_id: msg.id,
+                peer: peer.clone(),
+                port,
+                commands: commands.clone(),
+            };
+            match incoming.try_send(transfer) {
+                Ok(()) => None,
+                Err(_) => Some(WsMessage::status(0, &task_id, 3, "busy")),
+            }
+        }
+        "status" => {
+            let payload = msg.payload.as_ref();
+            let task_id = payload
+                .and_then(|p| p.get("taskId"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let status_type = payload
+                .and_then(|p| p.get("type"))
+                .and_then(|v| v.as_i64())
+                .unwrap_or(0);
+            let result = match status_type {
+                1 => Some(Ok(())),
+                3 => {
+                    let reason = payload
+                        .and_then(|p| p.get("reason"))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("rejected");
+                    Some(Err(anyhow::anyhow!("Rejected by peer: {}", reason)))
+                }
+                _ => None,
+            };
+            if let Some(result) = result
+                && let Some(done) = pending.remove(task_id)
+            {
+                let _ = done.send(result);
+            }
+            Some(WsMessage::ack(msg.id, "status", None))
+        }
+        name => Some(WsMessage::ack(msg.id, name, None)),
+    }
+}
+
+#[derive(Deserialize)]
+struct DownloadQuery {
+    #[serde(rename = "taskId")]
+    task_id: String,
+}
+
+#[derive(Clone)]
+struct ServerState {
+    tasks: TaskStore,
+    /// 主机一端把连入的 WebSocket 交给 [`SessionListener::accept`]
+    links: Option<mpsc::Sender<(IpAddr, Link)>>,
+}
+
+/// 启动会话的 HTTP 服务：总是提供 `/download`，主机一端另外提供 `/websocket`
+fn serve(
+    listener: TcpListener,
+    tasks: TaskStore,
+    links: Option<mpsc::Sender<(IpAddr, Link)>>,
+) -> JoinHandle<()> {
+    let mut app: Router<ServerState> = Router::new().route("/download", get(download_handler));
+    if links.is_some() {
+        app = app.route("/websocket", get(websocket_handler));
+    }
+    let app = app
+        .with_state(ServerState { tasks, links })
+        .into_make_service_with_connect_info::<SocketAddr>();
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            warn!("Session server error: {}", e);
+        }
+    })
+}
+
+async fn download_handler(
+    Query(query): Query<DownloadQuery>,
+    State(state): State<ServerState>,
+) -> impl IntoResponse {
+    let files = state.tasks.lock().unwrap().get(&query.task_id).cloned();
+    let Some(files) = files else {
+        return (StatusCode::NOT_FOUND, "Task not found").into_response();
+    };
+    let zip = match create_zip_response(&files).await {
+        Ok(zip) => zip,
+        Err(e) => {
+            warn!("Failed to create ZIP: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create ZIP").into_response();
+        }
+    };
+    // 打开之后 ZIP 随 `zip` 删除，句柄仍能读完
+    match tokio::fs::File::open(zip.path()).await {
+        Ok(file) => (
+            [("Content-Type", "application/zip")],
+            Body::from_stream(ReaderStream::new(file)),
+        )
+            .into_response(),
+        Err(e) => {
+            warn!("Failed to open ZIP: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to open ZIP").into_response()
+        }
+    }
+}
+
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<ServerState>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| async move {
+        if let Some(links) = state.links {
+            // 已有会话时 accept 已返回，接收端被丢弃，新连接随之关闭
+            let _ = links.try_send((addr.ip(), bridge_axum(socket)));
+        }
+    })
+}
+
+/// 把 axum 的 WebSocket 拆成文本帧通道
+fn bridge_axum(socket: WebSocket) -> Link {
+    let (mut write, mut read) = socket.split();
+    let (tx, mut out_rx) = mpsc::channel::<String>(32);
+    let (in_tx, rx) = mpsc::channel(32);
+
+    tokio::spawn(async move {
+        while let Some(text) = out_rx.recv().await {
+            if write.send(WsFrame::Text(text)).await.is_err() {
+                break;
+            }
+        }
+        let _ = write.close().await;
+    });
+    tokio::spawn(async move {
+        while let Some(Ok(frame)) = read.next().await {
+            let text = match frame {
+                WsFrame::Text(text) => text,
+                WsFrame::Close(_) => break,
+                _ => continue,
+            };
+            if in_tx.send(text).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Link { tx, rx }
+}
+
+/// 把 tungstenite 的 WebSocket 拆成文本帧通道
+fn bridge_tungstenite(ws: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>) -> Link {
+    let (mut write, mut read) = ws.split();
+    let (tx, mut out_rx) = mpsc::channel::<String>(32);
+    let (in_tx, rx) = mpsc::channel(32);
+
+    tokio::spawn(async move {
+        while let Some(text) = out_rx.recv().await {
+            if write.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+        let _ = write.close().await;
+    });
+    tokio::spawn(async move {
+        while let Some(Ok(frame)) = read.next().await {
+            let text = match frame {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            if in_tx.send(text).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Link { tx, rx }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_dir;
+
+    struct AcceptAll;
+
+    impl ReceiverCallback for AcceptAll {
+        fn on_send_request(&self, _request: &SendRequest) -> bool {
+            true
+        }
+        fn on_progress(&self, _received: u64, _total: u64) {}
+        fn on_complete(&self, _files: Vec<PathBuf>) {}
+        fn on_error(&self, _error: String) {}
+    }
+
+    #[tokio::test]
+    async fn test_both_sides_send_over_one_connection() {
+        let root = temp_dir("session");
+        let (src, host_out, guest_out) = (root.join("src"), root.join("host"), root.join("guest"));
+        std::fs::create_dir_all(&src).unwrap();
+        std::fs::write(src.join("a.txt"), b"from host").unwrap();
+        std::fs::write(src.join("b.txt"), b"from guest").unwrap();
+
+        let listener = SessionListener::bind(None).await.unwrap();
+        let port = listener.port();
+        let (host, guest) = tokio::join!(
+            listener.accept("host"),
+            Session::connect("127.0.0.1", &[], port, false, "guest")
+        );
+        let (mut host, mut guest) = (host.unwrap(), guest.unwrap());
+
+        // 主机 → 客户端
+        let receive = async {
+            let transfer = guest.incoming().next().await.unwrap();
+            assert_eq!(transfer.request().sender_name, "host");
+            transfer.accept(guest_out.clone(), &AcceptAll).await
+        };
+        let (sent, received) = tokio::join!(host.send(vec![src.join("a.txt")]), receive);
+        sent.unwrap();
+        assert_eq!(received.unwrap(), vec![guest_out.join("a.txt")]);
+
+        // 客户端 → 主机，复用同一连接
+        let receive = async {
+            let transfer = host.incoming().next().await.unwrap();
+            transfer.accept(host_out.clone(), &AcceptAll).await
+        };
+        let (sent, received) = tokio::join!(guest.send(vec![src.join("b.txt")]), receive);
+        sent.unwrap();
+        let files = received.unwrap();
+        assert_eq!(std::fs::read(&files[0]).unwrap(), b"from guest");
+
+        // 拒绝时发起方收到错误
+        let receive = async {
+            let transfer = host.incoming().next().await.unwrap();
+            transfer.reject("no thanks").await
+        };
+        let (sent, rejected) = tokio::join!(guest.send(vec![src.join("b.txt")]), receive);
+        rejected.unwrap();
+        assert!(sent.unwrap_err().to_string().contains("no thanks"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
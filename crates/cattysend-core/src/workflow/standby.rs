@@ -0,0 +1,61 @@
+//! 发送端热备
+//!
+//! 默认情况下，用户选择发送之后才开始生成热点配置、监听传输端口。开启热备
+//! （设置项 `warm_standby`）后，[`Standby::prepare`] 提前在 NetworkManager 中添加热点连接
+//! （不激活，接收端看不到）并绑定传输端口；[`Sender::with_standby`] 接上它后，
+//! 策略和网卡都相同的发送只需激活热点，从点击发送到接收端能连上基本只剩 BLE 握手的时间。
+//!
+//! 准备好的热点和端口只用一次，每次发送结束后应再次调用 [`Standby::prepare`]。
+//! 对端或品牌兼容性要求不同的凭据（WPA3、只建 2.4GHz 等）时，准备好的热点被丢弃，
+//! 发送照常创建新的热点。
+
+use crate::config::PortRange;
+use crate::transfer::WarmHttpTransport;
+use crate::wifi::{CredentialPolicy, PeerSupport, WifiBackend};
+use crate::workflow::sender::{SendOptions, Sender, default_wifi_backend};
+use std::sync::Arc;
+
+/// 多次发送之间共享的热点后端和传输端口
+pub struct Standby {
+    wifi: Arc<dyn WifiBackend>,
+    transport: Arc<WarmHttpTransport>,
+    credentials: CredentialPolicy,
+    ports: Option<PortRange>,
+}
+
+impl Standby {
+    /// 按发送选项准备（网卡、频段、凭据策略和端口范围应与之后的发送相同）
+    pub fn new(options: &SendOptions) -> Self {
+        Self {
+            wifi: Arc::new(default_wifi_backend(options)),
+            transport: Arc::new(WarmHttpTransport::new()),
+            credentials: options.credentials,
+            ports: options.ports,
+        }
+    }
+
+    /// 替换 WiFi 后端
+    pub fn with_wifi_backend(mut self, wifi: Arc<dyn WifiBackend>) -> Self {
+        self.wifi = wifi;
+        self
+    }
+
+    /// 准备下一次发送的传输端口和热点，替换之前准备的
+    ///
+    /// 热点按 CatShare 兼容的凭据准备，这也是默认设置下对 cattysend 接收端使用的凭据
+    pub async fn prepare(&self) -> anyhow::Result<u16> {
+        let port = self.transport.prepare(self.ports).await?;
+        self.wifi
+            .prepare_hotspot(&self.credentials.for_peer(PeerSupport::CATSHARE))
+            .await?;
+        Ok(port)
+    }
+
+    pub(crate) fn wifi(&self) -> Arc<dyn WifiBackend> {
+        self.wifi.clone()
+    }
+
+    pub(crate) fn transport(&self) -> Arc<WarmHttpTransport> {
+        self.transport.clone()
+    }
+}
@@ -0,0 +1,285 @@
+//! 工作流状态机
+//!
+//! 发送端和接收端的流程都由一组阶段（[`SendPhase`] / [`ReceivePhase`]）组成，
+//! 阶段之间允许的转换写在各自的 [`Phase::TRANSITIONS`] 表里。
+//! [`StateMachine`] 按表校验每次转换，并通过 watch 通道把当前状态交给界面，
+//! 界面只需 `subscribe()` 一次即可拿到最新状态，不必重放回调事件。
+//!
+//! 流程失败时状态停在 [`WorkflowState::Failed`]，记录失败前所处的阶段；
+//! [`WorkflowState::resume_phase`] 给出从哪个阶段重新开始不需要重做前面的步骤。
+
+use crate::workflow::receiver::ReceivePhase;
+use crate::workflow::sender::SendPhase;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tokio::sync::watch;
+
+/// 工作流的一个阶段
+pub trait Phase: Copy + Eq + Debug + Send + Sync + 'static {
+    /// 允许的转换 `(from, to)`，同一阶段重复进入总是允许
+    const TRANSITIONS: &'static [(Self, Self)];
+    /// 新一轮流程可以从这些阶段开始
+    const ENTRY: &'static [Self];
+
+    /// 在该阶段失败后，从哪个阶段重新开始
+    fn resume_from(self) -> Self;
+}
+
+/// 工作流当前状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", content = "phase", rename_all = "snake_case")]
+pub enum WorkflowState<P> {
+    /// 尚未开始
+    Idle,
+    /// 正处于某个阶段
+    Running(P),
+    /// 在某个阶段失败
+    Failed(P),
+}
+
+impl<P: Phase> WorkflowState<P> {
+    /// 当前（或失败时所处）的阶段
+    pub fn phase(self) -> Option<P> {
+        match self {
+            WorkflowState::Idle => None,
+            WorkflowState::Running(phase) | WorkflowState::Failed(phase) => Some(phase),
+        }
+    }
+
+    /// 失败后重新开始的阶段
+    pub fn resume_phase(self) -> Option<P> {
+        match self {
+            WorkflowState::Failed(phase) => Some(phase.resume_from()),
+            _ => None,
+        }
+    }
+
+    /// 能否在不重新开始流程的情况下进入 `to`
+    pub fn can_advance(self, to: P) -> bool {
+        match self {
+            WorkflowState::Idle => false,
+            WorkflowState::Running(from) => from == to || P::TRANSITIONS.contains(&(from, to)),
+            WorkflowState::Failed(at) => at.resume_from() == to,
+        }
+    }
+}
+
+/// 不在转换表中的状态转换
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid workflow transition from {from:?} to {to:?}")]
+pub struct InvalidTransition<P: Debug> {
+    pub from: WorkflowState<P>,
+    pub to: P,
+}
+
+/// 带转换校验的状态机，状态变化通过 watch 通道发布
+pub struct StateMachine<P: Phase> {
+    tx: watch::Sender<WorkflowState<P>>,
+}
+
+impl<P: Phase> Default for StateMachine<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Phase> StateMachine<P> {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(WorkflowState::Idle);
+        Self { tx }
+    }
+
+    /// 订阅状态变化
+    pub fn subscribe(&self) -> watch::Receiver<WorkflowState<P>> {
+        self.tx.subscribe()
+    }
+
+    /// 当前状态
+    pub fn current(&self) -> WorkflowState<P> {
+        *self.tx.borrow()
+    }
+
+    /// 开始新一轮流程，无论之前处于什么状态；`phase` 必须是 [`Phase::ENTRY`] 之一
+    pub fn start(&self, phase: P) -> Result<(), InvalidTransition<P>> {
+        if !P::ENTRY.contains(&phase) {
+            return Err(InvalidTransition {
+                from: self.current(),
+                to: phase,
+            });
+        }
+        self.tx.send_replace(WorkflowState::Running(phase));
+        Ok(())
+    }
+
+    /// 进入下一个阶段，转换不在表中时状态保持不变
+    pub fn advance(&self, phase: P) -> Result<(), InvalidTransition<P>> {
+        let mut result = Ok(());
+        self.tx.send_if_modified(|state| {
+            if !state.can_advance(phase) {
+                result = Err(InvalidTransition {
+                    from: *state,
+                    to: phase,
+                });
+                return false;
+            }
+            let next = WorkflowState::Running(phase);
+            std::mem::replace(state, next) != next
+        });
+        result
+    }
+
+    /// 标记当前阶段失败（尚未开始或已经失败时不变）
+    pub fn fail(&self) {
+        self.tx.send_if_modified(|state| match *state {
+            WorkflowState::Running(phase) => {
+                *state = WorkflowState::Failed(phase);
+                true
+            }
+            _ => false,
+        });
+    }
+}
+
+impl Phase for SendPhase {
+    const TRANSITIONS: &'static [(Self, Self)] = &[
+        (SendPhase::Scanning, SendPhase::Connecting),
+        (SendPhase::Connecting, SendPhase::ReadingStatus),
+        // 局域网握手不单独读取状态
+        (SendPhase::Connecting, SendPhase::WritingP2p),
+        (SendPhase::ReadingStatus, SendPhase::WritingP2p),
+        // 握手失败重试
+        (SendPhase::ReadingStatus, SendPhase::Connecting),
+        (SendPhase::WritingP2p, SendPhase::Connecting),
+        (SendPhase::WritingP2p, SendPhase::WaitingForReceiverWifi),
+        (SendPhase::WaitingForReceiverWifi, SendPhase::Transferring),
+        // 文件很小时可能收不到进度就已完成
+        (SendPhase::WaitingForReceiverWifi, SendPhase::Done),
+        (SendPhase::Transferring, SendPhase::Done),
+    ];
+    const ENTRY: &'static [Self] = &[SendPhase::Scanning, SendPhase::Connecting];
+
+    /// 热点和传输服务在失败时都已关闭，只能从握手重新开始
+    fn resume_from(self) -> Self {
+        match self {
+            SendPhase::Scanning => SendPhase::Scanning,
+            _ => SendPhase::Connecting,
+        }
+    }
+}
+
+impl Phase for ReceivePhase {
+    const TRANSITIONS: &'static [(Self, Self)] = &[
+        (ReceivePhase::Advertising, ReceivePhase::JoiningNetwork),
+        (ReceivePhase::JoiningNetwork, ReceivePhase::Connecting),
+        (ReceivePhase::Connecting, ReceivePhase::Receiving),
+        // 拒绝传输时不进入接收阶段
+        (ReceivePhase::Connecting, ReceivePhase::WaitingForUploads),
+        (ReceivePhase::Connecting, ReceivePhase::Done),
+        (ReceivePhase::Receiving, ReceivePhase::WaitingForUploads),
+        (ReceivePhase::Receiving, ReceivePhase::Done),
+        (ReceivePhase::WaitingForUploads, ReceivePhase::Done),
+    ];
+    const ENTRY: &'static [Self] = &[ReceivePhase::Advertising, ReceivePhase::JoiningNetwork];
+
+    /// 接入发送端网络之后失败时网络仍然连着，重新连接传输服务即可
+    fn resume_from(self) -> Self {
+        match self {
+            ReceivePhase::Advertising | ReceivePhase::JoiningNetwork => self,
+            ReceivePhase::Connecting | ReceivePhase::Receiving => ReceivePhase::Connecting,
+            ReceivePhase::WaitingForUploads | ReceivePhase::Done => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_happy_path() {
+        let machine = StateMachine::new();
+        let mut rx = machine.subscribe();
+        assert_eq!(machine.current(), WorkflowState::Idle);
+        assert!(machine.advance(SendPhase::Connecting).is_err());
+
+        machine.start(SendPhase::Connecting).unwrap();
+        for phase in &SendPhase::ALL[2..] {
+            machine.advance(*phase).unwrap();
+        }
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(
+            *rx.borrow_and_update(),
+            WorkflowState::Running(SendPhase::Done)
+        );
+    }
+
+    #[test]
+    fn test_invalid_transition_keeps_state() {
+        let machine = StateMachine::new();
+        machine.start(SendPhase::Connecting).unwrap();
+        let err = machine.advance(SendPhase::Transferring).unwrap_err();
+        assert_eq!(err.from, WorkflowState::Running(SendPhase::Connecting));
+        assert_eq!(
+            machine.current(),
+            WorkflowState::Running(SendPhase::Connecting)
+        );
+        assert!(machine.start(SendPhase::Done).is_err());
+    }
+
+    #[test]
+    fn test_handshake_retry_goes_back_to_connecting() {
+        let machine = StateMachine::new();
+        machine.start(SendPhase::Connecting).unwrap();
+        machine.advance(SendPhase::ReadingStatus).unwrap();
+        machine.advance(SendPhase::Connecting).unwrap();
+        machine.advance(SendPhase::Connecting).unwrap();
+        assert_eq!(
+            machine.current(),
+            WorkflowState::Running(SendPhase::Connecting)
+        );
+    }
+
+    #[test]
+    fn test_failure_resumes_from_known_phase() {
+        let machine = StateMachine::new();
+        machine.fail();
+        assert_eq!(machine.current(), WorkflowState::Idle);
+
+        machine.start(ReceivePhase::JoiningNetwork).unwrap();
+        machine.advance(ReceivePhase::Connecting).unwrap();
+        machine.advance(ReceivePhase::Receiving).unwrap();
+        machine.fail();
+        let state = machine.current();
+        assert_eq!(state, WorkflowState::Failed(ReceivePhase::Receiving));
+        assert_eq!(state.resume_phase(), Some(ReceivePhase::Connecting));
+
+        // 只能回到恢复点，或者重新开始
+        assert!(machine.advance(ReceivePhase::Done).is_err());
+        machine.advance(ReceivePhase::Connecting).unwrap();
+        machine.advance(ReceivePhase::Receiving).unwrap();
+    }
+
+    #[test]
+    fn test_every_phase_is_reachable() {
+        fn reachable<P: Phase>(all: &[P]) {
+            for phase in all {
+                assert!(
+                    P::ENTRY.contains(phase) || P::TRANSITIONS.iter().any(|(_, to)| to == phase),
+                    "{:?} is unreachable",
+                    phase
+                );
+            }
+        }
+        reachable(&SendPhase::ALL);
+        reachable(&ReceivePhase::ALL);
+    }
+
+    #[test]
+    fn test_state_serialization() {
+        let json = serde_json::to_string(&WorkflowState::Failed(SendPhase::WritingP2p)).unwrap();
+        assert_eq!(json, r#"{"state":"failed","phase":"writing_p2p"}"#);
+        let idle: WorkflowState<ReceivePhase> =
+            serde_json::from_str(r#"{"state":"idle"}"#).unwrap();
+        assert_eq!(idle, WorkflowState::Idle);
+    }
+}
@@ -0,0 +1,996 @@
+//! 端到端回环测试
+//!
+//! 使用 `loopback-test` feature 提供的模拟后端，在 127.0.0.1 上跑完整的
+//! Sender ↔ Receiver 流程，无需蓝牙和 WiFi：
+//!
+//! ```bash
+//! cargo test -p cattysend-core --features loopback-test --test loopback
+//! ```
+
+#![cfg(feature = "loopback-test")]
+
+use cattysend_core::ble::{
+    BleClient, BleClientError, DiscoveredDevice, HandshakeStep, ReceiverState,
+};
+use cattysend_core::crypto::{AtRestKey, BleSecurityPersistent, at_rest};
+use cattysend_core::testing::{
+    LOOPBACK_RECEIVER_MAC, LoopbackGattBackend, LoopbackWifiBackend, temp_dir,
+};
+use cattysend_core::transfer::UploadServer;
+use cattysend_core::{
+    Device, P2pInfo, ReceiveEvent, ReceiveOptions, Receiver, ReceiverCallback, RetryPolicy,
+    SendEvent, SendOptions, SendRequest, Sender, SimpleReceiveCallback, SimpleSendCallback,
+    TransferMode,
+};
+use futures_util::StreamExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn loopback_device() -> DiscoveredDevice {
+    DiscoveredDevice {
+        name: "loopback".to_string(),
+        address: LOOPBACK_RECEIVER_MAC.to_string(),
+        sender_id: "0000".to_string(),
+        brand: "Linux".to_string(),
+        brand_id: None,
+        rssi: None,
+        supports_5ghz: false,
+        busy: false,
+        supports_wpa3: false,
+        lan_endpoint: None,
+    }
+}
+
+fn loopback_pair(
+    output_dir: PathBuf,
+    security: Arc<BleSecurityPersistent>,
+    gatt: LoopbackGattBackend,
+) -> (Sender, Receiver) {
+    let sender = Sender::new(SendOptions {
+        retry: RetryPolicy::none(),
+        ..Default::default()
+    })
+    .unwrap()
+    .with_wifi_backend(Arc::new(LoopbackWifiBackend))
+    .with_ble_backend(Arc::new(gatt));
+
+    let receiver = Receiver::new(ReceiveOptions {
+        output_dir,
+        auto_accept: true,
+        use_tls: false,
+        retry: RetryPolicy {
+            initial_backoff: Duration::from_millis(50),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .unwrap()
+    .with_wifi_backend(Arc::new(LoopbackWifiBackend))
+    .with_security(security);
+
+    (sender, receiver)
+}
+
+/// 完整流程：BLE 握手（加密）→ WebSocket 协商 → ZIP 下载 → 完成状态
+#[tokio::test]
+async fn test_send_and_receive_over_loopback() {
+    let input_dir = temp_dir("send");
+    let output_dir = temp_dir("recv");
+    let input = input_dir.join("hello.txt");
+    std::fs::write(&input, b"hello over loopback").unwrap();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let (sender, receiver) = loopback_pair(output_dir.clone(), security, gatt);
+
+    let (receive_callback, mut receive_events) = SimpleReceiveCallback::new(true);
+    let receive = async {
+        let event = p2p_rx.recv().await.expect("sender never wrote P2P info");
+        // P2pInfo 应已被解密
+        assert!(event.sender_public_key.is_some());
+        assert_eq!(event.p2p_info.ssid, "DIRECT-loopback");
+        receiver.handle_p2p_event(event, &receive_callback).await
+    };
+
+    let (callback, mut send_events) = SimpleSendCallback::new();
+    let device = loopback_device();
+    let send = sender.send_to_device(&device, vec![input], &callback);
+
+    let (sent, received) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(send, receive)
+    })
+    .await
+    .expect("loopback transfer timed out");
+
+    sent.unwrap();
+    let files = received.unwrap();
+    assert_eq!(files, vec![output_dir.join("hello.txt")]);
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"hello over loopback");
+    // 修改时间随 sendRequest 的文件元数据一起恢复（毫秒精度）
+    let mtime_ms = |p: &PathBuf| {
+        std::fs::metadata(p)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    };
+    assert_eq!(mtime_ms(&files[0]), mtime_ms(&input_dir.join("hello.txt")));
+
+    // 两端显示同一个验证码（sendRequest 中的验证码不一致时接收端会拒绝）
+    let mut sender_code = None;
+    while let Ok(event) = send_events.try_recv() {
+        if let SendEvent::VerificationCode(code) = event {
+            sender_code = Some(code);
+        }
+    }
+    let mut receiver_code = None;
+    while let Ok(event) = receive_events.try_recv() {
+        if let ReceiveEvent::VerificationCode(code) = event {
+            receiver_code = Some(code);
+        }
+    }
+    assert!(sender_code.is_some());
+    assert_eq!(sender_code, receiver_code);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 高层 API：`Device::send_with` 的事件流和结果
+#[tokio::test]
+async fn test_device_send_over_loopback() {
+    let input_dir = temp_dir("api-send");
+    let output_dir = temp_dir("api-recv");
+    let input = input_dir.join("api.txt");
+    std::fs::write(&input, b"sent through the facade").unwrap();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let (sender, receiver) = loopback_pair(output_dir.clone(), security, gatt);
+
+    let (receive_callback, _receive_events) = SimpleReceiveCallback::new(true);
+    let receive = async {
+        let event = p2p_rx.recv().await.expect("sender never wrote P2P info");
+        receiver.handle_p2p_event(event, &receive_callback).await
+    };
+
+    let mut sending = Device::from(loopback_device()).send_with(sender, [&input]);
+    let send = async {
+        let mut completed = false;
+        while let Some(event) = sending.next().await {
+            completed |= matches!(event, SendEvent::Complete);
+        }
+        (completed, sending.await)
+    };
+
+    let ((completed, sent), received) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(send, receive)
+    })
+    .await
+    .expect("loopback transfer timed out");
+
+    sent.unwrap();
+    assert!(completed);
+    let files = received.unwrap();
+    assert_eq!(
+        std::fs::read(&files[0]).unwrap(),
+        b"sent through the facade"
+    );
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 二维码引导：不经过 BLE，接收端从引导载荷接入，两端验证码一致
+#[tokio::test]
+async fn test_send_via_bootstrap_payload() {
+    let input_dir = temp_dir("qr-send");
+    let output_dir = temp_dir("qr-recv");
+    let input = input_dir.join("qr.txt");
+    std::fs::write(&input, b"handed over by QR code").unwrap();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, _p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let receiver_key = security.get_public_key().to_string();
+    let (sender, receiver) = loopback_pair(output_dir.clone(), security, gatt);
+
+    let (callback, mut send_events) = SimpleSendCallback::new();
+    let send = sender.send_via_payload(Some(&receiver_key), vec![input], &callback);
+
+    let (receive_callback, mut receive_events) = SimpleReceiveCallback::new(true);
+    let receive = async {
+        let mut sender_code = None;
+        let payload = loop {
+            match send_events
+                .recv()
+                .await
+                .expect("sender never showed a payload")
+            {
+                SendEvent::BootstrapPayload(payload) => break payload,
+                SendEvent::VerificationCode(code) => sender_code = Some(code),
+                _ => {}
+            }
+        };
+        let files = receiver.receive_payload(&payload, &receive_callback).await;
+        while let Ok(event) = send_events.try_recv() {
+            if let SendEvent::VerificationCode(code) = event {
+                sender_code = Some(code);
+            }
+        }
+        (files, sender_code)
+    };
+
+    let (sent, (received, sender_code)) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(send, receive)
+    })
+    .await
+    .expect("loopback transfer timed out");
+
+    sent.unwrap();
+    let files = received.unwrap();
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"handed over by QR code");
+
+    let mut receiver_code = None;
+    while let Ok(event) = receive_events.try_recv() {
+        if let ReceiveEvent::VerificationCode(code) = event {
+            receiver_code = Some(code);
+        }
+    }
+    assert!(sender_code.is_some());
+    assert_eq!(sender_code, receiver_code);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 设置静态加密密钥时，输出目录里只有加密文件，用同一密钥可以还原
+#[tokio::test]
+async fn test_receive_encrypted_at_rest() {
+    let input_dir = temp_dir("enc-send");
+    let output_dir = temp_dir("enc-recv");
+    let input = input_dir.join("secret.txt");
+    std::fs::write(&input, b"for my eyes only").unwrap();
+
+    let key = AtRestKey::generate();
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let (sender, _) = loopback_pair(output_dir.clone(), security.clone(), gatt);
+    let receiver = Receiver::new(ReceiveOptions {
+        output_dir: output_dir.clone(),
+        auto_accept: true,
+        use_tls: false,
+        encryption_key: Some(key.clone()),
+        ..Default::default()
+    })
+    .unwrap()
+    .with_wifi_backend(Arc::new(LoopbackWifiBackend))
+    .with_security(security);
+
+    let receive = async {
+        let event = p2p_rx.recv().await.expect("sender never wrote P2P info");
+        let (callback, _events) = SimpleReceiveCallback::new(true);
+        receiver.handle_p2p_event(event, &callback).await
+    };
+    let (callback, _events) = SimpleSendCallback::new();
+    let device = loopback_device();
+    let send = sender.send_to_device(&device, vec![input], &callback);
+
+    let (sent, received) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(send, receive)
+    })
+    .await
+    .expect("loopback transfer timed out");
+
+    sent.unwrap();
+    let files = received.unwrap();
+    let encrypted = output_dir.join("secret.txt.cattyenc");
+    assert_eq!(files, vec![encrypted.clone()]);
+    let entries: Vec<_> = std::fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, vec!["secret.txt.cattyenc"]);
+
+    let restored = output_dir.join("secret.txt");
+    at_rest::decrypt_file(&key, &encrypted, &restored).unwrap();
+    assert_eq!(std::fs::read(&restored).unwrap(), b"for my eyes only");
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 下载开始前已暂停：发送端收到暂停通知，恢复后照常传完
+#[tokio::test]
+async fn test_pause_and_resume_download() {
+    let input_dir = temp_dir("pause-send");
+    let output_dir = temp_dir("pause-recv");
+    let input = input_dir.join("paused.txt");
+    std::fs::write(&input, b"paused then resumed").unwrap();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let (sender, receiver) = loopback_pair(output_dir.clone(), security, gatt);
+    receiver.pause();
+    let control = receiver.control();
+
+    let (receive_callback, mut receive_events) = SimpleReceiveCallback::new(true);
+    let receive = async {
+        let event = p2p_rx.recv().await.expect("sender never wrote P2P info");
+        receiver.handle_p2p_event(event, &receive_callback).await
+    };
+    let (callback, mut send_events) = SimpleSendCallback::new();
+    let device = loopback_device();
+    let send = sender.send_to_device(&device, vec![input], &callback);
+    let resume = async {
+        while let Some(event) = send_events.recv().await {
+            if matches!(event, SendEvent::Paused) {
+                control.resume();
+                return true;
+            }
+        }
+        false
+    };
+
+    let (sent, received, paused) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(send, receive, resume)
+    })
+    .await
+    .expect("loopback transfer timed out");
+
+    sent.unwrap();
+    assert!(paused, "sender never saw the pause");
+    let files = received.unwrap();
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"paused then resumed");
+
+    let mut pauses = Vec::new();
+    while let Ok(event) = receive_events.try_recv() {
+        match event {
+            ReceiveEvent::Paused => pauses.push(true),
+            ReceiveEvent::Resumed => pauses.push(false),
+            _ => {}
+        }
+    }
+    assert_eq!(pauses, vec![true, false]);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 接收端上报忙碌时，发送端不写入 P2P 信息
+#[tokio::test]
+async fn test_busy_receiver_is_rejected() {
+    let input_dir = temp_dir("busy");
+    let input = input_dir.join("busy.txt");
+    std::fs::write(&input, b"busy").unwrap();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let gatt = gatt.with_state(ReceiverState::Busy);
+    let (sender, _receiver) = loopback_pair(input_dir.clone(), security, gatt);
+
+    let (callback, _events) = SimpleSendCallback::new();
+    let result = sender
+        .send_to_device(&loopback_device(), vec![input], &callback)
+        .await;
+
+    assert!(result.unwrap_err().to_string().contains("busy"));
+    assert!(p2p_rx.try_recv().is_err());
+
+    let _ = std::fs::remove_dir_all(input_dir);
+}
+
+/// 分两步握手：先连接并读取 DeviceInfo（热点创建期间），之后才写入 P2P 信息
+#[tokio::test]
+async fn test_split_handshake_over_loopback() {
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let client = BleClient::with_backend(Box::new(gatt));
+
+    let pending = client
+        .begin_handshake(LOOPBACK_RECEIVER_MAC, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(
+        pending.device_info().key.as_deref(),
+        Some(security.get_public_key())
+    );
+    // 读取 DeviceInfo 后还没有写入任何东西
+    assert!(p2p_rx.try_recv().is_err());
+
+    let p2p_info = P2pInfo::new(
+        "DIRECT-split".to_string(),
+        "splitpsk".to_string(),
+        "02:00:00:00:00:01".to_string(),
+        8443,
+    );
+    let steps = std::sync::Mutex::new(Vec::new());
+    client
+        .finish_handshake(pending, &p2p_info, "abcd", |step| {
+            steps.lock().unwrap().push(step)
+        })
+        .await
+        .unwrap();
+    assert_eq!(steps.into_inner().unwrap(), vec![HandshakeStep::WritingP2p]);
+    let event = p2p_rx.recv().await.unwrap();
+    assert_eq!(event.p2p_info.ssid, "DIRECT-split");
+
+    // 忙碌的接收端在第一步就被拒绝
+    let (gatt, _p2p_rx) = LoopbackGattBackend::new(security);
+    let client = BleClient::with_backend(Box::new(gatt.with_state(ReceiverState::Busy)));
+    assert!(matches!(
+        client.begin_handshake(LOOPBACK_RECEIVER_MAC, |_| {}).await,
+        Err(BleClientError::DeviceBusy)
+    ));
+}
+
+/// 反向上传：接收完成后发送端在同一会话内把文件推回接收端
+#[tokio::test]
+async fn test_push_back_over_loopback() {
+    let input_dir = temp_dir("push-send");
+    let output_dir = temp_dir("push-recv");
+    let input = input_dir.join("hello.txt");
+    let reply = input_dir.join("reply.txt");
+    std::fs::write(&input, b"hello").unwrap();
+    std::fs::write(&reply, b"reply").unwrap();
+
+    // 对端需要事先知道上传端口，先找一个空闲端口
+    let upload_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let (sender, _) = loopback_pair(output_dir.clone(), security.clone(), gatt);
+    let receiver = Receiver::new(ReceiveOptions {
+        output_dir: output_dir.clone(),
+        auto_accept: true,
+        use_tls: false,
+        upload_port: Some(upload_port),
+        upload_idle_timeout: Duration::from_secs(2),
+        ..Default::default()
+    })
+    .unwrap()
+    .with_wifi_backend(Arc::new(LoopbackWifiBackend))
+    .with_security(security);
+
+    let receive = async {
+        let event = p2p_rx.recv().await.expect("sender never wrote P2P info");
+        let (callback, _events) = SimpleReceiveCallback::new(true);
+        receiver.handle_p2p_event(event, &callback).await
+    };
+
+    let (callback, _events) = SimpleSendCallback::new();
+    let send = async {
+        sender
+            .send_to_device(&loopback_device(), vec![input], &callback)
+            .await?;
+        sender
+            .push_files("127.0.0.1", upload_port, &[reply], &callback)
+            .await
+    };
+
+    let (sent, received) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(send, receive)
+    })
+    .await
+    .expect("loopback exchange timed out");
+
+    sent.unwrap();
+    let files = received.unwrap();
+    assert_eq!(
+        files,
+        vec![output_dir.join("hello.txt"), output_dir.join("reply.txt")]
+    );
+    assert_eq!(std::fs::read(&files[1]).unwrap(), b"reply");
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 反向模式：接收端建组并通过通知发回 P2P 信息，发送端加入后上传
+#[tokio::test]
+async fn test_join_receiver_group_over_loopback() {
+    let input_dir = temp_dir("join-send");
+    let output_dir = temp_dir("join-recv");
+    let input = input_dir.join("joined.txt");
+    std::fs::write(&input, b"joined").unwrap();
+
+    let (upload, mut uploaded) = UploadServer::new(output_dir.clone()).start().await.unwrap();
+    let group = P2pInfo::new(
+        "DIRECT-phone".to_string(),
+        "phonepsk".to_string(),
+        LOOPBACK_RECEIVER_MAC.to_string(),
+        upload.port() as i32,
+    );
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security);
+    let sender = Sender::new(SendOptions {
+        transfer_mode: TransferMode::JoinReceiver,
+        retry: RetryPolicy::none(),
+        ..Default::default()
+    })
+    .unwrap()
+    .with_wifi_backend(Arc::new(LoopbackWifiBackend))
+    .with_ble_backend(Arc::new(gatt.with_group_owner(group)));
+
+    let (callback, _events) = SimpleSendCallback::new();
+    tokio::time::timeout(
+        Duration::from_secs(30),
+        sender.send_to_device(&loopback_device(), vec![input], &callback),
+    )
+    .await
+    .expect("join exchange timed out")
+    .unwrap();
+
+    let saved = uploaded.recv().await.unwrap();
+    assert_eq!(saved, output_dir.join("joined.txt"));
+    assert_eq!(std::fs::read(&saved).unwrap(), b"joined");
+    // 建组请求不会交给普通的接收流程
+    assert!(p2p_rx.try_recv().is_err());
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 会话中接收方总是接受
+struct AcceptAll;
+
+impl ReceiverCallback for AcceptAll {
+    fn on_send_request(&self, _request: &SendRequest) -> bool {
+        true
+    }
+    fn on_progress(&self, _received: u64, _total: u64) {}
+    fn on_complete(&self, _files: Vec<PathBuf>) {}
+    fn on_error(&self, _error: String) {}
+}
+
+/// 双向会话：链路建立后两端各发起一次传输
+#[tokio::test]
+async fn test_session_over_loopback() {
+    let input_dir = temp_dir("session-send");
+    let sender_out = temp_dir("session-sender-out");
+    let receiver_out = temp_dir("session-receiver-out");
+    std::fs::write(input_dir.join("ping.txt"), b"ping").unwrap();
+    std::fs::write(input_dir.join("pong.txt"), b"pong").unwrap();
+
+    let security = Arc::new(BleSecurityPersistent::new().unwrap());
+    let (gatt, mut p2p_rx) = LoopbackGattBackend::new(security.clone());
+    let (sender, receiver) = loopback_pair(receiver_out.clone(), security, gatt);
+
+    let (send_callback, _send_events) = SimpleSendCallback::new();
+    let (receive_callback, _receive_events) = SimpleReceiveCallback::new(true);
+    let open_receiver = async {
+        let event = p2p_rx.recv().await.expect("sender never wrote P2P info");
+        receiver.open_session(event, &receive_callback).await
+    };
+    let device = loopback_device();
+    let (host, guest) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(sender.open_session(&device, &send_callback), open_receiver)
+    })
+    .await
+    .expect("session setup timed out");
+    let (mut host, mut guest) = (host.unwrap(), guest.unwrap());
+
+    // 发送端 → 接收端
+    let receive = async {
+        let incoming = guest.incoming().next().await.unwrap();
+        incoming.accept(receiver_out.clone(), &AcceptAll).await
+    };
+    let (sent, received) = tokio::join!(host.send(vec![input_dir.join("ping.txt")]), receive);
+    sent.unwrap();
+    assert_eq!(received.unwrap(), vec![receiver_out.join("ping.txt")]);
+
+    // 接收端 → 发送端，同一连接
+    let receive = async {
+        let incoming = host.incoming().next().await.unwrap();
+        incoming.accept(sender_out.clone(), &AcceptAll).await
+    };
+    let (sent, received) = tokio::join!(guest.send(vec![input_dir.join("pong.txt")]), receive);
+    sent.unwrap();
+    assert_eq!(received.unwrap(), vec![sender_out.join("pong.txt")]);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(sender_out);
+    let _ = std::fs::remove_dir_all(receiver_out);
+}
+
+/// 信息优先流程：接收端先取 `/info`，发起版本协商，凭推送的令牌下载
+#[tokio::test]
+async fn test_info_first_transfer() {
+    use cattysend_core::transfer::{PROTOCOL_V2, TransferServer, TransferStatus, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient};
+
+    let input_dir = temp_dir("info-first-send");
+    let output_dir = temp_dir("info-first-recv");
+    let path = input_dir.join("hello.txt");
+    std::fs::write(&path, b"hello").unwrap();
+
+    let task = TransferTask {
+        task_id: "info-first".to_string(),
+        files: vec![FileEntry::from_path(&path).await.unwrap()],
+        sender_id: "0000".to_string(),
+        sender_name: "loopback".to_string(),
+        verification_code: Default::default(),
+        auth_token: None,
+        tls: None,
+        sync: None,
+    };
+    let mut server = TransferServer::new(task).with_max_version(PROTOCOL_V2);
+    let port = server.start().await.unwrap();
+    let mut status = server.subscribe_status_async().await;
+
+    let client = ReceiverClient::new("127.0.0.1", port, output_dir.clone()).with_tls(false);
+    assert_eq!(client.detect_protocol().await, PROTOCOL_V2);
+
+    let files = tokio::time::timeout(Duration::from_secs(30), client.start(&AcceptAll))
+        .await
+        .expect("transfer timed out")
+        .unwrap();
+    assert_eq!(files, vec![output_dir.join("hello.txt")]);
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"hello");
+
+    // 没有令牌的下载被拒绝
+    let download = format!("http://127.0.0.1:{}/download?taskId=info-first", port);
+    let response = reqwest::get(&download).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FORBIDDEN);
+
+    loop {
+        match status.recv().await.unwrap() {
+            TransferStatus::Completed => break,
+            TransferStatus::Failed(e) => panic!("transfer failed: {}", e),
+            _ => {}
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 带访问令牌的任务：没有令牌的 WebSocket 和下载请求都被拒绝
+#[tokio::test]
+async fn test_auth_token_required() {
+    use cattysend_core::transfer::{TransferServer, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient};
+
+    let input_dir = temp_dir("auth-token-send");
+    let output_dir = temp_dir("auth-token-recv");
+    let path = input_dir.join("secret.txt");
+    std::fs::write(&path, b"secret").unwrap();
+
+    let task = TransferTask {
+        task_id: "auth-token".to_string(),
+        files: vec![FileEntry::from_path(&path).await.unwrap()],
+        sender_id: "0000".to_string(),
+        sender_name: "loopback".to_string(),
+        verification_code: Default::default(),
+        auth_token: Some("s3cret".to_string()),
+        tls: None,
+        sync: None,
+    };
+    let mut server = TransferServer::new(task);
+    let port = server.start().await.unwrap();
+
+    // 猜中任务 ID 也不能下载
+    let download = format!("http://127.0.0.1:{}/download?taskId=auth-token", port);
+    let response = reqwest::get(&download).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let response = reqwest::Client::new()
+        .get(&download)
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let client = ReceiverClient::new("127.0.0.1", port, output_dir.clone()).with_tls(false);
+    assert!(client.connect().await.is_err());
+
+    let client = client.with_auth_token(Some("s3cret".to_string()));
+    let files = tokio::time::timeout(Duration::from_secs(30), client.start(&AcceptAll))
+        .await
+        .expect("transfer timed out")
+        .unwrap();
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"secret");
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 一个服务器先后提供多个任务：无令牌的接收端拿到最早的无令牌任务，带令牌的拿到自己的任务
+#[tokio::test]
+async fn test_server_serves_multiple_tasks() {
+    use cattysend_core::transfer::{TransferServer, TransferStatus, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient};
+
+    let input_dir = temp_dir("multi-task-send");
+    async fn task(dir: &std::path::Path, id: &str, auth_token: Option<&str>) -> TransferTask {
+        let path = dir.join(format!("{}.txt", id));
+        std::fs::write(&path, id).unwrap();
+        TransferTask {
+            task_id: id.to_string(),
+            files: vec![FileEntry::from_path(&path).await.unwrap()],
+            sender_id: "0000".to_string(),
+            sender_name: "loopback".to_string(),
+            verification_code: Default::default(),
+            auth_token: auth_token.map(str::to_string),
+            tls: None,
+            sync: None,
+        }
+    }
+
+    let mut server = TransferServer::new(task(&input_dir, "first", None).await);
+    let port = server.start().await.unwrap();
+    let mut first_status = server.subscribe_status_async().await;
+    let mut second_status = server
+        .add_task(task(&input_dir, "second", Some("s3cret")).await)
+        .await
+        .unwrap();
+    assert!(
+        server
+            .add_task(task(&input_dir, "first", None).await)
+            .await
+            .is_err()
+    );
+
+    let first_out = temp_dir("multi-task-first");
+    let client = ReceiverClient::new("127.0.0.1", port, first_out.clone()).with_tls(false);
+    let files = tokio::time::timeout(Duration::from_secs(30), client.start(&AcceptAll))
+        .await
+        .expect("transfer timed out")
+        .unwrap();
+    assert_eq!(files, vec![first_out.join("first.txt")]);
+
+    let second_out = temp_dir("multi-task-second");
+    let client = ReceiverClient::new("127.0.0.1", port, second_out.clone())
+        .with_tls(false)
+        .with_auth_token(Some("s3cret".to_string()));
+    let files = tokio::time::timeout(Duration::from_secs(30), client.start(&AcceptAll))
+        .await
+        .expect("transfer timed out")
+        .unwrap();
+    assert_eq!(files, vec![second_out.join("second.txt")]);
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"second");
+
+    for status in [&mut first_status, &mut second_status] {
+        loop {
+            match status.recv().await.unwrap() {
+                TransferStatus::Completed => break,
+                TransferStatus::Failed(e) => panic!("transfer failed: {}", e),
+                _ => {}
+            }
+        }
+    }
+
+    // 移除后的任务不再提供；没有令牌的请求无从得知任务是否存在
+    assert!(server.remove_task("first").await);
+    assert_eq!(server.task_ids().await, vec!["second".to_string()]);
+    let download = format!("http://127.0.0.1:{}/download?taskId=first", port);
+    let response = reqwest::get(&download).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let response = reqwest::Client::new()
+        .get(&download)
+        .bearer_auth("s3cret")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(first_out);
+    let _ = std::fs::remove_dir_all(second_out);
+}
+
+/// 同时到来的两个 WebSocket 连接不会选中同一个任务
+#[tokio::test]
+async fn test_concurrent_sessions_claim_distinct_tasks() {
+    use cattysend_core::transfer::{TransferServer, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient};
+
+    let input_dir = temp_dir("claim-send");
+    let mut tasks = Vec::new();
+    for id in ["a", "b"] {
+        let path = input_dir.join(format!("{}.txt", id));
+        std::fs::write(&path, id).unwrap();
+        tasks.push(TransferTask {
+            task_id: id.to_string(),
+            files: vec![FileEntry::from_path(&path).await.unwrap()],
+            sender_id: "0000".to_string(),
+            sender_name: "loopback".to_string(),
+            verification_code: Default::default(),
+            auth_token: None,
+            tls: None,
+            sync: None,
+        });
+    }
+    let second = tasks.pop().unwrap();
+    let mut server = TransferServer::new(tasks.pop().unwrap());
+    let port = server.start().await.unwrap();
+    server.add_task(second).await.unwrap();
+
+    // 跳过 `/info`，只比较 WebSocket 选中的任务
+    async fn receive(port: u16, output_dir: PathBuf) -> Vec<PathBuf> {
+        let client = ReceiverClient::new("127.0.0.1", port, output_dir).with_tls(false);
+        let ws_stream = client.connect().await.unwrap();
+        client.receive(ws_stream, &AcceptAll).await.unwrap()
+    }
+    let (a_out, b_out) = (temp_dir("claim-a"), temp_dir("claim-b"));
+    let (a, b) = tokio::time::timeout(Duration::from_secs(30), async {
+        tokio::join!(receive(port, a_out.clone()), receive(port, b_out.clone()))
+    })
+    .await
+    .expect("transfer timed out");
+    let mut names: Vec<_> = a
+        .iter()
+        .chain(&b)
+        .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["a.txt", "b.txt"]);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(a_out);
+    let _ = std::fs::remove_dir_all(b_out);
+}
+
+/// 传输路由挂在宿主程序自己的 axum 应用中，与宿主的路由共用端口
+#[tokio::test]
+async fn test_router_embedded_in_host_app() {
+    use axum::routing::get;
+    use cattysend_core::transfer::{TransferServer, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient};
+    use std::net::SocketAddr;
+
+    let input_dir = temp_dir("embedded-send");
+    let output_dir = temp_dir("embedded-recv");
+    let path = input_dir.join("hello.txt");
+    std::fs::write(&path, b"hello").unwrap();
+
+    let task = TransferTask {
+        task_id: "embedded".to_string(),
+        files: vec![FileEntry::from_path(&path).await.unwrap()],
+        sender_id: "0000".to_string(),
+        sender_name: "loopback".to_string(),
+        verification_code: Default::default(),
+        auth_token: None,
+        tls: None,
+        sync: None,
+    };
+    let router = TransferServer::new(task).into_router().await;
+    let app = axum::Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .merge(router);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let app = app.into_make_service_with_connect_info::<SocketAddr>();
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let health = format!("http://127.0.0.1:{}/health", port);
+    assert_eq!(
+        reqwest::get(&health).await.unwrap().text().await.unwrap(),
+        "ok"
+    );
+
+    let client = ReceiverClient::new("127.0.0.1", port, output_dir.clone()).with_tls(false);
+    let files = tokio::time::timeout(Duration::from_secs(30), client.start(&AcceptAll))
+        .await
+        .expect("transfer timed out")
+        .unwrap();
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"hello");
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// cattysend 之间：固定证书的 HTTPS、逐个下载原始文件、跳过接收端已有的相同文件
+#[tokio::test]
+async fn test_extended_mode_with_pinned_cert() {
+    use cattysend_core::transfer::{TransferServer, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient, TlsIdentity};
+
+    let input_dir = temp_dir("extended-send");
+    let output_dir = temp_dir("extended-recv");
+    for (name, content) in [("same.txt", "unchanged"), ("new.txt", "fresh")] {
+        std::fs::write(input_dir.join(name), content).unwrap();
+    }
+    std::fs::write(output_dir.join("same.txt"), "unchanged").unwrap();
+
+    let identity = Arc::new(TlsIdentity::generate().unwrap());
+    let fingerprint = identity.fingerprint();
+    let mut files = Vec::new();
+    for name in ["same.txt", "new.txt"] {
+        files.push(FileEntry::from_path(&input_dir.join(name)).await.unwrap());
+    }
+    let task = TransferTask {
+        task_id: "extended".to_string(),
+        files,
+        sender_id: "0000".to_string(),
+        sender_name: "loopback".to_string(),
+        verification_code: Default::default(),
+        auth_token: Some("s3cret".to_string()),
+        tls: Some(identity),
+        sync: None,
+    };
+    let mut server = TransferServer::new(task);
+    let port = server.start().await.unwrap();
+
+    // 指纹不符的证书在发送令牌之前就被拒绝
+    let wrong = ReceiverClient::new("127.0.0.1", port, output_dir.clone())
+        .with_auth_token(Some("s3cret".to_string()))
+        .with_pinned_cert(Some("00".repeat(32)));
+    assert!(wrong.connect().await.is_err());
+
+    let client = ReceiverClient::new("127.0.0.1", port, output_dir.clone())
+        .with_tls(false)
+        .with_auth_token(Some("s3cret".to_string()))
+        .with_pinned_cert(Some(fingerprint));
+    let mut files = tokio::time::timeout(Duration::from_secs(30), client.start(&AcceptAll))
+        .await
+        .expect("transfer timed out")
+        .unwrap();
+    files.sort();
+    assert_eq!(
+        files,
+        vec![output_dir.join("new.txt"), output_dir.join("same.txt")]
+    );
+    assert_eq!(std::fs::read_to_string(&files[0]).unwrap(), "fresh");
+    // 相同的文件没有重新下载，也没有生成 "same (1).txt"
+    assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 2);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+    let _ = std::fs::remove_dir_all(output_dir);
+}
+
+/// 同一 IP 的 WebSocket 占满连接名额后，其他请求返回 429，断开后恢复
+#[tokio::test]
+async fn test_connection_limit() {
+    use cattysend_core::transfer::{ConnectionLimits, TransferServer, TransferTask};
+    use cattysend_core::{FileEntry, ReceiverClient};
+
+    let input_dir = temp_dir("limit-send");
+    let path = input_dir.join("hello.txt");
+    std::fs::write(&path, b"hello").unwrap();
+
+    let task = TransferTask {
+        task_id: "limit".to_string(),
+        files: vec![FileEntry::from_path(&path).await.unwrap()],
+        sender_id: "0000".to_string(),
+        sender_name: "loopback".to_string(),
+        verification_code: Default::default(),
+        auth_token: None,
+        tls: None,
+        sync: None,
+    };
+    let mut server = TransferServer::new(task).with_limits(ConnectionLimits {
+        per_ip: 1,
+        downloads: 1,
+    });
+    let port = server.start().await.unwrap();
+    let info = format!("http://127.0.0.1:{}/info", port);
+
+    let client = ReceiverClient::new("127.0.0.1", port, input_dir.clone()).with_tls(false);
+    let ws = client.connect().await.unwrap();
+    let response = reqwest::get(&info).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+
+    drop(ws);
+    // 服务端在读到连接关闭后才归还名额
+    let mut status = reqwest::StatusCode::TOO_MANY_REQUESTS;
+    for _ in 0..50 {
+        status = reqwest::get(&info).await.unwrap().status();
+        if status.is_success() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(status.is_success(), "{}", status);
+
+    let _ = std::fs::remove_dir_all(input_dir);
+}
@@ -0,0 +1,167 @@
+//! 传输协议一致性测试
+//!
+//! `tests/fixtures/catshare/` 下每个文件是一帧 WebSocket 消息，覆盖 CatShare 的
+//! sendRequest 各种变体、status 1/2/3 和版本协商。本端发出的 JSON 是紧凑格式、
+//! 键按字典序排列；CatShare 按键名读取，键的顺序不影响互通，
+//! 所以夹具统一按本端的顺序保存，逐字节比较。
+//!
+//! `send_request_wire_order.txt` 保留了 CatShare 发出时的键顺序，只要求语义一致。
+
+use cattysend_core::transfer::protocol::{Capabilities, PROTOCOL_V2, SendRequest, WsMessage};
+use serde_json::json;
+
+macro_rules! fixture {
+    ($name:literal) => {
+        (
+            $name,
+            include_str!(concat!("fixtures/catshare/", $name, ".txt")).trim_ascii_end(),
+        )
+    };
+}
+
+/// 按本端格式保存、需要逐字节往返的帧
+const FRAMES: &[(&str, &str)] = &[
+    fixture!("version_negotiation"),
+    fixture!("version_negotiation_ack"),
+    fixture!("version_negotiation_v2"),
+    fixture!("version_negotiation_cattysend"),
+    fixture!("send_request_single"),
+    fixture!("send_request_multi"),
+    fixture!("send_request_text"),
+    fixture!("send_request_id_only"),
+    fixture!("send_request_cattysend"),
+    fixture!("send_request_ack"),
+    fixture!("status_complete"),
+    fixture!("status_failed"),
+    fixture!("status_rejected"),
+    fixture!("status_ack"),
+];
+
+fn frame(name: &str) -> &'static str {
+    FRAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, text)| *text)
+        .unwrap_or_else(|| panic!("no fixture named {}", name))
+}
+
+fn send_request(name: &str) -> SendRequest {
+    let msg = WsMessage::try_parse(frame(name)).unwrap();
+    assert_eq!(
+        (msg.msg_type.as_str(), msg.name.as_str()),
+        ("action", "sendRequest")
+    );
+    serde_json::from_value(msg.payload.unwrap()).unwrap()
+}
+
+#[test]
+fn test_frames_round_trip_byte_for_byte() {
+    for (name, text) in FRAMES {
+        let msg = WsMessage::try_parse(text).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        msg.validate().unwrap();
+        assert_eq!(msg.to_string(), *text, "{} does not round-trip", name);
+    }
+}
+
+#[test]
+fn test_version_negotiation_frames() {
+    assert_eq!(
+        WsMessage::version_negotiation(0).to_string(),
+        frame("version_negotiation")
+    );
+    assert_eq!(
+        WsMessage::receiver_negotiation(0, PROTOCOL_V2).to_string(),
+        frame("version_negotiation_v2")
+    );
+    // cattysend 之间附带扩展能力，CatShare 按键名读取时看不到差别
+    let ours = WsMessage::version_negotiation(0).with_capabilities(&Capabilities::default());
+    assert_eq!(ours.to_string(), frame("version_negotiation_cattysend"));
+    let parsed = WsMessage::try_parse(frame("version_negotiation_cattysend")).unwrap();
+    assert_eq!(parsed.payload.as_ref().unwrap()["version"], 1);
+    assert_eq!(parsed.capabilities(), Some(Capabilities::default()));
+
+    // 接收端对发送端协商的回复
+    let ack = WsMessage::ack(
+        0,
+        "versionNegotiation",
+        Some(json!({ "version": 1, "threadLimit": 5 })),
+    );
+    assert_eq!(ack.to_string(), frame("version_negotiation_ack"));
+    assert_eq!(
+        WsMessage::ack(1, "sendRequest", None).to_string(),
+        frame("send_request_ack")
+    );
+}
+
+#[test]
+fn test_status_frames() {
+    let task_id = "1704081600000";
+    for (name, id, status_type, reason) in [
+        ("status_complete", 2, 1, "ok"),
+        ("status_failed", 2, 2, "download failed"),
+        ("status_rejected", 1, 3, "user refuse"),
+    ] {
+        let msg = WsMessage::status(id, task_id, status_type, reason);
+        assert_eq!(msg.to_string(), frame(name), "{}", name);
+
+        let parsed = WsMessage::try_parse(frame(name)).unwrap();
+        assert_eq!(parsed, msg);
+        assert_eq!(parsed.payload.unwrap()["type"], status_type);
+    }
+    assert_eq!(
+        WsMessage::ack(2, "status", None).to_string(),
+        frame("status_ack")
+    );
+}
+
+#[test]
+fn test_send_request_variants() {
+    let single = send_request("send_request_single");
+    assert_eq!(single.get_task_id(), "1704081600000");
+    assert_eq!(single.get_sender_id(), "a1b2");
+    assert_eq!(single.sender_name, "Xiaomi 14");
+    assert_eq!(single.file_count, 1);
+    assert_eq!(single.total_size, 2457600);
+    assert!(single.files.is_empty());
+    assert!(single.thumbnail.is_none() && single.cat_share_text.is_none());
+
+    let multi = send_request("send_request_multi");
+    assert_eq!(multi.file_count, 3);
+    assert_eq!(multi.mime_type, "*/*");
+    assert_eq!(multi.thumbnail.as_deref(), Some("iVBORw0KGgo="));
+
+    let text = send_request("send_request_text");
+    assert_eq!(
+        text.cat_share_text.as_deref(),
+        Some("https://example.com/?a=1&b=2")
+    );
+
+    // 旧版本只带 id，没有 taskId 和 senderId
+    let id_only = send_request("send_request_id_only");
+    assert_eq!(id_only.task_id, None);
+    assert_eq!(id_only.get_task_id(), "1704081900000");
+    assert_eq!(id_only.get_sender_id(), "unknown");
+
+    // 本端发出的扩展字段
+    let ours = send_request("send_request_cattysend");
+    assert_eq!(ours.files.len(), 2);
+    assert_eq!(ours.files[0].mode, Some(0o100644));
+    assert_eq!(ours.files[1].mode, None);
+    assert_eq!(
+        ours.files.iter().map(|f| f.size).sum::<u64>(),
+        ours.total_size
+    );
+    assert_eq!(ours.verification_code.as_deref(), Some("4821"));
+    assert_eq!(ours.download_port, None);
+}
+
+#[test]
+fn test_wire_order_capture_is_equivalent() {
+    let raw = include_str!("fixtures/catshare/send_request_wire_order.txt").trim_ascii_end();
+    let msg = WsMessage::try_parse(raw).unwrap();
+    assert_eq!(
+        msg,
+        WsMessage::try_parse(frame("send_request_single")).unwrap()
+    );
+    assert_eq!(msg.to_string(), frame("send_request_single"));
+}
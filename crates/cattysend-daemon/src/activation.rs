@@ -0,0 +1,55 @@
+//! systemd socket 激活（`sd_listen_fds` 协议）
+//!
+//! 由 `cattysend.socket` 启动时，systemd 已经创建并监听 IPC socket，
+//! 以 fd 3 传给守护进程，并设置 `LISTEN_PID`、`LISTEN_FDS`。
+
+use std::os::fd::{FromRawFd, RawFd};
+use std::os::unix::net::UnixListener;
+
+/// systemd 传入的第一个 fd（`SD_LISTEN_FDS_START`）
+const LISTEN_FDS_START: RawFd = 3;
+
+/// 取出 systemd 传入的 IPC socket；不是由 socket 激活启动时返回 None
+///
+/// `LISTEN_*` 环境变量会被子进程继承，但 `LISTEN_PID` 与它们的 PID 不符，不会误用。
+pub fn take_listener() -> std::io::Result<Option<UnixListener>> {
+    let pid = std::env::var("LISTEN_PID").ok();
+    let fds = std::env::var("LISTEN_FDS").ok();
+    if !passed_to(pid.as_deref(), fds.as_deref(), std::process::id()) {
+        return Ok(None);
+    }
+
+    // SAFETY: LISTEN_PID 指向本进程，fd 3 由 systemd 传入，且只在这里取用一次
+    let inherited = unsafe { UnixListener::from_raw_fd(LISTEN_FDS_START) };
+    // 传入的 fd 没有 CLOEXEC，复制一份（带 CLOEXEC）后关闭原 fd，
+    // 避免泄漏给 nmcli、wpa_cli 等子进程
+    let listener = inherited.try_clone()?;
+    drop(inherited);
+    listener.set_nonblocking(true)?;
+    Ok(Some(listener))
+}
+
+/// `LISTEN_PID` 是 `own_pid` 且至少传入了一个 fd
+fn passed_to(pid: Option<&str>, fds: Option<&str>, own_pid: u32) -> bool {
+    pid.and_then(|p| p.parse::<u32>().ok()) == Some(own_pid)
+        && fds
+            .and_then(|n| n.parse::<u32>().ok())
+            .is_some_and(|n| n >= 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_passed_to() {
+        assert!(passed_to(Some("42"), Some("1"), 42));
+        assert!(passed_to(Some("42"), Some("2"), 42));
+        // 其他进程的（被继承的环境变量）
+        assert!(!passed_to(Some("41"), Some("1"), 42));
+        assert!(!passed_to(Some("42"), Some("0"), 42));
+        assert!(!passed_to(None, Some("1"), 42));
+        assert!(!passed_to(Some("42"), None, 42));
+        assert!(!passed_to(Some("x"), Some("1"), 42));
+    }
+}
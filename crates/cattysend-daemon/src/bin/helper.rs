@@ -0,0 +1,9 @@
+//! Cattysend 特权助手
+//!
+//! 以 `setcap cap_net_admin+ep` 或 setuid root 安装，代替不带特权的守护进程、TUI 和 GUI
+//! 执行创建 P2P 组、热点隔离等网络命令。只接受固定的几种请求，协议见
+//! [`cattysend_core::wifi::helper`]。
+
+fn main() -> std::process::ExitCode {
+    cattysend_core::wifi::helper::serve_stdio()
+}
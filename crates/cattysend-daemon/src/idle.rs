@@ -0,0 +1,110 @@
+//! 空闲计时
+//!
+//! 由 socket 激活启动时，守护进程在一段时间内既没有 IPC 连接、也没有接收会话
+//! 就退出，下次有客户端连接时 systemd 会重新启动它。
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// 活动计数和最近一次活动结束的时间
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+    active: usize,
+    last: Instant,
+}
+
+/// 空闲计时器，可在多个任务间共享
+#[derive(Debug)]
+pub struct IdleTimer {
+    state: watch::Sender<Activity>,
+}
+
+/// 活动进行中的标记，drop 时结束活动并重新开始计时
+#[derive(Debug)]
+pub struct BusyGuard {
+    timer: Arc<IdleTimer>,
+}
+
+impl IdleTimer {
+    pub fn new() -> Arc<Self> {
+        let (state, _) = watch::channel(Activity {
+            active: 0,
+            last: Instant::now(),
+        });
+        Arc::new(Self { state })
+    }
+
+    /// 开始一段活动（IPC 连接、接收会话），返回的守卫存活期间不算空闲
+    pub fn busy(self: &Arc<Self>) -> BusyGuard {
+        self.state.send_modify(|a| a.active += 1);
+        BusyGuard {
+            timer: Arc::clone(self),
+        }
+    }
+
+    /// 连续 `timeout` 没有任何活动后返回
+    pub async fn wait_idle(&self, timeout: Duration) {
+        let mut rx = self.state.subscribe();
+        loop {
+            let Activity { active, last } = *rx.borrow_and_update();
+            if active > 0 {
+                if rx.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep_until(last + timeout) => return,
+                res = rx.changed() => {
+                    if res.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for BusyGuard {
+    fn drop(&mut self) {
+        self.timer.state.send_modify(|a| {
+            a.active -= 1;
+            a.last = Instant::now();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wait_idle() {
+        let timer = IdleTimer::new();
+        let timeout = Duration::from_millis(50);
+
+        // 有活动时不会空闲
+        let guard = timer.busy();
+        let idle = tokio::time::timeout(Duration::from_millis(150), timer.wait_idle(timeout));
+        assert!(idle.await.is_err());
+
+        // 活动结束后从结束时刻开始计时
+        drop(guard);
+        let started = Instant::now();
+        timer.wait_idle(timeout).await;
+        assert!(started.elapsed() >= timeout);
+
+        // 计时期间开始新的活动会打断计时
+        let waiter = tokio::spawn({
+            let timer = Arc::clone(&timer);
+            async move { timer.wait_idle(timeout).await }
+        });
+        let guard = timer.busy();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!waiter.is_finished());
+        drop(guard);
+        waiter.await.unwrap();
+    }
+}
@@ -1,8 +1,18 @@
 //! IPC Server - Unix Domain Socket 通信
 
+use crate::log_stream::LogStream;
+use crate::scheduler::{self, ScheduledSend};
+use crate::service::{SendJob, Service};
 use anyhow::Result;
+use cattysend_core::schedule::DEFAULT_WINDOW_MINS;
+use cattysend_core::{
+    DeviceIdentity, DeviceMatch, DiscoveredDevice, LogEntry, LogLevel, PowerState, ScanOptions,
+    SyncPlan, TimeOfDay,
+};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
 
@@ -19,32 +29,172 @@ pub enum IpcRequest {
     #[serde(rename = "status")]
     Status,
     #[serde(rename = "scan")]
-    Scan { timeout_secs: u64 },
+    Scan {
+        timeout_secs: u64,
+        /// 被动扫描（见 [`cattysend_core::ScanOptions::passive`]）
+        #[serde(default)]
+        passive: bool,
+        /// 找到第一个设备就结束（见 [`cattysend_core::ScanOptions::stop_on_first`]）
+        #[serde(default)]
+        stop_on_first: bool,
+        /// 把原始广播追加到该文件（绝对路径），见 [`cattysend_core::ble::capture`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        capture: Option<String>,
+    },
     #[serde(rename = "send")]
     Send {
-        file_path: String,
+        /// 要发送的文件（绝对路径，守护进程的工作目录与客户端不同）
+        file_paths: Vec<String>,
+        device_addr: Option<String>,
+        /// 不立即发送，在下一个该时刻（本地时间）发送，见 [`crate::scheduler`]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        at: Option<TimeOfDay>,
+        /// 跳过电池电量检查，见 [`cattysend_core::power`]
+        #[serde(default)]
+        force: bool,
+    },
+    /// 把目录同步到另一台 cattysend（见 [`cattysend_core::sync`]）
+    #[serde(rename = "sync")]
+    Sync {
+        /// 要同步的目录（绝对路径）
+        dir: String,
         device_addr: Option<String>,
+        /// 删除接收端目录中本地已没有的文件
+        #[serde(default)]
+        delete: bool,
+        /// 只返回会有的改动（`IpcResponse::SyncPlan`），不传输
+        #[serde(default)]
+        dry_run: bool,
+        /// 跳过电池电量检查
+        #[serde(default)]
+        force: bool,
     },
     #[serde(rename = "receive")]
-    Receive,
+    Receive {
+        #[serde(default)]
+        output_dir: Option<String>,
+        /// 可发现窗口时长（秒），None 表示一直广播
+        #[serde(default)]
+        window_secs: Option<u64>,
+    },
     #[serde(rename = "stop")]
     Stop,
+    /// 暂停当前接收会话的下载
+    #[serde(rename = "pause")]
+    Pause,
+    /// 恢复已暂停的下载
+    #[serde(rename = "resume")]
+    Resume,
+    /// 订阅事件流，之后连接上会持续收到 `IpcResponse::Event`
+    #[serde(rename = "subscribe")]
+    Subscribe,
+    /// 本机身份（公钥指纹、sender ID、广播名称和厂商），供 UI 显示指纹或二维码
+    #[serde(rename = "identity")]
+    Identity,
+    /// 订阅日志，之后连接上会持续收到 `level` 及更重要的 `IpcResponse::Log`
+    #[serde(rename = "logs")]
+    Logs {
+        #[serde(default = "default_log_level")]
+        level: LogLevel,
+    },
+}
+
+fn default_log_level() -> LogLevel {
+    LogLevel::Info
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "type")]
 pub enum IpcResponse {
     #[serde(rename = "ok")]
-    Ok { message: String },
+    Ok {
+        message: String,
+        /// 请求启动的会话（目前只有 `send` 返回），客户端据此过滤事件流
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+    },
     #[serde(rename = "error")]
     Error { message: String },
     #[serde(rename = "devices")]
     Devices { devices: Vec<DeviceInfo> },
+    /// `send` 指定的设备匹配到多个结果，需要客户端选择后用地址重试
+    #[serde(rename = "ambiguous")]
+    Ambiguous { devices: Vec<DeviceInfo> },
     #[serde(rename = "status")]
     Status {
         state: String,
         progress: Option<f32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        discoverable_remaining_secs: Option<u64>,
+        /// 电源状态，读取不到 UPower 时为 None
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        power: Option<PowerState>,
     },
+    #[serde(rename = "identity")]
+    Identity { identity: DeviceIdentity },
+    /// `sync` 的 dry-run 结果
+    #[serde(rename = "sync_plan")]
+    SyncPlan { plan: SyncPlan },
+    #[serde(rename = "event")]
+    Event {
+        /// 事件所属的会话（接收工作流开始后才有）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_id: Option<String>,
+        event: DaemonEvent,
+    },
+    #[serde(rename = "log")]
+    Log { entry: LogEntry },
+}
+
+/// 守护进程事件，通过 `subscribe` 推送给 UI
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind")]
+pub enum DaemonEvent {
+    /// 可发现窗口倒计时（每秒一次）
+    #[serde(rename = "discoverable")]
+    Discoverable {
+        remaining_secs: u64,
+        total_secs: u64,
+    },
+    /// 可发现窗口结束（超时、被停止或传输已开始）
+    #[serde(rename = "discoverable_ended")]
+    DiscoverableEnded,
+    #[serde(rename = "status")]
+    Status { message: String },
+    /// 扫描完成（任意客户端发起的扫描都会推送）
+    #[serde(rename = "devices")]
+    Devices { devices: Vec<DeviceInfo> },
+    /// 收到发送端的传输请求
+    #[serde(rename = "request")]
+    Request {
+        sender_name: String,
+        file_name: String,
+        file_count: u32,
+        total_size: u64,
+    },
+    /// 传输进度（发送时 `received` 为已发送的字节数）
+    #[serde(rename = "progress")]
+    Progress { received: u64, total: u64 },
+    /// 下载已暂停
+    #[serde(rename = "paused")]
+    Paused,
+    /// 下载已恢复
+    #[serde(rename = "resumed")]
+    Resumed,
+    #[serde(rename = "complete")]
+    Complete { files: Vec<String> },
+    #[serde(rename = "error")]
+    Error { message: String },
+    /// 当前的发送和接收被 `stop` 停止
+    #[serde(rename = "cancelled")]
+    Cancelled,
+}
+
+/// 带会话 ID 的守护进程事件（内部广播用）
+#[derive(Debug, Clone)]
+pub struct SessionEvent {
+    pub session_id: Option<String>,
+    pub event: DaemonEvent,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -52,21 +202,57 @@ pub struct DeviceInfo {
     pub name: String,
     pub address: String,
     pub rssi: Option<i16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+}
+
+impl From<DiscoveredDevice> for DeviceInfo {
+    fn from(d: DiscoveredDevice) -> Self {
+        Self {
+            name: d.name,
+            address: d.address,
+            rssi: d.rssi,
+            brand: Some(d.brand),
+        }
+    }
 }
 
-pub async fn run_ipc_server() -> Result<()> {
-    let path = socket_path();
+/// 运行 IPC 服务器
+///
+/// `activated` 为 systemd 传入的 socket（见 [`crate::activation`]），为 None 时自行创建。
+pub async fn run_ipc_server(
+    service: Arc<Service>,
+    activated: Option<std::os::unix::net::UnixListener>,
+    logs: LogStream,
+) -> Result<()> {
+    let listener = match activated {
+        Some(listener) => {
+            tracing::info!("IPC 服务器使用 systemd 传入的 socket");
+            UnixListener::from_std(listener)?
+        }
+        None => {
+            let path = socket_path();
 
-    // 删除旧的 socket 文件
-    let _ = std::fs::remove_file(&path);
+            // 删除旧的 socket 文件
+            let _ = std::fs::remove_file(&path);
 
-    let listener = UnixListener::bind(&path)?;
-    tracing::info!("IPC 服务器已启动: {:?}", path);
+            let listener = UnixListener::bind(&path)?;
+            tracing::info!("IPC 服务器已启动: {:?}", path);
+            listener
+        }
+    };
 
     loop {
         match listener.accept().await {
             Ok((stream, _)) => {
-                tokio::spawn(handle_client(stream));
+                // 连接（包括事件订阅）存续期间守护进程不算空闲
+                let busy = service.idle().busy();
+                let service = Arc::clone(&service);
+                let logs = logs.clone();
+                tokio::spawn(async move {
+                    let _busy = busy;
+                    handle_client(stream, service, logs).await
+                });
             }
             Err(e) => {
                 tracing::warn!("接受连接失败: {}", e);
@@ -75,7 +261,7 @@ pub async fn run_ipc_server() -> Result<()> {
     }
 }
 
-async fn handle_client(stream: UnixStream) -> Result<()> {
+async fn handle_client(stream: UnixStream, service: Arc<Service>, logs: LogStream) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
@@ -99,36 +285,187 @@ async fn handle_client(stream: UnixStream) -> Result<()> {
         tracing::debug!("收到请求: {:?}", request);
 
         let response = match request {
-            IpcRequest::Status => IpcResponse::Status {
-                state: "idle".to_string(),
-                progress: None,
-            },
-            IpcRequest::Scan { timeout_secs } => {
+            IpcRequest::Status => {
+                let (state, remaining) = service.status().await;
+                IpcResponse::Status {
+                    state,
+                    progress: None,
+                    discoverable_remaining_secs: remaining,
+                    power: PowerState::try_read().await.ok(),
+                }
+            }
+            IpcRequest::Scan {
+                timeout_secs,
+                passive,
+                stop_on_first,
+                capture,
+            } => {
                 tracing::info!("开始扫描设备 ({}s)...", timeout_secs);
-                // TODO: 调用 cattysend_core::ble::scanner
-                IpcResponse::Devices { devices: vec![] }
+                let options = ScanOptions {
+                    passive,
+                    stop_on_first,
+                    capture: capture.map(PathBuf::from),
+                    ..Default::default()
+                };
+                match service
+                    .scan_with(Duration::from_secs(timeout_secs), options)
+                    .await
+                {
+                    Ok(devices) => IpcResponse::Devices {
+                        devices: devices.into_iter().map(DeviceInfo::from).collect(),
+                    },
+                    Err(e) => IpcResponse::Error {
+                        message: format!("扫描失败: {}", e),
+                    },
+                }
             }
             IpcRequest::Send {
-                file_path,
-                device_addr,
+                file_paths,
+                device_addr: Some(device),
+                at: Some(at),
+                force,
             } => {
-                tracing::info!("发送文件: {} -> {:?}", file_path, device_addr);
-                IpcResponse::Ok {
-                    message: "发送任务已启动".to_string(),
+                let job = ScheduledSend {
+                    name: None,
+                    device,
+                    files: file_paths.into_iter().map(PathBuf::from).collect(),
+                    window: Duration::from_secs(DEFAULT_WINDOW_MINS * 60),
+                    force,
+                };
+                match scheduler::schedule_once(&service, at, job) {
+                    Some(_) => IpcResponse::Ok {
+                        message: format!("已安排在 {} 发送", at),
+                        session_id: None,
+                    },
+                    None => IpcResponse::Error {
+                        message: format!("无法安排在 {} 发送", at),
+                    },
                 }
             }
-            IpcRequest::Receive => {
-                tracing::info!("进入接收模式");
-                IpcResponse::Ok {
-                    message: "接收模式已启动".to_string(),
+            IpcRequest::Send {
+                file_paths,
+                device_addr,
+                at: None,
+                force,
+            } => match resolve_target(&service, device_addr).await {
+                Ok(device) => {
+                    tracing::info!(
+                        "发送文件: {} -> {} ({})",
+                        file_paths.join(", "),
+                        device.name,
+                        device.address
+                    );
+                    let name = device.name.clone();
+                    let files = file_paths.into_iter().map(PathBuf::from).collect();
+                    match service
+                        .start_send(device, SendJob::Files(files), force)
+                        .await
+                    {
+                        Ok(session_id) => IpcResponse::Ok {
+                            message: format!("发送任务已启动: {}", name),
+                            session_id,
+                        },
+                        Err(e) => IpcResponse::Error {
+                            message: format!("无法开始发送: {}", e),
+                        },
+                    }
+                }
+                Err(response) => response,
+            },
+            IpcRequest::Send { at: Some(_), .. } => IpcResponse::Error {
+                message: "定时发送需要指定目标设备".to_string(),
+            },
+            IpcRequest::Sync {
+                dir,
+                device_addr,
+                delete,
+                dry_run,
+                force,
+            } => match resolve_target(&service, device_addr).await {
+                Ok(device) => {
+                    let dir = PathBuf::from(dir);
+                    match service.prepare_sync(&device, dir.clone(), delete).await {
+                        Ok(job) if dry_run => IpcResponse::SyncPlan { plan: job.plan() },
+                        Ok(job) => {
+                            tracing::info!(
+                                "同步目录: {} -> {} ({})",
+                                dir.display(),
+                                device.name,
+                                device.address
+                            );
+                            let name = device.name.clone();
+                            match service.start_send(device, SendJob::Sync(job), force).await {
+                                Ok(session_id) => IpcResponse::Ok {
+                                    message: format!("同步任务已启动: {}", name),
+                                    session_id,
+                                },
+                                Err(e) => IpcResponse::Error {
+                                    message: format!("无法开始同步: {}", e),
+                                },
+                            }
+                        }
+                        Err(e) => IpcResponse::Error {
+                            message: format!("无法读取目录 {}: {}", dir.display(), e),
+                        },
+                    }
+                }
+                Err(response) => response,
+            },
+            IpcRequest::Receive {
+                output_dir,
+                window_secs,
+            } => {
+                tracing::info!("进入接收模式 (窗口: {:?}s)", window_secs);
+                let window = window_secs.map(Duration::from_secs);
+                match service
+                    .start_receive(output_dir.map(PathBuf::from), window)
+                    .await
+                {
+                    Ok(()) => IpcResponse::Ok {
+                        message: match window_secs {
+                            Some(secs) => format!("接收模式已启动，可发现 {}s", secs),
+                            None => "接收模式已启动".to_string(),
+                        },
+                        session_id: None,
+                    },
+                    Err(e) => IpcResponse::Error {
+                        message: format!("无法启动接收模式: {}", e),
+                    },
                 }
             }
             IpcRequest::Stop => {
                 tracing::info!("停止当前任务");
+                let message = if service.stop().await {
+                    "已停止"
+                } else {
+                    "当前没有任务"
+                };
                 IpcResponse::Ok {
-                    message: "已停止".to_string(),
+                    message: message.to_string(),
+                    session_id: None,
                 }
             }
+            IpcRequest::Pause | IpcRequest::Resume => {
+                let paused = matches!(request, IpcRequest::Pause);
+                let message = match (service.set_paused(paused).await, paused) {
+                    (false, _) => "当前没有任务",
+                    (true, true) => "已暂停",
+                    (true, false) => "已恢复",
+                };
+                IpcResponse::Ok {
+                    message: message.to_string(),
+                    session_id: None,
+                }
+            }
+            IpcRequest::Identity => IpcResponse::Identity {
+                identity: service.identity(),
+            },
+            IpcRequest::Subscribe => {
+                return stream_events(writer, service).await;
+            }
+            IpcRequest::Logs { level } => {
+                return stream_logs(writer, logs, level).await;
+            }
         };
 
         writer
@@ -140,3 +477,78 @@ async fn handle_client(stream: UnixStream) -> Result<()> {
 
     Ok(())
 }
+
+/// 按地址、sender_id、名称或收藏别名确定 `send` / `sync` 的目标设备
+///
+/// 找不到或匹配到多个设备时返回给客户端的响应。
+async fn resolve_target(
+    service: &Service,
+    device_addr: Option<String>,
+) -> std::result::Result<DiscoveredDevice, IpcResponse> {
+    let Some(query) = device_addr else {
+        return Err(IpcResponse::Error {
+            message: "无法确定目标设备: 未指定目标设备".to_string(),
+        });
+    };
+    match service.resolve_device(&query).await {
+        Ok(DeviceMatch::Found(device)) => Ok(device),
+        Ok(DeviceMatch::Ambiguous(devices)) => Err(IpcResponse::Ambiguous {
+            devices: devices.into_iter().map(DeviceInfo::from).collect(),
+        }),
+        Ok(DeviceMatch::NotFound) => Err(IpcResponse::Error {
+            message: format!("未找到设备: {}", query),
+        }),
+        Err(e) => Err(IpcResponse::Error {
+            message: format!("无法确定目标设备: {}", e),
+        }),
+    }
+}
+
+/// 把事件流写给订阅者，直到对方断开连接
+async fn stream_events(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    service: Arc<Service>,
+) -> Result<()> {
+    let mut events = service.subscribe();
+    loop {
+        let SessionEvent { session_id, event } = match events.recv().await {
+            Ok(event) => event,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                tracing::debug!("订阅者落后，丢弃 {} 条事件", n);
+                continue;
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        let resp = IpcResponse::Event { session_id, event };
+        writer
+            .write_all(serde_json::to_string(&resp)?.as_bytes())
+            .await?;
+        writer.write_all(b"\n").await?;
+    }
+}
+
+/// 把日志写给附加的 UI，直到对方断开连接
+///
+/// 这里不能再记录日志，否则每条日志都会引出新的日志。
+async fn stream_logs(
+    mut writer: tokio::net::unix::OwnedWriteHalf,
+    logs: LogStream,
+    level: LogLevel,
+) -> Result<()> {
+    let mut entries = logs.subscribe();
+    loop {
+        let entry = match entries.recv().await {
+            Ok(entry) => entry,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+        };
+        if entry.level > level {
+            continue;
+        }
+        let resp = IpcResponse::Log { entry };
+        writer
+            .write_all(serde_json::to_string(&resp)?.as_bytes())
+            .await?;
+        writer.write_all(b"\n").await?;
+    }
+}
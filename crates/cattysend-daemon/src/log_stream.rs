@@ -0,0 +1,110 @@
+//! 日志转发
+//!
+//! [`LogBroadcastLayer`] 把 tracing 事件转成 [`LogEntry`] 广播出去，IPC 的 `logs`
+//! 请求订阅 [`LogStream`] 后逐行推送给附加的 TUI/GUI。没有订阅者时不做任何格式化。
+
+use cattysend_core::{LogEntry, LogLevel};
+use std::fmt;
+use tokio::sync::broadcast;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+/// 广播通道容量，订阅者落后超过这么多条时丢弃最旧的日志
+const CHANNEL_CAPACITY: usize = 256;
+
+/// 创建转发层和对应的订阅入口
+pub fn channel() -> (LogBroadcastLayer, LogStream) {
+    let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+    (LogBroadcastLayer { tx: tx.clone() }, LogStream(tx))
+}
+
+/// 日志订阅入口
+#[derive(Clone)]
+pub struct LogStream(broadcast::Sender<LogEntry>);
+
+impl LogStream {
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.0.subscribe()
+    }
+}
+
+/// 把日志广播给订阅者的 Layer
+pub struct LogBroadcastLayer {
+    tx: broadcast::Sender<LogEntry>,
+}
+
+impl<S> Layer<S> for LogBroadcastLayer
+where
+    S: Subscriber,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+
+        let mut message = String::new();
+        event.record(&mut MessageVisitor(&mut message));
+        if message.is_empty() {
+            message = event.metadata().target().to_string();
+        }
+
+        let _ = self
+            .tx
+            .send(LogEntry::new(log_level(*event.metadata().level()), message));
+    }
+}
+
+fn log_level(level: Level) -> LogLevel {
+    match level {
+        Level::ERROR => LogLevel::Error,
+        Level::WARN => LogLevel::Warn,
+        Level::INFO => LogLevel::Info,
+        Level::DEBUG => LogLevel::Debug,
+        Level::TRACE => LogLevel::Trace,
+    }
+}
+
+/// 取出事件中的 `message` 字段，没有时使用第一个字段
+struct MessageVisitor<'a>(&'a mut String);
+
+impl tracing::field::Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            *self.0 = format!("{:?}", value);
+        } else if self.0.is_empty() {
+            *self.0 = format!("{}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == "message" {
+            *self.0 = value.to_string();
+        } else if self.0.is_empty() {
+            *self.0 = format!("{}={}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_broadcasts_events_to_subscribers() {
+        let (layer, logs) = channel();
+        let mut rx = logs.subscribe();
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!("热点创建失败: {}", "busy");
+            tracing::debug!(peer = "10.0.0.2");
+        });
+
+        let warn = rx.try_recv().unwrap();
+        assert_eq!(warn, LogEntry::new(LogLevel::Warn, "热点创建失败: busy"));
+        let debug = rx.try_recv().unwrap();
+        assert_eq!(debug, LogEntry::new(LogLevel::Debug, "peer=10.0.0.2"));
+        assert!(rx.try_recv().is_err());
+    }
+}
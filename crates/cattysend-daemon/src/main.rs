@@ -5,33 +5,97 @@
 //! - WiFi P2P 热点管理
 //! - HTTP/WebSocket 服务
 //! - 通过 Unix Socket 与 CLI 通信
+//!
+//! 设置中 `log_format = "json"` 时日志输出为 JSON 行，接收会话的日志带有
+//! `session_id`、`peer`、`phase` 字段。
+//!
+//! 以 `--log-dir <dir>` 启动时日志另写入该目录下的 `daemon.log`（按大小轮转，
+//! 保留 cattysend 各模块的 debug 日志）；只给出 `--log-dir` 时使用
+//! `~/.local/state/cattysend/logs`。
+//!
+//! TUI/GUI 可以通过 IPC 的 `logs` 请求附加到守护进程，实时接收按级别过滤的日志
+//! （见 [`log_stream`]）。
+//!
+//! 启用 `metrics` feature 时，另在本机提供 Prometheus 指标端点
+//! （`CATTYSEND_METRICS_ADDR`，默认 `127.0.0.1:9464`）。
+//!
+//! 支持 systemd socket 激活（`assets/user/cattysend.socket`）：首次有客户端连接时
+//! 才启动，空闲 `idle_exit_secs` 秒后退出。
+//!
+//! 本机身份密钥保存在系统密钥环中（`keyring` feature，默认开启），
+//! 不可用时退回到 `~/.config/cattysend/identity.key`。
+//!
+//! 设置中 `passive_receive = true` 时启动后即进入被动监听：GATT 服务常驻广播，
+//! 发送端写入连接信息后才接入 WiFi 开始接收，每次会话结束后自动恢复监听。
+//! 以 `--receive` 启动（登录自启动，见 [`cattysend_core::autostart`]）时同样进入被动监听。
+//! 设置了 `trusted_networks` 时，被动监听只在连接到其中某个 WiFi 网络时开启。
+//!
+//! 定时传输（`send --at` 和设置中的 `[[schedule]]` 规则）由 [`scheduler`] 执行，
+//! 结果写入传输历史；有待执行的任务时不会空闲退出。设置了 `[[on_appear]]` 钩子时
+//! 常驻低占空比扫描，目标设备出现后执行命令或发送文件（见 [`presence`]）。
 
+mod activation;
+mod idle;
 mod ipc;
+mod log_stream;
+mod presence;
+mod scheduler;
 mod service;
 
-use anyhow::Result;
-use tracing_subscriber::EnvFilter;
+use anyhow::{Context, Result};
+use cattysend_core::{AppSettings, IdentityStore, LogFormat, RotatingFile, autostart, logging};
+use log_stream::LogStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // 桥接 log crate（cattysend-core 使用）到 tracing
     let _ = tracing_log::LogTracer::init();
+    // 转发给客户端的状态文本使用用户的语言
+    cattysend_core::i18n::init();
 
-    // 初始化日志
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new("info,cattysend_core=debug")),
-        )
-        .try_init();
+    let (log_dir, args) = logging::take_log_dir_arg(std::env::args().collect());
+    let mut settings = AppSettings::load();
+    if args.iter().any(|arg| arg == autostart::RECEIVE_ARG) {
+        settings.passive_receive = true;
+    }
+
+    let logs = init_logging(settings.log_format, log_dir)?;
 
     tracing::info!("Cattysend Daemon starting...");
 
+    #[cfg(feature = "metrics")]
+    install_metrics();
+
+    let activated = activation::take_listener()?;
+    // 只有 socket 激活时才能空闲退出，否则退出后客户端无法再连上
+    let idle_exit = (activated.is_some() && settings.idle_exit_secs > 0)
+        .then(|| Duration::from_secs(settings.idle_exit_secs));
+
+    // 本机身份密钥在重启后保持不变，对端记住的公钥才有效；读写密钥环是同步 D-Bus 调用
+    let security = tokio::task::spawn_blocking(|| IdentityStore::default().load_or_create())
+        .await?
+        .context("无法加载身份密钥（可用 `cattysend-cli identity reset` 重新生成）")?;
+    let service = service::Service::new(settings, Arc::new(security));
+
     // 启动 IPC 服务器
-    let ipc_handle = tokio::spawn(ipc::run_ipc_server());
+    let ipc_handle = tokio::spawn(ipc::run_ipc_server(service.clone(), activated, logs));
 
     // 启动核心服务
-    let service_handle = tokio::spawn(service::run_service());
+    let service_handle = tokio::spawn(service::run_service(service.clone()));
+
+    let idle = async {
+        match idle_exit {
+            Some(timeout) => service.idle().wait_idle(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
 
     // 等待任一任务完成
     tokio::select! {
@@ -41,7 +105,101 @@ async fn main() -> Result<()> {
         res = service_handle => {
             tracing::error!("Core service exited: {:?}", res);
         }
+        () = idle => {
+            tracing::info!(
+                "空闲 {}s，退出（下次连接时由 systemd 重新启动）",
+                idle_exit.map_or(0, |t| t.as_secs())
+            );
+        }
     }
 
     Ok(())
 }
+
+/// 初始化日志：输出到标准输出，给出日志目录时另写入 `daemon.log`
+///
+/// 返回的 [`LogStream`] 供附加的 UI 订阅。设置了 `RUST_LOG` 时所有输出都按它过滤。
+fn init_logging(format: LogFormat, log_dir: Option<PathBuf>) -> Result<LogStream> {
+    let filter = |default: &str| {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default))
+    };
+
+    let file = log_dir
+        .map(|dir| {
+            RotatingFile::open(&dir, "daemon")
+                .with_context(|| format!("无法打开日志目录 {}", dir.display()))
+        })
+        .transpose()?;
+    let path = file.as_ref().map(RotatingFile::path);
+    let file_layer = file.map(|file| {
+        format_layer(format, Mutex::new(file), false)
+            .with_filter(filter("info,cattysend_core=debug,cattysend_daemon=debug"))
+    });
+
+    let (broadcast_layer, logs) = log_stream::channel();
+
+    let _ = tracing_subscriber::registry()
+        .with(
+            format_layer(format, std::io::stdout, true)
+                .with_filter(filter("info,cattysend_core=debug")),
+        )
+        .with(file_layer)
+        .with(
+            broadcast_layer.with_filter(filter("info,cattysend_core=debug,cattysend_daemon=debug")),
+        )
+        .try_init();
+
+    if let Some(path) = path {
+        tracing::info!("日志文件: {}", path.display());
+    }
+    Ok(logs)
+}
+
+/// 按设置的格式输出到 `writer` 的日志层
+fn format_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    }
+}
+
+/// 默认指标端点，只监听本机
+#[cfg(feature = "metrics")]
+const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9464";
+
+/// 安装 Prometheus 导出器，失败时只记录日志
+#[cfg(feature = "metrics")]
+fn install_metrics() {
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::net::SocketAddr;
+
+    let addr = std::env::var("CATTYSEND_METRICS_ADDR")
+        .unwrap_or_else(|_| DEFAULT_METRICS_ADDR.to_string());
+    let addr: SocketAddr = match addr.parse() {
+        Ok(addr) => addr,
+        Err(e) => {
+            tracing::warn!("无效的指标地址 {}: {}", addr, e);
+            return;
+        }
+    };
+
+    match PrometheusBuilder::new().with_http_listener(addr).install() {
+        Ok(()) => {
+            cattysend_core::metrics::describe();
+            tracing::info!("指标端点: http://{}/metrics", addr);
+        }
+        Err(e) => tracing::warn!("启动指标端点失败: {}", e),
+    }
+}
@@ -0,0 +1,166 @@
+//! 设备出现时的钩子
+//!
+//! 设置了 `[[on_appear]]` 时守护进程常驻低占空比扫描（[`PresenceWatcher`]），
+//! 目标设备出现后执行钩子的命令并发送其文件，发送结果写入传输历史。
+//! 有钩子时守护进程不会空闲退出。
+//!
+//! 钩子的发送无人确认，目标必须是地址、sender ID 或解析到地址的收藏别名；
+//! 只写了名称的钩子启动时被忽略。
+
+use crate::scheduler;
+use crate::service::{SendJob, Service};
+use cattysend_core::{
+    DiscoveredDevice, Favorites, HistoryEntry, HistoryOutcome, PresenceEvent, PresenceHook,
+    PresenceWatcher, cancel, is_exact_query,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// 另一个发送正在进行时，钩子的发送每隔多久检查一次
+const QUEUE_POLL: Duration = Duration::from_secs(15);
+
+/// 钩子的发送最多排队等待多久
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// 启动在场监视，为每个钩子解析 `@收藏别名`
+pub fn spawn(service: &Arc<Service>, hooks: &[PresenceHook]) {
+    let favorites = Favorites::load();
+    let hooks: Vec<(String, PresenceHook)> = hooks
+        .iter()
+        .filter_map(|hook| {
+            let target = match favorites.resolve(&hook.device) {
+                Some(Ok(address)) => address,
+                Some(Err(e)) => {
+                    tracing::warn!("忽略设备出现钩子 {}: {}", hook.label(), e);
+                    return None;
+                }
+                None => hook.device.clone(),
+            };
+            if !is_exact_query(&target) {
+                tracing::warn!(
+                    "忽略设备出现钩子 {}: 目标 {:?} 不是地址、sender ID 或收藏别名",
+                    hook.label(),
+                    target
+                );
+                return None;
+            }
+            Some((target, hook.clone()))
+        })
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let service = Arc::clone(service);
+    tokio::spawn(async move { run(service, hooks).await });
+}
+
+async fn run(service: Arc<Service>, hooks: Vec<(String, PresenceHook)>) {
+    // 空闲退出后钩子就不会再触发
+    let _busy = service.idle().busy();
+    let mut queries: Vec<String> = hooks.iter().map(|(query, _)| query.clone()).collect();
+    queries.sort();
+    queries.dedup();
+    tracing::info!("监视设备出现: {}", queries.join(", "));
+
+    let (tx, mut rx) = mpsc::channel(16);
+    let watcher = PresenceWatcher::new(queries);
+    tokio::spawn(async move {
+        if let Err(e) = watcher.run(tx).await {
+            tracing::warn!("设备在场监视已停止: {}", e);
+        }
+    });
+
+    // 每个钩子上次触发的时间
+    let mut fired: Vec<Option<Instant>> = vec![None; hooks.len()];
+    while let Some(event) = rx.recv().await {
+        let (query, device) = match event {
+            PresenceEvent::Appeared { query, device } => (query, device),
+            PresenceEvent::Left { query } => {
+                tracing::info!("设备 {} 已离开", query);
+                continue;
+            }
+        };
+        tracing::info!("设备 {} ({}) 出现", device.name, device.address);
+        for ((hook_query, hook), last) in hooks.iter().zip(fired.iter_mut()) {
+            if *hook_query != query {
+                continue;
+            }
+            if last.is_some_and(|at| at.elapsed() < hook.cooldown()) {
+                tracing::debug!("钩子 {} 仍在冷却中", hook.label());
+                continue;
+            }
+            *last = Some(Instant::now());
+            let service = Arc::clone(&service);
+            let hook = hook.clone();
+            let device = device.clone();
+            tokio::spawn(async move { fire(&service, &hook, device).await });
+        }
+    }
+}
+
+/// 执行钩子：先运行命令，再发送文件
+async fn fire(service: &Arc<Service>, hook: &PresenceHook, device: DiscoveredDevice) {
+    if let Some(command) = &hook.exec {
+        tracing::info!("执行钩子 {}: {}", hook.label(), command);
+        let status = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("CATTYSEND_DEVICE_NAME", &device.name)
+            .env("CATTYSEND_DEVICE_ADDRESS", &device.address)
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => tracing::warn!("钩子 {} 的命令退出: {}", hook.label(), status),
+            Err(e) => tracing::warn!("无法执行钩子 {} 的命令: {}", hook.label(), e),
+        }
+    }
+    if hook.send.is_empty() {
+        return;
+    }
+
+    let files = hook
+        .send
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let name = device.name.clone();
+    let outcome = send_queued(service, hook, device).await;
+    let entry = match outcome {
+        Ok(()) => HistoryEntry::now(&name, files, HistoryOutcome::Completed),
+        Err(e) if cancel::is_cancelled(&e) => {
+            HistoryEntry::now(&name, files, HistoryOutcome::Cancelled)
+        }
+        Err(e) => {
+            tracing::warn!("钩子 {} 的发送失败: {}", hook.label(), e);
+            HistoryEntry::now(&name, files, HistoryOutcome::Failed).with_message(e.to_string())
+        }
+    };
+    scheduler::record(entry.with_schedule(Some(hook.label().to_string()))).await;
+}
+
+/// 等当前的发送结束后再发送钩子的文件
+async fn send_queued(
+    service: &Arc<Service>,
+    hook: &PresenceHook,
+    device: DiscoveredDevice,
+) -> anyhow::Result<()> {
+    if let Some(missing) = hook.send.iter().find(|p| !p.is_file()) {
+        anyhow::bail!("文件不存在: {}", missing.display());
+    }
+    let deadline = Instant::now() + QUEUE_TIMEOUT;
+    while service.is_sending().await {
+        if Instant::now() >= deadline {
+            anyhow::bail!("等待其他发送结束超时");
+        }
+        tracing::info!("另一个发送正在进行，钩子 {} 排队等待", hook.label());
+        tokio::time::sleep(QUEUE_POLL).await;
+    }
+    tracing::info!("钩子 {}: 发送到 {}", hook.label(), device.name);
+    service
+        .send_and_wait(device, SendJob::Files(hook.send.clone()), false)
+        .await
+}
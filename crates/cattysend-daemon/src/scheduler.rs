@@ -0,0 +1,204 @@
+//! 定时传输的执行
+//!
+//! 启动时为设置中的每条 `[[schedule]]` 规则排期（见 [`cattysend_core::schedule`]）；
+//! `send --at` 提交的一次性任务只保存在内存中，守护进程退出后不再执行。
+//! 有待执行的任务时守护进程不会空闲退出。
+//!
+//! 到点后在扫描窗口内每隔 [`RETRY_INTERVAL`] 查找一次目标设备并尝试发送。发送无人确认，
+//! 目标只按地址、sender ID 或收藏别名查找（[`Service::resolve_device_exact`]），不按名称。
+//! 结果写入传输历史（[`History`]）。
+
+use crate::service::{SendJob, Service};
+use cattysend_core::{
+    DeviceMatch, History, HistoryEntry, HistoryOutcome, ScheduleRule, TimeOfDay, cancel,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+
+/// 扫描窗口内两次尝试之间的间隔
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 等待执行时刻时每次最多睡眠这么久，系统休眠唤醒后按墙上时间重新计算
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// 一次定时发送
+#[derive(Debug, Clone)]
+pub struct ScheduledSend {
+    /// 规则名称，一次性任务为 None
+    pub name: Option<String>,
+    /// 目标设备：地址、sender ID 或 `@收藏别名`，到点时才查找
+    pub device: String,
+    pub files: Vec<PathBuf>,
+    /// 扫描窗口
+    pub window: Duration,
+    /// 跳过电池电量检查（`send --at --force`）
+    pub force: bool,
+}
+
+impl ScheduledSend {
+    fn from_rule(rule: &ScheduleRule) -> Self {
+        Self {
+            name: Some(rule.name.clone()),
+            device: rule.device.clone(),
+            files: rule.paths.clone(),
+            window: rule.window(),
+            force: false,
+        }
+    }
+}
+
+/// 为每条规则启动排期任务
+pub fn spawn_rules(service: &Arc<Service>, rules: &[ScheduleRule]) {
+    for rule in rules {
+        let service = Arc::clone(service);
+        let rule = rule.clone();
+        tokio::spawn(async move { run_rule(service, rule).await });
+    }
+}
+
+/// 按规则重复执行
+async fn run_rule(service: Arc<Service>, rule: ScheduleRule) {
+    // 空闲退出后规则就不会再被执行
+    let _busy = service.idle().busy();
+    let job = ScheduledSend::from_rule(&rule);
+    loop {
+        let Some(next) = rule.next_run(SystemTime::now()) else {
+            tracing::warn!("定时规则 {} 无法计算下一次执行时间，停止排期", rule.name);
+            return;
+        };
+        log_next(&job, rule.at, next);
+        sleep_until(next).await;
+        execute(&service, &job).await;
+    }
+}
+
+/// 安排一次性发送，返回执行时间
+pub fn schedule_once(
+    service: &Arc<Service>,
+    at: TimeOfDay,
+    job: ScheduledSend,
+) -> Option<SystemTime> {
+    let next = at.next_after(&[], SystemTime::now())?;
+    log_next(&job, at, next);
+    let service = Arc::clone(service);
+    let busy = service.idle().busy();
+    tokio::spawn(async move {
+        let _busy = busy;
+        sleep_until(next).await;
+        execute(&service, &job).await;
+    });
+    Some(next)
+}
+
+fn log_next(job: &ScheduledSend, at: TimeOfDay, next: SystemTime) {
+    let secs = next
+        .duration_since(SystemTime::now())
+        .map_or(0, |d| d.as_secs());
+    tracing::info!(
+        "定时发送 {} -> {} 将在 {} 执行（{}s 后）",
+        job.name.as_deref().unwrap_or("send --at"),
+        job.device,
+        at,
+        secs
+    );
+}
+
+/// 按墙上时间等到 `at`
+async fn sleep_until(at: SystemTime) {
+    while let Ok(remaining) = at.duration_since(SystemTime::now()) {
+        if remaining.is_zero() {
+            break;
+        }
+        tokio::time::sleep(remaining.min(MAX_SLEEP)).await;
+    }
+}
+
+/// 执行一次定时发送并记录结果
+async fn execute(service: &Arc<Service>, job: &ScheduledSend) {
+    let entry = attempt(service, job).await.with_schedule(job.name.clone());
+    match (&entry.outcome, &entry.message) {
+        (HistoryOutcome::Completed, _) => tracing::info!("定时发送到 {} 完成", entry.device),
+        (outcome, message) => tracing::warn!(
+            "定时发送到 {} 未完成: {:?} {}",
+            entry.device,
+            outcome,
+            message.as_deref().unwrap_or_default()
+        ),
+    }
+    record(entry).await;
+}
+
+/// 把结果写入传输历史，失败时只记录日志
+pub async fn record(entry: HistoryEntry) {
+    let history = History::default();
+    match tokio::task::spawn_blocking(move || history.append(&entry)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("写入传输历史失败: {}", e),
+        Err(e) => tracing::warn!("写入传输历史失败: {}", e),
+    }
+}
+
+/// 在扫描窗口内查找设备并发送，直到成功、被取消或窗口结束
+async fn attempt(service: &Arc<Service>, job: &ScheduledSend) -> HistoryEntry {
+    let files: Vec<String> = job
+        .files
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let result =
+        |device: &str, outcome: HistoryOutcome| HistoryEntry::now(device, files.clone(), outcome);
+
+    if let Some(missing) = job.files.iter().find(|p| !p.is_file()) {
+        return result(&job.device, HistoryOutcome::Failed)
+            .with_message(format!("文件不存在: {}", missing.display()));
+    }
+
+    tracing::info!("开始定时发送，在 {:?} 内查找 {}", job.window, job.device);
+    let deadline = Instant::now() + job.window;
+    let mut device_name = job.device.clone();
+    let mut last_error = None;
+    loop {
+        if service.is_sending().await {
+            tracing::info!("另一个发送正在进行，稍后重试");
+        } else {
+            match service.resolve_device_exact(&job.device).await {
+                Ok(DeviceMatch::Found(device)) => {
+                    device_name.clone_from(&device.name);
+                    match service
+                        .send_and_wait(device, SendJob::Files(job.files.clone()), job.force)
+                        .await
+                    {
+                        Ok(()) => return result(&device_name, HistoryOutcome::Completed),
+                        Err(e) if cancel::is_cancelled(&e) => {
+                            return result(&device_name, HistoryOutcome::Cancelled);
+                        }
+                        Err(e) => last_error = Some(e.to_string()),
+                    }
+                }
+                Ok(DeviceMatch::Ambiguous(devices)) => {
+                    return result(&job.device, HistoryOutcome::Failed).with_message(format!(
+                        "{} 匹配到 {} 个设备，请使用地址或收藏别名",
+                        job.device,
+                        devices.len()
+                    ));
+                }
+                Ok(DeviceMatch::NotFound) => tracing::debug!("未找到 {}", job.device),
+                Err(e) => last_error = Some(format!("扫描失败: {}", e)),
+            }
+        }
+        if Instant::now() + RETRY_INTERVAL >= deadline {
+            break;
+        }
+        tokio::time::sleep(RETRY_INTERVAL).await;
+    }
+
+    match last_error {
+        Some(message) => result(&device_name, HistoryOutcome::Failed).with_message(message),
+        None => result(&job.device, HistoryOutcome::Missed).with_message(format!(
+            "{:?} 内未找到设备（只按地址、sender ID 或收藏别名查找）",
+            job.window
+        )),
+    }
+}
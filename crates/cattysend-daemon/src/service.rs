@@ -1,15 +1,864 @@
 //! Core Service - BLE/WiFi/Transfer 管理
 
+use crate::idle::IdleTimer;
+use crate::ipc::{self, DaemonEvent, SessionEvent};
+use crate::{presence, scheduler};
 use anyhow::Result;
-use cattysend_core::BleSecurityPersistent;
+use cattysend_core::autostart;
 use cattysend_core::ble::DeviceInfo;
+use cattysend_core::{
+    AppSettings, AtRestKey, BleScanner, BleSecurityPersistent, CancellationToken, DeviceIdentity,
+    DeviceMatch, DiscoveredDevice, Favorites, GattConnectionEvent, Interrupted, ReceiveEvent,
+    ReceiveOptions, Receiver, ScanOptions, SendEvent, SendOptions, Sender, SimpleReceiveCallback,
+    SimpleSendCallback, Standby, SyncJob, TransferControl, cancel, find_device, find_device_exact,
+    sync, wifi,
+};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::{Mutex, broadcast, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{Instrument, Span};
 
-pub async fn run_service() -> Result<()> {
+/// 扫描结果的有效期，过期后按名称查找设备会重新扫描
+const SCAN_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// 查找设备时重新扫描的时长
+const RESOLVE_SCAN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 被动监听两轮之间的最短间隔，避免蓝牙不可用时反复重启广播
+const PASSIVE_MIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 停止会话时等待其断开 WiFi、撤销广播的时间，超时后直接终止任务
+const STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// 守护进程共享状态
+///
+/// IPC 处理器和信号处理器通过它启动/停止接收模式，
+/// 所有状态变化都广播到 `events`，供 UI 订阅。
+pub struct Service {
+    settings: AppSettings,
+    /// 本机身份密钥（持久化，见 [`cattysend_core::IdentityStore`]），收发共用
+    security: Arc<BleSecurityPersistent>,
+    events: broadcast::Sender<SessionEvent>,
+    receive: Mutex<Option<ReceiveSession>>,
+    send: Mutex<Option<SendSession>>,
+    /// 最近一次扫描的时间和结果
+    last_scan: Mutex<Option<(Instant, Vec<DiscoveredDevice>)>>,
+    /// IPC 连接和接收会话的活动，socket 激活时据此空闲退出
+    idle: Arc<IdleTimer>,
+    /// 热备的热点和传输端口（见 [`AppSettings::warm_standby`]）
+    standby: Option<Standby>,
+}
+
+/// 发送的内容
+pub enum SendJob {
+    Files(Vec<PathBuf>),
+    /// 文件夹同步（见 [`cattysend_core::sync`]）
+    Sync(SyncJob),
+}
+
+impl SendJob {
+    /// 完成事件中报告的路径
+    fn paths(&self) -> Vec<String> {
+        match self {
+            SendJob::Files(files) => files
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            SendJob::Sync(job) => vec![job.root().to_string_lossy().to_string()],
+        }
+    }
+}
+
+/// 正在进行的接收会话
+struct ReceiveSession {
+    task: JoinHandle<()>,
+    /// 可发现窗口截止时间（None 表示一直广播直到收到文件）
+    deadline: Option<Instant>,
+    /// 下载的暂停/恢复句柄
+    control: TransferControl,
+    /// 被动监听（见 [`AppSettings::passive_receive`]）开启的会话
+    passive: bool,
+    /// 发送端是否已发起传输
+    engaged: Arc<AtomicBool>,
+    /// 取消后接收流程在下一个等待点结束并自行清理
+    cancel: CancellationToken,
+}
+
+impl ReceiveSession {
+    /// 取消会话并等待它清理完毕（最多 [`STOP_GRACE`]）
+    async fn shutdown(mut self) {
+        shutdown_task(&self.cancel, &mut self.task, "接收").await;
+    }
+}
+
+/// 正在进行的发送
+struct SendSession {
+    task: JoinHandle<()>,
+    /// 取消后发送流程关闭热点并结束
+    cancel: CancellationToken,
+}
+
+impl SendSession {
+    async fn shutdown(mut self) {
+        shutdown_task(&self.cancel, &mut self.task, "发送").await;
+    }
+}
+
+/// 取消会话任务并等待它清理完毕，超过 [`STOP_GRACE`] 时直接终止
+async fn shutdown_task(cancel: &CancellationToken, task: &mut JoinHandle<()>, kind: &str) {
+    cancel.cancel();
+    if tokio::time::timeout(STOP_GRACE, &mut *task).await.is_err() {
+        tracing::warn!("{}会话未能在 {:?} 内结束，强制终止", kind, STOP_GRACE);
+        task.abort();
+    }
+}
+
+impl Service {
+    pub fn new(settings: AppSettings, security: Arc<BleSecurityPersistent>) -> Arc<Self> {
+        let (events, _) = broadcast::channel(64);
+        let standby = settings
+            .warm_standby
+            .then(|| Standby::new(&send_options(&settings, false)));
+        Arc::new(Self {
+            settings,
+            security,
+            events,
+            receive: Mutex::new(None),
+            send: Mutex::new(None),
+            last_scan: Mutex::new(None),
+            idle: IdleTimer::new(),
+            standby,
+        })
+    }
+
+    pub fn idle(&self) -> &Arc<IdleTimer> {
+        &self.idle
+    }
+
+    /// 订阅守护进程事件
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
+    /// 广播事件（没有订阅者时发送失败是正常的）
+    fn emit(&self, session_id: Option<&str>, event: DaemonEvent) {
+        let _ = self.events.send(SessionEvent {
+            session_id: session_id.map(str::to_string),
+            event,
+        });
+    }
+
+    /// 本机身份：公钥指纹、sender ID 和广播的名称、厂商
+    pub fn identity(&self) -> DeviceIdentity {
+        DeviceIdentity::new(&self.security, &self.settings)
+    }
+
+    /// 当前状态和可发现窗口剩余时间
+    pub async fn status(&self) -> (String, Option<u64>) {
+        let mut guard = self.receive.lock().await;
+        if guard.as_ref().is_some_and(|s| s.task.is_finished()) {
+            *guard = None;
+        }
+        if guard.is_none() && self.is_sending().await {
+            return ("sending".to_string(), None);
+        }
+        match guard.as_ref() {
+            Some(session) => (
+                if session.control.is_paused() {
+                    "paused".to_string()
+                } else if session.passive && !session.engaged.load(Ordering::Relaxed) {
+                    "listening".to_string()
+                } else {
+                    "receiving".to_string()
+                },
+                session
+                    .deadline
+                    .map(|d| d.saturating_duration_since(Instant::now()).as_secs()),
+            ),
+            None => ("idle".to_string(), None),
+        }
+    }
+
+    /// 进入接收模式
+    ///
+    /// 指定 `window` 时只在该时间段内广播，倒计时结束后自动停止广播；
+    /// 如果窗口内已经开始传输，则让传输继续完成。
+    pub async fn start_receive(
+        self: &Arc<Self>,
+        output_dir: Option<PathBuf>,
+        window: Option<Duration>,
+    ) -> Result<()> {
+        let mut guard = self.receive.lock().await;
+        if let Some(old) = guard.take() {
+            tracing::info!("重新开始接收模式，终止旧会话");
+            old.shutdown().await;
+        }
+        *guard = Some(self.spawn_receive(output_dir, window, false)?);
+        Ok(())
+    }
+
+    /// 开启被动监听：GATT 服务常驻广播，发送端写入 P2P 信息后才接入 WiFi 开始传输
+    ///
+    /// 每个会话结束后自动进入下一轮；手动开启的接收会话优先，结束后同样恢复监听。
+    pub async fn start_passive(self: &Arc<Self>) -> Result<()> {
+        if !self.passive_allowed().await {
+            tracing::info!("未连接受信任的网络，暂不进入被动监听");
+            return Ok(());
+        }
+        let mut guard = self.receive.lock().await;
+        if guard.as_ref().is_some_and(|s| !s.task.is_finished()) {
+            return Ok(());
+        }
+        tracing::info!("进入被动监听模式");
+        *guard = Some(self.spawn_receive(None, None, true)?);
+        Ok(())
+    }
+
+    /// 会话结束后恢复被动监听（会话被替换或停止时不恢复）
+    async fn rearm_passive(self: &Arc<Self>, started: Instant, cancel: &CancellationToken) {
+        if !self.settings.passive_receive || cancel.is_cancelled() {
+            return;
+        }
+        tokio::time::sleep_until(started + PASSIVE_MIN_INTERVAL).await;
+        if !self.passive_allowed().await {
+            return;
+        }
+        let mut guard = self.receive.lock().await;
+        // 等待期间可能已有新的会话
+        if guard.as_ref().is_some_and(|s| !s.task.is_finished()) {
+            return;
+        }
+        match self.spawn_receive(None, None, true) {
+            Ok(session) => *guard = Some(session),
+            Err(e) => tracing::warn!("无法恢复被动监听: {}", e),
+        }
+    }
+
+    /// 被动监听是否允许：设置了 [`AppSettings::trusted_networks`] 时只在这些网络下可被发现
+    async fn passive_allowed(&self) -> bool {
+        autostart::on_trusted_network(&self.settings.trusted_networks).await
+    }
+
+    /// 跟随当前 WiFi 网络开启或停止被动监听
+    ///
+    /// 进入受信任的网络时开启，离开时停止尚未开始传输的被动监听会话。
+    pub async fn watch_trusted_networks(self: &Arc<Self>) {
+        let mut ticker = tokio::time::interval(autostart::TRUSTED_NETWORK_POLL);
+        loop {
+            ticker.tick().await;
+            if self.passive_allowed().await {
+                if let Err(e) = self.start_passive().await {
+                    tracing::warn!("无法进入被动监听模式: {}", e);
+                }
+                continue;
+            }
+            let mut guard = self.receive.lock().await;
+            let idle_passive = guard.as_ref().is_some_and(|s| {
+                s.passive && !s.task.is_finished() && !s.engaged.load(Ordering::Relaxed)
+            });
+            if idle_passive && let Some(session) = guard.take() {
+                tracing::info!("已离开受信任的网络，停止被动监听");
+                session.shutdown().await;
+            }
+        }
+    }
+
+    /// 启动接收任务（调用方持有 `receive` 锁并负责替换旧会话）
+    fn spawn_receive(
+        self: &Arc<Self>,
+        output_dir: Option<PathBuf>,
+        window: Option<Duration>,
+        passive: bool,
+    ) -> Result<ReceiveSession> {
+        // 配置了密钥却读不出来时不接收，避免静默退回明文保存
+        let encryption_key = match &self.settings.encryption_key_file {
+            Some(path) => Some(AtRestKey::load(path)?),
+            None => None,
+        };
+        let options = ReceiveOptions {
+            device_name: self.settings.device_name.clone(),
+            wifi_interface: self.settings.wifi_interface.clone(),
+            output_dir: output_dir.unwrap_or_else(|| self.settings.download_dir.clone()),
+            auto_accept: self.settings.auto_accept,
+            brand_id: self.settings.brand_id,
+            supports_5ghz: self.settings.supports_5ghz,
+            power_profile: self.settings.power_profile,
+            battery_saver: self.settings.battery_saver,
+            encryption_key,
+            sort_by_sender: self.settings.sort_by_sender,
+            allow_sync_delete: self.settings.allow_sync_delete,
+            group_owner: self.settings.group_owner,
+            ..Default::default()
+        };
+        let cancel = CancellationToken::new();
+        let receiver = Receiver::new(options)?
+            .with_security(self.security.clone())
+            .with_cancellation(cancel.clone());
+        let control = receiver.control();
+        let deadline = window.map(|w| Instant::now() + w);
+        let engaged = Arc::new(AtomicBool::new(false));
+
+        // 会话内的所有日志（包括 cattysend-core 的）都带上这个 span 的字段；
+        // session_id 由核心工作流生成，收到 Started 事件后记录
+        let span = tracing::info_span!(
+            "receive",
+            session_id = tracing::field::Empty,
+            peer = tracing::field::Empty,
+            phase = tracing::field::Empty,
+        );
+        let service = Arc::clone(self);
+        // 任务结束或被终止时守卫随之释放
+        let busy = self.idle.busy();
+        let session_engaged = engaged.clone();
+        let session_cancel = cancel.clone();
+        let task = tokio::spawn(
+            async move {
+                let started = Instant::now();
+                {
+                    let _busy = busy;
+                    service
+                        .run_receive(receiver, deadline, window, &session_engaged)
+                        .await;
+                }
+                service.rearm_passive(started, &session_cancel).await;
+            }
+            .instrument(span),
+        );
+
+        Ok(ReceiveSession {
+            task,
+            deadline,
+            control,
+            passive,
+            engaged,
+            cancel,
+        })
+    }
+
+    /// 是否有发送任务正在进行
+    pub async fn is_sending(&self) -> bool {
+        let mut guard = self.send.lock().await;
+        if guard.as_ref().is_some_and(|s| s.task.is_finished()) {
+            *guard = None;
+        }
+        guard.is_some()
+    }
+
+    /// 扫描 `dir` 准备同步到 `device`（读取整个目录并计算新文件的摘要）
+    pub async fn prepare_sync(
+        &self,
+        device: &DiscoveredDevice,
+        dir: PathBuf,
+        delete: bool,
+    ) -> Result<SyncJob> {
+        let key = sync::device_key(device).to_string();
+        tokio::task::spawn_blocking(move || SyncJob::prepare(&dir, &key, delete)).await?
+    }
+
+    /// 把文件发送给 `device`（或把目录同步过去），进度和结果通过事件推送
+    ///
+    /// 同一时间只进行一次发送，已有的发送先被取消。返回本次发送的会话 ID，
+    /// 客户端据此从事件流中挑出自己的事件。`force` 时跳过电池电量检查。
+    pub async fn start_send(
+        self: &Arc<Self>,
+        device: DiscoveredDevice,
+        job: SendJob,
+        force: bool,
+    ) -> Result<Option<String>> {
+        let (session_id, _done) = self.spawn_send(device, job, force).await?;
+        Ok(session_id)
+    }
+
+    /// 发送并等待结束（定时传输用），事件同样推送给订阅者
+    ///
+    /// 发送被 `stop` 或新的发送终止时返回取消错误。
+    pub async fn send_and_wait(
+        self: &Arc<Self>,
+        device: DiscoveredDevice,
+        job: SendJob,
+        force: bool,
+    ) -> Result<()> {
+        let (_, done) = self.spawn_send(device, job, force).await?;
+        done.await
+            .unwrap_or_else(|_| Err(Interrupted::Cancelled.into()))
+    }
+
+    /// 启动发送任务，返回会话 ID 和发送结果
+    async fn spawn_send(
+        self: &Arc<Self>,
+        device: DiscoveredDevice,
+        job: SendJob,
+        force: bool,
+    ) -> Result<(Option<String>, oneshot::Receiver<Result<()>>)> {
+        let mut guard = self.send.lock().await;
+        if let Some(old) = guard.take() {
+            tracing::info!("开始新的发送，终止旧任务");
+            old.shutdown().await;
+        }
+        let cancel = CancellationToken::new();
+        let mut sender = Sender::new(send_options(&self.settings, force))?
+            .with_security(self.security.clone())
+            .with_cancellation(cancel.clone());
+        if let Some(standby) = &self.standby {
+            sender = sender.with_standby(standby);
+        }
+
+        let span = tracing::info_span!("send", device = %device.name);
+        let service = Arc::clone(self);
+        let busy = self.idle.busy();
+        let (started_tx, started_rx) = oneshot::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+        let task = tokio::spawn(
+            async move {
+                let _busy = busy;
+                let res = service.run_send(sender, device, job, started_tx).await;
+                let _ = done_tx.send(res);
+                // 热点和端口只用一次，为下一次发送重新准备
+                service.warm_up().await;
+            }
+            .instrument(span),
+        );
+        *guard = Some(SendSession { task, cancel });
+        drop(guard);
+
+        // 工作流一开始就生成会话 ID；出错提前结束时没有
+        Ok((started_rx.await.ok(), done_rx))
+    }
+
+    /// 热备开启时准备下一次发送的热点连接和传输端口，失败只记日志
+    async fn warm_up(&self) {
+        let Some(standby) = &self.standby else {
+            return;
+        };
+        match standby.prepare().await {
+            Ok(port) => tracing::info!("热备就绪，传输端口 {}", port),
+            Err(e) => tracing::warn!("热备失败，发送时照常创建热点: {:#}", e),
+        }
+    }
+
+    async fn run_send(
+        &self,
+        sender: Sender,
+        device: DiscoveredDevice,
+        job: SendJob,
+        started: oneshot::Sender<String>,
+    ) -> Result<()> {
+        let (callback, mut rx) = SimpleSendCallback::new();
+        let paths = job.paths();
+        let send = async {
+            match job {
+                SendJob::Files(files) => sender.send_to_device(&device, files, &callback).await,
+                SendJob::Sync(job) => sender.sync_to_device(&device, &job, &callback).await,
+            }
+        };
+        tokio::pin!(send);
+
+        let mut trace = SendTrace {
+            session_id: None,
+            started: Some(started),
+        };
+
+        loop {
+            tokio::select! {
+                res = &mut send => {
+                    while let Ok(event) = rx.try_recv() {
+                        trace.observe(&event);
+                        self.forward_send(event, trace.session_id(), &paths);
+                    }
+                    match &res {
+                        Ok(()) => tracing::info!("发送完成"),
+                        Err(e) if cancel::is_cancelled(e) => tracing::info!("发送已取消"),
+                        Err(e) => {
+                            tracing::warn!("发送失败: {}", e);
+                            self.emit(trace.session_id(), DaemonEvent::Error { message: e.to_string() });
+                        }
+                    }
+                    return res;
+                }
+                Some(event) = rx.recv() => {
+                    trace.observe(&event);
+                    self.forward_send(event, trace.session_id(), &paths);
+                }
+            }
+        }
+    }
+
+    /// 发送事件转为守护进程事件，进度沿用 `Progress`（`received` 为已发送的字节数）
+    fn forward_send(&self, event: SendEvent, session_id: Option<&str>, paths: &[String]) {
+        let event = match event {
+            SendEvent::Status(message) => DaemonEvent::Status { message },
+            SendEvent::VerificationCode(code) => DaemonEvent::Status {
+                message: format!("验证码: {}", code),
+            },
+            SendEvent::ReceiverJoined { mac, ip } => DaemonEvent::Status {
+                message: format!("接收端 {} ({}) 已接入热点", mac, ip),
+            },
+            SendEvent::InterfaceBusy(busy) => DaemonEvent::Status {
+                message: format!(
+                    "{} 已连接 {}，创建热点会断开该连接",
+                    busy.interface,
+                    busy.network()
+                ),
+            },
+            SendEvent::Pairing(prompt) => DaemonEvent::Status {
+                message: format!("正在与 {} 配对，配对码 {}", prompt.address(), prompt.code()),
+            },
+            SendEvent::Progress { sent, total } => DaemonEvent::Progress {
+                received: sent,
+                total,
+            },
+            SendEvent::Paused => DaemonEvent::Paused,
+            SendEvent::Resumed => DaemonEvent::Resumed,
+            SendEvent::Complete => DaemonEvent::Complete {
+                files: paths.to_vec(),
+            },
+            SendEvent::Error(message) => DaemonEvent::Error { message },
+            _ => return,
+        };
+        self.emit(session_id, event);
+    }
+
+    /// 扫描附近设备，按信号强度从强到弱排序
+    pub async fn scan(&self, timeout: Duration) -> Result<Vec<DiscoveredDevice>> {
+        self.scan_with(timeout, ScanOptions::default()).await
+    }
+
+    /// 按 `options` 扫描（被动扫描、记录原始广播）
+    ///
+    /// 设置了 `learn_unknown_vendors` 时同时学习未知厂商的广播形状。
+    pub async fn scan_with(
+        &self,
+        timeout: Duration,
+        mut options: ScanOptions,
+    ) -> Result<Vec<DiscoveredDevice>> {
+        options.learn |= self.settings.learn_unknown_vendors;
+        let scanner = BleScanner::new().await?.with_options(options);
+        let devices = scanner.scan(timeout, None).await?;
+        *self.last_scan.lock().await = Some((Instant::now(), devices.clone()));
+        self.emit(
+            None,
+            DaemonEvent::Devices {
+                devices: devices.iter().cloned().map(ipc::DeviceInfo::from).collect(),
+            },
+        );
+
+        let mut favorites = Favorites::load();
+        if favorites.touch(&devices) {
+            if let Err(e) = favorites.save() {
+                tracing::warn!("保存收藏失败: {}", e);
+            }
+        }
+        Ok(devices)
+    }
+
+    /// 按地址、sender_id、名称或 `@收藏别名` 查找设备
+    ///
+    /// 优先使用最近的扫描结果；结果已过期或其中找不到时重新扫描一次。
+    pub async fn resolve_device(&self, query: &str) -> Result<DeviceMatch> {
+        self.resolve_with(query, find_device).await
+    }
+
+    /// 同 [`Self::resolve_device`]，但只认地址、sender_id 和 `@收藏别名`，不按名称查找
+    ///
+    /// 用于定时发送等无人确认的场合，见 [`find_device_exact`]。
+    pub async fn resolve_device_exact(&self, query: &str) -> Result<DeviceMatch> {
+        self.resolve_with(query, find_device_exact).await
+    }
+
+    async fn resolve_with(
+        &self,
+        query: &str,
+        find: fn(&[DiscoveredDevice], &str) -> DeviceMatch,
+    ) -> Result<DeviceMatch> {
+        let query = match Favorites::load().resolve(query) {
+            Some(address) => address?,
+            None => query.to_string(),
+        };
+        let query = query.as_str();
+
+        let cached = self
+            .last_scan
+            .lock()
+            .await
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < SCAN_CACHE_TTL)
+            .map(|(_, devices)| find(devices, query));
+        if let Some(found @ (DeviceMatch::Found(_) | DeviceMatch::Ambiguous(_))) = cached {
+            return Ok(found);
+        }
+
+        tracing::info!("扫描结果中没有 '{}'，重新扫描", query);
+        let devices = self.scan(RESOLVE_SCAN_TIMEOUT).await?;
+        Ok(find(&devices, query))
+    }
+
+    /// 停止当前的接收会话和发送，返回是否确实停止了任务
+    pub async fn stop(&self) -> bool {
+        let sending = self
+            .send
+            .lock()
+            .await
+            .take()
+            .filter(|s| !s.task.is_finished());
+        let stopped_send = sending.is_some();
+        if let Some(session) = sending {
+            session.shutdown().await;
+        }
+        let session = self.receive.lock().await.take();
+        match session {
+            Some(session) => {
+                let discoverable = session.deadline.is_some();
+                session.shutdown().await;
+                if discoverable {
+                    self.emit(None, DaemonEvent::DiscoverableEnded);
+                }
+            }
+            None if !stopped_send => return false,
+            None => {}
+        }
+        self.emit(None, DaemonEvent::Cancelled);
+        true
+    }
+
+    /// 暂停或恢复当前接收会话的下载，返回是否有会话
+    pub async fn set_paused(&self, paused: bool) -> bool {
+        match self.receive.lock().await.as_ref() {
+            Some(session) if !session.task.is_finished() => {
+                if paused {
+                    session.control.pause();
+                } else {
+                    session.control.resume();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn run_receive(
+        &self,
+        receiver: Receiver,
+        deadline: Option<Instant>,
+        window: Option<Duration>,
+        engaged: &AtomicBool,
+    ) {
+        let (callback, mut rx) = SimpleReceiveCallback::new(self.settings.auto_accept);
+        let total_secs = window.map_or(0, |w| w.as_secs());
+
+        let receive = receiver.start(&callback);
+        tokio::pin!(receive);
+
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        // 一旦发送端发起传输，窗口不再限制本次会话
+        let mut trace = SessionTrace::new(Span::current());
+
+        loop {
+            tokio::select! {
+                res = &mut receive => {
+                    while let Ok(event) = rx.try_recv() {
+                        trace.observe(&event);
+                        self.forward(event, trace.session_id());
+                    }
+                    if let Err(e) = &res
+                        && cancel::is_cancelled(e)
+                    {
+                        trace.enter("cancelled");
+                        tracing::info!("接收已取消");
+                    } else if let Err(e) = res {
+                        trace.enter("failed");
+                        tracing::warn!("接收失败: {}", e);
+                        self.emit(trace.session_id(), DaemonEvent::Error { message: e.to_string() });
+                    }
+                    break;
+                }
+                Some(event) = rx.recv() => {
+                    if matches!(event, ReceiveEvent::Request(_) | ReceiveEvent::Progress { .. })
+                        && !engaged.swap(true, Ordering::Relaxed)
+                        && deadline.is_some()
+                    {
+                        self.emit(trace.session_id(), DaemonEvent::DiscoverableEnded);
+                    }
+                    trace.observe(&event);
+                    self.forward(event, trace.session_id());
+                }
+                _ = ticker.tick(), if deadline.is_some() && !engaged.load(Ordering::Relaxed) => {
+                    let remaining = deadline
+                        .map_or(0, |d| d.saturating_duration_since(Instant::now()).as_secs());
+                    if remaining == 0 {
+                        tracing::info!("可发现窗口已结束，停止广播");
+                        self.emit(trace.session_id(), DaemonEvent::DiscoverableEnded);
+                        break;
+                    }
+                    self.emit(
+                        trace.session_id(),
+                        DaemonEvent::Discoverable {
+                            remaining_secs: remaining,
+                            total_secs,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn forward(&self, event: ReceiveEvent, session_id: Option<&str>) {
+        let event = match event {
+            ReceiveEvent::Status(message) => DaemonEvent::Status { message },
+            ReceiveEvent::BleConnection(GattConnectionEvent::Connected { address, .. }) => {
+                DaemonEvent::Status {
+                    message: format!("发送端 {} 已通过蓝牙连接", address),
+                }
+            }
+            ReceiveEvent::BleConnection(GattConnectionEvent::Disconnected { address }) => {
+                DaemonEvent::Status {
+                    message: format!("发送端 {} 已断开蓝牙连接", address),
+                }
+            }
+            ReceiveEvent::VerificationCode(code) => DaemonEvent::Status {
+                message: format!("验证码: {}", code),
+            },
+            ReceiveEvent::Pairing(prompt) => DaemonEvent::Status {
+                message: format!("正在与 {} 配对，配对码 {}", prompt.address(), prompt.code()),
+            },
+            ReceiveEvent::Request(req) => DaemonEvent::Request {
+                sender_name: req.sender_name,
+                file_name: req.file_name,
+                file_count: req.file_count,
+                total_size: req.total_size,
+            },
+            ReceiveEvent::Progress { received, total } => DaemonEvent::Progress { received, total },
+            ReceiveEvent::Paused => DaemonEvent::Paused,
+            ReceiveEvent::Resumed => DaemonEvent::Resumed,
+            ReceiveEvent::Complete(files) => DaemonEvent::Complete {
+                files: files
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect(),
+            },
+            ReceiveEvent::Uploaded(path) => DaemonEvent::Status {
+                message: format!("收到对端反向上传: {}", path.display()),
+            },
+            ReceiveEvent::Error(message) => DaemonEvent::Error { message },
+            // IPC 客户端只需要 Progress；会话 ID 附在之后的每个事件上
+            ReceiveEvent::Stats(_) | ReceiveEvent::Started { .. } => return,
+        };
+        self.emit(session_id, event);
+    }
+}
+
+/// 把接收事件整理成会话 span 上的 `session_id` / `peer` / `phase` 字段
+///
+/// 阶段变化时额外记录一条日志，日志管道按 `session_id` 聚合即可还原整个会话。
+struct SessionTrace {
+    span: Span,
+    session_id: Option<String>,
+    phase: &'static str,
+    has_peer: bool,
+}
+
+impl SessionTrace {
+    fn new(span: Span) -> Self {
+        Self {
+            span,
+            session_id: None,
+            phase: "",
+            has_peer: false,
+        }
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    fn enter(&mut self, phase: &'static str) {
+        if self.phase != phase {
+            self.phase = phase;
+            self.span.record("phase", phase);
+            tracing::info!("接收阶段: {}", phase);
+        }
+    }
+
+    fn set_peer(&mut self, peer: &str) {
+        self.span.record("peer", peer);
+        self.has_peer = true;
+    }
+
+    fn observe(&mut self, event: &ReceiveEvent) {
+        match event {
+            ReceiveEvent::Started { session_id } => {
+                self.span.record("session_id", session_id.as_str());
+                self.session_id = Some(session_id.clone());
+                self.enter("advertising");
+            }
+            ReceiveEvent::BleConnection(GattConnectionEvent::Connected { address, .. }) => {
+                self.set_peer(address);
+                self.enter("connected");
+            }
+            ReceiveEvent::Request(req) => {
+                // 局域网握手没有 BLE 连接事件，用发送端名称标识对端
+                if !self.has_peer {
+                    self.set_peer(&req.sender_name);
+                }
+                self.enter("requested");
+            }
+            ReceiveEvent::Progress { .. } => self.enter("transferring"),
+            ReceiveEvent::Complete(_) => self.enter("complete"),
+            ReceiveEvent::Error(_) => self.enter("failed"),
+            _ => {}
+        }
+    }
+}
+
+/// 记录发送会话的 ID，并在工作流开始时告知等待中的 [`Service::start_send`]
+struct SendTrace {
+    session_id: Option<String>,
+    started: Option<oneshot::Sender<String>>,
+}
+
+impl SendTrace {
+    fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    fn observe(&mut self, event: &SendEvent) {
+        if let SendEvent::Started { session_id } = event {
+            if let Some(started) = self.started.take() {
+                let _ = started.send(session_id.clone());
+            }
+            self.session_id = Some(session_id.clone());
+        }
+    }
+}
+
+/// 按设置生成发送选项，`force` 时忽略电量下限
+fn send_options(settings: &AppSettings, force: bool) -> SendOptions {
+    SendOptions {
+        wifi_interface: settings.wifi_interface.clone(),
+        use_5ghz: settings.supports_5ghz,
+        sender_name: settings.device_name.clone(),
+        ports: settings.transfer_ports,
+        credentials: settings.hotspot_credentials,
+        hotspot_idle_timeout: settings.hotspot_idle_timeout(),
+        hotspot_interface: settings.hotspot_interface.clone(),
+        busy_interface: settings.busy_interface,
+        min_battery_percent: if force {
+            0
+        } else {
+            settings.min_battery_percent
+        },
+        ..Default::default()
+    }
+}
+
+pub async fn run_service(service: Arc<Service>) -> Result<()> {
     tracing::info!("核心服务初始化...");
 
-    // 生成加密密钥对（持久化，在服务生命周期内保持一致）
-    let security = BleSecurityPersistent::new()?;
-    let public_key = security.get_public_key().to_string();
+    let public_key = service.security.get_public_key().to_string();
 
     // 获取 P2P 接口 MAC 地址
     let mac = get_p2p_mac().unwrap_or_else(|| "02:00:00:00:00:00".to_string());
@@ -17,12 +866,52 @@ pub async fn run_service() -> Result<()> {
     let info = DeviceInfo::new(public_key, mac);
 
     tracing::info!("设备信息: {:?}", info);
+
+    // 上次崩溃留下的热点连接和 P2P 组；此时还没有任何传输
+    let orphans = wifi::cleanup_orphans().await;
+    if !orphans.is_empty() {
+        tracing::info!(
+            "已清理残留的热点: 连接 [{}]，P2P 组 [{}]",
+            orphans.connections.join(", "),
+            orphans.groups.join(", ")
+        );
+    }
+
+    // 残留的热点清理完之后才准备热备的热点
+    service.warm_up().await;
+
+    if service.settings.passive_receive {
+        if service.settings.trusted_networks.is_empty() {
+            if let Err(e) = service.start_passive().await {
+                tracing::warn!("无法进入被动监听模式: {}", e);
+            }
+        } else {
+            let watcher = service.clone();
+            tokio::spawn(async move { watcher.watch_trusted_networks().await });
+        }
+    }
+    if !service.settings.schedules.is_empty() {
+        tracing::info!("共 {} 条定时传输规则", service.settings.schedules.len());
+        scheduler::spawn_rules(&service, &service.settings.schedules);
+    }
+    if !service.settings.on_appear.is_empty() {
+        presence::spawn(&service, &service.settings.on_appear);
+    }
     tracing::info!("等待 IPC 命令...");
 
-    // 保持服务运行
-    loop {
-        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    // SIGUSR1 作为"快捷键"入口：桌面环境可以把全局快捷键绑定到
+    // `pkill -USR1 cattysend-daemon`，开启默认时长的可发现窗口
+    let mut hotkey = signal(SignalKind::user_defined1())?;
+    let window = Duration::from_secs(service.settings.discoverable_window_secs);
+
+    while hotkey.recv().await.is_some() {
+        tracing::info!("收到 SIGUSR1，开启 {}s 可发现窗口", window.as_secs());
+        if let Err(e) = service.start_receive(None, Some(window)).await {
+            tracing::warn!("无法进入接收模式: {}", e);
+        }
     }
+
+    Ok(())
 }
 
 fn get_p2p_mac() -> Option<String> {
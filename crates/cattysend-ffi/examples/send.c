@@ -0,0 +1,89 @@
+/*
+ * 扫描附近的设备并把文件发给第一个空闲的设备
+ *
+ *   cargo build --release -p cattysend-ffi
+ *   cc examples/send.c -Iinclude -L../../target/release -lcattysend_ffi -o send
+ *   LD_LIBRARY_PATH=../../target/release ./send photo.jpg notes.txt
+ *
+ * 回调在库的工作线程上调用；GTK / Qt 程序应在回调中用 g_idle_add /
+ * QMetaObject::invokeMethod 把事件转回主线程，这里直接打印。
+ */
+
+#include "cattysend.h"
+
+#include <inttypes.h>
+#include <stdio.h>
+#include <string.h>
+
+/* 扫描期间记住的第一个空闲设备（只在扫描回调中写入，扫描结束后读取） */
+static char target[64];
+
+static void on_device(const CattysendDevice *device, void *user_data) {
+  (void)user_data;
+  printf("found %s (%s, %s)%s\n", device->name, device->address, device->brand,
+         device->busy ? " [busy]" : "");
+  if (!device->busy && target[0] == '\0') {
+    snprintf(target, sizeof target, "%s", device->address);
+  }
+}
+
+static void on_event(const CattysendEvent *event, void *user_data) {
+  (void)user_data;
+  switch (event->kind) {
+  case CATTYSEND_EVENT_KIND_PROGRESS:
+    printf("\r%" PRIu64 " / %" PRIu64 " bytes", event->transferred, event->total);
+    fflush(stdout);
+    break;
+  case CATTYSEND_EVENT_KIND_VERIFICATION_CODE:
+    printf("verification code: %s\n", event->text);
+    break;
+  case CATTYSEND_EVENT_KIND_COMPLETE:
+    printf("\ndone\n");
+    break;
+  default:
+    if (event->text != NULL) {
+      printf("%s\n", event->text);
+    }
+    break;
+  }
+}
+
+static void on_done(CattysendResult result, const char *error, void *user_data) {
+  const char *what = (const char *)user_data;
+  if (result == CATTYSEND_RESULT_ERROR) {
+    fprintf(stderr, "%s failed: %s\n", what, error);
+  }
+}
+
+int main(int argc, char **argv) {
+  if (argc < 2) {
+    fprintf(stderr, "usage: %s FILE...\n", argv[0]);
+    return 2;
+  }
+
+  CattysendRuntime *runtime = cattysend_runtime_new();
+  if (runtime == NULL) {
+    fprintf(stderr, "failed to start runtime\n");
+    return 1;
+  }
+  printf("cattysend %s\n", cattysend_version());
+
+  CattysendTask *scan = cattysend_scan(runtime, 0, on_device, on_done, (void *)"scan");
+  CattysendResult result = cattysend_task_wait(scan);
+  cattysend_task_free(scan);
+
+  if (result == CATTYSEND_RESULT_OK && target[0] == '\0') {
+    fprintf(stderr, "no idle device found\n");
+    result = CATTYSEND_RESULT_ERROR;
+  }
+  if (result == CATTYSEND_RESULT_OK) {
+    const char *const *paths = (const char *const *)&argv[1];
+    CattysendTask *send = cattysend_send(runtime, target, paths, (size_t)(argc - 1),
+                                         on_event, on_done, (void *)"send");
+    result = send != NULL ? cattysend_task_wait(send) : CATTYSEND_RESULT_INVALID_ARGUMENT;
+    cattysend_task_free(send);
+  }
+
+  cattysend_runtime_free(runtime);
+  return result == CATTYSEND_RESULT_OK ? 0 : 1;
+}
@@ -0,0 +1,168 @@
+/* cattysend C API，由 cbindgen 根据 crates/cattysend-ffi/src/lib.rs 生成，不要手动修改 */
+
+#ifndef CATTYSEND_H
+#define CATTYSEND_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// 事件类型
+typedef enum CattysendEventKind {
+  // 状态文本
+  CATTYSEND_EVENT_KIND_STATUS = 0,
+  // 传输进度
+  CATTYSEND_EVENT_KIND_PROGRESS = 1,
+  // 与对端核对的验证码
+  CATTYSEND_EVENT_KIND_VERIFICATION_CODE = 2,
+  // 收到的一个文件（路径）
+  CATTYSEND_EVENT_KIND_FILE = 3,
+  // 传输完成
+  CATTYSEND_EVENT_KIND_COMPLETE = 4,
+  // 错误信息（操作不一定因此结束，结果以完成回调为准）
+  CATTYSEND_EVENT_KIND_ERROR = 5,
+} CattysendEventKind;
+
+// 操作结果
+typedef enum CattysendResult {
+  CATTYSEND_RESULT_OK = 0,
+  CATTYSEND_RESULT_ERROR = 1,
+  CATTYSEND_RESULT_CANCELLED = 2,
+  CATTYSEND_RESULT_INVALID_ARGUMENT = 3,
+} CattysendResult;
+
+// 运行时（不透明）
+typedef struct CattysendRuntime CattysendRuntime;
+
+// 进行中的扫描、发送或接收（不透明）
+typedef struct CattysendTask CattysendTask;
+
+// 发送或接收过程中的事件
+typedef struct CattysendEvent {
+  CattysendEventKind kind;
+  // 状态文本、验证码、文件路径或错误信息，没有时为 NULL
+  const char *text;
+  // 已传输的字节数（`PROGRESS`）
+  uint64_t transferred;
+  // 总字节数（`PROGRESS`）
+  uint64_t total;
+} CattysendEvent;
+
+// 扫描到的设备
+typedef struct CattysendDevice {
+  const char *name;
+  // 蓝牙地址或局域网标识，可以直接传给 [`cattysend_send`]
+  const char *address;
+  const char *brand;
+  // 信号强度（dBm），未知时为 0
+  int16_t rssi;
+  // 对端正在与其他设备传输
+  bool busy;
+} CattysendDevice;
+
+// 扫描到一个设备
+typedef void (*CattysendDeviceCallback)(const CattysendDevice *device, void *user_data);
+
+// 发送或接收的事件
+typedef void (*CattysendEventCallback)(const CattysendEvent *event, void *user_data);
+
+// 操作结束，`error` 只在结果为 `ERROR` 时非 NULL
+typedef void (*CattysendDoneCallback)(CattysendResult result, const char *error, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// 库版本（静态字符串，不需要释放）
+const char *cattysend_version(void);
+
+// 创建运行时，读取 `~/.config/cattysend/settings.toml`；失败时返回 NULL
+CattysendRuntime *cattysend_runtime_new(void);
+
+// 释放运行时，取消所有未结束的操作
+//
+// # Safety
+//
+// `runtime` 为 NULL 或由 [`cattysend_runtime_new`] 返回且尚未释放；
+// 不能在回调中调用。
+void cattysend_runtime_free(CattysendRuntime *runtime);
+
+// 扫描附近的接收端，每发现一个设备调用一次 `on_device`
+//
+// `timeout_ms` 为 0 时扫描 5 秒。`runtime` 为 NULL 时返回 NULL。
+//
+// # Safety
+//
+// `runtime` 为 NULL 或有效的运行时，且在返回的句柄结束前不被释放。
+CattysendTask *cattysend_scan(CattysendRuntime *runtime,
+                              uint32_t timeout_ms,
+                              CattysendDeviceCallback on_device,
+                              CattysendDoneCallback on_done,
+                              void *user_data);
+
+// 把 `paths` 中的文件发送给 `target`（地址或名称）
+//
+// `target` 先在最近一次扫描的结果中查找，找不到时重新扫描。
+// 参数无效时返回 NULL，不调用回调。
+//
+// # Safety
+//
+// - `runtime` 为 NULL 或有效的运行时，且在返回的句柄结束前不被释放
+// - `target` 为以 NUL 结尾的 UTF-8 字符串
+// - `paths` 指向 `path_count` 个以 NUL 结尾的字符串
+CattysendTask *cattysend_send(CattysendRuntime *runtime,
+                              const char *target,
+                              const char *const *paths,
+                              size_t path_count,
+                              CattysendEventCallback on_event,
+                              CattysendDoneCallback on_done,
+                              void *user_data);
+
+// 进入接收模式，处理一个发送端后结束
+//
+// `output_dir` 为 NULL 时使用设置中的下载目录。`auto_accept` 为 false 时拒绝所有请求。
+// `runtime` 为 NULL 时返回 NULL。
+//
+// # Safety
+//
+// - `runtime` 为 NULL 或有效的运行时，且在返回的句柄结束前不被释放
+// - `output_dir` 为 NULL 或以 NUL 结尾的字符串
+CattysendTask *cattysend_receive(CattysendRuntime *runtime,
+                                 const char *output_dir,
+                                 bool auto_accept,
+                                 CattysendEventCallback on_event,
+                                 CattysendDoneCallback on_done,
+                                 void *user_data);
+
+// 取消操作，完成回调随后以 `CANCELLED` 调用
+//
+// # Safety
+//
+// `task` 为 NULL 或尚未释放的句柄。
+void cattysend_task_cancel(CattysendTask *task);
+
+// 阻塞等待操作结束（完成回调已经调用），返回结果
+//
+// 每个句柄只能等待一次，再次等待返回 `INVALID_ARGUMENT`。不能在回调中调用。
+//
+// # Safety
+//
+// `task` 为 NULL 或尚未释放的句柄。
+CattysendResult cattysend_task_wait(CattysendTask *task);
+
+// 释放句柄，未结束的操作随之取消
+//
+// 没有等待过的句柄会阻塞到操作收尾（完成回调照常以 `CANCELLED` 等结果调用），
+// 返回后不会再有任何回调，调用方可以立即释放 `user_data`。不能在回调中调用。
+//
+// # Safety
+//
+// `task` 为 NULL 或尚未释放的句柄。
+void cattysend_task_free(CattysendTask *task);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CATTYSEND_H */
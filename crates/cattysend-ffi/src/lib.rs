@@ -0,0 +1,645 @@
+//! cattysend 的 C 接口
+//!
+//! 供 C / C++ / Python（ctypes、cffi）编写的 GTK、Qt 程序嵌入协议。
+//! 头文件 `include/cattysend.h` 由 cbindgen 生成（配置见 `cbindgen.toml`），
+//! 示例程序见 `examples/send.c`。
+//!
+//! - [`CattysendRuntime`] 持有 tokio 运行时和从 `settings.toml` 读取的设置
+//! - 扫描、发送、接收立即返回 [`CattysendTask`] 句柄，事件和结果通过回调报告
+//! - 回调在运行时的工作线程上调用：GTK 程序用 `g_idle_add`、Qt 程序用
+//!   `QMetaObject::invokeMethod` 转回主线程；回调收到的指针只在回调期间有效，
+//!   `user_data` 必须可以跨线程使用
+//! - 句柄用 [`cattysend_task_free`] 释放：未结束的操作随之取消，释放返回后不会再有回调
+
+use cattysend_core::api::{Cattysend, DEFAULT_SCAN_TIMEOUT, Device, Task};
+use cattysend_core::discovery::DiscoveryMethod;
+use cattysend_core::{
+    AppSettings, AtRestKey, CancellationToken, DeviceMatch, DiscoveredDevice, ReceiveEvent,
+    ReceiveOptions, SendEvent, SendOptions, cancel, find_device,
+};
+use futures_util::StreamExt;
+use std::ffi::{CStr, CString, OsStr, c_char, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+
+/// 释放运行时时等待后台任务收尾的时间
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// 运行时（不透明）
+pub struct CattysendRuntime {
+    runtime: Runtime,
+    settings: AppSettings,
+    /// 最近一次扫描的结果，[`cattysend_send`] 先在其中查找目标
+    devices: Arc<Mutex<Vec<DiscoveredDevice>>>,
+}
+
+/// 进行中的扫描、发送或接收（不透明）
+pub struct CattysendTask {
+    cancel: CancellationToken,
+    handle: Option<JoinHandle<CattysendResult>>,
+    runtime: tokio::runtime::Handle,
+}
+
+/// 操作结果
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CattysendResult {
+    Ok = 0,
+    Error = 1,
+    Cancelled = 2,
+    InvalidArgument = 3,
+}
+
+/// 事件类型
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CattysendEventKind {
+    /// 状态文本
+    Status = 0,
+    /// 传输进度
+    Progress = 1,
+    /// 与对端核对的验证码
+    VerificationCode = 2,
+    /// 收到的一个文件（路径）
+    File = 3,
+    /// 传输完成
+    Complete = 4,
+    /// 错误信息（操作不一定因此结束，结果以完成回调为准）
+    Error = 5,
+}
+
+/// 发送或接收过程中的事件
+#[repr(C)]
+pub struct CattysendEvent {
+    pub kind: CattysendEventKind,
+    /// 状态文本、验证码、文件路径或错误信息，没有时为 NULL
+    pub text: *const c_char,
+    /// 已传输的字节数（`PROGRESS`）
+    pub transferred: u64,
+    /// 总字节数（`PROGRESS`）
+    pub total: u64,
+}
+
+/// 扫描到的设备
+#[repr(C)]
+pub struct CattysendDevice {
+    pub name: *const c_char,
+    /// 蓝牙地址或局域网标识，可以直接传给 [`cattysend_send`]
+    pub address: *const c_char,
+    pub brand: *const c_char,
+    /// 信号强度（dBm），未知时为 0
+    pub rssi: i16,
+    /// 对端正在与其他设备传输
+    pub busy: bool,
+}
+
+/// 扫描到一个设备
+pub type CattysendDeviceCallback =
+    Option<unsafe extern "C" fn(device: *const CattysendDevice, user_data: *mut c_void)>;
+
+/// 发送或接收的事件
+pub type CattysendEventCallback =
+    Option<unsafe extern "C" fn(event: *const CattysendEvent, user_data: *mut c_void)>;
+
+/// 操作结束，`error` 只在结果为 `ERROR` 时非 NULL
+pub type CattysendDoneCallback = Option<
+    unsafe extern "C" fn(result: CattysendResult, error: *const c_char, user_data: *mut c_void),
+>;
+
+/// 调用方的 `user_data`
+#[derive(Clone, Copy)]
+struct UserData(*mut c_void);
+
+// SAFETY: 调用方保证 user_data 可以在运行时的工作线程上使用（见模块文档）
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    fn ptr(self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// 去掉内部的 NUL 后转换为 C 字符串
+fn c_string(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+fn as_ptr(s: Option<&CString>) -> *const c_char {
+    s.map_or(ptr::null(), |s| s.as_ptr())
+}
+
+/// 读取调用方传入的 UTF-8 字符串
+///
+/// # Safety
+///
+/// `s` 为 NULL 或指向以 NUL 结尾的字符串。
+unsafe fn read_str(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    // SAFETY: 由调用方保证
+    let s = unsafe { CStr::from_ptr(s) };
+    s.to_str().ok().map(str::to_string)
+}
+
+/// 读取路径（不要求 UTF-8）
+///
+/// # Safety
+///
+/// `s` 为 NULL 或指向以 NUL 结尾的字符串。
+unsafe fn read_path(s: *const c_char) -> Option<PathBuf> {
+    if s.is_null() {
+        return None;
+    }
+    // SAFETY: 由调用方保证
+    let bytes = unsafe { CStr::from_ptr(s) }.to_bytes();
+    (!bytes.is_empty()).then(|| PathBuf::from(OsStr::from_bytes(bytes)))
+}
+
+fn emit(
+    callback: CattysendEventCallback,
+    user: UserData,
+    kind: CattysendEventKind,
+    text: Option<&str>,
+    transferred: u64,
+    total: u64,
+) {
+    let Some(callback) = callback else {
+        return;
+    };
+    let text = text.map(c_string);
+    let event = CattysendEvent {
+        kind,
+        text: as_ptr(text.as_ref()),
+        transferred,
+        total,
+    };
+    // SAFETY: 回调由调用方提供，事件在调用期间有效
+    unsafe { callback(&event, user.ptr()) };
+}
+
+fn emit_send(callback: CattysendEventCallback, user: UserData, event: SendEvent) {
+    use CattysendEventKind as Kind;
+    match event {
+        SendEvent::Status(status) => emit(callback, user, Kind::Status, Some(&status), 0, 0),
+        SendEvent::Progress { sent, total } => {
+            emit(callback, user, Kind::Progress, None, sent, total);
+        }
+        SendEvent::VerificationCode(code) => {
+            emit(callback, user, Kind::VerificationCode, Some(&code), 0, 0);
+        }
+        SendEvent::Complete => emit(callback, user, Kind::Complete, None, 0, 0),
+        SendEvent::Error(error) => emit(callback, user, Kind::Error, Some(&error), 0, 0),
+        _ => {}
+    }
+}
+
+fn emit_receive(callback: CattysendEventCallback, user: UserData, event: ReceiveEvent) {
+    use CattysendEventKind as Kind;
+    match event {
+        ReceiveEvent::Status(status) => emit(callback, user, Kind::Status, Some(&status), 0, 0),
+        ReceiveEvent::Progress { received, total } => {
+            emit(callback, user, Kind::Progress, None, received, total);
+        }
+        ReceiveEvent::VerificationCode(code) => {
+            emit(callback, user, Kind::VerificationCode, Some(&code), 0, 0);
+        }
+        ReceiveEvent::Complete(files) => {
+            for file in files {
+                let path = file.to_string_lossy();
+                emit(callback, user, Kind::File, Some(&path), 0, 0);
+            }
+            emit(callback, user, Kind::Complete, None, 0, 0);
+        }
+        ReceiveEvent::Error(error) => emit(callback, user, Kind::Error, Some(&error), 0, 0),
+        _ => {}
+    }
+}
+
+fn report_device(callback: CattysendDeviceCallback, user: UserData, device: &DiscoveredDevice) {
+    let Some(callback) = callback else {
+        return;
+    };
+    let name = c_string(&device.name);
+    let address = c_string(&device.address);
+    let brand = c_string(&device.brand);
+    let device = CattysendDevice {
+        name: name.as_ptr(),
+        address: address.as_ptr(),
+        brand: brand.as_ptr(),
+        rssi: device.rssi.unwrap_or(0),
+        busy: device.busy,
+    };
+    // SAFETY: 回调由调用方提供，设备信息在调用期间有效
+    unsafe { callback(&device, user.ptr()) };
+}
+
+/// 调用完成回调，返回结果码
+fn finish<T>(
+    result: &anyhow::Result<T>,
+    callback: CattysendDoneCallback,
+    user: UserData,
+) -> CattysendResult {
+    let (code, error) = match result {
+        Ok(_) => (CattysendResult::Ok, None),
+        Err(e) if cancel::is_cancelled(e) => (CattysendResult::Cancelled, None),
+        Err(e) => (CattysendResult::Error, Some(c_string(&format!("{:#}", e)))),
+    };
+    if let Some(callback) = callback {
+        // SAFETY: 回调由调用方提供，错误信息在调用期间有效
+        unsafe { callback(code, as_ptr(error.as_ref()), user.ptr()) };
+    }
+    code
+}
+
+/// 把 `task` 的事件交给 `on_event`，`cancel` 取消时取消 `task`，返回其结果
+async fn forward<E, T>(
+    mut task: Task<E, T>,
+    cancel: &CancellationToken,
+    mut on_event: impl FnMut(E),
+) -> anyhow::Result<T>
+where
+    E: Send + 'static,
+    T: Send + 'static,
+{
+    let mut cancelled = false;
+    loop {
+        tokio::select! {
+            () = cancel.cancelled(), if !cancelled => {
+                task.cancel();
+                cancelled = true;
+            }
+            event = task.next() => match event {
+                Some(event) => on_event(event),
+                None => break,
+            },
+        }
+    }
+    task.await
+}
+
+/// 在运行时上启动 `run`，返回交给调用方的句柄
+fn spawn<F, Fut>(runtime: &CattysendRuntime, run: F) -> *mut CattysendTask
+where
+    F: FnOnce(CancellationToken) -> Fut,
+    Fut: Future<Output = CattysendResult> + Send + 'static,
+{
+    let cancel = CancellationToken::new();
+    let handle = runtime.runtime.spawn(run(cancel.clone()));
+    Box::into_raw(Box::new(CattysendTask {
+        cancel,
+        handle: Some(handle),
+        runtime: runtime.runtime.handle().clone(),
+    }))
+}
+
+/// 在最近一次扫描的结果中查找 `target`，找不到时重新扫描
+async fn resolve(
+    devices: &Mutex<Vec<DiscoveredDevice>>,
+    target: &str,
+    cancel: &CancellationToken,
+) -> anyhow::Result<DiscoveredDevice> {
+    let cached = devices.lock().unwrap().clone();
+    if let DeviceMatch::Found(device) = find_device(&cached, target) {
+        return Ok(device);
+    }
+    let found: Vec<DiscoveredDevice> = forward(Cattysend::discover(), cancel, |_| {})
+        .await?
+        .iter()
+        .map(|d| d.info().clone())
+        .collect();
+    let result = find_device(&found, target);
+    *devices.lock().unwrap() = found;
+    match result {
+        DeviceMatch::Found(device) => Ok(device),
+        DeviceMatch::Ambiguous(matches) => anyhow::bail!(
+            "{} devices match {:?}, use the address instead",
+            matches.len(),
+            target
+        ),
+        DeviceMatch::NotFound => anyhow::bail!("no device matches {:?}", target),
+    }
+}
+
+/// 库版本（静态字符串，不需要释放）
+#[unsafe(no_mangle)]
+pub extern "C" fn cattysend_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// 创建运行时，读取 `~/.config/cattysend/settings.toml`；失败时返回 NULL
+#[unsafe(no_mangle)]
+pub extern "C" fn cattysend_runtime_new() -> *mut CattysendRuntime {
+    let Ok(runtime) = Runtime::new() else {
+        return ptr::null_mut();
+    };
+    Box::into_raw(Box::new(CattysendRuntime {
+        runtime,
+        settings: AppSettings::load(),
+        devices: Arc::default(),
+    }))
+}
+
+/// 释放运行时，取消所有未结束的操作
+///
+/// # Safety
+///
+/// `runtime` 为 NULL 或由 [`cattysend_runtime_new`] 返回且尚未释放；
+/// 不能在回调中调用。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_runtime_free(runtime: *mut CattysendRuntime) {
+    if runtime.is_null() {
+        return;
+    }
+    // SAFETY: 由调用方保证
+    let runtime = unsafe { Box::from_raw(runtime) };
+    runtime.runtime.shutdown_timeout(SHUTDOWN_GRACE);
+}
+
+/// 扫描附近的接收端，每发现一个设备调用一次 `on_device`
+///
+/// `timeout_ms` 为 0 时扫描 5 秒。`runtime` 为 NULL 时返回 NULL。
+///
+/// # Safety
+///
+/// `runtime` 为 NULL 或有效的运行时，且在返回的句柄结束前不被释放。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_scan(
+    runtime: *mut CattysendRuntime,
+    timeout_ms: u32,
+    on_device: CattysendDeviceCallback,
+    on_done: CattysendDoneCallback,
+    user_data: *mut c_void,
+) -> *mut CattysendTask {
+    // SAFETY: 由调用方保证
+    let Some(runtime) = (unsafe { runtime.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    let timeout = match timeout_ms {
+        0 => DEFAULT_SCAN_TIMEOUT,
+        ms => Duration::from_millis(u64::from(ms)),
+    };
+    let devices = runtime.devices.clone();
+    let user = UserData(user_data);
+    spawn(runtime, move |cancel| async move {
+        let task = Cattysend::discover_with(DiscoveryMethod::default(), timeout);
+        let result = forward(task, &cancel, |device: Device| {
+            report_device(on_device, user, device.info());
+        })
+        .await;
+        if let Ok(found) = &result {
+            *devices.lock().unwrap() = found.iter().map(|d| d.info().clone()).collect();
+        }
+        finish(&result, on_done, user)
+    })
+}
+
+/// 把 `paths` 中的文件发送给 `target`（地址或名称）
+///
+/// `target` 先在最近一次扫描的结果中查找，找不到时重新扫描。
+/// 参数无效时返回 NULL，不调用回调。
+///
+/// # Safety
+///
+/// - `runtime` 为 NULL 或有效的运行时，且在返回的句柄结束前不被释放
+/// - `target` 为以 NUL 结尾的 UTF-8 字符串
+/// - `paths` 指向 `path_count` 个以 NUL 结尾的字符串
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_send(
+    runtime: *mut CattysendRuntime,
+    target: *const c_char,
+    paths: *const *const c_char,
+    path_count: usize,
+    on_event: CattysendEventCallback,
+    on_done: CattysendDoneCallback,
+    user_data: *mut c_void,
+) -> *mut CattysendTask {
+    // SAFETY: 由调用方保证
+    let Some(runtime) = (unsafe { runtime.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    // SAFETY: 由调用方保证
+    let Some(target) = (unsafe { read_str(target) }) else {
+        return ptr::null_mut();
+    };
+    if paths.is_null() || path_count == 0 {
+        return ptr::null_mut();
+    }
+    // SAFETY: 由调用方保证
+    let raw_paths = unsafe { std::slice::from_raw_parts(paths, path_count) };
+    // SAFETY: 由调用方保证
+    let Some(files) = raw_paths
+        .iter()
+        .map(|&p| unsafe { read_path(p) })
+        .collect::<Option<Vec<_>>>()
+    else {
+        return ptr::null_mut();
+    };
+
+    let settings = &runtime.settings;
+    let options = SendOptions {
+        wifi_interface: settings.wifi_interface.clone(),
+        use_5ghz: settings.supports_5ghz,
+        sender_name: settings.device_name.clone(),
+        ports: settings.transfer_ports,
+        credentials: settings.hotspot_credentials,
+        hotspot_idle_timeout: settings.hotspot_idle_timeout(),
+        hotspot_interface: settings.hotspot_interface.clone(),
+        busy_interface: settings.busy_interface,
+        min_battery_percent: settings.min_battery_percent,
+        ..Default::default()
+    };
+    let devices = runtime.devices.clone();
+    let user = UserData(user_data);
+    spawn(runtime, move |cancel| async move {
+        let result = async {
+            let device = resolve(&devices, &target, &cancel).await?;
+            let task = Device::from(device).send(files, options);
+            forward(task, &cancel, |event| emit_send(on_event, user, event)).await
+        }
+        .await;
+        finish(&result, on_done, user)
+    })
+}
+
+/// 进入接收模式，处理一个发送端后结束
+///
+/// `output_dir` 为 NULL 时使用设置中的下载目录。`auto_accept` 为 false 时拒绝所有请求。
+/// `runtime` 为 NULL 时返回 NULL。
+///
+/// # Safety
+///
+/// - `runtime` 为 NULL 或有效的运行时，且在返回的句柄结束前不被释放
+/// - `output_dir` 为 NULL 或以 NUL 结尾的字符串
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_receive(
+    runtime: *mut CattysendRuntime,
+    output_dir: *const c_char,
+    auto_accept: bool,
+    on_event: CattysendEventCallback,
+    on_done: CattysendDoneCallback,
+    user_data: *mut c_void,
+) -> *mut CattysendTask {
+    // SAFETY: 由调用方保证
+    let Some(runtime) = (unsafe { runtime.as_ref() }) else {
+        return ptr::null_mut();
+    };
+    let settings = &runtime.settings;
+    // SAFETY: 由调用方保证
+    let output_dir =
+        unsafe { read_path(output_dir) }.unwrap_or_else(|| settings.download_dir.clone());
+    let encryption_key_file = settings.encryption_key_file.clone();
+    let options = ReceiveOptions {
+        device_name: settings.device_name.clone(),
+        wifi_interface: settings.wifi_interface.clone(),
+        output_dir,
+        auto_accept,
+        brand_id: settings.brand_id,
+        supports_5ghz: settings.supports_5ghz,
+        power_profile: settings.power_profile,
+        battery_saver: settings.battery_saver,
+        sort_by_sender: settings.sort_by_sender,
+        allow_sync_delete: settings.allow_sync_delete,
+        group_owner: settings.group_owner,
+        ..Default::default()
+    };
+    let user = UserData(user_data);
+    spawn(runtime, move |cancel| async move {
+        let result = async {
+            // 配置了密钥却读不出来时不接收，避免静默退回明文保存
+            let encryption_key = match &encryption_key_file {
+                Some(path) => Some(AtRestKey::load(path)?),
+                None => None,
+            };
+            let task = Cattysend::receive(ReceiveOptions {
+                encryption_key,
+                ..options
+            });
+            forward(task, &cancel, |event| emit_receive(on_event, user, event)).await
+        }
+        .await;
+        finish(&result, on_done, user)
+    })
+}
+
+/// 取消操作，完成回调随后以 `CANCELLED` 调用
+///
+/// # Safety
+///
+/// `task` 为 NULL 或尚未释放的句柄。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_task_cancel(task: *mut CattysendTask) {
+    // SAFETY: 由调用方保证
+    if let Some(task) = unsafe { task.as_ref() } {
+        task.cancel.cancel();
+    }
+}
+
+/// 阻塞等待操作结束（完成回调已经调用），返回结果
+///
+/// 每个句柄只能等待一次，再次等待返回 `INVALID_ARGUMENT`。不能在回调中调用。
+///
+/// # Safety
+///
+/// `task` 为 NULL 或尚未释放的句柄。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_task_wait(task: *mut CattysendTask) -> CattysendResult {
+    // SAFETY: 由调用方保证
+    let Some(task) = (unsafe { task.as_mut() }) else {
+        return CattysendResult::InvalidArgument;
+    };
+    match task.handle.take() {
+        Some(handle) => task
+            .runtime
+            .block_on(handle)
+            .unwrap_or(CattysendResult::Error),
+        None => CattysendResult::InvalidArgument,
+    }
+}
+
+/// 释放句柄，未结束的操作随之取消
+///
+/// 没有等待过的句柄会阻塞到操作收尾（完成回调照常以 `CANCELLED` 等结果调用），
+/// 返回后不会再有任何回调，调用方可以立即释放 `user_data`。不能在回调中调用。
+///
+/// # Safety
+///
+/// `task` 为 NULL 或尚未释放的句柄。
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn cattysend_task_free(task: *mut CattysendTask) {
+    if task.is_null() {
+        return;
+    }
+    // SAFETY: 由调用方保证
+    let mut task = unsafe { Box::from_raw(task) };
+    task.cancel.cancel();
+    if let Some(handle) = task.handle.take() {
+        let _ = task.runtime.block_on(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_c_string_strips_nul() {
+        assert_eq!(c_string("a\0b").as_bytes(), b"ab");
+        assert!(as_ptr(None).is_null());
+    }
+
+    #[test]
+    fn test_read_path_accepts_non_utf8() {
+        let raw = CString::new(b"/tmp/\xff.txt".to_vec()).unwrap();
+        let path = unsafe { read_path(raw.as_ptr()) }.unwrap();
+        assert_eq!(path.as_os_str().as_bytes(), b"/tmp/\xff.txt");
+        assert_eq!(unsafe { read_str(raw.as_ptr()) }, None);
+        assert_eq!(unsafe { read_path(ptr::null()) }, None);
+    }
+
+    /// 释放返回后，操作的回调都已经结束
+    #[test]
+    fn test_no_callback_after_free() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        let runtime = CattysendRuntime {
+            runtime: Runtime::new().unwrap(),
+            settings: AppSettings::default(),
+            devices: Arc::default(),
+        };
+        let task = spawn(&runtime, |cancel| async move {
+            cancel.cancelled().await;
+            // 收尾需要一点时间，完成回调在这之后才调用
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            CattysendResult::Cancelled
+        });
+        unsafe { cattysend_task_free(task) };
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_null_arguments_are_rejected() {
+        unsafe {
+            assert!(cattysend_scan(ptr::null_mut(), 0, None, None, ptr::null_mut()).is_null());
+            assert_eq!(
+                cattysend_task_wait(ptr::null_mut()),
+                CattysendResult::InvalidArgument
+            );
+            cattysend_task_cancel(ptr::null_mut());
+            cattysend_task_free(ptr::null_mut());
+            cattysend_runtime_free(ptr::null_mut());
+        }
+        let version = unsafe { CStr::from_ptr(cattysend_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+}
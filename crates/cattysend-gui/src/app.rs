@@ -7,14 +7,24 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
-use crate::components::{DeviceList, Header, ModeSelector, TransferPanel};
-use crate::state::{AppMode, DiscoveredDeviceInfo, TransferStatus};
+use crate::clipboard;
+use crate::components::{
+    DeviceList, Header, ModeSelector, PayloadInput, QrPayload, ReceivedFiles, TransferPanel,
+    VerificationPin,
+};
+use crate::state::{AppMode, DiscoveredDeviceInfo, ReceivedFile, TransferStatus};
 use crate::styles::GLOBAL_CSS;
+use crate::tray::{self, TrayAction, TrayHandle, TrayState};
 
+use cattysend_core::autostart::{self, Autostart, AutostartMode};
+use cattysend_core::logging;
+use cattysend_core::wifi::NmPermissionDenied;
 use cattysend_core::{
-    AppSettings, BleScanner, BrandId, ChannelScanCallback, DiscoveredDevice, LogEntry, LogLevel,
-    ReceiveEvent, ReceiveOptions, Receiver, SendEvent, SendOptions, Sender, SimpleReceiveCallback,
-    SimpleSendCallback,
+    AppSettings, AtRestKey, BleScanner, BleSecurityPersistent, BrandId, CancellationToken,
+    ChannelScanCallback, DaemonLogs, DiscoveredDevice, Favorites, GattConnectionEvent,
+    IdentityStore, LogEntry, LogLevel, ReceiveEvent, ReceiveOptions, Receiver, ScanOptions,
+    SendEvent, SendOptions, SendPhase, Sender, SimpleReceiveCallback, SimpleSendCallback,
+    TransferControl, cancel, tr,
 };
 
 /// 异步事件，用于从后台任务更新 UI
@@ -24,6 +34,12 @@ enum GuiEvent {
     ScanFinished,
     TransferStatusUpdate(TransferStatus),
     ReceiveStatusUpdate(ReceiveState),
+    /// 下载暂停（`true`）或恢复（`false`）
+    ReceivePaused(bool),
+    /// 接收端握手得到的验证码
+    VerificationCode(String),
+    /// 二维码引导的载荷已生成
+    BootstrapPayload(String),
     Log(LogLevel, String),
     Error(String),
 }
@@ -32,7 +48,7 @@ enum GuiEvent {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ReceiveState {
     Idle,
-    #[expect(dead_code, reason = "接收流程中间状态，保留用于未来状态机完善")]
+    /// 按二维码载荷接收，尚未接入发送端
     Starting,
     Advertising {
         device_name: String,
@@ -46,11 +62,19 @@ pub enum ReceiveState {
         file_name: String,
     },
     Completed {
-        files: Vec<PathBuf>,
+        files: Vec<ReceivedFile>,
     },
     Error(String),
 }
 
+/// 发送目标
+enum SendTarget {
+    /// 扫描到的设备，通过 BLE 握手交换 P2P 信息
+    Device(DiscoveredDevice),
+    /// 显示二维码由接收端扫描；给出接收端公钥时加密
+    Payload { peer_key: Option<String> },
+}
+
 /// 主应用
 #[component]
 pub fn App() -> Element {
@@ -61,44 +85,88 @@ pub fn App() -> Element {
     let mut selected_device = use_signal(|| Option::<String>::None);
     let mut selected_files = use_signal(Vec::<PathBuf>::new);
     let mut settings = use_signal(AppSettings::load);
+    let mut favorites = use_signal(Favorites::load);
+    // 本机身份密钥（与守护进程共用）；加载失败时收发各自使用临时密钥
+    let mut identity = use_signal(|| Option::<Arc<BleSecurityPersistent>>::None);
+    // 等待接收端扫描的二维码载荷
+    let mut bootstrap_payload = use_signal(|| Option::<String>::None);
 
     // === 接收 & 日志状态 ===
     let mut receive_state = use_signal(|| ReceiveState::Idle);
+    let mut receive_paused = use_signal(|| false);
+    let mut receive_code = use_signal(|| Option::<String>::None);
+    let mut receive_control = use_signal(|| Option::<TransferControl>::None);
     let mut logs = use_signal(Vec::<LogEntry>::new);
     let log_filter = use_signal(|| LogLevel::Info);
 
     // === 任务管理 ===
     let mut active_receive_task = use_signal(|| Option::<dioxus::prelude::Task>::None);
     let mut active_send_task = use_signal(|| Option::<dioxus::prelude::Task>::None);
+    // 取消按钮通过令牌通知任务，任务关闭热点、断开网络后自行结束
+    let mut send_cancel = use_signal(|| Option::<CancellationToken>::None);
+    let mut receive_cancel = use_signal(|| Option::<CancellationToken>::None);
 
     // === 事件处理循环 (协程) ===
     let event_handler = use_coroutine(move |mut rx: UnboundedReceiver<GuiEvent>| async move {
         while let Some(event) = rx.next().await {
             match event {
                 GuiEvent::DeviceFound(device) => {
+                    favorites.with_mut(|f| f.touch(std::slice::from_ref(&device)));
                     devices.with_mut(|devs| {
-                        if !devs.iter().any(|d| d.address == device.address) {
-                            devs.push(DiscoveredDeviceInfo {
-                                name: device.name.clone(),
-                                address: device.address.clone(),
-                                rssi: device.rssi.unwrap_or(-100),
-                                brand: Some(device.brand.clone()),
-                                brand_id: device.brand_id,
-                                sender_id: device.sender_id.clone(),
-                                supports_5ghz: device.supports_5ghz,
-                            });
+                        let info = DiscoveredDeviceInfo {
+                            name: device.name.clone(),
+                            address: device.address.clone(),
+                            rssi: device.rssi.unwrap_or(-100),
+                            brand: Some(device.brand.clone()),
+                            brand_id: device.brand_id,
+                            sender_id: device.sender_id.clone(),
+                            supports_5ghz: device.supports_5ghz,
+                            supports_wpa3: device.supports_wpa3,
+                        };
+                        // 同一设备换了随机地址时替换原来的条目
+                        let same = |d: &DiscoveredDeviceInfo| {
+                            d.address == device.address
+                                || device.identity()
+                                    == Some((d.sender_id.as_str(), d.name.as_str()))
+                        };
+                        match devs.iter_mut().find(|d| same(d)) {
+                            Some(existing) => *existing = info,
+                            None => devs.push(info),
                         }
                     });
                 }
                 GuiEvent::ScanFinished => {
                     status.set(TransferStatus::Idle);
+                    if let Err(e) = favorites.read().save() {
+                        log::warn!("Failed to save favorites: {}", e);
+                    }
                 }
                 GuiEvent::TransferStatusUpdate(s) => {
+                    // 接收端接入、开始传输后不再需要二维码
+                    if !matches!(s, TransferStatus::Connecting(_)) {
+                        bootstrap_payload.set(None);
+                    }
                     status.set(s);
                 }
                 GuiEvent::ReceiveStatusUpdate(s) => {
                     receive_state.set(s);
                 }
+                GuiEvent::BootstrapPayload(payload) => {
+                    bootstrap_payload.set(Some(payload));
+                }
+                GuiEvent::VerificationCode(code) => {
+                    receive_code.set(Some(code));
+                }
+                GuiEvent::ReceivePaused(paused) => {
+                    receive_paused.set(paused);
+                    // 下载阶段还没有进度事件，暂停时先切到接收界面
+                    if !matches!(*receive_state.read(), ReceiveState::Receiving { .. }) {
+                        receive_state.set(ReceiveState::Receiving {
+                            progress: 0.0,
+                            file_name: tr!("gui.receive.receiving_file"),
+                        });
+                    }
+                }
                 GuiEvent::Log(level, msg) => {
                     logs.with_mut(|l| {
                         l.push(LogEntry {
@@ -111,6 +179,7 @@ pub fn App() -> Element {
                     });
                 }
                 GuiEvent::Error(msg) => {
+                    bootstrap_payload.set(None);
                     status.set(TransferStatus::Error(msg.clone()));
                     logs.with_mut(|l| {
                         l.push(LogEntry {
@@ -125,10 +194,24 @@ pub fn App() -> Element {
 
     // 初始化日志
     use_effect(move || {
-        event_handler.send(GuiEvent::Log(
-            LogLevel::Info,
-            "Cattysend GUI 已启动".to_string(),
-        ));
+        event_handler.send(GuiEvent::Log(LogLevel::Info, tr!("gui.log.started")));
+    });
+
+    // 加载本机身份（密钥环访问是阻塞的）
+    use_effect(move || {
+        spawn(async move {
+            let loaded = tokio::task::spawn_blocking(|| IdentityStore::default().load_or_create())
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result);
+            match loaded {
+                Ok(security) => identity.set(Some(Arc::new(security))),
+                Err(e) => event_handler.send(GuiEvent::Log(
+                    LogLevel::Warn,
+                    tr!("gui.log.identity_fallback", error = e),
+                )),
+            }
+        });
     });
 
     // === 扫描逻辑 ===
@@ -137,6 +220,10 @@ pub fn App() -> Element {
         status.set(TransferStatus::Scanning);
 
         let tx_coroutine = event_handler;
+        let options = ScanOptions {
+            learn: settings.read().learn_unknown_vendors,
+            ..Default::default()
+        };
         spawn(async move {
             let (tx_mpsc, mut rx_mpsc) = mpsc::channel(100);
 
@@ -153,20 +240,41 @@ pub fn App() -> Element {
             match BleScanner::new().await {
                 Ok(scanner) => {
                     let _ = scanner
+                        .with_options(options)
                         .scan(Duration::from_secs(10), Some(Arc::new(callback)))
                         .await;
                     tx_coroutine.send(GuiEvent::ScanFinished);
                 }
-                Err(e) => tx_coroutine.send(GuiEvent::Error(format!("扫描失败: {}", e))),
+                Err(e) => tx_coroutine.send(GuiEvent::Error(tr!("gui.error.scan", error = e))),
             }
         });
     };
 
     // === 文件选择逻辑 ===
+    let on_toggle_favorite = move |address: String| {
+        let name = devices
+            .read()
+            .iter()
+            .find(|d| d.address == address)
+            .map_or_else(|| address.clone(), |d| d.name.clone());
+        let added = favorites.with_mut(|f| f.toggle(&name, &address));
+        let message = if added {
+            tr!("gui.log.favorite_added", name = name)
+        } else {
+            tr!("gui.log.favorite_removed", name = name)
+        };
+        match favorites.read().save() {
+            Ok(()) => event_handler.send(GuiEvent::Log(LogLevel::Info, message)),
+            Err(e) => {
+                event_handler.send(GuiEvent::Error(tr!("gui.error.save_favorites", error = e)))
+            }
+        }
+    };
+
     let on_select_files = move |_| {
         spawn(async move {
             if let Some(files) = rfd::AsyncFileDialog::new()
-                .set_title("选择文件")
+                .set_title(tr!("gui.dialog.select_files"))
                 .pick_files()
                 .await
             {
@@ -177,13 +285,172 @@ pub fn App() -> Element {
     };
 
     // === 发送逻辑 ===
+    let mut start_send = move |target: SendTarget| {
+        let files = selected_files.read().clone();
+        let tx = event_handler;
+        let current_settings = settings.read().clone();
+        let identity = identity.read().clone();
+
+        // 清除之前的发送任务
+        active_send_task.set(None);
+        let cancel = CancellationToken::new();
+        if let Some(old) = send_cancel.replace(Some(cancel.clone())) {
+            old.cancel();
+        }
+
+        status.set(TransferStatus::Connecting(SendPhase::Connecting));
+
+        let handle = spawn(async move {
+            let options = SendOptions {
+                wifi_interface: "wlan0".to_string(),
+                use_5ghz: current_settings.supports_5ghz,
+                sender_name: current_settings.device_name.clone(),
+                ports: current_settings.transfer_ports,
+                credentials: current_settings.hotspot_credentials,
+                hotspot_idle_timeout: current_settings.hotspot_idle_timeout(),
+                hotspot_interface: current_settings.hotspot_interface.clone(),
+                busy_interface: current_settings.busy_interface,
+                min_battery_percent: current_settings.min_battery_percent,
+                ..Default::default()
+            };
+
+            let (callback, mut rx) = SimpleSendCallback::new();
+            let tx_ev = tx;
+            let files_for_events = files.clone();
+
+            spawn(async move {
+                while let Some(event) = rx.recv().await {
+                    match event {
+                        SendEvent::Started { session_id } => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.session_started", id = session_id),
+                        )),
+                        SendEvent::Status(s) => tx_ev.send(GuiEvent::Log(LogLevel::Info, s)),
+                        // 传输开始后由 Progress / Complete 更新状态
+                        SendEvent::Phase(phase) if phase < SendPhase::Transferring => tx_ev.send(
+                            GuiEvent::TransferStatusUpdate(TransferStatus::Connecting(phase)),
+                        ),
+                        SendEvent::Phase(_) => {}
+                        SendEvent::Listening { port } => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.listening", port = port),
+                        )),
+                        SendEvent::VerificationCode(code) => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.verification_code", code = code),
+                        )),
+                        SendEvent::BootstrapPayload(payload) => {
+                            tx_ev.send(GuiEvent::BootstrapPayload(payload))
+                        }
+                        SendEvent::Retrying(retry) => {
+                            tx_ev.send(GuiEvent::Log(LogLevel::Warn, retry.to_string()))
+                        }
+                        SendEvent::ReceiverJoined { mac, ip } => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.receiver_joined", ip = ip, mac = mac),
+                        )),
+                        SendEvent::InterfaceBusy(busy) => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Warn,
+                            tr!(
+                                "gui.log.interface_busy",
+                                interface = busy.interface,
+                                network = busy.network()
+                            ),
+                        )),
+                        SendEvent::Pairing(prompt) => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!(
+                                "gui.log.pairing",
+                                address = prompt.address(),
+                                code = prompt.code()
+                            ),
+                        )),
+                        SendEvent::Progress { sent, total, .. } => {
+                            tx_ev.send(GuiEvent::TransferStatusUpdate(
+                                TransferStatus::Transferring {
+                                    current: sent,
+                                    total,
+                                    file_name: files_for_events
+                                        .first()
+                                        .map(|p| {
+                                            p.file_name()
+                                                .unwrap_or_default()
+                                                .to_string_lossy()
+                                                .into_owned()
+                                        })
+                                        .unwrap_or_default(),
+                                },
+                            ));
+                        }
+                        SendEvent::Complete => {
+                            tx_ev.send(GuiEvent::TransferStatusUpdate(TransferStatus::Completed {
+                                files: files_for_events.clone(),
+                            }));
+                        }
+                        SendEvent::Error(e) => tx_ev.send(GuiEvent::Error(e)),
+                        SendEvent::Paused => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.transfer_paused"),
+                        )),
+                        SendEvent::Resumed => tx_ev.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.transfer_resumed"),
+                        )),
+                        SendEvent::Stats(_) => {}
+                    }
+                }
+            });
+
+            let sender = Sender::new(options).map(|s| {
+                let s = s.with_cancellation(cancel);
+                match identity {
+                    Some(identity) => s.with_security(identity),
+                    None => s,
+                }
+            });
+            match sender {
+                Ok(sender) => {
+                    let result = match &target {
+                        SendTarget::Device(device) => {
+                            sender.send_to_device(device, files, &callback).await
+                        }
+                        SendTarget::Payload { peer_key } => {
+                            sender
+                                .send_via_payload(peer_key.as_deref(), files, &callback)
+                                .await
+                        }
+                    };
+                    match result {
+                        Ok(_) => {
+                            tx.send(GuiEvent::Log(LogLevel::Info, tr!("gui.log.send_complete")));
+                        }
+                        Err(e) if cancel::is_cancelled(&e) => {}
+                        Err(e) => {
+                            // 热点被 polkit 拒绝时说明缺少哪项授权
+                            let message = match e.downcast_ref::<NmPermissionDenied>() {
+                                Some(denied) => {
+                                    tr!("gui.error.nm_permission", permission = denied.permission)
+                                }
+                                None => tr!("gui.error.send", error = e),
+                            };
+                            tx.send(GuiEvent::Error(message));
+                        }
+                    }
+                }
+                Err(e) => {
+                    tx.send(GuiEvent::Error(tr!("gui.error.sender_init", error = e)));
+                }
+            }
+        });
+
+        // 保存任务句柄
+        active_send_task.set(Some(handle));
+    };
+
     let on_send = move |_| {
         // 检查是否正在传输中
         if status.read().is_busy() {
-            event_handler.send(GuiEvent::Log(
-                LogLevel::Warn,
-                "正在传输中，请等待完成".to_string(),
-            ));
+            event_handler.send(GuiEvent::Log(LogLevel::Warn, tr!("gui.log.busy")));
             return;
         }
 
@@ -191,105 +458,234 @@ pub fn App() -> Element {
             selected_device.read().clone(),
             selected_files.read().is_empty(),
         ) {
-            let files = selected_files.read().clone();
-            let tx = event_handler;
-            let current_settings = settings.read().clone();
             let device_info = devices.read().iter().find(|d| d.address == *addr).cloned();
 
             if let Some(dev) = device_info {
-                // 清除之前的发送任务
-                active_send_task.set(None);
-
-                status.set(TransferStatus::Connecting);
-
                 event_handler.send(GuiEvent::Log(
                     LogLevel::Info,
-                    format!("正在连接设备: {} ({})", dev.name, dev.address),
+                    tr!("gui.log.connecting", name = dev.name, address = dev.address),
                 ));
 
-                let handle = spawn(async move {
-                    let options = SendOptions {
-                        wifi_interface: "wlan0".to_string(),
-                        use_5ghz: current_settings.supports_5ghz,
-                        sender_name: current_settings.device_name.clone(),
-                    };
+                start_send(SendTarget::Device(DiscoveredDevice {
+                    address: dev.address.clone(),
+                    name: dev.name.clone(),
+                    rssi: Some(dev.rssi),
+                    brand: dev.brand.clone().unwrap_or_else(|| "Unknown".to_string()),
+                    brand_id: dev.brand_id,
+                    sender_id: dev.sender_id.clone(),
+                    supports_5ghz: dev.supports_5ghz,
+                    busy: false,
+                    supports_wpa3: dev.supports_wpa3,
+                    lan_endpoint: None,
+                }));
+            }
+        }
+    };
 
-                    let (callback, mut rx) = SimpleSendCallback::new();
-                    let tx_ev = tx;
-                    let files_for_events = files.clone();
+    // 不需要选中设备：P2P 信息以二维码交给接收端
+    let on_send_qr = move |peer_key: String| {
+        if status.read().is_busy() {
+            event_handler.send(GuiEvent::Log(LogLevel::Warn, tr!("gui.log.busy")));
+            return;
+        }
+        if selected_files.read().is_empty() {
+            return;
+        }
 
+        event_handler.send(GuiEvent::Log(LogLevel::Info, tr!("gui.log.qr_sending")));
+        let peer_key = peer_key.trim().to_string();
+        start_send(SendTarget::Payload {
+            peer_key: (!peer_key.is_empty()).then_some(peer_key),
+        });
+    };
+
+    // === 接收逻辑 ===
+    // `payload` 为 None 时广播等待发送端，否则按二维码载荷直接接入发送端
+    let mut start_receive = move |payload: Option<String>| {
+        // 清除之前的任务引用（Task drop时会取消）
+        active_receive_task.set(None);
+        let cancel = CancellationToken::new();
+        if let Some(old) = receive_cancel.replace(Some(cancel.clone())) {
+            old.cancel();
+        }
+
+        mode.set(AppMode::Receiving);
+        let control = TransferControl::new();
+        receive_control.set(Some(control.clone()));
+        receive_paused.set(false);
+        receive_code.set(None);
+
+        let tx = event_handler;
+        let current_settings = settings.read().clone();
+        let identity = identity.read().clone();
+
+        if payload.is_none() {
+            event_handler.send(GuiEvent::Log(
+                LogLevel::Info,
+                tr!(
+                    "gui.log.receive_starting",
+                    name = current_settings.device_name
+                ),
+            ));
+        }
+
+        // 启动新的接收任务
+        let handle = spawn(async move {
+            // 密钥文件读不出来时按启动失败处理，不退回明文保存
+            let receiver = current_settings
+                .encryption_key_file
+                .as_deref()
+                .map(AtRestKey::load)
+                .transpose()
+                .and_then(|encryption_key| {
+                    Receiver::new(ReceiveOptions {
+                        device_name: current_settings.device_name.clone(),
+                        brand_id: current_settings.brand_id,
+                        supports_5ghz: current_settings.supports_5ghz,
+                        power_profile: current_settings.power_profile,
+                        battery_saver: current_settings.battery_saver,
+                        encryption_key,
+                        sort_by_sender: current_settings.sort_by_sender,
+                        allow_sync_delete: current_settings.allow_sync_delete,
+                        group_owner: current_settings.group_owner,
+                        ..Default::default()
+                    })
+                    .map(|r| {
+                        let r = r.with_control(control).with_cancellation(cancel);
+                        match identity {
+                            Some(identity) => r.with_security(identity),
+                            None => r,
+                        }
+                    })
+                });
+
+            match receiver {
+                Ok(receiver) => {
+                    let (callback, mut rx) = SimpleReceiveCallback::new(true);
+
+                    if payload.is_some() {
+                        tx.send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Starting));
+                        tx.send(GuiEvent::Log(
+                            LogLevel::Info,
+                            tr!("gui.log.payload_receive"),
+                        ));
+                    } else {
+                        tx.send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Advertising {
+                            device_name: current_settings.device_name.clone(),
+                        }));
+                        tx.send(GuiEvent::Log(LogLevel::Info, tr!("gui.log.gatt_started")));
+                    }
+
+                    let tx_ev = tx;
                     spawn(async move {
                         while let Some(event) = rx.recv().await {
                             match event {
-                                SendEvent::Status(s) => {
+                                ReceiveEvent::Started { session_id } => tx_ev.send(GuiEvent::Log(
+                                    LogLevel::Info,
+                                    tr!("gui.log.session_started", id = session_id),
+                                )),
+                                ReceiveEvent::Status(s) => {
                                     tx_ev.send(GuiEvent::Log(LogLevel::Info, s))
                                 }
-                                SendEvent::Progress { sent, total, .. } => {
-                                    tx_ev.send(GuiEvent::TransferStatusUpdate(
-                                        TransferStatus::Transferring {
-                                            current: sent,
-                                            total,
-                                            file_name: files_for_events
-                                                .first()
-                                                .map(|p| {
-                                                    p.file_name()
-                                                        .unwrap_or_default()
-                                                        .to_string_lossy()
-                                                        .into_owned()
-                                                })
-                                                .unwrap_or_default(),
-                                        },
+                                ReceiveEvent::BleConnection(GattConnectionEvent::Connected {
+                                    address,
+                                    mtu,
+                                }) => tx_ev.send(GuiEvent::Log(
+                                    LogLevel::Info,
+                                    tr!("gui.log.ble_connected", address = address, mtu = mtu),
+                                )),
+                                ReceiveEvent::BleConnection(
+                                    GattConnectionEvent::Disconnected { address },
+                                ) => tx_ev.send(GuiEvent::Log(
+                                    LogLevel::Info,
+                                    tr!("gui.log.ble_disconnected", address = address),
+                                )),
+                                ReceiveEvent::VerificationCode(code) => {
+                                    tx_ev.send(GuiEvent::Log(
+                                        LogLevel::Info,
+                                        tr!("gui.log.verification_code", code = code),
                                     ));
+                                    tx_ev.send(GuiEvent::VerificationCode(code));
                                 }
-                                SendEvent::Complete => {
-                                    tx_ev.send(GuiEvent::TransferStatusUpdate(
-                                        TransferStatus::Completed {
-                                            files: files_for_events.clone(),
+                                ReceiveEvent::Pairing(prompt) => tx_ev.send(GuiEvent::Log(
+                                    LogLevel::Info,
+                                    tr!(
+                                        "gui.log.pairing",
+                                        address = prompt.address(),
+                                        code = prompt.code()
+                                    ),
+                                )),
+                                ReceiveEvent::Progress { received, total } => {
+                                    tx_ev.send(GuiEvent::ReceiveStatusUpdate(
+                                        ReceiveState::Receiving {
+                                            progress: if total > 0 {
+                                                (received as f32 / total as f32) * 100.0
+                                            } else {
+                                                0.0
+                                            },
+                                            file_name: tr!("gui.receive.receiving_file"),
                                         },
                                     ));
                                 }
-                                SendEvent::Error(e) => tx_ev.send(GuiEvent::Error(e)),
-                            }
-                        }
-                    });
-
-                    let target = DiscoveredDevice {
-                        address: dev.address.clone(),
-                        name: dev.name.clone(),
-                        rssi: Some(dev.rssi),
-                        brand: dev.brand.clone().unwrap_or_else(|| "Unknown".to_string()),
-                        brand_id: dev.brand_id,
-                        sender_id: dev.sender_id.clone(),
-                        supports_5ghz: dev.supports_5ghz,
-                    };
-
-                    match Sender::new(options) {
-                        Ok(sender) => {
-                            match sender.send_to_device(&target, files, &callback).await {
-                                Ok(_) => {
-                                    tx.send(GuiEvent::Log(
+                                ReceiveEvent::Paused => {
+                                    tx_ev.send(GuiEvent::ReceivePaused(true));
+                                    tx_ev.send(GuiEvent::Log(
                                         LogLevel::Info,
-                                        "文件发送完成".to_string(),
+                                        tr!("gui.log.transfer_paused"),
                                     ));
                                 }
-                                Err(e) => {
-                                    tx.send(GuiEvent::Error(format!("发送失败: {}", e)));
+                                ReceiveEvent::Resumed => {
+                                    tx_ev.send(GuiEvent::ReceivePaused(false));
+                                    tx_ev.send(GuiEvent::Log(
+                                        LogLevel::Info,
+                                        tr!("gui.log.transfer_resumed"),
+                                    ));
                                 }
+                                ReceiveEvent::Complete(files) => {
+                                    let files =
+                                        files.into_iter().map(ReceivedFile::from_path).collect();
+                                    tx_ev.send(GuiEvent::ReceiveStatusUpdate(
+                                        ReceiveState::Completed { files },
+                                    ));
+                                }
+                                ReceiveEvent::Error(e) => tx_ev
+                                    .send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Error(e))),
+                                _ => {}
                             }
                         }
-                        Err(e) => {
-                            tx.send(GuiEvent::Error(format!("无法初始化发送器: {}", e)));
+                    });
+
+                    match payload {
+                        // 载荷无效时不会有回调事件，直接显示错误
+                        Some(payload) => {
+                            if let Err(e) = receiver.receive_payload(&payload, &callback).await
+                                && !cancel::is_cancelled(&e)
+                            {
+                                tx.send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Error(tr!(
+                                    "gui.error.payload",
+                                    error = e
+                                ))));
+                            }
+                        }
+                        None => {
+                            let _ = receiver.start(&callback).await;
                         }
                     }
-                });
-
-                // 保存任务句柄
-                active_send_task.set(Some(handle));
+                }
+                Err(e) => {
+                    tx.send(GuiEvent::Error(tr!("gui.error.receiver_start", error = e)));
+                    tx.send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Error(tr!(
+                        "gui.error.init",
+                        error = e
+                    ))));
+                }
             }
-        }
+        });
+
+        // 保存任务句柄
+        active_receive_task.set(Some(handle));
     };
 
-    // === 接收逻辑 ===
     let mut on_mode_change = move |new_mode: AppMode| {
         // 如果切换到接收模式
         if new_mode == AppMode::Receiving {
@@ -297,103 +693,203 @@ pub fn App() -> Element {
             if *mode.read() == AppMode::Receiving {
                 event_handler.send(GuiEvent::Log(
                     LogLevel::Warn,
-                    "已在接收模式中，忽略重复请求".to_string(),
+                    tr!("gui.log.already_receiving"),
                 ));
                 return;
             }
-
-            // 清除之前的任务引用（Task drop时会取消）
+            start_receive(None);
+        } else {
+            // 切换到其他模式时取消接收，任务断开网络后自行结束
+            if let Some(cancel) = receive_cancel.take() {
+                cancel.cancel();
+            }
             active_receive_task.set(None);
-
-            mode.set(AppMode::Receiving);
-
-            let tx = event_handler;
-            let current_settings = settings.read().clone();
-
+            receive_control.set(None);
+            receive_state.set(ReceiveState::Idle);
             event_handler.send(GuiEvent::Log(
                 LogLevel::Info,
-                format!(
-                    "正在启动接收模式，设备名: '{}'",
-                    current_settings.device_name
-                ),
+                tr!("gui.log.receive_stopped"),
             ));
+            mode.set(new_mode);
+        }
+    };
 
-            // 启动新的接收任务
-            let handle = spawn(async move {
-                let options = ReceiveOptions {
-                    device_name: current_settings.device_name.clone(),
-                    brand_id: current_settings.brand_id,
-                    supports_5ghz: current_settings.supports_5ghz,
-                    ..Default::default()
-                };
-
-                match Receiver::new(options) {
-                    Ok(receiver) => {
-                        let (callback, mut rx) = SimpleReceiveCallback::new(true);
-
-                        tx.send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Advertising {
-                            device_name: current_settings.device_name.clone(),
-                        }));
+    // 以 `--daemon-logs` 启动：把守护进程的日志一并显示在日志中
+    use_hook(move || {
+        if !std::env::args().any(|arg| arg == logging::DAEMON_LOGS_ARG) {
+            return;
+        }
+        spawn(async move {
+            let level = *log_filter.peek();
+            let mut daemon_logs = match DaemonLogs::connect(level).await {
+                Ok(daemon_logs) => daemon_logs,
+                Err(e) => {
+                    event_handler.send(GuiEvent::Log(
+                        LogLevel::Warn,
+                        tr!("gui.log.daemon_logs_failed", error = e),
+                    ));
+                    return;
+                }
+            };
+            event_handler.send(GuiEvent::Log(
+                LogLevel::Info,
+                tr!("gui.log.daemon_logs_attached", level = level.name()),
+            ));
+            while let Ok(Some(entry)) = daemon_logs.next().await {
+                event_handler.send(GuiEvent::Log(
+                    entry.level,
+                    format!("[daemon] {}", entry.message),
+                ));
+            }
+            event_handler.send(GuiEvent::Log(
+                LogLevel::Warn,
+                tr!("gui.log.daemon_logs_ended"),
+            ));
+        });
+    });
 
-                        tx.send(GuiEvent::Log(
+    // 自启动时带 `--receive` 启动：在受信任的网络下进入接收模式，离开后停止尚未开始传输的接收
+    // （与守护进程的 `watch_trusted_networks` 相同）。用户自己退出接收模式后不再接管。
+    use_hook(move || {
+        if !std::env::args().any(|arg| arg == autostart::RECEIVE_ARG) {
+            return;
+        }
+        spawn(async move {
+            let mut ticker = tokio::time::interval(autostart::TRUSTED_NETWORK_POLL);
+            // 当前的接收模式是否由这里开启
+            let mut managed = false;
+            let mut announced = false;
+            loop {
+                ticker.tick().await;
+                let receiving = *mode.peek() == AppMode::Receiving;
+                if managed && !receiving {
+                    return;
+                }
+                let trusted = settings.peek().trusted_networks.clone();
+                let allowed = autostart::on_trusted_network(&trusted).await;
+                if allowed && !managed && *mode.peek() == AppMode::Home && !status.peek().is_busy()
+                {
+                    start_receive(None);
+                    managed = true;
+                    announced = false;
+                } else if !allowed && managed {
+                    // 握手开始（有验证码）或已经在传输时不打断
+                    let idle = matches!(*receive_state.peek(), ReceiveState::Advertising { .. })
+                        && receive_code.peek().is_none();
+                    if idle {
+                        on_mode_change(AppMode::Home);
+                        managed = false;
+                        event_handler.send(GuiEvent::Log(
                             LogLevel::Info,
-                            "GATT Server 已启动，等待连接...".to_string(),
+                            tr!("gui.log.autostart_left_trusted"),
                         ));
+                    }
+                } else if !allowed && !announced {
+                    announced = true;
+                    event_handler.send(GuiEvent::Log(
+                        LogLevel::Info,
+                        tr!("gui.log.autostart_untrusted"),
+                    ));
+                }
+            }
+        });
+    });
 
-                        let tx_ev = tx;
-                        spawn(async move {
-                            while let Some(event) = rx.recv().await {
-                                match event {
-                                    ReceiveEvent::Status(s) => {
-                                        tx_ev.send(GuiEvent::Log(LogLevel::Info, s))
-                                    }
-                                    ReceiveEvent::Progress { received, total } => {
-                                        tx_ev.send(GuiEvent::ReceiveStatusUpdate(
-                                            ReceiveState::Receiving {
-                                                progress: if total > 0 {
-                                                    (received as f32 / total as f32) * 100.0
-                                                } else {
-                                                    0.0
-                                                },
-                                                file_name: "正在接收...".to_string(),
-                                            },
-                                        ));
-                                    }
-                                    ReceiveEvent::Complete(files) => {
-                                        tx_ev.send(GuiEvent::ReceiveStatusUpdate(
-                                            ReceiveState::Completed { files },
-                                        ));
-                                    }
-                                    ReceiveEvent::Error(e) => tx_ev.send(
-                                        GuiEvent::ReceiveStatusUpdate(ReceiveState::Error(e)),
-                                    ),
-                                    _ => {}
-                                }
-                            }
-                        });
+    // === 系统托盘 ===
+    // 托盘"发送剪贴板"：剪贴板中的文件（文本写成临时文件）设为待发送文件，已选中设备时直接发送
+    let send_clipboard = move || {
+        spawn(async move {
+            let dir = dirs::cache_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join("cattysend")
+                .join("clipboard");
+            let files = tokio::task::spawn_blocking(move || {
+                clipboard::read().and_then(|content| content.into_files(&dir))
+            })
+            .await
+            .map_err(std::io::Error::other)
+            .and_then(|result| result);
+            match files {
+                Ok(files) => {
+                    event_handler.send(GuiEvent::Log(
+                        LogLevel::Info,
+                        tr!("gui.log.clipboard_selected", count = files.len()),
+                    ));
+                    selected_files.set(files);
+                    show_window();
+                    if selected_device.read().is_some() {
+                        let mut send = on_send;
+                        send(());
+                    }
+                }
+                Err(e) => {
+                    event_handler.send(GuiEvent::Error(tr!("gui.error.clipboard", error = e)))
+                }
+            }
+        });
+    };
 
-                        let _ = receiver.start(&callback).await;
+    let mut tray_handle = use_signal(|| Option::<TrayHandle>::None);
+    use_hook(move || {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        spawn(async move {
+            match tray::spawn(tx).await {
+                Ok(handle) => tray_handle.set(Some(handle)),
+                Err(e) => event_handler.send(GuiEvent::Log(
+                    LogLevel::Warn,
+                    tr!("gui.log.tray_unavailable", error = e),
+                )),
+            }
+        });
+        spawn(async move {
+            while let Some(action) = rx.recv().await {
+                match action {
+                    TrayAction::ToggleReceive => {
+                        let next = if *mode.read() == AppMode::Receiving {
+                            AppMode::Home
+                        } else {
+                            AppMode::Receiving
+                        };
+                        on_mode_change(next);
                     }
-                    Err(e) => {
-                        tx.send(GuiEvent::Error(format!("无法启动接收器: {}", e)));
-                        tx.send(GuiEvent::ReceiveStatusUpdate(ReceiveState::Error(format!(
-                            "初始化失败: {}",
-                            e
-                        ))));
+                    TrayAction::SendClipboard => send_clipboard(),
+                    TrayAction::ShowWindow => show_window(),
+                    TrayAction::Quit => {
+                        // 先通知收发任务关闭热点、断开网络
+                        for cancel in [send_cancel.take(), receive_cancel.take()]
+                            .into_iter()
+                            .flatten()
+                        {
+                            cancel.cancel();
+                        }
+                        dioxus::desktop::window().close();
                     }
                 }
-            });
+            }
+        });
+    });
 
-            // 保存任务句柄
-            active_receive_task.set(Some(handle));
-        } else {
-            // 切换到其他模式时，清除任务引用（Task drop时会取消）
-            active_receive_task.set(None);
-            receive_state.set(ReceiveState::Idle);
-            event_handler.send(GuiEvent::Log(LogLevel::Info, "已停止接收模式".to_string()));
-            mode.set(new_mode);
+    // 托盘标题和提示显示接收模式和传输进度
+    use_effect(move || {
+        let receiving = *mode.read() == AppMode::Receiving;
+        let progress = match (&*status.read(), &*receive_state.read()) {
+            (TransferStatus::Transferring { current, total, .. }, _) if *total > 0 => {
+                Some((current.saturating_mul(100) / total).min(100) as u8)
+            }
+            (_, ReceiveState::Receiving { progress, .. }) => Some(progress.clamp(0.0, 100.0) as u8),
+            _ => None,
+        };
+        if let Some(handle) = tray_handle.read().clone() {
+            spawn(async move {
+                handle
+                    .update(TrayState {
+                        receiving,
+                        progress,
+                    })
+                    .await
+            });
         }
-    };
+    });
 
     let filtered_logs = use_memo(move || {
         let filter = *log_filter.read();
@@ -404,6 +900,13 @@ pub fn App() -> Element {
             .collect::<Vec<LogEntry>>()
     });
 
+    // 接收端显示本机公钥，发送端可以用它加密二维码中的 P2P 信息
+    let public_key = identity
+        .read()
+        .as_ref()
+        .map(|security| security.get_public_key().to_string())
+        .unwrap_or_default();
+
     rsx! {
         style { "{GLOBAL_CSS}" }
         div { class: "app-container",
@@ -417,30 +920,54 @@ pub fn App() -> Element {
                         DeviceList {
                             devices: devices.read().clone(),
                             selected: selected_device.read().clone(),
+                            favorites: favorites.read().clone(),
                             on_select: move |a| selected_device.set(Some(a)),
+                            on_toggle_favorite: on_toggle_favorite,
                             on_refresh: on_refresh_devices,
                             is_scanning: matches!(*status.read(), TransferStatus::Scanning),
                         }
                     }
                     div { class: "bento-tile main-right",
-                        TransferPanel {
-                            status: status.read().clone(),
-                            selected_files: selected_files.read().clone(),
-                            on_select_files: on_select_files,
-                            on_send: on_send,
-                            on_cancel: move |_| status.set(TransferStatus::Idle),
+                        match bootstrap_payload.read().clone() {
+                            Some(payload) => rsx! {
+                                QrPayload {
+                                    payload: payload,
+                                    on_cancel: move |_| {
+                                        if let Some(cancel) = send_cancel.take() {
+                                            cancel.cancel();
+                                        }
+                                        bootstrap_payload.set(None);
+                                        status.set(TransferStatus::Idle);
+                                    },
+                                }
+                            },
+                            None => rsx! {
+                                TransferPanel {
+                                    status: status.read().clone(),
+                                    selected_files: selected_files.read().clone(),
+                                    on_select_files: on_select_files,
+                                    on_send: on_send,
+                                    on_send_qr: on_send_qr,
+                                    on_cancel: move |_| {
+                                        if let Some(cancel) = send_cancel.take() {
+                                            cancel.cancel();
+                                        }
+                                        status.set(TransferStatus::Idle);
+                                    },
+                                }
+                            },
                         }
                     }
                 },
                 AppMode::Receiving => rsx! {
                     div { class: "bento-tile", style: "grid-column: span 12; display: flex; flex-direction: column; overflow: hidden;",
-                        div { class: "card-header", h2 { "📥 接收模式" } button { class: "btn btn-secondary", onclick: move |_| on_mode_change(AppMode::Home), "停止" } }
+                        div { class: "card-header", h2 { "📥 " {tr!("gui.receive.title")} } button { class: "btn btn-secondary", onclick: move |_| on_mode_change(AppMode::Home), {tr!("gui.receive.stop")} } }
                         div { class: "receive-wrapper",
                             match receive_state.read().clone() {
                                 ReceiveState::Idle | ReceiveState::Starting => rsx! {
                                     div { class: "receive-container",
                                         div { class: "spinner" }
-                                        div { class: "status-pill", "正在初始化服务..." }
+                                        div { class: "status-pill", {tr!("gui.receive.starting")} }
                                     }
                                 },
                                 ReceiveState::Advertising { device_name } => rsx! {
@@ -451,27 +978,47 @@ pub fn App() -> Element {
                                             div { class: "radar-ring animating" }
                                             div { class: "radar-emitter", "📡" }
                                         }
+                                        if let Some(code) = receive_code.read().clone() {
+                                            VerificationPin { code }
+                                        }
                                         div { class: "status-pill",
                                             span { style: "color: var(--secondary); font-size: 24px; line-height: 0;", "●" }
-                                            span { "等待连接: {device_name}" }
+                                            span { {tr!("gui.receive.waiting", name = device_name)} }
+                                        }
+                                        p { style: "margin-top: 16px; font-weight: 500; color: #64748B;", {tr!("gui.receive.hint")} }
+                                        div { style: "margin-top: 24px; width: 100%;",
+                                            PayloadInput { public_key: public_key.clone(), on_submit: move |payload| start_receive(Some(payload)) }
                                         }
-                                        p { style: "margin-top: 16px; font-weight: 500; color: #64748B;", "在发送端选择此设备即可开始传输" }
                                     }
                                 },
                                 ReceiveState::Connecting { ssid } => rsx! {
                                     div { class: "receive-container",
                                         div { class: "spinner", style: "border-color: #cbd5e1; border-top-color: var(--accent);" }
-                                        div { class: "status-pill", "正在连接到 Wi-Fi: {ssid}" }
+                                        div { class: "status-pill", {tr!("gui.receive.connecting", ssid = ssid)} }
                                     }
                                 },
                                 ReceiveState::Receiving { progress, file_name } => rsx! {
                                     div { class: "receive-container",
+                                        if let Some(code) = receive_code.read().clone() {
+                                            VerificationPin { code }
+                                        }
                                         div { class: "rx-file-card",
                                             div { class: "rx-file-header",
                                                 div { class: "rx-file-icon", "📥" }
                                                 div { class: "rx-file-details",
                                                     div { class: "rx-file-name", "{file_name}" }
-                                                    div { class: "rx-file-status", "正在高速接收中..." }
+                                                    div { class: "rx-file-status",
+                                                        if *receive_paused.read() { {tr!("gui.receive.paused")} } else { {tr!("gui.receive.in_progress")} }
+                                                    }
+                                                }
+                                                button {
+                                                    class: "btn btn-secondary",
+                                                    onclick: move |_| {
+                                                        if let Some(control) = receive_control.read().as_ref() {
+                                                            if control.is_paused() { control.resume() } else { control.pause() }
+                                                        }
+                                                    },
+                                                    if *receive_paused.read() { {tr!("gui.receive.resume")} } else { {tr!("gui.receive.pause")} }
                                                 }
                                             }
                                             div { class: "progress-container",
@@ -484,14 +1031,10 @@ pub fn App() -> Element {
                                 ReceiveState::Completed { files } => rsx! {
                                     div { class: "receive-container",
                                         div { class: "radar-emitter", style: "background: var(--success); font-size: 36px; margin-bottom: 24px; animation: bounce-subtle 2s infinite;", "🎉" }
-                                        div { class: "status-pill", style: "border-color: var(--success); color: #166534; background: #f0fdf4;", "传输完成 ({files.len()} 个文件)" }
-                                        div { style: "margin-top: 24px; width: 100%; max-width: 400px; display: flex; flex-direction: column; gap: 10px;",
-                                            for file in files {
-                                                div { style: "background: white; padding: 12px 16px; border: 2px solid var(--border); font-weight: 600; display: flex; align-items: center; gap: 10px; box-shadow: 2px 2px 0px rgba(0,0,0,0.05);",
-                                                    span { "📄" }
-                                                    span { style: "overflow: hidden; text-overflow: ellipsis; white-space: nowrap;", "{file.file_name().unwrap_or_default().to_string_lossy()}" }
-                                                }
-                                            }
+                                        div { class: "status-pill", style: "border-color: var(--success); color: #166534; background: #f0fdf4;", {tr!("gui.receive.complete", count = files.len())} }
+                                        ReceivedFiles {
+                                            files,
+                                            on_error: move |e| event_handler.send(GuiEvent::Error(e)),
                                         }
                                     }
                                 },
@@ -499,7 +1042,11 @@ pub fn App() -> Element {
                                     div { class: "receive-container", style: "border-color: var(--error); background: #fff1f2;",
                                         div { style: "font-size: 64px; margin-bottom: 20px;", "❌" }
                                         div { class: "status-pill error", "{e}" }
-                                        p { style: "margin-top: 16px; width: 100%; text-align: center; color: var(--error);", "请检查网络或重试" }
+                                        p { style: "margin-top: 16px; width: 100%; text-align: center; color: var(--error);", {tr!("gui.receive.retry_hint")} }
+                                        // 蓝牙不可用时仍可以按二维码载荷接收
+                                        div { style: "margin-top: 24px; width: 100%;",
+                                            PayloadInput { public_key: public_key.clone(), on_submit: move |payload| start_receive(Some(payload)) }
+                                        }
                                     }
                                 },
                             }
@@ -511,27 +1058,28 @@ pub fn App() -> Element {
                 AppMode::Settings => {
                     let s = settings.read();
                     let brands = BrandId::all();
+                    let trusted_networks = s.trusted_networks.join(", ");
 
                     rsx! {
                         div { class: "bento-tile", style: "grid-column: span 12; display: flex; flex-direction: column; gap: 20px;",
-                            div { class: "card-header", h2 { "⚙️ 配置中心" } }
+                            div { class: "card-header", h2 { "⚙️ " {tr!("gui.settings.title")} } }
 
                             div { style: "display: grid; grid-template-columns: 1fr 1fr; gap: 24px;",
                                 // 左侧：基本信息
                                 div { style: "display: flex; flex-direction: column; gap: 16px;",
                                     div { class: "form-group",
-                                        label { style: "display: block; font-weight: 700; margin-bottom: 8px;", "设备名称" }
+                                        label { style: "display: block; font-weight: 700; margin-bottom: 8px;", {tr!("gui.settings.name")} }
                                         input {
                                             class: "input-field",
                                             style: "width: 100%; padding: 12px; border: 2px solid var(--border); font-size: 16px; font-weight: 600;",
                                             value: "{s.device_name}",
                                             oninput: move |e| settings.write().device_name = e.value()
                                         }
-                                        p { style: "font-size: 12px; color: #666; margin-top: 4px;", "其他设备扫描时将显示此名称" }
+                                        p { style: "font-size: 12px; color: #666; margin-top: 4px;", {tr!("gui.settings.name_hint")} }
                                     }
 
                                     div { class: "form-group",
-                                        label { style: "display: block; font-weight: 700; margin-bottom: 8px;", "设备品牌 (Vendor ID)" }
+                                        label { style: "display: block; font-weight: 700; margin-bottom: 8px;", {tr!("gui.settings.brand")} }
                                         select {
                                             class: "input-field",
                                             style: "width: 100%; padding: 12px; border: 2px solid var(--border); font-size: 16px; font-weight: 600; background: white;",
@@ -548,7 +1096,7 @@ pub fn App() -> Element {
                                                 }
                                             }
                                         }
-                                        p { style: "font-size: 12px; color: #666; margin-top: 4px;", "用于伪装成特定品牌以提高互传兼容性" }
+                                        p { style: "font-size: 12px; color: #666; margin-top: 4px;", {tr!("gui.settings.brand_hint")} }
                                     }
                                 }
 
@@ -562,9 +1110,50 @@ pub fn App() -> Element {
                                                 checked: s.supports_5ghz,
                                                 onchange: move |e| settings.write().supports_5ghz = e.checked()
                                             }
-                                            "启用 5GHz Wi-Fi 广播"
+                                            {tr!("gui.settings.wifi_5ghz")}
+                                        }
+                                        p { style: "font-size: 12px; color: #666; margin-left: 32px; margin-top: 4px;", {tr!("gui.settings.wifi_5ghz_hint")} }
+                                    }
+
+                                    div { class: "form-group",
+                                        label { style: "display: block; font-weight: 700; margin-bottom: 8px;", {tr!("gui.settings.autostart")} }
+                                        select {
+                                            class: "input-field",
+                                            style: "width: 100%; padding: 12px; border: 2px solid var(--border); font-size: 16px; font-weight: 600; background: white;",
+                                            onchange: move |e| {
+                                                if let Some(mode) = AutostartMode::all().iter().find(|m| m.name() == e.value()) {
+                                                    settings.write().autostart = *mode;
+                                                }
+                                            },
+                                            for autostart_mode in AutostartMode::all() {
+                                                option {
+                                                    value: "{autostart_mode.name()}",
+                                                    selected: s.autostart == *autostart_mode,
+                                                    {autostart_label(*autostart_mode)}
+                                                }
+                                            }
                                         }
-                                        p { style: "font-size: 12px; color: #666; margin-left: 32px; margin-top: 4px;", "开启后传输速度更快，但部分旧设备可能无法发现" }
+                                        p { style: "font-size: 12px; color: #666; margin-top: 4px;", {tr!("gui.settings.autostart_hint")} }
+                                    }
+
+                                    div { class: "form-group",
+                                        label { style: "display: block; font-weight: 700; margin-bottom: 8px;", {tr!("gui.settings.trusted_networks")} }
+                                        input {
+                                            class: "input-field",
+                                            style: "width: 100%; padding: 12px; border: 2px solid var(--border); font-size: 16px; font-weight: 600;",
+                                            value: "{trusted_networks}",
+                                            placeholder: "HomeWiFi, Office",
+                                            onchange: move |e| {
+                                                settings.write().trusted_networks = e
+                                                    .value()
+                                                    .split(',')
+                                                    .map(str::trim)
+                                                    .filter(|ssid| !ssid.is_empty())
+                                                    .map(String::from)
+                                                    .collect();
+                                            }
+                                        }
+                                        p { style: "font-size: 12px; color: #666; margin-top: 4px;", {tr!("gui.settings.trusted_networks_hint")} }
                                     }
                                 }
                             }
@@ -577,19 +1166,26 @@ pub fn App() -> Element {
                                         settings.set(AppSettings::load());
                                         mode.set(AppMode::Home);
                                     },
-                                    "取消"
+                                    {tr!("gui.settings.cancel")}
                                 }
                                 button {
                                     class: "btn btn-primary",
                                     onclick: move |_| {
                                         if let Err(e) = settings.read().save() {
-                                            event_handler.send(GuiEvent::Error(format!("保存设置失败: {}", e)));
+                                            event_handler.send(GuiEvent::Error(tr!("gui.error.save_settings", error = e)));
                                         } else {
-                                            event_handler.send(GuiEvent::Log(LogLevel::Info, "设置已保存".to_string()));
+                                            event_handler.send(GuiEvent::Log(LogLevel::Info, tr!("gui.log.settings_saved")));
+                                            let wanted = settings.read().autostart;
+                                            let autostart = Autostart::new();
+                                            if autostart.installed() != wanted
+                                                && let Err(e) = autostart.apply(wanted, &autostart::default_bin_dir())
+                                            {
+                                                event_handler.send(GuiEvent::Error(tr!("gui.error.autostart", error = e)));
+                                            }
                                             mode.set(AppMode::Home);
                                         }
                                     },
-                                    "保存更改"
+                                    {tr!("gui.settings.save")}
                                 }
                             }
                         }
@@ -599,3 +1195,20 @@ pub fn App() -> Element {
         }
     }
 }
+
+/// 显示并聚焦主窗口（托盘操作）
+fn show_window() {
+    let window = dioxus::desktop::window();
+    window.set_visible(true);
+    window.set_minimized(false);
+    window.set_focus();
+}
+
+/// 设置页中自启动选项的显示名
+fn autostart_label(mode: AutostartMode) -> String {
+    match mode {
+        AutostartMode::Off => tr!("gui.settings.autostart_off"),
+        AutostartMode::Daemon => tr!("gui.settings.autostart_daemon"),
+        AutostartMode::Gui => tr!("gui.settings.autostart_gui"),
+    }
+}
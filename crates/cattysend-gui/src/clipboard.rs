@@ -0,0 +1,106 @@
+//! 读取系统剪贴板（托盘的"发送剪贴板"）
+//!
+//! 文件管理器复制的文件以 `text/uri-list` 放在剪贴板中，直接发送这些文件；
+//! 其他文本写入临时文件后发送。Wayland 下使用 `wl-paste`，X11 下使用 `xclip`。
+//! 函数会等待外部命令结束，应在 `spawn_blocking` 中调用。
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// 剪贴板内容
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipboardContent {
+    Files(Vec<PathBuf>),
+    Text(String),
+}
+
+impl ClipboardContent {
+    /// 要发送的文件；文本写到 `dir` 下的临时文件
+    pub fn into_files(self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        match self {
+            ClipboardContent::Files(files) => Ok(files),
+            ClipboardContent::Text(text) => {
+                std::fs::create_dir_all(dir)?;
+                let stamp = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                let path = dir.join(format!("clipboard-{}.txt", stamp));
+                std::fs::write(&path, text)?;
+                Ok(vec![path])
+            }
+        }
+    }
+}
+
+/// 读取剪贴板，为空或没有可用的工具时返回错误
+pub fn read() -> std::io::Result<ClipboardContent> {
+    if let Ok(uris) = paste("text/uri-list") {
+        let files = parse_uri_list(&uris);
+        if !files.is_empty() {
+            return Ok(ClipboardContent::Files(files));
+        }
+    }
+    let text = paste("text/plain")?;
+    if text.is_empty() {
+        return Err(std::io::Error::other("clipboard is empty"));
+    }
+    Ok(ClipboardContent::Text(text))
+}
+
+/// 按 MIME 类型读取剪贴板
+fn paste(mime: &str) -> std::io::Result<String> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    let output = if wayland {
+        Command::new("wl-paste")
+            .args(["--no-newline", "--type", mime])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?
+    } else {
+        Command::new("xclip")
+            .args(["-o", "-selection", "clipboard", "-t", mime])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?
+    };
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "no {} on the clipboard",
+            mime
+        )));
+    }
+    String::from_utf8(output.stdout).map_err(std::io::Error::other)
+}
+
+/// 解析 `text/uri-list`，只保留存在的本地文件
+fn parse_uri_list(list: &str) -> Vec<PathBuf> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        .map(|path| PathBuf::from(percent_decode(path)))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+fn percent_decode(s: &str) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(b) = u8::from_str_radix(hex, 16)
+        {
+            out.push(b);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    std::ffi::OsString::from_vec(out)
+}
@@ -0,0 +1,63 @@
+//! 二维码引导组件
+//!
+//! 蓝牙不可用时，发送端以二维码显示 P2P 信息，接收端粘贴扫码（或复制）得到的载荷。
+
+use cattysend_core::tr;
+use dioxus::prelude::*;
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+
+/// 发送端：载荷的二维码和可复制的文本
+#[component]
+pub fn QrPayload(payload: String, on_cancel: EventHandler<()>) -> Element {
+    let code = qr_svg(&payload);
+
+    rsx! {
+        div { class: "qr-card",
+            h3 { style: "font-weight: 800;", "📷 " {tr!("gui.qr.title")} }
+            div { class: "qr-code", dangerous_inner_html: "{code}" }
+            p { class: "qr-hint", {tr!("gui.qr.hint")} }
+            textarea { class: "qr-text", readonly: true, value: "{payload}" }
+            button {
+                class: "btn btn-secondary",
+                onclick: move |_| on_cancel.call(()),
+                {tr!("gui.qr.cancel")}
+            }
+        }
+    }
+}
+
+/// 接收端：粘贴发送端的载荷；同时显示本机公钥，发送端可以用它加密
+#[component]
+pub fn PayloadInput(public_key: String, on_submit: EventHandler<String>) -> Element {
+    let mut payload = use_signal(String::new);
+
+    rsx! {
+        div { class: "qr-card",
+            h3 { style: "font-weight: 800;", "📷 " {tr!("gui.qr.receive_title")} }
+            p { class: "qr-hint", {tr!("gui.qr.receive_hint")} }
+            textarea {
+                class: "qr-text",
+                placeholder: "cattysend:p2p:...",
+                value: "{payload}",
+                oninput: move |e| payload.set(e.value()),
+            }
+            button {
+                class: "btn btn-primary",
+                disabled: payload.read().trim().is_empty(),
+                onclick: move |_| on_submit.call(payload.read().trim().to_string()),
+                {tr!("gui.qr.receive")}
+            }
+            if !public_key.is_empty() {
+                p { class: "qr-hint", {tr!("gui.qr.own_key")} }
+                textarea { class: "qr-text", readonly: true, value: "{public_key}" }
+            }
+        }
+    }
+}
+
+fn qr_svg(payload: &str) -> String {
+    QrCode::with_error_correction_level(payload, EcLevel::L)
+        .map(|code| code.render::<svg::Color>().min_dimensions(240, 240).build())
+        .unwrap_or_default()
+}
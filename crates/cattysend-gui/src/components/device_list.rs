@@ -1,68 +1,151 @@
 //! 设备列表组件
 
 use crate::state::DiscoveredDeviceInfo;
+use cattysend_core::{Favorite, Favorites, tr};
 use dioxus::prelude::*;
 
 /// 设备列表
+///
+/// 收藏的设备（包括本次未扫描到的）单独列在最前面。
 #[component]
 pub fn DeviceList(
     devices: Vec<DiscoveredDeviceInfo>,
+    favorites: Favorites,
     selected: Option<String>,
     on_select: EventHandler<String>,
+    on_toggle_favorite: EventHandler<String>,
     on_refresh: EventHandler<()>,
     is_scanning: bool,
 ) -> Element {
+    let (favorite_devices, nearby_devices): (Vec<_>, Vec<_>) = devices
+        .iter()
+        .cloned()
+        .partition(|d| favorites.contains_address(&d.address));
+    let offline: Vec<Favorite> = favorites
+        .devices
+        .iter()
+        .filter(|f| !devices.iter().any(|d| d.address == f.address))
+        .cloned()
+        .collect();
+    let alias_of = |address: &str| favorites.by_address(address).map(|f| f.alias.clone());
+
     rsx! {
         div {
             div { class: "card-header",
-                h2 { "周边设备" }
+                h2 { {tr!("gui.devices.title")} }
                 button {
                     class: "btn btn-accent",
                     disabled: is_scanning,
                     onclick: move |_| on_refresh.call(()),
-                    if is_scanning { "扫描中..." } else { "刷新" }
+                    if is_scanning { {tr!("gui.devices.scanning")} } else { {tr!("gui.devices.refresh")} }
                 }
             }
 
-            if devices.is_empty() {
+            if !favorites.devices.is_empty() {
+                div { class: "device-section-title", "★ " {tr!("gui.devices.favorites")} }
+                div { class: "device-list",
+                    for device in favorite_devices {
+                        DeviceItem {
+                            key: "{device.address}",
+                            selected: selected.as_deref() == Some(device.address.as_str()),
+                            alias: alias_of(&device.address),
+                            device: device,
+                            on_select: on_select,
+                            on_toggle_favorite: on_toggle_favorite,
+                        }
+                    }
+                    for favorite in offline {
+                        div { key: "{favorite.address}", class: "device-item offline",
+                            div { class: "device-icon", "⭐" }
+                            div { class: "device-info",
+                                div { class: "device-name", "{favorite.name}" }
+                                div { class: "device-address", "@{favorite.alias} · " {tr!("gui.devices.offline")} }
+                            }
+                        }
+                    }
+                }
+                div { class: "device-section-title", {tr!("gui.devices.nearby")} }
+            }
+
+            if nearby_devices.is_empty() {
                 div { class: "empty-state",
                     div { class: "empty-state-icon", "🛰️" }
-                    p { class: "empty-state-text", "正在监听无线电信号..." }
+                    p { class: "empty-state-text", {tr!("gui.devices.empty")} }
                 }
             } else {
                 div { class: "device-list",
-                    for device in devices.iter() {
-                        {
-                            let addr = device.address.clone();
-                            let is_selected = selected.as_deref() == Some(addr.as_str());
-                            let class_name = if is_selected { "device-item selected" } else { "device-item" };
-                            let icon = match device.brand.as_deref().unwrap_or("") {
-                                "xiaomi" | "Xiaomi" => "📱",
-                                "oppo" | "OPPO" => "📲",
-                                _ => "💻"
-                            };
+                    for device in nearby_devices {
+                        DeviceItem {
+                            key: "{device.address}",
+                            selected: selected.as_deref() == Some(device.address.as_str()),
+                            alias: None,
+                            device: device,
+                            on_select: on_select,
+                            on_toggle_favorite: on_toggle_favorite,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
 
-                            rsx! {
-                                div {
-                                    key: "{addr}",
-                                    class: "{class_name}",
-                                    onclick: move |_| on_select.call(addr.clone()),
+/// 设备列表中的一行
+#[component]
+fn DeviceItem(
+    device: DiscoveredDeviceInfo,
+    selected: bool,
+    alias: Option<String>,
+    on_select: EventHandler<String>,
+    on_toggle_favorite: EventHandler<String>,
+) -> Element {
+    let addr = device.address.clone();
+    let fav_addr = device.address.clone();
+    let class_name = if selected {
+        "device-item selected"
+    } else {
+        "device-item"
+    };
+    let icon = match device.brand.as_deref().unwrap_or("") {
+        "xiaomi" | "Xiaomi" => "📱",
+        "oppo" | "OPPO" => "📲",
+        _ => "💻",
+    };
+    // 收藏的设备显示别名
+    let alias_text = alias
+        .as_ref()
+        .map(|a| format!(" · @{}", a))
+        .unwrap_or_default();
+    let (star, star_title) = if alias.is_some() {
+        ("★", tr!("gui.devices.unfavorite"))
+    } else {
+        ("☆", tr!("gui.devices.favorite"))
+    };
 
-                                    div { class: "device-icon", "{icon}" }
+    rsx! {
+        div {
+            class: "{class_name}",
+            onclick: move |_| on_select.call(addr.clone()),
 
-                                    div { class: "device-info",
-                                        div { class: "device-name", "{device.name}" }
-                                        div { class: "device-address", "{device.address}" }
-                                    }
+            div { class: "device-icon", "{icon}" }
 
-                                    div { class: "device-rssi",
-                                        "{device.rssi} dBm"
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+            div { class: "device-info",
+                div { class: "device-name", "{device.name}" }
+                div { class: "device-address", "{device.address}{alias_text}" }
+            }
+
+            div { class: "device-rssi",
+                "{device.rssi} dBm"
+            }
+
+            button {
+                class: "favorite-toggle",
+                title: "{star_title}",
+                onclick: move |e| {
+                    e.stop_propagation();
+                    on_toggle_favorite.call(fav_addr.clone());
+                },
+                "{star}"
             }
         }
     }
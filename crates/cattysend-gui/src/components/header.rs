@@ -1,6 +1,7 @@
 //! 头部组件
 
 use crate::state::TransferStatus;
+use cattysend_core::tr;
 use dioxus::prelude::*;
 
 /// 应用头部
@@ -13,12 +14,12 @@ pub fn Header(status: TransferStatus) -> Element {
     };
 
     let status_text = match status {
-        TransferStatus::Idle => "系统就绪",
-        TransferStatus::Scanning => "正在探测周边设备...",
-        TransferStatus::Connecting => "建立安全通道...",
-        TransferStatus::Transferring { .. } => "数据传输中",
-        TransferStatus::Completed { .. } => "传输已完成",
-        TransferStatus::Error(_) => "系统异常",
+        TransferStatus::Idle => tr!("gui.status.idle"),
+        TransferStatus::Scanning => tr!("gui.status.scanning"),
+        TransferStatus::Connecting(_) => tr!("gui.status.connecting"),
+        TransferStatus::Transferring { .. } => tr!("gui.status.transferring"),
+        TransferStatus::Completed { .. } => tr!("gui.status.completed"),
+        TransferStatus::Error(_) => tr!("gui.status.error"),
     };
 
     rsx! {
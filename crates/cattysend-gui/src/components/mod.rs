@@ -1,11 +1,15 @@
 //! UI 组件模块
 
+mod bootstrap;
 mod device_list;
 mod header;
 mod mode_selector;
+mod receive;
 mod transfer_panel;
 
+pub use bootstrap::{PayloadInput, QrPayload};
 pub use device_list::DeviceList;
 pub use header::Header;
 pub use mode_selector::ModeSelector;
+pub use receive::{ReceivedFiles, VerificationPin};
 pub use transfer_panel::TransferPanel;
@@ -1,15 +1,31 @@
 //! 模式选择器组件
 
 use crate::state::AppMode;
+use cattysend_core::tr;
 use dioxus::prelude::*;
 
 /// 发送/接收模式选择器
 #[component]
 pub fn ModeSelector(current_mode: AppMode, on_change: EventHandler<AppMode>) -> Element {
     let modes = vec![
-        (AppMode::Home, "🏠", "主页", "发送文件"),
-        (AppMode::Receiving, "📥", "接收", "等待连接"),
-        (AppMode::Settings, "⚙️", "设置", "系统配置"),
+        (
+            AppMode::Home,
+            "🏠",
+            tr!("gui.mode.home"),
+            tr!("gui.mode.home_desc"),
+        ),
+        (
+            AppMode::Receiving,
+            "📥",
+            tr!("gui.mode.receive"),
+            tr!("gui.mode.receive_desc"),
+        ),
+        (
+            AppMode::Settings,
+            "⚙️",
+            tr!("gui.mode.settings"),
+            tr!("gui.mode.settings_desc"),
+        ),
     ];
 
     rsx! {
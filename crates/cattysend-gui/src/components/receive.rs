@@ -0,0 +1,106 @@
+//! 接收结果组件
+//!
+//! 握手后显示验证码，接收完成后列出文件并提供打开、在文件夹中显示和复制保存路径。
+
+use crate::open;
+use crate::state::ReceivedFile;
+use cattysend_core::tr;
+use dioxus::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// 写入剪贴板；WebView 不提供 Clipboard API 时退回 `execCommand`
+const COPY_JS: &str = r#"
+const text = await dioxus.recv();
+try {
+    await navigator.clipboard.writeText(text);
+} catch (_) {
+    const area = document.createElement("textarea");
+    area.value = text;
+    document.body.appendChild(area);
+    area.select();
+    document.execCommand("copy");
+    area.remove();
+}
+"#;
+
+/// 验证码，与发送端显示的数字核对
+#[component]
+pub fn VerificationPin(code: String) -> Element {
+    rsx! {
+        div { class: "pin-card",
+            div { class: "pin-label", {tr!("gui.receive.verification_code")} }
+            div { class: "pin-code", "{code}" }
+            div { class: "pin-hint", {tr!("gui.receive.verification_hint")} }
+        }
+    }
+}
+
+/// 接收完成的文件列表
+#[component]
+pub fn ReceivedFiles(files: Vec<ReceivedFile>, on_error: EventHandler<String>) -> Element {
+    let mut copied = use_signal(|| false);
+    let save_dir_label = files
+        .first()
+        .map(|f| f.folder().display().to_string())
+        .unwrap_or_default();
+    let copy_text = save_dir_label.clone();
+
+    rsx! {
+        div { class: "rx-files",
+            div { class: "rx-save-dir",
+                span { class: "rx-save-path", title: "{save_dir_label}", "📂 {save_dir_label}" }
+                button {
+                    class: "btn btn-small",
+                    onclick: move |_| {
+                        let eval = document::eval(COPY_JS);
+                        if eval.send(copy_text.clone()).is_ok() {
+                            copied.set(true);
+                        }
+                    },
+                    if *copied.read() { {tr!("gui.receive.path_copied")} } else { {tr!("gui.receive.copy_path")} }
+                }
+            }
+            for file in files {
+                div { class: "rx-file-row", key: "{file.path.display()}",
+                    span { "📄" }
+                    div { class: "rx-file-details",
+                        div { class: "rx-file-row-name", title: "{file.name}", "{file.name}" }
+                        div { class: "rx-file-status", "{file.size_label()}" }
+                    }
+                    button {
+                        class: "btn btn-small btn-primary",
+                        onclick: {
+                            let path = file.path.clone();
+                            move |_| launch(path.clone(), open::open_path, on_error)
+                        },
+                        {tr!("gui.receive.open_file")}
+                    }
+                    button {
+                        class: "btn btn-small",
+                        onclick: {
+                            let path = file.path.clone();
+                            move |_| launch(path.clone(), open::show_in_folder, on_error)
+                        },
+                        {tr!("gui.receive.show_in_folder")}
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 在后台线程执行打开操作，失败时报告给 `on_error`
+fn launch(path: PathBuf, action: fn(&Path) -> std::io::Result<()>, on_error: EventHandler<String>) {
+    spawn(async move {
+        let result = {
+            let path = path.clone();
+            tokio::task::spawn_blocking(move || action(&path)).await
+        };
+        let error = match result {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => e.to_string(),
+            Err(e) => e.to_string(),
+        };
+        on_error.call(tr!("gui.error.open", path = path.display(), error = error));
+    });
+}
@@ -1,6 +1,7 @@
 //! 传输面板组件
 
 use crate::state::TransferStatus;
+use cattysend_core::{SendPhase, tr};
 use dioxus::prelude::*;
 use std::path::PathBuf;
 
@@ -11,11 +12,16 @@ pub fn TransferPanel(
     selected_files: Vec<PathBuf>,
     on_select_files: EventHandler<()>,
     on_send: EventHandler<()>,
+    /// 不经过蓝牙，显示二维码发送；参数为接收端公钥（可为空）
+    on_send_qr: EventHandler<String>,
     on_cancel: EventHandler<()>,
 ) -> Element {
+    let mut peer_key = use_signal(String::new);
+    let peer_key_hint = tr!("gui.transfer.peer_key");
+
     rsx! {
         div {
-            h2 { "传输控制" }
+            h2 { {tr!("gui.transfer.title")} }
 
             match status {
                 TransferStatus::Idle => rsx! {
@@ -23,13 +29,13 @@ pub fn TransferPanel(
                         class: "dropzone",
                         onclick: move |_| on_select_files.call(()),
                         div { class: "dropzone-icon", "📁" }
-                        div { class: "dropzone-text", "点击选择要传输的文件" }
-                        div { class: "dropzone-hint", "支持任意格式文件" }
+                        div { class: "dropzone-text", {tr!("gui.transfer.dropzone")} }
+                        div { class: "dropzone-hint", {tr!("gui.transfer.dropzone_hint")} }
                     }
 
                     if !selected_files.is_empty() {
                         div { style: "margin-top: 24px;",
-                            h3 { style: "font-weight: 800; font-size: 14px; margin-bottom: 12px; text-transform: uppercase;", "待发送项目" }
+                            h3 { style: "font-weight: 800; font-size: 14px; margin-bottom: 12px; text-transform: uppercase;", {tr!("gui.transfer.queued")} }
                             div { style: "display: flex; flex-direction: column; gap: 8px;",
                                 for file in selected_files.iter() {
                                     div {
@@ -43,24 +49,49 @@ pub fn TransferPanel(
                                 class: "btn btn-primary",
                                 style: "width: 100%; margin-top: 24px;",
                                 onclick: move |_| on_send.call(()),
-                                "开始传输"
+                                {tr!("gui.transfer.start")}
+                            }
+
+                            // 蓝牙不可用时改用二维码交换 P2P 信息
+                            div { style: "margin-top: 16px; display: flex; flex-direction: column; gap: 8px;",
+                                input {
+                                    class: "input-field",
+                                    style: "width: 100%; padding: 10px; border: 2px solid var(--border); font-size: 13px;",
+                                    placeholder: "{peer_key_hint}",
+                                    value: "{peer_key}",
+                                    oninput: move |e| peer_key.set(e.value()),
+                                }
+                                button {
+                                    class: "btn btn-accent",
+                                    style: "width: 100%;",
+                                    onclick: move |_| on_send_qr.call(peer_key.read().clone()),
+                                    {tr!("gui.transfer.start_qr")}
+                                }
                             }
                         }
                     }
                 },
 
-                TransferStatus::Connecting | TransferStatus::Scanning => rsx! {
+                TransferStatus::Scanning => rsx! {
                     div { style: "text-align: center; padding: 40px;",
                         div { style: "font-size: 40px; margin-bottom: 20px; animation: pulse 1s infinite;", "📡" }
-                        p { style: "font-weight: 800;", "正在建立握手..." }
+                        p { style: "font-weight: 800;", {tr!("gui.transfer.handshake")} }
                     }
                 },
 
+                TransferStatus::Connecting(phase) => rsx! {
+                    div { style: "text-align: center; padding: 40px 40px 16px;",
+                        div { style: "font-size: 40px; margin-bottom: 20px; animation: pulse 1s infinite;", "📡" }
+                        p { style: "font-weight: 800;", {tr!("gui.transfer.handshake")} }
+                    }
+                    PhaseSteps { current: phase }
+                },
+
                 TransferStatus::Transferring { current, total, file_name } => {
                     let progress = if total > 0 { (current as f32 / total as f32) * 100.0 } else { 0.0 };
                     rsx! {
                         div {
-                            h3 { style: "font-weight: 800; margin-bottom: 16px;", "正在发送: {file_name}" }
+                            h3 { style: "font-weight: 800; margin-bottom: 16px;", {tr!("gui.transfer.sending", file = file_name)} }
                             div { class: "progress-container",
                                 div {
                                     class: "progress-fill",
@@ -75,25 +106,25 @@ pub fn TransferPanel(
                 TransferStatus::Completed { .. } => rsx! {
                     div { style: "text-align: center; padding: 40px;",
                         div { style: "font-size: 48px; margin-bottom: 16px;", "📦" }
-                        p { style: "font-weight: 800; color: var(--success);", "任务成功交付！" }
+                        p { style: "font-weight: 800; color: var(--success);", {tr!("gui.transfer.complete")} }
                         button {
                             class: "btn btn-secondary",
                             style: "margin-top: 24px;",
                             onclick: move |_| on_cancel.call(()),
-                            "返回"
+                            {tr!("gui.transfer.back")}
                         }
                     }
                 },
 
                 TransferStatus::Error(e) => rsx! {
                     div { style: "text-align: center; padding: 40px; border: 3px solid var(--error); background: #FFF1F2;",
-                        h3 { style: "color: var(--error); font-weight: 900;", "传输中断" }
+                        h3 { style: "color: var(--error); font-weight: 900;", {tr!("gui.transfer.interrupted")} }
                         p { style: "margin-top: 10px; font-weight: 600;", "{e}" }
                         button {
                             class: "btn btn-primary",
                             style: "margin-top: 24px;",
                             onclick: move |_| on_cancel.call(()),
-                            "重试"
+                            {tr!("gui.transfer.retry")}
                         }
                     }
                 },
@@ -101,3 +132,38 @@ pub fn TransferPanel(
         }
     }
 }
+
+/// 发送步骤指示
+#[component]
+fn PhaseSteps(current: SendPhase) -> Element {
+    rsx! {
+        ol { class: "phase-steps",
+            // 扫描在设备列表中完成，这里从连接开始
+            for phase in SendPhase::ALL.into_iter().skip(1) {
+                li { key: "{phase:?}", class: step_class(phase, current), {phase_label(phase)} }
+            }
+        }
+    }
+}
+
+fn step_class(phase: SendPhase, current: SendPhase) -> &'static str {
+    if phase < current {
+        "phase-step done"
+    } else if phase == current {
+        "phase-step current"
+    } else {
+        "phase-step"
+    }
+}
+
+fn phase_label(phase: SendPhase) -> String {
+    match phase {
+        SendPhase::Scanning => tr!("gui.phase.scanning"),
+        SendPhase::Connecting => tr!("gui.phase.connecting"),
+        SendPhase::ReadingStatus => tr!("gui.phase.reading_status"),
+        SendPhase::WritingP2p => tr!("gui.phase.writing_p2p"),
+        SendPhase::WaitingForReceiverWifi => tr!("gui.phase.waiting_for_receiver_wifi"),
+        SendPhase::Transferring => tr!("gui.phase.transferring"),
+        SendPhase::Done => tr!("gui.phase.done"),
+    }
+}
@@ -19,11 +19,16 @@
 //! ```
 
 mod app;
+mod clipboard;
 mod components;
+mod open;
 mod state;
 mod styles;
+mod tray;
 
 fn main() {
+    cattysend_core::i18n::init();
+
     // 初始化日志
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
@@ -0,0 +1,65 @@
+//! 用桌面环境打开接收到的文件
+//!
+//! 两个函数都会等待外部命令结束，应在 `spawn_blocking` 中调用。
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// 用默认程序打开文件（`xdg-open`）
+pub fn open_path(path: &Path) -> std::io::Result<()> {
+    run(Command::new("xdg-open").arg(path))
+}
+
+/// 在文件管理器中显示文件
+///
+/// 优先通过 `org.freedesktop.FileManager1.ShowItems` 选中该文件（Nautilus、Dolphin、
+/// Nemo 等都实现了这个接口），不可用时用 `xdg-open` 打开所在目录。
+pub fn show_in_folder(path: &Path) -> std::io::Result<()> {
+    let shown = run(Command::new("dbus-send").args([
+        "--session",
+        "--print-reply",
+        "--reply-timeout=2000",
+        "--dest=org.freedesktop.FileManager1",
+        "/org/freedesktop/FileManager1",
+        "org.freedesktop.FileManager1.ShowItems",
+        &format!("array:string:{}", file_uri(path)),
+        "string:",
+    ]));
+    if shown.is_ok() {
+        return Ok(());
+    }
+    open_path(path.parent().unwrap_or(Path::new("/")))
+}
+
+fn run(command: &mut Command) -> std::io::Result<()> {
+    let status = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{:?} exited with {}",
+            command.get_program(),
+            status
+        )))
+    }
+}
+
+/// `file://` URI；除路径分隔符和非保留字符外都做百分号编码
+/// （dbus-send 用逗号分隔数组元素，文件名中的逗号也必须编码）
+fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut uri = String::from("file://");
+    for &b in path.as_os_str().as_bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'/' | b'-' | b'_' | b'.' | b'~') {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
@@ -2,7 +2,8 @@
 //!
 //! 使用 Dioxus signals 管理应用状态
 
-use std::path::PathBuf;
+use cattysend_core::SendPhase;
+use std::path::{Path, PathBuf};
 
 /// 应用模式
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -25,6 +26,49 @@ pub struct DiscoveredDeviceInfo {
     pub brand_id: Option<i16>,
     pub sender_id: String,
     pub supports_5ghz: bool,
+    pub supports_wpa3: bool,
+}
+
+/// 接收完成的文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivedFile {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+}
+
+impl ReceivedFile {
+    /// 读取落盘文件的元数据（读取失败时大小记为 0）
+    pub fn from_path(path: PathBuf) -> Self {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self { path, name, size }
+    }
+
+    /// 所在目录
+    pub fn folder(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new("/"))
+    }
+
+    /// 以 B/KB/MB/GB 显示大小
+    pub fn size_label(&self) -> String {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+        let mut value = self.size as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{} B", self.size)
+        } else {
+            format!("{:.1} {}", value, UNITS[unit])
+        }
+    }
 }
 
 /// 传输状态
@@ -33,7 +77,8 @@ pub enum TransferStatus {
     #[default]
     Idle,
     Scanning,
-    Connecting,
+    /// 握手中，附带当前步骤
+    Connecting(SendPhase),
     Transferring {
         current: u64,
         total: u64,
@@ -66,7 +111,7 @@ impl TransferStatus {
         matches!(
             self,
             TransferStatus::Scanning
-                | TransferStatus::Connecting
+                | TransferStatus::Connecting(_)
                 | TransferStatus::Transferring { .. }
         )
     }
@@ -193,6 +193,59 @@ h2 { font-size: 24px; font-weight: 800; margin-bottom: 16px; }
     box-shadow: 2px 2px 0px var(--border);
 }
 
+.device-info {
+    flex: 1;
+}
+
+.device-item.offline {
+    background: var(--bg);
+    opacity: 0.6;
+    cursor: default;
+}
+
+.device-section-title {
+    font-weight: 800;
+    text-transform: uppercase;
+    margin: 16px 0 8px;
+}
+
+.favorite-toggle {
+    background: transparent;
+    border: none;
+    font-size: 22px;
+    cursor: pointer;
+}
+
+/* Send phase steps */
+.phase-steps {
+    list-style: none;
+    display: flex;
+    flex-direction: column;
+    gap: 6px;
+    max-width: 320px;
+    margin: 0 auto;
+    padding: 0;
+}
+
+.phase-step {
+    padding: 6px 10px;
+    border: 2px solid var(--border);
+    font-weight: 700;
+    font-size: 13px;
+    opacity: 0.45;
+}
+
+.phase-step.done {
+    background: var(--success);
+    opacity: 1;
+}
+
+.phase-step.current {
+    background: var(--accent);
+    box-shadow: 3px 3px 0px var(--border);
+    opacity: 1;
+}
+
 /* Progress */
 .progress-container {
     border: 3px solid var(--border);
@@ -406,6 +459,44 @@ h2 { font-size: 24px; font-weight: 800; margin-bottom: 16px; }
 .radar-ring:nth-child(2) { animation-delay: 0.8s; }
 .radar-ring:nth-child(3) { animation-delay: 1.6s; }
 
+/* QR Code Bootstrap */
+.qr-card {
+    width: 100%;
+    max-width: 420px;
+    margin: 0 auto;
+    border: 3px solid var(--border);
+    background: white;
+    padding: 20px;
+    display: flex;
+    flex-direction: column;
+    align-items: center;
+    gap: 12px;
+    box-shadow: var(--shadow-sm);
+}
+
+.qr-code svg {
+    display: block;
+    width: 240px;
+    height: 240px;
+}
+
+.qr-hint {
+    font-size: 12px;
+    color: #64748B;
+    text-align: center;
+}
+
+.qr-text {
+    width: 100%;
+    min-height: 64px;
+    padding: 8px;
+    border: 2px solid var(--border);
+    font-family: 'Courier New', monospace;
+    font-size: 11px;
+    word-break: break-all;
+    resize: vertical;
+}
+
 /* Status Text Badge - Modernized */
 .status-pill {
     background: white;
@@ -483,6 +574,85 @@ h2 { font-size: 24px; font-weight: 800; margin-bottom: 16px; }
     font-weight: 500;
 }
 
+/* Verification PIN */
+.pin-card {
+    border: 3px solid var(--border);
+    background: var(--accent);
+    padding: 12px 24px;
+    box-shadow: 4px 4px 0px var(--border);
+    text-align: center;
+    margin-bottom: 20px;
+}
+
+.pin-label {
+    font-size: 12px;
+    font-weight: 800;
+    text-transform: uppercase;
+}
+
+.pin-code {
+    font-size: 32px;
+    font-weight: 900;
+    letter-spacing: 6px;
+    font-variant-numeric: tabular-nums;
+}
+
+.pin-hint {
+    font-size: 12px;
+    color: #334155;
+    font-weight: 500;
+}
+
+/* Received Files */
+.btn-small {
+    padding: 6px 12px;
+    font-size: 12px;
+    border-width: 2px;
+    box-shadow: 2px 2px 0px var(--border);
+    flex-shrink: 0;
+}
+
+.rx-files {
+    margin-top: 24px;
+    width: 100%;
+    max-width: 560px;
+    display: flex;
+    flex-direction: column;
+    gap: 10px;
+}
+
+.rx-save-dir {
+    display: flex;
+    align-items: center;
+    gap: 10px;
+    font-size: 13px;
+    font-weight: 600;
+}
+
+.rx-save-path {
+    flex: 1;
+    overflow: hidden;
+    text-overflow: ellipsis;
+    white-space: nowrap;
+}
+
+.rx-file-row {
+    background: white;
+    padding: 12px 16px;
+    border: 2px solid var(--border);
+    display: flex;
+    align-items: center;
+    gap: 10px;
+    box-shadow: 2px 2px 0px rgba(0,0,0,0.05);
+}
+
+.rx-file-row-name {
+    font-weight: 700;
+    overflow: hidden;
+    text-overflow: ellipsis;
+    white-space: nowrap;
+}
+
 /* Connecting Spinner - Smoother */
 .spinner {
     width: 48px;
@@ -0,0 +1,156 @@
+//! 系统托盘（StatusNotifierItem）
+//!
+//! 通过 ksni 在 D-Bus 上注册托盘图标，菜单提供进入/退出接收模式、发送剪贴板、
+//! 打开主窗口和退出。传输进行中时标题和提示显示进度，图标切换为同步状态。
+//! 桌面环境没有 StatusNotifierWatcher 时 [`spawn`] 返回错误，GUI 照常运行。
+
+use cattysend_core::tr;
+use ksni::TrayMethods;
+use ksni::menu::{CheckmarkItem, StandardItem};
+use tokio::sync::mpsc;
+
+/// 托盘菜单发出的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayAction {
+    /// 进入或退出接收模式
+    ToggleReceive,
+    /// 发送剪贴板中的文件或文本
+    SendClipboard,
+    /// 显示主窗口
+    ShowWindow,
+    Quit,
+}
+
+/// 托盘显示的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TrayState {
+    /// 是否处于接收模式
+    pub receiving: bool,
+    /// 正在进行的传输的百分比
+    pub progress: Option<u8>,
+}
+
+/// 已注册的托盘
+#[derive(Clone)]
+pub struct TrayHandle(ksni::Handle<CattyTray>);
+
+impl TrayHandle {
+    /// 更新托盘显示
+    pub async fn update(&self, state: TrayState) {
+        self.0.update(move |tray| tray.state = state).await;
+    }
+}
+
+/// 注册托盘图标，菜单操作发到 `actions`
+pub async fn spawn(actions: mpsc::UnboundedSender<TrayAction>) -> anyhow::Result<TrayHandle> {
+    let tray = CattyTray {
+        state: TrayState::default(),
+        actions,
+    };
+    Ok(TrayHandle(tray.spawn().await?))
+}
+
+struct CattyTray {
+    state: TrayState,
+    actions: mpsc::UnboundedSender<TrayAction>,
+}
+
+impl CattyTray {
+    fn send(&self, action: TrayAction) {
+        let _ = self.actions.send(action);
+    }
+
+    fn status_text(&self) -> String {
+        match self.state.progress {
+            Some(percent) => tr!("gui.tray.progress", percent = percent),
+            None if self.state.receiving => tr!("gui.tray.receiving"),
+            None => tr!("gui.tray.idle"),
+        }
+    }
+}
+
+impl ksni::Tray for CattyTray {
+    fn id(&self) -> String {
+        "cattysend".into()
+    }
+
+    fn title(&self) -> String {
+        match self.state.progress {
+            Some(percent) => format!("Cattysend {}%", percent),
+            None => "Cattysend".into(),
+        }
+    }
+
+    fn icon_name(&self) -> String {
+        if self.state.progress.is_some() {
+            "emblem-synchronizing".into()
+        } else if self.state.receiving {
+            "folder-download".into()
+        } else {
+            "document-send".into()
+        }
+    }
+
+    fn status(&self) -> ksni::Status {
+        if self.state.progress.is_some() {
+            ksni::Status::NeedsAttention
+        } else {
+            ksni::Status::Active
+        }
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: "Cattysend".into(),
+            description: self.status_text(),
+            ..Default::default()
+        }
+    }
+
+    /// 左键单击显示主窗口
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.send(TrayAction::ShowWindow);
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        vec![
+            StandardItem {
+                label: self.status_text(),
+                enabled: false,
+                ..Default::default()
+            }
+            .into(),
+            ksni::MenuItem::Separator,
+            CheckmarkItem {
+                label: tr!("gui.tray.receive"),
+                checked: self.state.receiving,
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::ToggleReceive)),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("gui.tray.send_clipboard"),
+                icon_name: "edit-paste".into(),
+                enabled: self.state.progress.is_none(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::SendClipboard)),
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: tr!("gui.tray.open_window"),
+                icon_name: "window-new".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::ShowWindow)),
+                ..Default::default()
+            }
+            .into(),
+            ksni::MenuItem::Separator,
+            StandardItem {
+                label: tr!("gui.tray.quit"),
+                icon_name: "application-exit".into(),
+                activate: Box::new(|this: &mut Self| this.send(TrayAction::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+}
@@ -1,10 +1,14 @@
 //! Application state
 
+use cattysend_core::tr;
 pub use cattysend_core::{
-    AppSettings, BleScanner, ChannelScanCallback, DiscoveredDevice, LogEntry, LogLevel,
-    ReceiveEvent, ReceiveOptions, Receiver, SendOptions, Sender, SimpleReceiveCallback,
-    SimpleSendCallback,
+    AppSettings, BleScanner, BleSecurityPersistent, CancellationToken, ChannelScanCallback,
+    DaemonLogs, DiscoveredDevice, Favorite, Favorites, FileProgress, GattConnectionEvent,
+    IdentityStore, LogEntry, LogLevel, ReceiveEvent, ReceiveOptions, Receiver, ScanOptions,
+    SendOptions, SendPhase, Sender, SimpleReceiveCallback, SimpleSendCallback, TransferControl,
+    TransferStats, cancel,
 };
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -19,6 +23,10 @@ pub enum AppMode {
     Transferring,
     Settings,
     FileSelection,
+    /// 二维码发送前输入接收端公钥（可留空）
+    PeerKeyInput,
+    /// 输入发送端二维码中的引导载荷
+    PayloadInput,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -33,11 +41,21 @@ pub enum Tab {
 pub enum AppEvent {
     DeviceFound(DiscoveredDevice),
     ScanFinished,
+    /// 发送/接收工作流开始
+    SessionStarted(String),
     StatusUpdate(String),
+    /// 发送流程进入新阶段
+    Phase(SendPhase),
+    /// 二维码引导的载荷已生成
+    BootstrapPayload(String),
     ProgressUpdate {
         sent: u64,
         total: u64,
     },
+    /// 逐文件进度和速度采样
+    Stats(TransferStats),
+    /// 下载暂停（`true`）或恢复（`false`）
+    Paused(bool),
     TransferComplete,
     Error(String),
     /// 日志消息（显示在日志面板）
@@ -47,6 +65,20 @@ pub enum AppEvent {
     },
 }
 
+/// 发送目标
+enum SendTarget {
+    /// 扫描到的设备，通过 BLE/局域网握手交换 P2P 信息
+    Device(DiscoveredDevice),
+    /// 显示二维码由接收端扫描；给出接收端公钥时加密
+    Payload { peer_key: Option<String> },
+}
+
+/// 速度曲线保留的采样数（每秒一个，即最近 60 秒）
+pub const SPEED_HISTORY_LEN: usize = 60;
+
+/// 日志面板 PageUp/PageDown 每次滚动的条数
+pub const LOG_PAGE: isize = 10;
+
 #[derive(Debug, Clone)]
 pub struct FileEntry {
     pub name: String,
@@ -140,6 +172,15 @@ impl FileSelector {
         }
     }
 
+    /// 选中当前目录中最新的文件（例如在截图目录中选刚截的图）
+    pub fn select_latest(&mut self) -> Option<String> {
+        let latest = cattysend_core::watch::latest_file(&self.current_path).ok()??;
+        let latest = latest.to_string_lossy();
+        let idx = self.entries.iter().position(|e| e.path == latest)?;
+        self.selected = idx;
+        Some(self.entries[idx].name.clone())
+    }
+
     /// Returns: Some(path) if a file was selected, None if directory was entered
     pub fn enter(&mut self) -> Option<String> {
         if let Some(entry) = self.entries.get(self.selected) {
@@ -164,16 +205,46 @@ impl FileSelector {
 pub struct App {
     pub mode: AppMode,
     pub tab: Tab,
+    /// 扫描到的设备，收藏的设备排在最前面
     pub devices: Vec<DiscoveredDevice>,
     pub selected_device: usize,
+    pub favorites: Favorites,
     pub progress: f64,
     pub transfer_speed: f64,
     pub file_to_send: Option<String>,
+    /// 当前任务中各文件的进度
+    pub transfer_files: Vec<FileProgress>,
+    /// 传输文件列表中选中的行
+    pub selected_transfer_file: usize,
+    /// 最近的速度采样（字节/秒）
+    pub speed_history: VecDeque<u64>,
+    /// 发送流程当前所处的阶段
+    pub send_phase: Option<SendPhase>,
+    /// 当前（或最近一次）工作流的会话 ID
+    pub session_id: Option<String>,
+    /// 下载是否已暂停
+    pub paused: bool,
+    /// 接收模式下的暂停/恢复句柄
+    receive_control: Option<TransferControl>,
+    /// 本机身份密钥（与守护进程共用）；加载失败时为 None，收发各自使用临时密钥
+    pub identity: Option<Arc<BleSecurityPersistent>>,
+    /// 等待接收端扫描的二维码载荷，传输开始后清除
+    pub bootstrap_payload: Option<String>,
+    /// 以文本而不是二维码显示载荷（便于复制）
+    pub payload_as_text: bool,
+    /// 正在从二维码载荷接收（没有 BLE 广播）
+    pub receiving_from_payload: bool,
 
     /// 原始日志列表（所有级别）
     raw_logs: Vec<LogEntry>,
     /// 当前显示的日志级别过滤器
     pub log_filter: LogLevel,
+    /// 日志面板向旧日志方向滚动的条数（0 表示显示最新日志）
+    pub log_scroll: usize,
+    /// 已确认的日志搜索词，匹配处高亮
+    pub log_search: Option<String>,
+    /// 正在日志面板底部输入搜索词（使用 `input_buffer`）
+    pub log_search_editing: bool,
 
     pub scan_start: Option<Instant>,
 
@@ -183,6 +254,10 @@ pub struct App {
 
     // 任务句柄
     pub active_task: Option<tokio::task::JoinHandle<()>>,
+    /// 取消后发送/接收任务自行关闭热点、断开网络再退出
+    pub active_cancel: Option<CancellationToken>,
+    /// 附加到守护进程日志的后台任务
+    daemon_logs: Option<tokio::task::JoinHandle<()>>,
 
     // 权限状态
     pub has_nmcli: bool,
@@ -217,15 +292,32 @@ impl App {
             tab: Tab::Devices,
             devices: vec![],
             selected_device: 0,
+            favorites: Favorites::load(),
             progress: 0.0,
             transfer_speed: 0.0,
             file_to_send: None,
+            transfer_files: vec![],
+            selected_transfer_file: 0,
+            speed_history: VecDeque::with_capacity(SPEED_HISTORY_LEN),
+            send_phase: None,
+            session_id: None,
+            paused: false,
+            receive_control: None,
+            identity: None,
+            bootstrap_payload: None,
+            payload_as_text: false,
+            receiving_from_payload: false,
             raw_logs: vec![],
             log_filter: LogLevel::Info,
+            log_scroll: 0,
+            log_search: None,
+            log_search_editing: false,
             scan_start: None,
             event_rx,
             event_tx,
             active_task: None,
+            active_cancel: None,
+            daemon_logs: None,
             has_nmcli,
             has_net_raw,
             show_perm_warning: !has_nmcli || !has_net_raw,
@@ -234,18 +326,18 @@ impl App {
             input_buffer: String::new(),
             settings_focus_brand: false,
             file_selector: FileSelector::new(),
-            status_message: "就绪".to_string(),
+            status_message: tr!("tui.status.ready"),
         };
 
         // 添加初始消息
-        app.add_log(LogLevel::Info, "Cattysend TUI 启动".to_string());
+        app.add_log(LogLevel::Info, tr!("tui.log.started"));
         app.add_log(
             LogLevel::Info,
-            format!(
-                "配置已加载: 设备名='{}', 厂商='{}', 5GHz={}",
-                app.settings.device_name,
-                app.settings.brand_id.name(),
-                app.settings.supports_5ghz
+            tr!(
+                "tui.log.config_loaded",
+                name = app.settings.device_name,
+                brand = app.settings.brand_id.name(),
+                wifi_5ghz = app.settings.supports_5ghz
             ),
         );
 
@@ -253,26 +345,20 @@ impl App {
             if !app.has_nmcli {
                 app.add_log(
                     LogLevel::Warn,
-                    "⚠️ 系统缺少 nmcli，双连接功能将不可用。".to_string(),
+                    format!("⚠️ {}", tr!("tui.log.missing_nmcli")),
                 );
             }
             if !app.has_net_raw {
                 app.add_log(
                     LogLevel::Warn,
-                    "⚠️ 缺少 CAP_NET_RAW 权限，蓝牙扫描可能受限。".to_string(),
+                    format!("⚠️ {}", tr!("tui.log.missing_net_raw")),
                 );
             }
         } else {
-            app.add_log(
-                LogLevel::Info,
-                "✅ NetworkManager 已就绪，双连接支持已激活。".to_string(),
-            );
+            app.add_log(LogLevel::Info, format!("✅ {}", tr!("tui.log.nm_ready")));
         }
 
-        app.add_log(
-            LogLevel::Info,
-            "[s]扫描 [r]接收 [d]日志级别 [c]清空日志 [q]退出".to_string(),
-        );
+        app.add_log(LogLevel::Info, tr!("tui.log.help"));
 
         app
     }
@@ -282,24 +368,30 @@ impl App {
     }
 
     pub fn set_file_to_send(&mut self, path: String) {
-        let message = format!("待发送文件已设置: {}", path);
+        let message = tr!("tui.log.file_selected", path = path);
         self.file_to_send = Some(path);
         self.add_log(LogLevel::Info, message);
     }
 
-    pub fn run_sender(&mut self, device_addr: String, file_path: String) {
-        let tx = self.event_tx.clone();
+    /// 加载本机身份密钥（系统密钥环或 `~/.config/cattysend/identity.key`）
+    ///
+    /// 二维码引导时发送端用接收端的这个公钥加密，因此接收端必须使用同一身份。
+    pub async fn load_identity(&mut self) {
+        let loaded = tokio::task::spawn_blocking(|| IdentityStore::default().load_or_create())
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|result| result);
+        match loaded {
+            Ok(security) => self.identity = Some(Arc::new(security)),
+            Err(e) => self.add_log(LogLevel::Warn, tr!("tui.log.identity_fallback", error = e)),
+        }
+    }
 
+    pub fn run_sender(&mut self, device_addr: String, file_path: String) {
         self.add_log(
             LogLevel::Info,
-            format!("正在连接设备 {} (发送 {})...", device_addr, file_path),
+            tr!("tui.log.connecting", device = device_addr, file = file_path),
         );
-        self.mode = AppMode::Sending;
-
-        // 取消现有任务（如果有）
-        if let Some(handle) = self.active_task.take() {
-            handle.abort();
-        }
 
         // 查找选中的 DiscoveredDevice
         let device = self
@@ -308,73 +400,181 @@ impl App {
             .find(|d| d.address == device_addr)
             .cloned();
 
-        let settings = self.settings.clone();
-
         if let Some(device) = device {
-            let task = tokio::spawn(async move {
-                let options = SendOptions {
-                    wifi_interface: "wlan0".to_string(), // TODO: Auto-detect or config
-                    use_5ghz: settings.supports_5ghz,
-                    sender_name: settings.device_name.clone(),
-                };
+            self.spawn_sender(SendTarget::Device(device), file_path);
+        } else {
+            self.add_log(LogLevel::Error, tr!("tui.error.device_not_found"));
+            self.mode = AppMode::Idle;
+        }
+    }
 
-                // 1. 创建回调和接收通道
-                let (callback, mut rx_internal) = SimpleSendCallback::new();
-
-                // 2. 启动一个子任务来转发回调事件到主 App 通道
-                let tx_clone = tx.clone();
-                tokio::spawn(async move {
-                    while let Some(event) = rx_internal.recv().await {
-                        let tx = tx_clone.clone();
-                        match event {
-                            cattysend_core::SendEvent::Status(s) => {
-                                let _ = tx.send(AppEvent::StatusUpdate(s)).await;
-                            }
-                            cattysend_core::SendEvent::Progress { sent, total, .. } => {
-                                let _ = tx.send(AppEvent::ProgressUpdate { sent, total }).await;
-                            }
-                            cattysend_core::SendEvent::Complete => {
-                                let _ = tx.send(AppEvent::TransferComplete).await;
-                            }
-                            cattysend_core::SendEvent::Error(e) => {
-                                let _ = tx.send(AppEvent::Error(e)).await;
-                            }
+    /// 不经过蓝牙，显示二维码由接收端扫描（或粘贴载荷）后接入
+    pub fn run_qr_sender(&mut self, file_path: String, peer_key: Option<String>) {
+        self.add_log(LogLevel::Info, tr!("tui.log.qr_sending", file = file_path));
+        self.spawn_sender(SendTarget::Payload { peer_key }, file_path);
+    }
+
+    fn spawn_sender(&mut self, target: SendTarget, file_path: String) {
+        let tx = self.event_tx.clone();
+        self.mode = AppMode::Sending;
+        self.reset_transfer_stats();
+
+        // 取消现有任务（如果有）
+        self.cancel_active_task();
+        let cancel = CancellationToken::new();
+        self.active_cancel = Some(cancel.clone());
+
+        let settings = self.settings.clone();
+        let identity = self.identity.clone();
+
+        let task = tokio::spawn(async move {
+            let options = SendOptions {
+                wifi_interface: "wlan0".to_string(), // TODO: Auto-detect or config
+                use_5ghz: settings.supports_5ghz,
+                sender_name: settings.device_name.clone(),
+                ports: settings.transfer_ports,
+                credentials: settings.hotspot_credentials,
+                hotspot_idle_timeout: settings.hotspot_idle_timeout(),
+                hotspot_interface: settings.hotspot_interface.clone(),
+                busy_interface: settings.busy_interface,
+                min_battery_percent: settings.min_battery_percent,
+                ..Default::default()
+            };
+
+            // 1. 创建回调和接收通道
+            let (callback, mut rx_internal) = SimpleSendCallback::new();
+
+            // 2. 启动一个子任务来转发回调事件到主 App 通道
+            let tx_clone = tx.clone();
+            tokio::spawn(async move {
+                while let Some(event) = rx_internal.recv().await {
+                    let tx = tx_clone.clone();
+                    match event {
+                        cattysend_core::SendEvent::Started { session_id } => {
+                            let _ = tx.send(AppEvent::SessionStarted(session_id)).await;
                         }
-                    }
-                });
-
-                // 3. 执行发送
-                match Sender::new(options) {
-                    Ok(sender) => {
-                        if let Err(e) = sender
-                            .send_to_device(
-                                &device,
-                                vec![std::path::PathBuf::from(file_path)],
-                                &callback,
-                            )
-                            .await
-                        {
+                        cattysend_core::SendEvent::Status(s) => {
+                            let _ = tx.send(AppEvent::StatusUpdate(s)).await;
+                        }
+                        cattysend_core::SendEvent::Phase(phase) => {
+                            let _ = tx.send(AppEvent::Phase(phase)).await;
+                        }
+                        cattysend_core::SendEvent::Listening { port } => {
+                            let _ = tx
+                                .send(AppEvent::StatusUpdate(tr!(
+                                    "tui.log.listening",
+                                    port = port
+                                )))
+                                .await;
+                        }
+                        cattysend_core::SendEvent::VerificationCode(code) => {
                             let _ = tx
-                                .send(AppEvent::Error(format!("发送过程错误: {}", e)))
+                                .send(AppEvent::StatusUpdate(tr!(
+                                    "tui.log.verification_code",
+                                    code = code
+                                )))
                                 .await;
                         }
+                        cattysend_core::SendEvent::BootstrapPayload(payload) => {
+                            let _ = tx.send(AppEvent::BootstrapPayload(payload)).await;
+                        }
+                        cattysend_core::SendEvent::Retrying(retry) => {
+                            let _ = tx.send(AppEvent::StatusUpdate(retry.to_string())).await;
+                        }
+                        cattysend_core::SendEvent::ReceiverJoined { mac, ip } => {
+                            let _ = tx
+                                .send(AppEvent::StatusUpdate(tr!(
+                                    "tui.log.receiver_joined",
+                                    ip = ip,
+                                    mac = mac
+                                )))
+                                .await;
+                        }
+                        cattysend_core::SendEvent::InterfaceBusy(busy) => {
+                            let _ = tx
+                                .send(AppEvent::StatusUpdate(tr!(
+                                    "tui.log.interface_busy",
+                                    interface = busy.interface,
+                                    network = busy.network()
+                                )))
+                                .await;
+                        }
+                        cattysend_core::SendEvent::Pairing(prompt) => {
+                            let _ = tx
+                                .send(AppEvent::StatusUpdate(tr!(
+                                    "tui.log.pairing",
+                                    address = prompt.address(),
+                                    code = prompt.code()
+                                )))
+                                .await;
+                        }
+                        cattysend_core::SendEvent::Progress { sent, total, .. } => {
+                            let _ = tx.send(AppEvent::ProgressUpdate { sent, total }).await;
+                        }
+                        cattysend_core::SendEvent::Stats(stats) => {
+                            let _ = tx.send(AppEvent::Stats(stats)).await;
+                        }
+                        cattysend_core::SendEvent::Paused => {
+                            let _ = tx.send(AppEvent::Paused(true)).await;
+                        }
+                        cattysend_core::SendEvent::Resumed => {
+                            let _ = tx.send(AppEvent::Paused(false)).await;
+                        }
+                        cattysend_core::SendEvent::Complete => {
+                            let _ = tx.send(AppEvent::TransferComplete).await;
+                        }
+                        cattysend_core::SendEvent::Error(e) => {
+                            let _ = tx.send(AppEvent::Error(e)).await;
+                        }
                     }
-                    Err(e) => {
+                }
+            });
+
+            // 3. 执行发送
+            let sender = Sender::new(options).map(|s| {
+                let s = s.with_cancellation(cancel);
+                match identity {
+                    Some(identity) => s.with_security(identity),
+                    None => s,
+                }
+            });
+            match sender {
+                Ok(sender) => {
+                    let files = vec![std::path::PathBuf::from(file_path)];
+                    let result = match &target {
+                        SendTarget::Device(device) => {
+                            sender.send_to_device(device, files, &callback).await
+                        }
+                        SendTarget::Payload { peer_key } => {
+                            sender
+                                .send_via_payload(peer_key.as_deref(), files, &callback)
+                                .await
+                        }
+                    };
+                    if let Err(e) = result
+                        && !cancel::is_cancelled(&e)
+                    {
                         let _ = tx
-                            .send(AppEvent::Error(format!("无法初始化发送器: {}", e)))
+                            .send(AppEvent::Error(tr!("tui.error.send", error = e)))
                             .await;
                     }
                 }
-            });
-            self.active_task = Some(task);
-        } else {
-            self.add_log(LogLevel::Error, "未找到目标设备信息".to_string());
-            self.mode = AppMode::Idle;
-        }
+                Err(e) => {
+                    let _ = tx
+                        .send(AppEvent::Error(tr!("tui.error.sender_init", error = e)))
+                        .await;
+                }
+            }
+        });
+        self.active_task = Some(task);
     }
 
     /// 添加日志条目
     pub fn add_log(&mut self, level: LogLevel, message: String) {
+        // 已向上滚动时保持当前显示的内容不动
+        if self.log_scroll > 0 && level <= self.log_filter {
+            self.log_scroll += 1;
+        }
         self.raw_logs.push(LogEntry { level, message });
         // 保持最多 500 条日志
         if self.raw_logs.len() > 500 {
@@ -391,6 +591,116 @@ impl App {
             .collect()
     }
 
+    /// 滚动日志面板，正数向旧日志方向
+    pub fn scroll_logs(&mut self, delta: isize) {
+        let max = self.filtered_logs().len().saturating_sub(1);
+        self.log_scroll = self.log_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// 当前用于高亮的搜索词：输入时为输入框内容，否则为已确认的搜索词
+    pub fn log_query(&self) -> Option<&str> {
+        let query = if self.log_search_editing {
+            Some(self.input_buffer.as_str())
+        } else {
+            self.log_search.as_deref()
+        };
+        query.filter(|q| !q.is_empty())
+    }
+
+    /// 开始在日志面板中输入搜索词
+    pub fn begin_log_search(&mut self) {
+        self.input_buffer = self.log_search.clone().unwrap_or_default();
+        self.log_search_editing = true;
+    }
+
+    /// 确认输入的搜索词并跳到当前位置起第一条匹配
+    pub fn confirm_log_search(&mut self) {
+        self.log_search_editing = false;
+        let query = self.input_buffer.trim().to_string();
+        self.log_search = (!query.is_empty()).then_some(query);
+        if self.log_search.is_some() && !self.jump_to_match(self.log_scroll, true) {
+            let query = self.log_search.take().unwrap_or_default();
+            self.add_log(LogLevel::Warn, tr!("tui.log.no_match", query = query));
+        }
+    }
+
+    /// 清除搜索（包括正在输入的搜索词）
+    pub fn clear_log_search(&mut self) {
+        self.log_search_editing = false;
+        self.log_search = None;
+    }
+
+    /// 跳到下一条匹配，`older` 为真时向旧日志方向
+    pub fn next_log_match(&mut self, older: bool) {
+        let start = if older {
+            self.log_scroll + 1
+        } else if let Some(newer) = self.log_scroll.checked_sub(1) {
+            newer
+        } else {
+            return;
+        };
+        self.jump_to_match(start, older);
+    }
+
+    /// 当前过滤后的日志中匹配搜索词的条数
+    pub fn log_match_count(&self) -> usize {
+        let Some(query) = self.log_query() else {
+            return 0;
+        };
+        self.filtered_logs()
+            .iter()
+            .filter(|line| !match_ranges(line, query).is_empty())
+            .count()
+    }
+
+    /// 从显示位置 `start`（0 为最新）开始查找匹配并滚动到该条，找不到时返回 `false`
+    fn jump_to_match(&mut self, start: usize, older: bool) -> bool {
+        let Some(query) = self.log_search.clone() else {
+            return false;
+        };
+        let logs = self.filtered_logs();
+        let matches = |i: &usize| {
+            logs.len()
+                .checked_sub(i + 1)
+                .is_some_and(|idx| !match_ranges(&logs[idx], &query).is_empty())
+        };
+        let found = if older {
+            (start..logs.len()).find(matches)
+        } else {
+            (0..=start).rev().find(matches)
+        };
+        if let Some(position) = found {
+            self.log_scroll = position;
+        }
+        found.is_some()
+    }
+
+    /// 把全部日志（不受级别过滤影响）写到下载目录
+    pub fn export_logs(&mut self) {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let path = self
+            .settings
+            .download_dir
+            .join(format!("cattysend-log-{}.txt", stamp));
+        let text: String = self
+            .raw_logs
+            .iter()
+            .map(|e| format!("[{}] {}\n", e.level.name(), e.message))
+            .collect();
+        let result = std::fs::create_dir_all(&self.settings.download_dir)
+            .and_then(|_| std::fs::write(&path, text));
+        match result {
+            Ok(()) => self.add_log(
+                LogLevel::Info,
+                tr!("tui.log.exported", path = path.display()),
+            ),
+            Err(e) => self.add_log(LogLevel::Error, tr!("tui.error.export", error = e)),
+        }
+    }
+
     /// 切换日志级别（循环: Info -> Debug -> Trace -> Info）
     pub fn toggle_log_level(&mut self) {
         self.log_filter = match self.log_filter {
@@ -402,14 +712,64 @@ impl App {
         };
         self.add_log(
             LogLevel::Info,
-            format!("日志级别切换为: {}", self.log_filter.name()),
+            tr!("tui.log.level_changed", level = self.log_filter.name()),
         );
     }
 
+    /// 附加到守护进程的日志流（按当前日志级别过滤），已附加时断开
+    pub fn toggle_daemon_logs(&mut self) {
+        if let Some(task) = self.daemon_logs.take()
+            && !task.is_finished()
+        {
+            task.abort();
+            self.add_log(LogLevel::Info, tr!("tui.log.daemon_logs_detached"));
+            return;
+        }
+
+        let tx = self.event_tx.clone();
+        let level = self.log_filter;
+        self.daemon_logs = Some(tokio::spawn(async move {
+            let log = |level: LogLevel, message: String| AppEvent::LogMessage {
+                level: level.name().to_string(),
+                message,
+            };
+            let mut logs = match DaemonLogs::connect(level).await {
+                Ok(logs) => logs,
+                Err(e) => {
+                    let message = tr!("tui.log.daemon_logs_failed", error = e);
+                    let _ = tx.send(log(LogLevel::Warn, message)).await;
+                    return;
+                }
+            };
+            let attached = tr!("tui.log.daemon_logs_attached", level = level.name());
+            let _ = tx.send(log(LogLevel::Info, attached)).await;
+            loop {
+                match logs.next().await {
+                    Ok(Some(entry)) => {
+                        let message = format!("[daemon] {}", entry.message);
+                        if tx.send(log(entry.level, message)).await.is_err() {
+                            return;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        let message = tr!("tui.log.daemon_logs_failed", error = e);
+                        let _ = tx.send(log(LogLevel::Warn, message)).await;
+                        return;
+                    }
+                }
+            }
+            let _ = tx
+                .send(log(LogLevel::Warn, tr!("tui.log.daemon_logs_ended")))
+                .await;
+        }));
+    }
+
     /// 清空日志
     pub fn clear_logs(&mut self) {
         self.raw_logs.clear();
-        self.add_log(LogLevel::Info, "日志已清空".to_string());
+        self.log_scroll = 0;
+        self.add_log(LogLevel::Info, tr!("tui.log.cleared"));
     }
 
     pub fn start_scan(&mut self) {
@@ -421,28 +781,38 @@ impl App {
         self.scan_start = Some(Instant::now());
         self.devices.clear();
         self.selected_device = 0;
-        self.add_log(LogLevel::Info, "开始扫描附近设备...".to_string());
+        self.add_log(LogLevel::Info, tr!("tui.log.scan_started"));
 
         let tx = self.event_tx.clone();
 
         // 使用核心提供的通用扫描回调
         let callback = ChannelScanCallback::new(tx.clone(), AppEvent::DeviceFound);
         let callback = Arc::new(callback);
+        let options = ScanOptions {
+            learn: self.settings.learn_unknown_vendors,
+            ..Default::default()
+        };
 
         // 启动扫描任务
         tokio::spawn(async move {
             match BleScanner::new().await {
-                Ok(scanner) => match scanner.scan(Duration::from_secs(10), Some(callback)).await {
+                Ok(scanner) => match scanner
+                    .with_options(options)
+                    .scan(Duration::from_secs(10), Some(callback))
+                    .await
+                {
                     Ok(_) => {
                         let _ = tx.send(AppEvent::ScanFinished).await;
                     }
                     Err(e) => {
-                        let _ = tx.send(AppEvent::Error(format!("扫描失败: {}", e))).await;
+                        let _ = tx
+                            .send(AppEvent::Error(tr!("tui.error.scan", error = e)))
+                            .await;
                     }
                 },
                 Err(e) => {
                     let _ = tx
-                        .send(AppEvent::Error(format!("无法初始化扫描器: {}", e)))
+                        .send(AppEvent::Error(tr!("tui.error.scanner_init", error = e)))
                         .await;
                 }
             }
@@ -452,34 +822,75 @@ impl App {
     pub fn handle_event(&mut self, event: AppEvent) {
         match event {
             AppEvent::DeviceFound(device) => {
-                if !self.devices.iter().any(|d| d.address == device.address) {
-                    self.devices.push(device);
+                // 同一设备换了随机地址时替换原来的条目
+                match self.devices.iter_mut().find(|d| d.is_same_device(&device)) {
+                    Some(existing) => *existing = device,
+                    None => self.devices.push(device),
                 }
+                self.sort_devices();
             }
             AppEvent::ScanFinished => {
+                if self.favorites.touch(&self.devices) {
+                    self.save_favorites();
+                }
                 if self.mode == AppMode::Scanning {
                     self.mode = AppMode::Idle;
                     self.add_log(
                         LogLevel::Info,
-                        format!("扫描完成，发现 {} 个设备", self.devices.len()),
+                        tr!("tui.log.scan_finished", count = self.devices.len()),
                     );
                 }
             }
+            AppEvent::SessionStarted(id) => {
+                self.add_log(LogLevel::Info, tr!("tui.log.session_started", id = id));
+                self.session_id = Some(id);
+            }
             AppEvent::StatusUpdate(msg) => {
                 self.status_message = msg.clone();
                 self.add_log(LogLevel::Info, msg);
             }
+            AppEvent::Phase(phase) => {
+                self.send_phase = Some(phase);
+            }
+            AppEvent::BootstrapPayload(payload) => {
+                // 扫不了码时可以从日志中复制载荷
+                self.add_log(LogLevel::Info, tr!("tui.log.qr_payload", payload = payload));
+                self.bootstrap_payload = Some(payload);
+            }
             AppEvent::ProgressUpdate { sent, total } => {
+                self.bootstrap_payload = None;
                 self.progress = progress_ratio(sent, total);
                 self.mode = AppMode::Transferring;
             }
+            AppEvent::Stats(stats) => {
+                self.bootstrap_payload = None;
+                self.progress = progress_ratio(stats.transferred, stats.total);
+                self.transfer_speed = stats.bytes_per_sec as f64 / 1_000_000.0;
+                if self.speed_history.len() == SPEED_HISTORY_LEN {
+                    self.speed_history.pop_front();
+                }
+                self.speed_history.push_back(stats.bytes_per_sec);
+                self.transfer_files = stats.files;
+                self.mode = AppMode::Transferring;
+            }
+            AppEvent::Paused(paused) => {
+                self.paused = paused;
+                let message = if paused {
+                    tr!("tui.log.transfer_paused")
+                } else {
+                    tr!("tui.log.transfer_resumed")
+                };
+                self.add_log(LogLevel::Info, message);
+            }
             AppEvent::TransferComplete => {
                 self.mode = AppMode::Idle;
+                self.bootstrap_payload = None;
                 self.progress = 1.0;
-                self.add_log(LogLevel::Info, "传输任务已完成".to_string());
+                self.add_log(LogLevel::Info, tr!("tui.log.transfer_complete"));
             }
             AppEvent::Error(msg) => {
                 self.mode = AppMode::Idle;
+                self.bootstrap_payload = None;
                 self.add_log(LogLevel::Error, msg);
             }
             AppEvent::LogMessage { level, message } => {
@@ -491,22 +902,47 @@ impl App {
 
     pub fn toggle_receive_mode(&mut self) {
         if self.mode == AppMode::Receiving {
-            if let Some(handle) = self.active_task.take() {
-                handle.abort();
-            }
+            self.cancel_active_task();
+            self.receive_control = None;
             self.mode = AppMode::Idle;
-            self.add_log(LogLevel::Info, "停止接收模式".to_string());
+            self.add_log(LogLevel::Info, tr!("tui.log.receive_stopped"));
             return;
         }
 
+        self.add_log(LogLevel::Info, tr!("tui.log.receive_started"));
+        self.spawn_receiver(None);
+    }
+
+    /// 从发送端二维码中的载荷接收（不广播，直接接入发送端）
+    pub fn run_payload_receiver(&mut self, payload: String) {
+        self.cancel_active_task();
+        self.tab = Tab::Transfer;
+        self.add_log(LogLevel::Info, tr!("tui.log.payload_receive"));
+        self.spawn_receiver(Some(payload));
+    }
+
+    fn spawn_receiver(&mut self, payload: Option<String>) {
         self.mode = AppMode::Receiving;
-        self.add_log(LogLevel::Info, "进入接收模式，正在广播...".to_string());
+        self.receiving_from_payload = payload.is_some();
+        self.reset_transfer_stats();
 
         let tx = self.event_tx.clone();
         let options = ReceiveOptions::default();
+        let control = TransferControl::new();
+        self.receive_control = Some(control.clone());
+        let cancel = CancellationToken::new();
+        self.active_cancel = Some(cancel.clone());
+        let identity = self.identity.clone();
 
         let handle = tokio::spawn(async move {
-            match Receiver::new(options) {
+            let receiver = Receiver::new(options).map(|r| {
+                let r = r.with_control(control).with_cancellation(cancel);
+                match identity {
+                    Some(identity) => r.with_security(identity),
+                    None => r,
+                }
+            });
+            match receiver {
                 Ok(receiver) => {
                     let (callback, mut rx) = SimpleReceiveCallback::new(true); // auto_accept = true
 
@@ -515,9 +951,43 @@ impl App {
                     tokio::spawn(async move {
                         while let Some(event) = rx.recv().await {
                             match event {
+                                ReceiveEvent::Started { session_id } => {
+                                    let _ =
+                                        tx_clone.send(AppEvent::SessionStarted(session_id)).await;
+                                }
                                 ReceiveEvent::Status(s) => {
                                     let _ = tx_clone.send(AppEvent::StatusUpdate(s)).await;
                                 }
+                                ReceiveEvent::BleConnection(event) => {
+                                    let message = match event {
+                                        GattConnectionEvent::Connected { address, mtu } => tr!(
+                                            "tui.log.ble_connected",
+                                            address = address,
+                                            mtu = mtu
+                                        ),
+                                        GattConnectionEvent::Disconnected { address } => {
+                                            tr!("tui.log.ble_disconnected", address = address)
+                                        }
+                                    };
+                                    let _ = tx_clone.send(AppEvent::StatusUpdate(message)).await;
+                                }
+                                ReceiveEvent::VerificationCode(code) => {
+                                    let _ = tx_clone
+                                        .send(AppEvent::StatusUpdate(tr!(
+                                            "tui.log.verification_code",
+                                            code = code
+                                        )))
+                                        .await;
+                                }
+                                ReceiveEvent::Pairing(prompt) => {
+                                    let _ = tx_clone
+                                        .send(AppEvent::StatusUpdate(tr!(
+                                            "tui.log.pairing",
+                                            address = prompt.address(),
+                                            code = prompt.code()
+                                        )))
+                                        .await;
+                                }
                                 ReceiveEvent::Progress { received, total } => {
                                     let _ = tx_clone
                                         .send(AppEvent::ProgressUpdate {
@@ -526,6 +996,15 @@ impl App {
                                         })
                                         .await;
                                 }
+                                ReceiveEvent::Stats(stats) => {
+                                    let _ = tx_clone.send(AppEvent::Stats(stats)).await;
+                                }
+                                ReceiveEvent::Paused => {
+                                    let _ = tx_clone.send(AppEvent::Paused(true)).await;
+                                }
+                                ReceiveEvent::Resumed => {
+                                    let _ = tx_clone.send(AppEvent::Paused(false)).await;
+                                }
                                 ReceiveEvent::Complete(_) => {
                                     let _ = tx_clone.send(AppEvent::TransferComplete).await;
                                 }
@@ -537,15 +1016,21 @@ impl App {
                         }
                     });
 
-                    if let Err(e) = receiver.start(&callback).await {
+                    let result = match &payload {
+                        Some(payload) => receiver.receive_payload(payload, &callback).await,
+                        None => receiver.start(&callback).await,
+                    };
+                    if let Err(e) = result
+                        && !cancel::is_cancelled(&e)
+                    {
                         let _ = tx
-                            .send(AppEvent::Error(format!("接收流程出错: {}", e)))
+                            .send(AppEvent::Error(tr!("tui.error.receive", error = e)))
                             .await;
                     }
                 }
                 Err(e) => {
                     let _ = tx
-                        .send(AppEvent::Error(format!("无法初始化接收器: {}", e)))
+                        .send(AppEvent::Error(tr!("tui.error.receiver_init", error = e)))
                         .await;
                 }
             }
@@ -554,6 +1039,22 @@ impl App {
         self.active_task = Some(handle);
     }
 
+    /// 取消等待扫码的二维码发送
+    pub fn cancel_qr_send(&mut self) {
+        self.cancel_active_task();
+        self.bootstrap_payload = None;
+        self.mode = AppMode::Idle;
+        self.add_log(LogLevel::Info, tr!("tui.log.qr_cancelled"));
+    }
+
+    /// 取消当前的发送/接收任务，任务清理完毕后自行退出
+    fn cancel_active_task(&mut self) {
+        if let Some(cancel) = self.active_cancel.take() {
+            cancel.cancel();
+        }
+        self.active_task = None;
+    }
+
     pub fn next_device(&mut self) {
         if !self.devices.is_empty() {
             self.selected_device = (self.selected_device + 1) % self.devices.len();
@@ -569,6 +1070,108 @@ impl App {
         }
     }
 
+    /// 收藏或取消收藏当前选中的设备
+    pub fn toggle_favorite(&mut self) {
+        let Some(device) = self.devices.get(self.selected_device) else {
+            return;
+        };
+        let (name, address) = (device.name.clone(), device.address.clone());
+        let message = if self.favorites.toggle(&name, &address) {
+            let alias = self.favorites.by_address(&address).map(|f| f.alias.clone());
+            tr!(
+                "tui.log.favorite_added",
+                name = name,
+                alias = alias.unwrap_or_default()
+            )
+        } else {
+            tr!("tui.log.favorite_removed", name = name)
+        };
+        self.add_log(LogLevel::Info, message);
+        self.save_favorites();
+        self.sort_devices();
+    }
+
+    /// 收藏了但本次扫描没有发现的设备
+    pub fn offline_favorites(&self) -> Vec<&Favorite> {
+        self.favorites
+            .devices
+            .iter()
+            .filter(|f| !self.devices.iter().any(|d| d.address == f.address))
+            .collect()
+    }
+
+    /// `devices` 开头收藏设备的数量
+    pub fn favorite_device_count(&self) -> usize {
+        self.devices
+            .iter()
+            .take_while(|d| self.favorites.contains_address(&d.address))
+            .count()
+    }
+
+    /// 把收藏的设备移到最前面，选中项跟随原来的设备
+    fn sort_devices(&mut self) {
+        let selected = self
+            .devices
+            .get(self.selected_device)
+            .map(|d| d.address.clone());
+        let favorites = &self.favorites;
+        self.devices
+            .sort_by_key(|d| !favorites.contains_address(&d.address));
+        if let Some(index) =
+            selected.and_then(|addr| self.devices.iter().position(|d| d.address == addr))
+        {
+            self.selected_device = index;
+        }
+    }
+
+    fn save_favorites(&mut self) {
+        if let Err(e) = self.favorites.save() {
+            self.add_log(LogLevel::Error, tr!("tui.error.save", error = e));
+        }
+    }
+
+    /// 开始新任务前清空上一次的文件列表和速度曲线
+    fn reset_transfer_stats(&mut self) {
+        self.progress = 0.0;
+        self.transfer_speed = 0.0;
+        self.transfer_files.clear();
+        self.selected_transfer_file = 0;
+        self.speed_history.clear();
+        self.send_phase = None;
+        self.paused = false;
+        self.bootstrap_payload = None;
+    }
+
+    /// 暂停或恢复接收模式下的下载
+    ///
+    /// 发送端无法主动暂停，只显示接收端的暂停状态。
+    pub fn toggle_pause(&mut self) {
+        let Some(control) = &self.receive_control else {
+            return;
+        };
+        if control.is_paused() {
+            control.resume();
+        } else {
+            control.pause();
+        }
+    }
+
+    pub fn next_transfer_file(&mut self) {
+        if !self.transfer_files.is_empty() {
+            self.selected_transfer_file =
+                (self.selected_transfer_file + 1) % self.transfer_files.len();
+        }
+    }
+
+    pub fn previous_transfer_file(&mut self) {
+        if !self.transfer_files.is_empty() {
+            self.selected_transfer_file = self
+                .selected_transfer_file
+                .checked_sub(1)
+                .unwrap_or(self.transfer_files.len() - 1);
+        }
+    }
+
     pub fn next_tab(&mut self) {
         self.tab = match self.tab {
             Tab::Devices => Tab::Transfer,
@@ -584,6 +1187,19 @@ impl App {
     }
 }
 
+/// `line` 中与 `query` 匹配（忽略 ASCII 大小写）的字节范围
+pub fn match_ranges(line: &str, query: &str) -> Vec<std::ops::Range<usize>> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let haystack = line.to_ascii_lowercase();
+    let needle = query.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, m)| start..start + m.len())
+        .collect()
+}
+
 fn progress_ratio(sent: u64, total: u64) -> f64 {
     if total == 0 {
         0.0
@@ -594,7 +1210,7 @@ fn progress_ratio(sent: u64, total: u64) -> f64 {
 
 #[cfg(test)]
 mod tests {
-    use super::progress_ratio;
+    use super::{match_ranges, progress_ratio};
 
     #[test]
     fn progress_ratio_returns_zero_when_total_is_zero() {
@@ -605,4 +1221,14 @@ mod tests {
     fn progress_ratio_divides_sent_by_total() {
         assert_eq!(progress_ratio(25, 100), 0.25);
     }
+
+    #[test]
+    fn match_ranges_ignores_ascii_case() {
+        assert_eq!(
+            match_ranges("GATT ready, gatt up", "gatt"),
+            vec![0..4, 12..16]
+        );
+        assert_eq!(match_ranges("连接 Wi-Fi 成功", "wi-fi"), vec![7..12]);
+        assert!(match_ranges("anything", "").is_empty());
+    }
 }
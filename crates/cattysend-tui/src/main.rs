@@ -5,17 +5,24 @@
 //! # 日志
 //!
 //! 日志默认显示在 TUI 的"日志"标签页中。
-//! 如需输出到文件进行调试，设置 RUST_LOG 环境变量：
+//! 如需保留到文件进行调试，使用 `--log-dir`（按大小轮转，写入 `tui.log`，
+//! 只给出 `--log-dir` 时使用 `~/.local/state/cattysend/logs`）：
 //!
 //! ```bash
-//! RUST_LOG=debug cargo run -p cattysend-tui 2>> /tmp/cattysend.log
+//! cargo run -p cattysend-tui -- --log-dir /tmp/cattysend-logs
+//! RUST_LOG=debug cargo run -p cattysend-tui -- --log-dir
 //! ```
+//!
+//! 在日志标签页按 `a`（或以 `--daemon-logs` 启动）可附加到守护进程，
+//! 守护进程执行传输时的日志会一并显示在面板中，前缀为 `[daemon]`。
 
 mod app;
+mod mouse;
 mod tui_log;
 mod ui;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use cattysend_core::{RotatingFile, logging, tr};
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
     execute,
@@ -23,14 +30,33 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 use std::io;
+use std::sync::Mutex;
 use std::time::Duration;
-use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{EnvFilter, Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 use app::App;
 use tui_log::TuiLogLayer;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    cattysend_core::i18n::init();
+
+    // 解析命令行参数：`--log-dir` 和要发送的文件路径
+    let (log_dir, args) = logging::take_log_dir_arg(std::env::args().collect());
+    let file_path = args
+        .iter()
+        .skip(1)
+        .find(|arg| !arg.starts_with("--"))
+        .cloned();
+
+    // 在进入全屏界面之前打开日志文件，出错时错误信息还能正常显示
+    let log_file = log_dir
+        .map(|dir| {
+            RotatingFile::open(&dir, "tui")
+                .with_context(|| format!("无法打开日志目录 {}", dir.display()))
+        })
+        .transpose()?;
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -38,22 +64,18 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // 解析命令行参数（简单的文件路径）
-    let args: Vec<String> = std::env::args().collect();
-    let file_path = if args.len() > 1 {
-        Some(args[1].clone())
-    } else {
-        None
-    };
-
     // 创建 App（获取日志发送器）
     let mut app = App::new();
     if let Some(path) = file_path {
         app.set_file_to_send(path);
     }
+    app.load_identity().await;
 
     // 初始化日志系统，发送到 TUI 日志面板
-    init_logging(app.event_tx.clone());
+    init_logging(app.event_tx.clone(), log_file);
+    if args.iter().any(|arg| arg == logging::DAEMON_LOGS_ARG) {
+        app.toggle_daemon_logs();
+    }
 
     // Run app
     let res = run_app(&mut terminal, app).await;
@@ -74,39 +96,40 @@ async fn main() -> Result<()> {
 ///
 /// - 总是将日志发送到 TUI 日志面板
 /// - 如果设置了 RUST_LOG，同时输出到 stderr（用于调试）
-fn init_logging(log_tx: tokio::sync::mpsc::Sender<app::AppEvent>) {
+/// - 给出了日志文件时另写入文件，包含 cattysend 各模块的 debug 日志
+fn init_logging(log_tx: tokio::sync::mpsc::Sender<app::AppEvent>, log_file: Option<RotatingFile>) {
     // 桥接 log crate（cattysend-core 使用）到 tracing
     let _ = tracing_log::LogTracer::init();
 
-    // TUI 日志层 - 总是启用
-    let tui_layer = TuiLogLayer::new(log_tx);
-
-    // 设置过滤器
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        // 默认只显示 info 及以上级别
-        EnvFilter::new("info,cattysend_core=debug")
-    });
+    // 设置了 RUST_LOG 时所有输出都按它过滤
+    let filter = |default: &str| {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default))
+    };
 
-    // 如果设置了 RUST_LOG，同时输出到 stderr
-    if std::env::var("RUST_LOG").is_ok() {
-        use tracing_subscriber::fmt;
+    // TUI 日志层 - 总是启用，默认只显示 info 及以上级别
+    let tui_layer = TuiLogLayer::new(log_tx).with_filter(filter("info,cattysend_core=debug"));
 
-        let stderr_layer = fmt::layer()
+    let stderr_layer = std::env::var("RUST_LOG").is_ok().then(|| {
+        fmt::layer()
             .with_writer(io::stderr)
             .with_target(true)
-            .compact();
-
-        let _ = tracing_subscriber::registry()
-            .with(filter)
-            .with(tui_layer)
-            .with(stderr_layer)
-            .try_init();
-    } else {
-        let _ = tracing_subscriber::registry()
-            .with(filter)
-            .with(tui_layer)
-            .try_init();
-    }
+            .compact()
+            .with_filter(filter("info"))
+    });
+
+    let file_layer = log_file.map(|file| {
+        fmt::layer()
+            .with_writer(Mutex::new(file))
+            .with_ansi(false)
+            .with_target(true)
+            .with_filter(filter("info,cattysend_core=debug,cattysend_tui=debug"))
+    });
+
+    let _ = tracing_subscriber::registry()
+        .with(tui_layer)
+        .with(stderr_layer)
+        .with(file_layer)
+        .try_init();
 }
 
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result<()> {
@@ -114,8 +137,18 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
         terminal.draw(|f| ui::draw(f, &app))?;
 
         // 使用 poll 避免无限阻塞
-        if event::poll(Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
+        let event = if event::poll(Duration::from_millis(100))? {
+            Some(event::read()?)
+        } else {
+            None
+        };
+
+        if let Some(Event::Mouse(mouse)) = event {
+            let size = terminal.size()?;
+            mouse::handle_mouse(&mut app, mouse, Rect::new(0, 0, size.width, size.height));
+        }
+
+        if let Some(Event::Key(key)) = event
             && key.kind == KeyEventKind::Press
         {
             // 如果正在显示权限警告弹窗，拦截所有按键以关闭它
@@ -124,6 +157,30 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                 continue;
             }
 
+            // 日志搜索输入
+            if app.log_search_editing {
+                match key.code {
+                    KeyCode::Esc => app.clear_log_search(),
+                    KeyCode::Enter => app.confirm_log_search(),
+                    KeyCode::Backspace => {
+                        app.input_buffer.pop();
+                    }
+                    KeyCode::Char(c) => app.input_buffer.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+
+            // 二维码显示期间只响应切换显示方式和取消
+            if app.bootstrap_payload.is_some() {
+                match key.code {
+                    KeyCode::Char('t') => app.payload_as_text = !app.payload_as_text,
+                    KeyCode::Esc => app.cancel_qr_send(),
+                    _ => {}
+                }
+                continue;
+            }
+
             match app.mode {
                 app::AppMode::Settings => match key.code {
                     KeyCode::Esc => {
@@ -154,14 +211,14 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                         app.settings.brand_id = app.temp_brand_id;
 
                         if let Err(e) = app.settings.save() {
-                            app.add_log(app::LogLevel::Error, format!("保存失败: {}", e));
+                            app.add_log(app::LogLevel::Error, tr!("tui.error.save", error = e));
                         } else {
                             app.add_log(
                                 app::LogLevel::Info,
-                                format!(
-                                    "设置已更新: {} ({})",
-                                    app.settings.device_name,
-                                    app.settings.brand_id.name()
+                                tr!(
+                                    "tui.log.settings_saved",
+                                    name = app.settings.device_name,
+                                    brand = app.settings.brand_id.name()
                                 ),
                             );
                         }
@@ -175,10 +232,36 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                     }
                     _ => {}
                 },
+                app::AppMode::PeerKeyInput | app::AppMode::PayloadInput => match key.code {
+                    KeyCode::Esc => app.mode = app::AppMode::Idle,
+                    KeyCode::Enter => {
+                        let input = app.input_buffer.trim().to_string();
+                        if app.mode == app::AppMode::PayloadInput {
+                            app.run_payload_receiver(input);
+                        } else if let Some(file_path) = app.file_to_send.clone() {
+                            let peer_key = (!input.is_empty()).then_some(input);
+                            app.run_qr_sender(file_path, peer_key);
+                        }
+                    }
+                    KeyCode::Backspace => {
+                        app.input_buffer.pop();
+                    }
+                    KeyCode::Char(c) => {
+                        app.input_buffer.push(c);
+                    }
+                    _ => {}
+                },
                 app::AppMode::FileSelection => match key.code {
                     KeyCode::Esc => app.mode = app::AppMode::Idle,
                     KeyCode::Up | KeyCode::Char('k') => app.file_selector.previous(),
                     KeyCode::Down | KeyCode::Char('j') => app.file_selector.next(),
+                    KeyCode::Char('n') => match app.file_selector.select_latest() {
+                        Some(name) => app.add_log(
+                            app::LogLevel::Info,
+                            tr!("tui.log.latest_selected", name = name),
+                        ),
+                        None => app.add_log(app::LogLevel::Warn, tr!("tui.log.no_files")),
+                    },
                     KeyCode::Enter => {
                         if let Some(path) = app.file_selector.enter() {
                             app.set_file_to_send(path.clone());
@@ -195,6 +278,9 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                     _ => {}
                 },
                 _ => match key.code {
+                    KeyCode::Esc if app.tab == app::Tab::Log && app.log_search.is_some() => {
+                        app.clear_log_search();
+                    }
                     KeyCode::Char('q') | KeyCode::Esc => {
                         return Ok(());
                     }
@@ -204,12 +290,44 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                     KeyCode::Char('r') => {
                         app.toggle_receive_mode();
                     }
+                    KeyCode::Char(' ') => {
+                        app.toggle_pause();
+                    }
+                    KeyCode::Char('g') => {
+                        if app.file_to_send.is_some() {
+                            app.input_buffer.clear();
+                            app.mode = app::AppMode::PeerKeyInput;
+                        } else {
+                            app.add_log(app::LogLevel::Warn, tr!("tui.error.no_file"));
+                        }
+                    }
+                    KeyCode::Char('i') => {
+                        app.input_buffer.clear();
+                        app.mode = app::AppMode::PayloadInput;
+                    }
                     KeyCode::Char('p') => {
                         app.input_buffer = app.settings.device_name.clone();
                         app.temp_brand_id = app.settings.brand_id; // Sync temp brand with current
                         app.settings_focus_brand = false; // Reset focus to name
                         app.mode = app::AppMode::Settings;
                     }
+                    KeyCode::Up | KeyCode::Char('k') if app.tab == app::Tab::Transfer => {
+                        app.previous_transfer_file();
+                    }
+                    KeyCode::Down | KeyCode::Char('j') if app.tab == app::Tab::Transfer => {
+                        app.next_transfer_file();
+                    }
+                    KeyCode::Char('f') if app.tab == app::Tab::Devices => app.toggle_favorite(),
+                    KeyCode::PageUp if app.tab == app::Tab::Log => app.scroll_logs(app::LOG_PAGE),
+                    KeyCode::PageDown if app.tab == app::Tab::Log => {
+                        app.scroll_logs(-app::LOG_PAGE)
+                    }
+                    KeyCode::Home if app.tab == app::Tab::Log => app.log_scroll = 0,
+                    KeyCode::Char('/') if app.tab == app::Tab::Log => app.begin_log_search(),
+                    KeyCode::Char('n') if app.tab == app::Tab::Log => app.next_log_match(true),
+                    KeyCode::Char('N') if app.tab == app::Tab::Log => app.next_log_match(false),
+                    KeyCode::Char('e') if app.tab == app::Tab::Log => app.export_logs(),
+                    KeyCode::Char('a') if app.tab == app::Tab::Log => app.toggle_daemon_logs(),
                     KeyCode::Up | KeyCode::Char('k') => app.previous_device(),
                     KeyCode::Down | KeyCode::Char('j') => app.next_device(),
                     KeyCode::Enter => {
@@ -220,7 +338,7 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                             if let Some(device) = app.devices.get(app.selected_device).cloned() {
                                 app.run_sender(device.address.clone(), file_path);
                             } else {
-                                app.add_log(app::LogLevel::Warn, "无效的设备选择".to_string());
+                                app.add_log(app::LogLevel::Warn, tr!("tui.error.invalid_device"));
                             }
                         } else {
                             // Only allow file selection if we have devices to send to,
@@ -228,8 +346,8 @@ async fn run_app<B: Backend>(terminal: &mut Terminal<B>, mut app: App) -> Result
                             // Generally allowing it is better UX.
                             app.mode = app::AppMode::FileSelection;
                             app.file_selector.refresh();
-                            app.status_message = "选择文件".to_string();
-                            app.add_log(app::LogLevel::Info, "进入文件选择模式...".to_string());
+                            app.status_message = tr!("tui.status.select_file");
+                            app.add_log(app::LogLevel::Info, tr!("tui.log.file_selection"));
                         }
                     }
                     KeyCode::Tab => app.next_tab(),
@@ -0,0 +1,173 @@
+//! 鼠标操作
+//!
+//! 点击标签页标题切换标签，点击设备/文件列表选中条目，滚轮移动选中项或滚动日志面板。
+//! 点击位置按 [`ui`] 中与绘制共用的布局函数换算，不需要在绘制时记录区域。
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+use crate::app::{App, AppMode, Tab};
+use crate::ui::{self, DeviceRow};
+
+/// 滚轮每格滚动的日志条数
+const LOG_SCROLL_STEP: isize = 3;
+
+/// 处理一次鼠标事件，`screen` 为整个终端区域
+pub fn handle_mouse(app: &mut App, event: MouseEvent, screen: Rect) {
+    // 权限提示弹窗：任意点击关闭
+    if app.show_perm_warning {
+        if matches!(event.kind, MouseEventKind::Down(_)) {
+            app.dismiss_warning();
+        }
+        return;
+    }
+    // 二维码和文本输入期间不响应鼠标
+    if app.bootstrap_payload.is_some()
+        || matches!(
+            app.mode,
+            AppMode::Settings | AppMode::PeerKeyInput | AppMode::PayloadInput
+        )
+    {
+        return;
+    }
+
+    let [header, main, _] = ui::screen_layout(screen);
+    let position = Position::new(event.column, event.row);
+
+    if app.mode == AppMode::FileSelection {
+        handle_file_selection(app, event, main);
+        return;
+    }
+
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) if header.contains(position) => {
+            if let Some(tab) = ui::tab_at(header, event.column) {
+                app.tab = tab;
+            }
+        }
+        _ if !main.contains(position) => {}
+        // 接收模式主区域显示广播状态，没有可操作的内容
+        _ if app.mode == AppMode::Receiving && !app.receiving_from_payload => {}
+        _ => match app.tab {
+            Tab::Devices => handle_devices(app, event, main),
+            Tab::Transfer => handle_transfer(app, event, main),
+            Tab::Log => match event.kind {
+                MouseEventKind::ScrollUp => app.scroll_logs(LOG_SCROLL_STEP),
+                MouseEventKind::ScrollDown => app.scroll_logs(-LOG_SCROLL_STEP),
+                _ => {}
+            },
+        },
+    }
+}
+
+fn handle_devices(app: &mut App, event: MouseEvent, main: Rect) {
+    let [list_area, _] = ui::devices_layout(main);
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            // 设备列表不滚动，行号直接对应 `device_rows`
+            if let Some(row) = list_row(list_area, event, 0)
+                && let Some(DeviceRow::Device(index)) = ui::device_rows(app).get(row).copied()
+            {
+                app.selected_device = index;
+            }
+        }
+        MouseEventKind::ScrollUp => app.previous_device(),
+        MouseEventKind::ScrollDown => app.next_device(),
+        _ => {}
+    }
+}
+
+fn handle_transfer(app: &mut App, event: MouseEvent, main: Rect) {
+    let files_area = ui::transfer_layout(main)[2];
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let offset = ui::list_offset(app.selected_transfer_file, inner_height(files_area));
+            if let Some(row) = list_row(files_area, event, offset)
+                && row < app.transfer_files.len()
+            {
+                app.selected_transfer_file = row;
+            }
+        }
+        MouseEventKind::ScrollUp => app.previous_transfer_file(),
+        MouseEventKind::ScrollDown => app.next_transfer_file(),
+        _ => {}
+    }
+}
+
+/// 文件选择：单击选中，再次单击已选中的条目与回车相同（进入目录或选定文件）
+fn handle_file_selection(app: &mut App, event: MouseEvent, main: Rect) {
+    let selector = &mut app.file_selector;
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            let offset = ui::list_offset(selector.selected, inner_height(main));
+            let Some(row) = list_row(main, event, offset) else {
+                return;
+            };
+            if row >= selector.entries.len() {
+                return;
+            }
+            if row != selector.selected {
+                selector.selected = row;
+            } else if let Some(path) = selector.enter() {
+                app.set_file_to_send(path.clone());
+                app.mode = AppMode::Idle;
+                // 与回车相同：已选中设备时直接发送
+                if let Some(device) = app.devices.get(app.selected_device).cloned() {
+                    app.run_sender(device.address.clone(), path);
+                }
+            }
+        }
+        MouseEventKind::ScrollUp => selector.previous(),
+        MouseEventKind::ScrollDown => selector.next(),
+        _ => {}
+    }
+}
+
+/// 带边框的列表中被点击的条目下标（已加上滚动偏移）
+fn list_row(area: Rect, event: MouseEvent, offset: usize) -> Option<usize> {
+    let inner = inner(area);
+    inner
+        .contains(Position::new(event.column, event.row))
+        .then(|| offset + (event.row - inner.y) as usize)
+}
+
+fn inner(area: Rect) -> Rect {
+    area.inner(ratatui::layout::Margin::new(1, 1))
+}
+
+fn inner_height(area: Rect) -> u16 {
+    inner(area).height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn click(column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn list_row_skips_border_and_adds_offset() {
+        let area = Rect::new(0, 3, 20, 10);
+        assert_eq!(list_row(area, click(5, 3), 0), None);
+        assert_eq!(list_row(area, click(5, 4), 0), Some(0));
+        assert_eq!(list_row(area, click(5, 6), 4), Some(6));
+        assert_eq!(list_row(area, click(19, 6), 0), None);
+        assert_eq!(list_row(area, click(5, 12), 0), None);
+    }
+
+    #[test]
+    fn list_offset_keeps_selection_visible() {
+        assert_eq!(ui::list_offset(0, 5), 0);
+        assert_eq!(ui::list_offset(4, 5), 0);
+        assert_eq!(ui::list_offset(5, 5), 1);
+        assert_eq!(ui::list_offset(12, 5), 8);
+    }
+}
@@ -2,34 +2,206 @@
 
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Tabs, Wrap},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Sparkline, Tabs, Wrap},
 };
 
-use crate::app::{App, AppMode, Tab};
+use cattysend_core::tr;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::{EcLevel, QrCode};
+
+use crate::app::{App, AppMode, DiscoveredDevice, SendPhase, Tab, match_ranges};
+
+/// 标签页顺序，与标题栏中的显示顺序一致
+pub const TABS: [Tab; 3] = [Tab::Devices, Tab::Transfer, Tab::Log];
+
+/// 设备列表中的一行
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceRow {
+    /// "收藏"分组标题
+    Favorites,
+    /// "附近设备"分组标题
+    Nearby,
+    /// `app.devices` 中的设备
+    Device(usize),
+    /// `app.offline_favorites()` 中的离线收藏
+    Offline(usize),
+}
+
+/// 顶层布局：标题栏、主区域、状态栏
+///
+/// 绘制和鼠标点击定位共用，保证点击位置与显示一致。
+pub fn screen_layout(area: Rect) -> [Rect; 3] {
+    Layout::vertical([
+        Constraint::Length(3), // Header
+        Constraint::Min(10),   // Main content
+        Constraint::Length(3), // Status bar
+    ])
+    .areas(area)
+}
+
+/// 设备页布局：设备列表、帮助
+pub fn devices_layout(area: Rect) -> [Rect; 2] {
+    Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(area)
+}
+
+/// 传输页布局：进度、速度曲线、文件列表、状态
+pub fn transfer_layout(area: Rect) -> [Rect; 4] {
+    Layout::vertical([
+        Constraint::Length(5), // Progress
+        Constraint::Length(6), // Speed graph
+        Constraint::Min(5),    // File list
+        Constraint::Length(4), // Status + steps
+    ])
+    .areas(area)
+}
+
+/// 设备列表的行：收藏分组在前，附近设备在后
+pub fn device_rows(app: &App) -> Vec<DeviceRow> {
+    let favorite_count = app.favorite_device_count();
+    let offline_count = app.offline_favorites().len();
+    let mut rows = Vec::new();
+    if favorite_count > 0 || offline_count > 0 {
+        rows.push(DeviceRow::Favorites);
+        rows.extend((0..favorite_count).map(DeviceRow::Device));
+        rows.extend((0..offline_count).map(DeviceRow::Offline));
+        rows.push(DeviceRow::Nearby);
+    }
+    rows.extend((favorite_count..app.devices.len()).map(DeviceRow::Device));
+    rows
+}
+
+/// 带选中项的列表滚动后第一行的下标
+///
+/// 与 ratatui 对新建 `ListState` 的处理一致：选中项超出可见范围时让它停在最后一行。
+pub fn list_offset(selected: usize, visible_rows: u16) -> usize {
+    (selected + 1).saturating_sub(visible_rows.max(1) as usize)
+}
+
+/// 标题栏中 `column` 列处的标签页
+pub fn tab_at(header: Rect, column: u16) -> Option<Tab> {
+    // 边框占一列；每个标题左右各一个空格，标题之间一个分隔符
+    let mut x = header.x + 1;
+    for (tab, title) in TABS.into_iter().zip(tab_titles()) {
+        let end = x + Line::from(title).width() as u16 + 2;
+        if (x..end).contains(&column) {
+            return Some(tab);
+        }
+        x = end + 1;
+    }
+    None
+}
+
+fn tab_titles() -> [String; 3] {
+    [
+        format!("{} [1]", tr!("tui.tab.devices")),
+        format!("{} [2]", tr!("tui.tab.transfer")),
+        format!("{} [3]", tr!("tui.tab.log")),
+    ]
+}
 
 pub fn draw(frame: &mut Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(3), // Header
-            Constraint::Min(10),   // Main content
-            Constraint::Length(3), // Status bar
-        ])
-        .split(frame.area());
+    let [header, main, status_bar] = screen_layout(frame.area());
 
-    draw_header(frame, app, chunks[0]);
-    draw_main(frame, app, chunks[1]);
-    draw_status_bar(frame, app, chunks[2]);
+    draw_header(frame, app, header);
+    draw_main(frame, app, main);
+    draw_status_bar(frame, app, status_bar);
+
+    if let Some(payload) = &app.bootstrap_payload {
+        draw_qr_popup(frame, app, payload);
+    }
 
     if app.show_perm_warning {
         draw_popup(frame, app);
     }
 }
 
+/// 二维码引导：显示发送端的 P2P 信息，等待接收端扫描
+///
+/// 选择文本显示或终端放不下二维码时改为显示载荷文本，接收端可以直接粘贴。
+fn draw_qr_popup(frame: &mut Frame, app: &App, payload: &str) {
+    let art = QrCode::with_error_correction_level(payload, EcLevel::L)
+        .map(|code| code.render::<Dense1x2>().quiet_zone(true).build())
+        .unwrap_or_default();
+    let art_width = art.lines().map(|l| l.chars().count()).max().unwrap_or(0) as u16;
+    let art_height = art.lines().count() as u16;
+
+    let footer = vec![
+        Line::from(app.status_message.clone()),
+        Line::from(Span::styled(
+            tr!("tui.qr.hint"),
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(vec![
+            Span::styled(" [t] ", Style::default().fg(Color::Blue).bold()),
+            Span::raw(format!("{}   ", tr!("tui.qr.toggle"))),
+            Span::styled(" [Esc] ", Style::default().fg(Color::Red).bold()),
+            Span::raw(tr!("tui.qr.cancel")),
+        ]),
+    ];
+    let block = Block::default()
+        .title(format!(" 📷 {} ", tr!("tui.qr.title")))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::LightCyan))
+        .bg(Color::Black);
+
+    let screen = frame.area();
+    let width = art_width.max(40) + 2;
+    let height = art_height + footer.len() as u16 + 2;
+    let fits = !art.is_empty() && width <= screen.width && height <= screen.height;
+    if app.payload_as_text || !fits {
+        let intro = if fits {
+            tr!("tui.qr.text")
+        } else {
+            tr!("tui.qr.too_small")
+        };
+        let mut text = vec![
+            Line::from(intro),
+            Line::from(""),
+            Line::from(Span::styled(payload, Style::default().fg(Color::Cyan))),
+            Line::from(""),
+        ];
+        text.extend(footer);
+        let area = centered_rect(80, 60, screen);
+        frame.render_widget(ratatui::widgets::Clear, area);
+        frame.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+            area,
+        );
+        return;
+    }
+
+    let area = Rect::new(
+        screen.x + (screen.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    );
+    frame.render_widget(ratatui::widgets::Clear, area);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let [code_area, footer_area] =
+        Layout::vertical([Constraint::Length(art_height), Constraint::Min(0)]).areas(inner);
+    // 固定黑白配色，深色终端主题下也能扫
+    let code_area = Rect {
+        x: code_area.x + (code_area.width - art_width) / 2,
+        width: art_width,
+        ..code_area
+    };
+    frame.render_widget(
+        Paragraph::new(art).style(Style::default().fg(Color::Black).bg(Color::White)),
+        code_area,
+    );
+    frame.render_widget(
+        Paragraph::new(footer).alignment(Alignment::Center),
+        footer_area,
+    );
+}
+
 fn draw_popup(frame: &mut Frame, _app: &App) {
     let area = centered_rect(70, 50, frame.area());
     let block = Block::default()
-        .title(" 📡 网络配置提示 ")
+        .title(format!(" 📡 {} ", tr!("tui.popup.title")))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::LightCyan))
         .bg(Color::Black);
@@ -37,27 +209,33 @@ fn draw_popup(frame: &mut Frame, _app: &App) {
     let text = vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("💡 提示: ", Style::default().fg(Color::Cyan).bold()),
-            Span::raw("本项目已切换至更优雅的 NetworkManager 方案。"),
+            Span::styled(
+                format!("💡 {}: ", tr!("tui.popup.tip")),
+                Style::default().fg(Color::Cyan).bold(),
+            ),
+            Span::raw(tr!("tui.popup.tip_text")),
         ]),
         Line::from(""),
-        Line::from("双连接 (Concurrent Mode) 特性现在依赖于系统中的 NetworkManager。"),
+        Line::from(tr!("tui.popup.requires_nm")),
         Line::from(""),
         Line::from(vec![Span::styled(
-            "✅ 优势: ",
+            format!("✅ {}: ", tr!("tui.popup.benefits")),
             Style::default().fg(Color::Green).bold(),
         )]),
-        Line::from("  • 无需 root/sudo 权限"),
-        Line::from("  • 自动管理多网卡并发连接"),
-        Line::from("  • 连接更稳健，断开自动恢复"),
+        Line::from(format!("  • {}", tr!("tui.popup.benefit_no_root"))),
+        Line::from(format!("  • {}", tr!("tui.popup.benefit_concurrent"))),
+        Line::from(format!("  • {}", tr!("tui.popup.benefit_reconnect"))),
         Line::from(""),
         Line::from(vec![
-            Span::styled("⚠️ 注意: ", Style::default().fg(Color::Yellow).bold()),
-            Span::raw("如果连接失败，请确保已安装 nmcli 并运行 NetworkManager 服务。"),
+            Span::styled(
+                format!("⚠️ {}: ", tr!("tui.popup.note")),
+                Style::default().fg(Color::Yellow).bold(),
+            ),
+            Span::raw(tr!("tui.popup.note_text")),
         ]),
         Line::from(""),
         Line::from(Span::styled(
-            " [ 按任意键关闭此提示并继续 ] ",
+            format!(" [ {} ] ", tr!("tui.popup.dismiss")),
             Style::default().fg(Color::Gray).italic(),
         )),
     ];
@@ -92,12 +270,8 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
-    let titles = vec!["设备 [1]", "传输 [2]", "日志 [3]"];
-    let selected = match app.tab {
-        Tab::Devices => 0,
-        Tab::Transfer => 1,
-        Tab::Log => 2,
-    };
+    let titles = tab_titles();
+    let selected = TABS.iter().position(|&t| t == app.tab).unwrap_or(0);
 
     // 分别显示 NM 和 BLE 权限状态
     let nm_status = if app.has_nmcli {
@@ -139,7 +313,12 @@ fn draw_main(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    if app.mode == AppMode::Receiving {
+    if matches!(app.mode, AppMode::PeerKeyInput | AppMode::PayloadInput) {
+        draw_text_input(frame, app, area);
+        return;
+    }
+
+    if app.mode == AppMode::Receiving && !app.receiving_from_payload {
         draw_receiving_mode(frame, app, area);
         return;
     }
@@ -153,7 +332,7 @@ fn draw_main(frame: &mut Frame, app: &App, area: Rect) {
 
 fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
-        .title(" ⚙️ 设置 ")
+        .title(format!(" ⚙️ {} ", tr!("tui.settings.title")))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Yellow));
 
@@ -167,15 +346,18 @@ fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
     let value_style = Style::default().bg(Color::DarkGray).fg(Color::White);
 
     let name_label = if !app.settings_focus_brand {
-        Span::styled(">> 设备名称: ", active_style)
+        Span::styled(format!(">> {}: ", tr!("tui.settings.name")), active_style)
     } else {
-        Span::styled("   设备名称: ", inactive_style)
+        Span::styled(format!("   {}: ", tr!("tui.settings.name")), inactive_style)
     };
 
     let brand_label = if app.settings_focus_brand {
-        Span::styled(">> 设备品牌: ", active_style)
+        Span::styled(format!(">> {}: ", tr!("tui.settings.brand")), active_style)
     } else {
-        Span::styled("   设备品牌: ", inactive_style)
+        Span::styled(
+            format!("   {}: ", tr!("tui.settings.brand")),
+            inactive_style,
+        )
     };
 
     let content = vec![
@@ -208,16 +390,16 @@ fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
         // Help Text
         Line::from(vec![
             Span::styled(" [Tab] ", Style::default().fg(Color::Blue).bold()),
-            Span::raw("切换焦点   "),
+            Span::raw(format!("{}   ", tr!("tui.settings.switch_focus"))),
             Span::styled(" [←/→] ", Style::default().fg(Color::Blue).bold()),
-            Span::raw("修改品牌"),
+            Span::raw(tr!("tui.settings.change_brand")),
         ]),
         Line::from(""),
         Line::from(vec![
             Span::styled(" [Enter] ", Style::default().fg(Color::Green).bold()),
-            Span::raw("保存并返回   "),
+            Span::raw(format!("{}   ", tr!("tui.settings.save"))),
             Span::styled(" [Esc] ", Style::default().fg(Color::Red).bold()),
-            Span::raw("取消"),
+            Span::raw(tr!("tui.settings.cancel")),
         ]),
     ];
 
@@ -230,144 +412,404 @@ fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(paragraph, inner_area);
 }
 
-fn draw_devices_tab(frame: &mut Frame, app: &App, area: Rect) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
-        .split(area);
+/// 二维码收发前的文本输入：接收端公钥或发送端载荷
+fn draw_text_input(frame: &mut Frame, app: &App, area: Rect) {
+    let (title, hint) = if app.mode == AppMode::PeerKeyInput {
+        (
+            tr!("tui.input.peer_key_title"),
+            tr!("tui.input.peer_key_hint"),
+        )
+    } else {
+        (
+            tr!("tui.input.payload_title"),
+            tr!("tui.input.payload_hint"),
+        )
+    };
+    let block = Block::default()
+        .title(format!(" 📷 {} ", title))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
 
-    // Device list
-    let items: Vec<ListItem> = app
-        .devices
-        .iter()
-        .enumerate()
-        .map(|(i, dev)| {
-            let rssi_bar = rssi_to_bar(dev.rssi.unwrap_or(-100)); // Default to weak signal
-            let brand = &dev.brand;
-            let wifi_5g = if dev.supports_5ghz { "⚡5G" } else { "" };
-            let content = format!(
-                "{} ({}) {} {} [{}]",
-                dev.name, dev.sender_id, rssi_bar, wifi_5g, brand
-            );
-            let style = if i == app.selected_device {
-                Style::default().bg(Color::DarkGray).fg(Color::White)
-            } else {
-                Style::default()
-            };
-            ListItem::new(content).style(style)
+    let mut content = vec![
+        Line::from(""),
+        Line::from(hint),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(
+                app.input_buffer.as_str(),
+                Style::default().bg(Color::DarkGray).fg(Color::White),
+            ),
+            Span::styled("_", Style::default().fg(Color::White).bold()),
+        ]),
+        Line::from(""),
+    ];
+    // 发送端加密时需要接收端的公钥
+    if app.mode == AppMode::PayloadInput
+        && let Some(identity) = &app.identity
+    {
+        content.push(Line::from(Span::styled(
+            format!("{}:", tr!("tui.input.own_key")),
+            Style::default().fg(Color::Cyan),
+        )));
+        content.push(Line::from(identity.get_public_key()));
+        content.push(Line::from(""));
+    }
+    content.push(Line::from(vec![
+        Span::styled(" [Enter] ", Style::default().fg(Color::Green).bold()),
+        Span::raw(format!("{}   ", tr!("tui.input.confirm"))),
+        Span::styled(" [Esc] ", Style::default().fg(Color::Red).bold()),
+        Span::raw(tr!("tui.settings.cancel")),
+    ]));
+
+    let inner_area = centered_rect(80, 70, area);
+    let paragraph = Paragraph::new(content)
+        .block(block)
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(ratatui::widgets::Clear, inner_area);
+    frame.render_widget(paragraph, inner_area);
+}
+
+fn draw_devices_tab(frame: &mut Frame, app: &App, area: Rect) {
+    let [list_area, help_area] = devices_layout(area);
+
+    // Device list: 收藏分组在前，附近设备在后
+    let device_item = |i: usize, dev: &DiscoveredDevice| {
+        let rssi_bar = rssi_to_bar(dev.rssi.unwrap_or(-100)); // Default to weak signal
+        let brand = &dev.brand;
+        let wifi_5g = if dev.supports_5ghz { "⚡5G" } else { "" };
+        let busy = if dev.busy { " ⏳" } else { "" };
+        let alias = app
+            .favorites
+            .by_address(&dev.address)
+            .map(|f| format!(" @{}", f.alias))
+            .unwrap_or_default();
+        let content = format!(
+            "{}{} ({}) {} {} [{}]{}",
+            dev.name, alias, dev.sender_id, rssi_bar, wifi_5g, brand, busy
+        );
+        let style = if i == app.selected_device {
+            Style::default().bg(Color::DarkGray).fg(Color::White)
+        } else {
+            Style::default()
+        };
+        ListItem::new(content).style(style)
+    };
+    let section =
+        |title: String| ListItem::new(title).style(Style::default().fg(Color::Yellow).bold());
+
+    let offline = app.offline_favorites();
+    let items: Vec<ListItem> = device_rows(app)
+        .into_iter()
+        .map(|row| match row {
+            DeviceRow::Favorites => section(format!("★ {}", tr!("tui.devices.favorites"))),
+            DeviceRow::Nearby => section(format!("📱 {}", tr!("tui.devices.nearby"))),
+            DeviceRow::Device(i) => device_item(i, &app.devices[i]),
+            DeviceRow::Offline(i) => {
+                let f = offline[i];
+                ListItem::new(format!(
+                    "{} @{} ({})",
+                    f.name,
+                    f.alias,
+                    tr!("tui.devices.offline")
+                ))
+                .style(Style::default().fg(Color::DarkGray))
+            }
         })
         .collect();
 
     let title = match app.mode {
-        AppMode::Scanning => " 🔍 扫描中... ",
-        _ => " 📱 附近设备 ",
+        AppMode::Scanning => format!(" 🔍 {} ", tr!("tui.devices.scanning")),
+        _ => format!(" 📱 {} ", tr!("tui.devices.title")),
     };
 
     let list = List::new(items)
         .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
-    frame.render_widget(list, chunks[0]);
+    frame.render_widget(list, list_area);
 
     // Device details / help
     let help_text = if app.devices.is_empty() {
-        "按 's' 开始扫描\n按 'r' 进入接收模式\n按 'q' 退出"
+        tr!("tui.devices.help_empty")
     } else {
-        "↑/↓ 选择设备\nEnter 连接\nTab 切换标签\n\n按 's' 重新扫描"
+        tr!("tui.devices.help")
     };
 
     let help = Paragraph::new(help_text)
-        .block(Block::default().borders(Borders::ALL).title(" 帮助 "))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", tr!("tui.devices.help_title"))),
+        )
         .wrap(Wrap { trim: true });
 
-    frame.render_widget(help, chunks[1]);
+    frame.render_widget(help, help_area);
 }
 
 fn draw_transfer_tab(frame: &mut Frame, app: &App, area: Rect) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Length(5), // Progress
-            Constraint::Length(4), // Speed
-            Constraint::Min(5),    // File info
-        ])
-        .split(area);
+    let chunks = transfer_layout(area);
 
     // Progress bar
     let progress_percent = (app.progress * 100.0) as u16;
+    let (gauge_color, label) = if app.paused {
+        (
+            Color::Yellow,
+            format!("⏸ {}% ({})", progress_percent, tr!("tui.transfer.paused")),
+        )
+    } else {
+        (Color::Green, format!("{}%", progress_percent))
+    };
     let gauge = Gauge::default()
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" 📦 传输进度 "),
+                .title(format!(" 📦 {} ", tr!("tui.transfer.progress"))),
         )
-        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .gauge_style(Style::default().fg(gauge_color).bg(Color::Black))
         .percent(progress_percent)
-        .label(format!("{}%", progress_percent));
+        .label(label);
 
     frame.render_widget(gauge, chunks[0]);
 
-    // Speed
-    let speed_text = if app.mode == AppMode::Transferring {
-        format!("⚡ 传输速度: {:.1} MB/s", app.transfer_speed)
+    // Speed graph (最近 60 秒)
+    let speed_title = if app.mode == AppMode::Transferring {
+        format!(
+            " ⚡ {}: {:.1} MB/s ",
+            tr!("tui.transfer.speed"),
+            app.transfer_speed
+        )
     } else {
-        "⚡ 传输速度: --".to_string()
+        format!(" ⚡ {}: -- ", tr!("tui.transfer.speed"))
     };
+    let history: Vec<u64> = app.speed_history.iter().copied().collect();
+    let sparkline = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title(speed_title))
+        .style(Style::default().fg(Color::Cyan))
+        .data(&history);
+
+    frame.render_widget(sparkline, chunks[1]);
 
-    let speed =
-        Paragraph::new(speed_text).block(Block::default().borders(Borders::ALL).title(" 速度 "));
+    // File list
+    let items: Vec<ListItem> = app
+        .transfer_files
+        .iter()
+        .map(|f| {
+            let percent = (f.fraction() * 100.0) as u16;
+            let style = if percent >= 100 {
+                Style::default().fg(Color::Green)
+            } else if f.transferred > 0 {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            ListItem::new(format!(
+                "{:>3}%  {}  ({} / {})",
+                percent,
+                f.name,
+                format_bytes(f.transferred),
+                format_bytes(f.size)
+            ))
+            .style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " 📄 {} ({}) ",
+            tr!("tui.transfer.files"),
+            app.transfer_files.len()
+        )))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
 
-    frame.render_widget(speed, chunks[1]);
+    let mut state = ListState::default();
+    if !app.transfer_files.is_empty() {
+        state.select(Some(app.selected_transfer_file));
+    }
+    frame.render_stateful_widget(list, chunks[2], &mut state);
 
-    // File info
+    // Status
     let file_info = match app.mode {
-        AppMode::Transferring => format!("正在传输... {}", app.status_message),
-        AppMode::Sending => format!("发送模式: {}", app.status_message),
-        AppMode::Receiving => format!("接收模式: {}", app.status_message),
-        _ => "无活动传输".to_string(),
+        AppMode::Transferring => tr!("tui.transfer.transferring", status = app.status_message),
+        AppMode::Sending => tr!("tui.transfer.sending", status = app.status_message),
+        AppMode::Receiving => tr!("tui.transfer.receiving", status = app.status_message),
+        _ => tr!("tui.transfer.idle"),
     };
 
-    let info =
-        Paragraph::new(file_info).block(Block::default().borders(Borders::ALL).title(" 文件信息 "));
+    let mut lines = vec![Line::from(file_info)];
+    if let Some(phase) = app.send_phase {
+        lines.push(phase_steps(phase));
+    }
+    // 标题带上会话 ID 前缀，便于和守护进程/核心日志对照
+    let title = match &app.session_id {
+        Some(id) => format!(
+            " {} · {} ",
+            tr!("tui.transfer.status"),
+            id.get(..8).unwrap_or(id)
+        ),
+        None => format!(" {} ", tr!("tui.transfer.status")),
+    };
+    let info = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
 
-    frame.render_widget(info, chunks[2]);
+    frame.render_widget(info, chunks[3]);
+}
+
+/// 发送步骤指示：已完成的步骤为绿色，当前步骤加粗高亮
+fn phase_steps(current: SendPhase) -> Line<'static> {
+    let mut spans = Vec::new();
+    // 扫描在设备页完成，这里不显示
+    for phase in SendPhase::ALL.into_iter().skip(1) {
+        if !spans.is_empty() {
+            spans.push(Span::styled(" › ", Style::default().fg(Color::DarkGray)));
+        }
+        let style = if phase < current {
+            Style::default().fg(Color::Green)
+        } else if phase == current {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        spans.push(Span::styled(phase_label(phase), style));
+    }
+    Line::from(spans)
+}
+
+fn phase_label(phase: SendPhase) -> String {
+    match phase {
+        SendPhase::Scanning => tr!("tui.phase.scanning"),
+        SendPhase::Connecting => tr!("tui.phase.connecting"),
+        SendPhase::ReadingStatus => tr!("tui.phase.reading_status"),
+        SendPhase::WritingP2p => tr!("tui.phase.writing_p2p"),
+        SendPhase::WaitingForReceiverWifi => tr!("tui.phase.waiting_for_receiver_wifi"),
+        SendPhase::Transferring => tr!("tui.phase.transferring"),
+        SendPhase::Done => tr!("tui.phase.done"),
+    }
+}
+
+/// 以 B/KB/MB/GB 显示字节数
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
 }
 
 fn draw_log_tab(frame: &mut Frame, app: &App, area: Rect) {
+    let (area, prompt_area) = if app.log_search_editing {
+        let [logs, prompt] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(area);
+        (logs, Some(prompt))
+    } else {
+        (area, None)
+    };
+
     let logs = app.filtered_logs();
-    // 将日志合并为多行文本，最近的在下面（或者最近的在上面，取决于习惯，这里保持最近在最前）
+    let query = app.log_query();
+    // 最近的日志在最上面；向上滚动后跳过最新的 `log_scroll` 条
     let log_text: Vec<Line> = logs
         .iter()
         .rev()
+        .skip(app.log_scroll)
         .take(100) // 增加可显示的日志数
-        .map(|log| Line::from(log.as_str()))
+        .map(|log| highlight_matches(log, query))
         .collect();
 
-    let title = format!(" 📋 日志 [{}] - [d]级别 [c]清空 ", app.log_filter.name());
+    let mut title = format!(
+        " 📋 {} ",
+        tr!("tui.log_tab.title", level = app.log_filter.name())
+    );
+    if app.log_scroll > 0 {
+        title.push_str(&format!("↓{} ", app.log_scroll));
+    }
+    let mut block = Block::default().borders(Borders::ALL).title(title);
+    if let Some(query) = app
+        .log_search
+        .as_deref()
+        .filter(|_| !app.log_search_editing)
+    {
+        block = block.title_bottom(Line::from(format!(
+            " 🔎 {} ",
+            tr!(
+                "tui.log_tab.matches",
+                count = app.log_match_count(),
+                query = query
+            )
+        )));
+    }
 
     let paragraph = Paragraph::new(log_text)
-        .block(Block::default().borders(Borders::ALL).title(title))
+        .block(block)
         .wrap(Wrap { trim: true }); // 开启自动换行
 
     frame.render_widget(paragraph, area);
+
+    if let Some(prompt_area) = prompt_area {
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Yellow).bold()),
+            Span::styled(
+                app.input_buffer.as_str(),
+                Style::default().bg(Color::DarkGray).fg(Color::White),
+            ),
+            Span::styled("_", Style::default().fg(Color::White).bold()),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow))
+                .title(format!(" 🔎 {} ", tr!("tui.log_tab.search")))
+                .title_bottom(Line::from(format!(" {} ", tr!("tui.log_tab.search_hint")))),
+        );
+        frame.render_widget(prompt, prompt_area);
+    }
+}
+
+/// 日志行，搜索词的匹配处反色高亮
+fn highlight_matches<'a>(line: &'a str, query: Option<&str>) -> Line<'a> {
+    let Some(query) = query else {
+        return Line::from(line);
+    };
+    let highlight = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for range in match_ranges(line, query) {
+        spans.push(Span::raw(&line[pos..range.start]));
+        spans.push(Span::styled(&line[range.clone()], highlight));
+        pos = range.end;
+    }
+    spans.push(Span::raw(&line[pos..]));
+    Line::from(spans)
 }
 
 fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let mode_text = match app.mode {
-        AppMode::Idle => " ⏸️  空闲 ",
-        AppMode::Scanning => " 🔍 扫描中 ",
-        AppMode::Receiving => " 📥 接收模式 ",
-        AppMode::Sending => " 📤 发送中 ",
-        AppMode::Transferring => " 🔄 传输中 ",
-        AppMode::Settings => " ⚙️ 设置中 ",
-        AppMode::FileSelection => " 📂 选择文件 ",
+        AppMode::Idle => format!(" ⏸️  {} ", tr!("tui.mode.idle")),
+        AppMode::Scanning => format!(" 🔍 {} ", tr!("tui.mode.scanning")),
+        AppMode::Receiving => format!(" 📥 {} ", tr!("tui.mode.receiving")),
+        AppMode::Sending => format!(" 📤 {} ", tr!("tui.mode.sending")),
+        AppMode::Transferring => format!(" 🔄 {} ", tr!("tui.mode.transferring")),
+        AppMode::Settings => format!(" ⚙️ {} ", tr!("tui.mode.settings")),
+        AppMode::FileSelection => format!(" 📂 {} ", tr!("tui.mode.file_selection")),
+        AppMode::PeerKeyInput => format!(" 📷 {} ", tr!("tui.mode.qr_send")),
+        AppMode::PayloadInput => format!(" 📷 {} ", tr!("tui.mode.qr_receive")),
     };
 
     let status = Paragraph::new(format!(
-        "{}│ {} │ 设备: {} │ [s]扫描 [r]接收 [p]设置 [Tab]切换 [q]退出",
+        "{}│ {} │ {} │ {}",
         mode_text,
         app.status_message,
-        app.devices.len()
+        tr!("tui.status_bar.devices", count = app.devices.len()),
+        tr!("tui.status_bar.help")
     ))
     .block(Block::default().borders(Borders::ALL));
 
@@ -391,8 +833,11 @@ fn rssi_to_bar(rssi: i16) -> &'static str {
 fn draw_file_selection(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .title(format!(
-            " 📂 选择文件 - {} ",
-            app.file_selector.current_path.to_string_lossy()
+            " 📂 {} ",
+            tr!(
+                "tui.file_selection.title",
+                path = app.file_selector.current_path.to_string_lossy()
+            )
         ))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Cyan));
@@ -447,7 +892,7 @@ fn draw_file_selection(frame: &mut Frame, app: &App, area: Rect) {
 
 fn draw_receiving_mode(frame: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
-        .title(" 📥 接收模式 ")
+        .title(format!(" 📥 {} ", tr!("tui.receiving.title")))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::LightGreen));
 
@@ -463,7 +908,7 @@ fn draw_receiving_mode(frame: &mut Frame, app: &App, area: Rect) {
     let content = vec![
         Line::from(""),
         Line::from(vec![Span::styled(
-            "正在广播信号...",
+            tr!("tui.receiving.advertising"),
             Style::default()
                 .fg(Color::Green)
                 .bold()
@@ -471,14 +916,14 @@ fn draw_receiving_mode(frame: &mut Frame, app: &App, area: Rect) {
         )]),
         Line::from(""),
         Line::from(vec![
-            Span::raw("设备名称: "),
+            Span::raw(format!("{}: ", tr!("tui.settings.name"))),
             Span::styled(
                 &app.settings.device_name,
                 Style::default().fg(Color::Cyan).bold(),
             ),
         ]),
         Line::from(vec![
-            Span::raw("品牌身份: "),
+            Span::raw(format!("{}: ", tr!("tui.receiving.brand"))),
             Span::styled(
                 app.settings.brand_id.name(),
                 Style::default().fg(Color::Yellow),
@@ -486,12 +931,12 @@ fn draw_receiving_mode(frame: &mut Frame, app: &App, area: Rect) {
         ]),
         Line::from(""),
         Line::from(""),
-        Line::from(vec![Span::raw("请在其他设备上寻找并发送文件到此设备。")]),
+        Line::from(vec![Span::raw(tr!("tui.receiving.hint"))]),
         Line::from(""),
         Line::from(""),
         Line::from(vec![
             Span::styled(" [r] ", Style::default().fg(Color::Red).bold()),
-            Span::raw("停止接收并返回"),
+            Span::raw(tr!("tui.receiving.stop")),
         ]),
     ];
 
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use xshell::{Shell, cmd};
 
 #[derive(Parser)]
@@ -20,14 +21,22 @@ enum Commands {
         /// 日志级别 (trace, debug, info, warn, error)
         #[arg(short, long, default_value = "info")]
         log_level: String,
-        /// 日志输出文件 (默认 /tmp/cattysend.log)
+        /// 日志目录，写入其中的 tui.log (默认 ~/.local/state/cattysend/logs)
         #[arg(short = 'o', long)]
-        log_file: Option<String>,
+        log_dir: Option<String>,
     },
     /// 安装 systemd 服务
     Install,
     /// 卸载 systemd 服务
     Uninstall,
+    /// 安装用户级 systemd socket 激活（按需启动守护进程，空闲时退出）
+    InstallSocket,
+    /// 卸载用户级 socket 激活
+    UninstallSocket,
+    /// 为当前用户安装文件管理器右键菜单 "Send with Cattysend"
+    InstallDesktop,
+    /// 卸载文件管理器右键菜单
+    UninstallDesktop,
     /// 设置 capabilities (免 sudo 运行)
     SetupCaps,
     /// 打包发布 (tar.gz)
@@ -36,6 +45,8 @@ enum Commands {
     Test,
     /// 运行测试并生成覆盖率报告
     Coverage,
+    /// 运行加密和传输路径的基准测试
+    Bench,
     /// 清理构建产物
     Clean,
 }
@@ -55,16 +66,18 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Build => build(&sh)?,
         Commands::Dev => dev(&sh)?,
-        Commands::Tui {
-            log_level,
-            log_file,
-        } => tui(&sh, &log_level, log_file)?,
+        Commands::Tui { log_level, log_dir } => tui(&sh, &log_level, log_dir)?,
         Commands::Install => install(&sh)?,
         Commands::Uninstall => uninstall(&sh)?,
+        Commands::InstallSocket => install_socket(&sh)?,
+        Commands::UninstallSocket => uninstall_socket(&sh)?,
+        Commands::InstallDesktop => install_desktop(&sh)?,
+        Commands::UninstallDesktop => uninstall_desktop(&sh)?,
         Commands::SetupCaps => setup_caps(&sh)?,
         Commands::Dist => dist(&sh)?,
         Commands::Test => test(&sh)?,
         Commands::Coverage => coverage(&sh)?,
+        Commands::Bench => bench(&sh)?,
         Commands::Clean => clean(&sh)?,
     }
 
@@ -88,30 +101,30 @@ fn dev(sh: &Shell) -> Result<()> {
     Ok(())
 }
 
-fn tui(sh: &Shell, log_level: &str, log_file: Option<String>) -> Result<()> {
-    let log_file = log_file.unwrap_or_else(|| "/tmp/cattysend.log".to_string());
+fn tui(sh: &Shell, log_level: &str, log_dir: Option<String>) -> Result<()> {
+    let log_dir = log_dir.unwrap_or_else(|| {
+        let home = std::env::var("HOME").unwrap_or_default();
+        format!("{}/.local/state/cattysend/logs", home)
+    });
+    let log_file = format!("{}/tui.log", log_dir);
 
     println!("🖥️  启动 TUI 调试模式...");
     println!("   日志级别: {}", log_level);
     println!("   日志文件: {}", log_file);
     println!();
     println!("💡 提示: 在另一个终端运行以下命令查看实时日志:");
-    println!("   tail -f {}", log_file);
+    println!("   tail -F {}", log_file);
     println!();
 
-    // 设置环境变量并运行
     let rust_log = format!(
         "{level},cattysend_core={level},bluer={level},btleplug=info",
         level = log_level
     );
 
-    // 使用 shell 执行以支持重定向
-    let command = format!(
-        "RUST_LOG='{}' cargo run -p cattysend-tui 2>> '{}'",
-        rust_log, log_file
-    );
-
-    cmd!(sh, "bash -c {command}").run()?;
+    // TUI 自己写入按大小轮转的日志文件
+    cmd!(sh, "cargo run -p cattysend-tui -- --log-dir {log_dir}")
+        .env("RUST_LOG", rust_log)
+        .run()?;
 
     println!();
     println!("📁 日志已保存到: {}", log_file);
@@ -143,6 +156,7 @@ fn install(sh: &Shell) -> Result<()> {
         "sudo cp target/release/cattysend-cli /usr/local/bin/cattysend"
     )
     .run()?;
+    install_helper(sh)?;
 
     // 复制 TUI（如果存在）
     if std::path::Path::new("target/release/cattysend-tui").exists() {
@@ -174,6 +188,7 @@ fn uninstall(sh: &Shell) -> Result<()> {
     let _ = cmd!(sh, "sudo rm /etc/systemd/system/cattysend.service").run();
     let _ = cmd!(sh, "sudo rm /usr/local/bin/cattysend-daemon").run();
     let _ = cmd!(sh, "sudo rm /usr/local/bin/cattysend").run();
+    let _ = cmd!(sh, "sudo rm /usr/local/bin/cattysend-helper").run();
 
     cmd!(sh, "sudo systemctl daemon-reload").run()?;
 
@@ -181,6 +196,169 @@ fn uninstall(sh: &Shell) -> Result<()> {
     Ok(())
 }
 
+/// 安装特权助手并授予 CAP_NET_ADMIN
+///
+/// 守护进程和 GUI 在同目录下找到它后，创建 P2P 组和热点隔离都经由它执行，
+/// 自身不再需要 CAP_NET_ADMIN。助手装成 `root:cattysend 0750`，
+/// 只有 cattysend 组的成员能执行它（助手自己也会再检查一遍）。
+fn install_helper(sh: &Shell) -> Result<()> {
+    cmd!(sh, "sudo groupadd -f -r cattysend").run()?;
+    cmd!(
+        sh,
+        "sudo install -m 0750 -o root -g cattysend target/release/cattysend-helper /usr/local/bin/"
+    )
+    .run()?;
+    cmd!(
+        sh,
+        "sudo setcap cap_net_admin+ep /usr/local/bin/cattysend-helper"
+    )
+    .run()?;
+    let user = std::env::var("SUDO_USER")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_default();
+    if !user.is_empty() && user != "root" {
+        cmd!(sh, "sudo usermod -aG cattysend {user}").run()?;
+        println!("👥 已把 {} 加入 cattysend 组，重新登录后生效", user);
+    }
+    Ok(())
+}
+
+/// 用户级 systemd 单元目录
+fn user_unit_dir() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME")
+        && !dir.is_empty()
+    {
+        return Ok(PathBuf::from(dir).join("systemd/user"));
+    }
+    Ok(PathBuf::from(std::env::var("HOME")?).join(".config/systemd/user"))
+}
+
+fn install_socket(sh: &Shell) -> Result<()> {
+    println!("🔌 安装 socket 激活...");
+
+    if !std::path::Path::new("/usr/local/bin/cattysend-daemon").exists() {
+        println!("⚠️  /usr/local/bin/cattysend-daemon 不存在，请先运行 'cargo xtask install'");
+    }
+    // 系统服务和用户服务同时运行会争用蓝牙和网卡
+    if cmd!(sh, "systemctl is-enabled --quiet cattysend.service")
+        .quiet()
+        .run()
+        .is_ok()
+    {
+        println!("⚠️  系统服务 cattysend.service 已启用，请先运行 'cargo xtask uninstall'");
+    }
+
+    let unit_dir = user_unit_dir()?;
+    for unit in ["cattysend.socket", "cattysend.service"] {
+        let src = format!("assets/user/{}", unit);
+        let dest = unit_dir.join(unit);
+        cmd!(sh, "install -D -m 644 {src} {dest}").run()?;
+    }
+    cmd!(sh, "systemctl --user daemon-reload").run()?;
+    cmd!(sh, "systemctl --user enable --now cattysend.socket").run()?;
+
+    println!("✅ 安装完成");
+    println!("   守护进程会在第一次运行 'cattysend' 命令时启动");
+    println!("   空闲退出时间由 settings.toml 中的 idle_exit_secs 控制（0 表示不退出）");
+    Ok(())
+}
+
+fn uninstall_socket(sh: &Shell) -> Result<()> {
+    println!("🗑️  卸载 socket 激活...");
+
+    let _ = cmd!(
+        sh,
+        "systemctl --user disable --now cattysend.socket cattysend.service"
+    )
+    .run();
+    let unit_dir = user_unit_dir()?;
+    for unit in ["cattysend.socket", "cattysend.service"] {
+        let dest = unit_dir.join(unit);
+        let _ = cmd!(sh, "rm -f {dest}").run();
+    }
+    cmd!(sh, "systemctl --user daemon-reload").run()?;
+
+    println!("✅ 卸载完成");
+    Ok(())
+}
+
+/// 文件管理器集成：(源文件, 相对于 XDG 数据目录的安装路径, 权限)
+const DESKTOP_FILES: [(&str, &str, &str); 3] = [
+    // 出现在"打开方式"中，也供 Thunar、Nemo 等使用
+    (
+        "assets/desktop/cattysend-send.desktop",
+        "applications/cattysend-send.desktop",
+        "644",
+    ),
+    // Dolphin 服务菜单（KDE 6 只加载可执行的服务菜单）
+    (
+        "assets/desktop/cattysend-servicemenu.desktop",
+        "kio/servicemenus/cattysend.desktop",
+        "755",
+    ),
+    (
+        "assets/desktop/nautilus-send-with-cattysend.sh",
+        "nautilus/scripts/Send with Cattysend",
+        "755",
+    ),
+];
+
+/// 当前用户的 XDG 数据目录
+fn data_home() -> Result<PathBuf> {
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME")
+        && !dir.is_empty()
+    {
+        return Ok(PathBuf::from(dir));
+    }
+    Ok(PathBuf::from(std::env::var("HOME")?).join(".local/share"))
+}
+
+fn install_desktop(sh: &Shell) -> Result<()> {
+    println!("🖱️  安装文件管理器集成...");
+
+    let data_home = data_home()?;
+    for (src, dest, mode) in DESKTOP_FILES {
+        let dest = data_home.join(dest);
+        println!("📋 {}", dest.display());
+        cmd!(sh, "install -D -m {mode} {src} {dest}").run()?;
+    }
+    // 刷新"打开方式"缓存，没有该工具时不影响使用
+    let applications = data_home.join("applications");
+    let _ = cmd!(sh, "update-desktop-database {applications}")
+        .quiet()
+        .run();
+
+    if cmd!(sh, "which cattysend")
+        .quiet()
+        .ignore_stdout()
+        .run()
+        .is_err()
+    {
+        println!("⚠️  PATH 中没有 cattysend，请先运行 'cargo xtask install'");
+    }
+    println!("✅ 安装完成");
+    println!("   在文件管理器中右键文件 → 'Send with Cattysend'（Nautilus 在\"脚本\"子菜单中）");
+    println!("   选择设备和显示进度需要 zenity 或 kdialog");
+    Ok(())
+}
+
+fn uninstall_desktop(sh: &Shell) -> Result<()> {
+    println!("🗑️  卸载文件管理器集成...");
+
+    let data_home = data_home()?;
+    for (_, dest, _) in DESKTOP_FILES {
+        let dest = data_home.join(dest);
+        let _ = cmd!(sh, "rm -f {dest}").run();
+    }
+    let applications = data_home.join("applications");
+    let _ = cmd!(sh, "update-desktop-database {applications}")
+        .quiet()
+        .run();
+
+    println!("✅ 卸载完成");
+    Ok(())
+}
+
 fn setup_caps(sh: &Shell) -> Result<()> {
     println!("🔐 设置 Linux capabilities (免 sudo 网络操作)...");
     println!();
@@ -191,6 +369,15 @@ fn setup_caps(sh: &Shell) -> Result<()> {
 
     build(sh)?;
 
+    // 特权助手只需要 CAP_NET_ADMIN
+    let helper = "target/release/cattysend-helper";
+    if std::path::Path::new(helper).exists() {
+        println!("📦 设置 {}", helper);
+        cmd!(sh, "sudo setcap cap_net_admin+ep {helper}").run()?;
+        println!("   助手只接受 root 和 cattysend 组成员的调用，");
+        println!("   需要时运行 'sudo groupadd -r cattysend && sudo usermod -aG cattysend $USER'");
+    }
+
     // 设置所有二进制文件的 capabilities
     let binaries = [
         "target/release/cattysend-daemon",
@@ -213,6 +400,9 @@ fn setup_caps(sh: &Shell) -> Result<()> {
     println!("  • cattysend-daemon");
     println!("  • cattysend-cli");
     println!();
+    println!("💡 只想让特权助手带 CAP_NET_ADMIN 时，运行 'cargo xtask install'：");
+    println!("   它把 cattysend-helper 装到 /usr/local/bin 并单独授权，其他程序无需特权。");
+    println!();
     println!("💡 注意：每次重新编译后需要重新运行此命令。");
     println!("   或者添加到 sudoers 实现永久免密码：");
     println!();
@@ -234,12 +424,14 @@ fn dist(sh: &Shell) -> Result<()> {
 
     cmd!(sh, "mkdir -p dist/{dist_name}").run()?;
     cmd!(sh, "cp target/release/cattysend-daemon dist/{dist_name}/").run()?;
+    cmd!(sh, "cp target/release/cattysend-helper dist/{dist_name}/").run()?;
     cmd!(
         sh,
         "cp target/release/cattysend-cli dist/{dist_name}/cattysend"
     )
     .run()?;
     cmd!(sh, "cp assets/cattysend.service dist/{dist_name}/").run()?;
+    cmd!(sh, "cp -r assets/desktop assets/user dist/{dist_name}/").run()?;
     cmd!(sh, "cp README.md dist/{dist_name}/ || true").run()?;
 
     sh.change_dir("dist");
@@ -252,10 +444,24 @@ fn dist(sh: &Shell) -> Result<()> {
 fn test(sh: &Shell) -> Result<()> {
     println!("🧪 运行测试...");
     cmd!(sh, "cargo test --workspace").run()?;
+    // 端到端回环测试需要模拟后端
+    cmd!(
+        sh,
+        "cargo test -p cattysend-core --features loopback-test --test loopback"
+    )
+    .run()?;
     println!("✅ 测试完成");
     Ok(())
 }
 
+fn bench(sh: &Shell) -> Result<()> {
+    println!("⏱️  运行基准测试...");
+    cmd!(sh, "cargo bench -p cattysend-core --features loopback-test").run()?;
+    println!("✅ 基准测试完成");
+    println!("   HTML 报告: target/criterion/report/index.html");
+    Ok(())
+}
+
 fn coverage(sh: &Shell) -> Result<()> {
     println!("📊 运行测试覆盖率分析...");
 